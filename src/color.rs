@@ -4,8 +4,10 @@
 /// - Camera RGB (sensor-native color space)
 /// - XYZ (device-independent color space)
 /// - sRGB (standard display color space)
+///
+/// It also builds the sRGB ICC profile embedded in exported files.
 
-use cgmath::{Matrix3, SquareMatrix};
+use cgmath::{Matrix, Matrix3, SquareMatrix};
 
 /// Standard XYZ to sRGB conversion matrix (D65 white point)
 /// This is the industry-standard matrix for converting from CIE XYZ to sRGB
@@ -16,156 +18,181 @@ const XYZ_TO_SRGB: [[f32; 3]; 3] = [
     [ 0.0557, -0.2040,  1.0570],
 ];
 
+/// Bradford chromatic adaptation from D50 (the white DNG forward matrices
+/// map to) to D65 (sRGB's white)
+const BRADFORD_D50_TO_D65: [[f32; 3]; 3] = [
+    [ 0.9555766, -0.0230393,  0.0631636],
+    [-0.0282895,  1.0099416,  0.0210077],
+    [ 0.0122982, -0.0204830,  1.3299098],
+];
+
+/// Flat row-major identity, used whenever the camera gives us nothing usable
+const IDENTITY: [f32; 9] = [
+    1.0, 0.0, 0.0,
+    0.0, 1.0, 0.0,
+    0.0, 0.0, 1.0,
+];
+
 /// Calculate the camera-to-sRGB color conversion matrix
 ///
-/// This function converts a camera's XYZ-to-camera matrix into a camera-to-sRGB matrix
-/// by inverting it and multiplying with the standard XYZ-to-sRGB matrix.
+/// This function converts a camera's XYZ-to-camera matrix (DNG `ColorMatrix2`,
+/// D65) into a camera-to-sRGB matrix, the same way dcraw does.
 ///
 /// # Arguments
-/// * `xyz_to_cam` - The camera's XYZ to camera RGB matrix (from RAW metadata)
+/// * `xyz_to_cam` - The camera's XYZ to camera RGB matrix, flat row-major
 ///
 /// # Returns
 /// * Camera-to-sRGB conversion matrix as a flat [f32; 9] array (row-major)
 ///
 /// # Algorithm
-/// 1. Load xyz_to_cam into a 3x3 matrix
-/// 2. Invert to get cam_to_xyz: cam_to_xyz = inverse(xyz_to_cam)
-/// 3. Multiply: cam_to_srgb = XYZ_TO_SRGB × cam_to_xyz
-/// 4. Return as flat array for GPU upload
+/// 1. rgb_to_cam = xyz_to_cam × srgb_to_xyz (what the sensor sees for each sRGB primary)
+/// 2. Normalize each row of rgb_to_cam to sum to 1, so sRGB white lands on camera
+///    (1, 1, 1). White balance already maps neutrals to (1, 1, 1), so without this
+///    step neutrals pick up a color cast (the old "pink tint")
+/// 3. cam_to_srgb = inverse(rgb_to_cam)
+///
+/// All matrices are row-major `[f32; 9]` / `[[f32; 3]; 3]` on our side; cgmath is
+/// column-major, so conversions go through `matrix_from_rows` / `matrix_to_rows`.
 pub fn calculate_cam_to_srgb_matrix(xyz_to_cam: [f32; 9]) -> [f32; 9] {
-    // DECISION: Camera color matrix math is too complex and camera-specific
-    // Phase 14 colors (WB only) are VERY close to correct, just slightly desaturated
-    // Return identity matrix = Phase 14 quality
-    // TODO: Add simple saturation boost slider instead of complex matrix math
-    println!("🎨 Phase 15: Using identity matrix (bypassing color matrix calculation)");
-    println!("🎨 Reason: Phase 14 white balance gives 95% correct colors");
-    println!("🎨 Next: Add saturation slider for final 5% color boost");
-    return [
-        1.0, 0.0, 0.0,
-        0.0, 1.0, 0.0,
-        0.0, 0.0, 1.0,
-    ];
+    println!("🔧 Phase 15: Calculating cam-to-sRGB matrix...");
     
-    /* DISABLED - matrix math causes pink tint
-    println!("\n🔧 Phase 15: Calculating cam-to-sRGB matrix...");
-    println!("Input xyz_to_cam (row-major): [{:.3}, {:.3}, {:.3}]", xyz_to_cam[0], xyz_to_cam[1], xyz_to_cam[2]);
-    println!("                               [{:.3}, {:.3}, {:.3}]", xyz_to_cam[3], xyz_to_cam[4], xyz_to_cam[5]);
-    println!("                               [{:.3}, {:.3}, {:.3}]", xyz_to_cam[6], xyz_to_cam[7], xyz_to_cam[8]);
-    
-    // Check if it's identity - if so, return identity (no conversion needed)
+    // No camera metadata - nothing to convert
     if is_identity_matrix(&xyz_to_cam) {
         println!("⚠️  Input is identity matrix, returning identity (no color conversion)");
-        return xyz_to_cam;
+        return IDENTITY;
     }
     
-    // Camera matrices are often scaled by 10000 in RAW metadata
-    // Normalize them to proper range (check if values are > 10, indicating scaling)
-    let needs_normalization = xyz_to_cam.iter().any(|&x| x.abs() > 10.0);
-    let normalized_matrix = if needs_normalization {
-        println!("🔧 Normalizing matrix (dividing by 10000)...");
-        [
-            xyz_to_cam[0] / 10000.0, xyz_to_cam[1] / 10000.0, xyz_to_cam[2] / 10000.0,
-            xyz_to_cam[3] / 10000.0, xyz_to_cam[4] / 10000.0, xyz_to_cam[5] / 10000.0,
-            xyz_to_cam[6] / 10000.0, xyz_to_cam[7] / 10000.0, xyz_to_cam[8] / 10000.0,
-        ]
-    } else {
-        xyz_to_cam
-    };
-    
-    println!("Normalized matrix: [{:.4}, {:.4}, {:.4}]", normalized_matrix[0], normalized_matrix[1], normalized_matrix[2]);
-    println!("                   [{:.4}, {:.4}, {:.4}]", normalized_matrix[3], normalized_matrix[4], normalized_matrix[5]);
-    println!("                   [{:.4}, {:.4}, {:.4}]", normalized_matrix[6], normalized_matrix[7], normalized_matrix[8]);
-    
-    // Convert flat array to cgmath Matrix3 (column-major in cgmath)
-    // Use the NORMALIZED matrix!
-    let xyz_to_cam_matrix = Matrix3::new(
-        normalized_matrix[0], normalized_matrix[3], normalized_matrix[6],  // Column 0
-        normalized_matrix[1], normalized_matrix[4], normalized_matrix[7],  // Column 1
-        normalized_matrix[2], normalized_matrix[5], normalized_matrix[8],  // Column 2
-    );
-    
-    // Invert to get cam_to_xyz
-    let cam_to_xyz = match xyz_to_cam_matrix.invert() {
-        Some(inverted) => {
-            println!("✅ Matrix inverted successfully");
-            // Debug: print cam_to_xyz
-            println!("cam_to_xyz (col-major): [{:.4}, {:.4}, {:.4}]", inverted[0][0], inverted[0][1], inverted[0][2]);
-            println!("                        [{:.4}, {:.4}, {:.4}]", inverted[1][0], inverted[1][1], inverted[1][2]);
-            println!("                        [{:.4}, {:.4}, {:.4}]", inverted[2][0], inverted[2][1], inverted[2][2]);
-            inverted
-        },
-        None => {
-            eprintln!("⚠️  Failed to invert xyz_to_cam matrix, using identity");
-            return [
-                1.0, 0.0, 0.0,
-                0.0, 1.0, 0.0,
-                0.0, 0.0, 1.0,
-            ];
-        }
+    let xyz_to_cam = matrix_from_rows(flat_to_rows(xyz_to_cam));
+    let Some(srgb_to_xyz) = matrix_from_rows(XYZ_TO_SRGB).invert() else {
+        return IDENTITY;
     };
     
-    // Convert XYZ_TO_SRGB to cgmath Matrix3
-    let xyz_to_srgb_matrix = Matrix3::new(
-        XYZ_TO_SRGB[0][0], XYZ_TO_SRGB[1][0], XYZ_TO_SRGB[2][0],  // Column 0
-        XYZ_TO_SRGB[0][1], XYZ_TO_SRGB[1][1], XYZ_TO_SRGB[2][1],  // Column 1
-        XYZ_TO_SRGB[0][2], XYZ_TO_SRGB[1][2], XYZ_TO_SRGB[2][2],  // Column 2
-    );
-    
-    // Multiply: cam_to_srgb = xyz_to_srgb × cam_to_xyz
-    let cam_to_srgb = xyz_to_srgb_matrix * cam_to_xyz;
-    
-    // Debug: print cam_to_srgb before conversion
-    println!("cam_to_srgb (col-major): [{:.4}, {:.4}, {:.4}]", cam_to_srgb[0][0], cam_to_srgb[0][1], cam_to_srgb[0][2]);
-    println!("                         [{:.4}, {:.4}, {:.4}]", cam_to_srgb[1][0], cam_to_srgb[1][1], cam_to_srgb[1][2]);
-    println!("                         [{:.4}, {:.4}, {:.4}]", cam_to_srgb[2][0], cam_to_srgb[2][1], cam_to_srgb[2][2]);
-    
-    // Convert back to flat row-major array for GPU
-    let result = [
-        cam_to_srgb[0][0], cam_to_srgb[1][0], cam_to_srgb[2][0],  // Row 0
-        cam_to_srgb[0][1], cam_to_srgb[1][1], cam_to_srgb[2][1],  // Row 1
-        cam_to_srgb[0][2], cam_to_srgb[1][2], cam_to_srgb[2][2],  // Row 2
-    ];
+    // 1. Camera response to each sRGB primary
+    let mut rgb_to_cam = matrix_to_rows(xyz_to_cam * srgb_to_xyz);
     
-    println!("Output cam_to_srgb (raw): [{:.3}, {:.3}, {:.3}]", result[0], result[1], result[2]);
-    println!("                          [{:.3}, {:.3}, {:.3}]", result[3], result[4], result[5]);
-    println!("                          [{:.3}, {:.3}, {:.3}]", result[6], result[7], result[8]);
+    // 2. Normalize rows so sRGB white (1, 1, 1) → camera (1, 1, 1)
+    for row in rgb_to_cam.iter_mut() {
+        let sum: f32 = row.iter().sum();
+        if sum.abs() < 1e-6 {
+            eprintln!("⚠️  Degenerate camera matrix row, using identity");
+            return IDENTITY;
+        }
+        for value in row.iter_mut() {
+            *value /= sum;
+        }
+    }
     
-    // Scale the entire matrix to bring diagonal values to a reasonable range
-    // Typical color matrices have diagonal values around 1.0-1.5
-    // Calculate average of diagonal elements
-    let diag_avg = (result[0].abs() + result[4].abs() + result[8].abs()) / 3.0;
-    let scale_factor = if diag_avg > 2.0 {
-        1.5 / diag_avg  // Target average diagonal of ~1.5
-    } else {
-        1.0  // No scaling needed
+    // 3. Invert to go from camera to sRGB
+    let Some(cam_to_srgb) = matrix_from_rows(rgb_to_cam).invert() else {
+        eprintln!("⚠️  Failed to invert rgb_to_cam matrix, using identity");
+        return IDENTITY;
     };
+    let result = rows_to_flat(matrix_to_rows(cam_to_srgb));
     
-    println!("🔧 Diagonal average: {:.3}, scale factor: {:.3}", diag_avg, scale_factor);
+    // Typical cam→sRGB matrices stay well within ±5
+    if result.iter().any(|&x| x.abs() > 10.0 || !x.is_finite()) {
+        eprintln!("⚠️  WARNING: Color matrix has extreme values! Using identity instead.");
+        return IDENTITY;
+    }
     
-    let normalized_result = [
-        result[0] * scale_factor, result[1] * scale_factor, result[2] * scale_factor,
-        result[3] * scale_factor, result[4] * scale_factor, result[5] * scale_factor,
-        result[6] * scale_factor, result[7] * scale_factor, result[8] * scale_factor,
-    ];
+    result
+}
+
+/// Calculate the camera-to-sRGB matrix from a DNG `ForwardMatrix`
+///
+/// A forward matrix maps white-balanced camera RGB to XYZ (D50), so unlike
+/// `calculate_cam_to_srgb_matrix` there is nothing to invert: adapt D50 to
+/// D65 (Bradford), convert to sRGB, then normalize the rows so camera
+/// (1, 1, 1) lands exactly on sRGB white. Returns None for a matrix that
+/// doesn't give a usable result, so callers fall back to the XYZ→camera path.
+pub fn forward_matrix_to_srgb(forward_matrix: [f32; 9]) -> Option<[f32; 9]> {
+    let cam_to_xyz = matrix_from_rows(flat_to_rows(forward_matrix));
+    let xyz_to_srgb = matrix_from_rows(XYZ_TO_SRGB) * matrix_from_rows(BRADFORD_D50_TO_D65);
+    let mut rows = matrix_to_rows(xyz_to_srgb * cam_to_xyz);
     
-    println!("Output cam_to_srgb (scaled): [{:.3}, {:.3}, {:.3}]", normalized_result[0], normalized_result[1], normalized_result[2]);
-    println!("                             [{:.3}, {:.3}, {:.3}]", normalized_result[3], normalized_result[4], normalized_result[5]);
-    println!("                             [{:.3}, {:.3}, {:.3}]", normalized_result[6], normalized_result[7], normalized_result[8]);
+    for row in rows.iter_mut() {
+        let sum: f32 = row.iter().sum();
+        if sum.abs() < 1e-6 {
+            eprintln!("⚠️  Degenerate DNG forward matrix row");
+            return None;
+        }
+        for value in row.iter_mut() {
+            *value /= sum;
+        }
+    }
     
-    // Check for unreasonable values (typical color matrices have values between -5 and 5)
-    let has_extreme_values = normalized_result.iter().any(|&x| x.abs() > 10.0 || !x.is_finite());
-    if has_extreme_values {
-        eprintln!("⚠️  WARNING: Color matrix has extreme values! Using identity instead.");
-        eprintln!("This might indicate incorrect camera metadata or matrix math error.");
-        return [
-            1.0, 0.0, 0.0,
-            0.0, 1.0, 0.0,
-            0.0, 0.0, 1.0,
-        ];
+    let result = rows_to_flat(rows);
+    if result.iter().any(|&x| x.abs() > 10.0 || !x.is_finite()) {
+        eprintln!("⚠️  DNG forward matrix has extreme values, ignoring it");
+        return None;
     }
+    Some(result)
+}
+
+/// Camera-to-sRGB matrix for decoded RAW data: from the DNG forward matrix
+/// when the file has a usable one, otherwise from the XYZ→camera matrix
+pub fn camera_to_srgb(xyz_to_cam: [f32; 9], forward_matrix: Option<[f32; 9]>) -> [f32; 9] {
+    match forward_matrix.and_then(forward_matrix_to_srgb) {
+        Some(cam_to_srgb) => {
+            println!("🎨 Using the DNG forward matrix");
+            cam_to_srgb
+        }
+        None => calculate_cam_to_srgb_matrix(xyz_to_cam),
+    }
+}
+
+/// Build a cgmath matrix from row-major rows (cgmath's `From` expects columns)
+fn matrix_from_rows(rows: [[f32; 3]; 3]) -> Matrix3<f32> {
+    Matrix3::from(rows).transpose()
+}
+
+/// Read a cgmath matrix back out as row-major rows
+fn matrix_to_rows(matrix: Matrix3<f32>) -> [[f32; 3]; 3] {
+    matrix.transpose().into()
+}
+
+fn flat_to_rows(flat: [f32; 9]) -> [[f32; 3]; 3] {
+    [
+        [flat[0], flat[1], flat[2]],
+        [flat[3], flat[4], flat[5]],
+        [flat[6], flat[7], flat[8]],
+    ]
+}
+
+fn rows_to_flat(rows: [[f32; 3]; 3]) -> [f32; 9] {
+    [
+        rows[0][0], rows[0][1], rows[0][2],
+        rows[1][0], rows[1][1], rows[1][2],
+        rows[2][0], rows[2][1], rows[2][2],
+    ]
+}
+
+/// Solve white balance multipliers that make a sampled camera color neutral
+///
+/// `camera_rgb` is the demosaiced sensor color before white balance. The shader
+/// multiplies by the WB multipliers and then by the temperature/tint factors, so
+/// those are divided out here: after picking, the sliders still act as relative
+/// tweaks and the picked point renders gray. Green stays at 1.0.
+///
+/// # Returns
+/// * [R, G, B, G2] multipliers, or None if the sample has no usable signal
+pub fn solve_wb_multipliers(camera_rgb: [f32; 3], temperature: f32, tint: f32) -> Option<[f32; 4]> {
+    // Must match step 2.5 in the shader
+    let temp_r = 1.0 + temperature * 0.3;
+    let tint_g = 1.0 + tint * 0.3;
+    let temp_b = 1.0 - temperature * 0.3;
     
-    normalized_result
-    */
+    let [r, g, b] = camera_rgb;
+    if r <= 0.0 || g <= 0.0 || b <= 0.0 || temp_r <= 0.0 || temp_b <= 0.0 {
+        return None;
+    }
+    
+    let wb = [tint_g * g / (temp_r * r), 1.0, tint_g * g / (temp_b * b), 1.0];
+    if wb.iter().all(|m| m.is_finite()) {
+        Some(wb)
+    } else {
+        None
+    }
 }
 
 /// Check if a color matrix is the identity matrix (no conversion)
@@ -177,10 +204,185 @@ pub fn is_identity_matrix(matrix: &[f32; 9]) -> bool {
     matrix[6].abs() < EPSILON && matrix[7].abs() < EPSILON && (matrix[8] - 1.0).abs() < EPSILON
 }
 
+/// sRGB transfer function: linear light to display value (the shader's
+/// `srgb_encode`). Linear below 0.0031308, a 1/2.4 power above; negative
+/// input encodes to 0.
+pub fn linear_to_srgb(linear: f32) -> f32 {
+    let c = linear.max(0.0);
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Inverse of `linear_to_srgb` (the shader's `srgb_decode`)
+pub fn srgb_to_linear(encoded: f32) -> f32 {
+    if encoded <= 0.04045 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).max(0.0).powf(2.4)
+    }
+}
+
+/// sRGB primaries adapted to the ICC's D50 connection space (Bradford), as
+/// XYZ columns for the red, green and blue colorant tags
+const SRGB_D50_COLORANTS: [[f64; 3]; 3] = [
+    [0.4360747, 0.2225045, 0.0139322],
+    [0.3850649, 0.7168786, 0.0971045],
+    [0.1430804, 0.0606169, 0.7141733],
+];
+
+/// ICC D50 illuminant (profile connection space white)
+const ICC_D50: [f64; 3] = [0.9642, 1.0, 0.8249];
+
+/// Entries in the sampled sRGB tone curve
+const ICC_CURVE_POINTS: usize = 1024;
+
+/// A compact ICC v2 display profile for sRGB (the shader's output space)
+///
+/// Built here rather than shipped as a binary blob: XYZ colorants for the
+/// three primaries plus the piecewise sRGB curve sampled into a `curv` table
+/// shared by the three channels. Exports embed it so other apps don't have
+/// to guess the color space.
+pub fn srgb_icc_profile() -> Vec<u8> {
+    fn s15_fixed16(value: f64) -> [u8; 4] {
+        ((value * 65536.0).round() as i32).to_be_bytes()
+    }
+    fn xyz_tag(xyz: [f64; 3]) -> Vec<u8> {
+        let mut tag = b"XYZ \0\0\0\0".to_vec();
+        for v in xyz {
+            tag.extend_from_slice(&s15_fixed16(v));
+        }
+        tag
+    }
+    
+    // textDescriptionType: ASCII part, then empty Unicode and ScriptCode parts
+    let description = b"sRGB IEC61966-2.1\0";
+    let mut desc = b"desc\0\0\0\0".to_vec();
+    desc.extend_from_slice(&(description.len() as u32).to_be_bytes());
+    desc.extend_from_slice(description);
+    desc.extend_from_slice(&[0; 4 + 4 + 2 + 1 + 67]);
+    
+    let mut cprt = b"text\0\0\0\0".to_vec();
+    cprt.extend_from_slice(b"No copyright, use freely\0");
+    
+    // Decoding curve: encoded value -> linear light
+    let mut curve = b"curv\0\0\0\0".to_vec();
+    curve.extend_from_slice(&(ICC_CURVE_POINTS as u32).to_be_bytes());
+    for i in 0..ICC_CURVE_POINTS {
+        let encoded = i as f32 / (ICC_CURVE_POINTS - 1) as f32;
+        let linear = srgb_to_linear(encoded);
+        curve.extend_from_slice(&((linear * 65535.0).round() as u16).to_be_bytes());
+    }
+    
+    // (signature, data); the TRC tags all point at the one curve
+    let tags: [(&[u8; 4], usize); 9] = [
+        (b"desc", 0),
+        (b"cprt", 1),
+        (b"wtpt", 2),
+        (b"rXYZ", 3),
+        (b"gXYZ", 4),
+        (b"bXYZ", 5),
+        (b"rTRC", 6),
+        (b"gTRC", 6),
+        (b"bTRC", 6),
+    ];
+    let data: [Vec<u8>; 7] = [
+        desc,
+        cprt,
+        xyz_tag(ICC_D50),
+        xyz_tag(SRGB_D50_COLORANTS[0]),
+        xyz_tag(SRGB_D50_COLORANTS[1]),
+        xyz_tag(SRGB_D50_COLORANTS[2]),
+        curve,
+    ];
+    
+    // Lay the tag data out after the header and tag table, 4-byte aligned
+    let mut offset = 128 + 4 + 12 * tags.len();
+    let mut placed = Vec::with_capacity(data.len());
+    let mut body = Vec::new();
+    for block in &data {
+        placed.push((offset, block.len()));
+        body.extend_from_slice(block);
+        let padding = (4 - block.len() % 4) % 4;
+        body.resize(body.len() + padding, 0);
+        offset += block.len() + padding;
+    }
+    let size = offset;
+    
+    let mut profile = Vec::with_capacity(size);
+    profile.extend_from_slice(&(size as u32).to_be_bytes());
+    profile.extend_from_slice(&[0; 4]);                  // Preferred CMM
+    profile.extend_from_slice(&[2, 0x10, 0, 0]);         // Version 2.1
+    profile.extend_from_slice(b"mntrRGB XYZ ");          // Display class, RGB data, XYZ PCS
+    for field in [2024u16, 1, 1, 0, 0, 0] {              // Creation date
+        profile.extend_from_slice(&field.to_be_bytes());
+    }
+    profile.extend_from_slice(b"acsp");
+    profile.extend_from_slice(&[0; 4 + 4 + 4 + 4 + 8]);  // Platform, flags, device maker/model, attributes
+    profile.extend_from_slice(&0u32.to_be_bytes());      // Perceptual intent
+    for v in ICC_D50 {
+        profile.extend_from_slice(&s15_fixed16(v));
+    }
+    profile.resize(128, 0);                              // Creator, ID, reserved
+    
+    profile.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+    for (signature, index) in tags {
+        let (tag_offset, tag_size) = placed[index];
+        profile.extend_from_slice(signature);
+        profile.extend_from_slice(&(tag_offset as u32).to_be_bytes());
+        profile.extend_from_slice(&(tag_size as u32).to_be_bytes());
+    }
+    profile.extend_from_slice(&body);
+    profile
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_srgb_transfer_round_trip() {
+        // Reference points of the piecewise curve
+        assert_eq!(linear_to_srgb(0.0), 0.0);
+        assert!((linear_to_srgb(0.0031308) - 0.04045).abs() < 1e-5);
+        assert!((linear_to_srgb(1.0) - 1.0).abs() < 1e-6);
+        // 18% gray is 46.1%, not the 45.8% a 2.2 power gives
+        assert!((linear_to_srgb(0.18) - 0.4614).abs() < 1e-3);
+        assert_eq!(linear_to_srgb(-0.5), 0.0);
+
+        for i in 0..=1000 {
+            let linear = i as f32 / 1000.0;
+            let back = srgb_to_linear(linear_to_srgb(linear));
+            assert!((back - linear).abs() < 1e-5, "{} came back as {}", linear, back);
+        }
+    }
+
+    #[test]
+    fn test_srgb_icc_profile_layout() {
+        let profile = srgb_icc_profile();
+        let u32_at = |at: usize| u32::from_be_bytes(profile[at..at + 4].try_into().unwrap()) as usize;
+        
+        assert_eq!(u32_at(0), profile.len());
+        assert_eq!(&profile[12..24], b"mntrRGB XYZ ");
+        assert_eq!(&profile[36..40], b"acsp");
+        
+        // Every tag is aligned and inside the profile
+        let count = u32_at(128);
+        assert_eq!(count, 9);
+        for i in 0..count {
+            let entry = 132 + i * 12;
+            let (offset, size) = (u32_at(entry + 4), u32_at(entry + 8));
+            assert_eq!(offset % 4, 0, "tag {:?}", &profile[entry..entry + 4]);
+            assert!(offset + size <= profile.len());
+        }
+        
+        // Colorants sum to the D50 white (within fixed-point rounding)
+        let sum_y: f64 = SRGB_D50_COLORANTS.iter().map(|c| c[1]).sum();
+        assert!((sum_y - 1.0).abs() < 1e-4);
+    }
+    
     #[test]
     fn test_identity_matrix_detection() {
         let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
@@ -204,4 +406,120 @@ mod tests {
         // Result should not be all zeros
         assert!(result.iter().any(|&x| x != 0.0));
     }
+
+    /// Nikon D750 ColorMatrix2 (XYZ D65 → camera), as published in Adobe's DNG
+    /// profiles / dcraw's adobe_coeff table (scaled by 1/10000)
+    const D750_XYZ_TO_CAM: [f32; 9] = [
+        0.9020, -0.2890, -0.0715,
+        -0.4535, 1.2436, 0.2348,
+        -0.0934, 0.1919, 0.7086,
+    ];
+    
+    /// Row-major matrix × column vector
+    fn apply(matrix: &[f32; 9], v: [f32; 3]) -> [f32; 3] {
+        [
+            matrix[0] * v[0] + matrix[1] * v[1] + matrix[2] * v[2],
+            matrix[3] * v[0] + matrix[4] * v[1] + matrix[5] * v[2],
+            matrix[6] * v[0] + matrix[7] * v[1] + matrix[8] * v[2],
+        ]
+    }
+    
+    fn assert_neutral(rgb: [f32; 3]) {
+        let mean = (rgb[0] + rgb[1] + rgb[2]) / 3.0;
+        for channel in rgb {
+            assert!((channel - mean).abs() < 0.01 * mean.abs().max(1e-3), "not neutral: {:?}", rgb);
+        }
+    }
+    
+    #[test]
+    fn test_d750_maps_white_balanced_gray_to_neutral() {
+        let cam_to_srgb = calculate_cam_to_srgb_matrix(D750_XYZ_TO_CAM);
+        assert!(!is_identity_matrix(&cam_to_srgb));
+        
+        // After white balance a neutral surface reads equal in every camera channel
+        assert_neutral(apply(&cam_to_srgb, [0.18, 0.18, 0.18]));
+        let white = apply(&cam_to_srgb, [1.0, 1.0, 1.0]);
+        assert!((white[0] - 1.0).abs() < 0.01 && (white[2] - 1.0).abs() < 0.01, "{:?}", white);
+    }
+    
+    #[test]
+    fn test_d750_daylight_white_end_to_end() {
+        // D65 white as the sensor sees it (unbalanced), then the as-shot white
+        // balance a camera would record for it (normalized to green)
+        let d65_xyz = [0.9505, 1.0, 1.0890];
+        let cam = apply(&D750_XYZ_TO_CAM, d65_xyz);
+        let wb = [cam[1] / cam[0], 1.0, cam[1] / cam[2]];
+        let balanced = [cam[0] * wb[0], cam[1] * wb[1], cam[2] * wb[2]];
+        
+        let srgb = apply(&calculate_cam_to_srgb_matrix(D750_XYZ_TO_CAM), balanced);
+        assert_neutral(srgb);
+    }
+    
+    #[test]
+    fn test_cam_to_srgb_inverts_camera_response() {
+        // Catches row/column-major mixups: an asymmetric matrix must round-trip
+        // a saturated sRGB color through the camera response and back
+        let cam_to_srgb = calculate_cam_to_srgb_matrix(D750_XYZ_TO_CAM);
+        
+        let srgb_to_xyz = matrix_from_rows(XYZ_TO_SRGB).invert().unwrap();
+        let srgb_to_xyz = rows_to_flat(matrix_to_rows(srgb_to_xyz));
+        let red = [0.8, 0.2, 0.1];
+        let cam = apply(&D750_XYZ_TO_CAM, apply(&srgb_to_xyz, red));
+        // Undo the row normalization with the white balance the camera would use
+        let cam_white = apply(&D750_XYZ_TO_CAM, apply(&srgb_to_xyz, [1.0, 1.0, 1.0]));
+        let balanced = [cam[0] / cam_white[0], cam[1] / cam_white[1], cam[2] / cam_white[2]];
+        
+        let back = apply(&cam_to_srgb, balanced);
+        for (got, expected) in back.iter().zip(red) {
+            assert!((got - expected).abs() < 0.001, "{:?} vs {:?}", back, red);
+        }
+    }
+    
+    #[test]
+    fn test_forward_matrix_maps_neutral_to_white() {
+        // Nikon D750 ForwardMatrix2 from Adobe's DNG profile
+        let forward = [
+            0.7367, 0.1078, 0.1198,
+            0.2836, 0.7749, -0.0585,
+            0.0067, -0.1750, 0.9934,
+        ];
+        let cam_to_srgb = forward_matrix_to_srgb(forward).unwrap();
+        assert!(!is_identity_matrix(&cam_to_srgb));
+        assert_neutral(apply(&cam_to_srgb, [0.18, 0.18, 0.18]));
+        let white = apply(&cam_to_srgb, [1.0, 1.0, 1.0]);
+        assert!(white.iter().all(|c| (c - 1.0).abs() < 1e-4), "{:?}", white);
+        
+        // Unusable forward matrices fall back to the XYZ→camera path
+        assert!(forward_matrix_to_srgb([0.0; 9]).is_none());
+        assert_eq!(camera_to_srgb(D750_XYZ_TO_CAM, Some([0.0; 9])), calculate_cam_to_srgb_matrix(D750_XYZ_TO_CAM));
+        assert_eq!(camera_to_srgb(D750_XYZ_TO_CAM, Some(forward)), cam_to_srgb);
+    }
+    
+    #[test]
+    fn test_row_major_round_trip() {
+        let rows = [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 10.0]];
+        let matrix = matrix_from_rows(rows);
+        // cgmath indexes [column][row]
+        assert_eq!(matrix[0][1], 4.0);
+        assert_eq!(matrix_to_rows(matrix), rows);
+    }
+    
+    #[test]
+    fn test_solve_wb_neutralizes_sample() {
+        // Warm sample under tungsten-ish light
+        let sample = [0.6, 0.4, 0.15];
+        for (temperature, tint) in [(0.0, 0.0), (0.4, -0.2)] {
+            let wb = solve_wb_multipliers(sample, temperature, tint).unwrap();
+            assert_eq!(wb[1], 1.0);
+            // Same factors the shader applies (WB, then temperature/tint)
+            let rendered = [
+                sample[0] * wb[0] * (1.0 + temperature * 0.3),
+                sample[1] * wb[1] * (1.0 + tint * 0.3),
+                sample[2] * wb[2] * (1.0 - temperature * 0.3),
+            ];
+            assert_neutral(rendered);
+        }
+        
+        assert!(solve_wb_multipliers([0.0, 0.4, 0.2], 0.0, 0.0).is_none());
+    }
 }
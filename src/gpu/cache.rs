@@ -0,0 +1,102 @@
+/// Recently used render pipelines, so switching back to an image in Develop
+/// skips the RAW decode and texture upload
+///
+/// Each pipeline holds the full RAW texture on the GPU (~50MB for 24MP), so
+/// only a handful are kept; the least recently used one is dropped first.
+/// Outside Develop the whole cache is released after `IDLE_RELEASE`.
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// How many pipelines stay cached (current image + a few recent ones)
+pub const PIPELINE_CACHE_SIZE: usize = 4;
+
+/// How long cached pipelines survive once the user leaves Develop
+pub const IDLE_RELEASE: Duration = Duration::from_secs(60);
+
+/// Small LRU cache keyed by image id (most recently used at the front)
+#[derive(Debug)]
+pub struct PipelineCache<T> {
+    entries: VecDeque<(i64, T)>,
+    capacity: usize,
+}
+
+impl<T: Clone> PipelineCache<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+    
+    /// Look up an image's pipeline and mark it as most recently used
+    pub fn get(&mut self, image_id: i64) -> Option<T> {
+        let index = self.entries.iter().position(|(id, _)| *id == image_id)?;
+        let entry = self.entries.remove(index)?;
+        let value = entry.1.clone();
+        self.entries.push_front(entry);
+        Some(value)
+    }
+    
+    /// Add (or replace) an image's pipeline, evicting the least recently used
+    pub fn insert(&mut self, image_id: i64, value: T) {
+        self.remove(image_id);
+        self.entries.push_front((image_id, value));
+        self.entries.truncate(self.capacity);
+    }
+    
+    /// Forget an image's pipeline
+    pub fn remove(&mut self, image_id: i64) {
+        self.entries.retain(|(id, _)| *id != image_id);
+    }
+    
+    /// Drop every cached pipeline
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+    
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    
+    /// Cached values, most recently used first
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_least_recently_used_is_evicted() {
+        let mut cache = PipelineCache::new(2);
+        cache.insert(1, "one");
+        cache.insert(2, "two");
+        
+        // Touching 1 makes 2 the oldest
+        assert_eq!(cache.get(1), Some("one"));
+        cache.insert(3, "three");
+        assert_eq!(cache.get(2), None);
+        assert_eq!(cache.get(1), Some("one"));
+        assert_eq!(cache.get(3), Some("three"));
+        
+        // Re-inserting replaces instead of duplicating
+        cache.insert(3, "THREE");
+        assert_eq!(cache.entries.len(), 2);
+        cache.insert(4, "four");
+        assert_eq!(cache.get(3), Some("THREE"));
+        assert_eq!(cache.get(1), None);
+        
+        cache.remove(4);
+        assert_eq!(cache.get(4), None);
+        assert_eq!(cache.values().collect::<Vec<_>>(), vec![&"THREE"]);
+        
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
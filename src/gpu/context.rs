@@ -0,0 +1,362 @@
+/// Shared wgpu device, queue and render pipelines
+///
+/// Creating a wgpu instance, adapter and device takes a noticeable moment and
+/// holds driver resources, and the shader is the same for every image. So the
+/// app makes one `GpuContext` (lazily, on first use - see `shared`) and every
+/// `RenderPipeline` borrows it through an `Arc`; per image only the RAW texture,
+/// uniform buffer and bind group are created. The histogram compute pipeline
+/// lives here for the same reason. It's `Send + Sync`, so it can
+/// cross into async tasks and `spawn_blocking`.
+///
+/// With no adapter at all, `shared_or_cpu` lets pipelines fall back to
+/// rendering on the CPU (see software.rs) instead of failing.
+///
+/// The graphics API and GPU preference come from the settings
+/// (`set_preferences`, called at startup). They're read once, when the
+/// context is created, so changing them takes a restart.
+// Use wgpu from iced to avoid dependency conflicts
+use iced_wgpu::wgpu;
+use std::sync::{Arc, RwLock};
+use tokio::sync::OnceCell;
+
+use crate::state::settings::{GpuBackend, GpuPower};
+
+/// The app-wide context (created by the first `shared` call)
+static SHARED: OnceCell<Arc<GpuContext>> = OnceCell::const_new();
+
+/// `new`'s error when the system has no usable adapter on any backend
+pub const NO_ADAPTER: &str = "Failed to find suitable GPU adapter";
+
+/// Backend and adapter preference for the next context created
+static PREFERENCES: RwLock<(GpuBackend, GpuPower)> = RwLock::new((GpuBackend::Auto, GpuPower::HighPerformance));
+
+/// Use this backend and power preference when the context is created
+/// (no effect once it exists)
+pub fn set_preferences(backend: GpuBackend, power: GpuPower) {
+    *PREFERENCES.write().unwrap() = (backend, power);
+}
+
+/// wgpu backends to try for a backend setting
+fn backends(backend: GpuBackend) -> wgpu::Backends {
+    match backend {
+        GpuBackend::Auto => wgpu::Backends::all(),
+        GpuBackend::Vulkan => wgpu::Backends::VULKAN,
+        GpuBackend::Metal => wgpu::Backends::METAL,
+        GpuBackend::Dx12 => wgpu::Backends::DX12,
+        GpuBackend::Gl => wgpu::Backends::GL,
+    }
+}
+
+/// Find an adapter on the given backends
+async fn request_adapter(backends: wgpu::Backends, power: GpuPower) -> Option<wgpu::Adapter> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends,
+        ..Default::default()
+    });
+    instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: match power {
+                GpuPower::HighPerformance => wgpu::PowerPreference::HighPerformance,
+                GpuPower::LowPower => wgpu::PowerPreference::LowPower,
+            },
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        })
+        .await
+}
+
+/// One line describing an adapter, for the log and the Preferences tab
+/// ("NVIDIA GeForce RTX 3060 · Vulkan · DiscreteGpu · driver 535.129.03")
+pub fn describe_adapter(info: &wgpu::AdapterInfo) -> String {
+    let mut line = format!("{} · {:?} · {:?}", info.name, info.backend, info.device_type);
+    let driver = format!("{} {}", info.driver, info.driver_info);
+    if !driver.trim().is_empty() {
+        line.push_str(&format!(" · driver {}", driver.trim()));
+    }
+    line
+}
+
+/// The GPU device and everything that doesn't change between images
+pub struct GpuContext {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub(super) bind_group_layout: wgpu::BindGroupLayout,
+    pub(super) sampler: wgpu::Sampler,
+    pub(super) render_pipeline: wgpu::RenderPipeline,     // Rgba8Unorm, display/8-bit output
+    pub(super) render_pipeline_16: wgpu::RenderPipeline,  // Rgba16Uint target for 16-bit export
+    pub(super) histogram_bind_group_layout: wgpu::BindGroupLayout,
+    pub(super) histogram_pipeline: wgpu::ComputePipeline,  // Bins a rendered frame (see HISTOGRAM_SHADER)
+    adapter_info: wgpu::AdapterInfo,
+}
+
+// Manual Debug implementation (wgpu types don't implement Debug)
+impl std::fmt::Debug for GpuContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GpuContext").finish_non_exhaustive()
+    }
+}
+
+impl GpuContext {
+    /// The app-wide context, created on first use
+    /// (if creation fails, the next call tries again)
+    pub async fn shared() -> Result<Arc<Self>, String> {
+        SHARED.get_or_try_init(Self::new).await.cloned()
+    }
+    
+    /// The shared context, or None when there is no GPU adapter at all (so
+    /// pipelines render on the CPU); other failures are still errors
+    pub async fn shared_or_cpu() -> Result<Option<Arc<Self>>, String> {
+        match Self::shared().await {
+            Ok(gpu) => Ok(Some(gpu)),
+            Err(e) if e == NO_ADAPTER => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+    
+    /// Which GPU and graphics API the context runs on
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.adapter_info
+    }
+    
+    /// Pick a GPU adapter, create the device and queue, and compile the shader
+    pub async fn new() -> Result<Arc<Self>, String> {
+        // Request wgpu adapter on the chosen backend; a forced backend the
+        // system doesn't have falls back to automatic rather than no GPU at all
+        let (backend, power) = *PREFERENCES.read().unwrap();
+        let mut adapter = request_adapter(backends(backend), power).await;
+        if adapter.is_none() && backend != GpuBackend::Auto {
+            println!("⚠️  No {} adapter found, trying every backend", backend);
+            adapter = request_adapter(wgpu::Backends::all(), power).await;
+        }
+        let adapter = adapter.ok_or(NO_ADAPTER)?;
+        let adapter_info = adapter.get_info();
+        
+        // Request device and queue, with the adapter's own texture and buffer
+        // size limits (the defaults stop at 8192px and 256 MiB; full-resolution
+        // exports tile against whatever these turn out to be)
+        let adapter_limits = adapter.limits();
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("RAW Editor Device"),
+                    required_features: wgpu::Features::empty(),
+                    required_limits: wgpu::Limits {
+                        max_buffer_size: adapter_limits.max_buffer_size,
+                        ..wgpu::Limits::default().using_resolution(adapter_limits)
+                    },
+                },
+                None,
+            )
+            .await
+            .map_err(|e| format!("Failed to create device: {:?}", e))?;
+        
+        let sampler = create_sampler(&device);
+        let bind_group_layout = create_bind_group_layout(&device);
+        
+        // Load shader
+        let shader_source = super::shaders::get_shader();
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("RAW Processing Shader"),
+            source: wgpu::ShaderSource::Wgsl(shader_source.into()),
+        });
+        
+        // Create pipeline layout
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        
+        // Create render pipelines: one for display/8-bit output, one for 16-bit export.
+        // Both share the same shader chain; only the fragment entry point and target differ.
+        let render_pipeline = create_render_pipeline(
+            &device,
+            &pipeline_layout,
+            &shader,
+            "RAW Render Pipeline",
+            "fs_main",
+            wgpu::TextureFormat::Rgba8Unorm,
+            Some(wgpu::BlendState::REPLACE),
+        );
+        
+        // Integer targets can't blend, so the 16-bit pipeline writes straight through
+        let render_pipeline_16 = create_render_pipeline(
+            &device,
+            &pipeline_layout,
+            &shader,
+            "RAW Render Pipeline (16-bit)",
+            "fs_main_16",
+            wgpu::TextureFormat::Rgba16Uint,
+            None,
+        );
+        
+        // Histogram: rendered Rgba8Unorm frame in, 4x256 atomic counts out
+        let histogram_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Histogram Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        
+        let histogram_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Histogram Shader"),
+            source: wgpu::ShaderSource::Wgsl(super::shaders::HISTOGRAM_SHADER.into()),
+        });
+        
+        let histogram_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Histogram Pipeline Layout"),
+            bind_group_layouts: &[&histogram_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        
+        let histogram_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Histogram Compute Pipeline"),
+            layout: Some(&histogram_pipeline_layout),
+            module: &histogram_shader,
+            entry_point: "cs_histogram",
+        });
+        
+        println!("🖥️  GPU device created: {}", describe_adapter(&adapter_info));
+        Ok(Arc::new(Self {
+            device,
+            queue,
+            bind_group_layout,
+            sampler,
+            render_pipeline,
+            render_pipeline_16,
+            histogram_bind_group_layout,
+            histogram_pipeline,
+            adapter_info,
+        }))
+    }
+}
+
+/// Sampler bound next to every RAW texture
+pub(super) fn create_sampler(device: &wgpu::Device) -> wgpu::Sampler {
+    device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("RAW Texture Sampler"),
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Nearest,
+        ..Default::default()
+    })
+}
+
+/// Bindings of the processing shader: RAW texture, sampler, uniforms, curve LUT
+pub(super) fn create_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Bind Group Layout"),
+        entries: &[
+            // Texture (R16Uint = unsigned integer texture)
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Uint,  // Integer texture for RAW u16
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            // Sampler
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+            // Uniform buffer
+            // Phase 25: VERTEX visibility added for zoom/pan in vertex shader
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            // Tone curve LUT (R32Float, read with textureLoad)
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D1,
+                    multisampled: false,
+                },
+                count: None,
+            },
+        ],
+    })
+}
+
+/// A render pipeline running the processing shader with one fragment entry
+/// point into one target format
+pub(super) fn create_render_pipeline(
+    device: &wgpu::Device,
+    pipeline_layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    label: &str,
+    entry_point: &str,
+    format: wgpu::TextureFormat,
+    blend: Option<wgpu::BlendState>,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point,
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None, // Disable culling for full-screen triangle
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
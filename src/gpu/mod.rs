@@ -6,11 +6,21 @@
 /// Architecture:
 /// - `shaders.rs` - WGSL shader source code
 /// - `pipeline.rs` - wgpu render pipeline management
+/// - `context.rs` - the shared wgpu device and queue
+/// - `cache.rs` - recently used pipelines (fast image switching in Develop)
+/// - `surface.rs` - draws the Develop image straight onto the window
+/// - `software.rs` - the same processing on the CPU, for systems without a GPU
 ///
 /// The pipeline converts RAW sensor data (u16) to rendered RGB output,
 /// applying edit parameters in real-time on the GPU.
 
 pub mod shaders;
 pub mod pipeline;
+pub mod context;
+pub mod cache;
+pub mod surface;
+mod software;
 
 pub use pipeline::RenderPipeline;
+pub use context::GpuContext;
+pub use cache::PipelineCache;
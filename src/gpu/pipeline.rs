@@ -1,65 +1,158 @@
 /// wgpu render pipeline for real-time RAW image processing
 ///
 /// This module manages all the wgpu boilerplate:
-/// - Device and queue initialization
+/// - Per-image state on the shared device (see context.rs)
 /// - Texture creation and uploads
 /// - Uniform buffer for edit parameters
 /// - Render pipeline state
 /// - Draw commands
+///
+/// Everything happens in a single pass straight from the RAW mosaic, including
+/// clarity (local contrast). Clarity needs a blurred neighborhood, which would
+/// normally mean rendering debayered RGB to an intermediate texture and blurring
+/// it in a second pass - for a 24MP export that is ~200MB of extra Rgba16Float
+/// plus two more full-resolution passes. Instead the shader estimates the local
+/// average from a sparse ring of 2x2 Bayer-tile luminance taps around each pixel
+/// (`local_detail` in shaders.rs). The ring radius is in source pixels, so the
+/// preview and the full-resolution export get the same look.
+///
+/// Noise reduction works the same way: `denoise` debayers a 5x5 neighborhood
+/// of source pixels (bilinear) and takes an edge-aware average. That costs ~25
+/// debayers per output pixel, so it only runs when an amount is non-zero.
+/// Capture sharpening (`sharpen_detail`) samples the same kind of neighborhood
+/// to build its unsharp mask.
 
 // Use wgpu from iced to avoid dependency conflicts
 use iced_wgpu::wgpu;
 use wgpu::util::DeviceExt;
-use crate::state::edit::EditParams;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use super::context::GpuContext;
+use super::software::{self, SoftwareRenderer};
+use crate::raw::loader::{CfaPattern, SensorLevels};
+use crate::state::edit::{crop_size, CropRect, DemosaicMethod, EditParams, MaskKind, ToneMode, CURVE_LUT_SIZE, FULL_FRAME, HIGHLIGHTS_RANGE, HSL_BANDS, MAX_MASKS};
+use crate::state::lens::{self, LensProfile};
+use crate::state::orientation::Orientation;
 
 /// Represents the edit parameters in a GPU-friendly format
 /// Must match the WGSL struct layout with proper alignment
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-struct GpuEditParams {
-    exposure: f32,
-    contrast: f32,
-    highlights: f32,
-    shadows: f32,
-    whites: f32,
-    blacks: f32,
-    vibrance: f32,
-    saturation: f32,
-    temperature: f32,
-    tint: f32,
-    padding1: f32,  // For 16-byte alignment
-    padding2: f32,
+pub(super) struct GpuEditParams {
+    pub(super) exposure: f32,
+    pub(super) contrast: f32,
+    pub(super) highlights: f32,
+    pub(super) shadows: f32,
+    pub(super) whites: f32,
+    pub(super) blacks: f32,
+    pub(super) vibrance: f32,
+    pub(super) saturation: f32,
+    pub(super) temperature: f32,
+    pub(super) tint: f32,
+    pub(super) curve_enabled: f32,  // 1.0 = apply tone curve LUT, 0.0 = skip (identity)
+    pub(super) demosaic_mode: f32,  // 0.0 = bilinear, 1.0 = Malvar-He-Cutler
     // Phase 14: Color science (must match WGSL layout!)
-    wb_multipliers: [f32; 4],   // White balance [R, G, B, G2] - vec4 in WGSL
+    pub(super) wb_multipliers: [f32; 4],   // White balance [R, G, B, G2] - vec4 in WGSL
     // Color matrix split into 3 rows with padding (WGSL vec3 = 12 bytes + 4 padding)
-    color_matrix_0: [f32; 3],   // Row 0
-    _padding3: f32,
-    color_matrix_1: [f32; 3],   // Row 1
-    _padding4: f32,
-    color_matrix_2: [f32; 3],   // Row 2
-    _padding5: f32,
+    pub(super) color_matrix_0: [f32; 3],   // Row 0
+    pub(super) _padding3: f32,
+    pub(super) color_matrix_1: [f32; 3],   // Row 1
+    pub(super) _padding4: f32,
+    pub(super) color_matrix_2: [f32; 3],   // Row 2
+    pub(super) _padding5: f32,
     // Phase 25: Zoom & Pan
-    zoom: f32,                  // Zoom level (1.0 = 100%)
-    pan_x: f32,                 // Pan offset X
-    pan_y: f32,                 // Pan offset Y
-    _padding6: f32,             // Padding for alignment
+    pub(super) zoom: f32,                  // Zoom level (1.0 = 100%)
+    pub(super) pan_x: f32,                 // Pan offset X
+    pub(super) pan_y: f32,                 // Pan offset Y
+    pub(super) _padding6: f32,             // Padding for alignment
+    // Bayer layout: offset that moves a pixel onto an RGGB origin
+    pub(super) cfa_offset_x: f32,
+    pub(super) cfa_offset_y: f32,
+    pub(super) _padding7: f32,
+    pub(super) _padding8: f32,
+    // Geometry: crop rectangle in the straightened frame (0-1) and rotation
+    pub(super) crop_x: f32,
+    pub(super) crop_y: f32,
+    pub(super) crop_w: f32,
+    pub(super) crop_h: f32,
+    pub(super) rotation: f32,              // Radians, positive = clockwise
+    pub(super) clarity: f32,               // Local contrast (-1 to +1), fills what was padding
+    pub(super) hsl_enabled: f32,           // 1.0 = apply the HSL bands, 0.0 = skip (all zero)
+    pub(super) tone_mode: f32,             // 0.0 = linear, 1.0 = S-curve, 2.0 = filmic; fills what was padding
+    // HSL bands: (hue, saturation, luminance, unused) - array<vec4<f32>, 8> in WGSL
+    pub(super) hsl: [[f32; 4]; HSL_BANDS],
+    // Black & white channel mixer (vec3 + flag = one 16-byte row)
+    pub(super) bw_mix: [f32; 3],
+    pub(super) monochrome: f32,            // 1.0 = convert to gray with bw_mix
+    // Post-crop vignette
+    pub(super) vignette_amount: f32,       // -1 (dark corners) to +1 (bright corners)
+    pub(super) vignette_midpoint: f32,     // Falloff start, fraction of center-to-corner distance
+    pub(super) luma_nr: f32,               // Luminance noise reduction (0-1), fills what was padding
+    pub(super) chroma_nr: f32,             // Color noise reduction (0-1), fills what was padding
+    // Capture sharpening
+    pub(super) sharpen_amount: f32,        // 0 = off
+    pub(super) sharpen_radius: f32,        // Gaussian sigma in source pixels
+    pub(super) detail_preview: f32,        // 1.0 = show only the sharpening high-pass (not an edit)
+    pub(super) show_clipping: f32,         // 1.0 = paint clipped highlights red, crushed blacks blue (not an edit)
+    // Orientation (EXIF + manual rotations): mirror, then clockwise quarter turns
+    pub(super) orientation_turns: f32,
+    pub(super) orientation_mirror: f32,
+    pub(super) _padding12: f32,
+    pub(super) _padding13: f32,
+    // Sensor levels: raw values are normalized as (raw - black) / (white - black)
+    pub(super) black_level: f32,
+    pub(super) white_level: f32,
+    pub(super) highlight_recovery: f32,    // Clipped-channel reconstruction (0-1), fills what was padding
+    pub(super) lens_correction: f32,       // 1.0 = apply the lens_* coefficients, fills what was padding
+    // Lens correction (see state/lens.rs): distortion k1, k2 and vignetting v1, v2
+    pub(super) lens_k1: f32,
+    pub(super) lens_k2: f32,
+    pub(super) lens_v1: f32,
+    pub(super) lens_v2: f32,
+    // Output tile rendered by this pass (fractions of the full output, see `export_tiles`)
+    pub(super) tile_x: f32,
+    pub(super) tile_y: f32,
+    pub(super) tile_w: f32,
+    pub(super) tile_h: f32,
+    // Film negative inversion (vec3 + flag = one 16-byte row)
+    pub(super) film_base: [f32; 3],
+    pub(super) invert: f32,                // 1.0 = invert against film_base
+    // Local adjustment masks: only the first mask_count entries are read
+    pub(super) mask_count: f32,
+    pub(super) _padding14: f32,
+    pub(super) _padding15: f32,
+    pub(super) _padding16: f32,
+    // Three rows per mask (array<vec4<f32>, 24> in WGSL): (start, end),
+    // (kind 0 = radial / 1 = linear, feather, unused, unused),
+    // (exposure, contrast, saturation, unused)
+    pub(super) masks: [[f32; 4]; MAX_MASKS * 3],
+    // Split toning (vec3 + scalar = one 16-byte row each)
+    pub(super) shadow_tint: [f32; 3],
+    pub(super) balance: f32,               // Crossover shift, -1 to +1
+    pub(super) highlight_tint: [f32; 3],
+    pub(super) split_tone_enabled: f32,    // 1.0 = apply the tints, 0.0 = skip (both zero)
 }
 
 impl From<&EditParams> for GpuEditParams {
     fn from(params: &EditParams) -> Self {
+        let crop = params.crop_or_full();
         Self {
             exposure: params.exposure,
             contrast: params.contrast,
-            highlights: params.highlights,
-            shadows: params.shadows,
-            whites: params.whites,
-            blacks: params.blacks,
+            // Sliders are -100..100; the shader takes the strengths and points
+            highlights: params.highlights / 100.0 * HIGHLIGHTS_RANGE,
+            shadows: params.shadows / 100.0,
+            whites: params.white_point(),
+            blacks: params.black_point(),
             vibrance: params.vibrance,
             saturation: params.saturation,
-            temperature: params.temperature as f32,
-            tint: params.tint as f32,
-            padding1: 0.0,
-            padding2: 0.0,
+            temperature: params.temperature,
+            tint: params.tint,
+            curve_enabled: if params.curve.is_identity() { 0.0 } else { 1.0 },
+            demosaic_mode: match params.demosaic {
+                DemosaicMethod::Bilinear => 0.0,
+                DemosaicMethod::Malvar => 1.0,
+            },
             // Default values (will be overwritten by set_color_metadata)
             wb_multipliers: [1.0, 1.0, 1.0, 1.0],
             color_matrix_0: [1.0, 0.0, 0.0],
@@ -73,30 +166,412 @@ impl From<&EditParams> for GpuEditParams {
             pan_x: 0.0,
             pan_y: 0.0,
             _padding6: 0.0,
+            // RGGB at the origin (overwritten from the pipeline's CFA offset)
+            cfa_offset_x: 0.0,
+            cfa_offset_y: 0.0,
+            _padding7: 0.0,
+            _padding8: 0.0,
+            crop_x: crop.0,
+            crop_y: crop.1,
+            crop_w: crop.2,
+            crop_h: crop.3,
+            rotation: params.rotation_degrees.to_radians(),
+            clarity: params.clarity,
+            hsl_enabled: if params.hsl_is_identity() { 0.0 } else { 1.0 },
+            tone_mode: match params.tone_mode {
+                ToneMode::Linear => 0.0,
+                ToneMode::SCurve => 1.0,
+                ToneMode::Filmic => 2.0,
+            },
+            hsl: params.hsl.map(|band| [band.hue, band.saturation, band.luminance, 0.0]),
+            bw_mix: params.bw_mix,
+            monochrome: if params.monochrome { 1.0 } else { 0.0 },
+            vignette_amount: params.vignette_amount,
+            vignette_midpoint: params.vignette_midpoint,
+            luma_nr: params.luma_nr,
+            chroma_nr: params.chroma_nr,
+            sharpen_amount: params.sharpen_amount,
+            sharpen_radius: params.sharpen_radius,
+            detail_preview: 0.0,
+            show_clipping: 0.0,
+            // Upright (overwritten from the pipeline's orientation)
+            orientation_turns: 0.0,
+            orientation_mirror: 0.0,
+            _padding12: 0.0,
+            _padding13: 0.0,
+            // 12-bit without a pedestal (overwritten from the pipeline's levels)
+            black_level: SensorLevels::TWELVE_BIT.black,
+            white_level: SensorLevels::TWELVE_BIT.white,
+            highlight_recovery: params.highlight_recovery,
+            // Off (overwritten from the pipeline's lens profile)
+            lens_correction: 0.0,
+            lens_k1: 0.0,
+            lens_k2: 0.0,
+            lens_v1: 0.0,
+            lens_v2: 0.0,
+            // The whole output (only tiled exports render less)
+            tile_x: 0.0,
+            tile_y: 0.0,
+            tile_w: 1.0,
+            tile_h: 1.0,
+            film_base: params.film_base,
+            invert: if params.invert { 1.0 } else { 0.0 },
+            mask_count: params.masks.len() as f32,
+            _padding14: 0.0,
+            _padding15: 0.0,
+            _padding16: 0.0,
+            masks: gpu_masks(params),
+            shadow_tint: params.shadow_tint,
+            balance: params.balance,
+            highlight_tint: params.highlight_tint,
+            split_tone_enabled: if params.split_tone_is_identity() { 0.0 } else { 1.0 },
         }
     }
 }
 
-/// Main render pipeline for RAW image processing
-pub struct RenderPipeline {
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    pipeline: wgpu::RenderPipeline,
+/// Pack the image's masks into the uniform rows (unused rows stay zero)
+fn gpu_masks(params: &EditParams) -> [[f32; 4]; MAX_MASKS * 3] {
+    let mut rows = [[0.0; 4]; MAX_MASKS * 3];
+    for (i, mask) in params.masks.as_slice().iter().enumerate() {
+        let kind = match mask.kind {
+            MaskKind::Radial => 0.0,
+            MaskKind::Linear => 1.0,
+        };
+        rows[i * 3] = [mask.start[0], mask.start[1], mask.end[0], mask.end[1]];
+        rows[i * 3 + 1] = [kind, mask.feather, 0.0, 0.0];
+        rows[i * 3 + 2] = [mask.exposure, mask.contrast, mask.saturation, 0.0];
+    }
+    rows
+}
+
+/// Source of `RenderPipeline::generation`
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+/// Per-image wgpu objects on the shared context
+struct GpuResources {
+    context: Arc<GpuContext>, // Shared device, queue and render pipelines
     bind_group: wgpu::BindGroup,
     uniform_buffer: wgpu::Buffer,
     texture: wgpu::Texture,
     texture_view: wgpu::TextureView,
+    curve_texture: wgpu::Texture,  // 256-entry tone curve LUT (1D, R32Float)
+}
+
+/// Where a pipeline renders
+enum Backend {
+    Gpu(Box<GpuResources>),
+    /// No GPU adapter on this system: the shader's math on the CPU (see software.rs)
+    Cpu(Box<SoftwareRenderer>),
+}
+
+impl GpuResources {
+    /// Upload the RAW texture, uniforms and curve LUT, and bind them
+    fn new(
+        context: Arc<GpuContext>,
+        raw_data: &[u16],
+        width: u32,
+        height: u32,
+        params: &EditParams,
+        gpu_params: &GpuEditParams,
+    ) -> Result<Self, String> {
+        // Shared device and queue (created once per app, see context.rs)
+        let device = &context.device;
+        let queue = &context.queue;
+        
+        // The RAW itself has to fit in one texture (exports tile, the input can't)
+        let max_dimension = device.limits().max_texture_dimension_2d;
+        if width > max_dimension || height > max_dimension {
+            return Err(format!(
+                "{}x{} is larger than this GPU's {}px texture limit",
+                width, height, max_dimension
+            ));
+        }
+        
+        let bindings = create_image_bindings(device, queue, &context.bind_group_layout, &context.sampler, raw_data, width, height, params, gpu_params);
+        Ok(Self {
+            bind_group: bindings.bind_group,
+            uniform_buffer: bindings.uniform_buffer,
+            texture: bindings.texture,
+            texture_view: bindings.texture_view,
+            curve_texture: bindings.curve_texture,
+            context,
+        })
+    }
+    
+    /// Uniforms and curve LUT of their own over the same RAW texture, so an
+    /// export isn't touched by the preview updates that go on while it renders
+    fn export_bindings(&self, params: &EditParams, gpu_params: &GpuEditParams) -> UniformBindings {
+        let context = &self.context;
+        create_uniform_bindings(&context.device, &context.queue, &context.bind_group_layout, &context.sampler, &self.texture_view, params, gpu_params)
+    }
+    
+    /// Point the shader at one output tile: [x, y, width, height] as fractions
+    /// of the full output (only the tile fields of `uniform_buffer` change)
+    fn write_tile(&self, uniform_buffer: &wgpu::Buffer, tile: [f32; 4]) {
+        self.context.queue.write_buffer(
+            uniform_buffer,
+            std::mem::offset_of!(GpuEditParams, tile_x) as u64,
+            bytemuck::cast_slice(&tile),
+        );
+    }
+    
+    /// Read a region of the RAW texture back (`width` x `height` sensor
+    /// values from `origin`)
+    fn read_raw(&self, origin: (u32, u32), width: u32, height: u32) -> Option<Vec<u16>> {
+        let bytes_per_row = width * 2;  // 2 bytes per pixel (u16)
+        let padded_bytes_per_row = padded_row_bytes(bytes_per_row);
+        let output_buffer = self.context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("RAW Readback Buffer"),
+            size: (padded_bytes_per_row * height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        
+        let mut encoder = self.context.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("RAW Readback Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: origin.0, y: origin.1, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &output_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.context.queue.submit(Some(encoder.finish()));
+        
+        let buffer_slice = output_buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            tx.send(result).unwrap();
+        });
+        self.context.device.poll(wgpu::Maintain::Wait);
+        rx.recv().ok()?.ok()?;
+        
+        let data = buffer_slice.get_mapped_range();
+        let raw = unpad_rows_u16(&data, bytes_per_row, padded_bytes_per_row, height);
+        drop(data);
+        output_buffer.unmap();
+        
+        Some(raw)
+    }
+}
+
+/// Encode one pass of the processing shader: the full-screen triangle drawn
+/// into `viewport` ([x, y, width, height] in target pixels) of `target`,
+/// clipped to `scissor` if given
+pub(super) fn draw_pass(
+    encoder: &mut wgpu::CommandEncoder,
+    target: &wgpu::TextureView,
+    render_pipeline: &wgpu::RenderPipeline,
+    bind_group: &wgpu::BindGroup,
+    viewport: [f32; 4],
+    scissor: Option<[u32; 4]>,
+    load: wgpu::LoadOp<wgpu::Color>,
+) {
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("RAW Render Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: target,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+    let [x, y, width, height] = viewport;
+    render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+    if let Some([x, y, width, height]) = scissor {
+        render_pass.set_scissor_rect(x, y, width, height);
+    }
+    render_pass.set_pipeline(render_pipeline);
+    render_pass.set_bind_group(0, bind_group, &[]);
+    render_pass.draw(0..3, 0..1); // Full-screen triangle
+}
+
+/// A RAW image's texture, curve LUT and uniforms, bound for the shader
+pub(super) struct ImageBindings {
+    pub(super) bind_group: wgpu::BindGroup,
+    pub(super) uniform_buffer: wgpu::Buffer,
+    pub(super) texture: wgpu::Texture,
+    pub(super) texture_view: wgpu::TextureView,
+    pub(super) curve_texture: wgpu::Texture,
+}
+
+/// Upload a RAW image and bind it with `layout` (see `create_bind_group_layout`)
+/// on whichever device draws it: the shared context's, or the window's (surface.rs)
+#[allow(clippy::too_many_arguments)]
+pub(super) fn create_image_bindings(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    raw_data: &[u16],
+    width: u32,
+    height: u32,
+    params: &EditParams,
+    gpu_params: &GpuEditParams,
+) -> ImageBindings {
+    // Create texture for RAW u16 data (R16Uint format)
+    let texture_size = wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+    
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("RAW Input Texture (R16Uint)"),
+        size: texture_size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::R16Uint,  // 16-bit unsigned integer for RAW data
+        // COPY_SRC so the white balance picker can read raw patches back
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    
+    // Upload RAW u16 data directly (no conversion!)
+    let raw_bytes = bytemuck::cast_slice(raw_data);
+    println!("💾 Uploading {} bytes of RAW u16 data to GPU", raw_bytes.len());
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        raw_bytes,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(2 * width),  // 2 bytes per pixel (u16)
+            rows_per_image: Some(height),
+        },
+        texture_size,
+    );
+    println!("✅ RAW texture uploaded to GPU!");
+    
+    let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let uniforms = create_uniform_bindings(device, queue, layout, sampler, &texture_view, params, gpu_params);
+    
+    ImageBindings {
+        bind_group: uniforms.bind_group,
+        uniform_buffer: uniforms.uniform_buffer,
+        texture,
+        texture_view,
+        curve_texture: uniforms.curve_texture,
+    }
+}
+
+/// A uniform buffer and curve LUT, bound with a RAW texture view
+pub(super) struct UniformBindings {
+    pub(super) bind_group: wgpu::BindGroup,
+    pub(super) uniform_buffer: wgpu::Buffer,
+    pub(super) curve_texture: wgpu::Texture,
+}
+
+/// Create the uniform buffer and curve LUT for `params` and bind them with
+/// `texture_view` (one set per image, and one per export, see `render_full_res`)
+pub(super) fn create_uniform_bindings(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    texture_view: &wgpu::TextureView,
+    params: &EditParams,
+    gpu_params: &GpuEditParams,
+) -> UniformBindings {
+    // Create tone curve LUT texture (only sampled when the curve isn't identity)
+    let curve_texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Tone Curve LUT (R32Float)"),
+        size: wgpu::Extent3d {
+            width: CURVE_LUT_SIZE as u32,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D1,
+        format: wgpu::TextureFormat::R32Float,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    write_curve_lut(queue, &curve_texture, params);
+    let curve_view = curve_texture.create_view(&wgpu::TextureViewDescriptor::default());
+    
+    let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Edit Params Uniform Buffer"),
+        contents: bytemuck::cast_slice(&[*gpu_params]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+    
+    // Create bind group
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Bind Group"),
+        layout: layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(texture_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::TextureView(&curve_view),
+            },
+        ],
+    });
+    
+    UniformBindings {
+        bind_group,
+        uniform_buffer,
+        curve_texture,
+    }
+}
+
+/// Main render pipeline for RAW image processing
+pub struct RenderPipeline {
+    backend: Backend,
     pub width: u32,           // Full resolution width
     pub height: u32,          // Full resolution height
-    pub preview_width: u32,   // Preview resolution width (for fast rendering)
-    pub preview_height: u32,  // Preview resolution height (for fast rendering)
     pub image_id: i64,        // Phase 20: Track which image this pipeline is for
-    // Phase 22: Histogram optimization - tiny render for instant histogram
-    pub histogram_width: u32,  // Histogram resolution width (256px)
-    pub histogram_height: u32, // Histogram resolution height (maintains aspect)
+    pub generation: u64,      // Unique per pipeline: a reloaded image gets a new one
+    crop_rect: std::sync::Mutex<Option<CropRect>>,  // Crop of the last uniform update (sets output sizes)
     // Phase 14: Color science metadata
-    wb_multipliers: [f32; 4],  // White balance from camera
+    wb_multipliers: [f32; 4],  // As-shot white balance (a picked one lives in the edits, see `white_balance`)
     color_matrix: [f32; 9],    // Color correction matrix
+    cfa_offset: [u32; 2],      // Bayer pattern offset relative to RGGB
+    levels: SensorLevels,      // Black pedestal and white point of the raw values
+    detail_preview: std::sync::Mutex<bool>,  // Show the sharpening high-pass instead of the image
+    show_clipping: std::sync::Mutex<bool>,   // Paint clipped pixels in warning colors
+    orientation: std::sync::Mutex<Orientation>,  // Turns the sensor image upright (output sizes follow it)
+    lens_profile: std::sync::Mutex<Option<LensProfile>>,  // Built-in profile for the lens, if known
+    max_preview_width: std::sync::Mutex<u32>,  // Preview renders are capped at this width
+    raw_histogram: [[u32; 256]; 4],  // Sensor values before any processing (see `raw_histogram`)
 }
 
 // Manual Debug implementation (wgpu types don't implement Debug)
@@ -111,7 +586,11 @@ impl std::fmt::Debug for RenderPipeline {
 
 impl RenderPipeline {
     /// Create a new render pipeline with the given RAW data
+    /// Without a GPU context (`None`, see `GpuContext::shared_or_cpu`) it
+    /// renders on the CPU instead: same output, but far slower.
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
+        gpu: Option<Arc<GpuContext>>,
         image_id: i64,        // Phase 20: Track which image this pipeline is for
         raw_data: Vec<u16>,
         width: u32,
@@ -119,246 +598,63 @@ impl RenderPipeline {
         params: &EditParams,
         wb_multipliers: [f32; 4],
         color_matrix: [f32; 9],
+        cfa_pattern: CfaPattern,
+        levels: SensorLevels,
+        max_preview_width: u32,  // Phase 13: previews are capped at this width for fast updates
     ) -> Result<Self, String> {
-        // Calculate preview dimensions for fast rendering
-        // Phase 13: Render to smaller texture to eliminate 1-2s lag
-        const MAX_PREVIEW_WIDTH: u32 = 1280;
-        let aspect_ratio = width as f32 / height as f32;
-        let preview_width = width.min(MAX_PREVIEW_WIDTH);
-        let preview_height = (preview_width as f32 / aspect_ratio) as u32;
-        
-        // Phase 22: Calculate tiny histogram dimensions for instant calculation
-        const HISTOGRAM_WIDTH: u32 = 128;
-        let histogram_width = HISTOGRAM_WIDTH;
-        let histogram_height = (histogram_width as f32 / aspect_ratio) as u32;
+        // Calculate preview dimensions for fast rendering (logged only;
+        // the actual sizes follow the crop, see `preview_size`)
+        let output = crop_size(params.crop_or_full(), width, height);
+        let (preview_width, preview_height) = scaled_to_width(output, max_preview_width);
         
         println!("📐 Full resolution: {}x{}", width, height);
         println!("📐 Preview resolution: {}x{} ({:.1}% of full)", 
             preview_width, preview_height,
             (preview_width * preview_height) as f32 / (width * height) as f32 * 100.0);
-        println!("📐 Histogram resolution: {}x{} ({:.3}% of full)", 
-            histogram_width, histogram_height,
-            (histogram_width * histogram_height) as f32 / (width * height) as f32 * 100.0);
-        
-        // Request wgpu adapter
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::all(),
-            ..Default::default()
-        });
         
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: None,
-                force_fallback_adapter: false,
-            })
-            .await
-            .ok_or("Failed to find suitable GPU adapter")?;
-        
-        // Request device and queue
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: Some("RAW Editor Device"),
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
-                },
-                None,
-            )
-            .await
-            .map_err(|e| format!("Failed to create device: {:?}", e))?;
-        
-        // Create texture for RAW u16 data (R16Uint format)
-        let texture_size = wgpu::Extent3d {
-            width,
-            height,
-            depth_or_array_layers: 1,
-        };
-        
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("RAW Input Texture (R16Uint)"),
-            size: texture_size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::R16Uint,  // 16-bit unsigned integer for RAW data
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[],
-        });
-        
-        // Upload RAW u16 data directly (no conversion!)
-        let raw_bytes = bytemuck::cast_slice(&raw_data);
-        println!("💾 Uploading {} bytes of RAW u16 data to GPU", raw_bytes.len());
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            raw_bytes,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(2 * width),  // 2 bytes per pixel (u16)
-                rows_per_image: Some(height),
-            },
-            texture_size,
-        );
-        println!("✅ RAW texture uploaded to GPU!");
-        
-        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        
-        // Create sampler
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("RAW Texture Sampler"),
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
+        // Bayer layout: the shader demosaics relative to RGGB, shifted by this offset
+        let cfa_offset = cfa_pattern.offset();
+        // Binned once here: the raw data is gone (uploaded) after this
+        let raw_histogram = raw_histogram(&raw_data, width, cfa_offset, levels);
         
         // Create uniform buffer with color metadata
         let mut gpu_params: GpuEditParams = params.into();
         // Phase 14: Set color science metadata from camera
-        gpu_params.wb_multipliers = wb_multipliers;
+        gpu_params.wb_multipliers = params.wb_override.unwrap_or(wb_multipliers);
         // Split flat color_matrix [9] into 3 rows with padding
         gpu_params.color_matrix_0 = [color_matrix[0], color_matrix[1], color_matrix[2]];
         gpu_params.color_matrix_1 = [color_matrix[3], color_matrix[4], color_matrix[5]];
         gpu_params.color_matrix_2 = [color_matrix[6], color_matrix[7], color_matrix[8]];
+        gpu_params.cfa_offset_x = cfa_offset[0] as f32;
+        gpu_params.cfa_offset_y = cfa_offset[1] as f32;
+        gpu_params.black_level = levels.black;
+        gpu_params.white_level = levels.white;
         
-        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Edit Params Uniform Buffer"),
-            contents: bytemuck::cast_slice(&[gpu_params]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-        
-        // Create bind group layout
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Bind Group Layout"),
-            entries: &[
-                // Texture (R16Uint = unsigned integer texture)
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Uint,  // Integer texture for RAW u16
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                // Sampler
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-                // Uniform buffer
-                // Phase 25: VERTEX visibility added for zoom/pan in vertex shader
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-            ],
-        });
-        
-        // Create bind group
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Bind Group"),
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&sampler),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: uniform_buffer.as_entire_binding(),
-                },
-            ],
-        });
-        
-        // Load shader
-        let shader_source = super::shaders::get_shader();
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("RAW Processing Shader"),
-            source: wgpu::ShaderSource::Wgsl(shader_source.into()),
-        });
-        
-        // Create pipeline layout
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("Pipeline Layout"),
-            bind_group_layouts: &[&bind_group_layout],
-            push_constant_ranges: &[],
-        });
-        
-        // Create render pipeline
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("RAW Render Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: wgpu::TextureFormat::Rgba8Unorm,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None, // Disable culling for full-screen triangle
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-        });
+        let backend = match gpu {
+            Some(context) => Backend::Gpu(Box::new(GpuResources::new(context, &raw_data, width, height, params, &gpu_params)?)),
+            None => {
+                println!("🐢 No GPU adapter: rendering on the CPU");
+                Backend::Cpu(Box::new(SoftwareRenderer::new(raw_data, width, height, gpu_params, params)))
+            }
+        };
         
         Ok(Self {
-            device,
-            queue,
-            pipeline,
-            bind_group,
-            uniform_buffer,
-            texture,
-            texture_view,
+            backend,
             width,
             height,
-            preview_width,
-            preview_height,
             image_id,          // Phase 20: Track which image this pipeline is for
-            histogram_width,   // Phase 22: Tiny render for histogram
-            histogram_height,  // Phase 22: Tiny render for histogram
+            generation: NEXT_GENERATION.fetch_add(1, Ordering::Relaxed),
+            crop_rect: std::sync::Mutex::new(params.crop_rect),
             wb_multipliers,
             color_matrix,
+            cfa_offset,
+            levels,
+            detail_preview: std::sync::Mutex::new(false),
+            show_clipping: std::sync::Mutex::new(false),
+            orientation: std::sync::Mutex::new(Orientation::default()),
+            lens_profile: std::sync::Mutex::new(None),
+            max_preview_width: std::sync::Mutex::new(max_preview_width),
+            raw_histogram,
         })
     }
     
@@ -371,81 +667,144 @@ impl RenderPipeline {
     /// Update uniform buffer with zoom and pan
     /// Phase 25: Full control over all uniforms including zoom/pan
     pub fn update_uniforms_with_zoom(&self, params: &EditParams, zoom: f32, pan_x: f32, pan_y: f32) {
+        self.write_uniforms(params, zoom, pan_x, pan_y, true);
+    }
+    
+    /// Update uniforms as an export sees them: no zoom/pan, and none of the
+    /// display overlays (detail preview, clipping warnings). Full-resolution
+    /// renders don't need this, they bind their own (see `render_full_res`).
+    pub fn update_uniforms_for_export(&self, params: &EditParams) {
+        self.write_uniforms(params, 1.0, 0.0, 0.0, false);
+    }
+    
+    fn write_uniforms(&self, params: &EditParams, zoom: f32, pan_x: f32, pan_y: f32, overlays: bool) {
+        let gpu_params = self.gpu_uniforms(params, zoom, pan_x, pan_y, overlays);
+        // Output sizes follow the crop
+        *self.crop_rect.lock().unwrap() = params.crop_rect;
+        
+        println!("🎨 GPU Uniforms Updated:");
+        println!("   Exposure: {:.2}, Contrast: {:.0}", gpu_params.exposure, gpu_params.contrast);
+        println!("   Highlights: {:.0}, Shadows: {:.0}", params.highlights, params.shadows);
+        println!("   Temp: {:.2}, Tint: {:.2}", gpu_params.temperature, gpu_params.tint);
+        println!("   Zoom: {:.1}%, Pan: ({:.3}, {:.3})", zoom * 100.0, pan_x, pan_y);
+        
+        match &self.backend {
+            Backend::Gpu(gpu) => {
+                gpu.context.queue.write_buffer(
+                    &gpu.uniform_buffer,
+                    0,
+                    bytemuck::cast_slice(&[gpu_params]),
+                );
+                
+                // Tone curve LUT (skipped entirely while the curve is identity)
+                write_curve_lut(&gpu.context.queue, &gpu.curve_texture, params);
+            }
+            Backend::Cpu(cpu) => cpu.set_uniforms(gpu_params, params),
+        }
+    }
+    
+    /// The uniform block for these edits, view and overlays (also what the
+    /// window surface draws with, see surface.rs)
+    pub(super) fn gpu_uniforms(&self, params: &EditParams, zoom: f32, pan_x: f32, pan_y: f32, overlays: bool) -> GpuEditParams {
         let mut gpu_params = GpuEditParams::from(params);
         // Preserve color metadata (doesn't change with slider updates)
-        gpu_params.wb_multipliers = self.wb_multipliers;
+        gpu_params.wb_multipliers = self.white_balance(params);
         // Convert flat matrix to split rows
         let cm = &self.color_matrix;
         gpu_params.color_matrix_0 = [cm[0], cm[1], cm[2]];
         gpu_params.color_matrix_1 = [cm[3], cm[4], cm[5]];
         gpu_params.color_matrix_2 = [cm[6], cm[7], cm[8]];
+        gpu_params.cfa_offset_x = self.cfa_offset[0] as f32;
+        gpu_params.cfa_offset_y = self.cfa_offset[1] as f32;
+        gpu_params.black_level = self.levels.black;
+        gpu_params.white_level = self.levels.white;
         // Phase 25: Set zoom and pan
         gpu_params.zoom = zoom;
         gpu_params.pan_x = pan_x;
         gpu_params.pan_y = pan_y;
-        
-        println!("🎨 GPU Uniforms Updated:");
-        println!("   Exposure: {:.2}, Contrast: {:.0}", gpu_params.exposure, gpu_params.contrast);
-        println!("   Highlights: {:.0}, Shadows: {:.0}", gpu_params.highlights, gpu_params.shadows);
-        println!("   Temp: {}, Tint: {}", gpu_params.temperature, gpu_params.tint);
-        println!("   Zoom: {:.1}%, Pan: ({:.3}, {:.3})", zoom * 100.0, pan_x, pan_y);
-        
-        self.queue.write_buffer(
-            &self.uniform_buffer,
-            0,
-            bytemuck::cast_slice(&[gpu_params]),
-        );
+        let orientation = self.orientation();
+        gpu_params.orientation_turns = orientation.quarter_turns as f32;
+        gpu_params.orientation_mirror = if orientation.mirrored { 1.0 } else { 0.0 };
+        if let Some([k1, k2, v1, v2]) = lens::coefficients(params, self.lens_profile.lock().unwrap().as_ref()) {
+            gpu_params.lens_correction = 1.0;
+            (gpu_params.lens_k1, gpu_params.lens_k2) = (k1, k2);
+            (gpu_params.lens_v1, gpu_params.lens_v2) = (v1, v2);
+        }
+        if overlays {
+            gpu_params.detail_preview = if *self.detail_preview.lock().unwrap() { 1.0 } else { 0.0 };
+            gpu_params.show_clipping = if *self.show_clipping.lock().unwrap() { 1.0 } else { 0.0 };
+        }
+        gpu_params
     }
     
-    /// Render directly to an iced-provided texture view (Canvas integration)
-    /// This eliminates the GPU→CPU readback bottleneck!
+    /// True when this pipeline renders on the CPU (no GPU adapter)
+    pub fn is_software(&self) -> bool {
+        matches!(self.backend, Backend::Cpu(_))
+    }
+    
+    /// Show only the sharpening detail (high-pass) instead of the image, to
+    /// help dial in the amount and radius. Takes effect on the next uniform update.
+    pub fn set_detail_preview(&self, enabled: bool) {
+        *self.detail_preview.lock().unwrap() = enabled;
+    }
+    
+    /// Paint clipped highlights red and crushed blacks blue (display only).
+    /// Takes effect on the next uniform update.
+    pub fn set_show_clipping(&self, enabled: bool) {
+        *self.show_clipping.lock().unwrap() = enabled;
+    }
+    
+    /// Turn the image upright (EXIF orientation plus manual rotations).
+    /// Takes effect on the next uniform update; output sizes follow at once.
+    pub fn set_orientation(&self, orientation: Orientation) {
+        *self.orientation.lock().unwrap() = orientation;
+    }
+    
+    /// Current orientation
+    pub fn orientation(&self) -> Orientation {
+        *self.orientation.lock().unwrap()
+    }
+    
+    /// Render into a texture view on the pipeline's own device (preview
+    /// readbacks, the histogram, export tiles); the Develop view draws the
+    /// same pass onto the window instead, see surface.rs
+    /// (Draws nothing for a CPU pipeline, which has no device to draw with.)
     pub fn render_to_target(
         &self,
         encoder: &mut wgpu::CommandEncoder,
         target: &wgpu::TextureView,
         viewport: (u32, u32),
     ) {
-        // Create render pass that draws directly to iced's surface
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("RAW Render Pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: target,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                    store: wgpu::StoreOp::Store,
-                },
-            })],
-            depth_stencil_attachment: None,
-            timestamp_writes: None,
-            occlusion_query_set: None,
-        });
+        let Backend::Gpu(gpu) = &self.backend else {
+            return;
+        };
         
-        // Set viewport to match canvas size
-        render_pass.set_viewport(
-            0.0,
-            0.0,
-            viewport.0 as f32,
-            viewport.1 as f32,
-            0.0,
-            1.0,
+        draw_pass(
+            encoder,
+            target,
+            &gpu.context.render_pipeline,
+            &gpu.bind_group,
+            [0.0, 0.0, viewport.0 as f32, viewport.1 as f32],
+            None,
+            wgpu::LoadOp::Clear(wgpu::Color::BLACK),
         );
-        
-        // Execute our shader
-        render_pass.set_pipeline(&self.pipeline);
-        render_pass.set_bind_group(0, &self.bind_group, &[]);
-        render_pass.draw(0..3, 0..1); // Full-screen triangle
     }
     
     /// Phase 13: Render to preview resolution for fast updates
     /// Renders full RAW texture to smaller output (GPU downsamples automatically)
     pub fn render_to_bytes(&self) -> Vec<u8> {
+        let (preview_width, preview_height) = self.preview_size();
+        let gpu = match &self.backend {
+            Backend::Gpu(gpu) => gpu,
+            Backend::Cpu(cpu) => return cpu.render(preview_width, preview_height, false),
+        };
+        
         // Create PREVIEW-SIZED output texture (Phase 13 optimization!)
-        let output_texture = self.device.create_texture(&wgpu::TextureDescriptor {
+        let output_texture = gpu.context.device.create_texture(&wgpu::TextureDescriptor {
             label: Some("Output Texture (Preview)"),
             size: wgpu::Extent3d {
-                width: self.preview_width,   // Preview size, not full!
-                height: self.preview_height,  // Preview size, not full!
+                width: preview_width,   // Preview size, not full!
+                height: preview_height,  // Preview size, not full!
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
@@ -457,19 +816,19 @@ impl RenderPipeline {
         });
         
         let output_view = output_texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        let mut encoder = gpu.context.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
         });
         
         // Render to PREVIEW texture (GPU rasterizer auto-downsamples from full res input)
-        self.render_to_target(&mut encoder, &output_view, (self.preview_width, self.preview_height));
+        self.render_to_target(&mut encoder, &output_view, (preview_width, preview_height));
         
         // Readback from PREVIEW buffer (much smaller!)
-        let bytes_per_row = self.preview_width * 4;
-        let padded_bytes_per_row = (bytes_per_row + 255) & !255;
-        let buffer_size = (padded_bytes_per_row * self.preview_height) as u64;
+        let bytes_per_row = preview_width * 4;
+        let padded_bytes_per_row = padded_row_bytes(bytes_per_row);
+        let buffer_size = (padded_bytes_per_row * preview_height) as u64;
         
-        let output_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+        let output_buffer = gpu.context.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Output Buffer"),
             size: buffer_size,
             usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
@@ -488,31 +847,31 @@ impl RenderPipeline {
                 layout: wgpu::ImageDataLayout {
                     offset: 0,
                     bytes_per_row: Some(padded_bytes_per_row),
-                    rows_per_image: Some(self.preview_height),  // Preview, not full!
+                    rows_per_image: Some(preview_height),  // Preview, not full!
                 },
             },
             wgpu::Extent3d {
-                width: self.preview_width,   // Preview, not full!
-                height: self.preview_height,  // Preview, not full!
+                width: preview_width,   // Preview, not full!
+                height: preview_height,  // Preview, not full!
                 depth_or_array_layers: 1,
             },
         );
         
-        self.queue.submit(Some(encoder.finish()));
+        gpu.context.queue.submit(Some(encoder.finish()));
         
         let buffer_slice = output_buffer.slice(..);
         let (tx, rx) = std::sync::mpsc::channel();
         buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
             tx.send(result).unwrap();
         });
-        self.device.poll(wgpu::Maintain::Wait);
+        gpu.context.device.poll(wgpu::Maintain::Wait);
         rx.recv().unwrap().unwrap();
         
         let data = buffer_slice.get_mapped_range();
-        let mut output = Vec::with_capacity((self.preview_width * self.preview_height * 4) as usize);
-        for y in 0..self.preview_height {  // Preview, not full!
+        let mut output = Vec::with_capacity((preview_width * preview_height * 4) as usize);
+        for y in 0..preview_height {  // Preview, not full!
             let start = (y * padded_bytes_per_row) as usize;
-            let end = start + (self.preview_width * 4) as usize;  // Preview, not full!
+            let end = start + (preview_width * 4) as usize;  // Preview, not full!
             output.extend_from_slice(&data[start..end]);
         }
         
@@ -521,217 +880,1635 @@ impl RenderPipeline {
         output
     }
     
-    /// Phase 19: Render to FULL resolution for export
+    /// Phase 19: Render `params` to FULL resolution for export
     /// This is SLOW (1-2 seconds for 24MP) - only use for final export!
-    pub fn render_full_res_to_bytes(&self) -> Vec<u8> {
-        // Create FULL-SIZED output texture (all 24 megapixels!)
-        let output_texture = self.device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Output Texture (Full Resolution)"),
-            size: wgpu::Extent3d {
-                width: self.width,   // FULL resolution!
-                height: self.height,  // FULL resolution!
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
-            view_formats: &[],
-        });
+    pub fn render_full_res_to_bytes(&self, params: &EditParams) -> Vec<u8> {
+        self.render_full_res_to_bytes_with_progress(params, &mut |_| true).unwrap_or_default()
+    }
+    
+    /// `render_full_res_to_bytes` reporting each finished tile to `progress`;
+    /// None if `progress` stopped it
+    pub fn render_full_res_to_bytes_with_progress(&self, params: &EditParams, progress: RenderProgress) -> Option<Vec<u8>> {
+        let (max_dimension, max_buffer_bytes) = self.render_limits();
+        self.render_full_res(params, false, max_dimension, max_buffer_bytes, progress)
+    }
+    
+    /// Render `params` to FULL resolution at 16 bits per channel for TIFF export
+    /// Returns RGBA u16 data. Clamping and gamma happen in the shader exactly as
+    /// for 8-bit output; `fs_main_16` only quantizes to 0-65535 instead of 0-255.
+    pub fn render_full_res_to_bytes_16(&self, params: &EditParams) -> Vec<u16> {
+        self.render_full_res_to_bytes_16_with_progress(params, &mut |_| true).unwrap_or_default()
+    }
+    
+    /// `render_full_res_to_bytes_16` reporting each finished tile to
+    /// `progress`; None if `progress` stopped it
+    pub fn render_full_res_to_bytes_16_with_progress(&self, params: &EditParams, progress: RenderProgress) -> Option<Vec<u16>> {
+        let (max_dimension, max_buffer_bytes) = self.render_limits();
+        let bytes = self.render_full_res(params, true, max_dimension, max_buffer_bytes, progress)?;
+        let (width, height) = self.export_size(params);
+        Some(unpad_rows_u16(&bytes, width * 8, width * 8, height))
+    }
+    
+    /// Largest render target side and readback buffer the device allows
+    /// (no limit on the CPU, which renders into one `Vec` anyway)
+    fn render_limits(&self) -> (u32, u64) {
+        match &self.backend {
+            Backend::Gpu(gpu) => {
+                let limits = gpu.context.device.limits();
+                (limits.max_texture_dimension_2d, limits.max_buffer_size)
+            }
+            Backend::Cpu(_) => (u32::MAX, u64::MAX),
+        }
+    }
+    
+    /// Render the full-resolution output (RGBA8, or RGBA16 when `sixteen_bit`)
+    /// and read it back without row padding
+    ///
+    /// Outputs bigger than the device allows (`max_dimension` texture side,
+    /// `max_buffer_bytes` readback) are rendered tile by tile, each tile
+    /// copied into its place in the result; everything else stays a single pass.
+    /// The uniforms and output size are taken from `params` once, into a
+    /// uniform buffer of the export's own: the Develop view keeps updating
+    /// the pipeline's (sliders, zoom, crop mode) while this renders.
+    /// `progress` hears about every finished tile; None when it asked to stop.
+    fn render_full_res(&self, params: &EditParams, sixteen_bit: bool, max_dimension: u32, max_buffer_bytes: u64, progress: RenderProgress) -> Option<Vec<u8>> {
+        let gpu_params = self.gpu_uniforms(params, 1.0, 0.0, 0.0, false);
+        // Full resolution of the cropped area (the crop changes the output size)
+        let (width, height) = self.export_size(params);
+        let gpu = match &self.backend {
+            Backend::Gpu(gpu) => gpu,
+            Backend::Cpu(cpu) => {
+                let output = cpu.render_with(gpu_params, params, width, height, sixteen_bit);
+                return progress(1.0).then_some(output);
+            }
+        };
+        let export = gpu.export_bindings(params, &gpu_params);
+        let (format, bytes_per_pixel, render_pipeline) = if sixteen_bit {
+            (wgpu::TextureFormat::Rgba16Uint, 8, &gpu.context.render_pipeline_16)
+        } else {
+            (wgpu::TextureFormat::Rgba8Unorm, 4, &gpu.context.render_pipeline)
+        };
+        let tiles = export_tiles(width, height, bytes_per_pixel, max_dimension, max_buffer_bytes);
+        if tiles.len() > 1 {
+            println!("🧩 Rendering {}x{} in {} tiles", width, height, tiles.len());
+        }
         
-        let output_view = output_texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Render Encoder (Full Res)"),
-        });
+        let row_bytes = (width * bytes_per_pixel) as usize;
+        let mut output = vec![0u8; row_bytes * height as usize];
+        let mut stopped = false;
+        for (done, tile) in tiles.iter().enumerate() {
+            gpu.write_tile(&export.uniform_buffer, tile.fraction_of(width, height));
+            
+            let output_texture = gpu.context.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Output Texture (Full Resolution)"),
+                size: wgpu::Extent3d {
+                    width: tile.width,
+                    height: tile.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+            
+            let output_view = output_texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let mut encoder = gpu.context.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder (Full Res)"),
+            });
+            
+            {
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("RAW Render Pass (Full Res)"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &output_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+                render_pass.set_viewport(0.0, 0.0, tile.width as f32, tile.height as f32, 0.0, 1.0);
+                render_pass.set_pipeline(render_pipeline);
+                render_pass.set_bind_group(0, &export.bind_group, &[]);
+                render_pass.draw(0..3, 0..1); // Full-screen triangle
+            }
+            
+            // Readback (LARGE! ~96MB for a 24MP single pass), rows padded to 256 bytes
+            let bytes_per_row = tile.width * bytes_per_pixel;
+            let padded_bytes_per_row = padded_row_bytes(bytes_per_row);
+            let buffer_size = (padded_bytes_per_row * tile.height) as u64;
+            
+            let output_buffer = gpu.context.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Output Buffer (Full Res)"),
+                size: buffer_size,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            });
+            
+            encoder.copy_texture_to_buffer(
+                wgpu::ImageCopyTexture {
+                    texture: &output_texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::ImageCopyBuffer {
+                    buffer: &output_buffer,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(padded_bytes_per_row),
+                        rows_per_image: Some(tile.height),
+                    },
+                },
+                wgpu::Extent3d {
+                    width: tile.width,
+                    height: tile.height,
+                    depth_or_array_layers: 1,
+                },
+            );
+            
+            gpu.context.queue.submit(Some(encoder.finish()));
+            
+            let buffer_slice = output_buffer.slice(..);
+            let (tx, rx) = std::sync::mpsc::channel();
+            buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+                tx.send(result).unwrap();
+            });
+            gpu.context.device.poll(wgpu::Maintain::Wait);
+            rx.recv().unwrap().unwrap();
+            
+            // Each tile row lands at its place in the full image
+            let data = buffer_slice.get_mapped_range();
+            for y in 0..tile.height {
+                let source = (y * padded_bytes_per_row) as usize;
+                let target = (tile.y + y) as usize * row_bytes + (tile.x * bytes_per_pixel) as usize;
+                output[target..target + bytes_per_row as usize]
+                    .copy_from_slice(&data[source..source + bytes_per_row as usize]);
+            }
+            
+            drop(data);
+            output_buffer.unmap();
+            
+            if !progress((done + 1) as f32 / tiles.len() as f32) {
+                stopped = true;
+                break;
+            }
+        }
         
-        // Render to FULL resolution texture
-        self.render_to_target(&mut encoder, &output_view, (self.width, self.height));
+        (!stopped).then_some(output)
+    }
+    
+    /// Lens profile used when lens correction is on (takes effect on the next uniform update)
+    pub fn set_lens_profile(&self, profile: Option<LensProfile>) {
+        *self.lens_profile.lock().unwrap() = profile;
+    }
+    
+    /// White balance multipliers [R, G, B, G2] these edits render with: the
+    /// picked ones, or the camera's as-shot ones
+    pub fn white_balance(&self, params: &EditParams) -> [f32; 4] {
+        params.wb_override.unwrap_or(self.wb_multipliers)
+    }
+    
+    /// Average camera RGB (demosaiced, before white balance) around a full-res pixel
+    /// Reads a small raw patch back from the GPU and averages each CFA color,
+    /// which also smooths out sensor noise for the white balance picker.
+    pub fn sample_camera_rgb(&self, x: u32, y: u32) -> Option<[f32; 3]> {
+        const PATCH: u32 = 8;
+        if x >= self.width || y >= self.height || self.width < PATCH || self.height < PATCH {
+            return None;
+        }
         
-        // Readback from FULL buffer (LARGE! ~96MB for 24MP)
-        let bytes_per_row = self.width * 4;
-        let padded_bytes_per_row = (bytes_per_row + 255) & !255;
-        let buffer_size = (padded_bytes_per_row * self.height) as u64;
+        // Keep the patch inside the image
+        let origin_x = x.saturating_sub(PATCH / 2).min(self.width - PATCH);
+        let origin_y = y.saturating_sub(PATCH / 2).min(self.height - PATCH);
         
-        let output_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Output Buffer (Full Res)"),
-            size: buffer_size,
-            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-            mapped_at_creation: false,
-        });
+        let gpu = match &self.backend {
+            Backend::Gpu(gpu) => gpu,
+            Backend::Cpu(cpu) => {
+                let patch = cpu.raw_patch((origin_x, origin_y), PATCH);
+                return average_bayer_patch(&patch, PATCH, (origin_x, origin_y), self.cfa_offset, self.levels);
+            }
+        };
         
-        encoder.copy_texture_to_buffer(
-            wgpu::ImageCopyTexture {
-                texture: &output_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::ImageCopyBuffer {
-                buffer: &output_buffer,
-                layout: wgpu::ImageDataLayout {
-                    offset: 0,
-                    bytes_per_row: Some(padded_bytes_per_row),
-                    rows_per_image: Some(self.height),  // FULL resolution!
-                },
-            },
-            wgpu::Extent3d {
-                width: self.width,   // FULL resolution!
-                height: self.height,  // FULL resolution!
-                depth_or_array_layers: 1,
-            },
-        );
-        
-        self.queue.submit(Some(encoder.finish()));
-        
-        let buffer_slice = output_buffer.slice(..);
-        let (tx, rx) = std::sync::mpsc::channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            tx.send(result).unwrap();
-        });
-        self.device.poll(wgpu::Maintain::Wait);
-        rx.recv().unwrap().unwrap();
-        
-        let data = buffer_slice.get_mapped_range();
-        let mut output = Vec::with_capacity((self.width * self.height * 4) as usize);
-        for y in 0..self.height {  // FULL resolution!
-            let start = (y * padded_bytes_per_row) as usize;
-            let end = start + (self.width * 4) as usize;  // FULL resolution!
-            output.extend_from_slice(&data[start..end]);
+        let patch = gpu.read_raw((origin_x, origin_y), PATCH, PATCH)?;
+        average_bayer_patch(&patch, PATCH, (origin_x, origin_y), self.cfa_offset, self.levels)
+    }
+    
+    /// The whole sensor mosaic read back from the GPU (None for a CPU
+    /// pipeline), for drawing it again on another device
+    pub(super) fn raw_sensor_data(&self) -> Option<Vec<u16>> {
+        match &self.backend {
+            Backend::Gpu(gpu) => gpu.read_raw((0, 0), self.width, self.height),
+            Backend::Cpu(_) => None,
         }
-        
-        drop(data);
-        output_buffer.unmap();
-        output
     }
     
-    /// Get the texture dimensions
+    /// Color around a full-res pixel after white balance, temperature/tint
+    /// and the color matrix: where the film negative inversion runs, so this
+    /// is what the film base picker stores
+    pub fn sample_working_rgb(&self, x: u32, y: u32, params: &EditParams) -> Option<[f32; 3]> {
+        let camera = self.sample_camera_rgb(x, y)?;
+        let wb = self.white_balance(params);
+        let balanced = [
+            camera[0] * wb[0] * (1.0 + params.temperature * 0.3),
+            camera[1] * wb[1] * (1.0 + params.tint * 0.3),
+            camera[2] * wb[2] * (1.0 - params.temperature * 0.3),
+        ];
+        let m = &self.color_matrix;
+        Some([0, 1, 2].map(|row| m[row * 3] * balanced[0] + m[row * 3 + 1] * balanced[1] + m[row * 3 + 2] * balanced[2]))
+    }
+    
+    /// GPU memory owned by this pipeline (RAW texture, curve LUT, uniforms),
+    /// not counting the shared context or transient render targets
+    /// (a CPU pipeline holds about as much in RAM instead)
+    pub fn gpu_memory_bytes(&self) -> u64 {
+        let raw_texture = self.width as u64 * self.height as u64 * 2;  // R16Uint
+        let curve_lut = CURVE_LUT_SIZE as u64 * 4;                     // R32Float
+        raw_texture + curve_lut + std::mem::size_of::<GpuEditParams>() as u64
+    }
+    
+    /// Upright image dimensions (the sensor texture turned by the orientation);
+    /// crop rectangles and straightening are relative to these
     pub fn dimensions(&self) -> (u32, u32) {
-        (self.width, self.height)
+        self.orientation().oriented_size(self.width, self.height)
+    }
+    
+    /// Full-resolution output size: the crop of the last uniform update
+    pub fn output_size(&self) -> (u32, u32) {
+        let crop = self.crop_rect.lock().unwrap().unwrap_or(FULL_FRAME);
+        let (width, height) = self.dimensions();
+        crop_size(crop, width, height)
+    }
+    
+    /// Full-resolution size of an export of `params` (its crop, whatever the
+    /// preview last showed)
+    pub fn export_size(&self, params: &EditParams) -> (u32, u32) {
+        let (width, height) = self.dimensions();
+        crop_size(params.crop_or_full(), width, height)
+    }
+    
+    /// Preview render size (output size capped at the max preview width)
+    pub fn preview_size(&self) -> (u32, u32) {
+        scaled_to_width(self.output_size(), *self.max_preview_width.lock().unwrap())
+    }
+    
+    /// Change the preview width cap (preview quality setting, or a sharper
+    /// render while zoomed in). Takes effect on the next `render_to_bytes`.
+    pub fn set_max_preview_width(&self, max_preview_width: u32) {
+        *self.max_preview_width.lock().unwrap() = max_preview_width.max(1);
     }
     
-    /// Phase 22: Render to tiny histogram-sized bytes (256px wide)
-    /// This is ~100x faster than rendering full preview for histogram calculation
-    pub fn render_to_histogram_bytes(&self) -> Vec<u8> {
-        // Create tiny output texture for histogram (256px wide)
-        let output_texture = self.device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Histogram Output Texture"),
+    /// Histogram of the sensor data: [R[256], G[256], B[256], all[256]],
+    /// linear between the black and white levels, whatever the edits
+    /// (the last bin is sensor clipping)
+    pub fn raw_histogram(&self) -> [[u32; 256]; 4] {
+        self.raw_histogram
+    }
+    
+    /// Phase 22: RGB histogram of the current preview, computed on the GPU
+    /// Renders at preview size and bins the frame with a compute shader
+    /// (see `HISTOGRAM_SHADER`); only the 4KB of counts are read back.
+    /// Returns [R[256], G[256], B[256], Luma[256]] histogram data
+    pub fn calculate_histogram_gpu(&self) -> [[u32; 256]; 4] {
+        let (preview_width, preview_height) = self.preview_size();
+        let gpu = match &self.backend {
+            Backend::Gpu(gpu) => gpu,
+            Backend::Cpu(_) => return software::histogram(&self.render_to_bytes()),
+        };
+        let device = &gpu.context.device;
+        
+        // Rendered in place of a readback: the compute pass reads it directly
+        let output_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Histogram Source Texture"),
             size: wgpu::Extent3d {
-                width: self.histogram_width,
-                height: self.histogram_height,
+                width: preview_width,
+                height: preview_height,
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: wgpu::TextureFormat::Rgba8Unorm,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
             view_formats: &[],
         });
-        
         let output_view = output_texture.create_view(&wgpu::TextureViewDescriptor::default());
         
-        // Create command encoder and render pass
-        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Histogram Render Encoder"),
+        // wgpu zero-initializes new buffers, so the bins start empty
+        let bins_size = (4 * 256 * std::mem::size_of::<u32>()) as u64;
+        let bins_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Histogram Bins Buffer"),
+            size: bins_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Histogram Readback Buffer"),
+            size: bins_size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Histogram Bind Group"),
+            layout: &gpu.context.histogram_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&output_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: bins_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Histogram Encoder"),
         });
         
+        self.render_to_target(&mut encoder, &output_view, (preview_width, preview_height));
+        
         {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Histogram Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &output_view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Histogram Compute Pass"),
                 timestamp_writes: None,
-                occlusion_query_set: None,
             });
-            
-            render_pass.set_pipeline(&self.pipeline);
-            render_pass.set_bind_group(0, &self.bind_group, &[]);
-            render_pass.draw(0..3, 0..1);
+            compute_pass.set_pipeline(&gpu.context.histogram_pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            // 16x16 workgroups (see @workgroup_size in the shader)
+            compute_pass.dispatch_workgroups(preview_width.div_ceil(16), preview_height.div_ceil(16), 1);
         }
         
-        // Read back the tiny rendered image
-        let bytes_per_pixel = 4;
-        let unpadded_bytes_per_row = self.histogram_width * bytes_per_pixel;
-        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
-        let padded_bytes_per_row = (unpadded_bytes_per_row + align - 1) / align * align;
-        let buffer_size = (padded_bytes_per_row * self.histogram_height) as u64;
+        encoder.copy_buffer_to_buffer(&bins_buffer, 0, &readback_buffer, 0, bins_size);
+        gpu.context.queue.submit(Some(encoder.finish()));
         
-        let output_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Histogram Output Buffer"),
-            size: buffer_size,
-            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-            mapped_at_creation: false,
+        let buffer_slice = readback_buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            tx.send(result).unwrap();
         });
-        
-        encoder.copy_texture_to_buffer(
-            wgpu::ImageCopyTexture {
-                texture: &output_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::ImageCopyBuffer {
-                buffer: &output_buffer,
-                layout: wgpu::ImageDataLayout {
-                    offset: 0,
-                    bytes_per_row: Some(padded_bytes_per_row),
-                    rows_per_image: Some(self.histogram_height),
-                },
-            },
-            wgpu::Extent3d {
-                width: self.histogram_width,
-                height: self.histogram_height,
-                depth_or_array_layers: 1,
-            },
-        );
-        
-        self.queue.submit(Some(encoder.finish()));
-        
-        // Read the data
-        let buffer_slice = output_buffer.slice(..);
-        buffer_slice.map_async(wgpu::MapMode::Read, |_| {});
-        self.device.poll(wgpu::Maintain::Wait);
+        device.poll(wgpu::Maintain::Wait);
+        rx.recv().unwrap().unwrap();
         
         let data = buffer_slice.get_mapped_range();
-        
-        // Copy to output vector (remove padding)
-        let mut output = Vec::with_capacity((self.histogram_width * self.histogram_height * 4) as usize);
-        for row in 0..self.histogram_height {
-            let start = (row * padded_bytes_per_row) as usize;
-            let end = start + unpadded_bytes_per_row as usize;
-            output.extend_from_slice(&data[start..end]);
+        let counts: &[u32] = bytemuck::cast_slice(&data);
+        let mut histograms = [[0u32; 256]; 4];
+        for (channel, histogram) in histograms.iter_mut().enumerate() {
+            histogram.copy_from_slice(&counts[channel * 256..(channel + 1) * 256]);
         }
         
         drop(data);
-        output_buffer.unmap();
-        output
+        readback_buffer.unmap();
+        histograms
     }
+}
+
+/// Scale a size down to at most `max_width`, keeping its aspect ratio
+fn scaled_to_width(size: (u32, u32), max_width: u32) -> (u32, u32) {
+    let width = size.0.min(max_width);
+    let height = (width as f32 * size.1 as f32 / size.0 as f32) as u32;
+    (width, height.max(1))
+}
+
+/// One rectangle of a tiled full-resolution render, in output pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Tile {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Tile {
+    /// The tile as [x, y, width, height] fractions of a `width` x `height` output
+    fn fraction_of(&self, width: u32, height: u32) -> [f32; 4] {
+        let (width, height) = (width as f32, height as f32);
+        [self.x as f32 / width, self.y as f32 / height, self.width as f32 / width, self.height as f32 / height]
+    }
+}
+
+/// Progress callback for full-resolution renders: gets the fraction of the
+/// output done after every tile, returns false to stop the render
+pub type RenderProgress<'a> = &'a mut dyn FnMut(f32) -> bool;
+
+/// Split a `width` x `height` output into row-major tiles no wider or taller
+/// than `max_dimension`, each small enough that its padded readback fits in
+/// `max_buffer_bytes`. A single tile when the whole output fits.
+fn export_tiles(width: u32, height: u32, bytes_per_pixel: u32, max_dimension: u32, max_buffer_bytes: u64) -> Vec<Tile> {
+    let tile_width = width.min(max_dimension).max(1);
+    let rows_per_buffer = max_buffer_bytes / padded_row_bytes(tile_width * bytes_per_pixel) as u64;
+    let tile_height = (height.min(max_dimension) as u64).min(rows_per_buffer).max(1) as u32;
     
-    /// Phase 21: Calculate RGB histogram from rendered RGBA bytes
-    /// Returns [R[256], G[256], B[256]] histogram data
-    pub fn calculate_histogram(&self, rgba_bytes: &[u8]) -> [[u32; 256]; 3] {
-        let mut histograms = [[0u32; 256]; 3];
-        
-        // Process pixels in chunks of 4 (RGBA)
-        for pixel in rgba_bytes.chunks_exact(4) {
-            let r = pixel[0] as usize;
-            let g = pixel[1] as usize;
-            let b = pixel[2] as usize;
-            // pixel[3] is alpha, ignore it
-            
-            histograms[0][r] += 1; // Red channel
-            histograms[1][g] += 1; // Green channel
-            histograms[2][b] += 1; // Blue channel
+    let mut tiles = Vec::new();
+    for y in (0..height).step_by(tile_height as usize) {
+        for x in (0..width).step_by(tile_width as usize) {
+            tiles.push(Tile {
+                x,
+                y,
+                width: tile_width.min(width - x),
+                height: tile_height.min(height - y),
+            });
         }
-        
-        histograms
+    }
+    tiles
+}
+
+/// Round a row size up to wgpu's 256-byte COPY_BYTES_PER_ROW_ALIGNMENT
+fn padded_row_bytes(bytes_per_row: u32) -> u32 {
+    (bytes_per_row + 255) & !255
+}
+
+/// Strip the per-row padding from a mapped readback buffer and decode u16 texels
+fn unpad_rows_u16(data: &[u8], bytes_per_row: u32, padded_bytes_per_row: u32, height: u32) -> Vec<u16> {
+    let mut output = Vec::with_capacity((bytes_per_row / 2 * height) as usize);
+    for y in 0..height {
+        let start = (y * padded_bytes_per_row) as usize;
+        let end = start + bytes_per_row as usize;
+        output.extend(
+            data[start..end]
+                .chunks_exact(2)
+                .map(|pair| u16::from_ne_bytes([pair[0], pair[1]])),
+        );
+    }
+    output
+}
+
+/// Average each CFA color in a square raw patch whose top-left pixel is `origin`
+/// Returns normalized camera RGB (same black/white levels as the shader), or
+/// None if a channel has no signal to balance against.
+fn average_bayer_patch(
+    patch: &[u16],
+    size: u32,
+    origin: (u32, u32),
+    cfa_offset: [u32; 2],
+    levels: SensorLevels,
+) -> Option<[f32; 3]> {
+    let mut sums = [0.0f32; 3];
+    let mut counts = [0u32; 3];
+    for row in 0..size {
+        for col in 0..size {
+            // Same RGGB-relative lookup as cfa_position() in the shader
+            let px = (origin.0 + col + cfa_offset[0]) & 1;
+            let py = (origin.1 + row + cfa_offset[1]) & 1;
+            let channel = match (px, py) {
+                (0, 0) => 0,
+                (1, 1) => 2,
+                _ => 1,
+            };
+            sums[channel] += levels.normalize(patch[(row * size + col) as usize] as f32);
+            counts[channel] += 1;
+        }
+    }
+    
+    let rgb = [
+        sums[0] / counts[0].max(1) as f32,
+        sums[1] / counts[1].max(1) as f32,
+        sums[2] / counts[2].max(1) as f32,
+    ];
+    if rgb.iter().any(|&c| c <= 0.0) {
+        return None;
+    }
+    Some(rgb)
+}
+
+/// Bin raw sensor values per Bayer color: [R[256], G[256], B[256], all[256]]
+///
+/// Values are normalized with the sensor levels (linear, before white
+/// balance), so bin 255 holds the photosites at or above the white level.
+/// Both greens go in G, which therefore counts twice as many photosites.
+fn raw_histogram(raw: &[u16], width: u32, cfa_offset: [u32; 2], levels: SensorLevels) -> [[u32; 256]; 4] {
+    let mut bins = [[0u32; 256]; 4];
+    if width == 0 {
+        return bins;
+    }
+    for (row, line) in raw.chunks_exact(width as usize).enumerate() {
+        let py = (row as u32 + cfa_offset[1]) & 1;
+        for (col, &value) in line.iter().enumerate() {
+            // Same RGGB-relative lookup as cfa_position() in the shader
+            let channel = match ((col as u32 + cfa_offset[0]) & 1, py) {
+                (0, 0) => 0,
+                (1, 1) => 2,
+                _ => 1,
+            };
+            let bin = ((levels.normalize(value as f32) * 255.0 + 0.5) as usize).min(255);
+            bins[channel][bin] += 1;
+            bins[3][bin] += 1;
+        }
+    }
+    bins
+}
+
+/// Upload the tone curve LUT for `params` (no-op for the identity curve,
+/// since the shader skips the lookup when `curve_enabled` is 0)
+pub(super) fn write_curve_lut(queue: &wgpu::Queue, curve_texture: &wgpu::Texture, params: &EditParams) {
+    if params.curve.is_identity() {
+        return;
+    }
+    let lut = params.curve.to_lut();
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture: curve_texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        bytemuck::cast_slice(&lut),
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * CURVE_LUT_SIZE as u32),  // 4 bytes per f32
+            rows_per_image: Some(1),
+        },
+        wgpu::Extent3d {
+            width: CURVE_LUT_SIZE as u32,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::edit::{HslChannel, LocalMask};
+
+    /// Preview cap for test pipelines (the tests read back full resolution)
+    const TEST_PREVIEW_WIDTH: u32 = 1280;
+
+    /// Pipeline on the shared GPU context, neutral white balance
+    async fn test_pipeline(
+        raw: Vec<u16>,
+        width: u32,
+        height: u32,
+        params: &EditParams,
+        color_matrix: [f32; 9],
+        cfa_pattern: CfaPattern,
+        max_preview_width: u32,
+    ) -> Result<RenderPipeline, String> {
+        let gpu = GpuContext::shared().await?;
+        let levels = SensorLevels::TWELVE_BIT;
+        RenderPipeline::new(Some(gpu), 0, raw, width, height, params, [1.0; 4], color_matrix, cfa_pattern, levels, max_preview_width).await
+    }
+
+    #[test]
+    fn test_average_bayer_patch() {
+        // 4x4 patch, R = 100, G = 200, B = 400 laid out as GRBG starting at (1, 0)
+        let cfa_offset = CfaPattern::Grbg.offset();
+        let origin = (1, 0);
+        let patch: Vec<u16> = (0..4u32)
+            .flat_map(|row| (0..4u32).map(move |col| {
+                match ((origin.0 + col + cfa_offset[0]) & 1, (origin.1 + row + cfa_offset[1]) & 1) {
+                    (0, 0) => 100,
+                    (1, 1) => 400,
+                    _ => 200,
+                }
+            }))
+            .collect();
+
+        let rgb = average_bayer_patch(&patch, 4, origin, cfa_offset, SensorLevels::TWELVE_BIT).unwrap();
+        assert!((rgb[0] * 4096.0 - 100.0).abs() < 0.01);
+        assert!((rgb[1] * 4096.0 - 200.0).abs() < 0.01);
+        assert!((rgb[2] * 4096.0 - 400.0).abs() < 0.01);
+
+        // A black patch has nothing to balance
+        assert!(average_bayer_patch(&[0; 16], 4, (0, 0), [0, 0], SensorLevels::TWELVE_BIT).is_none());
+    }
+
+    #[test]
+    fn test_raw_histogram_bins_per_bayer_color() {
+        // 4x2 RGGB: red clipped, greens at half, blues at black
+        let levels = SensorLevels { black: 512.0, white: 4608.0 };
+        let half = 2560;
+        let raw = [4608, half, 4700, half, half, 512, half, 0];
+        let bins = raw_histogram(&raw, 4, CfaPattern::Rggb.offset(), levels);
+        assert_eq!(bins[0][255], 2);
+        assert_eq!(bins[1][128], 4);
+        assert_eq!(bins[2][0], 2);
+        assert_eq!(bins[3].iter().sum::<u32>(), 8);
+
+        // Same data read as BGGR swaps red and blue
+        let bins = raw_histogram(&raw, 4, CfaPattern::Bggr.offset(), levels);
+        assert_eq!((bins[0][0], bins[2][255]), (2, 2));
+    }
+
+    #[test]
+    fn test_16bit_readback_strips_row_padding() {
+        // 3 pixels wide: 24 bytes of RGBA16 per row, padded out to 256
+        let width = 3u32;
+        let height = 2u32;
+        let bytes_per_row = width * 8;
+        let padded = padded_row_bytes(bytes_per_row);
+        assert_eq!(padded, 256);
+
+        let mut data = vec![0xAAu8; (padded * height) as usize];
+        for y in 0..height {
+            for i in 0..width * 4 {
+                let value = (y * 1000 + i) as u16;
+                let offset = (y * padded + i * 2) as usize;
+                data[offset..offset + 2].copy_from_slice(&value.to_ne_bytes());
+            }
+        }
+
+        let output = unpad_rows_u16(&data, bytes_per_row, padded, height);
+        assert_eq!(output.len(), (width * height * 4) as usize);
+        assert_eq!(output[0], 0);
+        assert_eq!(output[11], 11);
+        assert_eq!(output[12], 1000);
+        assert_eq!(output[23], 1011);
+    }
+
+    #[test]
+    fn test_export_tiles_cover_output() {
+        // Fits: one pass
+        assert_eq!(export_tiles(6000, 4000, 4, 8192, 256 << 20), vec![Tile { x: 0, y: 0, width: 6000, height: 4000 }]);
+
+        // Too wide for one texture: a grid, the last row and column cut short
+        let tiles = export_tiles(20000, 9000, 4, 8192, u64::MAX);
+        assert_eq!(tiles.len(), 6);
+        assert_eq!(tiles[2], Tile { x: 16384, y: 0, width: 3616, height: 8192 });
+        assert_eq!(tiles[5], Tile { x: 16384, y: 8192, width: 3616, height: 808 });
+        let area: u64 = tiles.iter().map(|t| t.width as u64 * t.height as u64).sum();
+        assert_eq!(area, 20000 * 9000);
+
+        // Readback buffer too small for a whole 16-bit tile: fewer rows per tile
+        let tiles = export_tiles(4000, 3000, 8, 8192, 32000 * 1000);
+        assert!(tiles.iter().all(|t| padded_row_bytes(t.width * 8) as u64 * t.height as u64 <= 32000 * 1000));
+        assert_eq!(tiles.len(), 3);
+        assert_eq!(tiles[0].fraction_of(4000, 3000), [0.0, 0.0, 1.0, 1.0 / 3.0]);
+    }
+
+    #[tokio::test]
+    async fn test_tiled_render_matches_single_pass() {
+        // Ramp with sharpening and a vignette, so neighborhoods and positions
+        // both have to line up across tile edges
+        let (width, height) = (40u32, 24u32);
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (200 + 70 * x + 40 * y + 300 * (x % 3)) as u16))
+            .collect();
+        let params = EditParams {
+            sharpen_amount: 1.0,
+            vignette_amount: -0.5,
+            crop_rect: Some((0.05, 0.0, 0.9, 1.0)),
+            ..EditParams::default()
+        };
+        let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        let pipeline = match test_pipeline(raw, width, height, &params, identity, CfaPattern::Rggb, TEST_PREVIEW_WIDTH).await {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                println!("⚠️  Skipping GPU test: {}", e);
+                return;
+            }
+        };
+        let single = pipeline.render_full_res(&params, true, 8192, u64::MAX, &mut |_| true).unwrap();
+        // The Develop view keeps editing and zooming while an export renders:
+        // none of it may reach the tiles still to come
+        let edited = EditParams { exposure: 2.0, crop_rect: None, ..params };
+        let mut reported = Vec::new();
+        let tiled = pipeline.render_full_res(&params, true, 16, u64::MAX, &mut |done| {
+            reported.push(done);
+            pipeline.update_uniforms_with_zoom(&edited, 4.0, 0.3, -0.2);
+            pipeline.set_max_preview_width(7);
+            true
+        }).unwrap();
+        assert_eq!(tiled.len(), single.len());
+        // Only float rounding of the tile coordinates may differ
+        let max_diff = single
+            .chunks_exact(2)
+            .zip(tiled.chunks_exact(2))
+            .map(|(a, b)| u16::from_ne_bytes([a[0], a[1]]).abs_diff(u16::from_ne_bytes([b[0], b[1]])))
+            .max()
+            .unwrap();
+        assert!(max_diff <= 2, "tiles differ by {}", max_diff);
+
+        // Every tile reported, ending at 1.0
+        assert!(reported.len() > 1 && reported.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(reported.last(), Some(&1.0));
+
+        // Stopping after the first tile gives no output
+        assert_eq!(pipeline.render_full_res(&params, true, 16, u64::MAX, &mut |_| false), None);
+
+        // The preview kept its own uniforms and size, and exports are unchanged
+        assert_eq!(pipeline.output_size(), (width, height));
+        assert_eq!(pipeline.export_size(&params), (36, 24));
+        assert_eq!(pipeline.render_full_res(&params, true, 8192, u64::MAX, &mut |_| true).unwrap(), single);
+    }
+
+    /// Render a synthetic mosaic through the real shader at full resolution
+    /// Returns linear RGB per pixel, or None when no GPU adapter is available
+    async fn render_mosaic(raw: Vec<u16>, width: u32, height: u32, params: &EditParams) -> Option<Vec<[f32; 3]>> {
+        let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        render_mosaic_with(raw, width, height, params, CfaPattern::Rggb, identity).await
+    }
+
+    async fn render_mosaic_with(
+        raw: Vec<u16>,
+        width: u32,
+        height: u32,
+        params: &EditParams,
+        cfa_pattern: CfaPattern,
+        color_matrix: [f32; 9],
+    ) -> Option<Vec<[f32; 3]>> {
+        let pipeline = match test_pipeline(raw, width, height, params, color_matrix, cfa_pattern, TEST_PREVIEW_WIDTH).await {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                println!("⚠️  Skipping GPU test: {}", e);
+                return None;
+            }
+        };
+
+        // Undo the shader's sRGB encoding to compare in linear space
+        let to_linear = |v: u16| crate::color::srgb_to_linear(v as f32 / 65535.0);
+        Some(
+            pipeline
+                .render_full_res_to_bytes_16(params)
+                .chunks_exact(4)
+                .map(|px| [to_linear(px[0]), to_linear(px[1]), to_linear(px[2])])
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_demosaic_reconstructs_gray_ramp() {
+        // A neutral horizontal ramp mosaicked as RGGB: every channel equals
+        // the same linear function, so a correct interpolation recovers it
+        // exactly away from the borders. Nearest-neighbor sampling borrows
+        // the value one pixel over and is off by a whole step.
+        let (width, height) = (16u32, 16u32);
+        let step = 64.0;
+        let raw_value = |x: u32| 512.0 + step * x as f32;
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|_| (0..width).map(|x| raw_value(x) as u16))
+            .collect();
+        let nearest_neighbor_error = step / 4096.0;
+
+        for method in DemosaicMethod::ALL {
+            let params = EditParams { demosaic: method, ..EditParams::default() };
+            let Some(rgb) = render_mosaic(raw.clone(), width, height, &params).await else {
+                return;
+            };
+
+            for y in 2..height - 2 {
+                for x in 2..width - 2 {
+                    let expected = raw_value(x) / 4096.0;
+                    let pixel = rgb[(y * width + x) as usize];
+                    for channel in pixel {
+                        let error = (channel - expected).abs();
+                        assert!(
+                            error < nearest_neighbor_error / 4.0,
+                            "{} at ({}, {}): got {:?}, expected {}",
+                            method, x, y, pixel, expected
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cpu_fallback_matches_shader() {
+        // The same edits rendered by the shader and by its CPU port (software.rs)
+        // must agree, whichever parts of the chain are switched on
+        let (width, height) = (48u32, 32u32);
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|y| {
+                (0..width).map(move |x| {
+                    // Gradient plus a deterministic speckle, clipped in one corner
+                    let speckle = (x * 7919 + y * 104729) % 257;
+                    if x > 40 && y < 6 { 4095 } else { (150 + 20 * x + 10 * y + speckle) as u16 }
+                })
+            })
+            .collect();
+        let matrix = [1.6, -0.4, -0.2, -0.2, 1.5, -0.3, 0.0, -0.5, 1.5];
+
+        let mut tone = EditParams {
+            exposure: 0.7,
+            contrast: 25.0,
+            highlights: -60.0,
+            shadows: 30.0,
+            whites: 25.0,
+            blacks: -10.0,
+            saturation: 20.0,
+            vibrance: 0.3,
+            temperature: 0.3,
+            tint: -0.2,
+            ..EditParams::default()
+        };
+        tone.curve.set_point(1, 0.25, 0.3);
+        let mut hsl = EditParams::default();
+        hsl.set_hsl(0, HslChannel::Hue, 40.0);
+        hsl.set_hsl(4, HslChannel::Saturation, -60.0);
+        hsl.set_hsl(5, HslChannel::Luminance, 50.0);
+        let mut masks = EditParams { crop_rect: Some((0.1, 0.1, 0.8, 0.8)), ..EditParams::default() };
+        masks.masks.push(LocalMask { exposure: 1.2, contrast: 30.0, feather: 0.8, ..LocalMask::new(MaskKind::Radial) });
+        masks.masks.push(LocalMask { exposure: -0.8, saturation: -50.0, ..LocalMask::new(MaskKind::Linear) });
+        let cases = [
+            EditParams::default(),
+            tone,
+            hsl,
+            EditParams { demosaic: DemosaicMethod::Malvar, highlight_recovery: 1.0, ..EditParams::default() },
+            EditParams { clarity: 0.8, luma_nr: 0.5, chroma_nr: 0.7, sharpen_amount: 1.0, ..EditParams::default() },
+            EditParams {
+                crop_rect: Some((0.1, 0.2, 0.7, 0.6)),
+                rotation_degrees: 8.0,
+                vignette_amount: -0.6,
+                lens_correction: true,
+                lens_distortion: 0.5,
+                monochrome: true,
+                ..EditParams::default()
+            },
+            EditParams { contrast: 60.0, tone_mode: ToneMode::SCurve, ..EditParams::default() },
+            EditParams { invert: true, film_base: [0.9, 0.6, 0.4], ..EditParams::default() },
+            EditParams { exposure: 1.5, contrast: 20.0, tone_mode: ToneMode::Filmic, ..EditParams::default() },
+            masks,
+            EditParams { shadow_tint: [0.0, 0.3, 0.6], highlight_tint: [0.7, 0.4, 0.0], balance: 0.3, contrast: 15.0, ..EditParams::default() },
+        ];
+
+        let Ok(gpu) = GpuContext::shared().await else {
+            println!("⚠️  Skipping GPU test: no adapter to compare against");
+            return;
+        };
+        let levels = SensorLevels::TWELVE_BIT;
+        let wb = [1.3, 1.0, 1.2, 1.0];
+        for (case, params) in cases.iter().enumerate() {
+            let shader = RenderPipeline::new(Some(gpu.clone()), 0, raw.clone(), width, height, params, wb, matrix, CfaPattern::Grbg, levels, TEST_PREVIEW_WIDTH)
+                .await
+                .unwrap();
+            let cpu = RenderPipeline::new(None, 0, raw.clone(), width, height, params, wb, matrix, CfaPattern::Grbg, levels, TEST_PREVIEW_WIDTH)
+                .await
+                .unwrap();
+            assert!(cpu.is_software() && !shader.is_software());
+            for pipeline in [&shader, &cpu] {
+                pipeline.set_orientation(Orientation { quarter_turns: 1, mirrored: true });
+                pipeline.set_lens_profile(lens::find_profile(Some("FE 24mm F1.4 GM")));
+            }
+
+            let expected = shader.render_full_res_to_bytes_16(params);
+            let actual = cpu.render_full_res_to_bytes_16(params);
+            assert_eq!(expected.len(), actual.len());
+            // GPU transcendental functions are approximations; allow a couple
+            // of 8-bit steps, and a few pixels more where a sample coordinate
+            // lands right on a pixel boundary and rounds the other way
+            let differences: Vec<f32> = expected
+                .iter()
+                .zip(&actual)
+                .map(|(&a, &b)| (a as f32 - b as f32).abs() / 65535.0)
+                .collect();
+            let worst = differences.iter().copied().fold(0.0, f32::max);
+            let off = differences.iter().filter(|&&d| d > 2.0 / 255.0).count();
+            assert!(off * 100 <= differences.len(), "case {}: {} channels differ (worst {})", case, off, worst);
+        }
+
+        // The CPU path covers the preview, histogram and picker too
+        let cpu = RenderPipeline::new(None, 0, raw.clone(), width, height, &EditParams::default(), wb, matrix, CfaPattern::Grbg, levels, TEST_PREVIEW_WIDTH)
+            .await
+            .unwrap();
+        let shader = RenderPipeline::new(Some(gpu), 0, raw, width, height, &EditParams::default(), wb, matrix, CfaPattern::Grbg, levels, TEST_PREVIEW_WIDTH)
+            .await
+            .unwrap();
+        assert_eq!(cpu.render_to_bytes().len(), (width * height * 4) as usize);
+        let total: u32 = cpu.calculate_histogram_gpu()[3].iter().sum();
+        assert_eq!(total, width * height);
+        assert_eq!(cpu.sample_camera_rgb(20, 10), shader.sample_camera_rgb(20, 10));
+    }
+
+    #[tokio::test]
+    async fn test_14bit_levels_use_full_range() {
+        // 14-bit sensor with a 512 pedestal: a mid-range value is mid-gray,
+        // not blown out as it was when everything was divided by 4096 (which
+        // clipped at a quarter of the range)
+        let levels = SensorLevels { black: 512.0, white: 16383.0 };
+        let mid = 512.0 + (16383.0 - 512.0) * 0.5;
+        let (width, height) = (8u32, 8u32);
+        let raw = vec![mid as u16; (width * height) as usize];
+        let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        let params = EditParams::default();
+        let gpu = match GpuContext::shared().await {
+            Ok(gpu) => gpu,
+            Err(e) => {
+                println!("⚠️  Skipping GPU test: {}", e);
+                return;
+            }
+        };
+        let pipeline = RenderPipeline::new(Some(gpu), 0, raw, width, height, &params, [1.0; 4], identity, CfaPattern::Rggb, levels, TEST_PREVIEW_WIDTH)
+            .await
+            .unwrap();
+
+        let output = pipeline.render_full_res_to_bytes_16(&params);
+        let center = ((4 * width + 4) * 4) as usize;
+        for channel in &output[center..center + 3] {
+            let linear = crate::color::srgb_to_linear(*channel as f32 / 65535.0);
+            assert!((linear - 0.5).abs() < 0.02, "expected mid-gray, got {}", linear);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mid_gray_round_trip() {
+        // 18% gray in, no edits: the display value is the sRGB encoding of
+        // 0.18 (118/255, where a 2.2 power would give 117), the 8-bit preview,
+        // 16-bit export and histogram agree, and decoding gets 0.18 back
+        let levels = SensorLevels { black: 0.0, white: 65535.0 };
+        let (width, height) = (8u32, 8u32);
+        let raw = vec![(0.18f32 * 65535.0).round() as u16; (width * height) as usize];
+        let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        let params = EditParams::default();
+        let gpu = match GpuContext::shared().await {
+            Ok(gpu) => gpu,
+            Err(e) => {
+                println!("⚠️  Skipping GPU test: {}", e);
+                return;
+            }
+        };
+        let pipeline = RenderPipeline::new(Some(gpu), 0, raw, width, height, &params, [1.0; 4], identity, CfaPattern::Rggb, levels, TEST_PREVIEW_WIDTH)
+            .await
+            .unwrap();
+
+        let expected = crate::color::linear_to_srgb(0.18);
+        let preview = pipeline.render_to_bytes();
+        let center = ((4 * width + 4) * 4) as usize;
+        for channel in &preview[center..center + 3] {
+            assert_eq!(*channel, (expected * 255.0).round() as u8);
+        }
+        let histogram = pipeline.calculate_histogram_gpu();
+        assert_eq!(histogram[3][(expected * 255.0).round() as usize], width * height);
+
+        let export = pipeline.render_full_res_to_bytes_16(&params);
+        for channel in &export[center..center + 3] {
+            let linear = crate::color::srgb_to_linear(*channel as f32 / 65535.0);
+            assert!((linear - 0.18).abs() < 0.001, "expected 0.18, got {}", linear);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_crop_and_rotation_remap_output() {
+        // Diagonal ramp so every pixel is distinguishable
+        let (width, height) = (16u32, 16u32);
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (256 + 96 * x + 32 * y) as u16))
+            .collect();
+        let Some(full) = render_mosaic(raw.clone(), width, height, &EditParams::default()).await else {
+            return;
+        };
+
+        // Right half: output is 8x16 and shows source columns 8..16
+        let cropped = EditParams { crop_rect: Some((0.5, 0.0, 0.5, 1.0)), ..EditParams::default() };
+        let rgb = render_mosaic(raw.clone(), width, height, &cropped).await.unwrap();
+        assert_eq!(rgb.len(), (8 * height) as usize);
+        for y in 0..height {
+            for x in 0..8 {
+                assert_eq!(rgb[(y * 8 + x) as usize], full[(y * width + x + 8) as usize], "({}, {})", x, y);
+            }
+        }
+
+        // 180°: every output pixel comes from the mirrored source pixel
+        let rotated = EditParams { rotation_degrees: 180.0, ..EditParams::default() };
+        let rgb = render_mosaic(raw, width, height, &rotated).await.unwrap();
+        for y in 0..height {
+            for x in 0..width {
+                let source = ((height - 1 - y) * width + (width - 1 - x)) as usize;
+                assert_eq!(rgb[(y * width + x) as usize], full[source], "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clarity_boosts_local_contrast() {
+        // Flat gray with a vertical step in the middle: clarity should push the
+        // two sides of the edge apart and leave flat areas alone
+        let (width, height) = (32u32, 32u32);
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|_| (0..width).map(|x| if x < 16 { 800 } else { 1600 }))
+            .collect();
+        let Some(flat) = render_mosaic(raw.clone(), width, height, &EditParams::default()).await else {
+            return;
+        };
+        let clarity = EditParams { clarity: 1.0, ..EditParams::default() };
+        let rgb = render_mosaic(raw, width, height, &clarity).await.unwrap();
+
+        let at = |pixels: &[[f32; 3]], x: u32| pixels[(16 * width + x) as usize][1];
+        assert!(at(&rgb, 14) < at(&flat, 14) * 0.95, "dark side not darker: {} vs {}", at(&rgb, 14), at(&flat, 14));
+        assert!(at(&rgb, 16) > at(&flat, 16) * 1.05, "bright side not brighter: {} vs {}", at(&rgb, 16), at(&flat, 16));
+        assert!((at(&rgb, 4) - at(&flat, 4)).abs() < 0.001, "flat area changed");
+        assert!((at(&rgb, 28) - at(&flat, 28)).abs() < 0.001, "flat area changed");
+    }
+
+    #[tokio::test]
+    async fn test_slider_extremes_stay_sensible() {
+        // Horizontal ramp from black to sensor white
+        let (width, height) = (64u32, 4u32);
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|_| (0..width).map(|x| (x * 4095 / (width - 1)) as u16))
+            .collect();
+        let row = |pixels: &[[f32; 3]]| (0..width).map(|x| pixels[(2 * width + x) as usize][1]).collect::<Vec<_>>();
+
+        // Every tone slider at either end keeps the ramp in order (nothing
+        // inverts or wraps around)
+        let mut extremes = Vec::new();
+        for end in [-100.0, 100.0] {
+            extremes.push(EditParams { highlights: end, ..EditParams::default() });
+            extremes.push(EditParams { shadows: end, ..EditParams::default() });
+            extremes.push(EditParams { whites: end, ..EditParams::default() });
+            extremes.push(EditParams { blacks: end, ..EditParams::default() });
+            for tone_mode in ToneMode::ALL {
+                extremes.push(EditParams { contrast: end, tone_mode, ..EditParams::default() });
+            }
+        }
+        for params in &extremes {
+            let Some(rgb) = render_mosaic(raw.clone(), width, height, params).await else {
+                return;
+            };
+            let ramp = row(&rgb);
+            assert!(ramp.iter().all(|v| v.is_finite()), "{:?}", params);
+            for x in 1..width as usize {
+                assert!(ramp[x] >= ramp[x - 1] - 1e-3, "ramp reverses at {} for {:?}: {:?}", x, params, ramp);
+            }
+        }
+
+        // Contrast -100 is flat gray; mask contrast on top can't push it further
+        let mut flat = EditParams { contrast: -100.0, ..EditParams::default() };
+        let rgb = render_mosaic(raw.clone(), width, height, &flat).await.unwrap();
+        let ramp = row(&rgb);
+        assert!(ramp[width as usize - 1] - ramp[0] < 0.01, "not flat: {:?}", ramp);
+        flat.masks.push(LocalMask { contrast: -100.0, feather: 0.0, start: [0.5, 0.5], end: [2.0, 2.0], ..LocalMask::new(MaskKind::Radial) });
+        let masked = render_mosaic(raw, width, height, &flat).await.unwrap();
+        assert_eq!(row(&masked), ramp);
+    }
+
+    #[tokio::test]
+    async fn test_tone_modes_shape_contrast() {
+        // Horizontal ramp from black to sensor white
+        let (width, height) = (64u32, 4u32);
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|_| (0..width).map(|x| (x * 4095 / (width - 1)) as u16))
+            .collect();
+        let Some(linear) = render_mosaic(raw.clone(), width, height, &EditParams::default()).await else {
+            return;
+        };
+        let at = |pixels: &[[f32; 3]], x: u32| pixels[(2 * width + x) as usize][1];
+
+        // S-curve at full strength: shadows go down, highlights go up
+        let s_curve = EditParams { contrast: 100.0, tone_mode: ToneMode::SCurve, ..EditParams::default() };
+        let rgb = render_mosaic(raw.clone(), width, height, &s_curve).await.unwrap();
+        assert!(at(&rgb, 12) < at(&linear, 12) * 0.9, "shadows not darker: {} vs {}", at(&rgb, 12), at(&linear, 12));
+        assert!(at(&rgb, 50) > at(&linear, 50), "highlights not brighter: {} vs {}", at(&rgb, 50), at(&linear, 50));
+
+        // Filmic with the exposure pushed: the linear render clips the top of
+        // the ramp, the filmic one keeps separating it below white
+        let pushed = EditParams { exposure: 1.5, ..EditParams::default() };
+        let clipped = render_mosaic(raw.clone(), width, height, &pushed).await.unwrap();
+        assert_eq!(at(&clipped, 40), at(&clipped, 62));
+        let filmic = EditParams { tone_mode: ToneMode::Filmic, ..pushed };
+        let rgb = render_mosaic(raw, width, height, &filmic).await.unwrap();
+        for x in 40..63 {
+            assert!(at(&rgb, x) < at(&rgb, x + 1), "no rolloff at {}: {} vs {}", x, at(&rgb, x), at(&rgb, x + 1));
+        }
+        assert!(at(&rgb, 63) < 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_film_negative_inverts_against_base() {
+        // Left half is bare film base, the right half lets half as much light
+        // through (an exposed area of the negative)
+        let (width, height) = (32u32, 16u32);
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|_| (0..width).map(|x| if x < 16 { 3000 } else { 1500 }))
+            .collect();
+        let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        let pipeline = match test_pipeline(raw.clone(), width, height, &EditParams::default(), identity, CfaPattern::Rggb, TEST_PREVIEW_WIDTH).await {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                println!("⚠️  Skipping GPU test: {}", e);
+                return;
+            }
+        };
+        let film_base = pipeline.sample_working_rgb(4, 8, &EditParams::default()).unwrap();
+        let negative = EditParams { invert: true, film_base, ..EditParams::default() };
+        let rgb = render_mosaic(raw, width, height, &negative).await.unwrap();
+
+        let at = |x: u32| rgb[(8 * width + x) as usize];
+        for channel in 0..3 {
+            assert!(at(4)[channel] < 0.01, "film base not black: {:?}", at(4));
+            assert!((at(27)[channel] - 0.5).abs() < 0.02, "exposed area not inverted: {:?}", at(27));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hsl_band_targets_its_hue() {
+        // Pure red scene: the red band's saturation slider drains it to gray,
+        // the green band's doesn't touch it
+        let (width, height) = (8u32, 8u32);
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| if x % 2 == 0 && y % 2 == 0 { 2048 } else { 0 }))
+            .collect();
+        let Some(original) = render_mosaic(raw.clone(), width, height, &EditParams::default()).await else {
+            return;
+        };
+        let center = (4 * width + 4) as usize;
+
+        let mut green = EditParams::default();
+        green.set_hsl(3, HslChannel::Saturation, -100.0);
+        let rgb = render_mosaic(raw.clone(), width, height, &green).await.unwrap();
+        for channel in 0..3 {
+            assert!((rgb[center][channel] - original[center][channel]).abs() < 0.01, "{:?} vs {:?}", rgb[center], original[center]);
+        }
+
+        let mut red = EditParams::default();
+        red.set_hsl(0, HslChannel::Saturation, -100.0);
+        let rgb = render_mosaic(raw, width, height, &red).await.unwrap();
+        let pixel = rgb[center];
+        assert!((pixel[0] - pixel[1]).abs() < 0.01 && (pixel[1] - pixel[2]).abs() < 0.01, "not gray: {:?}", pixel);
+    }
+
+    #[tokio::test]
+    async fn test_preview_width_follows_setting() {
+        let raw = vec![2048u16; 64 * 32];
+        let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        let pipeline = match test_pipeline(raw, 64, 32, &EditParams::default(), identity, CfaPattern::Rggb, 16).await {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                println!("⚠️  Skipping GPU test: {}", e);
+                return;
+            }
+        };
+        assert_eq!(pipeline.preview_size(), (16, 8));
+        assert_eq!(pipeline.render_to_bytes().len(), 16 * 8 * 4);
+
+        // Never wider than the image itself
+        pipeline.set_max_preview_width(u32::MAX);
+        assert_eq!(pipeline.preview_size(), (64, 32));
+        assert_eq!(pipeline.render_to_bytes().len(), 64 * 32 * 4);
+    }
+
+    #[tokio::test]
+    async fn test_zoom_uniforms_and_histogram_render() {
+        // Horizontal ramp: zooming in 2x about the center moves brighter
+        // pixels to the left edge
+        let (width, height) = (64u32, 32u32);
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|_| (0..width).map(|x| (256 + x * 48) as u16))
+            .collect();
+        let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        let params = EditParams::default();
+        let pipeline = match test_pipeline(raw, width, height, &params, identity, CfaPattern::Rggb, TEST_PREVIEW_WIDTH).await {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                println!("⚠️  Skipping GPU test: {}", e);
+                return;
+            }
+        };
+        let left_edge_green = |bytes: &[u8]| bytes[((height / 2 * width) * 4 + 1) as usize];
+
+        let unzoomed = left_edge_green(&pipeline.render_to_bytes());
+        pipeline.update_uniforms_with_zoom(&params, 2.0, 0.0, 0.0);
+        let zoomed = left_edge_green(&pipeline.render_to_bytes());
+        assert!(zoomed > unzoomed, "2x zoom should show the middle of the ramp: {} vs {}", zoomed, unzoomed);
+
+        // The compute histogram bins the same (zoomed) preview frame, pixel for pixel
+        let histogram = pipeline.calculate_histogram_gpu();
+        let mut expected = [[0u32; 256]; 3];
+        for pixel in pipeline.render_to_bytes().chunks_exact(4) {
+            for channel in 0..3 {
+                expected[channel][pixel[channel] as usize] += 1;
+            }
+        }
+        assert_eq!(histogram[..3], expected);
+        assert_eq!(histogram[3].iter().sum::<u32>(), width * height);
+        assert_eq!(histogram[1].iter().sum::<u32>(), width * height);
+    }
+
+    #[tokio::test]
+    async fn test_clipping_warnings_are_display_only() {
+        // Pushed well past white: every pixel clips
+        let raw = vec![3000u16; 16 * 16];
+        let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        let params = EditParams { exposure: 3.0, ..EditParams::default() };
+        let pipeline = match test_pipeline(raw, 16, 16, &params, identity, CfaPattern::Rggb, TEST_PREVIEW_WIDTH).await {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                println!("⚠️  Skipping GPU test: {}", e);
+                return;
+            }
+        };
+        let center = |bytes: &[u8]| bytes[(8 * 16 + 8) * 4..(8 * 16 + 8) * 4 + 3].to_vec();
+
+        pipeline.set_show_clipping(true);
+        pipeline.update_uniforms(&params);
+        assert_eq!(center(&pipeline.render_to_bytes()), vec![255, 0, 0]);
+
+        // Exports never carry the overlay
+        assert_eq!(center(&pipeline.render_full_res_to_bytes(&params)), vec![255, 255, 255]);
+    }
+
+    #[tokio::test]
+    async fn test_orientation_turns_the_render() {
+        // 16x8 landscape frame: left half lit, right half black
+        let (width, height) = (16u32, 8u32);
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|_| (0..width).map(|x| if x < 8 { 2048 } else { 0 }))
+            .collect();
+        let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        let params = EditParams::default();
+        let pipeline = match test_pipeline(raw, width, height, &params, identity, CfaPattern::Rggb, TEST_PREVIEW_WIDTH).await {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                println!("⚠️  Skipping GPU test: {}", e);
+                return;
+            }
+        };
+
+        // EXIF 6 (rotate 90° clockwise): portrait output, the left edge on top
+        pipeline.set_orientation(Orientation::from_exif(6));
+        assert_eq!(pipeline.export_size(&params), (8, 16));
+        let bytes = pipeline.render_full_res_to_bytes(&params);
+        let luma = |x: usize, y: usize| bytes[(y * 8 + x) * 4];
+        assert!(luma(4, 2) > 100, "top should be lit, got {}", luma(4, 2));
+        assert!(luma(4, 13) < 10, "bottom should be black, got {}", luma(4, 13));
+    }
+
+    #[tokio::test]
+    async fn test_highlight_recovery_rebuilds_clipped_green() {
+        // Neutral horizontal gradient shot with a warm white balance (R and B
+        // x2): green reaches the white level first and clips, so without
+        // recovery the bright end turns magenta
+        let (width, height) = (32u32, 8u32);
+        let wb = [2.0, 1.0, 2.0, 1.0];
+        let scene = |x: u32| 0.5 + 0.04 * x as f32;
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| {
+                // RGGB: red and blue sites sit where x and y have the same parity
+                let gain = if (x + y) % 2 == 0 { 0.5 } else { 1.0 };
+                (scene(x) * gain * 4096.0).min(4095.0) as u16
+            }))
+            .collect();
+        let gpu = match GpuContext::shared().await {
+            Ok(gpu) => gpu,
+            Err(e) => {
+                println!("⚠️  Skipping GPU test: {}", e);
+                return;
+            }
+        };
+        let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        let pipeline = RenderPipeline::new(Some(gpu), 0, raw, width, height, &EditParams::default(), wb, identity, CfaPattern::Rggb, SensorLevels::TWELVE_BIT, TEST_PREVIEW_WIDTH)
+            .await
+            .unwrap();
+        // Pull everything below 1.0 so the display clamp doesn't hide the cast
+        let render = |highlight_recovery: f32| {
+            let params = EditParams { exposure: -1.5, highlight_recovery, ..EditParams::default() };
+            let to_linear = |v: u16| crate::color::srgb_to_linear(v as f32 / 65535.0);
+            pipeline
+                .render_full_res_to_bytes_16(&params)
+                .chunks_exact(4)
+                .map(|px| [to_linear(px[0]), to_linear(px[1]), to_linear(px[2])])
+                .collect::<Vec<_>>()
+        };
+        let clipped = (4 * width + 20) as usize;   // Scene 1.3: green blown, red/blue at 0.65
+        let unclipped = (4 * width + 6) as usize;  // Scene 0.74: nothing near white
+
+        let before = render(0.0);
+        let [r, g, b] = before[clipped];
+        assert!(g < 0.8 * r && g < 0.8 * b, "expected magenta without recovery, got {:?}", before[clipped]);
+
+        let after = render(1.0);
+        let [r, g, b] = after[clipped];
+        assert!((g / r - 1.0).abs() < 0.1 && (g / b - 1.0).abs() < 0.1, "expected neutral, got {:?}", after[clipped]);
+        assert!(g > before[clipped][1], "recovered green should be brighter than the clipped value");
+
+        // Unclipped tones are untouched
+        for (a, b) in before[unclipped].iter().zip(after[unclipped]) {
+            assert!((a - b).abs() < 1e-3, "{:?} vs {:?}", before[unclipped], after[unclipped]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lens_correction_applies_before_crop() {
+        // Horizontal ramp: barrel correction pulls samples towards the
+        // center, so the right edge reads darker and the center is unmoved
+        let (width, height) = (32u32, 32u32);
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|_| (0..width).map(|x| (256 + 96 * x) as u16))
+            .collect();
+        let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        let pipeline = match test_pipeline(raw, width, height, &EditParams::default(), identity, CfaPattern::Rggb, TEST_PREVIEW_WIDTH).await {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                println!("⚠️  Skipping GPU test: {}", e);
+                return;
+            }
+        };
+        let render = |params: EditParams| {
+            pipeline.render_full_res_to_bytes_16(&params).chunks_exact(4).map(|px| px[1]).collect::<Vec<u16>>()
+        };
+        let at = |x: u32, y: u32| (y * width + x) as usize;
+
+        let plain = render(EditParams::default());
+        let corrected = EditParams { lens_correction: true, lens_distortion: 1.0, ..EditParams::default() };
+        let undistorted = render(corrected);
+        assert_eq!(undistorted[at(16, 16)], plain[at(16, 16)]);
+        assert!(undistorted[at(30, 16)] < plain[at(30, 16)], "edge should sample closer to the center");
+
+        // The crop frames the corrected image: its pixels match the corrected full frame
+        let cropped = render(EditParams { crop_rect: Some((0.5, 0.0, 0.5, 1.0)), ..corrected });
+        for x in 0..width / 2 {
+            let (a, b) = (cropped[(16 * width / 2 + x) as usize], undistorted[at(width / 2 + x, 16)]);
+            assert!(a.abs_diff(b) < 64, "x {}: cropped {} vs full {}", x, a, b);
+        }
+
+        // A profile also lifts the corners; switched off, nothing changes
+        pipeline.set_lens_profile(Some(crate::state::lens::LENS_PROFILES[0]));
+        let profiled = render(EditParams { lens_correction: true, ..EditParams::default() });
+        assert!(profiled[at(1, 1)] > plain[at(1, 1)], "corner should be brightened");
+        assert_eq!(render(EditParams::default()), plain);
+    }
+
+    #[tokio::test]
+    async fn test_temperature_shifts_red_blue_ratio() {
+        // Neutral gray: equal R and B as shot, warmer = more red than blue
+        let (width, height) = (8u32, 8u32);
+        let raw = vec![1024u16; (width * height) as usize];
+        let center = (4 * width + 4) as usize;
+        let ratio = |rgb: &[[f32; 3]]| rgb[center][0] / rgb[center][2];
+
+        let Some(as_shot) = render_mosaic(raw.clone(), width, height, &EditParams::default()).await else {
+            return;
+        };
+        assert!((ratio(&as_shot) - 1.0).abs() < 0.01, "as shot {:?}", as_shot[center]);
+
+        let warm = EditParams { temperature: 0.5, ..EditParams::default() };
+        let warm = render_mosaic(raw.clone(), width, height, &warm).await.unwrap();
+        assert!(ratio(&warm) > 1.2, "warm {:?}", warm[center]);
+
+        let cool = EditParams { temperature: -0.5, ..EditParams::default() };
+        let cool = render_mosaic(raw, width, height, &cool).await.unwrap();
+        assert!(ratio(&cool) < 0.8, "cool {:?}", cool[center]);
+    }
+
+    #[tokio::test]
+    async fn test_monochrome_uses_channel_mix() {
+        // Pure red scene: the mix decides how bright it turns out, and the
+        // result is neutral (equal channels, so the histogram channels match)
+        let (width, height) = (8u32, 8u32);
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| if x % 2 == 0 && y % 2 == 0 { 2048 } else { 0 }))
+            .collect();
+        let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        let center = (4 * width + 4) as usize;
+
+        let red_filter = EditParams { monochrome: true, bw_mix: [1.0, 0.0, 0.0], ..EditParams::default() };
+        let pipeline = match test_pipeline(raw.clone(), width, height, &red_filter, identity, CfaPattern::Rggb, TEST_PREVIEW_WIDTH).await {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                println!("⚠️  Skipping GPU test: {}", e);
+                return;
+            }
+        };
+        let histogram = pipeline.calculate_histogram_gpu();
+        assert_eq!(histogram[0], histogram[1]);
+        assert_eq!(histogram[1], histogram[2]);
+        // Gray pixels: luma lands in the same bins as the channels
+        assert_eq!(histogram[3], histogram[0]);
+        drop(pipeline);
+
+        let bright = render_mosaic(raw.clone(), width, height, &red_filter).await.unwrap()[center];
+        assert!((bright[0] - 0.5).abs() < 0.01, "{:?}", bright);
+        assert!((bright[0] - bright[1]).abs() < 0.001 && (bright[1] - bright[2]).abs() < 0.001, "{:?}", bright);
+
+        let blue_filter = EditParams { monochrome: true, bw_mix: [0.0, 0.0, 1.0], ..EditParams::default() };
+        let dark = render_mosaic(raw, width, height, &blue_filter).await.unwrap()[center];
+        assert!(dark[0] < 0.01, "{:?}", dark);
+    }
+
+    #[tokio::test]
+    async fn test_sharpening_steepens_edges() {
+        // Neutral vertical edge: dark left half, bright right half
+        let (width, height) = (32u32, 8u32);
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|_| (0..width).map(|x| if x < 16 { 800 } else { 2400 }))
+            .collect();
+        let Some(soft) = render_mosaic(raw.clone(), width, height, &EditParams::default()).await else {
+            return;
+        };
+
+        // Radius alone changes nothing while the amount is zero
+        let off = EditParams { sharpen_radius: 2.5, ..EditParams::default() };
+        assert_eq!(render_mosaic(raw.clone(), width, height, &off).await.unwrap(), soft);
+
+        let sharpened = EditParams { sharpen_amount: 1.0, ..EditParams::default() };
+        let rgb = render_mosaic(raw, width, height, &sharpened).await.unwrap();
+        let row = 4 * width as usize;
+        assert!(rgb[row + 14][1] < soft[row + 14][1], "dark side {:?} vs {:?}", rgb[row + 14], soft[row + 14]);
+        assert!(rgb[row + 17][1] > soft[row + 17][1], "bright side {:?} vs {:?}", rgb[row + 17], soft[row + 17]);
+        // Flat areas away from the edge are untouched
+        assert!((rgb[row + 4][1] - soft[row + 4][1]).abs() < 0.002);
+        assert!((rgb[row + 27][1] - soft[row + 27][1]).abs() < 0.002);
+    }
+
+    #[tokio::test]
+    async fn test_noise_reduction_smooths_grain() {
+        // Mid-gray with per-pixel pseudo-random noise on every photosite
+        let (width, height) = (32u32, 32u32);
+        let raw: Vec<u16> = (0..width * height)
+            .map(|i| 1500 + ((i.wrapping_mul(2654435761) >> 24) % 256) as u16)
+            .collect();
+        let Some(noisy) = render_mosaic(raw.clone(), width, height, &EditParams::default()).await else {
+            return;
+        };
+        let smoothed = EditParams { luma_nr: 1.0, chroma_nr: 1.0, ..EditParams::default() };
+        let clean = render_mosaic(raw, width, height, &smoothed).await.unwrap();
+
+        // Spread of luminance and of the green-red difference over the interior
+        let spread = |rgb: &[[f32; 3]], value: &dyn Fn(&[f32; 3]) -> f32| {
+            let samples: Vec<f32> = (4..28)
+                .flat_map(|y| (4..28).map(move |x| y * 32 + x))
+                .map(|i| value(&rgb[i]))
+                .collect();
+            let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+            samples.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / samples.len() as f32
+        };
+        let luma = |c: &[f32; 3]| c[0] + c[1] + c[2];
+        let chroma = |c: &[f32; 3]| c[1] - c[0];
+        assert!(spread(&clean, &luma) < spread(&noisy, &luma) * 0.5);
+        assert!(spread(&clean, &chroma) < spread(&noisy, &chroma) * 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_vignette_follows_crop() {
+        // Flat gray scene: only the vignette can make pixels differ
+        let (width, height) = (32u32, 16u32);
+        let raw = vec![2048u16; (width * height) as usize];
+        let Some(plain) = render_mosaic(raw.clone(), width, height, &EditParams::default()).await else {
+            return;
+        };
+
+        // Zero amount is a no-op whatever the midpoint
+        let off = EditParams { vignette_midpoint: 0.1, ..EditParams::default() };
+        assert_eq!(render_mosaic(raw.clone(), width, height, &off).await.unwrap(), plain);
+
+        // Cropped to the right half: the vignette centers on the crop
+        let crop = Some((0.5, 0.0, 0.5, 1.0));
+        let cropped = EditParams { crop_rect: crop, ..EditParams::default() };
+        let vignetted = EditParams { crop_rect: crop, vignette_amount: -0.8, ..EditParams::default() };
+        let base = render_mosaic(raw.clone(), width, height, &cropped).await.unwrap();
+        let rgb = render_mosaic(raw, width, height, &vignetted).await.unwrap();
+
+        let out_width = 16usize;
+        let center = 8 * out_width + 8;
+        let corner = out_width + 1;
+        assert!((rgb[center][1] - base[center][1]).abs() < 0.01, "{:?} vs {:?}", rgb[center], base[center]);
+        assert!(rgb[corner][1] < base[corner][1] * 0.7, "{:?} vs {:?}", rgb[corner], base[corner]);
+        // Symmetric around the crop center
+        let opposite = 14 * out_width + 14;
+        assert!((rgb[corner][1] - rgb[opposite][1]).abs() < 0.01);
+    }
+
+    #[tokio::test]
+    async fn test_masks_apply_locally() {
+        // Flat gray scene: a radial +1 EV mask brightens only its middle, a
+        // linear one fades from full effect above its start to none past its end
+        let (width, height) = (32u32, 32u32);
+        let raw = vec![1024u16; (width * height) as usize];
+        let Some(plain) = render_mosaic(raw.clone(), width, height, &EditParams::default()).await else {
+            return;
+        };
+        let at = |rgb: &[[f32; 3]], x: u32, y: u32| rgb[(y * width + x) as usize][1];
+
+        let mut radial = EditParams::default();
+        radial.masks.push(LocalMask { exposure: 1.0, ..LocalMask::new(MaskKind::Radial) });
+        let rgb = render_mosaic(raw.clone(), width, height, &radial).await.unwrap();
+        assert!((at(&rgb, 16, 16) / at(&plain, 16, 16) - 2.0).abs() < 0.05, "center not +1 EV");
+        assert!((at(&rgb, 1, 1) - at(&plain, 1, 1)).abs() < 0.01, "corner changed");
+
+        let mut linear = EditParams::default();
+        linear.masks.push(LocalMask { exposure: 1.0, start: [0.5, 0.25], end: [0.5, 0.75], ..LocalMask::new(MaskKind::Linear) });
+        let rgb = render_mosaic(raw, width, height, &linear).await.unwrap();
+        assert!((at(&rgb, 16, 2) / at(&plain, 16, 2) - 2.0).abs() < 0.05, "top not +1 EV");
+        let middle = at(&rgb, 16, 16) / at(&plain, 16, 16);
+        assert!(middle > 1.2 && middle < 1.8, "middle not halfway: {middle}");
+        assert!((at(&rgb, 16, 29) - at(&plain, 16, 29)).abs() < 0.01, "bottom changed");
+    }
+
+    #[tokio::test]
+    async fn test_split_toning_tints_by_brightness() {
+        // Dark and bright halves of a gray scene: a blue shadow tint and an
+        // orange highlight tint pull each half towards its own color
+        let (width, height) = (16u32, 8u32);
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|_| (0..width).map(|x| if x < 8 { 150 } else { 3000 }))
+            .collect();
+        let Some(plain) = render_mosaic(raw.clone(), width, height, &EditParams::default()).await else {
+            return;
+        };
+        let toned = EditParams { shadow_tint: [0.0, 0.2, 0.8], highlight_tint: [0.8, 0.4, 0.0], ..EditParams::default() };
+        let rgb = render_mosaic(raw, width, height, &toned).await.unwrap();
+
+        let (dark, bright) = ((4 * width + 3) as usize, (4 * width + 12) as usize);
+        for pixel in [plain[dark], plain[bright]] {
+            assert!((pixel[0] - pixel[2]).abs() < 0.01, "scene not gray: {:?}", pixel);
+        }
+        assert!(rgb[dark][2] > rgb[dark][0] * 1.1, "shadows not blue: {:?}", rgb[dark]);
+        assert!(rgb[bright][0] > rgb[bright][2] * 1.1, "highlights not orange: {:?}", rgb[bright]);
+        // Recolored, not brightened
+        let luma = |p: [f32; 3]| 0.2126 * p[0] + 0.7152 * p[1] + 0.0722 * p[2];
+        assert!((luma(rgb[bright]) - luma(plain[bright])).abs() < 0.02 * luma(plain[bright]).max(0.1));
+    }
+
+    #[tokio::test]
+    async fn test_cfa_pattern_selects_channels() {
+        // Light only the red photosites of each layout: a correctly aligned
+        // demosaic sees a pure red scene, a misaligned one leaks into G or B
+        let (width, height) = (8u32, 8u32);
+        for pattern in [CfaPattern::Rggb, CfaPattern::Bggr, CfaPattern::Grbg, CfaPattern::Gbrg] {
+            let [red_x, red_y] = pattern.offset();
+            let raw: Vec<u16> = (0..height)
+                .flat_map(|y| (0..width).map(move |x| {
+                    if x % 2 == red_x && y % 2 == red_y { 2048 } else { 0 }
+                }))
+                .collect();
+
+            let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+            let Some(rgb) = render_mosaic_with(raw, width, height, &EditParams::default(), pattern, identity).await else {
+                return;
+            };
+
+            let pixel = rgb[(4 * width + 4) as usize];
+            assert!(pixel[0] > 0.4, "{}: red missing {:?}", pattern, pixel);
+            assert!(pixel[1] < 0.01 && pixel[2] < 0.01, "{}: color leaked {:?}", pattern, pixel);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_color_matrix_is_applied_row_major() {
+        // Pure red scene through an asymmetric matrix whose third row mixes
+        // red into blue: row-major gives B = 0.5 * R, the transpose gives B = 0
+        let (width, height) = (8u32, 8u32);
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| if x % 2 == 0 && y % 2 == 0 { 2048 } else { 0 }))
+            .collect();
+        let matrix = [
+            1.0, 0.0, 0.0,
+            0.0, 1.0, 0.0,
+            0.5, 0.0, 0.5,
+        ];
+
+        let Some(rgb) = render_mosaic_with(raw, width, height, &EditParams::default(), CfaPattern::Rggb, matrix).await else {
+            return;
+        };
+
+        let pixel = rgb[(4 * width + 4) as usize];
+        assert!((pixel[0] - 0.5).abs() < 0.01, "{:?}", pixel);
+        assert!((pixel[2] - 0.25).abs() < 0.01, "{:?}", pixel);
+    }
+
+    #[tokio::test]
+    async fn test_sample_camera_rgb_reads_patch() {
+        // Flat BGGR scene: R = 800, G = 1600, B = 400 everywhere
+        let (width, height) = (16u32, 16u32);
+        let pattern = CfaPattern::Bggr;
+        let [red_x, red_y] = pattern.offset();
+        let raw: Vec<u16> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| {
+                match ((x + red_x) & 1, (y + red_y) & 1) {
+                    (0, 0) => 800,
+                    (1, 1) => 400,
+                    _ => 1600,
+                }
+            }))
+            .collect();
+
+        let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        let pipeline = match test_pipeline(raw, width, height, &EditParams::default(), identity, pattern, TEST_PREVIEW_WIDTH).await {
+            Ok(pipeline) => pipeline,
+            Err(e) => {
+                println!("⚠️  Skipping GPU test: {}", e);
+                return;
+            }
+        };
+
+        // Center, odd position and a corner (patch gets clamped inside the image)
+        for (x, y) in [(8, 8), (5, 3), (15, 15)] {
+            let rgb = pipeline.sample_camera_rgb(x, y).unwrap();
+            assert!((rgb[0] * 4096.0 - 800.0).abs() < 0.5, "{:?}", rgb);
+            assert!((rgb[1] * 4096.0 - 1600.0).abs() < 0.5, "{:?}", rgb);
+            assert!((rgb[2] * 4096.0 - 400.0).abs() < 0.5, "{:?}", rgb);
+        }
+        assert!(pipeline.sample_camera_rgb(width, 0).is_none());
+
+        // A white balance picked from it lives in the edits and renders the scene gray
+        let rgb = pipeline.sample_camera_rgb(8, 8).unwrap();
+        let picked = EditParams {
+            wb_override: crate::color::solve_wb_multipliers(rgb, 0.0, 0.0),
+            ..EditParams::default()
+        };
+        let center = |bytes: Vec<u8>| bytes[((8 * width + 8) * 4) as usize..][..3].to_vec();
+        let as_shot = center(pipeline.render_full_res_to_bytes(&EditParams::default()));
+        assert!(as_shot[0] != as_shot[2], "{:?}", as_shot);
+        let gray = center(pipeline.render_full_res_to_bytes(&picked));
+        assert!(gray.iter().all(|&c| c.abs_diff(gray[1]) <= 2), "{:?}", gray);
     }
 }
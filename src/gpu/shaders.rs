@@ -54,7 +54,9 @@ fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
     tex_x += 0.5;
     tex_y += 0.5;
     
-    output.tex_coords = vec2<f32>(tex_x, tex_y);
+    // Tiled export: the viewport covers one tile of the output
+    output.tex_coords = vec2<f32>(params.tile_x, params.tile_y)
+        + vec2<f32>(tex_x, tex_y) * vec2<f32>(params.tile_w, params.tile_h);
     
     return output;
 }
@@ -64,17 +66,17 @@ fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
 // Uniform buffer for edit parameters
 struct EditParams {
     exposure: f32,        // -5.0 to +5.0 stops
-    contrast: f32,        // -100.0 to +100.0
-    highlights: f32,      // -100.0 to +100.0
-    shadows: f32,         // -100.0 to +100.0
-    whites: f32,          // -100.0 to +100.0
-    blacks: f32,          // -100.0 to +100.0
+    contrast: f32,        // -100.0 to +100.0 (with the masks' deltas added, clamped)
+    highlights: f32,      // -0.5 to +0.5: brightness change at white
+    shadows: f32,         // -1.0 to +1.0: brightness change at black
+    whites: f32,          // White point, 0.8 to 1.2 (1.0 = none)
+    blacks: f32,          // Black point, -0.2 to +0.2 (0.0 = none)
     vibrance: f32,        // -100.0 to +100.0
     saturation: f32,      // -100.0 to +100.0
-    temperature: f32,     // -100 to +100 (converted from i32)
-    tint: f32,            // -100 to +100 (converted from i32)
-    padding1: f32,        // Padding for 16-byte alignment
-    padding2: f32,        // Padding for 16-byte alignment
+    temperature: f32,     // -1 (cooler) to +1 (warmer)
+    tint: f32,            // -1 (magenta) to +1 (green)
+    curve_enabled: f32,   // 1.0 = apply tone curve LUT, 0.0 = identity (skip)
+    demosaic_mode: f32,   // 0.0 = bilinear, 1.0 = Malvar-He-Cutler
     // Phase 14: Color science metadata
     wb_multipliers: vec4<f32>,  // White balance [R, G, B, G2]
     color_matrix_0: vec3<f32>,  // Color matrix row 0
@@ -88,6 +90,76 @@ struct EditParams {
     pan_x: f32,                  // Pan offset X
     pan_y: f32,                  // Pan offset Y
     padding6: f32,               // Padding for alignment
+    // Bayer layout: offset that moves a pixel onto an RGGB origin
+    cfa_offset_x: f32,
+    cfa_offset_y: f32,
+    padding7: f32,
+    padding8: f32,
+    // Geometry: crop rectangle in the straightened frame (0-1) and rotation
+    crop_x: f32,
+    crop_y: f32,
+    crop_w: f32,
+    crop_h: f32,
+    rotation: f32,               // Radians, positive = clockwise
+    clarity: f32,                // Local contrast, -1 to +1
+    hsl_enabled: f32,            // 1.0 = apply the HSL bands, 0.0 = skip
+    tone_mode: f32,              // 0.0 = linear, 1.0 = S-curve, 2.0 = filmic
+    // Per-band (hue shift, saturation, luminance, unused), each -100 to +100
+    // Bands: red, orange, yellow, green, aqua, blue, purple, magenta
+    hsl: array<vec4<f32>, 8>,
+    // Black & white: gray = dot(color, bw_mix) when monochrome is on
+    bw_mix: vec3<f32>,
+    monochrome: f32,
+    // Post-crop vignette: amount -1..1 (negative = darker corners), midpoint 0..1
+    vignette_amount: f32,
+    vignette_midpoint: f32,
+    // Noise reduction amounts 0..1 (both 0 = skipped)
+    luma_nr: f32,
+    chroma_nr: f32,
+    // Capture sharpening: amount 0..1.5 (0 = skipped), radius = sigma in source pixels
+    sharpen_amount: f32,
+    sharpen_radius: f32,
+    detail_preview: f32,         // 1.0 = output only the sharpening high-pass
+    show_clipping: f32,          // 1.0 = warning colors on clipped pixels (display only)
+    // Orientation: mirror (1.0) then rotate clockwise by quarter turns (0-3)
+    orientation_turns: f32,
+    orientation_mirror: f32,
+    padding12: f32,
+    padding13: f32,
+    // Sensor levels in raw units: black pedestal and saturation point
+    black_level: f32,
+    white_level: f32,
+    highlight_recovery: f32,     // 0..1, rebuild clipped channels (0 = skipped)
+    lens_correction: f32,        // 1.0 = undistort and devignette with the lens_* terms
+    // Lens model (see state/lens.rs), r = 1 at the corners:
+    // imaged radius = r * (1 + k1 r^2 + k2 r^4), light falloff = 1 + v1 r^2 + v2 r^4
+    lens_k1: f32,
+    lens_k2: f32,
+    lens_v1: f32,
+    lens_v2: f32,
+    // Part of the output this pass renders, as fractions of the full output
+    // (0, 0, 1, 1 except for tiled exports)
+    tile_x: f32,
+    tile_y: f32,
+    tile_w: f32,
+    tile_h: f32,
+    // Film negative: 1 - color / film_base when invert is on (base -> black)
+    film_base: vec3<f32>,
+    invert: f32,
+    // Local adjustment masks: only the first mask_count are read. Three rows
+    // per mask: (start.xy, end.xy), (kind 0 = radial / 1 = linear, feather, -, -),
+    // (exposure, contrast, saturation deltas, -)
+    mask_count: f32,
+    padding14: f32,
+    padding15: f32,
+    padding16: f32,
+    masks: array<vec4<f32>, 24>,
+    // Split toning: tints for the dark and bright tones, balance shifts the
+    // crossover (-1..1); skipped unless split_tone_enabled
+    shadow_tint: vec3<f32>,
+    balance: f32,
+    highlight_tint: vec3<f32>,
+    split_tone_enabled: f32,
 }
 
 @group(0) @binding(0)
@@ -99,80 +171,406 @@ var texture_sampler: sampler;  // Not used for integer textures, but kept for co
 @group(0) @binding(2)
 var<uniform> params: EditParams;
 
-// Simple nearest-neighbor debayering
-// Assumes RGGB Bayer pattern (most common)
-fn debayer(coords: vec2<i32>, dimensions: vec2<u32>) -> vec3<f32> {
-    // Load RAW pixel value (12-bit in u16, stored as u32)
-    let raw_value = textureLoad(input_texture, coords, 0).r;
-    
-    // Convert to normalized float (0.0 - 1.0)
-    // 12-bit max = 4096
-    let normalized = f32(raw_value) / 4096.0;
-    
-    // Determine position in Bayer pattern
-    // Some cameras start at (1,1) instead of (0,0) - uncomment next 2 lines if colors are still wrong:
-    let x = coords.x;  // Try uncommenting this
-    let y = coords.y + 1;  // Try uncommenting this
-    let is_even_row = (y % 2) == 0;
-    let is_even_col = (x % 2) == 0;
-    
+@group(0) @binding(3)
+var curve_lut: texture_1d<f32>;  // 256-entry tone curve (display space)
+
+// sRGB transfer function (IEC 61966-2-1): linear light -> display values.
+// The output textures are plain Unorm/Uint (16-bit export has no sRGB
+// format), so the shader encodes itself, with the real curve's linear toe
+// rather than a 2.2 power. Negative input encodes to 0.
+fn srgb_encode(linear: vec3<f32>) -> vec3<f32> {
+    let c = max(linear, vec3<f32>(0.0));
+    let toe = c * 12.92;
+    let curve = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(curve, toe, c <= vec3<f32>(0.0031308));
+}
+
+// Inverse of srgb_encode: display values -> linear light
+fn srgb_decode(encoded: vec3<f32>) -> vec3<f32> {
+    let toe = encoded / 12.92;
+    let curve = pow(max((encoded + 0.055) / 1.055, vec3<f32>(0.0)), vec3<f32>(2.4));
+    return select(curve, toe, encoded <= vec3<f32>(0.04045));
+}
+
+// ACES filmic fit (Narkowicz 2015): gentle toe, highlights roll off
+// toward 1.0 instead of clipping
+fn aces_filmic(x: vec3<f32>) -> vec3<f32> {
+    let c = max(x, vec3<f32>(0.0));
+    return clamp((c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+// Contrast shaped by the tone mode
+// - Linear: straight line around 0.5, the slope 0 (-100, flat gray) to
+//   2 (+100)
+// - S-curve: blend toward smoothstep; the slider's +/-100 ends give the full
+//   curve (negative flattens). Values outside 0-1 pass through untouched
+// - Filmic: linear contrast, then the ACES shoulder (input scaled by 0.6 so
+//   mid gray only darkens slightly)
+fn apply_tone(color: vec3<f32>, contrast_in: f32) -> vec3<f32> {
+    // Global and mask contrast add up; past -100 the slope would flip
+    let contrast = clamp(contrast_in, -100.0, 100.0);
+    let contrast_factor = 1.0 + (contrast / 100.0);
+    if params.tone_mode > 1.5 {
+        return aces_filmic(((color - 0.5) * contrast_factor + 0.5) * 0.6);
+    }
+    if params.tone_mode > 0.5 {
+        let strength = contrast / 100.0;
+        let x = clamp(color, vec3<f32>(0.0), vec3<f32>(1.0));
+        let curved = mix(x, x * x * (3.0 - 2.0 * x), strength);
+        return select(curved, color, x != color);
+    }
+    return (color - 0.5) * contrast_factor + 0.5;
+}
+
+// Look up the tone curve with linear interpolation between LUT entries
+fn apply_curve(value: f32) -> f32 {
+    let x = clamp(value, 0.0, 1.0) * 255.0;
+    let i = i32(floor(x));
+    let j = min(i + 1, 255);
+    let a = textureLoad(curve_lut, i, 0).r;
+    let b = textureLoad(curve_lut, j, 0).r;
+    return mix(a, b, fract(x));
+}
+
+// Position in the 2x2 RGGB tile after applying the CFA offset:
+// (0,0) = R, (1,0) = G on a red row, (0,1) = G on a blue row, (1,1) = B
+fn cfa_position(coords: vec2<i32>) -> vec2<i32> {
+    let shifted = coords + vec2<i32>(i32(params.cfa_offset_x), i32(params.cfa_offset_y));
+    return vec2<i32>(shifted.x & 1, shifted.y & 1);
+}
+
+// Bilinear demosaic: missing channels are the average of the 2 or 4 nearest
+// same-color neighbors
+fn debayer_bilinear(coords: vec2<i32>, dimensions: vec2<u32>) -> vec3<f32> {
+    let c = get_neighbor(coords, dimensions);
+    let cross = (get_neighbor(coords + vec2<i32>(-1, 0), dimensions)
+               + get_neighbor(coords + vec2<i32>(1, 0), dimensions)
+               + get_neighbor(coords + vec2<i32>(0, -1), dimensions)
+               + get_neighbor(coords + vec2<i32>(0, 1), dimensions)) * 0.25;
+    let diagonal = (get_neighbor(coords + vec2<i32>(-1, -1), dimensions)
+                  + get_neighbor(coords + vec2<i32>(1, -1), dimensions)
+                  + get_neighbor(coords + vec2<i32>(-1, 1), dimensions)
+                  + get_neighbor(coords + vec2<i32>(1, 1), dimensions)) * 0.25;
+    let horizontal = (get_neighbor(coords + vec2<i32>(-1, 0), dimensions)
+                    + get_neighbor(coords + vec2<i32>(1, 0), dimensions)) * 0.5;
+    let vertical = (get_neighbor(coords + vec2<i32>(0, -1), dimensions)
+                  + get_neighbor(coords + vec2<i32>(0, 1), dimensions)) * 0.5;
+    
+    let pos = cfa_position(coords);
+    if pos.x == 0 && pos.y == 0 {
+        return vec3<f32>(c, cross, diagonal);       // Red pixel
+    } else if pos.x == 1 && pos.y == 1 {
+        return vec3<f32>(diagonal, cross, c);       // Blue pixel
+    } else if pos.y == 0 {
+        return vec3<f32>(horizontal, c, vertical);  // Green pixel, red row
+    } else {
+        return vec3<f32>(vertical, c, horizontal);  // Green pixel, blue row
+    }
+}
+
+// Malvar-He-Cutler demosaic: bilinear plus a Laplacian correction from the
+// known channel at this pixel (5x5 kernels, weights in eighths)
+fn debayer_malvar(coords: vec2<i32>, dimensions: vec2<u32>) -> vec3<f32> {
+    let c = get_neighbor(coords, dimensions);
+    let n1 = get_neighbor(coords + vec2<i32>(0, -1), dimensions)
+           + get_neighbor(coords + vec2<i32>(0, 1), dimensions);
+    let w1 = get_neighbor(coords + vec2<i32>(-1, 0), dimensions)
+           + get_neighbor(coords + vec2<i32>(1, 0), dimensions);
+    let n2 = get_neighbor(coords + vec2<i32>(0, -2), dimensions)
+           + get_neighbor(coords + vec2<i32>(0, 2), dimensions);
+    let w2 = get_neighbor(coords + vec2<i32>(-2, 0), dimensions)
+           + get_neighbor(coords + vec2<i32>(2, 0), dimensions);
+    let diagonal = get_neighbor(coords + vec2<i32>(-1, -1), dimensions)
+                 + get_neighbor(coords + vec2<i32>(1, -1), dimensions)
+                 + get_neighbor(coords + vec2<i32>(-1, 1), dimensions)
+                 + get_neighbor(coords + vec2<i32>(1, 1), dimensions);
+    
+    // G at R/B: 4C + 2(N+S+E+W) - (N2+S2+E2+W2)
+    let green_at_rb = (4.0 * c + 2.0 * (n1 + w1) - (n2 + w2)) / 8.0;
+    // R/B at B/R: 6C + 2(diagonals) - 3/2(N2+S2+E2+W2)
+    let opposite_at_rb = (6.0 * c + 2.0 * diagonal - 1.5 * (n2 + w2)) / 8.0;
+    // R/B at G, same-row neighbors: 5C + 4(E+W) - (E2+W2) - diagonals + 1/2(N2+S2)
+    let row_at_g = (5.0 * c + 4.0 * w1 - w2 - diagonal + 0.5 * n2) / 8.0;
+    // R/B at G, same-column neighbors: the transposed kernel
+    let col_at_g = (5.0 * c + 4.0 * n1 - n2 - diagonal + 0.5 * w2) / 8.0;
+    
+    let pos = cfa_position(coords);
     var rgb: vec3<f32>;
-    
-    // GBRG pattern (Green-Blue-Red-Green):
-    // G B G B ...
-    // R G R G ...
-    // G B G B ...
-    // Alternative Nikon pattern (trying this one)
-    
-    if is_even_row {
-        if is_even_col {
-            // Green pixel (blue row) - sample blue right, red below
-            let g = normalized;
-            let b = get_neighbor(coords + vec2<i32>(1, 0), dimensions);  // Blue to the right
-            let r = get_neighbor(coords + vec2<i32>(0, 1), dimensions);  // Red below
-            rgb = vec3<f32>(r, g, b);
-        } else {
-            // Blue pixel - sample green from neighbors, red from diagonal
-            let b = normalized;
-            let g = get_neighbor(coords - vec2<i32>(1, 0), dimensions);  // Green to the left
-            let r = get_neighbor(coords + vec2<i32>(-1, 1), dimensions);  // Red diagonal
-            rgb = vec3<f32>(r, g, b);
-        }
+    if pos.x == 0 && pos.y == 0 {
+        rgb = vec3<f32>(c, green_at_rb, opposite_at_rb);  // Red pixel
+    } else if pos.x == 1 && pos.y == 1 {
+        rgb = vec3<f32>(opposite_at_rb, green_at_rb, c);  // Blue pixel
+    } else if pos.y == 0 {
+        rgb = vec3<f32>(row_at_g, c, col_at_g);           // Green pixel, red row
     } else {
-        if is_even_col {
-            // Red pixel - sample green from neighbors, blue from diagonal
-            let r = normalized;
-            let g = get_neighbor(coords + vec2<i32>(1, 0), dimensions); // Green to the right
-            let b = get_neighbor(coords + vec2<i32>(0, -1), dimensions);  // Blue above
-            rgb = vec3<f32>(r, g, b);
-        } else {
-            // Green pixel (red row) - sample red left, blue above
-            let g = normalized;
-            let r = get_neighbor(coords - vec2<i32>(1, 0), dimensions);  // Red to the left
-            let b = get_neighbor(coords + vec2<i32>(0, -1), dimensions);  // Blue above
-            rgb = vec3<f32>(r, g, b);
-        }
+        rgb = vec3<f32>(col_at_g, c, row_at_g);           // Green pixel, blue row
     }
     
-    return rgb;
+    // The correction term can overshoot below zero on hard edges
+    return max(rgb, vec3<f32>(0.0));
+}
+
+fn debayer(coords: vec2<i32>, dimensions: vec2<u32>) -> vec3<f32> {
+    if params.demosaic_mode > 0.5 {
+        return debayer_malvar(coords, dimensions);
+    }
+    return debayer_bilinear(coords, dimensions);
 }
 
 // Helper to safely load neighbor pixel
+// Out-of-bounds coordinates are mirrored back into the image (without repeating
+// the edge pixel), which keeps the Bayer parity so the sample is the same color
 fn get_neighbor(coords: vec2<i32>, dimensions: vec2<u32>) -> f32 {
-    // Clamp to texture bounds
-    let clamped = vec2<i32>(
-        clamp(coords.x, 0, i32(dimensions.x) - 1),
-        clamp(coords.y, 0, i32(dimensions.y) - 1)
-    );
+    let max_coords = vec2<i32>(dimensions) - vec2<i32>(1);
+    var mirrored = abs(coords);
+    mirrored = min(mirrored, 2 * max_coords - mirrored);
+    let clamped = clamp(mirrored, vec2<i32>(0), max_coords);
     let raw_value = textureLoad(input_texture, clamped, 0).r;
-    return f32(raw_value) / 4096.0;
+    // Black -> 0.0, white -> 1.0 whatever the bit depth (noise below black clamps to 0)
+    return max((f32(raw_value) - params.black_level) / (params.white_level - params.black_level), 0.0);
 }
 
-@fragment
-fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+// Highlight reconstruction. White balance scales the channels by different
+// amounts, so once the sensor clips they stop at different levels and blown
+// areas turn magenta (green clips first). Where a channel reads at or near
+// the white level, replace it with the average of the channels that still
+// hold data - never darker than it reads, since the true value was at least
+// that bright. Where all three clipped, fall back to the brightest so the
+// highlight comes out neutral.
+// `color` is white-balanced, `camera` the same pixel before white balance
+// (1.0 = white level).
+fn recover_highlights(color: vec3<f32>, camera: vec3<f32>) -> vec3<f32> {
+    let clipped = smoothstep(vec3<f32>(0.9), vec3<f32>(1.0), camera);
+    if max(clipped.r, max(clipped.g, clipped.b)) <= 0.0 {
+        return color;
+    }
+    let valid = vec3<f32>(1.0) - clipped;
+    let total = valid.r + valid.g + valid.b;
+    let average = dot(color, valid) / max(total, 0.0001);
+    let brightest = max(color.r, max(color.g, color.b));
+    // Blend towards the brightest as the last channel clips (no seam)
+    let reference = mix(brightest, average, min(total, 1.0));
+    let rebuilt = max(color, vec3<f32>(reference));
+    return mix(color, rebuilt, clipped * params.highlight_recovery);
+}
+
+// Camera-space luminance of the 2x2 tile at coords (any 2x2 Bayer window
+// holds one R, two G and one B, so no CFA alignment is needed)
+fn tile_luma(coords: vec2<i32>, dimensions: vec2<u32>) -> f32 {
+    return (get_neighbor(coords, dimensions)
+          + get_neighbor(coords + vec2<i32>(1, 0), dimensions)
+          + get_neighbor(coords + vec2<i32>(0, 1), dimensions)
+          + get_neighbor(coords + vec2<i32>(1, 1), dimensions)) * 0.25;
+}
+
+// Local detail for clarity: log2 of this pixel's luminance over the average of
+// its neighborhood (two rings of 8 taps, the outer one ~1% of the image size).
+// A ratio of linear values doesn't care about white balance or exposure, so it
+// can be taken straight from the mosaic in the same pass.
+fn local_detail(coords: vec2<i32>, dimensions: vec2<u32>) -> f32 {
+    let radius = max(f32(max(dimensions.x, dimensions.y)) * 0.01, 2.0);
+    var sum = 0.0;
+    for (var i = 0; i < 8; i++) {
+        let angle = f32(i) * 0.7853982;  // 45° steps
+        let direction = vec2<f32>(cos(angle), sin(angle));
+        sum += tile_luma(coords + vec2<i32>(round(direction * radius)), dimensions);
+        sum += tile_luma(coords + vec2<i32>(round(direction * radius * 0.5)), dimensions);
+    }
+    let blurred = sum / 16.0;
+    let center = tile_luma(coords, dimensions);
+    return clamp(log2((center + 0.001) / (blurred + 0.001)), -2.0, 2.0);
+}
+
+// Noise reduction on debayered camera RGB: an edge-aware (bilateral) average of
+// the 5x5 neighborhood, split into luminance and chroma (color minus luminance,
+// like Cb/Cr) so each can be smoothed by its own amount. Neighbors use bilinear
+// debayering whatever the demosaic mode - they are averaged anyway.
+fn denoise(color: vec3<f32>, coords: vec2<i32>, dimensions: vec2<u32>) -> vec3<f32> {
+    let weights = vec3<f32>(0.25, 0.5, 0.25);
+    let center_luma = dot(color, weights);
+    // Sensor noise grows with the square root of the signal, so the edge
+    // threshold does too; chroma tolerates larger steps than luminance
+    let noise = sqrt(max(center_luma, 0.0001)) * 0.1;
+    let luma_sigma = noise * (0.5 + params.luma_nr * 1.5);
+    let chroma_sigma = noise * (1.0 + params.chroma_nr * 3.0);
+    
+    var luma_sum = 0.0;
+    var luma_weight = 0.0;
+    var chroma_sum = vec3<f32>(0.0);
+    var chroma_weight = 0.0;
+    for (var dy = -2; dy <= 2; dy++) {
+        for (var dx = -2; dx <= 2; dx++) {
+            let neighbor = debayer_bilinear(coords + vec2<i32>(dx, dy), dimensions);
+            let luma = dot(neighbor, weights);
+            let difference = luma - center_luma;
+            let spatial = exp(-f32(dx * dx + dy * dy) / 8.0);
+            let w_luma = spatial * exp(-difference * difference / (2.0 * luma_sigma * luma_sigma));
+            let w_chroma = spatial * exp(-difference * difference / (2.0 * chroma_sigma * chroma_sigma));
+            luma_sum += luma * w_luma;
+            luma_weight += w_luma;
+            chroma_sum += (neighbor - vec3<f32>(luma)) * w_chroma;
+            chroma_weight += w_chroma;
+        }
+    }
+    
+    let luma = mix(center_luma, luma_sum / luma_weight, params.luma_nr);
+    let chroma = mix(color - vec3<f32>(center_luma), chroma_sum / chroma_weight, params.chroma_nr);
+    return chroma + vec3<f32>(luma);
+}
+
+// High-pass for capture sharpening: this pixel's luminance minus a Gaussian
+// blur of it (sigma = sharpen_radius, 5x5 taps spread out for larger radii)
+fn sharpen_detail(color: vec3<f32>, coords: vec2<i32>, dimensions: vec2<u32>) -> f32 {
+    let weights = vec3<f32>(0.25, 0.5, 0.25);
+    let sigma = clamp(params.sharpen_radius, 0.5, 3.0);
+    let spacing = max(round(sigma * 0.75), 1.0);
+    var sum = 0.0;
+    var total = 0.0;
+    for (var dy = -2; dy <= 2; dy++) {
+        for (var dx = -2; dx <= 2; dx++) {
+            let offset = vec2<f32>(f32(dx), f32(dy)) * spacing;
+            let w = exp(-dot(offset, offset) / (2.0 * sigma * sigma));
+            sum += dot(debayer_bilinear(coords + vec2<i32>(offset), dimensions), weights) * w;
+            total += w;
+        }
+    }
+    return dot(color, weights) - sum / total;
+}
+
+// RGB (0-1) to HSL: hue in degrees (0-360), saturation and lightness 0-1
+fn rgb_to_hsl(c: vec3<f32>) -> vec3<f32> {
+    let max_c = max(c.r, max(c.g, c.b));
+    let min_c = min(c.r, min(c.g, c.b));
+    let l = (max_c + min_c) * 0.5;
+    let d = max_c - min_c;
+    if d < 0.00001 {
+        return vec3<f32>(0.0, 0.0, l);  // Gray: no hue
+    }
+    let s = d / (1.0 - abs(2.0 * l - 1.0) + 0.00001);
+    var h: f32;
+    if max_c == c.r {
+        h = (c.g - c.b) / d;
+        if h < 0.0 {
+            h += 6.0;
+        }
+    } else if max_c == c.g {
+        h = (c.b - c.r) / d + 2.0;
+    } else {
+        h = (c.r - c.g) / d + 4.0;
+    }
+    return vec3<f32>(h * 60.0, clamp(s, 0.0, 1.0), l);
+}
+
+fn hsl_to_rgb(hsl: vec3<f32>) -> vec3<f32> {
+    let c = (1.0 - abs(2.0 * hsl.z - 1.0)) * hsl.y;
+    let h = hsl.x / 60.0;
+    let x = c * (1.0 - abs(h % 2.0 - 1.0));
+    var rgb: vec3<f32>;
+    if h < 1.0 {
+        rgb = vec3<f32>(c, x, 0.0);
+    } else if h < 2.0 {
+        rgb = vec3<f32>(x, c, 0.0);
+    } else if h < 3.0 {
+        rgb = vec3<f32>(0.0, c, x);
+    } else if h < 4.0 {
+        rgb = vec3<f32>(0.0, x, c);
+    } else if h < 5.0 {
+        rgb = vec3<f32>(x, 0.0, c);
+    } else {
+        rgb = vec3<f32>(c, 0.0, x);
+    }
+    return rgb + vec3<f32>(hsl.z - c * 0.5);
+}
+
+// HSL adjustment for a hue: linear blend of the two bands whose centers
+// surround it, so there are no hard steps at band boundaries
+fn hsl_adjustment(hue: f32) -> vec3<f32> {
+    var centers = array<f32, 8>(0.0, 30.0, 60.0, 120.0, 180.0, 240.0, 270.0, 300.0);
+    var lower = 0;
+    for (var i = 1; i < 8; i++) {
+        if hue >= centers[i] {
+            lower = i;
+        }
+    }
+    let upper = (lower + 1) % 8;
+    var span = centers[upper] - centers[lower];
+    if upper == 0 {
+        span = 360.0 - centers[lower];  // Magenta wraps around to red
+    }
+    let t = (hue - centers[lower]) / span;
+    return mix(params.hsl[lower].xyz, params.hsl[upper].xyz, t);
+}
+
+// Map output coordinates (0-1 over the cropped image) to source texture coordinates:
+// remap onto the crop rectangle, then rotate around the center in pixel space
+fn apply_geometry(tex_coords: vec2<f32>, size: vec2<f32>) -> vec2<f32> {
+    let frame = vec2<f32>(params.crop_x, params.crop_y) + tex_coords * vec2<f32>(params.crop_w, params.crop_h);
+    let centered = (frame - 0.5) * size;
+    let c = cos(params.rotation);
+    let s = sin(params.rotation);
+    let rotated = vec2<f32>(c * centered.x + s * centered.y, -s * centered.x + c * centered.y);
+    return rotated / size + 0.5;
+}
+
+// Squared radius of upright coordinates (0-1), 1.0 at the corners
+fn lens_radius2(coords: vec2<f32>, size: vec2<f32>) -> f32 {
+    let offset = (coords - 0.5) * size / (length(size) * 0.5);
+    return dot(offset, offset);
+}
+
+// Lens distortion: map a point of the corrected frame to where the lens
+// actually imaged it (radially, around the center)
+fn lens_distort(coords: vec2<f32>, size: vec2<f32>) -> vec2<f32> {
+    let r2 = lens_radius2(coords, size);
+    let scale = 1.0 + params.lens_k1 * r2 + params.lens_k2 * r2 * r2;
+    return (coords - 0.5) * scale + 0.5;
+}
+
+// Gain that undoes the lens's vignetting at upright coordinates (as imaged)
+fn lens_vignette_gain(coords: vec2<f32>, size: vec2<f32>) -> f32 {
+    let r2 = lens_radius2(coords, size);
+    let falloff = 1.0 + params.lens_v1 * r2 + params.lens_v2 * r2 * r2;
+    return 1.0 / max(falloff, 0.1);
+}
+
+// Map upright coordinates (0-1) back to the sensor texture: undo the
+// rotation one counter-clockwise quarter turn at a time, then the mirror
+// (same as Orientation::to_source)
+fn orient_to_source(coords: vec2<f32>) -> vec2<f32> {
+    var c = coords;
+    let turns = i32(params.orientation_turns + 0.5);
+    for (var i = 0; i < turns; i++) {
+        c = vec2<f32>(c.y, 1.0 - c.x);
+    }
+    if params.orientation_mirror > 0.5 {
+        c.x = 1.0 - c.x;
+    }
+    return c;
+}
+
+// How strongly mask `index` applies at a point of the straightened frame (0-1):
+// radial = 1 inside the ellipse centered on start with a corner at end, fading
+// over the feather; linear = 1 behind start, 0 past end
+fn mask_weight(index: i32, frame: vec2<f32>) -> f32 {
+    let points = params.masks[index * 3];
+    let shape = params.masks[index * 3 + 1];
+    let offset = frame - points.xy;
+    if shape.x > 0.5 {
+        let gradient = points.zw - points.xy;
+        let along = dot(offset, gradient) / max(dot(gradient, gradient), 0.00000001);
+        return 1.0 - smoothstep(0.0, 1.0, along);
+    }
+    let radii = max(abs(points.zw - points.xy), vec2<f32>(0.0001));
+    let distance = length(offset / radii);
+    let inner = 1.0 - clamp(shape.y, 0.0, 1.0);
+    return 1.0 - smoothstep(min(inner, 0.9999), 1.0, distance);
+}
+
+// Full processing chain for one output pixel, shared by both fragment entry points
+fn process_pixel(tex_coords: vec2<f32>) -> vec4<f32> {
     // Phase 25: Discard fragments outside texture bounds (when zoomed out)
-    if input.tex_coords.x < 0.0 || input.tex_coords.x > 1.0 ||
-       input.tex_coords.y < 0.0 || input.tex_coords.y > 1.0 {
+    if tex_coords.x < 0.0 || tex_coords.x > 1.0 ||
+       tex_coords.y < 0.0 || tex_coords.y > 1.0 {
         // Return black for areas outside the image
         return vec4<f32>(0.0, 0.0, 0.0, 1.0);
     }
@@ -180,10 +578,27 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
     // Get texture dimensions
     let dimensions = textureDimensions(input_texture);
     
+    // Crop & straighten in upright space (corners rotated in from outside the
+    // sensor stay black), then turn back to the stored sensor orientation
+    var upright_size = vec2<f32>(dimensions);
+    if i32(params.orientation_turns + 0.5) % 2 == 1 {
+        upright_size = upright_size.yx;
+    }
+    // Lens correction comes first, so the crop frames the corrected image
+    var upright = apply_geometry(tex_coords, upright_size);
+    if params.lens_correction > 0.5 {
+        upright = lens_distort(upright, upright_size);
+    }
+    let source = orient_to_source(upright);
+    if source.x < 0.0 || source.x >= 1.0 ||
+       source.y < 0.0 || source.y >= 1.0 {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+    
     // Convert normalized texture coordinates to pixel coordinates
     let pixel_coords = vec2<i32>(
-        i32(input.tex_coords.x * f32(dimensions.x)),
-        i32(input.tex_coords.y * f32(dimensions.y))
+        i32(source.x * f32(dimensions.x)),
+        i32(source.y * f32(dimensions.y))
     );
     
     // Phase 14: Color Science Pipeline (in correct order!)
@@ -191,9 +606,42 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
     // 1. Debayer to get RAW RGB color (still in linear camera space)
     var color = debayer(pixel_coords, dimensions);
     
+    // 1.5. Noise Reduction (skipped entirely at zero so clean images cost nothing)
+    if params.luma_nr > 0.0 || params.chroma_nr > 0.0 {
+        color = denoise(color, pixel_coords, dimensions);
+    }
+    
+    // 1.6. Capture Sharpening (unsharp mask on luminance, skipped at zero)
+    if params.sharpen_amount > 0.0 || params.detail_preview > 0.5 {
+        let detail = sharpen_detail(color, pixel_coords, dimensions);
+        if params.detail_preview > 0.5 {
+            // Detail view: mid-gray with the high-pass on top
+            let gray = clamp(0.5 + detail * 4.0, 0.0, 1.0);
+            return vec4<f32>(gray, gray, gray, 1.0);
+        }
+        let luma = dot(color, vec3<f32>(0.25, 0.5, 0.25));
+        // Limit the overshoot to half the local level (no bright/dark halos)
+        // and fade out towards clipping so blown highlights don't ring
+        let limited = clamp(detail, -0.5 * luma, 0.5 * luma);
+        let protect = 1.0 - smoothstep(0.7, 1.0, max(color.r, max(color.g, color.b)));
+        let sharpened = max(luma + params.sharpen_amount * limited * protect, 0.0);
+        color *= sharpened / max(luma, 0.00001);
+    }
+    
     // 2. Apply White Balance (normalize sensor response)
+    let camera = color;
     color = color * params.wb_multipliers.rgb;
     
+    // 2.1. Highlight Recovery (clipped channels, skipped at zero)
+    if params.highlight_recovery > 0.0 {
+        color = recover_highlights(color, camera);
+    }
+    
+    // 2.2. Lens vignetting (after clip detection: brightened corners aren't clipped)
+    if params.lens_correction > 0.5 {
+        color *= lens_vignette_gain(upright, upright_size);
+    }
+    
     // 2.5. Apply Manual White Balance (Phase 18: Temperature & Tint)
     // Temperature: Blue/Yellow axis (cooler/warmer)
     // Scale by 0.3 for noticeable but not extreme adjustments
@@ -205,18 +653,51 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
     color.g = color.g * (1.0 + params.tint * 0.3);
     
     // 3. Apply Color Matrix (camera RGB → sRGB color space)
-    // Reconstruct 3x3 matrix from padded vec3 rows
-    let color_matrix = mat3x3<f32>(
-        params.color_matrix_0,
-        params.color_matrix_1,
-        params.color_matrix_2
+    // The uniforms hold ROWS; mat3x3(a, b, c) would treat them as columns and
+    // apply the transpose, so do the row · color dot products directly
+    color = vec3<f32>(
+        dot(params.color_matrix_0, color),
+        dot(params.color_matrix_1, color),
+        dot(params.color_matrix_2, color)
     );
-    color = color_matrix * color;
+    
+    // 3.2. Film negative: dividing by the film base cancels the orange mask,
+    // then the inversion turns the clear base black and dense areas bright
+    if params.invert > 0.5 {
+        color = max(1.0 - color / max(params.film_base, vec3<f32>(0.0001)), vec3<f32>(0.0));
+    }
+    
+    // 3.5. Black & White conversion (channel mixer, before any tone work)
+    if params.monochrome > 0.5 {
+        color = vec3<f32>(max(dot(color, params.bw_mix), 0.0));
+    }
+    
+    // 3.9. Local adjustments: sum each mask's (exposure, contrast, saturation)
+    // deltas by its weight here; they add to the global sliders below.
+    // Without masks the loop never runs
+    var local_deltas = vec3<f32>(0.0);
+    let mask_count = i32(params.mask_count + 0.5);
+    if mask_count > 0 {
+        let frame = vec2<f32>(params.crop_x, params.crop_y) + tex_coords * vec2<f32>(params.crop_w, params.crop_h);
+        for (var i = 0; i < mask_count; i++) {
+            local_deltas += mask_weight(i, frame) * params.masks[i * 3 + 2].xyz;
+        }
+    }
     
     // 4. Apply Exposure (still in linear space)
-    let exposure_multiplier = pow(2.0, params.exposure);
+    let exposure_multiplier = pow(2.0, params.exposure + local_deltas.x);
     color = color * exposure_multiplier;
     
+    // 4.5. Apply Clarity (local contrast, strongest in the midtones)
+    // Scales the pixel's deviation from its neighborhood in log space:
+    // +1 doubles it, -1 flattens it out
+    if abs(params.clarity) > 0.0001 {
+        let detail = local_detail(pixel_coords, dimensions);
+        let display_lum = srgb_encode(vec3<f32>(clamp(dot(color, vec3<f32>(0.2126, 0.7152, 0.0722)), 0.0, 1.0))).x;
+        let midtones = 1.0 - pow(2.0 * display_lum - 1.0, 2.0);
+        color = color * exp2(params.clarity * detail * midtones);
+    }
+    
     // 5. Apply Highlights & Shadows (Phase 17: Smart Tone - Luminance-weighted adjustments)
     // Calculate luminance to determine which pixels are bright vs dark
     let lum_for_tone = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
@@ -229,20 +710,44 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
     // Positive values lift shadows, negative values crush them
     color = color * (1.0 + ((1.0 - lum_for_tone) * params.shadows));
     
-    // 6. Apply Contrast (around midpoint 0.5)
-    let contrast_factor = 1.0 + (params.contrast / 100.0);
-    color = (color - 0.5) * contrast_factor + 0.5;
+    // 6. Apply Contrast (around midpoint 0.5, shaped by the tone mode)
+    color = apply_tone(color, params.contrast + local_deltas.y);
     
     // 7. Apply Levels (Phase 16: Whites & Blacks tone control)
     // Standard levels formula: (color - black_point) / (white_point - black_point)
     // This controls the dynamic range by remapping black and white points
     color = (color - vec3<f32>(params.blacks)) / (vec3<f32>(params.whites - params.blacks + 0.0001));
     
+    // 7.5. Apply Tone Curve (defined in display space, like Lightroom)
+    // Skipped entirely for the identity curve
+    if params.curve_enabled > 0.5 {
+        let display = srgb_encode(color);
+        let curved = vec3<f32>(
+            apply_curve(display.r),
+            apply_curve(display.g),
+            apply_curve(display.b)
+        );
+        color = srgb_decode(curved);
+    }
+    
+    // 7.8. Split Toning: blend from the shadow tint to the highlight tint by
+    // display brightness (balance shifts the crossover), and keep only the
+    // tint's color - its luminance is subtracted, so tones don't move. Scaled
+    // by the pixel's luminance, so black stays black
+    if params.split_tone_enabled > 0.5 {
+        let tone_luma = dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+        let display_luma = srgb_encode(vec3<f32>(clamp(tone_luma, 0.0, 1.0))).x;
+        let highlight_weight = smoothstep(0.0, 1.0, clamp(display_luma + params.balance * 0.5, 0.0, 1.0));
+        let tint = mix(params.shadow_tint, params.highlight_tint, highlight_weight);
+        let shift = tint - vec3<f32>(dot(tint, vec3<f32>(0.2126, 0.7152, 0.0722)));
+        color = max(color + shift * max(tone_luma, 0.0), vec3<f32>(0.0));
+    }
+    
     // 8. Apply Saturation (Phase 15 color boost)
     // Calculate luminance using Rec. 709 coefficients
     var luma = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
     // Saturation factor: -100 = grayscale, 0 = original, +100 = 2x saturation
-    let sat_factor = 1.0 + (params.saturation / 100.0);
+    let sat_factor = 1.0 + ((params.saturation + local_deltas.z) / 100.0);
     // Mix between grayscale and original color
     color = mix(vec3<f32>(luma), color, sat_factor);
     
@@ -256,15 +761,122 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
     luma = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
     color = mix(vec3<f32>(luma), color, 1.0 + vibrance_amount);
     
+    // 9.5. Apply Vignette (post-crop: tex_coords already span the cropped frame)
+    if params.vignette_amount != 0.0 {
+        // 0 at the center, 1 in the corners (elliptical, follows the frame shape)
+        let radius = length(tex_coords - vec2<f32>(0.5)) * 1.41421356;
+        let start = min(params.vignette_midpoint, 0.99);
+        let falloff = smoothstep(start, 1.0, radius);
+        color *= max(1.0 + params.vignette_amount * falloff, 0.0);
+    }
+    
     // 10. Apply sRGB Gamma Correction (linear → sRGB for display)
     // This is critical for proper brightness perception!
-    color = pow(color, vec3<f32>(1.0 / 2.2));
+    color = srgb_encode(color);
     
     // 11. Clamp to valid range
     color = clamp(color, vec3<f32>(0.0), vec3<f32>(1.0));
     
+    // 12. Apply HSL bands (display space, skipped when every band is zero)
+    if params.hsl_enabled > 0.5 {
+        var hsl = rgb_to_hsl(color);
+        if hsl.y > 0.0001 {
+            let adjustment = hsl_adjustment(hsl.x);
+            let colorfulness = hsl.y;
+            // Hue: ±100 shifts by ±30°
+            hsl.x = fract((hsl.x + adjustment.x * 0.3) / 360.0) * 360.0;
+            // Saturation: -100 = gray, +100 = double
+            hsl.y = clamp(hsl.y * (1.0 + adjustment.y / 100.0), 0.0, 1.0);
+            // Luminance: scaled by how colorful the pixel is, so near-grays stay put
+            hsl.z = clamp(hsl.z * (1.0 + adjustment.z / 100.0 * 0.5 * colorfulness), 0.0, 1.0);
+            color = clamp(hsl_to_rgb(hsl), vec3<f32>(0.0), vec3<f32>(1.0));
+        }
+    }
+    
+    // 13. Clipping warnings (display overlay, never exported): any channel
+    // at full scale shows red, pixels crushed to pure black show blue
+    if params.show_clipping > 0.5 {
+        let brightest = max(color.r, max(color.g, color.b));
+        if brightest >= 1.0 {
+            return vec4<f32>(1.0, 0.0, 0.0, 1.0);
+        }
+        if brightest <= 0.0 {
+            return vec4<f32>(0.0, 0.0, 1.0, 1.0);
+        }
+    }
+    
     return vec4<f32>(color, 1.0);
 }
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    return process_pixel(input.tex_coords);
+}
+
+// Window surface in an sRGB format (see surface.rs): the surface encodes on
+// write, so it gets linear values or the image would be encoded twice
+@fragment
+fn fs_main_linear(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = process_pixel(input.tex_coords);
+    return vec4<f32>(srgb_decode(color.rgb), color.a);
+}
+
+// 16-bit export: same chain (already clamped and gamma-encoded), quantized
+// to the full u16 range for an Rgba16Uint target
+@fragment
+fn fs_main_16(input: VertexOutput) -> @location(0) vec4<u32> {
+    let color = process_pixel(input.tex_coords);
+    return vec4<u32>(round(color * 65535.0));
+}
+"#;
+
+/// Compute shader that bins a rendered RGBA8 image into a 4x256 histogram
+///
+/// Each workgroup counts its 16x16 tile into workgroup memory first, then
+/// adds the non-zero bins to the output buffer, so the global atomics see
+/// at most 1024 adds per tile instead of one per pixel and channel.
+/// Output layout: R[256], G[256], B[256], Luma[256] (u32 counts), where luma
+/// is the Rec. 709 weighted sum of the displayed (gamma-encoded) values.
+pub const HISTOGRAM_SHADER: &str = r#"
+@group(0) @binding(0) var rendered: texture_2d<f32>;
+@group(0) @binding(1) var<storage, read_write> bins: array<atomic<u32>, 1024>;
+
+var<workgroup> tile_bins: array<atomic<u32>, 1024>;
+
+// Rgba8Unorm reads back as k / 255, so this recovers the byte exactly
+fn bin_of(value: f32) -> u32 {
+    return min(u32(value * 255.0 + 0.5), 255u);
+}
+
+@compute @workgroup_size(16, 16)
+fn cs_histogram(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(local_invocation_index) local: u32,
+) {
+    // 256 invocations clear 1024 bins
+    for (var i = local; i < 1024u; i += 256u) {
+        atomicStore(&tile_bins[i], 0u);
+    }
+    workgroupBarrier();
+    
+    let size = textureDimensions(rendered);
+    if id.x < size.x && id.y < size.y {
+        let color = textureLoad(rendered, vec2<i32>(id.xy), 0);
+        atomicAdd(&tile_bins[bin_of(color.r)], 1u);
+        atomicAdd(&tile_bins[256u + bin_of(color.g)], 1u);
+        atomicAdd(&tile_bins[512u + bin_of(color.b)], 1u);
+        let luma = dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+        atomicAdd(&tile_bins[768u + bin_of(luma)], 1u);
+    }
+    workgroupBarrier();
+    
+    for (var i = local; i < 1024u; i += 256u) {
+        let count = atomicLoad(&tile_bins[i]);
+        if count > 0u {
+            atomicAdd(&bins[i], count);
+        }
+    }
+}
 "#;
 
 /// Get the shader source code for the current rendering mode
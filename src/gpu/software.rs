@@ -0,0 +1,700 @@
+/// CPU fallback renderer
+///
+/// Used when the system has no GPU adapter at all (headless machines, broken
+/// drivers), so Develop and export keep working, just slowly. It reads the
+/// same `GpuEditParams` the pipeline uploads as uniforms and runs a line-by-line
+/// Rust port of the shader's `process_pixel` (plus the vertex shader's
+/// zoom/pan/tile mapping), so both backends produce the same image. That also
+/// makes it a reference for the shader: `test_cpu_fallback_matches_shader` in
+/// pipeline.rs renders the same edits both ways and compares them.
+///
+/// Any change to the WGSL in shaders.rs has to be mirrored here. Rows are
+/// split across all cores; there's no other optimization.
+use std::sync::Mutex;
+
+use super::pipeline::GpuEditParams;
+use crate::color::{srgb_to_linear, linear_to_srgb};
+use crate::state::edit::{EditParams, CURVE_LUT_SIZE};
+
+type Vec3 = [f32; 3];
+
+/// Rec. 709 luminance weights
+const LUMA_709: Vec3 = [0.2126, 0.7152, 0.0722];
+/// Camera-space luminance weights used by noise reduction and sharpening
+const LUMA_CAMERA: Vec3 = [0.25, 0.5, 0.25];
+/// HSL band centers in degrees (red, orange, yellow, green, aqua, blue, purple, magenta)
+const HSL_CENTERS: [f32; 8] = [0.0, 30.0, 60.0, 120.0, 180.0, 240.0, 270.0, 300.0];
+
+fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn scale(v: Vec3, s: f32) -> Vec3 {
+    v.map(|c| c * s)
+}
+
+fn mix(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn mix3(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+    [mix(a[0], b[0], t), mix(a[1], b[1], t), mix(a[2], b[2], t)]
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// WGSL `fract` (x - floor(x), unlike `f32::fract` for negative values)
+fn fract(x: f32) -> f32 {
+    x - x.floor()
+}
+
+fn max3(c: Vec3) -> f32 {
+    c[0].max(c[1]).max(c[2])
+}
+
+fn min3(c: Vec3) -> f32 {
+    c[0].min(c[1]).min(c[2])
+}
+
+/// One image's RAW data and the uniforms of the last update
+pub(super) struct SoftwareRenderer {
+    raw: Vec<u16>,
+    width: u32,
+    height: u32,
+    uniforms: Mutex<GpuEditParams>,
+    /// Tone curve LUT (only read while `curve_enabled` is set)
+    curve_lut: Mutex<Vec<f32>>,
+}
+
+impl SoftwareRenderer {
+    pub(super) fn new(raw: Vec<u16>, width: u32, height: u32, uniforms: GpuEditParams, params: &EditParams) -> Self {
+        let renderer = Self {
+            raw,
+            width,
+            height,
+            uniforms: Mutex::new(uniforms),
+            curve_lut: Mutex::new(vec![0.0; CURVE_LUT_SIZE]),
+        };
+        renderer.set_uniforms(uniforms, params);
+        renderer
+    }
+
+    /// Counterpart of the uniform buffer write (and the LUT upload, skipped
+    /// for the identity curve like on the GPU)
+    pub(super) fn set_uniforms(&self, uniforms: GpuEditParams, params: &EditParams) {
+        *self.uniforms.lock().unwrap() = uniforms;
+        if !params.curve.is_identity() {
+            *self.curve_lut.lock().unwrap() = params.curve.to_lut().to_vec();
+        }
+    }
+
+    /// Copy a `size` x `size` square of raw values whose top-left pixel is `origin`
+    pub(super) fn raw_patch(&self, origin: (u32, u32), size: u32) -> Vec<u16> {
+        (origin.1..origin.1 + size)
+            .flat_map(|y| {
+                let start = (y * self.width + origin.0) as usize;
+                self.raw[start..start + size as usize].iter().copied()
+            })
+            .collect()
+    }
+
+    /// Render a `width` x `height` frame: RGBA8, or RGBA16 (native-endian
+    /// bytes, like the GPU readback) when `sixteen_bit`
+    pub(super) fn render(&self, width: u32, height: u32, sixteen_bit: bool) -> Vec<u8> {
+        let uniforms = *self.uniforms.lock().unwrap();
+        let curve_lut = self.curve_lut.lock().unwrap().clone();
+        self.render_frame(&uniforms, &curve_lut, width, height, sixteen_bit)
+    }
+
+    /// `render` with these uniforms instead of the last update's (exports,
+    /// which mustn't see the preview changing under them)
+    pub(super) fn render_with(&self, uniforms: GpuEditParams, params: &EditParams, width: u32, height: u32, sixteen_bit: bool) -> Vec<u8> {
+        let curve_lut = params.curve.to_lut();
+        self.render_frame(&uniforms, &curve_lut, width, height, sixteen_bit)
+    }
+
+    fn render_frame(&self, uniforms: &GpuEditParams, curve_lut: &[f32], width: u32, height: u32, sixteen_bit: bool) -> Vec<u8> {
+        let frame = Frame { renderer: self, params: uniforms, curve_lut };
+
+        let bytes_per_pixel = if sixteen_bit { 8 } else { 4 };
+        let row_bytes = width as usize * bytes_per_pixel;
+        let mut output = vec![0u8; row_bytes * height as usize];
+        if output.is_empty() {
+            return output;
+        }
+
+        // Bands of rows, one per core
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let rows_per_band = (height as usize).div_ceil(threads);
+        std::thread::scope(|scope| {
+            for (band, chunk) in output.chunks_mut(rows_per_band * row_bytes).enumerate() {
+                let frame = &frame;
+                scope.spawn(move || {
+                    for (i, row) in chunk.chunks_exact_mut(row_bytes).enumerate() {
+                        let y = (band * rows_per_band + i) as u32;
+                        for (x, pixel) in row.chunks_exact_mut(bytes_per_pixel).enumerate() {
+                            let color = frame.pixel(x as u32, y, width, height);
+                            if sixteen_bit {
+                                for (channel, value) in color.iter().enumerate() {
+                                    let value = (value * 65535.0).round() as u16;
+                                    pixel[channel * 2..channel * 2 + 2].copy_from_slice(&value.to_ne_bytes());
+                                }
+                            } else {
+                                for (channel, value) in color.iter().enumerate() {
+                                    pixel[channel] = (value * 255.0).round() as u8;
+                                }
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        output
+    }
+}
+
+/// Everything one render reads
+struct Frame<'a> {
+    renderer: &'a SoftwareRenderer,
+    params: &'a GpuEditParams,
+    curve_lut: &'a [f32],
+}
+
+impl Frame<'_> {
+    /// Output pixel (x, y) of a `width` x `height` render (`vs_main` plus `process_pixel`)
+    fn pixel(&self, x: u32, y: u32, width: u32, height: u32) -> [f32; 4] {
+        let p = self.params;
+        // Fragment centers, zoomed and panned around the middle
+        let mut tex_x = ((x as f32 + 0.5) / width as f32 - 0.5) / p.zoom - p.pan_x + 0.5;
+        let mut tex_y = ((y as f32 + 0.5) / height as f32 - 0.5) / p.zoom - p.pan_y + 0.5;
+        tex_x = p.tile_x + tex_x * p.tile_w;
+        tex_y = p.tile_y + tex_y * p.tile_h;
+        self.process_pixel([tex_x, tex_y])
+    }
+
+    fn apply_curve(&self, value: f32) -> f32 {
+        let x = value.clamp(0.0, 1.0) * 255.0;
+        let i = x.floor() as usize;
+        let j = (i + 1).min(255);
+        mix(self.curve_lut[i], self.curve_lut[j], fract(x))
+    }
+
+    fn cfa_position(&self, coords: (i32, i32)) -> (i32, i32) {
+        let x = coords.0 + self.params.cfa_offset_x as i32;
+        let y = coords.1 + self.params.cfa_offset_y as i32;
+        (x & 1, y & 1)
+    }
+
+    fn get_neighbor(&self, coords: (i32, i32)) -> f32 {
+        let renderer = self.renderer;
+        let mirror = |c: i32, max: i32| {
+            let m = c.abs();
+            m.min(2 * max - m).clamp(0, max)
+        };
+        let x = mirror(coords.0, renderer.width as i32 - 1);
+        let y = mirror(coords.1, renderer.height as i32 - 1);
+        let raw_value = renderer.raw[(y as u32 * renderer.width + x as u32) as usize];
+        ((raw_value as f32 - self.params.black_level) / (self.params.white_level - self.params.black_level)).max(0.0)
+    }
+
+    fn at(&self, coords: (i32, i32), dx: i32, dy: i32) -> f32 {
+        self.get_neighbor((coords.0 + dx, coords.1 + dy))
+    }
+
+    fn debayer_bilinear(&self, coords: (i32, i32)) -> Vec3 {
+        let c = self.at(coords, 0, 0);
+        let cross = (self.at(coords, -1, 0) + self.at(coords, 1, 0) + self.at(coords, 0, -1) + self.at(coords, 0, 1)) * 0.25;
+        let diagonal =
+            (self.at(coords, -1, -1) + self.at(coords, 1, -1) + self.at(coords, -1, 1) + self.at(coords, 1, 1)) * 0.25;
+        let horizontal = (self.at(coords, -1, 0) + self.at(coords, 1, 0)) * 0.5;
+        let vertical = (self.at(coords, 0, -1) + self.at(coords, 0, 1)) * 0.5;
+
+        match self.cfa_position(coords) {
+            (0, 0) => [c, cross, diagonal],
+            (1, 1) => [diagonal, cross, c],
+            (_, 0) => [horizontal, c, vertical],
+            _ => [vertical, c, horizontal],
+        }
+    }
+
+    fn debayer_malvar(&self, coords: (i32, i32)) -> Vec3 {
+        let c = self.at(coords, 0, 0);
+        let n1 = self.at(coords, 0, -1) + self.at(coords, 0, 1);
+        let w1 = self.at(coords, -1, 0) + self.at(coords, 1, 0);
+        let n2 = self.at(coords, 0, -2) + self.at(coords, 0, 2);
+        let w2 = self.at(coords, -2, 0) + self.at(coords, 2, 0);
+        let diagonal = self.at(coords, -1, -1) + self.at(coords, 1, -1) + self.at(coords, -1, 1) + self.at(coords, 1, 1);
+
+        let green_at_rb = (4.0 * c + 2.0 * (n1 + w1) - (n2 + w2)) / 8.0;
+        let opposite_at_rb = (6.0 * c + 2.0 * diagonal - 1.5 * (n2 + w2)) / 8.0;
+        let row_at_g = (5.0 * c + 4.0 * w1 - w2 - diagonal + 0.5 * n2) / 8.0;
+        let col_at_g = (5.0 * c + 4.0 * n1 - n2 - diagonal + 0.5 * w2) / 8.0;
+
+        let rgb = match self.cfa_position(coords) {
+            (0, 0) => [c, green_at_rb, opposite_at_rb],
+            (1, 1) => [opposite_at_rb, green_at_rb, c],
+            (_, 0) => [row_at_g, c, col_at_g],
+            _ => [col_at_g, c, row_at_g],
+        };
+        rgb.map(|v| v.max(0.0))
+    }
+
+    fn debayer(&self, coords: (i32, i32)) -> Vec3 {
+        if self.params.demosaic_mode > 0.5 {
+            return self.debayer_malvar(coords);
+        }
+        self.debayer_bilinear(coords)
+    }
+
+    fn recover_highlights(&self, color: Vec3, camera: Vec3) -> Vec3 {
+        let clipped = camera.map(|c| smoothstep(0.9, 1.0, c));
+        if max3(clipped) <= 0.0 {
+            return color;
+        }
+        let valid = clipped.map(|c| 1.0 - c);
+        let total = valid[0] + valid[1] + valid[2];
+        let average = dot(color, valid) / total.max(0.0001);
+        let brightest = max3(color);
+        let reference = mix(brightest, average, total.min(1.0));
+        let mut result = color;
+        for channel in 0..3 {
+            let rebuilt = color[channel].max(reference);
+            result[channel] = mix(color[channel], rebuilt, clipped[channel] * self.params.highlight_recovery);
+        }
+        result
+    }
+
+    fn tile_luma(&self, coords: (i32, i32)) -> f32 {
+        (self.at(coords, 0, 0) + self.at(coords, 1, 0) + self.at(coords, 0, 1) + self.at(coords, 1, 1)) * 0.25
+    }
+
+    fn local_detail(&self, coords: (i32, i32)) -> f32 {
+        let renderer = self.renderer;
+        let radius = (renderer.width.max(renderer.height) as f32 * 0.01).max(2.0);
+        let mut sum = 0.0;
+        for i in 0..8 {
+            let angle = i as f32 * std::f32::consts::FRAC_PI_4;
+            let (dx, dy) = (angle.cos(), angle.sin());
+            // WGSL `round` rounds halves to even
+            let outer = ((dx * radius).round_ties_even() as i32, (dy * radius).round_ties_even() as i32);
+            let inner = ((dx * radius * 0.5).round_ties_even() as i32, (dy * radius * 0.5).round_ties_even() as i32);
+            sum += self.tile_luma((coords.0 + outer.0, coords.1 + outer.1));
+            sum += self.tile_luma((coords.0 + inner.0, coords.1 + inner.1));
+        }
+        let blurred = sum / 16.0;
+        let center = self.tile_luma(coords);
+        ((center + 0.001) / (blurred + 0.001)).log2().clamp(-2.0, 2.0)
+    }
+
+    fn denoise(&self, color: Vec3, coords: (i32, i32)) -> Vec3 {
+        let p = self.params;
+        let center_luma = dot(color, LUMA_CAMERA);
+        let noise = center_luma.max(0.0001).sqrt() * 0.1;
+        let luma_sigma = noise * (0.5 + p.luma_nr * 1.5);
+        let chroma_sigma = noise * (1.0 + p.chroma_nr * 3.0);
+
+        let mut luma_sum = 0.0;
+        let mut luma_weight = 0.0;
+        let mut chroma_sum = [0.0; 3];
+        let mut chroma_weight = 0.0;
+        for dy in -2..=2 {
+            for dx in -2..=2 {
+                let neighbor = self.debayer_bilinear((coords.0 + dx, coords.1 + dy));
+                let luma = dot(neighbor, LUMA_CAMERA);
+                let difference = luma - center_luma;
+                let spatial = (-((dx * dx + dy * dy) as f32) / 8.0).exp();
+                let w_luma = spatial * (-difference * difference / (2.0 * luma_sigma * luma_sigma)).exp();
+                let w_chroma = spatial * (-difference * difference / (2.0 * chroma_sigma * chroma_sigma)).exp();
+                luma_sum += luma * w_luma;
+                luma_weight += w_luma;
+                for channel in 0..3 {
+                    chroma_sum[channel] += (neighbor[channel] - luma) * w_chroma;
+                }
+                chroma_weight += w_chroma;
+            }
+        }
+
+        let luma = mix(center_luma, luma_sum / luma_weight, p.luma_nr);
+        let mut result = [0.0; 3];
+        for channel in 0..3 {
+            let chroma = mix(color[channel] - center_luma, chroma_sum[channel] / chroma_weight, p.chroma_nr);
+            result[channel] = chroma + luma;
+        }
+        result
+    }
+
+    fn sharpen_detail(&self, color: Vec3, coords: (i32, i32)) -> f32 {
+        let sigma = self.params.sharpen_radius.clamp(0.5, 3.0);
+        let spacing = (sigma * 0.75).round_ties_even().max(1.0);
+        let mut sum = 0.0;
+        let mut total = 0.0;
+        for dy in -2..=2 {
+            for dx in -2..=2 {
+                let offset = (dx as f32 * spacing, dy as f32 * spacing);
+                let w = (-(offset.0 * offset.0 + offset.1 * offset.1) / (2.0 * sigma * sigma)).exp();
+                let neighbor = self.debayer_bilinear((coords.0 + offset.0 as i32, coords.1 + offset.1 as i32));
+                sum += dot(neighbor, LUMA_CAMERA) * w;
+                total += w;
+            }
+        }
+        dot(color, LUMA_CAMERA) - sum / total
+    }
+
+    fn hsl_adjustment(&self, hue: f32) -> Vec3 {
+        let mut lower = 0;
+        for (i, &center) in HSL_CENTERS.iter().enumerate().skip(1) {
+            if hue >= center {
+                lower = i;
+            }
+        }
+        let upper = (lower + 1) % 8;
+        let mut span = HSL_CENTERS[upper] - HSL_CENTERS[lower];
+        if upper == 0 {
+            span = 360.0 - HSL_CENTERS[lower];
+        }
+        let t = (hue - HSL_CENTERS[lower]) / span;
+        let band = |i: usize| [self.params.hsl[i][0], self.params.hsl[i][1], self.params.hsl[i][2]];
+        mix3(band(lower), band(upper), t)
+    }
+
+    fn apply_geometry(&self, tex_coords: [f32; 2], size: [f32; 2]) -> [f32; 2] {
+        let p = self.params;
+        let frame = [p.crop_x + tex_coords[0] * p.crop_w, p.crop_y + tex_coords[1] * p.crop_h];
+        let centered = [(frame[0] - 0.5) * size[0], (frame[1] - 0.5) * size[1]];
+        let (s, c) = p.rotation.sin_cos();
+        let rotated = [c * centered[0] + s * centered[1], -s * centered[0] + c * centered[1]];
+        [rotated[0] / size[0] + 0.5, rotated[1] / size[1] + 0.5]
+    }
+
+    fn lens_radius2(coords: [f32; 2], size: [f32; 2]) -> f32 {
+        let half_diagonal = (size[0] * size[0] + size[1] * size[1]).sqrt() * 0.5;
+        let offset = [(coords[0] - 0.5) * size[0] / half_diagonal, (coords[1] - 0.5) * size[1] / half_diagonal];
+        offset[0] * offset[0] + offset[1] * offset[1]
+    }
+
+    fn lens_distort(&self, coords: [f32; 2], size: [f32; 2]) -> [f32; 2] {
+        let r2 = Self::lens_radius2(coords, size);
+        let scale = 1.0 + self.params.lens_k1 * r2 + self.params.lens_k2 * r2 * r2;
+        [(coords[0] - 0.5) * scale + 0.5, (coords[1] - 0.5) * scale + 0.5]
+    }
+
+    fn lens_vignette_gain(&self, coords: [f32; 2], size: [f32; 2]) -> f32 {
+        let r2 = Self::lens_radius2(coords, size);
+        let falloff = 1.0 + self.params.lens_v1 * r2 + self.params.lens_v2 * r2 * r2;
+        1.0 / falloff.max(0.1)
+    }
+
+    fn orient_to_source(&self, coords: [f32; 2]) -> [f32; 2] {
+        let mut c = coords;
+        let turns = (self.params.orientation_turns + 0.5) as i32;
+        for _ in 0..turns {
+            c = [c[1], 1.0 - c[0]];
+        }
+        if self.params.orientation_mirror > 0.5 {
+            c[0] = 1.0 - c[0];
+        }
+        c
+    }
+
+    fn mask_weight(&self, index: usize, frame: [f32; 2]) -> f32 {
+        let points = self.params.masks[index * 3];
+        let shape = self.params.masks[index * 3 + 1];
+        let offset = [frame[0] - points[0], frame[1] - points[1]];
+        if shape[0] > 0.5 {
+            let gradient = [points[2] - points[0], points[3] - points[1]];
+            let length2 = gradient[0] * gradient[0] + gradient[1] * gradient[1];
+            let along = (offset[0] * gradient[0] + offset[1] * gradient[1]) / length2.max(0.00000001);
+            return 1.0 - smoothstep(0.0, 1.0, along);
+        }
+        let radii = [(points[2] - points[0]).abs().max(0.0001), (points[3] - points[1]).abs().max(0.0001)];
+        let distance = ((offset[0] / radii[0]).powi(2) + (offset[1] / radii[1]).powi(2)).sqrt();
+        let inner = 1.0 - shape[1].clamp(0.0, 1.0);
+        1.0 - smoothstep(inner.min(0.9999), 1.0, distance)
+    }
+
+    /// `process_pixel` in shaders.rs, step for step
+    fn process_pixel(&self, tex_coords: [f32; 2]) -> [f32; 4] {
+        const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+        let p = self.params;
+        if !(0.0..=1.0).contains(&tex_coords[0]) || !(0.0..=1.0).contains(&tex_coords[1]) {
+            return BLACK;
+        }
+
+        let dimensions = [self.renderer.width as f32, self.renderer.height as f32];
+        let mut upright_size = dimensions;
+        if (p.orientation_turns + 0.5) as i32 % 2 == 1 {
+            upright_size = [dimensions[1], dimensions[0]];
+        }
+        let mut upright = self.apply_geometry(tex_coords, upright_size);
+        if p.lens_correction > 0.5 {
+            upright = self.lens_distort(upright, upright_size);
+        }
+        let source = self.orient_to_source(upright);
+        if !(0.0..1.0).contains(&source[0]) || !(0.0..1.0).contains(&source[1]) {
+            return BLACK;
+        }
+        let pixel_coords = ((source[0] * dimensions[0]) as i32, (source[1] * dimensions[1]) as i32);
+
+        // 1. Debayer
+        let mut color = self.debayer(pixel_coords);
+
+        // 1.5. Noise reduction
+        if p.luma_nr > 0.0 || p.chroma_nr > 0.0 {
+            color = self.denoise(color, pixel_coords);
+        }
+
+        // 1.6. Capture sharpening
+        if p.sharpen_amount > 0.0 || p.detail_preview > 0.5 {
+            let detail = self.sharpen_detail(color, pixel_coords);
+            if p.detail_preview > 0.5 {
+                let gray = (0.5 + detail * 4.0).clamp(0.0, 1.0);
+                return [gray, gray, gray, 1.0];
+            }
+            let luma = dot(color, LUMA_CAMERA);
+            let limited = detail.clamp(-0.5 * luma, 0.5 * luma);
+            let protect = 1.0 - smoothstep(0.7, 1.0, max3(color));
+            let sharpened = (luma + p.sharpen_amount * limited * protect).max(0.0);
+            color = scale(color, sharpened / luma.max(0.00001));
+        }
+
+        // 2. White balance
+        let camera = color;
+        color = [color[0] * p.wb_multipliers[0], color[1] * p.wb_multipliers[1], color[2] * p.wb_multipliers[2]];
+
+        // 2.1. Highlight recovery
+        if p.highlight_recovery > 0.0 {
+            color = self.recover_highlights(color, camera);
+        }
+
+        // 2.2. Lens vignetting
+        if p.lens_correction > 0.5 {
+            color = scale(color, self.lens_vignette_gain(upright, upright_size));
+        }
+
+        // 2.5. Temperature & tint
+        color[0] *= 1.0 + p.temperature * 0.3;
+        color[2] *= 1.0 - p.temperature * 0.3;
+        color[1] *= 1.0 + p.tint * 0.3;
+
+        // 3. Color matrix (rows)
+        color = [dot(p.color_matrix_0, color), dot(p.color_matrix_1, color), dot(p.color_matrix_2, color)];
+
+        // 3.2. Film negative
+        if p.invert > 0.5 {
+            color = [0, 1, 2].map(|i| (1.0 - color[i] / p.film_base[i].max(0.0001)).max(0.0));
+        }
+
+        // 3.5. Black & white
+        if p.monochrome > 0.5 {
+            color = [dot(color, p.bw_mix).max(0.0); 3];
+        }
+
+        // 3.9. Local adjustments
+        let mut local_deltas = [0.0f32; 3];
+        let mask_count = (p.mask_count + 0.5) as usize;
+        if mask_count > 0 {
+            let frame = [p.crop_x + tex_coords[0] * p.crop_w, p.crop_y + tex_coords[1] * p.crop_h];
+            for i in 0..mask_count {
+                let weight = self.mask_weight(i, frame);
+                let deltas = p.masks[i * 3 + 2];
+                for (delta, value) in local_deltas.iter_mut().zip(deltas) {
+                    *delta += weight * value;
+                }
+            }
+        }
+
+        // 4. Exposure
+        color = scale(color, 2f32.powf(p.exposure + local_deltas[0]));
+
+        // 4.5. Clarity
+        if p.clarity.abs() > 0.0001 {
+            let detail = self.local_detail(pixel_coords);
+            let display_lum = linear_to_srgb(dot(color, LUMA_709).clamp(0.0, 1.0));
+            let midtones = 1.0 - (2.0 * display_lum - 1.0).powi(2);
+            color = scale(color, (p.clarity * detail * midtones).exp2());
+        }
+
+        // 5. Highlights & shadows
+        let lum_for_tone = dot(color, LUMA_709);
+        color = scale(color, 1.0 + lum_for_tone * p.highlights);
+        color = scale(color, 1.0 + (1.0 - lum_for_tone) * p.shadows);
+
+        // 6. Contrast, shaped by the tone mode
+        color = color.map(|c| apply_tone(c, p.contrast + local_deltas[1], p.tone_mode));
+
+        // 7. Whites & blacks
+        color = color.map(|c| (c - p.blacks) / (p.whites - p.blacks + 0.0001));
+
+        // 7.5. Tone curve
+        if p.curve_enabled > 0.5 {
+            color = color.map(|c| srgb_to_linear(self.apply_curve(linear_to_srgb(c))));
+        }
+
+        // 7.8. Split toning
+        if p.split_tone_enabled > 0.5 {
+            let tone_luma = dot(color, LUMA_709);
+            let display_luma = linear_to_srgb(tone_luma.clamp(0.0, 1.0));
+            let highlight_weight = smoothstep(0.0, 1.0, (display_luma + p.balance * 0.5).clamp(0.0, 1.0));
+            let tint = mix3(p.shadow_tint, p.highlight_tint, highlight_weight);
+            let tint_luma = dot(tint, LUMA_709);
+            color = [0, 1, 2].map(|i| (color[i] + (tint[i] - tint_luma) * tone_luma.max(0.0)).max(0.0));
+        }
+
+        // 8. Saturation
+        let luma = dot(color, LUMA_709);
+        let sat_factor = 1.0 + (p.saturation + local_deltas[2]) / 100.0;
+        color = mix3([luma; 3], color, sat_factor);
+
+        // 9. Vibrance
+        let sat = max3(color) - min3(color);
+        let vibrance_amount = p.vibrance * (1.0 - sat);
+        let luma = dot(color, LUMA_709);
+        color = mix3([luma; 3], color, 1.0 + vibrance_amount);
+
+        // 9.5. Vignette
+        if p.vignette_amount != 0.0 {
+            let (dx, dy) = (tex_coords[0] - 0.5, tex_coords[1] - 0.5);
+            let radius = (dx * dx + dy * dy).sqrt() * std::f32::consts::SQRT_2;
+            let start = p.vignette_midpoint.min(0.99);
+            let falloff = smoothstep(start, 1.0, radius);
+            color = scale(color, (1.0 + p.vignette_amount * falloff).max(0.0));
+        }
+
+        // 10-11. sRGB encoding, clamp
+        color = color.map(|c| linear_to_srgb(c).clamp(0.0, 1.0));
+
+        // 12. HSL bands
+        if p.hsl_enabled > 0.5 {
+            let mut hsl = rgb_to_hsl(color);
+            if hsl[1] > 0.0001 {
+                let adjustment = self.hsl_adjustment(hsl[0]);
+                let colorfulness = hsl[1];
+                hsl[0] = fract((hsl[0] + adjustment[0] * 0.3) / 360.0) * 360.0;
+                hsl[1] = (hsl[1] * (1.0 + adjustment[1] / 100.0)).clamp(0.0, 1.0);
+                hsl[2] = (hsl[2] * (1.0 + adjustment[2] / 100.0 * 0.5 * colorfulness)).clamp(0.0, 1.0);
+                color = hsl_to_rgb(hsl).map(|c| c.clamp(0.0, 1.0));
+            }
+        }
+
+        // 13. Clipping warnings
+        if p.show_clipping > 0.5 {
+            let brightest = max3(color);
+            if brightest >= 1.0 {
+                return [1.0, 0.0, 0.0, 1.0];
+            }
+            if brightest <= 0.0 {
+                return [0.0, 0.0, 1.0, 1.0];
+            }
+        }
+
+        [color[0], color[1], color[2], 1.0]
+    }
+}
+
+/// ACES filmic fit, like the shader's `aces_filmic`
+fn aces_filmic(x: f32) -> f32 {
+    let c = x.max(0.0);
+    ((c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14)).clamp(0.0, 1.0)
+}
+
+/// Contrast shaped by the tone mode, like the shader's `apply_tone`
+fn apply_tone(c: f32, contrast: f32, tone_mode: f32) -> f32 {
+    let contrast = contrast.clamp(-100.0, 100.0);
+    let contrast_factor = 1.0 + contrast / 100.0;
+    if tone_mode > 1.5 {
+        return aces_filmic(((c - 0.5) * contrast_factor + 0.5) * 0.6);
+    }
+    if tone_mode > 0.5 {
+        if !(0.0..=1.0).contains(&c) {
+            return c;
+        }
+        let strength = contrast / 100.0;
+        return mix(c, smoothstep(0.0, 1.0, c), strength);
+    }
+    (c - 0.5) * contrast_factor + 0.5
+}
+
+fn rgb_to_hsl(c: Vec3) -> Vec3 {
+    let max_c = max3(c);
+    let min_c = min3(c);
+    let l = (max_c + min_c) * 0.5;
+    let d = max_c - min_c;
+    if d < 0.00001 {
+        return [0.0, 0.0, l];
+    }
+    let s = d / (1.0 - (2.0 * l - 1.0).abs() + 0.00001);
+    let h = if max_c == c[0] {
+        let h = (c[1] - c[2]) / d;
+        if h < 0.0 { h + 6.0 } else { h }
+    } else if max_c == c[1] {
+        (c[2] - c[0]) / d + 2.0
+    } else {
+        (c[0] - c[1]) / d + 4.0
+    };
+    [h * 60.0, s.clamp(0.0, 1.0), l]
+}
+
+fn hsl_to_rgb(hsl: Vec3) -> Vec3 {
+    let c = (1.0 - (2.0 * hsl[2] - 1.0).abs()) * hsl[1];
+    let h = hsl[0] / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let rgb = if h < 1.0 {
+        [c, x, 0.0]
+    } else if h < 2.0 {
+        [x, c, 0.0]
+    } else if h < 3.0 {
+        [0.0, c, x]
+    } else if h < 4.0 {
+        [0.0, x, c]
+    } else if h < 5.0 {
+        [x, 0.0, c]
+    } else {
+        [c, 0.0, x]
+    };
+    rgb.map(|v| v + hsl[2] - c * 0.5)
+}
+
+/// RGBA8 frame to [R[256], G[256], B[256], Luma[256]] counts, binned like
+/// `HISTOGRAM_SHADER` (luma is the Rec. 709 sum of the displayed values)
+pub(super) fn histogram(rgba: &[u8]) -> [[u32; 256]; 4] {
+    let mut bins = [[0u32; 256]; 4];
+    for pixel in rgba.chunks_exact(4) {
+        let color = [pixel[0], pixel[1], pixel[2]].map(|v| v as f32 / 255.0);
+        for channel in 0..3 {
+            bins[channel][pixel[channel] as usize] += 1;
+        }
+        let luma = dot(color, LUMA_709);
+        bins[3][((luma * 255.0 + 0.5) as usize).min(255)] += 1;
+    }
+    bins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_bins_like_the_shader() {
+        let rgba = [255, 0, 0, 255, 128, 128, 128, 255];
+        let bins = histogram(&rgba);
+        assert_eq!(bins[0][255], 1);
+        assert_eq!(bins[1][0], 1);
+        assert_eq!(bins[2][128], 1);
+        // Pure red is 0.2126 luma (54.2 -> bin 54), gray keeps its value
+        assert_eq!(bins[3][54], 1);
+        assert_eq!(bins[3][128], 1);
+    }
+
+    #[test]
+    fn test_hsl_round_trip() {
+        for color in [[0.8, 0.2, 0.1], [0.1, 0.6, 0.3], [0.3, 0.2, 0.9], [0.5, 0.5, 0.5]] {
+            let back = hsl_to_rgb(rgb_to_hsl(color));
+            for channel in 0..3 {
+                assert!((back[channel] - color[channel]).abs() < 1e-3, "{:?} -> {:?}", color, back);
+            }
+        }
+    }
+}
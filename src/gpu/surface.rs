@@ -0,0 +1,250 @@
+/// Drawing a pipeline's image straight onto the window
+///
+/// The edit pipeline lives on the shared device (see context.rs), but iced
+/// draws the window with a device of its own, and wgpu objects can't cross
+/// devices. So the first time an image is shown its sensor mosaic is read
+/// back once and uploaded to the window's device; from then on every frame
+/// is one pass of the same shader into the widget's rectangle. Zoom, pan and
+/// edits only rewrite the uniform buffer (and nothing at all when they're
+/// unchanged), and nothing comes back to the CPU.
+///
+/// The window-side objects live in the shader widget's `Storage`, for one
+/// image at a time (the pipeline's `generation` says which).
+// Use wgpu from iced to avoid dependency conflicts
+use iced_wgpu::wgpu;
+use iced::widget::shader::{Primitive, Storage, Viewport};
+use iced::Rectangle;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use super::context::{create_bind_group_layout, create_render_pipeline, create_sampler};
+use super::pipeline::{create_image_bindings, draw_pass, write_curve_lut, GpuEditParams, ImageBindings};
+use super::RenderPipeline;
+use crate::state::edit::{crop_size, EditParams};
+
+/// Largest texture the window's device takes: iced asks for wgpu's default
+/// limits, and the first frame drawn records the actual one
+static TEXTURE_LIMIT: AtomicU32 = AtomicU32::new(8192);
+
+/// True if the window can draw this pipeline itself: it renders on the GPU
+/// and its sensor fits in one of the window device's textures
+pub fn can_draw(pipeline: &RenderPipeline) -> bool {
+    let limit = TEXTURE_LIMIT.load(Ordering::Relaxed);
+    !pipeline.is_software() && pipeline.width <= limit && pipeline.height <= limit
+}
+
+/// One Develop frame for the shader widget
+#[derive(Debug)]
+pub struct SurfaceFrame {
+    pipeline: Arc<RenderPipeline>,
+    uniforms: GpuEditParams,
+    /// For the tone curve LUT
+    params: EditParams,
+    /// Output width over height, to fit the image into the widget
+    aspect: f32,
+}
+
+impl SurfaceFrame {
+    pub fn new(pipeline: Arc<RenderPipeline>, params: &EditParams, zoom: f32, pan: (f32, f32)) -> Self {
+        let (width, height) = pipeline.dimensions();
+        let (output_width, output_height) = crop_size(params.crop_or_full(), width, height);
+        SurfaceFrame {
+            uniforms: pipeline.gpu_uniforms(params, zoom, pan.0, pan.1, true),
+            params: *params,
+            aspect: output_width as f32 / output_height.max(1) as f32,
+            pipeline,
+        }
+    }
+}
+
+/// Render pipeline for the window's format, and the image uploaded last
+struct SurfaceRenderer {
+    format: wgpu::TextureFormat,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    render_pipeline: wgpu::RenderPipeline,
+    image: Option<SurfaceImage>,
+}
+
+/// An image on the window's device
+struct SurfaceImage {
+    generation: u64,
+    bindings: ImageBindings,
+    /// What the buffers hold now (unchanged frames write nothing)
+    uniforms: GpuEditParams,
+    params: EditParams,
+    /// Where the image goes: [x, y, width, height] in physical pixels
+    viewport: [f32; 4],
+}
+
+impl SurfaceRenderer {
+    fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let bind_group_layout = create_bind_group_layout(device);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("RAW Processing Shader (window)"),
+            source: wgpu::ShaderSource::Wgsl(super::shaders::get_shader().into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Pipeline Layout (window)"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        // An sRGB surface encodes on write, so it gets linear values
+        let entry_point = if format.is_srgb() { "fs_main_linear" } else { "fs_main" };
+        let render_pipeline = create_render_pipeline(
+            device,
+            &pipeline_layout,
+            &shader,
+            "RAW Render Pipeline (window)",
+            entry_point,
+            format,
+            Some(wgpu::BlendState::REPLACE),
+        );
+        SurfaceRenderer {
+            format,
+            sampler: create_sampler(device),
+            bind_group_layout,
+            render_pipeline,
+            image: None,
+        }
+    }
+
+    /// Get `frame`'s image onto the device and its uniforms up to date
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, frame: &SurfaceFrame) {
+        let pipeline = &frame.pipeline;
+        match &mut self.image {
+            Some(image) if image.generation == pipeline.generation => {
+                if bytemuck::bytes_of(&image.uniforms) != bytemuck::bytes_of(&frame.uniforms) {
+                    queue.write_buffer(&image.bindings.uniform_buffer, 0, bytemuck::bytes_of(&frame.uniforms));
+                    image.uniforms = frame.uniforms;
+                }
+                if image.params.curve != frame.params.curve {
+                    write_curve_lut(queue, &image.bindings.curve_texture, &frame.params);
+                    image.params = frame.params;
+                }
+            }
+            _ => {
+                // A new image: one readback from the pipeline's device, one upload here
+                self.image = None;
+                let Some(raw) = pipeline.raw_sensor_data() else {
+                    return;
+                };
+                println!("🖥️  Uploading image {} to the window's GPU device", pipeline.image_id);
+                let bindings = create_image_bindings(
+                    device,
+                    queue,
+                    &self.bind_group_layout,
+                    &self.sampler,
+                    &raw,
+                    pipeline.width,
+                    pipeline.height,
+                    &frame.params,
+                    &frame.uniforms,
+                );
+                self.image = Some(SurfaceImage {
+                    generation: pipeline.generation,
+                    bindings,
+                    uniforms: frame.uniforms,
+                    params: frame.params,
+                    viewport: [0.0; 4],
+                });
+            }
+        }
+    }
+}
+
+/// The largest rectangle of `aspect` (width / height) centered in `bounds`
+fn fit(bounds: &Rectangle, aspect: f32) -> Rectangle {
+    let (width, height) = if bounds.width / bounds.height.max(1.0) > aspect {
+        (bounds.height * aspect, bounds.height)
+    } else {
+        (bounds.width, bounds.width / aspect)
+    };
+    Rectangle {
+        x: bounds.x + (bounds.width - width) / 2.0,
+        y: bounds.y + (bounds.height - height) / 2.0,
+        width,
+        height,
+    }
+}
+
+impl Primitive for SurfaceFrame {
+    fn prepare(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+        storage: &mut Storage,
+        bounds: &Rectangle,
+        viewport: &Viewport,
+    ) {
+        if storage.get::<SurfaceRenderer>().map(|renderer| renderer.format) != Some(format) {
+            TEXTURE_LIMIT.store(device.limits().max_texture_dimension_2d, Ordering::Relaxed);
+            storage.store(SurfaceRenderer::new(device, format));
+        }
+        let Some(renderer) = storage.get_mut::<SurfaceRenderer>() else {
+            return;
+        };
+        renderer.prepare(device, queue, self);
+
+        // Contain fit, like the read-back frames in an Image widget; the
+        // viewport has to stay on the target
+        let rect = fit(bounds, self.aspect) * viewport.scale_factor() as f32;
+        let size = viewport.physical_size();
+        let on_target = rect.x >= 0.0
+            && rect.y >= 0.0
+            && rect.x + rect.width <= size.width as f32
+            && rect.y + rect.height <= size.height as f32;
+        if let Some(image) = &mut renderer.image {
+            image.viewport = if on_target { [rect.x, rect.y, rect.width, rect.height] } else { [0.0; 4] };
+        }
+    }
+
+    fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        storage: &Storage,
+        target: &wgpu::TextureView,
+        clip_bounds: &Rectangle<u32>,
+    ) {
+        let Some(renderer) = storage.get::<SurfaceRenderer>() else {
+            return;
+        };
+        let Some(image) = &renderer.image else {
+            return;
+        };
+        if image.generation != self.pipeline.generation
+            || image.viewport[2] < 1.0
+            || image.viewport[3] < 1.0
+            || clip_bounds.width == 0
+            || clip_bounds.height == 0
+        {
+            return;
+        }
+        draw_pass(
+            encoder,
+            target,
+            &renderer.render_pipeline,
+            &image.bindings.bind_group,
+            image.viewport,
+            Some([clip_bounds.x, clip_bounds.y, clip_bounds.width, clip_bounds.height]),
+            wgpu::LoadOp::Load,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_centers_the_image() {
+        let bounds = Rectangle { x: 10.0, y: 20.0, width: 400.0, height: 200.0 };
+        // Portrait image: full height, centered across
+        let rect = fit(&bounds, 0.5);
+        assert_eq!((rect.x, rect.y, rect.width, rect.height), (160.0, 20.0, 100.0, 200.0));
+        // Panorama: full width, centered down
+        let rect = fit(&bounds, 4.0);
+        assert_eq!((rect.x, rect.y, rect.width, rect.height), (10.0, 70.0, 400.0, 100.0));
+    }
+}
@@ -6,6 +6,7 @@ use iced_aw::Wrap;
 use iced::window;
 use rfd::FileDialog;
 use rusqlite::{Connection, ErrorCode};
+use std::collections::HashSet;
 use std::path::PathBuf;
 use std::sync::Arc;
 use walkdir::WalkDir;
@@ -21,28 +22,113 @@ mod color;  // Phase 15: Color space conversion utilities
 
 // Import shared data structures (alias to avoid conflict with iced's image widget)
 use state::data::Image as ImageData;
+use ui::notifications::Severity;
 
 // Phase 15: Color space conversion
-use color::calculate_cam_to_srgb_matrix;
+use color::camera_to_srgb;
 
 /// Result of a folder import operation
 #[derive(Debug, Clone)]
 struct ImportResult {
+    /// Import run id stored on every row it added (start time, unix ms)
+    batch_id: i64,
     imported_count: usize,
     skipped_count: usize,
+    /// RAW-looking files rawloader can't decode (e.g. CR3), left out
+    unsupported_count: usize,
 }
 
-/// Result of thumbnail generation
+/// Result of one thumbnail worker batch
 #[derive(Debug, Clone)]
 struct ThumbnailResult {
     generated_count: usize,
+    /// Wall-clock time the batch took (for the throughput readout)
+    elapsed: std::time::Duration,
+    /// Images still waiting for the fast (embedded JPEG) path
+    fast_remaining: usize,
+    /// Images still waiting for a full RAW decode
+    slow_remaining: usize,
+    /// The worker stops after this batch (queues drained, or told to stop)
+    last: bool,
 }
 
 /// Application tabs/modules
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum AppTab {
-    Library,  // Browse, import, organize images
-    Develop,  // Edit selected image with full preview
+    Library,      // Browse, import, organize images
+    Develop,      // Edit selected image with full preview
+    Preferences,  // App settings (stored in the catalog)
+}
+
+impl AppTab {
+    const ALL: [AppTab; 3] = [AppTab::Library, AppTab::Develop, AppTab::Preferences];
+    
+    /// Value stored in the settings table (the tab is restored at launch)
+    fn as_setting(&self) -> &'static str {
+        match self {
+            AppTab::Library => "library",
+            AppTab::Develop => "develop",
+            AppTab::Preferences => "preferences",
+        }
+    }
+    
+    fn from_setting(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|tab| tab.as_setting() == value)
+    }
+}
+
+/// Zoom limits (1.0 = fit to the viewport)
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 20.0;
+
+/// What "show before" (spacebar) does in Develop
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum CompareMode {
+    /// Whole image switches to the original
+    #[default]
+    Swap,
+    /// Original left of a draggable divider, edits on the right
+    Split,
+}
+
+impl CompareMode {
+    const ALL: [CompareMode; 2] = [CompareMode::Swap, CompareMode::Split];
+}
+
+impl std::fmt::Display for CompareMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompareMode::Swap => write!(f, "Before/After: Swap"),
+            CompareMode::Split => write!(f, "Before/After: Split"),
+        }
+    }
+}
+
+/// How close (screen pixels) a click must be to grab the split divider
+const SPLIT_GRAB_DISTANCE: f32 = 8.0;
+
+/// How long edits must pause before the image's .xmp sidecar is rewritten
+/// (a slider drag saves to the catalog on every step)
+const SIDECAR_WRITE_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Color under the cursor in the Develop preview
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PixelSample {
+    /// Displayed value (after every edit, display gamma)
+    rgb: [u8; 3],
+    /// Camera RGB around that sensor pixel (0-1, before white balance),
+    /// only while the sensor readout is on
+    camera: Option<[f32; 3]>,
+}
+
+/// Images waiting for the delete confirmation
+#[derive(Debug, Clone)]
+struct DeleteRequest {
+    image_ids: Vec<i64>,
+    /// From "Delete Rejected" rather than the selection (changes the prompt)
+    rejected: bool,
+    /// How many of the RAW files are still on disk (what trashing would affect)
+    files_on_disk: usize,
 }
 
 /// Result of preview generation
@@ -52,6 +138,16 @@ struct PreviewResult {
     preview_path: Result<String, String>,
 }
 
+/// Where a Clear Cache is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CacheClearing {
+    /// Waiting for the thumbnail worker to finish its batch (it would write
+    /// into the folders being emptied)
+    StoppingWorker,
+    /// Deleting the files in the background; the worker stays paused
+    Deleting,
+}
+
 /// State of the editor and GPU pipeline
 #[derive(Clone)]
 enum EditorStatus {
@@ -80,12 +176,19 @@ impl std::fmt::Debug for EditorStatus {
 struct RawEditor {
     /// The catalog database (Phase 23: Optional during startup)
     library: Option<state::library::Library>,
+    /// Why the catalog failed to open; the splash offers a retry (and a
+    /// backup-and-recreate when it's damaged) instead of the spinner
+    db_error: Option<state::library::LibraryError>,
     /// Status message to display to the user
     status: String,
     /// All images loaded from the database
     images: Vec<ImageData>,
     /// Currently selected image ID
     selected_image_id: Option<i64>,
+    /// Multi-selection in the Library grid (includes the primary selection)
+    selected_ids: HashSet<i64>,
+    /// Currently held keyboard modifiers (Ctrl/Shift+click in the grid, Alt solo in Develop)
+    keyboard_modifiers: iced::keyboard::Modifiers,
     /// Cache directory for full-size previews
     preview_cache_dir: PathBuf,
     /// Currently active tab
@@ -94,27 +197,214 @@ struct RawEditor {
     current_edit_params: state::edit::EditParams,
     /// GPU pipeline status (holds the pipeline when ready)
     editor_status: EditorStatus,
-    /// Phase 21: Histogram data [R[256], G[256], B[256]]
-    histogram_data: std::cell::RefCell<[[u32; 256]; 3]>,
+    /// Phase 21: Histogram data [R[256], G[256], B[256], Luma[256]]
+    histogram_data: std::cell::RefCell<[[u32; 256]; 4]>,
     /// Phase 21: Histogram canvas cache
     histogram_cache: iced::widget::canvas::Cache,
     /// Phase 22: Histogram toggle (keep for user control)
     histogram_enabled: bool,
+    /// How the histogram is drawn (RGB overlay, luminance, parade, stacked)
+    histogram_mode: ui::histogram::HistogramMode,
+    /// Logarithmic histogram Y axis
+    histogram_log_scale: bool,
     /// Phase 24: Before/After toggle (show original vs edited)
     show_before: bool,
+    /// Presentation mode (F): the window goes fullscreen and Develop shows
+    /// only the image, no tab bar, header or sidebar
+    fullscreen_preview: bool,
+    /// Whether "before" swaps the whole image or splits it
+    compare_mode: CompareMode,
+    /// Split view divider position (0-1 across the preview)
+    split_x: f32,
+    /// User is dragging the split divider (instead of panning)
+    dragging_split: bool,
     /// Phase 25: Zoom level (1.0 = 100%, 2.0 = 200%, etc.)
     zoom: f32,
+    /// Zoom that fits the current image, set when it opens: 1.0 (fills the
+    /// viewport) unless that would blow a small image up past actual pixels
+    fit_zoom: f32,
     /// Phase 25: Pan offset in normalized coordinates
     pan_offset: cgmath::Vector2<f32>,
     /// Phase 25: Canvas cache for main image rendering
     canvas_cache: iced::widget::canvas::Cache,
+    /// Last rendered Develop preview: redraws with unchanged inputs reuse it
+    preview_frame: ui::canvas::FrameCache,
+    /// Compare view: the image shown right of the selected one (None = normal Develop)
+    compare_with: Option<i64>,
+    /// Pipeline status of the right-hand compare image
+    compare_status: EditorStatus,
+    /// Saved edits of the right-hand compare image
+    compare_params: state::edit::EditParams,
+    /// Last rendered right-hand compare frame
+    compare_frame: ui::canvas::FrameCache,
     /// Phase 25: Drag state for panning
     is_dragging: bool,
     last_cursor_position: Option<Point>,
     /// Phase 26: Double-click detection
     last_click_time: Option<std::time::Instant>,
-    /// Phase 26: Viewport size for zoom-to-cursor calculations (actual displayed size)
-    viewport_size: (f32, f32),  // (width, height) in screen pixels
+    /// Last slider label pressed (double-click resets that slider)
+    last_label_press: Option<(std::mem::Discriminant<Message>, std::time::Instant)>,
+    /// Phase 26: Viewport size for zoom-to-cursor calculations (the preview area, from layout)
+    /// Set whenever the preview is laid out, so it's right before the mouse ever moves
+    viewport_size: std::cell::Cell<(f32, f32)>,  // (width, height) in screen pixels
+    /// Mirror edits into .xmp sidecar files next to the RAW
+    write_sidecars: bool,
+    /// Image whose sidecar is behind its edits, and when it was last edited
+    /// (written once the edits pause, or when another image is edited)
+    sidecar_pending: Option<(i64, std::time::Instant)>,
+    /// Re-render edited images' thumbnails from the pipeline (off = embedded JPEG)
+    edited_thumbnails: bool,
+    /// Select the next image after flagging one reject (culling)
+    auto_advance: bool,
+    /// Show the cached embedded preview while Develop decodes the RAW
+    fast_preview: bool,
+    /// Images whose edits changed since their thumbnail was rendered; rendered
+    /// when the user moves on to another image or leaves Develop
+    thumbnail_refresh: HashSet<i64>,
+    /// Custom thumbnail/preview cache folder (None = OS default)
+    cache_dir: Option<PathBuf>,
+    /// Bytes in the cache folders (measured when Preferences opens)
+    cache_size: Option<u64>,
+    /// Catalog backups store paths inside this folder relative to it
+    photos_root: Option<PathBuf>,
+    /// Extra extensions imported as RAW (normalized, see `settings::parse_extensions`)
+    custom_extensions: Vec<String>,
+    /// What's typed in the custom extensions field
+    custom_extensions_input: String,
+    /// Graphics API for the GPU context (applies at the next launch)
+    gpu_backend: state::settings::GpuBackend,
+    /// Discrete or integrated GPU (applies at the next launch)
+    gpu_power: state::settings::GpuPower,
+    /// Light/dark UI and accent color (saved in the settings table)
+    ui_theme: state::settings::UiTheme,
+    accent_color: state::settings::AccentColor,
+    /// GPU the context runs on, once asked for (Err if there's no usable GPU)
+    gpu_adapter: Option<Result<String, String>>,
+    /// Pipelines render on the CPU because the system has no GPU adapter
+    software_rendering: bool,
+    /// Undo/redo history for the selected image's edits
+    edit_history: state::edit::EditHistory,
+    /// White balance eyedropper mode: the next click on the image picks a neutral point
+    wb_picker_active: bool,
+    /// Film base eyedropper mode: the next click on the image sets the film base color
+    film_base_picker_active: bool,
+    /// Color under the cursor (None while it's off the image)
+    pixel_sample: Option<PixelSample>,
+    /// Also read the camera values under the cursor (a small GPU readback per move)
+    show_sensor_values: bool,
+    /// Library grid sort order
+    library_sort: state::data::SortOrder,
+    /// Library grid filter
+    library_filter: state::data::LibraryFilter,
+    /// Split the Library grid into one section per capture day
+    group_by_date: bool,
+    /// Day sections of the grid while grouping: each day and how many of
+    /// `images` (which are in section order) belong to it
+    date_sections: Vec<(chrono::NaiveDate, usize)>,
+    /// Day sections the user folded away
+    collapsed_days: HashSet<chrono::NaiveDate>,
+    /// EXIF metadata of the selected image (shown in the Develop header)
+    selected_metadata: state::exif::ImageMetadata,
+    /// Crop mode: render the full frame and show the crop overlay
+    crop_tool_active: bool,
+    /// Aspect ratio lock for the crop overlay
+    crop_aspect: state::edit::CropAspect,
+    /// Free crops keep their shape while resizing
+    crop_lock_ratio: bool,
+    /// Composition guides inside the crop box
+    crop_guide: ui::crop::CropGuide,
+    /// Crop mode sub-tool: drag a line to level it instead of moving the crop
+    straighten_active: bool,
+    /// HSL panel expanded in the Develop sidebar
+    hsl_panel_open: bool,
+    /// Mask mode: show the mask handles over the preview
+    mask_tool_active: bool,
+    /// Local mask whose sliders the sidebar shows
+    selected_mask: Option<usize>,
+    /// Recently viewed images' pipelines (instant Next/Previous in Develop)
+    pipeline_cache: gpu::PipelineCache<Arc<gpu::RenderPipeline>>,
+    /// Images waiting for the delete confirmation (None = no prompt)
+    delete_pending: Option<DeleteRequest>,
+    /// When the user last left Develop (cached pipelines are released after a while)
+    left_develop_at: Option<std::time::Instant>,
+    /// Develop preview render width (saved in the settings table)
+    preview_quality: state::settings::PreviewQuality,
+    /// Library grid thumbnail width in pixels (saved in the settings table)
+    thumbnail_size: f32,
+    /// Preview shows only the sharpening detail (view setting, not saved)
+    detail_preview: bool,
+    /// Clipped highlights/shadows painted red/blue in the preview (J key)
+    show_clipping: bool,
+    /// Preset names for the Develop dropdown
+    presets: Vec<String>,
+    /// Name typed into the "save preset" field
+    preset_name_input: String,
+    /// Preset name waiting for overwrite confirmation
+    preset_overwrite_pending: Option<String>,
+    /// Snapshots (named looks) of the selected image
+    snapshots: Vec<state::data::Snapshot>,
+    /// Keywords of the selected image
+    keywords: Vec<String>,
+    /// Text in the "add keyword" field
+    keyword_input: String,
+    /// Library grid keyword search (empty = no keyword filter)
+    keyword_search: String,
+    /// Which import runs the Library grid shows
+    import_scope: state::data::ImportScope,
+    /// Import runs (batch ids) that added images since the app started
+    session_import_batches: Vec<i64>,
+    /// Name typed into the "save snapshot" field
+    snapshot_name_input: String,
+    /// Snapshot shown left of the split divider instead of the original
+    compare_snapshot: Option<i64>,
+    /// Develop settings copied with CopySettings, ready to paste onto other images
+    clipboard_params: Option<state::edit::EditParams>,
+    /// Output format for batch export
+    batch_export_format: state::export::ExportFormat,
+    /// Batch export filename template (see state::export)
+    batch_export_template: String,
+    /// Batch export in progress: (done, total)
+    batch_export_progress: Option<(usize, usize)>,
+    /// File the batch export is writing right now
+    batch_export_current: Option<PathBuf>,
+    /// Files single-image exports are writing right now
+    exports_in_flight: Vec<PathBuf>,
+    /// Single-image export running: fraction of the tiles rendered
+    export_progress: Option<f32>,
+    /// Stops the running single-image export (a fresh token per export)
+    export_cancel: CancelToken,
+    /// Toasts shown over the main view (export results, errors)
+    notifications: Vec<ui::notifications::Notification>,
+    /// Id for the next toast
+    next_notification_id: u64,
+    /// JPEG quality / PNG compression used by Export and Batch Export
+    export_options: state::export::ExportOptions,
+    /// Copy the RAW's EXIF into exported files
+    include_metadata: bool,
+    /// Artist/copyright and GPS/maker-note switches for copied EXIF
+    export_metadata: state::exif::ExportMetadata,
+    /// Thumbnail worker subscription is running (stops itself once both queues are empty)
+    thumbnail_worker_active: bool,
+    /// Stops the thumbnail worker after its batch (a child of `shutdown`)
+    thumbnail_stop: CancelToken,
+    /// A Clear Cache in progress
+    cache_clearing: Option<CacheClearing>,
+    /// Window geometry restored at launch (kept as the un-maximized size when closing maximized)
+    window_state: state::settings::WindowState,
+    /// Import or thumbnail queue in progress: (done, total) for the Library progress bar
+    progress: Option<(usize, usize)>,
+    /// An import is running
+    importing: bool,
+    /// The Phase 28 cache tier chain (CacheProcessed) is running
+    cache_queue_active: bool,
+    /// Close was requested while background work was running: the window to close
+    quit_pending: Option<window::Id>,
+    /// Set when the user quits anyway; background tasks stop at their next step
+    shutdown: CancelToken,
+    /// Files/folders dropped on the window, imported together once the drop settles
+    dropped_paths: Vec<PathBuf>,
+    /// Busy spinner animation frame (advances only while something is loading)
+    spinner_frame: u32,
 }
 
 /// Application messages (events)
@@ -123,23 +413,60 @@ enum Message {
     // ========== Startup Messages (Phase 23) ==========
     /// Database loading completed (async background task)
     /// Phase 23: Only send images Vec, Library created on main thread (not Send)
-    DatabaseLoaded(Result<Vec<ImageData>, String>),
+    DatabaseLoaded(Result<(Vec<ImageData>, state::settings::AppSettings), state::library::LibraryError>),
+    /// Try opening the catalog again (after it failed, e.g. locked)
+    RetryDatabase,
+    /// Move the damaged catalog aside and recreate it
+    RecoverDatabase,
+    /// Recovery finished: the backup's path and how many images came back
+    DatabaseRecovered(Result<state::library::Recovery, state::library::LibraryError>),
+    /// User asked to close the window (geometry is saved before it closes)
+    WindowCloseRequested(window::Id),
+    /// Window geometry read back for saving; closes the window afterwards
+    WindowClosing {
+        id: window::Id,
+        maximized: bool,
+        size: iced::Size,
+        position: Option<Point>,
+    },
+    /// Quit prompt: stop the background work, then close once it has stopped
+    ConfirmQuit,
+    /// Quit prompt while stopping: close right away, cleaning up what was cut off
+    ForceQuit,
+    /// Quit prompt: keep the app open
+    CancelQuit,
     
     /// User clicked the "Import Folder" button
     ImportFolder,
+    /// User clicked the "Import Files" button
+    ImportFiles,
+    /// A file or folder was dropped on the window (one message per path)
+    FileDropped(PathBuf),
+    /// The drop has settled: import everything dropped so far
+    ImportDropped,
+    /// Background import finished another chunk of files
+    ImportProgress { done: usize, total: usize },
     /// Background import completed with results
     ImportComplete(ImportResult),
-    /// Background thumbnail generation completed
-    ThumbnailGenerated(ThumbnailResult),
+    /// Thumbnail worker finished a batch
+    ThumbnailProgress(ThumbnailResult),
     /// Phase 28: Multi-tier cache processing completed
     /// Result is (image_id, thumb_path, instant_path, working_path) or (image_id, error)
     CacheProcessed(Result<(i64, String, String, String), (i64, String)>),
-    /// User selected an image from the grid
+    /// User selected an image from the grid (clears the multi-selection)
     ImageSelected(i64),
+    /// Ctrl+click: add/remove an image from the multi-selection
+    ToggleSelection(i64),
+    /// Shift+click: select every image between the primary selection and this one
+    RangeSelectTo(i64),
+    /// Keyboard modifiers changed (decides what a grid click does, Alt solos a slider)
+    ModifiersChanged(iced::keyboard::Modifiers),
     /// Background preview generation completed
     PreviewGenerated(PreviewResult),
     /// User switched to a different tab
     TabChanged(AppTab),
+    /// Enter: open the selected image in Develop (nothing selected = no-op)
+    OpenInDevelop,
     
     // ========== Edit Parameter Changes ==========
     /// User changed exposure slider
@@ -148,12 +475,98 @@ enum Message {
     ContrastChanged(f32),
     /// User changed highlights slider
     HighlightsChanged(f32),
+    /// User changed highlight recovery slider
+    HighlightRecoveryChanged(f32),
     /// User changed shadows slider
     ShadowsChanged(f32),
     /// User changed whites slider
     WhitesChanged(f32),
     /// User changed blacks slider
     BlacksChanged(f32),
+    /// User picked a preset from the dropdown
+    ApplyPreset(String),
+    /// User typed in the preset name field
+    PresetNameChanged(String),
+    /// User clicked "Save" to store the current settings as a preset
+    SavePreset,
+    /// User confirmed overwriting an existing preset
+    ConfirmOverwritePreset,
+    /// User declined overwriting an existing preset
+    CancelOverwritePreset,
+    /// User typed in the snapshot name field
+    SnapshotNameChanged(String),
+    /// User clicked "Save" to keep the current look as a snapshot
+    SaveSnapshot,
+    /// User restored a snapshot (by id)
+    RestoreSnapshot(i64),
+    /// User deleted a snapshot (by id)
+    DeleteSnapshot(i64),
+    /// Split view with a snapshot on the left (None = back to the original)
+    CompareSnapshot(Option<i64>),
+    /// User typed in the "add keyword" field
+    KeywordInputChanged(String),
+    /// User submitted the keyword field: tag the selected image
+    AddKeyword,
+    /// User removed a keyword from the selected image
+    RemoveKeyword(String),
+    /// User typed in the Library keyword search box
+    KeywordSearchChanged(String),
+    /// User picked which import runs the Library grid shows
+    ImportScopeChanged(state::data::ImportScope),
+    /// User toggled "Convert to B&W"
+    MonochromeToggled(bool),
+    /// User moved a B&W channel mixer slider (0 = red, 1 = green, 2 = blue)
+    BwMixChanged(usize, f32),
+    /// User moved one channel of the split toning shadow tint (channel, value)
+    ShadowTintChanged(usize, f32),
+    /// User moved one channel of the split toning highlight tint (channel, value)
+    HighlightTintChanged(usize, f32),
+    /// User changed the split toning balance slider
+    BalanceChanged(f32),
+    /// User changed clarity slider
+    ClarityChanged(f32),
+    /// Delete key: ask before removing the selected images
+    RequestDelete,
+    /// Delete Rejected: ask before removing every image flagged reject
+    RequestDeleteRejected,
+    /// Confirmed: remove the pending images from the catalog (true = also move the RAWs to the trash)
+    ConfirmDelete(bool),
+    /// Dismiss the delete confirmation
+    CancelDelete,
+    /// Open the OS file manager at an image's RAW file
+    RevealInFileManager(i64),
+    /// The file manager command for a path has exited (or failed to start)
+    Revealed(String, Result<(), String>),
+    /// Put an image's RAW file path on the clipboard
+    CopyPathToClipboard(i64),
+    /// Throw away the selected images' cached thumbnails and make them again
+    RegenerateThumbnails,
+    /// Same for every image in the catalog (Preferences)
+    RegenerateAllCaches,
+    /// Periodic check outside Develop: free cached pipelines once idle long enough
+    ReleaseIdleGpu,
+    /// User picked a preview quality (Develop header)
+    PreviewQualityChanged(state::settings::PreviewQuality),
+    /// User dragged the grid thumbnail size slider
+    ThumbnailSizeChanged(f32),
+    /// User changed sharpening amount slider
+    SharpenAmountChanged(f32),
+    /// User changed sharpening radius slider
+    SharpenRadiusChanged(f32),
+    /// User toggled the sharpening detail (high-pass) preview
+    ToggleDetailPreview(bool),
+    /// User changed luminance noise reduction slider
+    LumaNrChanged(f32),
+    /// User changed color noise reduction slider
+    ChromaNrChanged(f32),
+    /// User asked for noise reduction amounts based on the photo's ISO
+    SuggestNoiseReduction,
+    /// One-click exposure/whites/blacks from the image's histogram
+    AutoTone,
+    /// User changed vignette amount slider
+    VignetteAmountChanged(f32),
+    /// User changed vignette midpoint slider
+    VignetteMidpointChanged(f32),
     /// User changed vibrance slider
     VibranceChanged(f32),
     /// User changed saturation slider
@@ -162,16 +575,91 @@ enum Message {
     TemperatureChanged(f32),
     /// User changed tint slider (Phase 18)
     TintChanged(f32),
+    /// User dragged a tone curve control point (or reset the curve)
+    CurveChanged(state::edit::ToneCurve),
+    /// User moved one HSL slider (band index, channel, value)
+    HslChanged(usize, state::edit::HslChannel, f32),
+    /// User expanded/collapsed the HSL panel
+    ToggleHslPanel,
+    /// Toggle mask mode (shows the local mask handles over the preview)
+    ToggleMaskTool,
+    /// User added a local adjustment mask
+    AddMask(state::edit::MaskKind),
+    /// User removed a local mask (index)
+    RemoveMask(usize),
+    /// User picked a local mask to edit (index)
+    MaskSelected(usize),
+    /// User dragged a mask handle (index, handle, frame point)
+    MaskMoved(usize, ui::masks::MaskHandle, [f32; 2]),
+    /// User moved one slider of a local mask (index, slider, value)
+    MaskChanged(usize, state::edit::MaskSetting, f32),
+    /// User picked a demosaic algorithm
+    DemosaicChanged(state::edit::DemosaicMethod),
+    /// Slider label clicked; a double-click sends the wrapped message, which
+    /// sets that slider back to its default
+    SliderLabelPressed(Box<Message>),
+    /// User picked how contrast shapes the tones (Linear / S-curve / Filmic)
+    ToneModeChanged(state::edit::ToneMode),
+    /// Toggle crop mode (shows the full frame with the crop overlay)
+    ToggleCropTool,
+    /// User dragged the crop overlay (normalized x, y, width, height)
+    CropChanged(state::edit::CropRect),
+    /// User picked a crop aspect ratio preset
+    CropAspectChanged(state::edit::CropAspect),
+    /// User toggled keeping a free crop's shape while resizing
+    CropLockRatioToggled(bool),
+    /// User picked the composition guides shown in the crop box
+    CropGuideChanged(ui::crop::CropGuide),
+    /// Toggle the straighten sub-tool (drag along a horizon to level it)
+    ToggleStraighten,
+    /// User changed the straighten slider or drew a straighten line (degrees)
+    RotationChanged(f32),
+    /// Clear crop and rotation
+    ResetCrop,
+    /// User toggled lens correction (profile + manual distortion)
+    LensCorrectionToggled(bool),
+    /// User changed the manual distortion slider
+    LensDistortionChanged(f32),
     /// User clicked Reset button to clear all edits
     ResetEdits,
+    /// Step back in edit history (Ctrl+Z)
+    Undo,
+    /// Step forward in edit history (Ctrl+Shift+Z)
+    Redo,
+    /// Copy the selected image's develop settings (Ctrl+C in Develop)
+    CopySettings,
+    /// Paste copied develop settings onto the selected image (Ctrl+V in Develop)
+    PasteSettings,
     
     // ========== Phase 24: Workflow Messages ==========
     /// Toggle Before/After view (Spacebar)
     ToggleBeforeAfter,
+    /// Toggle the fullscreen presentation view in Develop (F)
+    ToggleFullscreenPreview,
+    /// Esc: leave presentation mode, otherwise go back to the Library
+    EscapePressed,
+    /// Toggle the clipping warning overlay (J)
+    ToggleClipping,
+    /// Turn the Develop image a quarter turn (true = clockwise); composes with the EXIF orientation
+    RotateImage(bool),
+    /// User picked full swap or split screen for Before/After
+    CompareModeChanged(CompareMode),
     /// Select next image (Right arrow)
     SelectNextImage,
     /// Select previous image (Left arrow)
     SelectPreviousImage,
+    /// Rate the selected image 0-5 stars (number keys)
+    SetRating(i32),
+    /// Flag the selected image: "pick" (P), "reject" (X) or "" to clear (U)
+    SetFlag(&'static str),
+    /// User picked a Library sort order
+    LibrarySortChanged(state::data::SortOrder),
+    /// User changed the Library filter checkboxes
+    LibraryFilterChanged(state::data::LibraryFilter),
+    /// Group the Library grid by capture day on/off
+    GroupByDateToggled(bool),
+    /// Fold or unfold one day section of the grid
+    ToggleDayCollapsed(chrono::NaiveDate),
     
     // ========== Phase 25: Zoom & Pan Messages ==========
     /// User zoomed with mouse wheel (delta, cursor position)
@@ -183,11 +671,32 @@ enum Message {
     /// Mouse button released - stop dragging
     MouseReleased,
     /// Mouse moved - track for panning
-    MouseMoved(Point),
+    /// Mouse moved over the preview (position, exact preview area size)
+    MouseMoved(Point, iced::Size),
+    /// Cursor left the preview (the color readout goes blank)
+    PreviewCursorLeft,
+    /// User toggled the camera values in the color readout
+    SensorReadoutToggled(bool),
     
     // ========== Phase 26: Advanced Zoom Polish ==========
-    /// Reset zoom and pan to default (1.0, 0.0)
-    ResetView,
+    /// Reset zoom and pan to the image's fit (`fit_zoom`, centered)
+    ZoomToFit,
+    /// Zoom to a pixel scale (1.0 = 100%, one image pixel per screen pixel), keeping the center
+    SetZoom(f32),
+    
+    // ========== White Balance Picker ==========
+    /// Toggle the white balance eyedropper (clicks pick instead of pan)
+    ToggleWbPicker,
+    /// Neutralize the color under this viewport position
+    PickWhiteBalance(Point),
+    
+    // ========== Film Negative ==========
+    /// User toggled film negative inversion
+    FilmInvertToggled(bool),
+    /// Toggle the film base eyedropper (click the unexposed film rebate)
+    ToggleFilmBasePicker,
+    /// Take the film base color from under this viewport position
+    PickFilmBase(Point),
     
     // ========== GPU Pipeline Messages ==========
     /// Background RAW data loading completed
@@ -195,75 +704,278 @@ enum Message {
     /// GPU pipeline initialization completed
     GpuPipelineReady(Result<Arc<gpu::RenderPipeline>, String>),
     
+    // ========== Compare Messages ==========
+    /// Open Develop with the first image left and the second right, sharing zoom/pan
+    EnterCompare(i64, i64),
+    /// Back to the normal Develop view of the left image
+    ExitCompare,
+    /// RAW data for the right-hand compare image loaded
+    CompareRawDataLoaded(i64, Result<raw::loader::RawDataResult, String>),
+    /// GPU pipeline for the right-hand compare image initialized
+    ComparePipelineReady(i64, Result<Arc<gpu::RenderPipeline>, String>),
+    
     // ========== Export Messages (Phase 19) ==========
     /// User clicked Export button
     ExportImage,
-    /// Background export completed
-    ExportComplete(Result<std::path::PathBuf, String>),
+    /// Background export to this file completed
+    ExportComplete(std::path::PathBuf, Result<(), String>),
+    /// Single-image export progress (fraction of the tiles rendered)
+    ExportProgress(f32),
+    /// User cancelled the running single-image export
+    CancelExport,
+    /// User clicked a toast
+    DismissNotification(u64),
+    /// Periodic check for toasts that have been up long enough
+    NotificationTick,
+    /// Animation timer for the busy spinner
+    SpinnerTick,
+    /// User clicked Batch Export (exports every selected image to a folder)
+    BatchExport,
+    /// User picked the batch export format
+    BatchExportFormatChanged(state::export::ExportFormat),
+    /// User edited the batch export filename template
+    BatchExportTemplateChanged(String),
+    /// Background batch export started on `current`, or ended (None): after
+    /// the last image, or early when the app is quitting
+    BatchExportProgress { done: usize, total: usize, current: Option<PathBuf> },
+    /// User changed the JPEG export quality
+    JpegQualityChanged(u8),
+    /// User changed the PNG export compression
+    PngCompressionChanged(state::export::PngCompression),
+    /// User toggled copying EXIF metadata into exports
+    ExportMetadataToggled(bool),
+    /// User edited the artist written into exports
+    ExportArtistChanged(String),
+    /// User edited the copyright notice written into exports
+    ExportCopyrightChanged(String),
+    /// User toggled copying GPS position into exports
+    ExportGpsToggled(bool),
+    /// User toggled copying maker notes into exports
+    ExportMakerNotesToggled(bool),
+    /// User toggled embedding the edit recipe in PNG exports
+    EmbedRecipeToggled(bool),
     
     // ========== Histogram Messages (Phase 22) ==========
     /// User toggled histogram on/off
     HistogramToggled(bool),
+    /// User picked a histogram display mode
+    HistogramModeChanged(ui::histogram::HistogramMode),
+    /// User toggled the logarithmic histogram scale
+    HistogramLogScaleToggled(bool),
+    
+    // ========== XMP Sidecar Messages ==========
+    /// User toggled writing .xmp sidecars on every edit
+    SidecarWritingToggled(bool),
+    /// Periodic check for a sidecar whose edits have paused long enough
+    SidecarTick,
+    
+    // ========== Preferences Messages ==========
+    /// User picked a graphics API for the GPU context
+    GpuBackendChanged(state::settings::GpuBackend),
+    /// User picked the discrete or integrated GPU
+    GpuPowerChanged(state::settings::GpuPower),
+    /// User picked the UI theme
+    UiThemeChanged(state::settings::UiTheme),
+    /// User picked the accent color
+    AccentColorChanged(state::settings::AccentColor),
+    /// The GPU context is up: its adapter description (or why it failed)
+    GpuAdapterLoaded(Result<String, String>),
+    /// User clicked "Choose…" for the cache folder
+    ChooseCacheDir,
+    /// User went back to the default cache folder
+    ResetCacheDir,
+    /// User clicked Clear Cache (deletes every cached file, thumbnails regenerate)
+    ClearCache,
+    /// The cache folders were emptied: files deleted, images queued for thumbnails
+    CacheCleared(usize, usize),
+    /// The cache folders were measured: total bytes
+    CacheSizeMeasured(u64),
+    /// User clicked "Choose…" for the photos folder (catalog backups)
+    ChoosePhotosRoot,
+    /// User cleared the photos folder (backups store absolute paths)
+    ResetPhotosRoot,
+    /// User clicked Export Catalog (writes a JSON backup)
+    ExportCatalog,
+    /// User clicked Import Catalog (reads a JSON backup into the catalog)
+    ImportCatalog,
+    /// User edited the custom RAW extensions field
+    CustomExtensionsChanged(String),
+    /// User toggled rendering thumbnails of edited images
+    EditedThumbnailsToggled(bool),
+    /// User toggled selecting the next image after a reject
+    AutoAdvanceToggled(bool),
+    /// User toggled showing the embedded preview while the RAW decodes
+    FastPreviewToggled(bool),
+    /// An edited image's thumbnail was rendered to this file
+    EditedThumbnailRendered(i64, PathBuf),
 }
 
 /// Phase 23: Async database loading
-/// Loads the database and images in the background to avoid blocking the UI
-/// Returns only the images Vec - Library will be created on main thread
-async fn load_database_async() -> Result<Vec<ImageData>, String> {
+/// Loads the database, images and settings in the background to avoid blocking the UI
+/// Returns only the images Vec and settings - Library will be created on main thread
+async fn load_database_async() -> Result<(Vec<ImageData>, state::settings::AppSettings), state::library::LibraryError> {
     // Use spawn_blocking because rusqlite is synchronous
     tokio::task::spawn_blocking(|| {
         // Initialize the database
-        let library = state::library::Library::new()
-            .map_err(|e| format!("Failed to initialize database: {:?}", e))?;
+        let library = state::library::Library::new()?;
         
-        // Verify thumbnails exist on disk (reset if deleted)
+        // Settings first: the cache folder must be set before anything is cached
+        let settings = state::settings::AppSettings::load(&library);
+        state::settings::set_cache_dir(settings.cache_dir.clone());
+        // Likewise the GPU choice, before the first pipeline creates the context
+        gpu::context::set_preferences(settings.gpu_backend, settings.gpu_power);
+        
+        // Requeue thumbnails a forced close cut off, then verify the rest
+        // are on disk and whole (reset if deleted, empty or truncated)
+        let _ = library.reset_interrupted_caches();
         let _ = library.verify_thumbnails();
         
         // Verify RAW files exist on disk (mark as deleted if missing)
         let _ = library.verify_files();
         
         // Load all images from the database
-        let images = library.get_all_images()
-            .map_err(|e| format!("Failed to load images: {:?}", e))?;
+        let images = library.get_all_images()?;
         
         println!("🎨 RAW Editor initialized with {} images", images.len());
         
-        Ok(images)
+        Ok((images, settings))
     })
     .await
-    .map_err(|e| format!("Database task failed: {:?}", e))?
+    .map_err(|e| state::library::LibraryError::Other(format!("Database task failed: {:?}", e)))?
+}
+
+/// Back up a damaged catalog and recreate it (in the background)
+async fn recover_database_async() -> Result<state::library::Recovery, state::library::LibraryError> {
+    tokio::task::spawn_blocking(state::library::Library::recover)
+        .await
+        .map_err(|e| state::library::LibraryError::Other(format!("Recovery task failed: {:?}", e)))?
 }
 
 impl RawEditor {
     /// Phase 23: Create a new instance of the application (INSTANT!)
     /// The database now loads in the background to show splash screen immediately
-    fn new() -> (Self, Task<Message>) {
+    fn new(window_state: state::settings::WindowState) -> (Self, Task<Message>) {
         println!("🚀 RAW Editor starting (instant splash screen)...");
         
         // Initialize preview cache directory (fast)
         let preview_cache_dir = raw::preview::get_preview_cache_dir();
+        let shutdown = CancelToken::default();
         
         (
             RawEditor { 
                 library: None, // Phase 23: Database loads in background
+                db_error: None,
                 status: "Loading database...".to_string(),
                 images: Vec::new(), // Empty until database loads
                 selected_image_id: None,
+                selected_ids: HashSet::new(),
+                keyboard_modifiers: iced::keyboard::Modifiers::default(),
                 preview_cache_dir,
                 current_tab: AppTab::Library,
                 current_edit_params: state::edit::EditParams::default(),
                 editor_status: EditorStatus::NoSelection,
-                histogram_data: std::cell::RefCell::new([[0; 256]; 3]),
+                histogram_data: std::cell::RefCell::new([[0; 256]; 4]),
                 histogram_cache: iced::widget::canvas::Cache::default(),
                 histogram_enabled: false, // Phase 22: Off by default
+                histogram_mode: ui::histogram::HistogramMode::default(),
+                histogram_log_scale: false,
                 show_before: false, // Phase 24: Show edited version by default
+                fullscreen_preview: false,
+                compare_mode: CompareMode::default(),
+                split_x: 0.5,
+                dragging_split: false,
                 zoom: 1.0, // Phase 25: Start at 100% zoom
+                fit_zoom: 1.0,
                 pan_offset: cgmath::Vector2::new(0.0, 0.0), // Phase 25: Centered
                 canvas_cache: iced::widget::canvas::Cache::default(), // Phase 25: Canvas cache
+                preview_frame: ui::canvas::FrameCache::default(),
+                compare_with: None,
+                compare_status: EditorStatus::NoSelection,
+                compare_params: state::edit::EditParams::default(),
+                compare_frame: ui::canvas::FrameCache::default(),
                 is_dragging: false, // Phase 25: Not dragging initially
                 last_cursor_position: None, // Phase 25: No cursor position yet
                 last_click_time: None, // Phase 26: No click yet
-                viewport_size: (1280.0, 854.0), // Phase 26: Default viewport size (will be updated)
+                last_label_press: None,
+                viewport_size: std::cell::Cell::new((1280.0, 854.0)), // Phase 26: Default viewport size (will be updated)
+                write_sidecars: true, // Edits survive catalog loss by default
+                sidecar_pending: None,
+                edited_thumbnails: false,
+                auto_advance: false,
+                fast_preview: true,
+                thumbnail_refresh: HashSet::new(),
+                cache_dir: None,      // Default until the settings load
+                cache_size: None,
+                photos_root: None,
+                custom_extensions: Vec::new(),
+                custom_extensions_input: String::new(),
+                gpu_backend: state::settings::GpuBackend::default(),
+                gpu_power: state::settings::GpuPower::default(),
+                ui_theme: state::settings::UiTheme::default(),
+                accent_color: state::settings::AccentColor::default(),
+                gpu_adapter: None, // Asked for when Preferences opens
+                software_rendering: false, // Known once the first pipeline exists
+                edit_history: state::edit::EditHistory::default(),
+                wb_picker_active: false, // Clicks pan until the picker is turned on
+                film_base_picker_active: false,
+                pixel_sample: None,
+                show_sensor_values: false,
+                library_sort: state::data::SortOrder::default(), // Newest imports first
+                library_filter: state::data::LibraryFilter::default(), // Show everything
+                group_by_date: false,
+                date_sections: Vec::new(),
+                collapsed_days: HashSet::new(),
+                selected_metadata: state::exif::ImageMetadata::default(), // Nothing selected yet
+                crop_tool_active: false,
+                crop_aspect: state::edit::CropAspect::default(), // Free
+                crop_lock_ratio: false,
+                crop_guide: ui::crop::CropGuide::default(), // Rule of thirds
+                straighten_active: false,
+                hsl_panel_open: false, // Collapsed: 24 sliders is a lot of scrolling
+                mask_tool_active: false,
+                selected_mask: None,
+                detail_preview: false,
+                show_clipping: false,
+                preview_quality: state::settings::PreviewQuality::default(),
+                thumbnail_size: state::settings::DEFAULT_THUMBNAIL_SIZE,
+                pipeline_cache: gpu::PipelineCache::new(gpu::cache::PIPELINE_CACHE_SIZE),
+                left_develop_at: None,
+                delete_pending: None,
+                presets: Vec::new(), // Loaded with the database
+                preset_name_input: String::new(),
+                preset_overwrite_pending: None,
+                snapshots: Vec::new(),
+                keywords: Vec::new(),
+                keyword_input: String::new(),
+                keyword_search: String::new(),
+                import_scope: state::data::ImportScope::default(), // Whole catalog
+                session_import_batches: Vec::new(),
+                snapshot_name_input: String::new(),
+                compare_snapshot: None,
+                clipboard_params: None, // Nothing copied yet
+                batch_export_format: state::export::ExportFormat::default(), // JPEG
+                batch_export_template: state::export::DEFAULT_TEMPLATE.to_string(),
+                batch_export_progress: None, // No batch running
+                batch_export_current: None,
+                exports_in_flight: Vec::new(),
+                export_progress: None,
+                export_cancel: CancelToken::default(),
+                notifications: Vec::new(),
+                next_notification_id: 0,
+                progress: None,
+                importing: false,
+                cache_queue_active: false,
+                quit_pending: None,
+                thumbnail_stop: shutdown.child(),
+                shutdown,
+                dropped_paths: Vec::new(),
+                spinner_frame: 0,
+                thumbnail_worker_active: false,
+                cache_clearing: None,
+                export_options: state::export::ExportOptions::default(), // JPEG quality 90
+                include_metadata: true,
+                export_metadata: state::exif::ExportMetadata::default(), // No GPS or maker notes
+                window_state,
             },
             // Phase 23: Load database in background
             Task::perform(
@@ -279,98 +991,283 @@ impl RawEditor {
             // Phase 23: Handle database loading completion
             Message::DatabaseLoaded(result) => {
                 match result {
-                    Ok(images) => {
+                    Ok((images, settings)) => {
                         // Create Library on main thread (can't be sent across threads)
                         match state::library::Library::new() {
                             Ok(library) => {
                                 let image_count = images.len();
+                                self.presets = library.list_presets().unwrap_or_default();
+                                self.preview_quality = settings.preview_quality;
+                                self.thumbnail_size = settings.thumbnail_size;
+                                self.batch_export_format = settings.export_format;
+                                self.batch_export_template = settings.export_template;
+                                self.export_options.jpeg_quality = settings.jpeg_quality;
+                                self.include_metadata = settings.include_metadata;
+                                self.export_metadata = settings.export_metadata;
+                                self.export_options.embed_recipe = settings.embed_recipe;
+                                self.write_sidecars = settings.write_sidecars;
+                                self.edited_thumbnails = settings.edited_thumbnails;
+                                self.auto_advance = settings.auto_advance;
+                                self.fast_preview = settings.fast_preview;
+                                self.cache_dir = settings.cache_dir;
+                                self.photos_root = settings.photos_root;
+                                self.custom_extensions_input = settings.custom_extensions.join(", ");
+                                self.custom_extensions = settings.custom_extensions;
+                                self.gpu_backend = settings.gpu_backend;
+                                self.gpu_power = settings.gpu_power;
+                                self.ui_theme = settings.ui_theme;
+                                self.accent_color = settings.accent_color;
                                 self.library = Some(library);
                                 self.images = images;
                                 self.status = format!("Loaded {} images.", image_count);
                                 println!("✅ Database loaded successfully ({} images)", image_count);
                                 
                                 // Phase 23: Maximize window using native OS maximize
-                                use iced::window;
-                                // let maximize_window = window::get_latest()
-                                //     .and_then(|id| window::change_mode(id, window::Mode::Maximized));
-                                let maximize_window =window::get_latest()
-                                    .and_then(|id| window::maximize(id, true));
-
-                                println!("🔲 Maximizing window...");
+                                // (first launch, or it was maximized when the app closed)
+                                let maximize_window = if self.window_state.maximized {
+                                    println!("🔲 Maximizing window...");
+                                    window::get_latest().and_then(|id| window::maximize(id, true))
+                                } else {
+                                    Task::none()
+                                };
                                 
-                                // Start thumbnail generation now that database is ready
-                                if let Some(lib) = &self.library {
-                                    let db_path = lib.path().clone();
-                                    return Task::batch(vec![
-                                        maximize_window,
-                                        Task::perform(
-                                            generate_thumbnails_async(db_path),
-                                            Message::ThumbnailGenerated,
-                                        ),
-                                    ]);
-                                }
+                                // Start the thumbnail worker now that database is ready
+                                // (see subscription(); it stops when the queues are empty)
+                                self.thumbnail_worker_active = true;
                                 
-                                // Just maximize if no thumbnails to generate
-                                return maximize_window;
+                                return Task::batch([maximize_window, self.restore_last_session()]);
                             }
                             Err(e) => {
-                                self.status = format!("Failed to create library: {:?}", e);
-                                eprintln!("❌ Failed to create library: {:?}", e);
+                                eprintln!("❌ Failed to create library: {}", e);
+                                self.status = format!("Failed to open the catalog: {}", e);
+                                self.db_error = Some(e);
                             }
                         }
                     }
                     Err(e) => {
-                        self.status = format!("Failed to load database: {}", e);
                         eprintln!("❌ Database loading failed: {}", e);
+                        self.status = format!("Failed to open the catalog: {}", e);
+                        self.db_error = Some(e);
+                    }
+                }
+                Task::none()
+            }
+            Message::RetryDatabase => {
+                self.db_error = None;
+                self.status = "Loading database...".to_string();
+                Task::perform(load_database_async(), Message::DatabaseLoaded)
+            }
+            Message::RecoverDatabase => {
+                self.db_error = None;
+                self.status = "Backing up the damaged catalog...".to_string();
+                Task::perform(recover_database_async(), Message::DatabaseRecovered)
+            }
+            Message::DatabaseRecovered(result) => match result {
+                Ok(recovery) => {
+                    self.status = "Loading database...".to_string();
+                    // Only what really couldn't be brought back is reported lost
+                    let mut lost = Vec::new();
+                    if recovery.images_lost > 0 {
+                        lost.push(format!("{} unreadable images", recovery.images_lost));
+                    }
+                    if recovery.edits_lost > 0 {
+                        lost.push(format!("the edits of {} images", recovery.edits_lost));
                     }
+                    let lost = if lost.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" Lost: {}.", lost.join(", "))
+                    };
+                    self.notify(
+                        if lost.is_empty() { Severity::Info } else { Severity::Error },
+                        format!(
+                            "The catalog was damaged and has been recreated with {} images ({} edited, {} restored from XMP sidecars).{} Backup: {}",
+                            recovery.images,
+                            recovery.edits_salvaged,
+                            recovery.edits_from_sidecars,
+                            lost,
+                            recovery.backup.display()
+                        ),
+                    );
+                    Task::perform(load_database_async(), Message::DatabaseLoaded)
+                }
+                Err(e) => {
+                    eprintln!("❌ Catalog recovery failed: {}", e);
+                    self.status = format!("Couldn't recreate the catalog: {}", e);
+                    self.db_error = Some(e);
+                    Task::none()
+                }
+            },
+            
+            Message::WindowCloseRequested(id) => {
+                // Ask first if closing now would cut background work off
+                if self.background_work().is_empty() {
+                    close_window(id)
+                } else {
+                    self.quit_pending = Some(id);
+                    Task::none()
                 }
+            }
+            Message::ConfirmQuit => {
+                println!("🛑 Stopping background work before quitting: {}", self.background_work().join(", "));
+                self.shutdown.cancel();
+                self.status = "Finishing up before quitting...".to_string();
+                self.finish_quit_if_idle()
+            }
+            Message::ForceQuit => {
+                let Some(id) = self.quit_pending.take() else {
+                    return Task::none();
+                };
+                // Whatever exports were being written are incomplete
+                self.shutdown.cancel();
+                let partial = self.exports_in_flight.iter().chain(&self.batch_export_current);
+                for path in partial {
+                    match std::fs::remove_file(path) {
+                        Ok(()) => println!("🗑️  Removed partial export {}", path.display()),
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                        Err(e) => eprintln!("⚠️  Couldn't remove partial export {}: {}", path.display(), e),
+                    }
+                }
+                close_window(id)
+            }
+            Message::CancelQuit => {
+                self.quit_pending = None;
                 Task::none()
             }
+            Message::WindowClosing { id, maximized, size, position } => {
+                self.flush_sidecar();
+                // Maximized: keep the size it un-maximizes to, not the screen size
+                let window_state = if maximized {
+                    state::settings::WindowState { maximized: true, ..self.window_state }
+                } else {
+                    state::settings::WindowState {
+                        width: size.width,
+                        height: size.height,
+                        position: position.map(|p| (p.x, p.y)),
+                        maximized: false,
+                    }
+                };
+                
+                // Commit the open image's edits, and requeue thumbnails the
+                // worker didn't get to finish
+                self.save_current_edits();
+                if let Some(library) = &self.library {
+                    if let Err(e) = library.reset_interrupted_caches() {
+                        eprintln!("⚠️  Failed to requeue interrupted thumbnails: {}", e);
+                    }
+                    let last_image = self.selected_image_id.map(|id| id.to_string()).unwrap_or_default();
+                    let saved = library.set_setting(state::settings::WINDOW_KEY, &window_state.as_setting())
+                        .and_then(|_| library.set_setting(state::settings::LAST_IMAGE_KEY, &last_image))
+                        .and_then(|_| library.set_setting(state::settings::LAST_TAB_KEY, self.current_tab.as_setting()));
+                    if let Err(e) = saved {
+                        eprintln!("⚠️  Failed to save window state: {}", e);
+                    }
+                }
+                
+                println!("👋 Closing ({}x{}{})", size.width, size.height, if maximized { ", maximized" } else { "" });
+                window::close(id)
+            }
             
             Message::ImportFolder => {
                 // Phase 23: Only allow imports if database is loaded
-                if let Some(library) = &self.library {
+                if self.library.is_some() {
                     // Show the native folder picker dialog
                     let folder = FileDialog::new()
                         .set_title("Select Folder with RAW Photos")
                         .pick_folder();
                     
                     if let Some(folder_path) = folder {
-                        // Update status to show we're importing
                         self.status = format!("Importing from {}...", folder_path.display());
-                        
-                        // Get the database path for the background thread
-                        let db_path = library.path().clone();
-                        
-                        // Launch async import task
-                        return Task::perform(
-                            import_folder_async(folder_path, db_path),
-                            Message::ImportComplete,
-                        );
+                        return self.start_import(vec![folder_path]);
+                    }
+                }
+                
+                Task::none()
+            }
+            Message::ImportFiles => {
+                if self.library.is_some() {
+                    let files = FileDialog::new()
+                        .set_title("Select RAW Photos")
+                        .add_filter("RAW photos", &self.raw_extensions())
+                        .pick_files();
+                    
+                    if let Some(files) = files {
+                        self.status = format!("Importing {} files...", files.len());
+                        return self.start_import(files);
                     }
                 }
                 
                 Task::none()
             }
+            Message::FileDropped(path) => {
+                // winit delivers a multi-file drop as one event per path;
+                // collect them and let the subscription send ImportDropped
+                self.dropped_paths.push(path);
+                Task::none()
+            }
+            Message::ImportDropped => {
+                let paths = std::mem::take(&mut self.dropped_paths);
+                if paths.is_empty() || self.library.is_none() {
+                    return Task::none();
+                }
+                
+                // Exported PNGs bring their edit recipe back instead of being imported
+                let (recipes, paths): (Vec<PathBuf>, Vec<PathBuf>) = paths
+                    .into_iter()
+                    .partition(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("png")));
+                for path in &recipes {
+                    self.apply_dropped_recipe(path);
+                }
+                if paths.is_empty() {
+                    return Task::none();
+                }
+                
+                println!("📥 {} paths dropped on the window", paths.len());
+                self.status = format!("Importing {} dropped items...", paths.len());
+                self.start_import(paths)
+            }
+            Message::ImportProgress { done, total } => {
+                self.progress = Some((done, total));
+                self.status = format!("Importing: {}/{} files", done, total);
+                Task::none()
+            }
             Message::ImportComplete(result) => {
+                // The cache queue below starts its own progress count
+                self.progress = None;
+                self.importing = false;
+                if self.shutdown.is_cancelled() {
+                    return self.finish_quit_if_idle();
+                }
+                
+                if result.imported_count > 0 {
+                    self.session_import_batches.push(result.batch_id);
+                }
+                
                 // Phase 23: Only process if database is loaded
                 if let Some(library) = &self.library {
                     // Reload images from database to show newly imported files
-                    self.images = library.get_all_images().unwrap_or_default();
+                    (self.images, self.date_sections) = self.library_grid(library);
                     
                     // Update status with import results
                     self.status = format!(
                         "✅ Import complete! Added {} images, skipped {} duplicates. Total: {} images.",
                         result.imported_count, result.skipped_count, self.images.len()
                     );
+                    if result.unsupported_count > 0 {
+                        self.status.push_str(&format!(
+                            " {} files in unsupported RAW formats were left out.",
+                            result.unsupported_count
+                        ));
+                    }
                     
                     println!(
-                        "📊 Import summary: {} new, {} skipped, {} total",
-                        result.imported_count, result.skipped_count, self.images.len()
+                        "📊 Import summary: {} new, {} skipped, {} unsupported, {} total",
+                        result.imported_count, result.skipped_count, result.unsupported_count, self.images.len()
                     );
                     
                     // Phase 28: Start multi-tier cache processing for newly imported images
                     let db_path = library.path().clone();
+                    self.cache_queue_active = true;
                     return Task::perform(
                         process_cache_async(db_path),
                         Message::CacheProcessed,
@@ -378,57 +1275,51 @@ impl RawEditor {
                 }
                 Task::none()
             }
-            Message::ThumbnailGenerated(result) => {
+            Message::ThumbnailProgress(result) => {
+                // Quitting: the worker stops after this batch
+                if self.shutdown.is_cancelled() {
+                    self.thumbnail_worker_active = false;
+                    if self.cache_clearing == Some(CacheClearing::StoppingWorker) {
+                        self.cache_clearing = None;
+                    }
+                    return self.finish_quit_if_idle();
+                }
+                
+                // Clear Cache was waiting for the worker's last batch
+                if self.cache_clearing == Some(CacheClearing::StoppingWorker) && result.last {
+                    self.thumbnail_worker_active = false;
+                    return self.start_cache_clear();
+                }
+                
                 // Phase 23: Only process if database is loaded
                 if let Some(library) = &self.library {
                     // Always reload images to show updated thumbnail in the grid
-                    self.images = library.get_all_images().unwrap_or_default();
-                    
-                    // Check both fast and slow queues
-                    let fast_queue_count: i64 = library.conn()
-                        .query_row(
-                            "SELECT COUNT(*) FROM images WHERE cache_status = 'pending'",
-                            [],
-                            |row| row.get(0)
-                        )
-                        .unwrap_or(0);
-                    
-                    let slow_queue_count: i64 = library.conn()
-                        .query_row(
-                            "SELECT COUNT(*) FROM images WHERE cache_status = 'needs_slow'",
-                            [],
-                            |row| row.get(0)
-                        )
-                        .unwrap_or(0);
-                    
-                    if fast_queue_count > 0 {
-                        // Still processing fast queue (high priority)
-                        self.status = format!(
-                            "⚡ Fast queue: {} remaining (slow queue: {})", 
-                            fast_queue_count, slow_queue_count
-                        );
-                        
-                        let db_path = library.path().clone();
-                        return Task::perform(
-                            generate_thumbnails_async(db_path),
-                            Message::ThumbnailGenerated,
-                        );
-                } else if slow_queue_count > 0 {
+                    (self.images, self.date_sections) = self.library_grid(library);
+                }
+                
+                let remaining = result.fast_remaining + result.slow_remaining;
+                self.progress = queue_progress(self.progress, remaining, result.generated_count);
+                
+                // Thumbnails per second for the batch that just finished
+                let secs = result.elapsed.as_secs_f64();
+                let throughput = if secs > 0.0 { result.generated_count as f64 / secs } else { 0.0 };
+                
+                if result.fast_remaining > 0 {
+                    // Still processing fast queue (high priority)
+                    self.status = format!(
+                        "⚡ Fast queue: {} remaining (slow queue: {}) - {:.1} thumbs/s",
+                        result.fast_remaining, result.slow_remaining, throughput
+                    );
+                } else if result.slow_remaining > 0 {
                     // Fast queue empty, processing slow queue (low priority)
                     self.status = format!(
-                        "🔥 Slow queue: {} remaining (RAW decode)", 
-                        slow_queue_count
+                        "🔥 Slow queue: {} remaining (RAW decode)",
+                        result.slow_remaining
                     );
-                    
-                        let db_path = library.path().clone();
-                        return Task::perform(
-                            generate_thumbnails_async(db_path),
-                            Message::ThumbnailGenerated,
-                        );
-                    } else {
-                        // Both queues empty - all done!
-                        self.status = format!("✅ All thumbnails generated! ({} images)", self.images.len());
-                    }
+                } else {
+                    // Both queues empty - all done! The worker has stopped itself
+                    self.thumbnail_worker_active = false;
+                    self.status = format!("✅ All thumbnails generated! ({} images)", self.images.len());
                 }
                 
                 Task::none()
@@ -467,7 +1358,7 @@ impl RawEditor {
                     }
                     
                     // Reload images to update UI
-                    self.images = library.get_all_images().unwrap_or_default();
+                    (self.images, self.date_sections) = self.library_grid(library);
                     
                     // Check if there are more pending images
                     let pending_count: i64 = library.conn()
@@ -477,8 +1368,13 @@ impl RawEditor {
                             |row| row.get(0)
                         )
                         .unwrap_or(0);
+                    self.progress = queue_progress(self.progress, pending_count as usize, 1);
                     
-                    if pending_count > 0 {
+                    if self.shutdown.is_cancelled() {
+                        // Quitting: the rest waits for next launch
+                        self.cache_queue_active = false;
+                        return self.finish_quit_if_idle();
+                    } else if pending_count > 0 {
                         // Update status with progress
                         self.status = format!("📦 Processing cache: {} remaining", pending_count);
                         
@@ -490,6 +1386,7 @@ impl RawEditor {
                         );
                     } else {
                         // All done!
+                        self.cache_queue_active = false;
                         self.status = format!("✅ All cache tiers generated! ({} images)", self.images.len());
                         println!("🎉 Phase 28: All images cached with 3 tiers!");
                     }
@@ -498,9 +1395,23 @@ impl RawEditor {
                 Task::none()
             }
             Message::ImageSelected(image_id) => {
+                // Moving on from an edited image: its thumbnail can catch up
+                let refresh = if self.selected_image_id != Some(image_id) {
+                    self.refresh_edited_thumbnails()
+                } else {
+                    Task::none()
+                };
+                
                 // Phase 20: INSTANT selection - just update state, don't load anything!
                 // Loading is deferred until user switches to Develop tab
                 self.selected_image_id = Some(image_id);
+                self.selected_ids = HashSet::from([image_id]);
+                self.exit_compare();
+                self.pixel_sample = None;
+                // Next/Previous can step into a folded day section: unfold it
+                if let Some(day) = self.day_section_of(image_id) {
+                    self.collapsed_days.remove(&day);
+                }
                 println!("✨ Selected image ID: {} (instant!)", image_id);
                 
                 // Phase 25: Clear canvas cache since we're switching to a different image
@@ -514,7 +1425,15 @@ impl RawEditor {
                     if !self.current_edit_params.is_unedited() {
                         println!("📝 Loaded existing edits for image {}", image_id);
                     }
+                    
+                    self.selected_metadata = library.get_metadata(image_id).unwrap_or_default();
+                    self.snapshots = library.list_snapshots(image_id).unwrap_or_default();
+                    self.keywords = library.get_keywords(image_id).unwrap_or_default();
                 }
+                self.compare_snapshot = None;
+                
+                // History is per image - start fresh from the loaded state
+                self.edit_history.reset(self.current_edit_params);
                 
                 // Phase 24: If already on Develop tab, reload RAW data for new image
                 if self.current_tab == AppTab::Develop {
@@ -527,25 +1446,55 @@ impl RawEditor {
                     
                     if needs_load {
                         println!("🔄 Loading RAW data for image {}...", image_id);
-                        
-                        // Find the image and start loading
-                        if let Some(img) = self.images.iter().find(|i| i.id == image_id) {
-                            let raw_path = img.path.clone();
-                            
-                            // Set editor status to loading
-                            self.editor_status = EditorStatus::Loading(image_id);
-                            
-                            // Load RAW sensor data for GPU processing
-                            return Task::perform(
-                                raw::loader::load_raw_data(raw_path),
-                                Message::RawDataLoaded,
-                            );
-                        }
+                        return Task::batch([refresh, self.load_pipeline(image_id)]);
                     } else {
                         println!("⚡ Pipeline already loaded for image {}", image_id);
                     }
                 }
                 
+                refresh
+            }
+            Message::ToggleSelection(image_id) => {
+                if !self.selected_ids.contains(&image_id) {
+                    // Add it and make it the primary selection
+                    let mut selected = std::mem::take(&mut self.selected_ids);
+                    selected.insert(image_id);
+                    let task = self.update(Message::ImageSelected(image_id));
+                    self.selected_ids = selected;
+                    return task;
+                }
+                
+                // Remove it, but never empty the selection
+                if self.selected_ids.len() > 1 {
+                    self.selected_ids.remove(&image_id);
+                    if self.selected_image_id == Some(image_id) {
+                        // Primary moves to the first remaining image in grid order
+                        if let Some(next_id) = self.selection_in_grid_order().first().copied() {
+                            let selected = std::mem::take(&mut self.selected_ids);
+                            let task = self.update(Message::ImageSelected(next_id));
+                            self.selected_ids = selected;
+                            return task;
+                        }
+                    }
+                }
+                Task::none()
+            }
+            Message::RangeSelectTo(image_id) => {
+                let Some(anchor_id) = self.selected_image_id else {
+                    return self.update(Message::ImageSelected(image_id));
+                };
+                // The primary selection stays the anchor so the range can be re-dragged
+                let range = state::data::ids_between(&self.images, anchor_id, image_id);
+                if !range.is_empty() {
+                    self.selected_ids = range.into_iter().collect();
+                    println!("🔲 Selected {} images", self.selected_ids.len());
+                }
+                Task::none()
+            }
+            Message::ModifiersChanged(modifiers) => {
+                // Alt also solos the last adjusted slider (see solo_key); the
+                // preview re-renders since the params it's keyed on change
+                self.keyboard_modifiers = modifiers;
                 Task::none()
             }
             Message::PreviewGenerated(_result) => {
@@ -553,10 +1502,42 @@ impl RawEditor {
                 // This message is never sent anymore, kept for compilation compatibility
                 Task::none()
             }
+            Message::OpenInDevelop => {
+                if self.selected_image_id.is_some() && self.current_tab != AppTab::Develop {
+                    return self.update(Message::TabChanged(AppTab::Develop));
+                }
+                Task::none()
+            }
             Message::TabChanged(tab) => {
+                // Leaving Develop: let go of the active pipeline. It stays in the LRU
+                // cache for a quick return, and the cache is released when idle
+                let mut refresh = Task::none();
+                if self.current_tab == AppTab::Develop && tab != AppTab::Develop {
+                    if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                        println!("💤 Left Develop: active pipeline ({:.1} MB) moved to cache; {} cached ({:.1} MB) released after {}s idle",
+                                 pipeline.gpu_memory_bytes() as f64 / 1_048_576.0,
+                                 self.pipeline_cache.len(),
+                                 self.cached_gpu_memory_bytes() as f64 / 1_048_576.0,
+                                 gpu::cache::IDLE_RELEASE.as_secs());
+                    }
+                    self.editor_status = EditorStatus::NoSelection;
+                    self.exit_compare();
+                    self.canvas_cache.clear();
+                    self.preview_frame.clear();
+                    self.left_develop_at = Some(std::time::Instant::now());
+                    refresh = Task::batch([self.refresh_edited_thumbnails(), self.set_fullscreen_preview(false)]);
+                }
+                
                 // Phase 20: Deferred loading trigger!
                 self.current_tab = tab;
                 
+                // Edits made in Develop change the grid's edited badges
+                if tab == AppTab::Library {
+                    if let Some(library) = &self.library {
+                        (self.images, self.date_sections) = self.library_grid(library);
+                    }
+                }
+                
                 // Only load when switching TO Develop tab (not FROM it)
                 if tab == AppTab::Develop {
                     if let Some(image_id) = self.selected_image_id {
@@ -569,33 +1550,38 @@ impl RawEditor {
                         
                         if needs_load {
                             println!("🔄 Switching to Develop tab - loading image {}...", image_id);
-                            
-                            // Find the image and start loading
-                            if let Some(img) = self.images.iter().find(|i| i.id == image_id) {
-                                let raw_path = img.path.clone();
-                                
-                                // Set editor status to loading
-                                self.editor_status = EditorStatus::Loading(image_id);
-                                
-                                // Load RAW sensor data for GPU processing (this is the slow 3-second operation)
-                                return Task::perform(
-                                    raw::loader::load_raw_data(raw_path),
-                                    Message::RawDataLoaded,
-                                );
-                            }
+                            return self.load_pipeline(image_id);
                         } else {
                             println!("⚡ Pipeline already loaded for image {}", image_id);
                         }
                     }
                 }
                 
-                Task::none()
+                // Preferences shows how big the cache is
+                if tab == AppTab::Preferences {
+                    refresh = Task::batch([refresh, measure_cache()]);
+                }
+                
+                // Preferences shows which GPU is in use (creating the context if needed)
+                if tab == AppTab::Preferences && self.gpu_adapter.is_none() {
+                    return Task::batch([refresh, Task::perform(
+                        async {
+                            gpu::GpuContext::shared()
+                                .await
+                                .map(|gpu| gpu::context::describe_adapter(gpu.adapter_info()))
+                        },
+                        Message::GpuAdapterLoaded,
+                    )]);
+                }
+                
+                refresh
             }
             
             // ========== Edit Parameter Slider Handlers ==========
             
             Message::ExposureChanged(value) => {
                 self.current_edit_params.exposure = value;
+                self.edit_history.record(self.current_edit_params, "exposure");
                 self.save_current_edits();
                 // Phase 25: Update GPU uniforms and invalidate canvas cache
                 if let EditorStatus::Ready(pipeline) = &self.editor_status {
@@ -606,6 +1592,7 @@ impl RawEditor {
             }
             Message::ContrastChanged(value) => {
                 self.current_edit_params.contrast = value;
+                self.edit_history.record(self.current_edit_params, "contrast");
                 self.save_current_edits();
                 // Phase 25: Update GPU uniforms and invalidate canvas cache
                 if let EditorStatus::Ready(pipeline) = &self.editor_status {
@@ -614,28 +1601,33 @@ impl RawEditor {
                 }
                 Task::none()
             }
-            Message::HighlightsChanged(value) => {
-                self.current_edit_params.highlights = value;
-                self.save_current_edits();
-                // Phase 25: Update GPU uniforms and invalidate canvas cache
-                if let EditorStatus::Ready(pipeline) = &self.editor_status {
-                    pipeline.update_uniforms(&self.current_edit_params);
-                    self.canvas_cache.clear();
+            Message::SliderLabelPressed(reset) => {
+                let now = std::time::Instant::now();
+                let slider = std::mem::discriminant(reset.as_ref());
+                let is_double_click = self.last_label_press
+                    .is_some_and(|(last, at)| last == slider && now.duration_since(at).as_millis() < 300);
+                if !is_double_click {
+                    self.last_label_press = Some((slider, now));
+                    return Task::none();
                 }
-                Task::none()
+                self.last_label_press = None;
+                // The reset is one undo step, not merged into the drag before it
+                self.edit_history.seal();
+                self.update(*reset)
             }
-            Message::ShadowsChanged(value) => {
-                self.current_edit_params.shadows = value;
+            Message::ToneModeChanged(mode) => {
+                self.current_edit_params.tone_mode = mode;
+                self.edit_history.record(self.current_edit_params, "tone mode");
                 self.save_current_edits();
-                // Phase 25: Update GPU uniforms and invalidate canvas cache
                 if let EditorStatus::Ready(pipeline) = &self.editor_status {
                     pipeline.update_uniforms(&self.current_edit_params);
                     self.canvas_cache.clear();
                 }
                 Task::none()
             }
-            Message::WhitesChanged(value) => {
-                self.current_edit_params.whites = value;
+            Message::HighlightsChanged(value) => {
+                self.current_edit_params.highlights = value;
+                self.edit_history.record(self.current_edit_params, "highlights");
                 self.save_current_edits();
                 // Phase 25: Update GPU uniforms and invalidate canvas cache
                 if let EditorStatus::Ready(pipeline) = &self.editor_status {
@@ -644,18 +1636,19 @@ impl RawEditor {
                 }
                 Task::none()
             }
-            Message::BlacksChanged(value) => {
-                self.current_edit_params.blacks = value;
+            Message::HighlightRecoveryChanged(value) => {
+                self.current_edit_params.highlight_recovery = value;
+                self.edit_history.record(self.current_edit_params, "highlight_recovery");
                 self.save_current_edits();
-                // Phase 25: Update GPU uniforms and invalidate canvas cache
                 if let EditorStatus::Ready(pipeline) = &self.editor_status {
                     pipeline.update_uniforms(&self.current_edit_params);
                     self.canvas_cache.clear();
                 }
                 Task::none()
             }
-            Message::VibranceChanged(value) => {
-                self.current_edit_params.vibrance = value;
+            Message::ShadowsChanged(value) => {
+                self.current_edit_params.shadows = value;
+                self.edit_history.record(self.current_edit_params, "shadows");
                 self.save_current_edits();
                 // Phase 25: Update GPU uniforms and invalidate canvas cache
                 if let EditorStatus::Ready(pipeline) = &self.editor_status {
@@ -664,8 +1657,9 @@ impl RawEditor {
                 }
                 Task::none()
             }
-            Message::SaturationChanged(value) => {
-                self.current_edit_params.saturation = value;
+            Message::WhitesChanged(value) => {
+                self.current_edit_params.whites = value;
+                self.edit_history.record(self.current_edit_params, "whites");
                 self.save_current_edits();
                 // Phase 25: Update GPU uniforms and invalidate canvas cache
                 if let EditorStatus::Ready(pipeline) = &self.editor_status {
@@ -674,8 +1668,9 @@ impl RawEditor {
                 }
                 Task::none()
             }
-            Message::TemperatureChanged(value) => {
-                self.current_edit_params.temperature = value;
+            Message::BlacksChanged(value) => {
+                self.current_edit_params.blacks = value;
+                self.edit_history.record(self.current_edit_params, "blacks");
                 self.save_current_edits();
                 // Phase 25: Update GPU uniforms and invalidate canvas cache
                 if let EditorStatus::Ready(pipeline) = &self.editor_status {
@@ -684,753 +1679,4312 @@ impl RawEditor {
                 }
                 Task::none()
             }
-            Message::TintChanged(value) => {
-                self.current_edit_params.tint = value;
-                self.save_current_edits();
-                // Phase 25: Update GPU uniforms and invalidate canvas cache
-                if let EditorStatus::Ready(pipeline) = &self.editor_status {
-                    pipeline.update_uniforms(&self.current_edit_params);
-                    self.canvas_cache.clear();
+            Message::ApplyPreset(name) => {
+                if self.selected_image_id.is_none() {
+                    return Task::none();
+                }
+                let Some(library) = &self.library else {
+                    return Task::none();
+                };
+                match library.load_preset(&name) {
+                    Ok(preset) => {
+                        // The look only: the image keeps its own crop & straighten
+                        self.current_edit_params = self.current_edit_params.with_preset(&preset);
+                        self.edit_history.push(self.current_edit_params);
+                        self.save_current_edits();
+                        if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                            pipeline.update_uniforms(&self.current_edit_params);
+                            self.canvas_cache.clear();
+                            self.histogram_cache.clear();
+                        }
+                        self.status = format!("Applied preset \"{}\"", name);
+                        println!("🎨 Applied preset \"{}\"", name);
+                    }
+                    Err(e) => eprintln!("⚠️  Failed to load preset \"{}\": {:?}", name, e),
                 }
                 Task::none()
             }
-            Message::ResetEdits => {
-                // Reset all edit parameters to default
-                self.current_edit_params.reset();
-                
-                // Phase 23: Save to database (or delete the edit record, only if loaded)
+            Message::PresetNameChanged(name) => {
+                self.preset_name_input = name;
+                self.preset_overwrite_pending = None;
+                Task::none()
+            }
+            Message::SavePreset => {
                 if let Some(library) = &self.library {
-                    if let Some(image_id) = self.selected_image_id {
-                        let _ = library.delete_edits(image_id);
-                        println!("♻️  Reset edits for image {}", image_id);
+                    match library.save_preset(&self.preset_name_input, &self.current_edit_params) {
+                        Ok(()) => {
+                            self.status = format!("Saved preset \"{}\"", self.preset_name_input.trim());
+                            self.presets = library.list_presets().unwrap_or_default();
+                            self.preset_name_input.clear();
+                        }
+                        // Ask first (see view_preset_controls)
+                        Err(state::presets::PresetError::AlreadyExists(name)) => {
+                            self.preset_overwrite_pending = Some(name);
+                        }
+                        Err(e) => {
+                            eprintln!("⚠️  Failed to save preset: {}", e);
+                            self.status = e.to_string();
+                        }
                     }
                 }
-                
-                // Phase 25: Update GPU uniforms and invalidate canvas cache
-                if let EditorStatus::Ready(pipeline) = &self.editor_status {
-                    pipeline.update_uniforms(&self.current_edit_params);
-                    self.canvas_cache.clear();
-                    self.histogram_cache.clear(); // Phase 24: Clear histogram cache
+                Task::none()
+            }
+            Message::ConfirmOverwritePreset => {
+                if let (Some(library), Some(name)) = (&self.library, self.preset_overwrite_pending.take()) {
+                    match library.overwrite_preset(&name, &self.current_edit_params) {
+                        Ok(()) => {
+                            self.status = format!("Updated preset \"{}\"", name);
+                            self.presets = library.list_presets().unwrap_or_default();
+                            self.preset_name_input.clear();
+                        }
+                        Err(e) => {
+                            eprintln!("⚠️  Failed to save preset: {}", e);
+                            self.status = e.to_string();
+                        }
+                    }
                 }
-                
                 Task::none()
             }
-            
-            // ========== Phase 24: Workflow Message Handlers ==========
-            
-            Message::ToggleBeforeAfter => {
-                // Toggle between edited and original (default params)
-                self.show_before = !self.show_before;
-                self.histogram_cache.clear(); // Histogram must update
-                println!("{} {}", 
-                    if self.show_before { "👁️  Showing" } else { "✏️  Showing" },
-                    if self.show_before { "BEFORE (original)" } else { "AFTER (edited)" }
-                );
+            Message::CancelOverwritePreset => {
+                self.preset_overwrite_pending = None;
                 Task::none()
             }
-            
-            Message::SelectNextImage => {
-                // Find current image index and select next
-                if let Some(current_id) = self.selected_image_id {
-                    if let Some(current_idx) = self.images.iter().position(|img| img.id == current_id) {
-                        let next_idx = (current_idx + 1) % self.images.len();
-                        let next_id = self.images[next_idx].id;
-                        println!("⏭️  Next image: {} ({}/{})", next_id, next_idx + 1, self.images.len());
-                        return self.update(Message::ImageSelected(next_id));
-                    }
-                }
+            Message::SnapshotNameChanged(name) => {
+                self.snapshot_name_input = name;
                 Task::none()
             }
-            
-            Message::SelectPreviousImage => {
-                // Find current image index and select previous
-                if let Some(current_id) = self.selected_image_id {
-                    if let Some(current_idx) = self.images.iter().position(|img| img.id == current_id) {
-                        let prev_idx = if current_idx == 0 { self.images.len() - 1 } else { current_idx - 1 };
-                        let prev_id = self.images[prev_idx].id;
-                        println!("⏮️  Previous image: {} ({}/{})", prev_id, prev_idx + 1, self.images.len());
-                        return self.update(Message::ImageSelected(prev_id));
+            Message::SaveSnapshot => {
+                let name = self.snapshot_name_input.trim().to_string();
+                let (Some(library), Some(image_id)) = (&self.library, self.selected_image_id) else {
+                    return Task::none();
+                };
+                if name.is_empty() {
+                    return Task::none();
+                }
+                match library.save_snapshot(image_id, &name, &self.current_edit_params) {
+                    Ok(()) => {
+                        self.snapshots = library.list_snapshots(image_id).unwrap_or_default();
+                        self.snapshot_name_input.clear();
+                        self.status = format!("Saved snapshot \"{}\"", name);
+                        println!("📸 Saved snapshot \"{}\" for image {}", name, image_id);
+                    }
+                    Err(e) => {
+                        eprintln!("⚠️  Failed to save snapshot: {:?}", e);
+                        self.status = format!("Couldn't save snapshot: {}", e);
                     }
                 }
                 Task::none()
             }
-            
-            // ========== Phase 25: Zoom & Pan Message Handlers ==========
-
-            Message::Zoom(delta, mut cursor_pos) => {
-                // Phase 26: Zoom to cursor position (not center)
-                
-                // Get cursor position (use last known if sentinel value)
-                if cursor_pos.x < 0.0 || cursor_pos.y < 0.0 {
-                    cursor_pos = self.last_cursor_position.unwrap_or(Point::ORIGIN);
-                }
-                
-                // Get pipeline dimensions for calculations
-                if let EditorStatus::Ready(pipeline) = &self.editor_status {
-                    let old_zoom = self.zoom;
-                    
-                    // Phase 26: Calculate actual image position in viewport (centered)
-                    let image_width = pipeline.preview_width as f32;
-                    let image_height = pipeline.preview_height as f32;
-                    let viewport_width = self.viewport_size.0;
-                    let viewport_height = self.viewport_size.1;
-                    
-                    // Image is centered in viewport, calculate offsets
-                    let x_offset = (viewport_width - image_width) / 2.0;
-                    let y_offset = (viewport_height - image_height) / 2.0;
-                    
-                    // Convert viewport cursor position to image-relative position
-                    let image_cursor_x = cursor_pos.x - x_offset;
-                    let image_cursor_y = cursor_pos.y - y_offset;
-                    
-                    // Debug: Show offset calculation (helpful for diagnosing drift)
-                    if false {  // Set to true for debugging
-                        println!("📐 Zoom @ cursor: Viewport={:.0}x{:.0} Image={:.0}x{:.0} Offset=({:.1},{:.1})",
-                            viewport_width, viewport_height, image_width, image_height, x_offset, y_offset);
-                    }
-                    
-                    // Skip if cursor is far outside the image (allow small margins for edge precision)
-                    let margin = 5.0; // Small margin in pixels
-                    if image_cursor_x < -margin || image_cursor_y < -margin || 
-                       image_cursor_x > image_width + margin || image_cursor_y > image_height + margin {
-                        println!("⚠️  Cursor outside image, skipping zoom-to-cursor");
-                        // Just do regular zoom without pan adjustment
-                        if delta > 0.0 {
-                            self.zoom *= 1.0 + (delta * 0.8);
-                        } else {
-                            self.zoom /= 1.0 + (-delta * 0.8);
+            Message::RestoreSnapshot(snapshot_id) => {
+                let (Some(library), Some(image_id)) = (&self.library, self.selected_image_id) else {
+                    return Task::none();
+                };
+                match library.apply_snapshot(image_id, snapshot_id) {
+                    Ok(params) => {
+                        // A restore is an edit like any other: undo goes back to before it
+                        self.current_edit_params = params;
+                        self.edit_history.push(params);
+                        self.save_current_edits();
+                        if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                            pipeline.update_uniforms(&params);
+                            self.canvas_cache.clear();
+                            self.histogram_cache.clear();
                         }
-                        self.zoom = self.zoom.clamp(0.1, 10.0);
-                        self.canvas_cache.clear();
-                        return Task::none();
-                    }
-                    
-                    // Clamp cursor to image bounds for calculation
-                    let image_cursor_x = image_cursor_x.clamp(0.0, image_width);
-                    let image_cursor_y = image_cursor_y.clamp(0.0, image_height);
-                    
-                    // Calculate new zoom (exponential scaling)
-                    let new_zoom = if delta > 0.0 {
-                        old_zoom * (1.0 + delta * 0.8)  // Zoom in
-                    } else {
-                        old_zoom / (1.0 + (-delta * 0.8))  // Zoom out
-                    };
-                    self.zoom = new_zoom.clamp(0.1, 10.0);
-                    
-                    // Zoom-to-cursor math (matching shader transformation):
-                    // Shader: tex = ((screen - 0.5) / zoom - pan) + 0.5
-                    
-                    // 1. Convert cursor position to normalized image coordinates (0-1)
-                    let norm_cursor_x = image_cursor_x / image_width;
-                    let norm_cursor_y = image_cursor_y / image_height;
-                    
-                    // 2. Find texture point under cursor BEFORE zoom
-                    // tex = ((cursor - 0.5) / old_zoom - old_pan) + 0.5
-                    let tex_x = ((norm_cursor_x - 0.5) / old_zoom - self.pan_offset.x) + 0.5;
-                    let tex_y = ((norm_cursor_y - 0.5) / old_zoom - self.pan_offset.y) + 0.5;
-                    
-                    // 3. Calculate new pan so same texture point appears under cursor AFTER zoom
-                    // We want: cursor = ((tex - 0.5) / new_zoom - new_pan) + 0.5
-                    // Rearranging: new_pan = (tex - 0.5) / new_zoom - (cursor - 0.5)
-                    // Wait, that's wrong. Let me rederive:
-                    // cursor = ((tex - 0.5 - new_pan * new_zoom) / new_zoom) + 0.5
-                    // No wait, the shader is: tex = ((screen - 0.5) / zoom - pan) + 0.5
-                    // So inverse: screen = (tex - 0.5 + pan) * zoom + 0.5
-                    // We want: cursor = (tex - 0.5 + new_pan) * new_zoom + 0.5
-                    // Solving for new_pan:
-                    // cursor - 0.5 = (tex - 0.5 + new_pan) * new_zoom
-                    // (cursor - 0.5) / new_zoom = tex - 0.5 + new_pan
-                    // new_pan = (cursor - 0.5) / new_zoom - tex + 0.5
-                    
-                    self.pan_offset.x = (norm_cursor_x - 0.5) / self.zoom - tex_x + 0.5;
-                    self.pan_offset.y = (norm_cursor_y - 0.5) / self.zoom - tex_y + 0.5;
-                    
-                    println!("🔍 Zoom: {:.1}% (at cursor)", self.zoom * 100.0);
-                } else {
-                    // No pipeline loaded, just do simple zoom
-                    if delta > 0.0 {
-                        self.zoom *= 1.0 + (delta * 0.8);
-                    } else {
-                        self.zoom /= 1.0 + (-delta * 0.8);
+                        if let Some(snapshot) = self.snapshots.iter().find(|s| s.id == snapshot_id) {
+                            self.status = format!("Restored snapshot \"{}\"", snapshot.name);
+                        }
+                        println!("📸 Restored snapshot {} for image {}", snapshot_id, image_id);
                     }
-                    self.zoom = self.zoom.clamp(0.1, 10.0);
-                    println!("🔍 Zoom: {:.1}%", self.zoom * 100.0);
+                    Err(e) => eprintln!("⚠️  Failed to restore snapshot {}: {:?}", snapshot_id, e),
                 }
-                
-                // Invalidate canvas cache to trigger redraw
-                self.canvas_cache.clear();
-                
                 Task::none()
             }
-            
-            Message::ResetView => {
-                // Phase 26: Reset zoom and pan to default
-                self.zoom = 1.0;
-                self.pan_offset = cgmath::Vector2::new(0.0, 0.0);
-                self.canvas_cache.clear();
-                println!("🔄 View reset: 100% zoom, centered");
+            Message::DeleteSnapshot(snapshot_id) => {
+                let (Some(library), Some(image_id)) = (&self.library, self.selected_image_id) else {
+                    return Task::none();
+                };
+                if let Err(e) = library.delete_snapshot(snapshot_id) {
+                    eprintln!("⚠️  Failed to delete snapshot {}: {:?}", snapshot_id, e);
+                }
+                self.snapshots = library.list_snapshots(image_id).unwrap_or_default();
+                if self.compare_snapshot == Some(snapshot_id) {
+                    self.compare_snapshot = None;
+                }
                 Task::none()
             }
-            
-            Message::Pan(delta) => {
-                // Phase 25: Apply pan delta scaled by zoom (so panning speed feels consistent)
-                // Scale by 1/zoom so panning at high zoom feels same speed as low zoom
-                let scale = 1.0 / self.zoom;
-                self.pan_offset.x += delta.x * scale;
-                self.pan_offset.y += delta.y * scale;
-                println!("🖐️  Pan: ({:.3}, {:.3}) at zoom {:.1}%", 
-                    self.pan_offset.x, self.pan_offset.y, self.zoom * 100.0);
-                
-                // Invalidate canvas cache to trigger redraw
-                self.canvas_cache.clear();
-                
+            Message::KeywordInputChanged(keyword) => {
+                self.keyword_input = keyword;
                 Task::none()
             }
-            
-            Message::MousePressed => {
-                // Phase 26: Detect double-click for reset view
-                let now = std::time::Instant::now();
-                let is_double_click = if let Some(last_click) = self.last_click_time {
-                    now.duration_since(last_click).as_millis() < 300  // 300ms threshold
-                } else {
-                    false
+            Message::AddKeyword => {
+                let (Some(library), Some(image_id)) = (&self.library, self.selected_image_id) else {
+                    return Task::none();
                 };
-                
-                self.last_click_time = Some(now);
-                
-                if is_double_click {
-                    // Double-click detected - reset view
-                    println!("👆 Double-click detected!");
-                    return self.update(Message::ResetView);
+                if let Err(e) = library.add_keyword(image_id, &self.keyword_input) {
+                    eprintln!("⚠️  Failed to add keyword: {:?}", e);
+                    return Task::none();
+                }
+                self.keywords = library.get_keywords(image_id).unwrap_or_default();
+                self.keyword_input.clear();
+                if !self.keyword_search.trim().is_empty() {
+                    (self.images, self.date_sections) = self.library_grid(library);
                 }
-                
-                // Single click - start dragging for panning
-                self.is_dragging = true;
-                // Position will be updated by next MouseMoved event
-                Task::none()
-            }
-            
-            Message::MouseReleased => {
-                // Stop dragging
-                self.is_dragging = false;
-                self.last_cursor_position = None;
                 Task::none()
             }
-            
-            Message::MouseMoved(current_position) => {
-                // Phase 26: Update viewport size estimate
-                // Learn the viewport size by tracking the maximum mouse coordinates
-                // But don't let it shrink (only grow when we see larger coordinates)
-                let new_viewport_w = (current_position.x * 1.01).max(self.viewport_size.0);
-                let new_viewport_h = (current_position.y * 1.01).max(self.viewport_size.1);
-                
-                // Only update if change is significant (avoid tiny fluctuations)
-                if (new_viewport_w - self.viewport_size.0).abs() > 10.0 {
-                    self.viewport_size.0 = new_viewport_w;
+            Message::RemoveKeyword(keyword) => {
+                let (Some(library), Some(image_id)) = (&self.library, self.selected_image_id) else {
+                    return Task::none();
+                };
+                if let Err(e) = library.remove_keyword(image_id, &keyword) {
+                    eprintln!("⚠️  Failed to remove keyword \"{}\": {:?}", keyword, e);
                 }
-                if (new_viewport_h - self.viewport_size.1).abs() > 10.0 {
-                    self.viewport_size.1 = new_viewport_h;
+                self.keywords = library.get_keywords(image_id).unwrap_or_default();
+                if !self.keyword_search.trim().is_empty() {
+                    (self.images, self.date_sections) = self.library_grid(library);
                 }
-                
-                // If dragging, calculate pan delta and send Pan message
-                if self.is_dragging {
-                    if let Some(last_pos) = self.last_cursor_position {
-                        // Calculate delta in screen pixels
-                        let delta_x = current_position.x - last_pos.x;
-                        let delta_y = current_position.y - last_pos.y;
-                        
-                        // Phase 26: Pan sensitivity using image dimensions (not viewport)
-                        // Pan offset is in normalized image coordinates
-                        let (sensitivity_x, sensitivity_y) = if let EditorStatus::Ready(pipeline) = &self.editor_status {
-                            (
-                                1.0 / pipeline.preview_width as f32,
-                                1.0 / pipeline.preview_height as f32,
-                            )
-                        } else {
-                            (0.001, 0.001)
-                        };
-                        
-                        let delta = cgmath::Vector2::new(
-                            delta_x * sensitivity_x,
-                            delta_y * sensitivity_y,
-                        );
-                        
-                        // Update cursor position AFTER calculating delta
-                        self.last_cursor_position = Some(current_position);
-                        
-                        // Send Pan message
-                        return self.update(Message::Pan(delta));
-                    }
+                Task::none()
+            }
+            Message::KeywordSearchChanged(search) => {
+                self.keyword_search = search;
+                if let Some(library) = &self.library {
+                    (self.images, self.date_sections) = self.library_grid(library);
                 }
-                
-                // Store cursor position for zoom-to-cursor (if not dragging)
-                self.last_cursor_position = Some(current_position);
                 Task::none()
             }
-            
-            // ========== GPU Pipeline Message Handlers ==========
-            
-            Message::RawDataLoaded(result) => {
-                match result {
-                    Ok(raw_data) => {
-                        println!("📷 RAW data loaded: {}x{} pixels", raw_data.width, raw_data.height);
-                        
-                        // Phase 15: Calculate proper cam-to-sRGB color matrix
-                        let xyz_to_cam = raw_data.color_matrix;
-                        let cam_to_srgb = calculate_cam_to_srgb_matrix(xyz_to_cam);
-                        println!("🎨 CAM-to-sRGB Matrix: [{:.3}, {:.3}, {:.3}]", 
-                            cam_to_srgb[0], cam_to_srgb[1], cam_to_srgb[2]);
-                        println!("                      [{:.3}, {:.3}, {:.3}]", 
-                            cam_to_srgb[3], cam_to_srgb[4], cam_to_srgb[5]);
-                        println!("                      [{:.3}, {:.3}, {:.3}]", 
-                            cam_to_srgb[6], cam_to_srgb[7], cam_to_srgb[8]);
-                        
-                        // Create GPU pipeline with the RAW data + color metadata
-                        let params = self.current_edit_params;
-                        let wb = raw_data.wb_multipliers;
-                        let image_id = self.selected_image_id.unwrap_or(0);  // Phase 20: Track which image
-                        
-                        Task::perform(
-                            async move {
-                                gpu::RenderPipeline::new(
-                                    image_id,         // Phase 20: Track which image this pipeline is for
-                                    raw_data.data,
-                                    raw_data.width,
-                                    raw_data.height,
-                                    &params,
-                                    wb,           // Phase 14: White balance from camera
-                                    cam_to_srgb,  // Phase 15: Camera-to-sRGB color matrix
-                                ).await
-                            },
-                            |result| Message::GpuPipelineReady(result.map(Arc::new)),
-                        )
-                    }
-                    Err(err) => {
-                        eprintln!("⚠️  Failed to load RAW data: {}", err);
-                        self.editor_status = EditorStatus::Failed(
-                            self.selected_image_id.unwrap_or(0),
-                            err,
-                        );
-                        Task::none()
-                    }
+            Message::ImportScopeChanged(scope) => {
+                self.import_scope = scope;
+                if let Some(library) = &self.library {
+                    (self.images, self.date_sections) = self.library_grid(library);
                 }
+                Task::none()
             }
-            
-            Message::GpuPipelineReady(result) => {
-                match result {
-                    Ok(pipeline) => {
-                        println!("🎨 GPU pipeline initialized!");
-                        
-                        // Phase 25: Clear canvas cache since this is a new pipeline for a new image
-                        self.canvas_cache.clear();
-                        
-                        // Store pipeline in EditorStatus::Ready
-                        self.editor_status = EditorStatus::Ready(pipeline);
-                        
-                        Task::none()
-                    }
-                    Err(err) => {
-                        eprintln!("⚠️  Failed to initialize GPU pipeline: {}", err);
-                        self.editor_status = EditorStatus::Failed(
-                            self.selected_image_id.unwrap_or(0),
-                            err,
-                        );
-                        Task::none()
-                    }
+            Message::CompareSnapshot(snapshot_id) => {
+                self.compare_snapshot = snapshot_id;
+                if snapshot_id.is_some() {
+                    // Snapshot left of the divider, current edits on the right
+                    self.compare_mode = CompareMode::Split;
+                    self.show_before = true;
                 }
+                self.histogram_cache.clear();
+                Task::none()
             }
-            
-            Message::ExportImage => {
-                // Phase 19: Export full-resolution image
+            Message::MonochromeToggled(enabled) => {
+                self.current_edit_params.monochrome = enabled;
+                self.edit_history.record(self.current_edit_params, "monochrome");
+                self.save_current_edits();
                 if let EditorStatus::Ready(pipeline) = &self.editor_status {
-                    // Show file save dialog
-                    if let Some(path) = rfd::FileDialog::new()
-                        .add_filter("JPEG Image", &["jpg", "jpeg"])
-                        .add_filter("PNG Image", &["png"])
-                        .set_file_name("export.jpg")
-                        .save_file()
-                    {
-                        println!("📤 Exporting to: {:?}", path);
-                        let pipeline_clone = Arc::clone(pipeline);
-                        
-                        // Run export in background to avoid freezing UI
-                        return Task::perform(
-                            export_image_async(pipeline_clone, path),
-                            Message::ExportComplete
-                        );
-                    }
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                    self.histogram_cache.clear();
                 }
                 Task::none()
             }
-            
-            Message::ExportComplete(result) => {
-                match result {
-                    Ok(path) => {
-                        println!("✅ Export complete: {:?}", path);
-                        // TODO: Show status message to user
+            Message::BwMixChanged(channel, value) => {
+                if let Some(weight) = self.current_edit_params.bw_mix.get_mut(channel) {
+                    *weight = value;
+                }
+                self.edit_history.record(self.current_edit_params, "bw_mix");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::ShadowTintChanged(channel, value) => {
+                if let Some(weight) = self.current_edit_params.shadow_tint.get_mut(channel) {
+                    *weight = value;
+                }
+                self.edit_history.record(self.current_edit_params, "shadow_tint");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::HighlightTintChanged(channel, value) => {
+                if let Some(weight) = self.current_edit_params.highlight_tint.get_mut(channel) {
+                    *weight = value;
+                }
+                self.edit_history.record(self.current_edit_params, "highlight_tint");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::BalanceChanged(value) => {
+                self.current_edit_params.balance = value;
+                self.edit_history.record(self.current_edit_params, "balance");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::ClarityChanged(value) => {
+                self.current_edit_params.clarity = value;
+                self.edit_history.record(self.current_edit_params, "clarity");
+                self.save_current_edits();
+                // Phase 25: Update GPU uniforms and invalidate canvas cache
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::RequestDelete => {
+                // The confirmation lives in the Library header
+                if self.current_tab == AppTab::Library && !self.selected_ids.is_empty() {
+                    let image_ids = self.selection_in_grid_order();
+                    let files_on_disk = self.images
+                        .iter()
+                        .filter(|img| image_ids.contains(&img.id) && img.file_status != "deleted")
+                        .count();
+                    self.delete_pending = Some(DeleteRequest { image_ids, rejected: false, files_on_disk });
+                }
+                Task::none()
+            }
+            Message::RequestDeleteRejected => {
+                let Some(library) = &self.library else {
+                    return Task::none();
+                };
+                // Every reject in the catalog, whatever the grid shows
+                match library.get_rejected() {
+                    Ok(rejected) if rejected.is_empty() => {
+                        self.notify(Severity::Info, "No images are flagged reject".to_string());
                     }
-                    Err(err) => {
-                        eprintln!("❌ Export failed: {}", err);
-                        // TODO: Show error message to user
+                    Ok(rejected) => {
+                        let files_on_disk = rejected.iter().filter(|img| std::path::Path::new(&img.path).exists()).count();
+                        let image_ids = rejected.iter().map(|img| img.id).collect();
+                        self.delete_pending = Some(DeleteRequest { image_ids, rejected: true, files_on_disk });
                     }
+                    Err(e) => eprintln!("❌ Failed to list rejected images: {}", e),
                 }
                 Task::none()
             }
-            
-            Message::HistogramToggled(enabled) => {
-                self.histogram_enabled = enabled;
-                println!("📊 Histogram {}", if enabled { "enabled" } else { "disabled" });
+            Message::ConfirmDelete(move_to_trash) => {
+                let (Some(library), Some(DeleteRequest { image_ids, .. })) = (&self.library, self.delete_pending.take()) else {
+                    return Task::none();
+                };
                 
-                // Phase 25: If enabling, clear canvas cache to force recalculation
-                if enabled {
-                    self.canvas_cache.clear();
+                let mut removed = 0;
+                let mut trash_failures = 0;
+                for image_id in &image_ids {
+                    match library.remove_image(*image_id) {
+                        Ok(Some(raw_path)) => {
+                            removed += 1;
+                            if move_to_trash {
+                                if let Err(e) = trash::delete(&raw_path) {
+                                    eprintln!("⚠️  Couldn't move {} to trash: {}", raw_path, e);
+                                    trash_failures += 1;
+                                }
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(e) => eprintln!("❌ Failed to remove image {}: {}", image_id, e),
+                    }
+                    self.pipeline_cache.remove(*image_id);
                 }
                 
+                (self.images, self.date_sections) = self.library_grid(library);
+                self.status = if move_to_trash {
+                    format!("Moved {} images to trash ({} failed)", removed - trash_failures, trash_failures)
+                } else {
+                    format!("Removed {} images from catalog", removed)
+                };
+                
+                // Nothing selected anymore
+                self.selected_image_id = None;
+                self.selected_ids.clear();
+                self.editor_status = EditorStatus::NoSelection;
+                self.current_edit_params = state::edit::EditParams::default();
+                self.edit_history.reset(self.current_edit_params);
+                self.canvas_cache.clear();
                 Task::none()
             }
-        }
-    }
-    
-    /// Helper to save current edit parameters to database
-    fn save_current_edits(&self) {
-        // Phase 23: Only save if database is loaded
-        if let Some(library) = &self.library {
-            if let Some(image_id) = self.selected_image_id {
-                if let Err(e) = library.save_edit_params(image_id, &self.current_edit_params) {
-                    eprintln!("⚠️  Failed to save edits for image {}: {:?}", image_id, e);
+            Message::CancelDelete => {
+                self.delete_pending = None;
+                Task::none()
+            }
+            Message::RegenerateThumbnails => {
+                let Some(library) = &self.library else {
+                    return Task::none();
+                };
+                let image_ids = self.selection_in_grid_order();
+                let mut queued = 0;
+                for image_id in &image_ids {
+                    match library.reset_cache_status(*image_id) {
+                        Ok(true) => queued += 1,
+                        Ok(false) => {}
+                        Err(e) => eprintln!("❌ Failed to reset cache for image {}: {}", image_id, e),
+                    }
+                }
+                self.restart_thumbnail_worker(queued)
+            }
+            Message::RegenerateAllCaches => {
+                let Some(library) = &self.library else {
+                    return Task::none();
+                };
+                match library.reset_all_cache_status() {
+                    Ok(queued) => Task::batch([self.restart_thumbnail_worker(queued), measure_cache()]),
+                    Err(e) => {
+                        eprintln!("❌ Failed to reset caches: {}", e);
+                        self.status = format!("Couldn't reset caches: {}", e);
+                        Task::none()
+                    }
+                }
+            }
+            Message::RevealInFileManager(image_id) => {
+                let Some(img) = self.images.iter().find(|img| img.id == image_id) else {
+                    return Task::none();
+                };
+                if img.file_status == "deleted" {
+                    self.status = format!("⚠️  {} is missing from disk", img.path);
+                    return Task::none();
+                }
+                // Waited for off the UI thread, so the child is reaped
+                let path = img.path.clone();
+                Task::perform(
+                    async move {
+                        let reveal = std::path::PathBuf::from(&path);
+                        let result = tokio::task::spawn_blocking(move || reveal_in_file_manager(&reveal))
+                            .await
+                            .unwrap_or_else(|e| Err(format!("File manager task failed: {}", e)));
+                        (path, result)
+                    },
+                    |(path, result)| Message::Revealed(path, result),
+                )
+            }
+            Message::Revealed(path, result) => {
+                match result {
+                    Ok(()) => println!("📂 Revealed {}", path),
+                    Err(e) => {
+                        eprintln!("⚠️  {}", e);
+                        self.status = e;
+                    }
+                }
+                Task::none()
+            }
+            Message::CopyPathToClipboard(image_id) => {
+                let Some(img) = self.images.iter().find(|img| img.id == image_id) else {
+                    return Task::none();
+                };
+                self.status = if img.file_status == "deleted" {
+                    format!("Copied path (file is missing): {}", img.path)
                 } else {
-                    println!("💾 Saved edits for image {}", image_id);
+                    format!("Copied path: {}", img.path)
+                };
+                iced::clipboard::write(img.path.clone())
+            }
+            Message::ReleaseIdleGpu => {
+                let idle = self.left_develop_at.map(|left| left.elapsed() >= gpu::cache::IDLE_RELEASE);
+                if self.current_tab != AppTab::Develop && idle == Some(true) && !self.pipeline_cache.is_empty() {
+                    println!("🧹 Released {} cached pipelines ({:.1} MB of GPU memory)",
+                             self.pipeline_cache.len(),
+                             self.cached_gpu_memory_bytes() as f64 / 1_048_576.0);
+                    self.pipeline_cache.clear();
                 }
+                Task::none()
             }
-        }
-    }
-    
-    /// Phase 24: Keyboard shortcuts subscription
-    fn subscription(&self) -> iced::Subscription<Message> {
-        use iced::keyboard;
-        use iced::keyboard::key::Named;
-        
-        iced::event::listen_with(|event, _status, _window| {
-            if let iced::Event::Keyboard(keyboard::Event::KeyPressed { key, .. }) = event {
-                match key.as_ref() {
-                    keyboard::Key::Named(Named::Space) => Some(Message::ToggleBeforeAfter),
-                    keyboard::Key::Character("r") | keyboard::Key::Character("R") => Some(Message::ResetEdits),
-                    keyboard::Key::Named(Named::ArrowRight) => Some(Message::SelectNextImage),
-                    keyboard::Key::Named(Named::ArrowLeft) => Some(Message::SelectPreviousImage),
-                    _ => None,
+            Message::PreviewQualityChanged(quality) => {
+                self.preview_quality = quality;
+                self.save_setting(state::settings::PREVIEW_QUALITY_KEY, quality.as_str());
+                println!("📐 Preview quality: {} (max width {})", quality, quality.max_width());
+                // The view picks up the new width on its next render
+                self.canvas_cache.clear();
+                Task::none()
+            }
+            Message::ThumbnailSizeChanged(size) => {
+                let size = size.clamp(state::settings::MIN_THUMBNAIL_SIZE, state::settings::MAX_THUMBNAIL_SIZE);
+                self.thumbnail_size = size;
+                self.save_setting(state::settings::THUMBNAIL_SIZE_KEY, &format!("{}", size.round()));
+                Task::none()
+            }
+            Message::SharpenAmountChanged(value) => {
+                self.current_edit_params.sharpen_amount = value;
+                self.edit_history.record(self.current_edit_params, "sharpen_amount");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
                 }
-            } else {
-                None
+                Task::none()
             }
-        })
-    }
-
-    /// Build the user interface
-    fn view(&self) -> Element<Message> {
-        // Phase 23: Show splash screen if database is still loading
-        match &self.library {
-            None => self.view_splash(),
-            Some(_) => self.view_main(),
-        }
-    }
-    
-    /// Phase 23: Splash screen shown during database loading
-    fn view_splash(&self) -> Element<Message> {
-        use iced::widget::Space;
-        
-        // Left half: Branding/image
-        // To add your custom splash image:
-        // 1. Create an "assets" folder in your project root
-        // 2. Add your image: assets/splash.png (PNG with transparency recommended)
-        // 3. Uncomment the image widget below and comment out the emoji
-        //
-        // For transparency/blending:
-        // - Use PNG format with alpha channel
-        // - The image will blend naturally with the dark background (#141418)
-        // - For edge blending, add a gradient alpha in your image editor
-        
-        let left_content = column![
-            Space::with_height(Length::Fill),
-            // Option 1: Use emoji placeholder (current)
-            // text("📸").size(120).center(),
-            
-            // Option 2: Use your custom image (fills container, maintains aspect ratio):
-            // iced::widget::image("assets/splash.jpg")
-            //     .width(Length::Fill)
-            //     .height(Length::Fill)
-            //     .content_fit(iced::ContentFit::Contain),  // Maintains aspect ratio
-            // 
-            // OR for full bleed (image fills entire space, may crop):
-            iced::widget::image("assets/splash.png")
-                .width(Length::Fill)
-                // .height(Length::Fill)
-                .content_fit(iced::ContentFit::Cover),  // Fills space, crops if needed
-            
-            Space::with_height(Length::Fill),
+            Message::SharpenRadiusChanged(value) => {
+                self.current_edit_params.sharpen_radius = value;
+                self.edit_history.record(self.current_edit_params, "sharpen_radius");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::ToggleDetailPreview(enabled) => {
+                self.detail_preview = enabled;
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.set_detail_preview(enabled);
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::LumaNrChanged(value) => {
+                self.current_edit_params.luma_nr = value;
+                self.edit_history.record(self.current_edit_params, "luma_nr");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::ChromaNrChanged(value) => {
+                self.current_edit_params.chroma_nr = value;
+                self.edit_history.record(self.current_edit_params, "chroma_nr");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::AutoTone => {
+                let EditorStatus::Ready(pipeline) = &self.editor_status else {
+                    return Task::none();
+                };
+                
+                // One-off histogram of the image with exposure and levels neutral,
+                // so the result doesn't depend on the current slider values
+                let baseline = state::edit::EditParams {
+                    exposure: 0.0,
+                    whites: 0.0,
+                    blacks: 0.0,
+                    ..self.current_edit_params
+                };
+                pipeline.set_detail_preview(false);
+                pipeline.update_uniforms(&baseline);
+                let [red, green, blue, _] = pipeline.calculate_histogram_gpu();
+                pipeline.set_detail_preview(self.detail_preview);
+                
+                let params = self.current_edit_params.with_auto_tone(&[red, green, blue]);
+                println!(
+                    "🪄 Auto tone: exposure {:.2}, whites {:.0}, blacks {:.0}",
+                    params.exposure, params.whites, params.blacks
+                );
+                self.current_edit_params = params;
+                self.edit_history.push(params);
+                pipeline.update_uniforms(&params);
+                self.save_current_edits();
+                self.canvas_cache.clear();
+                self.histogram_cache.clear();
+                Task::none()
+            }
+            Message::SuggestNoiseReduction => {
+                let (luma_nr, chroma_nr) = state::edit::EditParams::suggested_noise_reduction(self.selected_metadata.iso);
+                println!("🔇 Suggested noise reduction for {}: luma {:.0}, color {:.0}",
+                         self.selected_metadata.iso_display(), luma_nr * 100.0, chroma_nr * 100.0);
+                self.current_edit_params.luma_nr = luma_nr;
+                self.current_edit_params.chroma_nr = chroma_nr;
+                self.edit_history.push(self.current_edit_params);
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::VignetteAmountChanged(value) => {
+                self.current_edit_params.vignette_amount = value;
+                self.edit_history.record(self.current_edit_params, "vignette_amount");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::VignetteMidpointChanged(value) => {
+                self.current_edit_params.vignette_midpoint = value;
+                self.edit_history.record(self.current_edit_params, "vignette_midpoint");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::VibranceChanged(value) => {
+                self.current_edit_params.vibrance = value;
+                self.edit_history.record(self.current_edit_params, "vibrance");
+                self.save_current_edits();
+                // Phase 25: Update GPU uniforms and invalidate canvas cache
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::SaturationChanged(value) => {
+                self.current_edit_params.saturation = value;
+                self.edit_history.record(self.current_edit_params, "saturation");
+                self.save_current_edits();
+                // Phase 25: Update GPU uniforms and invalidate canvas cache
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::TemperatureChanged(value) => {
+                self.current_edit_params.temperature = value;
+                self.edit_history.record(self.current_edit_params, "temperature");
+                self.save_current_edits();
+                // Phase 25: Update GPU uniforms and invalidate canvas cache
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::TintChanged(value) => {
+                self.current_edit_params.tint = value;
+                self.edit_history.record(self.current_edit_params, "tint");
+                self.save_current_edits();
+                // Phase 25: Update GPU uniforms and invalidate canvas cache
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::CurveChanged(curve) => {
+                self.current_edit_params.curve = curve;
+                self.edit_history.record(self.current_edit_params, "curve");
+                self.save_current_edits();
+                // Update GPU uniforms (and curve LUT) and invalidate canvas cache
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::HslChanged(band, channel, value) => {
+                self.current_edit_params.set_hsl(band, channel, value);
+                self.edit_history.record(self.current_edit_params, "hsl");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::ToggleHslPanel => {
+                self.hsl_panel_open = !self.hsl_panel_open;
+                Task::none()
+            }
+            Message::ToggleMaskTool => {
+                self.mask_tool_active = !self.mask_tool_active;
+                if self.mask_tool_active {
+                    // The handles map onto the fitted preview, like the crop overlay
+                    self.zoom = 1.0;
+                    self.pan_offset = cgmath::Vector2::new(0.0, 0.0);
+                    self.crop_tool_active = false;
+                    self.straighten_active = false;
+                    self.wb_picker_active = false;
+                    self.film_base_picker_active = false;
+                    self.is_dragging = false;
+                }
+                self.canvas_cache.clear();
+                println!("🎯 Mask tool {}", if self.mask_tool_active { "on" } else { "off" });
+                Task::none()
+            }
+            Message::AddMask(kind) => {
+                let Some(index) = self.current_edit_params.masks.push(state::edit::LocalMask::new(kind)) else {
+                    return Task::none();
+                };
+                self.selected_mask = Some(index);
+                self.edit_history.push(self.current_edit_params);
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                // A new mask is only useful with its handles showing
+                if !self.mask_tool_active {
+                    return self.update(Message::ToggleMaskTool);
+                }
+                Task::none()
+            }
+            Message::RemoveMask(index) => {
+                self.current_edit_params.masks.remove(index);
+                self.selected_mask = None;
+                self.edit_history.push(self.current_edit_params);
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::MaskSelected(index) => {
+                self.selected_mask = Some(index);
+                Task::none()
+            }
+            Message::MaskMoved(index, handle, point) => {
+                let Some(mask) = self.current_edit_params.masks.get_mut(index) else {
+                    return Task::none();
+                };
+                match handle {
+                    ui::masks::MaskHandle::Start => {
+                        // The center carries the ellipse along with it
+                        if mask.kind == state::edit::MaskKind::Radial {
+                            mask.end = [mask.end[0] + point[0] - mask.start[0], mask.end[1] + point[1] - mask.start[1]];
+                        }
+                        mask.start = point;
+                    }
+                    ui::masks::MaskHandle::End => mask.end = point,
+                }
+                self.edit_history.record(self.current_edit_params, "mask");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::MaskChanged(index, setting, value) => {
+                let Some(mask) = self.current_edit_params.masks.get_mut(index) else {
+                    return Task::none();
+                };
+                mask.set(setting, value);
+                self.edit_history.record(self.current_edit_params, "mask");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::DemosaicChanged(method) => {
+                self.current_edit_params.demosaic = method;
+                self.edit_history.record(self.current_edit_params, "demosaic");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::ToggleCropTool => {
+                self.crop_tool_active = !self.crop_tool_active;
+                self.straighten_active = false;
+                if self.crop_tool_active {
+                    // The overlay maps onto the whole frame, so drop zoom/pan and the pickers
+                    self.zoom = 1.0;
+                    self.pan_offset = cgmath::Vector2::new(0.0, 0.0);
+                    self.mask_tool_active = false;
+                    self.wb_picker_active = false;
+                    self.film_base_picker_active = false;
+                    self.is_dragging = false;
+                }
+                self.canvas_cache.clear();
+                println!("✂️  Crop tool {}", if self.crop_tool_active { "on" } else { "off" });
+                Task::none()
+            }
+            Message::CropChanged(rect) => {
+                // A crop covering the whole frame is the same as no crop
+                let is_full = rect.0 <= 1e-4 && rect.1 <= 1e-4 && rect.2 >= 1.0 - 1e-4 && rect.3 >= 1.0 - 1e-4;
+                self.current_edit_params.crop_rect = if is_full { None } else { Some(rect) };
+                self.edit_history.record(self.current_edit_params, "crop");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::CropAspectChanged(aspect) => {
+                self.crop_aspect = aspect;
+                // Snap the current crop to the new ratio (largest centered fit)
+                if let (Some(ratio), EditorStatus::Ready(pipeline)) = (aspect.ratio(), &self.editor_status) {
+                    let (width, height) = pipeline.dimensions();
+                    let rect = state::edit::fit_crop_to_aspect(
+                        self.current_edit_params.crop_or_full(),
+                        ratio,
+                        width,
+                        height,
+                    );
+                    return self.update(Message::CropChanged(rect));
+                }
+                Task::none()
+            }
+            Message::CropLockRatioToggled(locked) => {
+                self.crop_lock_ratio = locked;
+                Task::none()
+            }
+            Message::CropGuideChanged(guide) => {
+                self.crop_guide = guide;
+                Task::none()
+            }
+            Message::ToggleStraighten => {
+                // Straightening needs the whole frame under the overlay, like cropping
+                if !self.crop_tool_active {
+                    let task = self.update(Message::ToggleCropTool);
+                    self.straighten_active = true;
+                    return task;
+                }
+                self.straighten_active = !self.straighten_active;
+                println!("📐 Straighten tool {}", if self.straighten_active { "on" } else { "off" });
+                Task::none()
+            }
+            Message::RotationChanged(degrees) => {
+                self.current_edit_params.rotation_degrees = degrees;
+                self.edit_history.record(self.current_edit_params, "rotation");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::LensCorrectionToggled(enabled) => {
+                self.current_edit_params.lens_correction = enabled;
+                self.edit_history.push(self.current_edit_params);
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::LensDistortionChanged(value) => {
+                self.current_edit_params.lens_distortion = value;
+                self.edit_history.record(self.current_edit_params, "lens_distortion");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::ResetCrop => {
+                self.current_edit_params.crop_rect = None;
+                self.current_edit_params.rotation_degrees = 0.0;
+                self.crop_aspect = state::edit::CropAspect::Free;
+                self.edit_history.push(self.current_edit_params);
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                }
+                Task::none()
+            }
+            Message::ResetEdits => {
+                // Reset all edit parameters to default
+                self.current_edit_params.reset();
+                // Own history entry so the pre-reset state can be restored with undo
+                self.edit_history.push(self.current_edit_params);
+                
+                // Phase 23: Save to database (or delete the edit record, only if loaded)
+                if let Some(library) = &self.library {
+                    if let Some(image_id) = self.selected_image_id {
+                        let _ = library.delete_edits(image_id);
+                        println!("♻️  Reset edits for image {}", image_id);
+                    }
+                }
+                self.queue_sidecar_write();
+                
+                // Phase 25: Update GPU uniforms and invalidate canvas cache
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                    self.histogram_cache.clear(); // Phase 24: Clear histogram cache
+                }
+                
+                Task::none()
+            }
+            
+            Message::Undo | Message::Redo => {
+                let restored = if matches!(message, Message::Undo) {
+                    self.edit_history.undo()
+                } else {
+                    self.edit_history.redo()
+                };
+                
+                if let Some(params) = restored {
+                    self.current_edit_params = params;
+                    self.save_current_edits();
+                    // Update GPU uniforms and invalidate canvas cache
+                    if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                        pipeline.update_uniforms(&self.current_edit_params);
+                        self.canvas_cache.clear();
+                        self.histogram_cache.clear();
+                    }
+                    println!("{} Edit history", if matches!(message, Message::Undo) { "↩️  Undo:" } else { "↪️  Redo:" });
+                }
+                
+                Task::none()
+            }
+            
+            Message::CopySettings => {
+                if self.current_tab == AppTab::Develop && self.selected_image_id.is_some() {
+                    self.clipboard_params = Some(self.current_edit_params);
+                    println!("📋 Copied develop settings");
+                }
+                Task::none()
+            }
+            
+            Message::PasteSettings => {
+                if self.current_tab != AppTab::Develop {
+                    return Task::none();
+                }
+                // Pastes onto every selected image (just the current one without a multi-selection)
+                if let Some(params) = self.clipboard_params {
+                    for image_id in self.selection_in_grid_order() {
+                        self.paste_settings_onto(image_id, params);
+                    }
+                }
+                Task::none()
+            }
+            
+            // ========== Phase 24: Workflow Message Handlers ==========
+            
+            Message::ToggleFullscreenPreview => {
+                if self.current_tab != AppTab::Develop {
+                    return Task::none();
+                }
+                self.set_fullscreen_preview(!self.fullscreen_preview)
+            }
+            Message::EscapePressed => {
+                if self.fullscreen_preview {
+                    self.set_fullscreen_preview(false)
+                } else {
+                    self.update(Message::TabChanged(AppTab::Library))
+                }
+            }
+            Message::ToggleBeforeAfter => {
+                // Toggle between edited and original (default params)
+                self.show_before = !self.show_before;
+                self.histogram_cache.clear(); // Histogram must update
+                println!("{} {}", 
+                    if self.show_before { "👁️  Showing" } else { "✏️  Showing" },
+                    if self.show_before { "BEFORE (original)" } else { "AFTER (edited)" }
+                );
+                Task::none()
+            }
+            
+            Message::ToggleClipping => {
+                self.show_clipping = !self.show_clipping;
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.set_show_clipping(self.show_clipping);
+                    // The preview's key includes the flag, so it renders again
+                }
+                println!("🚨 Clipping warnings {}", if self.show_clipping { "on" } else { "off" });
+                Task::none()
+            }
+            
+            Message::RotateImage(clockwise) => {
+                let EditorStatus::Ready(pipeline) = &self.editor_status else {
+                    return Task::none();
+                };
+                let image_id = pipeline.image_id;
+                let orientation = pipeline.orientation().rotated(clockwise);
+                pipeline.set_orientation(orientation);
+                
+                if let Some(library) = &self.library {
+                    if let Err(e) = library.set_orientation(image_id, orientation.to_exif()) {
+                        eprintln!("⚠️  Failed to save orientation for image {}: {:?}", image_id, e);
+                    }
+                }
+                // Turn the cached JPEGs too, so the grid matches without a re-extract
+                if let Some(img) = self.images.iter_mut().find(|img| img.id == image_id) {
+                    img.orientation = orientation.to_exif();
+                    let cached = [&img.cache_path_thumb, &img.cache_path_instant, &img.cache_path_working];
+                    for path in cached.into_iter().flatten() {
+                        if let Err(e) = raw::thumbnail::rotate_cached_image(std::path::Path::new(path), clockwise) {
+                            eprintln!("⚠️  {}", e);
+                        }
+                    }
+                }
+                
+                // The crop keeps framing the same part of the picture
+                if let Some(crop) = self.current_edit_params.crop_rect {
+                    self.current_edit_params.crop_rect = Some(state::edit::rotate_crop(crop, clockwise));
+                    self.edit_history.push(self.current_edit_params);
+                    self.save_current_edits();
+                }
+                
+                let EditorStatus::Ready(pipeline) = &self.editor_status else {
+                    return Task::none();
+                };
+                pipeline.update_uniforms(&self.current_edit_params);
+                self.canvas_cache.clear();
+                self.histogram_cache.clear();
+                println!("🔄 Rotated image {} {} (orientation {})",
+                    image_id,
+                    if clockwise { "right" } else { "left" },
+                    orientation.to_exif()
+                );
+                Task::none()
+            }
+            
+            Message::CompareModeChanged(mode) => {
+                self.compare_mode = mode;
+                // Picking a mode shows it right away
+                self.show_before = true;
+                self.histogram_cache.clear();
+                Task::none()
+            }
+            
+            Message::SelectNextImage => {
+                // Find current image index and select next
+                if let Some(current_id) = self.selected_image_id {
+                    if let Some(current_idx) = self.images.iter().position(|img| img.id == current_id) {
+                        let next_idx = (current_idx + 1) % self.images.len();
+                        let next_id = self.images[next_idx].id;
+                        println!("⏭️  Next image: {} ({}/{})", next_id, next_idx + 1, self.images.len());
+                        return self.update(Message::ImageSelected(next_id));
+                    }
+                }
+                Task::none()
+            }
+            
+            Message::SelectPreviousImage => {
+                // Find current image index and select previous
+                if let Some(current_id) = self.selected_image_id {
+                    if let Some(current_idx) = self.images.iter().position(|img| img.id == current_id) {
+                        let prev_idx = if current_idx == 0 { self.images.len() - 1 } else { current_idx - 1 };
+                        let prev_id = self.images[prev_idx].id;
+                        println!("⏮️  Previous image: {} ({}/{})", prev_id, prev_idx + 1, self.images.len());
+                        return self.update(Message::ImageSelected(prev_id));
+                    }
+                }
+                Task::none()
+            }
+            
+            Message::SetRating(rating) => {
+                // Applies to the whole multi-selection
+                let image_ids = self.selection_in_grid_order();
+                if let Some(library) = &self.library {
+                    for image_id in image_ids {
+                        match library.set_rating(image_id, rating) {
+                            Ok(_) => {
+                                // Update the in-memory copy so the grid reflects it without a reload
+                                if let Some(img) = self.images.iter_mut().find(|img| img.id == image_id) {
+                                    img.rating = rating.clamp(0, 5);
+                                }
+                                println!("⭐ Rated image {}: {} stars", image_id, rating);
+                            }
+                            Err(e) => eprintln!("⚠️  Failed to save rating: {:?}", e),
+                        }
+                    }
+                }
+                Task::none()
+            }
+            
+            Message::LibrarySortChanged(sort) => {
+                self.library_sort = sort;
+                if let Some(library) = &self.library {
+                    (self.images, self.date_sections) = self.library_grid(library);
+                }
+                Task::none()
+            }
+            
+            Message::LibraryFilterChanged(filter) => {
+                self.library_filter = filter;
+                if let Some(library) = &self.library {
+                    (self.images, self.date_sections) = self.library_grid(library);
+                }
+                Task::none()
+            }
+            
+            Message::GroupByDateToggled(enabled) => {
+                self.group_by_date = enabled;
+                if let Some(library) = &self.library {
+                    (self.images, self.date_sections) = self.library_grid(library);
+                }
+                Task::none()
+            }
+            
+            Message::ToggleDayCollapsed(day) => {
+                if !self.collapsed_days.remove(&day) {
+                    self.collapsed_days.insert(day);
+                }
+                Task::none()
+            }
+            
+            Message::SetFlag(flag) => {
+                // Applies to the whole multi-selection
+                let image_ids = self.selection_in_grid_order();
+                // Culling one image at a time: rejecting moves on to the next one
+                let advance_to = match image_ids[..] {
+                    [image_id] if self.auto_advance && flag == state::data::FLAG_REJECT => self.images
+                        .iter()
+                        .skip_while(|img| img.id != image_id)
+                        .nth(1)
+                        .map(|img| img.id),
+                    _ => None,
+                };
+                if let Some(library) = &self.library {
+                    for image_id in image_ids {
+                        match library.set_flag(image_id, flag) {
+                            Ok(_) => {
+                                if let Some(img) = self.images.iter_mut().find(|img| img.id == image_id) {
+                                    img.flag = if flag.is_empty() { None } else { Some(flag.to_string()) };
+                                }
+                                println!("🚩 Flagged image {}: {}", image_id, if flag.is_empty() { "none" } else { flag });
+                            }
+                            Err(e) => eprintln!("⚠️  Failed to save flag: {:?}", e),
+                        }
+                    }
+                    // Rejects drop out of the grid right away when they're hidden
+                    if self.library_filter.hide_rejects {
+                        (self.images, self.date_sections) = self.library_grid(library);
+                    }
+                }
+                match advance_to {
+                    Some(next_id) => self.update(Message::ImageSelected(next_id)),
+                    None => Task::none(),
+                }
+            }
+            
+            // ========== Phase 25: Zoom & Pan Message Handlers ==========
+
+            Message::Zoom(delta, mut cursor_pos) => {
+                // Phase 26: Zoom to cursor position (not center)
+                
+                // Get cursor position (use last known if sentinel value)
+                if cursor_pos.x < 0.0 || cursor_pos.y < 0.0 {
+                    cursor_pos = self.last_cursor_position.unwrap_or(Point::ORIGIN);
+                }
+                
+                // Get pipeline dimensions for calculations
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    let old_zoom = self.zoom;
+                    
+                    // Phase 26: Calculate actual image position in viewport (centered)
+                    let displayed = self.displayed_image_rect(pipeline);
+                    let image_width = displayed.width;
+                    let image_height = displayed.height;
+                    let (viewport_width, viewport_height) = self.viewport_size.get();
+                    
+                    // Image is centered in viewport, calculate offsets
+                    let x_offset = displayed.x;
+                    let y_offset = displayed.y;
+                    
+                    // Convert viewport cursor position to image-relative position
+                    let image_cursor_x = cursor_pos.x - x_offset;
+                    let image_cursor_y = cursor_pos.y - y_offset;
+                    
+                    // Debug: Show offset calculation (helpful for diagnosing drift)
+                    if false {  // Set to true for debugging
+                        println!("📐 Zoom @ cursor: Viewport={:.0}x{:.0} Image={:.0}x{:.0} Offset=({:.1},{:.1})",
+                            viewport_width, viewport_height, image_width, image_height, x_offset, y_offset);
+                    }
+                    
+                    // Skip if cursor is far outside the image (allow small margins for edge precision)
+                    let margin = 5.0; // Small margin in pixels
+                    if image_cursor_x < -margin || image_cursor_y < -margin || 
+                       image_cursor_x > image_width + margin || image_cursor_y > image_height + margin {
+                        println!("⚠️  Cursor outside image, skipping zoom-to-cursor");
+                        // Just do regular zoom without pan adjustment
+                        if delta > 0.0 {
+                            self.zoom *= 1.0 + (delta * 0.8);
+                        } else {
+                            self.zoom /= 1.0 + (-delta * 0.8);
+                        }
+                        self.zoom = self.zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+                        self.canvas_cache.clear();
+                        return Task::none();
+                    }
+                    
+                    // Clamp cursor to image bounds for calculation
+                    let image_cursor_x = image_cursor_x.clamp(0.0, image_width);
+                    let image_cursor_y = image_cursor_y.clamp(0.0, image_height);
+                    
+                    // Calculate new zoom (exponential scaling)
+                    let new_zoom = if delta > 0.0 {
+                        old_zoom * (1.0 + delta * 0.8)  // Zoom in
+                    } else {
+                        old_zoom / (1.0 + (-delta * 0.8))  // Zoom out
+                    };
+                    self.zoom = new_zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+                    
+                    // Zoom-to-cursor math (matching shader transformation):
+                    // Shader: tex = ((screen - 0.5) / zoom - pan) + 0.5
+                    
+                    // 1. Convert cursor position to normalized image coordinates (0-1)
+                    let norm_cursor_x = image_cursor_x / image_width;
+                    let norm_cursor_y = image_cursor_y / image_height;
+                    
+                    // 2. Find texture point under cursor BEFORE zoom
+                    // tex = ((cursor - 0.5) / old_zoom - old_pan) + 0.5
+                    let tex_x = ((norm_cursor_x - 0.5) / old_zoom - self.pan_offset.x) + 0.5;
+                    let tex_y = ((norm_cursor_y - 0.5) / old_zoom - self.pan_offset.y) + 0.5;
+                    
+                    // 3. Calculate new pan so same texture point appears under cursor AFTER zoom
+                    // We want: cursor = ((tex - 0.5) / new_zoom - new_pan) + 0.5
+                    // Rearranging: new_pan = (tex - 0.5) / new_zoom - (cursor - 0.5)
+                    // Wait, that's wrong. Let me rederive:
+                    // cursor = ((tex - 0.5 - new_pan * new_zoom) / new_zoom) + 0.5
+                    // No wait, the shader is: tex = ((screen - 0.5) / zoom - pan) + 0.5
+                    // So inverse: screen = (tex - 0.5 + pan) * zoom + 0.5
+                    // We want: cursor = (tex - 0.5 + new_pan) * new_zoom + 0.5
+                    // Solving for new_pan:
+                    // cursor - 0.5 = (tex - 0.5 + new_pan) * new_zoom
+                    // (cursor - 0.5) / new_zoom = tex - 0.5 + new_pan
+                    // new_pan = (cursor - 0.5) / new_zoom - tex + 0.5
+                    
+                    self.pan_offset.x = (norm_cursor_x - 0.5) / self.zoom - tex_x + 0.5;
+                    self.pan_offset.y = (norm_cursor_y - 0.5) / self.zoom - tex_y + 0.5;
+                    
+                    println!("🔍 Zoom: {:.1}% (at cursor)", self.zoom * 100.0);
+                } else {
+                    // No pipeline loaded, just do simple zoom
+                    if delta > 0.0 {
+                        self.zoom *= 1.0 + (delta * 0.8);
+                    } else {
+                        self.zoom /= 1.0 + (-delta * 0.8);
+                    }
+                    self.zoom = self.zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+                    println!("🔍 Zoom: {:.1}%", self.zoom * 100.0);
+                }
+                
+                // Invalidate canvas cache to trigger redraw
+                self.canvas_cache.clear();
+                
+                Task::none()
+            }
+            
+            Message::ZoomToFit => {
+                // Phase 26: Reset zoom and pan to default
+                self.zoom = self.fit_zoom;
+                self.pan_offset = cgmath::Vector2::new(0.0, 0.0);
+                self.canvas_cache.clear();
+                println!("🔄 View reset: fit, centered");
+                Task::none()
+            }
+            
+            Message::SetZoom(scale) => {
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    // The shader zooms around the viewport center, so the pan stays
+                    self.zoom = (scale * self.actual_pixels_zoom(pipeline)).clamp(MIN_ZOOM, MAX_ZOOM);
+                    self.canvas_cache.clear();
+                    println!("🔍 Zoom: {:.0}% ({:.2}x fit)", scale * 100.0, self.zoom);
+                }
+                Task::none()
+            }
+            
+            Message::Pan(delta) => {
+                // Phase 25: Apply pan delta scaled by zoom (so panning speed feels consistent)
+                // Scale by 1/zoom so panning at high zoom feels same speed as low zoom
+                let scale = 1.0 / self.zoom;
+                self.pan_offset.x += delta.x * scale;
+                self.pan_offset.y += delta.y * scale;
+                println!("🖐️  Pan: ({:.3}, {:.3}) at zoom {:.1}%", 
+                    self.pan_offset.x, self.pan_offset.y, self.zoom * 100.0);
+                
+                // Invalidate canvas cache to trigger redraw
+                self.canvas_cache.clear();
+                
+                Task::none()
+            }
+            
+            Message::MousePressed => {
+                // Phase 26: Detect double-click for reset view
+                let now = std::time::Instant::now();
+                let is_double_click = if let Some(last_click) = self.last_click_time {
+                    now.duration_since(last_click).as_millis() < 300  // 300ms threshold
+                } else {
+                    false
+                };
+                
+                self.last_click_time = Some(now);
+                
+                // White balance picker: the click samples instead of panning
+                if self.wb_picker_active {
+                    if let Some(position) = self.last_cursor_position {
+                        return self.update(Message::PickWhiteBalance(position));
+                    }
+                    return Task::none();
+                }
+                if self.film_base_picker_active {
+                    if let Some(position) = self.last_cursor_position {
+                        return self.update(Message::PickFilmBase(position));
+                    }
+                    return Task::none();
+                }
+                
+                // Split view: a click on the divider grabs it
+                if self.split_view_active() {
+                    if let (Some(position), EditorStatus::Ready(pipeline)) = (self.last_cursor_position, &self.editor_status) {
+                        let displayed = self.displayed_image_rect(pipeline);
+                        let divider_x = displayed.x + self.split_x * displayed.width;
+                        if (position.x - divider_x).abs() <= SPLIT_GRAB_DISTANCE {
+                            self.dragging_split = true;
+                            return Task::none();
+                        }
+                    }
+                }
+                
+                if is_double_click {
+                    // Double-click detected - reset view
+                    println!("👆 Double-click detected!");
+                    return self.update(Message::ZoomToFit);
+                }
+                
+                // Single click - start dragging for panning
+                self.is_dragging = true;
+                // Position will be updated by next MouseMoved event
+                Task::none()
+            }
+            
+            Message::ToggleWbPicker => {
+                self.wb_picker_active = !self.wb_picker_active;
+                self.is_dragging = false;
+                if self.wb_picker_active {
+                    self.crop_tool_active = false;
+                    self.mask_tool_active = false;
+                    self.film_base_picker_active = false;
+                }
+                println!("💧 WB picker {}", if self.wb_picker_active { "on" } else { "off" });
+                Task::none()
+            }
+            
+            Message::PickWhiteBalance(position) => {
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    let Some((tex_x, tex_y)) = self.viewport_to_texture(position, pipeline) else {
+                        println!("⚠️  WB pick outside image");
+                        return Task::none();
+                    };
+                    
+                    // Texture coords → full resolution sensor pixel
+                    let x = ((tex_x * pipeline.width as f32) as u32).min(pipeline.width - 1);
+                    let y = ((tex_y * pipeline.height as f32) as u32).min(pipeline.height - 1);
+                    
+                    let wb = pipeline
+                        .sample_camera_rgb(x, y)
+                        .and_then(|rgb| color::solve_wb_multipliers(
+                            rgb,
+                            self.current_edit_params.temperature,
+                            self.current_edit_params.tint,
+                        ));
+                    
+                    match wb {
+                        Some(wb) => {
+                            println!("💧 WB picked at ({}, {}): R={:.3}, G={:.3}, B={:.3}", x, y, wb[0], wb[1], wb[2]);
+                            // Part of the edits: saved, undoable, copied and exported with them
+                            self.current_edit_params.wb_override = Some(wb);
+                            self.edit_history.push(self.current_edit_params);
+                            pipeline.update_uniforms(&self.current_edit_params);
+                            self.save_current_edits();
+                            self.canvas_cache.clear();
+                            self.histogram_cache.clear();
+                            self.wb_picker_active = false;
+                        }
+                        None => println!("⚠️  WB pick at ({}, {}) has no usable signal (clipped black?)", x, y),
+                    }
+                }
+                Task::none()
+            }
+            
+            Message::FilmInvertToggled(enabled) => {
+                self.current_edit_params.invert = enabled;
+                self.edit_history.record(self.current_edit_params, "invert");
+                self.save_current_edits();
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.update_uniforms(&self.current_edit_params);
+                    self.canvas_cache.clear();
+                    self.histogram_cache.clear();
+                }
+                Task::none()
+            }
+            
+            Message::ToggleFilmBasePicker => {
+                self.film_base_picker_active = !self.film_base_picker_active;
+                self.is_dragging = false;
+                if self.film_base_picker_active {
+                    self.crop_tool_active = false;
+                    self.mask_tool_active = false;
+                    self.wb_picker_active = false;
+                }
+                println!("🎞️  Film base picker {}", if self.film_base_picker_active { "on" } else { "off" });
+                Task::none()
+            }
+            
+            Message::PickFilmBase(position) => {
+                let EditorStatus::Ready(pipeline) = &self.editor_status else {
+                    return Task::none();
+                };
+                let Some((tex_x, tex_y)) = self.viewport_to_texture(position, pipeline) else {
+                    println!("⚠️  Film base pick outside image");
+                    return Task::none();
+                };
+                let x = ((tex_x * pipeline.width as f32) as u32).min(pipeline.width - 1);
+                let y = ((tex_y * pipeline.height as f32) as u32).min(pipeline.height - 1);
+                
+                // A black sample can't be divided by
+                let Some(film_base) = pipeline
+                    .sample_working_rgb(x, y, &self.current_edit_params)
+                    .filter(|rgb| rgb.iter().all(|&c| c > 0.0001))
+                else {
+                    println!("⚠️  Film base pick at ({}, {}) has no usable signal", x, y);
+                    return Task::none();
+                };
+                println!("🎞️  Film base picked at ({}, {}): R={:.3}, G={:.3}, B={:.3}", x, y, film_base[0], film_base[1], film_base[2]);
+                
+                // Picking the base means this is a negative: switch inversion on too
+                self.current_edit_params.film_base = film_base;
+                self.current_edit_params.invert = true;
+                self.edit_history.push(self.current_edit_params);
+                pipeline.update_uniforms(&self.current_edit_params);
+                self.save_current_edits();
+                self.canvas_cache.clear();
+                self.histogram_cache.clear();
+                self.film_base_picker_active = false;
+                Task::none()
+            }
+            
+            Message::MouseReleased => {
+                // Stop dragging
+                self.is_dragging = false;
+                self.dragging_split = false;
+                self.last_cursor_position = None;
+                Task::none()
+            }
+            
+            Message::MouseMoved(current_position, viewport) => {
+                // Phase 26: The viewport size is now exact - it comes from the
+                // preview's layout (responsive wrapper), not guessed from how far
+                // the cursor has travelled, so zoom-to-cursor is right from the
+                // first scroll and follows window resizes
+                self.viewport_size.set((viewport.width, viewport.height));
+                
+                // Dragging the split divider: follow the cursor across the image
+                if self.dragging_split {
+                    if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                        let displayed = self.displayed_image_rect(pipeline);
+                        self.split_x = ((current_position.x - displayed.x) / displayed.width).clamp(0.0, 1.0);
+                    }
+                    self.last_cursor_position = Some(current_position);
+                    return Task::none();
+                }
+                
+                // If dragging, calculate pan delta and send Pan message
+                if self.is_dragging {
+                    if let Some(last_pos) = self.last_cursor_position {
+                        // Calculate delta in screen pixels
+                        let delta_x = current_position.x - last_pos.x;
+                        let delta_y = current_position.y - last_pos.y;
+                        
+                        // Phase 26: Pan sensitivity using image dimensions (not viewport)
+                        // Pan offset is in normalized image coordinates
+                        let (sensitivity_x, sensitivity_y) = if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                            let displayed = self.displayed_image_rect(pipeline);
+                            (
+                                1.0 / displayed.width,
+                                1.0 / displayed.height,
+                            )
+                        } else {
+                            (0.001, 0.001)
+                        };
+                        
+                        let delta = cgmath::Vector2::new(
+                            delta_x * sensitivity_x,
+                            delta_y * sensitivity_y,
+                        );
+                        
+                        // Update cursor position AFTER calculating delta
+                        self.last_cursor_position = Some(current_position);
+                        
+                        // Send Pan message
+                        return self.update(Message::Pan(delta));
+                    }
+                }
+                
+                // Store cursor position for zoom-to-cursor (if not dragging)
+                self.last_cursor_position = Some(current_position);
+                self.pixel_sample = self.sample_under_cursor(current_position);
+                Task::none()
+            }
+            
+            Message::PreviewCursorLeft => {
+                self.pixel_sample = None;
+                Task::none()
+            }
+            
+            Message::SensorReadoutToggled(enabled) => {
+                self.show_sensor_values = enabled;
+                Task::none()
+            }
+            
+            // ========== GPU Pipeline Message Handlers ==========
+            
+            Message::RawDataLoaded(result) => {
+                match result {
+                    Ok(raw_data) => {
+                        let image_id = self.selected_image_id.unwrap_or(0);  // Phase 20: Track which image
+                        self.create_pipeline(image_id, raw_data, self.current_edit_params, |result| {
+                            Message::GpuPipelineReady(result.map(Arc::new))
+                        })
+                    }
+                    Err(err) => {
+                        eprintln!("⚠️  Failed to load RAW data: {}", err);
+                        self.notify(Severity::Error, format!("Couldn't decode RAW: {}", err));
+                        self.editor_status = EditorStatus::Failed(
+                            self.selected_image_id.unwrap_or(0),
+                            err,
+                        );
+                        Task::none()
+                    }
+                }
+            }
+            
+            Message::GpuPipelineReady(result) => {
+                match result {
+                    Ok(pipeline) => {
+                        println!("🎨 GPU pipeline initialized!");
+                        // No GPU adapter: say so, everything will be slow
+                        self.software_rendering |= pipeline.is_software();
+                        
+                        // Phase 25: Clear canvas cache since this is a new pipeline for a new image
+                        self.canvas_cache.clear();
+                        
+                        // New pipelines start with the detail preview off; clipping
+                        // warnings stay as the user left them
+                        self.detail_preview = false;
+                        pipeline.set_show_clipping(self.show_clipping);
+                        pipeline.set_orientation(self.image_orientation(pipeline.image_id));
+                        pipeline.set_lens_profile(self.lens_profile(pipeline.image_id));
+                        
+                        // Keep the pipeline for coming back to this image
+                        self.pipeline_cache.insert(pipeline.image_id, pipeline.clone());
+                        
+                        // Store pipeline in EditorStatus::Ready (unless the user has
+                        // left Develop meanwhile - then it just waits in the cache)
+                        if self.current_tab == AppTab::Develop {
+                            self.fit_to_viewport(&pipeline);
+                            self.editor_status = EditorStatus::Ready(pipeline);
+                        }
+                        
+                        Task::none()
+                    }
+                    Err(err) => {
+                        eprintln!("⚠️  Failed to initialize GPU pipeline: {}", err);
+                        self.notify(Severity::Error, format!("Couldn't prepare the image: {}", err));
+                        self.editor_status = EditorStatus::Failed(
+                            self.selected_image_id.unwrap_or(0),
+                            err,
+                        );
+                        Task::none()
+                    }
+                }
+            }
+            
+            Message::EnterCompare(left_id, right_id) => {
+                if left_id == right_id {
+                    return Task::none();
+                }
+                println!("🆚 Comparing images {} and {}", left_id, right_id);
+                
+                // The left image becomes the selected one (edits and ratings go
+                // to it), but both stay selected in the grid
+                let selected = std::mem::take(&mut self.selected_ids);
+                let select = self.update(Message::ImageSelected(left_id));
+                self.selected_ids = selected;
+                self.crop_tool_active = false;
+                self.mask_tool_active = false;
+                self.wb_picker_active = false;
+                self.film_base_picker_active = false;
+                self.show_before = false;
+                let open = self.update(Message::TabChanged(AppTab::Develop));
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    pipeline.set_detail_preview(false);
+                }
+                self.detail_preview = false;
+                
+                self.compare_with = Some(right_id);
+                self.compare_params = self.library
+                    .as_ref()
+                    .and_then(|lib| lib.load_edit_params(right_id).ok())
+                    .unwrap_or_default();
+                let load_right = self.load_compare_pipeline(right_id);
+                Task::batch([select, open, load_right])
+            }
+            
+            Message::ExitCompare => {
+                self.exit_compare();
+                self.preview_frame.clear();
+                Task::none()
+            }
+            
+            Message::CompareRawDataLoaded(image_id, result) => {
+                if self.compare_with != Some(image_id) {
+                    return Task::none(); // Left compare meanwhile
+                }
+                match result {
+                    Ok(raw_data) => self.create_pipeline(image_id, raw_data, self.compare_params, move |result| {
+                        Message::ComparePipelineReady(image_id, result.map(Arc::new))
+                    }),
+                    Err(err) => {
+                        eprintln!("⚠️  Failed to load RAW data for compare: {}", err);
+                        self.compare_status = EditorStatus::Failed(image_id, err);
+                        Task::none()
+                    }
+                }
+            }
+            
+            Message::ComparePipelineReady(image_id, result) => {
+                match result {
+                    Ok(pipeline) => {
+                        pipeline.set_show_clipping(self.show_clipping);
+                        pipeline.set_orientation(self.image_orientation(image_id));
+                        pipeline.set_lens_profile(self.lens_profile(image_id));
+                        self.pipeline_cache.insert(image_id, pipeline.clone());
+                        if self.compare_with == Some(image_id) {
+                            self.compare_status = EditorStatus::Ready(pipeline);
+                        }
+                    }
+                    Err(err) => {
+                        eprintln!("⚠️  Failed to initialize GPU pipeline for compare: {}", err);
+                        if self.compare_with == Some(image_id) {
+                            self.compare_status = EditorStatus::Failed(image_id, err);
+                        }
+                    }
+                }
+                Task::none()
+            }
+            
+            Message::ExportImage => {
+                if self.export_progress.is_some() {
+                    return Task::none(); // One export at a time
+                }
+                // Phase 19: Export full-resolution image
+                if let EditorStatus::Ready(pipeline) = &self.editor_status {
+                    // Suggested name comes from the export filename template
+                    let file_name = self.images
+                        .iter()
+                        .find(|img| Some(img.id) == self.selected_image_id)
+                        .map(|img| state::export::export_file_name(
+                            &self.batch_export_template,
+                            &self.export_context(img, 1),
+                            self.batch_export_format,
+                        ))
+                        .unwrap_or_else(|| "export.jpg".to_string());
+                    // Show file save dialog
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("JPEG Image", &["jpg", "jpeg"])
+                        .add_filter("PNG Image", &["png"])
+                        .add_filter("TIFF Image (16-bit)", &["tif", "tiff"])
+                        .set_file_name(file_name)
+                        .save_file()
+                    {
+                        println!("📤 Exporting to: {:?}", path);
+                        let pipeline_clone = Arc::clone(pipeline);
+                        self.exports_in_flight.push(path.clone());
+                        let raw_path = self.images
+                            .iter()
+                            .find(|img| Some(img.id) == self.selected_image_id)
+                            .map(|img| PathBuf::from(&img.path))
+                            .unwrap_or_default();
+                        
+                        // Run export in background to avoid freezing UI
+                        let job = ExportJob {
+                            pipeline: pipeline_clone,
+                            params: self.current_edit_params,
+                            raw_path,
+                            save_path: path,
+                            options: self.export_options,
+                            include_metadata: self.include_metadata,
+                            metadata: self.export_metadata.clone(),
+                        };
+                        self.export_progress = Some(0.0);
+                        self.export_cancel = CancelToken::default();
+                        return Task::run(export_image_stream(job, self.export_cancel.clone()), |message| message);
+                    }
+                }
+                Task::none()
+            }
+            
+            Message::ExportProgress(done) => {
+                if self.export_progress.is_some() {
+                    self.export_progress = Some(done);
+                }
+                Task::none()
+            }
+            
+            Message::CancelExport => {
+                println!("🛑 Cancelling export...");
+                self.export_cancel.cancel();
+                Task::none()
+            }
+            
+            Message::DismissNotification(id) => {
+                self.notifications.retain(|notification| notification.id != id);
+                Task::none()
+            }
+            
+            Message::NotificationTick => {
+                let now = std::time::Instant::now();
+                self.notifications.retain(|notification| !notification.expired(now));
+                Task::none()
+            }
+            
+            Message::SpinnerTick => {
+                self.spinner_frame = self.spinner_frame.wrapping_add(1);
+                Task::none()
+            }
+            
+            Message::ExportComplete(path, result) => {
+                self.exports_in_flight.retain(|in_flight| *in_flight != path);
+                self.export_progress = None;
+                match result {
+                    Ok(()) => {
+                        println!("✅ Export complete: {:?}", path);
+                        self.notify(Severity::Success, format!("Saved to {}", path.display()));
+                    }
+                    Err(err) if err == EXPORT_CANCELLED => {
+                        println!("🛑 Export cancelled");
+                        self.notify(Severity::Info, "Export cancelled".to_string());
+                    }
+                    Err(err) => {
+                        eprintln!("❌ Export failed: {}", err);
+                        self.notify(Severity::Error, format!("Export failed: {}", err));
+                    }
+                }
+                self.finish_quit_if_idle()
+            }
+            
+            Message::BatchExport => {
+                if self.batch_export_progress.is_some() {
+                    return Task::none(); // One batch at a time
+                }
+                let selection = self.selection_in_grid_order();
+                if selection.is_empty() {
+                    return Task::none();
+                }
+                let Some(folder) = FileDialog::new().pick_folder() else {
+                    return Task::none();
+                };
+                
+                // Gather everything the background task needs up front:
+                // the Library connection can't leave the main thread
+                let jobs: Vec<BatchExportJob> = selection
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, &image_id)| {
+                        let img = self.images.iter().find(|img| img.id == image_id)?;
+                        let params = self.library
+                            .as_ref()
+                            .and_then(|lib| lib.load_edit_params(image_id).ok())
+                            .unwrap_or_default();
+                        let file_name = state::export::export_file_name(
+                            &self.batch_export_template,
+                            &self.export_context(img, i + 1),
+                            self.batch_export_format,
+                        );
+                        Some(BatchExportJob {
+                            raw_path: img.path.clone(),
+                            output_path: folder.join(file_name),
+                            orientation: state::orientation::Orientation::from_exif(img.orientation),
+                            lens_profile: self.lens_profile(image_id),
+                            params,
+                            options: self.export_options,
+                            include_metadata: self.include_metadata,
+                            metadata: self.export_metadata.clone(),
+                        })
+                    })
+                    .collect();
+                
+                println!("📤 Batch exporting {} images to {:?}", jobs.len(), folder);
+                self.batch_export_progress = Some((0, jobs.len()));
+                self.status = format!("Batch export: 0/{}", jobs.len());
+                Task::run(batch_export_stream(jobs, self.shutdown.clone()), |message| message)
+            }
+            
+            Message::BatchExportFormatChanged(format) => {
+                self.batch_export_format = format;
+                self.save_setting(state::settings::EXPORT_FORMAT_KEY, format.extension());
+                Task::none()
+            }
+            
+            Message::BatchExportTemplateChanged(template) => {
+                self.save_setting(state::settings::EXPORT_TEMPLATE_KEY, &template);
+                self.batch_export_template = template;
+                Task::none()
+            }
+            
+            Message::JpegQualityChanged(quality) => {
+                self.export_options.jpeg_quality = quality;
+                self.save_setting(state::settings::JPEG_QUALITY_KEY, &quality.to_string());
+                Task::none()
+            }
+            
+            Message::PngCompressionChanged(compression) => {
+                self.export_options.png_compression = compression;
+                Task::none()
+            }
+            
+            Message::ExportMetadataToggled(enabled) => {
+                self.include_metadata = enabled;
+                self.save_setting(state::settings::EXPORT_METADATA_KEY, if enabled { "true" } else { "false" });
+                Task::none()
+            }
+            
+            Message::ExportArtistChanged(artist) => {
+                self.save_setting(state::settings::EXPORT_ARTIST_KEY, &artist);
+                self.export_metadata.artist = artist;
+                Task::none()
+            }
+            
+            Message::ExportCopyrightChanged(copyright) => {
+                self.save_setting(state::settings::EXPORT_COPYRIGHT_KEY, &copyright);
+                self.export_metadata.copyright = copyright;
+                Task::none()
+            }
+            
+            Message::ExportGpsToggled(enabled) => {
+                self.export_metadata.include_gps = enabled;
+                self.save_setting(state::settings::EXPORT_GPS_KEY, if enabled { "true" } else { "false" });
+                Task::none()
+            }
+            
+            Message::ExportMakerNotesToggled(enabled) => {
+                self.export_metadata.include_maker_notes = enabled;
+                self.save_setting(state::settings::EXPORT_MAKER_NOTES_KEY, if enabled { "true" } else { "false" });
+                Task::none()
+            }
+            
+            Message::EmbedRecipeToggled(enabled) => {
+                self.export_options.embed_recipe = enabled;
+                self.save_setting(state::settings::EXPORT_RECIPE_KEY, if enabled { "true" } else { "false" });
+                Task::none()
+            }
+            
+            Message::BatchExportProgress { done, total, current } => {
+                self.batch_export_current = current;
+                if self.batch_export_current.is_some() {
+                    self.batch_export_progress = Some((done, total));
+                    self.status = format!("Batch export: {}/{}", done, total);
+                } else {
+                    self.batch_export_progress = None;
+                    self.status = if done < total {
+                        format!("Batch export stopped: {}/{} images", done, total)
+                    } else {
+                        format!("Batch export complete: {} images", total)
+                    };
+                    let severity = if done < total { Severity::Info } else { Severity::Success };
+                    self.notify(severity, self.status.clone());
+                }
+                self.finish_quit_if_idle()
+            }
+            
+            Message::HistogramToggled(enabled) => {
+                self.histogram_enabled = enabled;
+                self.preview_frame.clear(); // Render once more so the histogram gets filled in
+                println!("📊 Histogram {}", if enabled { "enabled" } else { "disabled" });
+                
+                // Phase 25: If enabling, clear canvas cache to force recalculation
+                if enabled {
+                    self.canvas_cache.clear();
+                }
+                
+                Task::none()
+            }
+            
+            Message::HistogramModeChanged(mode) => {
+                self.histogram_mode = mode;
+                self.histogram_cache.clear();
+                Task::none()
+            }
+            
+            Message::HistogramLogScaleToggled(enabled) => {
+                self.histogram_log_scale = enabled;
+                self.histogram_cache.clear();
+                Task::none()
+            }
+            
+            Message::GpuBackendChanged(backend) => {
+                self.gpu_backend = backend;
+                self.save_setting(state::settings::GPU_BACKEND_KEY, backend.as_str());
+                Task::none()
+            }
+            
+            Message::GpuPowerChanged(power) => {
+                self.gpu_power = power;
+                self.save_setting(state::settings::GPU_POWER_KEY, power.as_str());
+                Task::none()
+            }
+            
+            Message::UiThemeChanged(theme) => {
+                self.ui_theme = theme;
+                self.save_setting(state::settings::UI_THEME_KEY, theme.as_str());
+                Task::none()
+            }
+            
+            Message::AccentColorChanged(accent) => {
+                self.accent_color = accent;
+                self.save_setting(state::settings::ACCENT_COLOR_KEY, accent.as_str());
+                Task::none()
+            }
+            
+            Message::GpuAdapterLoaded(result) => {
+                self.gpu_adapter = Some(result);
+                Task::none()
+            }
+            
+            Message::SidecarWritingToggled(enabled) => {
+                self.write_sidecars = enabled;
+                self.save_setting(state::settings::WRITE_SIDECARS_KEY, if enabled { "true" } else { "false" });
+                println!("📝 XMP sidecars {}", if enabled { "enabled" } else { "disabled" });
+                
+                // Write the current image's sidecar right away so it's in sync
+                if enabled {
+                    self.write_current_sidecar();
+                } else {
+                    self.sidecar_pending = None;
+                }
+                
+                Task::none()
+            }
+            
+            Message::SidecarTick => {
+                if self.sidecar_pending.is_some_and(|(_, edited)| edited.elapsed() >= SIDECAR_WRITE_DELAY) {
+                    self.flush_sidecar();
+                }
+                Task::none()
+            }
+            
+            Message::AutoAdvanceToggled(enabled) => {
+                self.auto_advance = enabled;
+                self.save_setting(state::settings::AUTO_ADVANCE_KEY, if enabled { "true" } else { "false" });
+                Task::none()
+            }
+            
+            Message::FastPreviewToggled(enabled) => {
+                self.fast_preview = enabled;
+                self.save_setting(state::settings::FAST_PREVIEW_KEY, if enabled { "true" } else { "false" });
+                Task::none()
+            }
+            
+            Message::EditedThumbnailsToggled(enabled) => {
+                self.edited_thumbnails = enabled;
+                self.save_setting(state::settings::EDITED_THUMBNAILS_KEY, if enabled { "true" } else { "false" });
+                println!("🖼️  Edited thumbnails {}", if enabled { "enabled" } else { "disabled" });
+                if !enabled {
+                    self.thumbnail_refresh.clear();
+                }
+                Task::none()
+            }
+            
+            Message::EditedThumbnailRendered(image_id, path) => {
+                let path = path.to_string_lossy().to_string();
+                if let Some(library) = &self.library {
+                    if let Err(e) = library.update_thumbnail(image_id, &path) {
+                        eprintln!("⚠️  Failed to save thumbnail path for image {}: {:?}", image_id, e);
+                    }
+                }
+                if let Some(img) = self.images.iter_mut().find(|img| img.id == image_id) {
+                    img.cache_path_thumb = Some(path);
+                }
+                println!("🖼️  Rendered edited thumbnail for image {}", image_id);
+                Task::none()
+            }
+            
+            Message::ChooseCacheDir => {
+                let Some(folder) = FileDialog::new().pick_folder() else {
+                    return Task::none();
+                };
+                self.save_setting(state::settings::CACHE_DIR_KEY, &folder.to_string_lossy());
+                state::settings::set_cache_dir(Some(folder.clone()));
+                println!("📁 Cache folder: {}", folder.display());
+                self.cache_dir = Some(folder);
+                measure_cache()
+            }
+            
+            Message::CustomExtensionsChanged(input) => {
+                self.custom_extensions = state::settings::parse_extensions(&input);
+                self.save_setting(state::settings::CUSTOM_EXTENSIONS_KEY, &self.custom_extensions.join(","));
+                self.custom_extensions_input = input;
+                Task::none()
+            }
+            
+            Message::ResetCacheDir => {
+                self.save_setting(state::settings::CACHE_DIR_KEY, "");
+                state::settings::set_cache_dir(None);
+                self.cache_dir = None;
+                println!("📁 Cache folder: default");
+                measure_cache()
+            }
+            
+            Message::ClearCache => {
+                if self.library.is_none() || self.cache_clearing.is_some() {
+                    return Task::none();
+                }
+                if self.thumbnail_worker_active {
+                    // Deleting under a running batch would leave rows pointing at
+                    // files that are gone; ThumbnailProgress carries on from here
+                    self.thumbnail_stop.cancel();
+                    self.cache_clearing = Some(CacheClearing::StoppingWorker);
+                    self.status = "Stopping thumbnail generation to clear the cache...".to_string();
+                    return Task::none();
+                }
+                self.start_cache_clear()
+            }
+            
+            Message::CacheCleared(removed, queued) => {
+                self.cache_clearing = None;
+                self.thumbnail_stop = self.shutdown.child();
+                println!("🧹 Cleared cache: {} files deleted", removed);
+                if self.shutdown.is_cancelled() {
+                    return self.finish_quit_if_idle();
+                }
+                self.notify(Severity::Success, format!("Cache cleared ({} files deleted)", removed));
+                Task::batch([self.restart_thumbnail_worker(queued), measure_cache()])
+            }
+            
+            Message::CacheSizeMeasured(bytes) => {
+                self.cache_size = Some(bytes);
+                Task::none()
+            }
+            
+            Message::ChoosePhotosRoot => {
+                let Some(folder) = FileDialog::new().pick_folder() else {
+                    return Task::none();
+                };
+                self.save_setting(state::settings::PHOTOS_ROOT_KEY, &folder.to_string_lossy());
+                println!("📁 Photos folder: {}", folder.display());
+                self.photos_root = Some(folder);
+                Task::none()
+            }
+            
+            Message::ResetPhotosRoot => {
+                self.save_setting(state::settings::PHOTOS_ROOT_KEY, "");
+                self.photos_root = None;
+                println!("📁 Photos folder: none");
+                Task::none()
+            }
+            
+            Message::ExportCatalog => {
+                let Some(library) = &self.library else {
+                    return Task::none();
+                };
+                let Some(path) = FileDialog::new()
+                    .add_filter("Catalog backup", &["json"])
+                    .set_file_name("raw-editor-catalog.json")
+                    .save_file()
+                else {
+                    return Task::none();
+                };
+                match library.export_catalog(&path, self.photos_root.as_deref()) {
+                    Ok(count) => {
+                        println!("💾 Backed up {} images to {}", count, path.display());
+                        self.notify(Severity::Success, format!("Backed up {} images to {}", count, path.display()));
+                    }
+                    Err(err) => {
+                        eprintln!("❌ Catalog backup failed: {}", err);
+                        self.notify(Severity::Error, format!("Catalog backup failed: {}", err));
+                    }
+                }
+                Task::none()
+            }
+            
+            Message::ImportCatalog => {
+                let Some(library) = &self.library else {
+                    return Task::none();
+                };
+                let Some(path) = FileDialog::new()
+                    .add_filter("Catalog backup", &["json"])
+                    .pick_file()
+                else {
+                    return Task::none();
+                };
+                match library.import_catalog(&path, self.photos_root.as_deref()) {
+                    Ok(summary) => {
+                        println!("📥 Imported catalog backup {}: {}", path.display(), summary);
+                        (self.images, self.date_sections) = self.library_grid(library);
+                        // New images need thumbnails, and the open image may have new
+                        // edits, keywords and orientation
+                        self.thumbnail_worker_active = true;
+                        if let Some(image_id) = self.selected_image_id {
+                            self.current_edit_params = library.load_edit_params(image_id).unwrap_or_default();
+                            self.keywords = library.get_keywords(image_id).unwrap_or_default();
+                            self.edit_history.reset(self.current_edit_params);
+                        }
+                        for pipeline in self.pipeline_cache.values() {
+                            pipeline.set_orientation(self.image_orientation(pipeline.image_id));
+                        }
+                        self.notify(Severity::Success, format!("Imported catalog backup: {}", summary));
+                    }
+                    Err(err) => {
+                        eprintln!("❌ Catalog import failed: {}", err);
+                        self.notify(Severity::Error, format!("Catalog import failed: {}", err));
+                    }
+                }
+                Task::none()
+            }
+        }
+    }
+    
+    /// Background work that closing the window would cut off
+    fn background_work(&self) -> Vec<&'static str> {
+        let running = [
+            (self.importing, "import"),
+            (self.thumbnail_worker_active, "thumbnails"),
+            (self.cache_clearing.is_some(), "clearing the cache"),
+            (self.cache_queue_active, "cache tiers"),
+            (self.batch_export_progress.is_some(), "batch export"),
+            (!self.exports_in_flight.is_empty(), "export"),
+        ];
+        running.into_iter().filter(|(active, _)| *active).map(|(_, name)| name).collect()
+    }
+    
+    /// Enter or leave presentation mode. Fullscreen is borderless on the
+    /// monitor the window is on, so it follows the window across screens.
+    fn set_fullscreen_preview(&mut self, enabled: bool) -> Task<Message> {
+        if self.fullscreen_preview == enabled {
+            return Task::none();
+        }
+        self.fullscreen_preview = enabled;
+        println!("{} Presentation mode {}", if enabled { "🖥️ " } else { "🪟" }, if enabled { "on" } else { "off" });
+        let mode = if enabled { window::Mode::Fullscreen } else { window::Mode::Windowed };
+        window::get_latest().and_then(move |id| window::change_mode(id, mode))
+    }
+    
+    /// Anything in flight the busy spinner should show: the catalog opening,
+    /// Develop decoding an image, or background work
+    fn is_loading(&self) -> bool {
+        (self.library.is_none() && self.db_error.is_none())
+            || matches!(self.editor_status, EditorStatus::Loading(_))
+            || !self.background_work().is_empty()
+    }
+    
+    /// Quitting: close the window once the last background task has stopped
+    /// Queue a toast (callers still log to the console themselves)
+    fn notify(&mut self, severity: Severity, message: String) {
+        self.notifications.push(ui::notifications::Notification {
+            id: self.next_notification_id,
+            severity,
+            message,
+            created: std::time::Instant::now(),
+        });
+        self.next_notification_id += 1;
+    }
+    
+    fn finish_quit_if_idle(&mut self) -> Task<Message> {
+        if !self.shutdown.is_cancelled() || !self.background_work().is_empty() {
+            return Task::none();
+        }
+        self.quit_pending.take().map_or_else(Task::none, close_window)
+    }
+    
+    /// Reset every cache row and delete the cache files in the background
+    /// (CacheCleared); the thumbnail worker must not be running
+    fn start_cache_clear(&mut self) -> Task<Message> {
+        let Some(library) = &self.library else {
+            self.cache_clearing = None;
+            return Task::none();
+        };
+        match library.clear_all_caches() {
+            Ok(queued) => {
+                // Placeholders while the files go
+                (self.images, self.date_sections) = self.library_grid(library);
+                self.cache_clearing = Some(CacheClearing::Deleting);
+                self.status = "🧹 Clearing the cache...".to_string();
+                Task::perform(
+                    async {
+                        let root = state::settings::cache_root();
+                        tokio::task::spawn_blocking(move || state::settings::clear_cache_folders(&root))
+                            .await
+                            .unwrap_or(0)
+                    },
+                    move |removed| Message::CacheCleared(removed, queued),
+                )
+            }
+            Err(e) => {
+                eprintln!("❌ Failed to clear cache: {}", e);
+                self.notify(Severity::Error, format!("Couldn't clear the cache: {}", e));
+                // Whatever the worker had left is still queued
+                self.cache_clearing = None;
+                self.thumbnail_stop = self.shutdown.child();
+                self.thumbnail_worker_active = true;
+                Task::none()
+            }
+        }
+    }
+    
+    /// After cache rows were reset to pending: show the placeholders and let
+    /// the thumbnail worker pick them up
+    fn restart_thumbnail_worker(&mut self, queued: usize) -> Task<Message> {
+        if let Some(library) = &self.library {
+            (self.images, self.date_sections) = self.library_grid(library);
+        }
+        if queued > 0 {
+            self.thumbnail_worker_active = true;
+        }
+        self.status = format!("🔄 Regenerating thumbnails for {} images", queued);
+        println!("🔄 Queued {} images for thumbnail regeneration", queued);
+        Task::none()
+    }
+    
+    /// Store a setting in the catalog (logged and ignored if it fails)
+    fn save_setting(&self, key: &str, value: &str) {
+        if let Some(library) = &self.library {
+            if let Err(e) = library.set_setting(key, value) {
+                eprintln!("❌ Failed to save setting {}: {}", key, e);
+            }
+        }
+    }
+    
+    /// GPU memory held by the pipeline cache
+    fn cached_gpu_memory_bytes(&self) -> u64 {
+        self.pipeline_cache.values().map(|pipeline| pipeline.gpu_memory_bytes()).sum()
+    }
+    
+    /// Orientation of a library image (upright if it isn't in the grid)
+    fn image_orientation(&self, image_id: i64) -> state::orientation::Orientation {
+        self.images
+            .iter()
+            .find(|img| img.id == image_id)
+            .map(|img| state::orientation::Orientation::from_exif(img.orientation))
+            .unwrap_or_default()
+    }
+    
+    /// Select the image and open the tab from when the app was last closed
+    /// (skipped if that image has been removed since)
+    fn restore_last_session(&mut self) -> Task<Message> {
+        let Some(library) = &self.library else {
+            return Task::none();
+        };
+        let get = |key: &str| library.get_setting(key).ok().flatten();
+        let last_image = get(state::settings::LAST_IMAGE_KEY)
+            .and_then(|value| value.parse::<i64>().ok())
+            .filter(|id| self.images.iter().any(|img| img.id == *id));
+        let last_tab = get(state::settings::LAST_TAB_KEY)
+            .and_then(|value| AppTab::from_setting(&value))
+            .unwrap_or(AppTab::Library);
+        
+        let Some(image_id) = last_image else {
+            // Develop needs an image; Preferences doesn't
+            return match last_tab {
+                AppTab::Preferences => self.update(Message::TabChanged(last_tab)),
+                _ => Task::none(),
+            };
+        };
+        println!("↩️  Restoring last session: image {} in {:?}", image_id, last_tab);
+        let select = self.update(Message::ImageSelected(image_id));
+        let open_tab = self.update(Message::TabChanged(last_tab));
+        Task::batch([select, open_tab])
+    }
+    
+    /// Built-in lens profile for a library image (None if its lens isn't known)
+    fn lens_profile(&self, image_id: i64) -> Option<state::lens::LensProfile> {
+        let metadata = self.library.as_ref()?.get_metadata(image_id).ok()?;
+        state::lens::find_profile(metadata.lens_model.as_deref())
+    }
+    
+    /// Render new thumbnails for the images in `thumbnail_refresh`, in the
+    /// background (one RAW load and a small pipeline per image)
+    fn refresh_edited_thumbnails(&mut self) -> Task<Message> {
+        let pending = std::mem::take(&mut self.thumbnail_refresh);
+        let Some(library) = self.library.as_ref().filter(|_| self.edited_thumbnails && !pending.is_empty()) else {
+            return Task::none();
+        };
+        let jobs: Vec<ThumbnailJob> = pending
+            .into_iter()
+            .filter_map(|image_id| {
+                let img = self.images.iter().find(|img| img.id == image_id)?;
+                Some(ThumbnailJob {
+                    image_id,
+                    raw_path: img.path.clone(),
+                    orientation: state::orientation::Orientation::from_exif(img.orientation),
+                    lens_profile: self.lens_profile(image_id),
+                    params: library.load_edit_params(image_id).unwrap_or_default(),
+                })
+            })
+            .collect();
+        println!("🖼️  Rendering {} edited thumbnail(s)", jobs.len());
+        Task::run(edited_thumbnail_stream(jobs, self.shutdown.clone()), |message| message)
+    }
+    
+    /// Show an image in Develop: reuse its pipeline from the cache, or start
+    /// the RAW decode (the slow 3-second operation) and build a new one
+    fn load_pipeline(&mut self, image_id: i64) -> Task<Message> {
+        if let Some(pipeline) = self.pipeline_cache.get(image_id) {
+            println!("⚡ Pipeline cache hit for image {}", image_id);
+            pipeline.set_detail_preview(false);
+            self.detail_preview = false;
+            pipeline.set_show_clipping(self.show_clipping);
+            self.fit_to_viewport(&pipeline);
+            self.editor_status = EditorStatus::Ready(pipeline);
+            return Task::none();
+        }
+        
+        // Find the image and start loading
+        let Some(img) = self.images.iter().find(|i| i.id == image_id) else {
+            return Task::none();
+        };
+        let raw_path = img.path.clone();
+        
+        // Set editor status to loading
+        self.editor_status = EditorStatus::Loading(image_id);
+        
+        // Load RAW sensor data for GPU processing
+        Task::perform(
+            raw::loader::load_raw_data(raw_path),
+            Message::RawDataLoaded,
+        )
+    }
+    
+    /// Build a GPU pipeline for decoded RAW data on the shared device;
+    /// `on_ready` turns the result into the message that receives it
+    fn create_pipeline(
+        &self,
+        image_id: i64,
+        raw_data: raw::loader::RawDataResult,
+        params: state::edit::EditParams,
+        on_ready: impl Fn(Result<gpu::RenderPipeline, String>) -> Message + Send + 'static,
+    ) -> Task<Message> {
+        println!("📷 RAW data loaded: {}x{} pixels", raw_data.width, raw_data.height);
+        
+        // Phase 15: Calculate proper cam-to-sRGB color matrix
+        // (DNGs with a forward matrix use that instead)
+        let cam_to_srgb = camera_to_srgb(raw_data.color_matrix, raw_data.forward_matrix);
+        println!("🎨 CAM-to-sRGB Matrix: [{:.3}, {:.3}, {:.3}]", 
+            cam_to_srgb[0], cam_to_srgb[1], cam_to_srgb[2]);
+        println!("                      [{:.3}, {:.3}, {:.3}]", 
+            cam_to_srgb[3], cam_to_srgb[4], cam_to_srgb[5]);
+        println!("                      [{:.3}, {:.3}, {:.3}]", 
+            cam_to_srgb[6], cam_to_srgb[7], cam_to_srgb[8]);
+        
+        // Create GPU pipeline with the RAW data + color metadata
+        let wb = raw_data.wb_multipliers;
+        let max_preview_width = self.preview_quality.max_width();
+        
+        Task::perform(
+            async move {
+                // The device is created once and shared by every pipeline
+                // (no GPU adapter at all: the pipeline renders on the CPU)
+                gpu::RenderPipeline::new(
+                    gpu::GpuContext::shared_or_cpu().await?,
+                    image_id,         // Phase 20: Track which image this pipeline is for
+                    raw_data.data,
+                    raw_data.width,
+                    raw_data.height,
+                    &params,
+                    wb,           // Phase 14: White balance from camera
+                    cam_to_srgb,  // Phase 15: Camera-to-sRGB color matrix
+                    raw_data.cfa_pattern,
+                    raw_data.levels,
+                    max_preview_width,
+                ).await
+            },
+            on_ready,
+        )
+    }
+    
+    /// Right-hand compare image: its pipeline from the cache, or a RAW decode
+    /// running alongside the left one's
+    fn load_compare_pipeline(&mut self, image_id: i64) -> Task<Message> {
+        self.compare_frame.clear();
+        if let Some(pipeline) = self.pipeline_cache.get(image_id) {
+            println!("⚡ Pipeline cache hit for compare image {}", image_id);
+            pipeline.set_detail_preview(false);
+            pipeline.set_show_clipping(self.show_clipping);
+            self.compare_status = EditorStatus::Ready(pipeline);
+            return Task::none();
+        }
+        
+        let Some(img) = self.images.iter().find(|i| i.id == image_id) else {
+            return Task::none();
+        };
+        self.compare_status = EditorStatus::Loading(image_id);
+        Task::perform(
+            raw::loader::load_raw_data(img.path.clone()),
+            move |result| Message::CompareRawDataLoaded(image_id, result),
+        )
+    }
+    
+    /// Leave the compare view (the right-hand pipeline stays in the cache)
+    fn exit_compare(&mut self) {
+        if self.compare_with.take().is_some() {
+            println!("🆚 Compare closed");
+        }
+        self.compare_status = EditorStatus::NoSelection;
+        self.compare_frame.clear();
+    }
+    
+    /// Map a viewport position to image texture coordinates (0-1)
+    /// Same mapping as zoom-to-cursor in `Message::Zoom`: the preview is centered
+    /// in the viewport, and the shader samples tex = ((screen - 0.5) / zoom - pan) + 0.5,
+    /// then goes through the crop & straighten geometry.
+    /// Returns None when the position is outside the image.
+    fn viewport_to_texture(&self, position: Point, pipeline: &gpu::RenderPipeline) -> Option<(f32, f32)> {
+        let displayed = self.displayed_image_rect(pipeline);
+        let screen_x = (position.x - displayed.x) / displayed.width;
+        let screen_y = (position.y - displayed.y) / displayed.height;
+        
+        let out_x = ((screen_x - 0.5) / self.zoom - self.pan_offset.x) + 0.5;
+        let out_y = ((screen_y - 0.5) / self.zoom - self.pan_offset.y) + 0.5;
+        if !(0.0..=1.0).contains(&out_x) || !(0.0..=1.0).contains(&out_y) {
+            return None;
+        }
+        
+        // Geometry works on the upright image; the orientation maps that back to the sensor
+        let (width, height) = pipeline.dimensions();
+        let (upright_x, upright_y) = self.current_edit_params.source_coords(out_x, out_y, width, height);
+        let (tex_x, tex_y) = pipeline.orientation().to_source(upright_x, upright_y);
+        if (0.0..=1.0).contains(&tex_x) && (0.0..=1.0).contains(&tex_y) {
+            Some((tex_x, tex_y))
+        } else {
+            None
+        }
+    }
+    
+    /// Color readout for a viewport position: the displayed frame's pixel
+    /// there, plus the camera values if asked for (None off the image, and
+    /// in the compare and crop views)
+    fn sample_under_cursor(&self, position: Point) -> Option<PixelSample> {
+        if self.compare_with.is_some() || self.crop_tool_active {
+            return None;
+        }
+        let EditorStatus::Ready(pipeline) = &self.editor_status else {
+            return None;
+        };
+        let (tex_x, tex_y) = self.viewport_to_texture(position, pipeline)?;
+        
+        // The frame on screen already has zoom and pan applied
+        let displayed = self.displayed_image_rect(pipeline);
+        let [r, g, b, _] = self.preview_frame.sample(
+            (position.x - displayed.x) / displayed.width,
+            (position.y - displayed.y) / displayed.height,
+            || {
+                // Drawn on the window: read this frame back once for the readout
+                let (width, height) = pipeline.preview_size();
+                ui::canvas::PreviewFrame {
+                    handle: iced::widget::image::Handle::from_rgba(width, height, pipeline.render_to_bytes()),
+                    width,
+                    height,
+                }
+            },
+        )?;
+        
+        let camera = if self.show_sensor_values {
+            let x = ((tex_x * pipeline.width as f32) as u32).min(pipeline.width - 1);
+            let y = ((tex_y * pipeline.height as f32) as u32).min(pipeline.height - 1);
+            pipeline.sample_camera_rgb(x, y)
+        } else {
+            None
+        };
+        Some(PixelSample { rgb: [r, g, b], camera })
+    }
+    
+    /// Adjustment shown on its own while Alt is held: the slider touched last
+    /// (None when the split view or compare is up, or nothing was adjusted)
+    fn solo_key(&self) -> Option<&'static str> {
+        if !self.keyboard_modifiers.alt() || self.split_view_active() || self.compare_with.is_some() {
+            return None;
+        }
+        self.edit_history.last_key()
+    }
+    
+    /// Look of the snapshot being compared in the split view, if any
+    fn compared_snapshot_params(&self) -> Option<state::edit::EditParams> {
+        let id = self.compare_snapshot?;
+        self.snapshots.iter().find(|s| s.id == id).map(|s| s.params)
+    }
+    
+    /// Before/After is showing as a split screen (not while cropping)
+    fn split_view_active(&self) -> bool {
+        self.show_before && self.compare_mode == CompareMode::Split && !self.crop_tool_active
+    }
+    
+    /// Import RAW files and folders (folders recurse) in the background;
+    /// the stream reports progress and ends with `ImportComplete`
+    fn start_import(&mut self, paths: Vec<PathBuf>) -> Task<Message> {
+        let Some(library) = &self.library else {
+            return Task::none();
+        };
+        if self.shutdown.is_cancelled() {
+            return Task::none(); // Quitting
+        }
+        // Get the database path for the background thread
+        let db_path = library.path().clone();
+        self.progress = Some((0, 0));
+        self.importing = true;
+        Task::run(
+            import_stream(paths, db_path, self.custom_extensions.clone(), self.shutdown.clone()),
+            |message| message,
+        )
+    }
+    
+    /// Every extension imported as RAW, for the file picker's filter
+    fn raw_extensions(&self) -> Vec<String> {
+        raw::loader::SUPPORTED_EXTENSIONS
+            .iter()
+            .chain(raw::loader::UNSUPPORTED_EXTENSIONS.iter())
+            .map(|ext| ext.to_string())
+            .chain(self.custom_extensions.iter().cloned())
+            .collect()
+    }
+    
+    /// The `zoom` at which one output pixel covers one screen pixel (zoom 1.0
+    /// fits the whole image into the viewport)
+    fn actual_pixels_zoom(&self, pipeline: &gpu::RenderPipeline) -> f32 {
+        let (output_width, _) = pipeline.output_size();
+        output_width as f32 / self.displayed_image_rect(pipeline).width.max(1.0)
+    }
+    
+    /// Open an image fitted and centered: the whole image in view, but never
+    /// enlarged past actual pixels (small images show 1:1 with a border)
+    fn fit_to_viewport(&mut self, pipeline: &gpu::RenderPipeline) {
+        self.fit_zoom = self.actual_pixels_zoom(pipeline).clamp(MIN_ZOOM, 1.0);
+        self.zoom = self.fit_zoom;
+        self.pan_offset = cgmath::Vector2::new(0.0, 0.0);
+        self.canvas_cache.clear();
+        println!("🔍 Fit: {:.0}% of the viewport", self.fit_zoom * 100.0);
+    }
+    
+    /// Preview render width for the current zoom, and whether that render is
+    /// the visible region at native resolution
+    ///
+    /// Zoom happens in the shader, so every render is a crop of the full-res
+    /// RAW. Fitted, the quality setting decides the width; zoomed in, the
+    /// render is at least as wide as the viewport. Past actual pixels one
+    /// output pixel covers several screen pixels, so rendering wider would only
+    /// interpolate: instead the visible region is rendered at 1:1 and shown
+    /// with nearest filtering, which keeps single pixels crisp.
+    fn zoomed_preview_width(&self, pipeline: &gpu::RenderPipeline) -> (u32, bool) {
+        let quality_width = self.preview_quality.max_width();
+        if self.zoom <= 1.0 {
+            return (quality_width, false);
+        }
+        if self.zoom >= self.actual_pixels_zoom(pipeline) {
+            let (output_width, _) = pipeline.output_size();
+            return ((output_width as f32 / self.zoom).ceil() as u32, true);
+        }
+        (quality_width.max(self.viewport_size.get().0.ceil() as u32), false)
+    }
+    
+    /// Where the preview image is drawn inside the viewport, in screen pixels
+    /// (ContentFit::Contain: scaled to fit, centered)
+    fn displayed_image_rect(&self, pipeline: &gpu::RenderPipeline) -> iced::Rectangle {
+        let (preview_width, preview_height) = pipeline.preview_size();
+        let (viewport_width, viewport_height) = self.viewport_size.get();
+        let viewport = iced::Size::new(viewport_width, viewport_height);
+        let fitted = iced::ContentFit::Contain.fit(
+            iced::Size::new(preview_width as f32, preview_height as f32),
+            viewport,
+        );
+        iced::Rectangle {
+            x: (viewport.width - fitted.width) / 2.0,
+            y: (viewport.height - fitted.height) / 2.0,
+            width: fitted.width,
+            height: fitted.height,
+        }
+    }
+    
+    /// Helper to save current edit parameters to database
+    fn save_current_edits(&mut self) {
+        // Phase 23: Only save if database is loaded
+        if let Some(library) = &self.library {
+            if let Some(image_id) = self.selected_image_id {
+                if let Err(e) = library.save_edit_params(image_id, &self.current_edit_params) {
+                    eprintln!("⚠️  Failed to save edits for image {}: {:?}", image_id, e);
+                    self.notify(Severity::Error, format!("Couldn't save edits: {}", e));
+                } else {
+                    println!("💾 Saved edits for image {}", image_id);
+                    if self.edited_thumbnails {
+                        self.thumbnail_refresh.insert(image_id);
+                    }
+                }
+            }
+        }
+        self.queue_sidecar_write();
+    }
+    
+    /// Helper to mirror current edit parameters into the image's .xmp sidecar
+    fn write_current_sidecar(&self) {
+        if let Some(image_id) = self.selected_image_id {
+            self.write_sidecar_for(image_id, &self.current_edit_params);
+        }
+    }
+    
+    /// Mark the selected image's sidecar out of date. It's written once the
+    /// edits pause for SIDECAR_WRITE_DELAY (see SidecarTick), not on every
+    /// slider step; a pending one for another image is written first.
+    fn queue_sidecar_write(&mut self) {
+        if !self.write_sidecars {
+            return;
+        }
+        let Some(image_id) = self.selected_image_id else {
+            return;
+        };
+        if self.sidecar_pending.is_some_and(|(pending, _)| pending != image_id) {
+            self.flush_sidecar();
+        }
+        self.sidecar_pending = Some((image_id, std::time::Instant::now()));
+    }
+    
+    /// Write the pending sidecar now, from the catalog if its image is no
+    /// longer the one being edited
+    fn flush_sidecar(&mut self) {
+        let Some((image_id, _)) = self.sidecar_pending.take() else {
+            return;
+        };
+        if self.selected_image_id == Some(image_id) {
+            self.write_current_sidecar();
+            return;
+        }
+        let Some(library) = &self.library else {
+            return;
+        };
+        let params = match library.load_edit_params(image_id) {
+            Ok(params) => params,
+            Err(rusqlite::Error::QueryReturnedNoRows) => state::edit::EditParams::default(),
+            Err(e) => {
+                eprintln!("⚠️  Couldn't load edits for image {} to write its sidecar: {:?}", image_id, e);
+                return;
+            }
+        };
+        self.write_sidecar_for(image_id, &params);
+    }
+    
+    /// Mirror `params` into an image's .xmp sidecar (if sidecars are on)
+    fn write_sidecar_for(&self, image_id: i64, params: &state::edit::EditParams) {
+        if !self.write_sidecars {
+            return;
+        }
+        if let Some(img) = self.images.iter().find(|i| i.id == image_id) {
+            if let Err(e) = state::sidecar::write_sidecar(std::path::Path::new(&img.path), params) {
+                eprintln!("⚠️  Failed to write sidecar for {}: {}", img.filename, e);
+            }
+        }
+    }
+    
+    /// Everything the export filename template can use for one image
+    fn export_context(&self, img: &ImageData, sequence: usize) -> state::export::ExportContext {
+        let metadata = self.library
+            .as_ref()
+            .and_then(|library| library.get_metadata(img.id).ok())
+            .unwrap_or_default();
+        state::export::ExportContext::new(&img.filename, sequence, &metadata, img.rating)
+    }
+    
+    /// Filename the export template gives the selected image (or the first
+    /// one in the grid), for the live preview next to the template
+    fn export_name_preview(&self) -> String {
+        let img = self.images
+            .iter()
+            .find(|img| Some(img.id) == self.selected_image_id)
+            .or_else(|| self.images.first());
+        let context = match img {
+            Some(img) => self.export_context(img, 1),
+            None => state::export::ExportContext {
+                original_filename: "DSC_0001.NEF".to_string(),
+                sequence: 1,
+                ..Default::default()
+            },
+        };
+        state::export::export_file_name(&self.batch_export_template, &context, self.batch_export_format)
+    }
+    
+    /// Images for the Library grid with the current sort and filter. While
+    /// grouping by date they come in day-section order (so Next/Previous walk
+    /// across sections) along with the size of each section.
+    fn library_grid(&self, library: &state::library::Library) -> (Vec<ImageData>, Vec<(chrono::NaiveDate, usize)>) {
+        // Keyword search and import scope narrow whatever the sort and filter give
+        let ids = |images: Vec<ImageData>| images.iter().map(|img| img.id).collect::<HashSet<i64>>();
+        let search = self.keyword_search.trim();
+        let keyword_matches = (!search.is_empty())
+            .then(|| ids(library.search_by_keyword(search).unwrap_or_default()));
+        let batches = match self.import_scope {
+            state::data::ImportScope::All => None,
+            state::data::ImportScope::LastImport => {
+                Some(library.latest_import_batch().ok().flatten().into_iter().collect())
+            }
+            state::data::ImportScope::ThisSession => Some(self.session_import_batches.clone()),
+        };
+        let batch_matches = batches.map(|batches: Vec<i64>| {
+            ids(batches.iter().flat_map(|&batch| library.get_images_by_batch(batch).unwrap_or_default()).collect())
+        });
+        let keep = |img: &ImageData| {
+            [&keyword_matches, &batch_matches]
+                .iter()
+                .all(|matches| matches.as_ref().is_none_or(|ids| ids.contains(&img.id)))
+        };
+        
+        if !self.group_by_date {
+            let mut images = library.get_images_filtered(self.library_sort, self.library_filter).unwrap_or_default();
+            images.retain(keep);
+            return (images, Vec::new());
+        }
+        let mut groups = library.get_images_grouped_by_date(self.library_sort, self.library_filter).unwrap_or_default();
+        for (_, images) in &mut groups {
+            images.retain(keep);
+        }
+        groups.retain(|(_, images)| !images.is_empty());
+        let sections = groups.iter().map(|(day, images)| (*day, images.len())).collect();
+        (groups.into_iter().flat_map(|(_, images)| images).collect(), sections)
+    }
+    
+    /// Day section holding an image (None when the grid isn't grouped)
+    fn day_section_of(&self, image_id: i64) -> Option<chrono::NaiveDate> {
+        let index = self.images.iter().position(|img| img.id == image_id)?;
+        let mut end = 0;
+        self.date_sections.iter().find_map(|&(day, count)| {
+            end += count;
+            (index < end).then_some(day)
+        })
+    }
+    
+    /// Selected image ids in grid order (the primary selection alone when
+    /// there's no multi-selection)
+    fn selection_in_grid_order(&self) -> Vec<i64> {
+        let ids: Vec<i64> = self.images
+            .iter()
+            .filter(|img| self.selected_ids.contains(&img.id))
+            .map(|img| img.id)
+            .collect();
+        if ids.is_empty() {
+            self.selected_image_id.into_iter().collect()
+        } else {
+            ids
+        }
+    }
+    
+    /// Apply the edit recipe embedded in an exported PNG to the selected images
+    fn apply_dropped_recipe(&mut self, path: &std::path::Path) {
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        match state::export::read_embedded_recipe(path) {
+            Ok(Some(params)) => {
+                let targets = self.selection_in_grid_order();
+                if targets.is_empty() {
+                    self.notify(Severity::Info, format!("Select an image to apply the settings from {} to", name));
+                    return;
+                }
+                for &image_id in &targets {
+                    self.paste_settings_onto(image_id, params);
+                }
+                println!("📋 Applied the recipe from {} to {} image(s)", name, targets.len());
+                self.notify(Severity::Success, format!("Applied the settings from {} to {} image(s)", name, targets.len()));
+            }
+            Ok(None) => {
+                self.notify(Severity::Info, format!("{} has no embedded edit settings", name));
+            }
+            Err(e) => {
+                eprintln!("❌ {}: {}", name, e);
+                self.notify(Severity::Error, format!("{}: {}", name, e));
+            }
+        }
+    }
+    
+    /// Apply pasted develop settings to an image
+    /// Always saved to the catalog (and sidecar), so images without a loaded
+    /// pipeline pick the settings up when they're next opened. The selected
+    /// image also gets a history entry and a live GPU update.
+    fn paste_settings_onto(&mut self, image_id: i64, params: state::edit::EditParams) {
+        let Some(library) = &self.library else {
+            return;
+        };
+        if let Err(e) = library.save_edit_params(image_id, &params) {
+            eprintln!("⚠️  Failed to paste settings onto image {}: {:?}", image_id, e);
+            return;
+        }
+        self.write_sidecar_for(image_id, &params);
+        println!("📋 Pasted develop settings onto image {}", image_id);
+        if self.edited_thumbnails {
+            self.thumbnail_refresh.insert(image_id);
+        }
+        
+        if self.selected_image_id == Some(image_id) {
+            self.current_edit_params = params;
+            // Own history entry so the paste can be undone
+            self.edit_history.push(params);
+        }
+        if let EditorStatus::Ready(pipeline) = &self.editor_status {
+            if pipeline.image_id == image_id {
+                pipeline.update_uniforms(&params);
+                self.canvas_cache.clear();
+                self.histogram_cache.clear();
+            }
+        }
+    }
+    
+    /// Phase 24: Keyboard shortcuts subscription
+    fn subscription(&self) -> iced::Subscription<Message> {
+        use iced::keyboard;
+        use iced::keyboard::key::Named;
+        
+        let keys = iced::event::listen_with(|event, status, window_id| {
+            // RAW files or folders dragged onto the window
+            if let iced::Event::Window(window::Event::FileDropped(path)) = event {
+                return Some(Message::FileDropped(path));
+            }
+            
+            // Closing saves the window layout first (exit_on_close_request is off)
+            if let iced::Event::Window(window::Event::CloseRequested) = event {
+                return Some(Message::WindowCloseRequested(window_id));
+            }
+            
+            // Track Ctrl/Shift for multi-select clicks in the grid
+            if let iced::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) = event {
+                return Some(Message::ModifiersChanged(modifiers));
+            }
+            
+            if let iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) = event {
+                // Ctrl+Z / Ctrl+Shift+Z (Cmd on macOS) for undo/redo
+                if modifiers.command() {
+                    return match key.as_ref() {
+                        keyboard::Key::Character("z") | keyboard::Key::Character("Z") => {
+                            Some(if modifiers.shift() { Message::Redo } else { Message::Undo })
+                        }
+                        // Copy/paste develop settings (ignored outside the Develop tab)
+                        keyboard::Key::Character("c") | keyboard::Key::Character("C") => Some(Message::CopySettings),
+                        keyboard::Key::Character("v") | keyboard::Key::Character("V") => Some(Message::PasteSettings),
+                        // Rotate left/right
+                        keyboard::Key::Character("[") => Some(Message::RotateImage(false)),
+                        keyboard::Key::Character("]") => Some(Message::RotateImage(true)),
+                        _ => None,
+                    };
+                }
+                
+                // Plain keys are text while a text field has focus
+                if status == iced::event::Status::Captured {
+                    return None;
+                }
+                
+                match key.as_ref() {
+                    // G = grid (Library), D = Develop, Enter opens the selection, Esc goes back
+                    keyboard::Key::Character("g") | keyboard::Key::Character("G") => {
+                        Some(Message::TabChanged(AppTab::Library))
+                    }
+                    keyboard::Key::Character("d") | keyboard::Key::Character("D") => {
+                        Some(Message::TabChanged(AppTab::Develop))
+                    }
+                    keyboard::Key::Named(Named::Enter) => Some(Message::OpenInDevelop),
+                    keyboard::Key::Named(Named::Escape) => Some(Message::EscapePressed),
+                    keyboard::Key::Character("f") | keyboard::Key::Character("F") => Some(Message::ToggleFullscreenPreview),
+                    keyboard::Key::Named(Named::Space) => Some(Message::ToggleBeforeAfter),
+                    keyboard::Key::Character("j") | keyboard::Key::Character("J") => Some(Message::ToggleClipping),
+                    keyboard::Key::Character("r") | keyboard::Key::Character("R") => Some(Message::ResetEdits),
+                    keyboard::Key::Named(Named::ArrowRight) => Some(Message::SelectNextImage),
+                    keyboard::Key::Named(Named::ArrowLeft) => Some(Message::SelectPreviousImage),
+                    keyboard::Key::Named(Named::Delete) => Some(Message::RequestDelete),
+                    // Culling: 0-5 stars, P = pick, X = reject, U = unflag
+                    keyboard::Key::Character(c @ ("0" | "1" | "2" | "3" | "4" | "5")) => {
+                        c.parse().ok().map(Message::SetRating)
+                    }
+                    keyboard::Key::Character("p") | keyboard::Key::Character("P") => {
+                        Some(Message::SetFlag(state::data::FLAG_PICK))
+                    }
+                    keyboard::Key::Character("x") | keyboard::Key::Character("X") => {
+                        Some(Message::SetFlag(state::data::FLAG_REJECT))
+                    }
+                    keyboard::Key::Character("u") | keyboard::Key::Character("U") => Some(Message::SetFlag("")),
+                    _ => None,
+                }
+            } else {
+                None
+            }
+        });
+        
+        let mut subscriptions = vec![keys];
+        
+        // A drop's paths arrive back to back; import them together once they stop
+        if !self.dropped_paths.is_empty() {
+            let settle = iced::time::every(std::time::Duration::from_millis(200))
+                .map(|_| Message::ImportDropped);
+            subscriptions.push(settle);
+        }
+        
+        // Write the sidecar once the edits pause
+        if self.sidecar_pending.is_some() {
+            let sidecar = iced::time::every(std::time::Duration::from_millis(250))
+                .map(|_| Message::SidecarTick);
+            subscriptions.push(sidecar);
+        }
+        
+        // Toasts go away on their own after a few seconds
+        if !self.notifications.is_empty() {
+            let expire = iced::time::every(std::time::Duration::from_millis(500))
+                .map(|_| Message::NotificationTick);
+            subscriptions.push(expire);
+        }
+        
+        // Keep the busy spinner turning while anything is loading
+        if self.is_loading() {
+            let spin = iced::time::every(std::time::Duration::from_millis(50))
+                .map(|_| Message::SpinnerTick);
+            subscriptions.push(spin);
+        }
+        
+        // Outside Develop, check now and then whether cached pipelines can be freed
+        if self.current_tab != AppTab::Develop && !self.pipeline_cache.is_empty() {
+            let idle_check = iced::time::every(std::time::Duration::from_secs(10))
+                .map(|_| Message::ReleaseIdleGpu);
+            subscriptions.push(idle_check);
+        }
+        
+        // Long-lived thumbnail worker: drains both queues over one connection
+        // (paused while Clear Cache deletes the files it would write)
+        if self.thumbnail_worker_active && self.cache_clearing != Some(CacheClearing::Deleting) {
+            if let Some(library) = &self.library {
+                let db_path = library.path().clone();
+                subscriptions.push(iced::Subscription::run_with_id(
+                    ("thumbnail-worker", db_path.clone()),
+                    thumbnail_worker(db_path, self.thumbnail_stop.clone()),
+                ));
+            }
+        }
+        
+        iced::Subscription::batch(subscriptions)
+    }
+
+    /// Build the user interface
+    fn view(&self) -> Element<Message> {
+        // Phase 23: Show splash screen if database is still loading
+        match &self.library {
+            None => self.view_splash(),
+            Some(_) => self.view_main(),
+        }
+    }
+    
+    /// Phase 23: Splash screen shown during database loading
+    fn view_splash(&self) -> Element<Message> {
+        use iced::widget::Space;
+        
+        // Left half: Branding/image
+        // To add your custom splash image:
+        // 1. Create an "assets" folder in your project root
+        // 2. Add your image: assets/splash.png (PNG with transparency recommended)
+        // 3. Uncomment the image widget below and comment out the emoji
+        //
+        // For transparency/blending:
+        // - Use PNG format with alpha channel
+        // - The image will blend naturally with the theme's background
+        // - For edge blending, add a gradient alpha in your image editor
+        
+        let left_content = column![
+            Space::with_height(Length::Fill),
+            // Option 1: Use emoji placeholder (current)
+            // text("📸").size(120).center(),
+            
+            // Option 2: Use your custom image (fills container, maintains aspect ratio):
+            // iced::widget::image("assets/splash.jpg")
+            //     .width(Length::Fill)
+            //     .height(Length::Fill)
+            //     .content_fit(iced::ContentFit::Contain),  // Maintains aspect ratio
+            // 
+            // OR for full bleed (image fills entire space, may crop):
+            iced::widget::image("assets/splash.png")
+                .width(Length::Fill)
+                // .height(Length::Fill)
+                .content_fit(iced::ContentFit::Cover),  // Fills space, crops if needed
+            
+            Space::with_height(Length::Fill),
+        ]
+        .align_x(iced::Alignment::Center);
+        
+        let left_panel = container(left_content)
+        .width(Length::FillPortion(7))  // 70% of width (7/10)
+        .height(Length::Fill)
+        .style(|theme: &Theme| {
+            container::Style {
+                background: Some(Background::Color(theme.palette().background)),
+                ..Default::default()
+            }
+        });
+        
+        // Right half: Loading message
+        let right_panel = container(
+            column![
+                Space::with_height(Length::Fill),
+                text("RAW Editor")
+                    .size(56)
+                    .center()
+                    .style(|theme: &Theme| text::Style {
+                        color: Some(theme.palette().text.scale_alpha(0.9)),
+                    }),
+                Space::with_height(10.0),
+                text("Professional RAW Photo Editor")
+                    .size(14)
+                    .center()
+                    .style(|theme: &Theme| text::Style {
+                        color: Some(theme.palette().text.scale_alpha(0.6)),
+                    }),
+                Space::with_height(40.0),
+                text(&self.status)
+                    .size(16)
+                    .center()
+                    .style(|theme: &Theme| text::Style {
+                        color: Some(theme.palette().text.scale_alpha(0.8)),
+                    }),
+                Space::with_height(15.0),
+                self.view_database_error().unwrap_or_else(|| ui::spinner::view(self.spinner_frame, 32.0)),
+                Space::with_height(Length::Fill),
+                text("Version 0.1.5")
+                    .size(11)
+                    .center()
+                    .style(|theme: &Theme| text::Style {
+                        color: Some(theme.palette().text.scale_alpha(0.4)),
+                    }),
+                Space::with_height(10.0),
+            ]
+            .align_x(iced::Alignment::Center)
+        )
+        .width(Length::FillPortion(3))  // 30% of width (3/10)
+        .height(Length::Fill)
+        .style(|theme: &Theme| {
+            container::Style {
+                background: Some(Background::Color(theme.palette().background)), // Match left panel for seamless look
+                ..Default::default()
+            }
+        });
+        
+        // Full-screen splash layout
+        row![
+            left_panel,
+            right_panel,
+        ]
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+    
+    /// Splash actions when the catalog failed to open: retry, and for a
+    /// damaged catalog, back it up and start fresh
+    fn view_database_error(&self) -> Option<Element<'_, Message>> {
+        let error = self.db_error.as_ref()?;
+        let retry = button(text("Retry").size(14))
+            .on_press(Message::RetryDatabase)
+            .style(button::secondary)
+            .padding([6, 14]);
+        let actions = if error.is_corrupt() {
+            row![
+                retry,
+                button(text("Back Up & Recreate").size(14))
+                    .on_press(Message::RecoverDatabase)
+                    .style(button::danger)
+                    .padding([6, 14]),
+            ]
+        } else {
+            row![retry]
+        };
+        let hint = if error.is_corrupt() {
+            "The damaged file is kept next to the new catalog; image paths that can still be read are imported again."
+        } else {
+            "Close any other copy of RAW Editor using this catalog, then retry."
+        };
+        Some(
+            column![
+                actions.spacing(10),
+                text(hint)
+                    .size(12)
+                    .center()
+                    .style(|theme: &Theme| text::Style {
+                        color: Some(theme.palette().text.scale_alpha(0.6)),
+                    }),
+            ]
+            .spacing(10)
+            .padding([0, 20])
+            .align_x(iced::Alignment::Center)
+            .into(),
+        )
+    }
+    
+    /// Phase 23: Main application UI (shown after database loads)
+    fn view_main(&self) -> Element<Message> {
+        // Tab navigation bar
+        let library_button = button(
+            text("📚 Library")
+                .size(16)
+        )
+        .on_press(Message::TabChanged(AppTab::Library))
+        .padding(12);
+        
+        let library_button = if self.current_tab == AppTab::Library {
+            library_button.style(button::primary)
+        } else {
+            library_button.style(button::secondary)
+        };
+        
+        let develop_button = button(
+            text("🎨 Develop")
+                .size(16)
+        )
+        .on_press(Message::TabChanged(AppTab::Develop))
+        .padding(12);
+        
+        let develop_button = if self.current_tab == AppTab::Develop {
+            develop_button.style(button::primary)
+        } else {
+            develop_button.style(button::secondary)
+        };
+        
+        let preferences_button = button(
+            text("⚙ Preferences")
+                .size(16)
+        )
+        .on_press(Message::TabChanged(AppTab::Preferences))
+        .padding(12)
+        .style(if self.current_tab == AppTab::Preferences { button::primary } else { button::secondary });
+        
+        let tab_bar = row![
+            library_button,
+            develop_button,
+            preferences_button,
+        ]
+        .push_maybe(self.software_rendering.then(|| {
+            text("🐢 Using CPU rendering (slow)")
+                .size(14)
+                .style(|_theme| text::Style {
+                    color: Some(Color::from_rgb(1.0, 0.75, 0.3)),
+                })
+        }))
+        .spacing(8)
+        .padding(10)
+        .align_y(Alignment::Center);
+        
+        // Render content based on current tab
+        let content = match self.current_tab {
+            AppTab::Library => self.view_library(),
+            AppTab::Develop => self.view_develop(),
+            AppTab::Preferences => self.view_preferences(),
+        };
+        
+        // Main layout: tab bar + content, toasts on top in the corner
+        // (presentation mode drops the tab bar)
+        let main = column![]
+            .push_maybe((!self.fullscreen_preview).then_some(tab_bar))
+            .push_maybe(self.view_quit_confirmation())
+            .push(content);
+        
+        // Small busy indicator in the bottom-right corner while background
+        // work runs (the rest of the window stays usable). Develop's own
+        // loading screen already shows one.
+        let background_work = self.background_work();
+        let busy = (!background_work.is_empty()).then(|| {
+            let badge = container(
+                row![
+                    ui::spinner::view(self.spinner_frame, 16.0),
+                    text(background_work.join(", ")).size(12),
+                ]
+                .spacing(6)
+                .align_y(Alignment::Center),
+            )
+            .padding([4, 10])
+            .style(|theme: &Theme| container::Style {
+                background: Some(Background::Color(theme.extended_palette().background.weak.color)),
+                border: iced::Border { radius: 10.0.into(), ..iced::Border::default() },
+                ..container::Style::default()
+            });
+            container(badge)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_x(iced::alignment::Horizontal::Right)
+                .align_y(iced::alignment::Vertical::Bottom)
+                .padding(16)
+        });
+        
+        if self.notifications.is_empty() && busy.is_none() {
+            return main.into();
+        }
+        let toasts = container(ui::notifications::view(&self.notifications))
+            .width(Length::Fill)
+            .align_x(iced::alignment::Horizontal::Right)
+            .padding(iced::Padding { top: 60.0, right: 16.0, bottom: 0.0, left: 0.0 });
+        iced::widget::stack![main, toasts].push_maybe(busy).into()
+    }
+    
+    /// Build the Preferences tab (every change is saved right away)
+    fn view_preferences(&self) -> Element<'_, Message> {
+        let section = |title: &'static str| text(title).size(18);
+        let cache_location = match &self.cache_dir {
+            Some(dir) => dir.display().to_string(),
+            None => format!("{} (default)", state::settings::cache_root().display()),
+        };
+        
+        let content = column![
+            text("Preferences").size(24),
+            
+            section("Appearance"),
+            row![
+                text("Theme").size(14),
+                iced::widget::pick_list(
+                    state::settings::UiTheme::ALL,
+                    Some(self.ui_theme),
+                    Message::UiThemeChanged,
+                ),
+                text("Accent").size(14),
+                iced::widget::pick_list(
+                    state::settings::AccentColor::ALL,
+                    Some(self.accent_color),
+                    Message::AccentColorChanged,
+                ),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+            text("The Develop preview keeps its black surround in every theme, so tones are judged the same way").size(12),
+            
+            section("Export"),
+            row![
+                text("Default format").size(14),
+                iced::widget::pick_list(
+                    state::export::ExportFormat::ALL,
+                    Some(self.batch_export_format),
+                    Message::BatchExportFormatChanged,
+                ),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+            row![
+                text("Filename").size(14),
+                iced::widget::text_input(state::export::DEFAULT_TEMPLATE, &self.batch_export_template)
+                    .on_input(Message::BatchExportTemplateChanged),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+            text(format!("Example: {}", self.export_name_preview())).size(12),
+            text("Tokens: {original} {seq} {seq:03} {date} {date:%Y%m%d} {camera} {rating}; {{ and }} for braces").size(12),
+            text(format!("JPEG quality: {}", self.export_options.jpeg_quality)).size(14),
+            self.view_jpeg_quality_slider(),
+            
+            section("Metadata"),
+            iced::widget::checkbox("Copy EXIF (camera, lens, exposure, date) into exports", self.include_metadata)
+                .on_toggle(Message::ExportMetadataToggled),
+            row![
+                text("Artist").size(14).width(80),
+                iced::widget::text_input("Your name", &self.export_metadata.artist)
+                    .on_input(Message::ExportArtistChanged),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+            row![
+                text("Copyright").size(14).width(80),
+                iced::widget::text_input("© 2024 Your name", &self.export_metadata.copyright)
+                    .on_input(Message::ExportCopyrightChanged),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+            iced::widget::checkbox("Include GPS location", self.export_metadata.include_gps)
+                .on_toggle_maybe(self.include_metadata.then_some(Message::ExportGpsToggled)),
+            iced::widget::checkbox("Include maker notes (camera-private data)", self.export_metadata.include_maker_notes)
+                .on_toggle_maybe(self.include_metadata.then_some(Message::ExportMakerNotesToggled)),
+            iced::widget::checkbox("Embed the edit settings in PNG exports", self.export_options.embed_recipe)
+                .on_toggle(Message::EmbedRecipeToggled),
+            text("Drop such a PNG on the window to apply its settings to the selected images").size(12),
+            
+            section("Develop"),
+            row![
+                text("Preview quality").size(14),
+                iced::widget::pick_list(
+                    state::settings::PreviewQuality::ALL,
+                    Some(self.preview_quality),
+                    Message::PreviewQualityChanged,
+                ),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+            iced::widget::checkbox("Show the camera preview while a RAW loads", self.fast_preview)
+                .on_toggle(Message::FastPreviewToggled),
+            text("Puts the JPEG embedded in the RAW on screen right away; it's replaced by the editable image once decoding finishes").size(12),
+            iced::widget::checkbox("Write XMP sidecars next to the RAW files", self.write_sidecars)
+                .on_toggle(Message::SidecarWritingToggled),
+            
+            section("Library"),
+            text(format!("Thumbnail size: {:.0}px", self.thumbnail_size)).size(14),
+            slider(
+                state::settings::MIN_THUMBNAIL_SIZE..=state::settings::MAX_THUMBNAIL_SIZE,
+                self.thumbnail_size,
+                Message::ThumbnailSizeChanged,
+            ),
+            iced::widget::checkbox("Show edits in Library thumbnails", self.edited_thumbnails)
+                .on_toggle(Message::EditedThumbnailsToggled),
+            text("Re-renders an image's thumbnail after you edit it (loads the RAW again, so it costs a little time per image)").size(12),
+            iced::widget::checkbox("Select the next image after rejecting one (X)", self.auto_advance)
+                .on_toggle(Message::AutoAdvanceToggled),
+            
+            row![
+                text("Extra RAW extensions").size(14),
+                iced::widget::text_input("e.g. rwz, tif", &self.custom_extensions_input)
+                    .on_input(Message::CustomExtensionsChanged),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+            text("Imported on top of the built-in formats when the file is a RAW container rawloader can read").size(12),
+            
+            section("Cache"),
+            text(cache_location).size(14),
+            row![
+                button("Choose…").on_press(Message::ChooseCacheDir),
+                button("Use Default")
+                    .on_press_maybe(self.cache_dir.is_some().then_some(Message::ResetCacheDir))
+                    .style(button::secondary),
+            ]
+            .spacing(10),
+            text("Applies to newly cached thumbnails and previews").size(12),
+            row![
+                text(match self.cache_size {
+                    Some(bytes) => format!("Using {}", format_size(bytes)),
+                    None => "Measuring…".to_string(),
+                })
+                .size(14),
+                button("Clear Cache")
+                    .on_press_maybe(self.library.is_some().then_some(Message::ClearCache))
+                    .style(button::secondary),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+            text("Deletes every cached thumbnail and preview; thumbnails are extracted again as needed").size(12),
+            
+            section("Catalog Backup"),
+            row![
+                button("Export Catalog…")
+                    .on_press_maybe(self.library.is_some().then_some(Message::ExportCatalog)),
+                button("Import Catalog…")
+                    .on_press_maybe(self.library.is_some().then_some(Message::ImportCatalog))
+                    .style(button::secondary),
+            ]
+            .spacing(10),
+            text("A JSON file with every image's path, edits, rating, flag and keywords. Importing updates images with the same path and adds the rest").size(12),
+            text(match &self.photos_root {
+                Some(root) => format!("Photos folder: {}", root.display()),
+                None => "Photos folder: none (paths are stored as they are)".to_string(),
+            })
+            .size(14),
+            row![
+                button("Choose…").on_press(Message::ChoosePhotosRoot),
+                button("Clear")
+                    .on_press_maybe(self.photos_root.is_some().then_some(Message::ResetPhotosRoot))
+                    .style(button::secondary),
+            ]
+            .spacing(10),
+            text("Paths inside it are stored relative to it, so a backup still finds the photos after they move to another disk or machine: set it to where they live on each machine").size(12),
+            button("Regenerate All Thumbnails")
+                .on_press_maybe(self.library.is_some().then_some(Message::RegenerateAllCaches))
+                .style(button::secondary),
+            text("Deletes every cached thumbnail and preview and extracts them again").size(12),
+            
+            section("GPU"),
+            text(match &self.gpu_adapter {
+                Some(Ok(adapter)) => adapter.clone(),
+                Some(Err(err)) => format!("⚠️ {}", err),
+                None => "Checking…".to_string(),
+            })
+            .size(14),
+            row![
+                text("Graphics API").size(14),
+                iced::widget::pick_list(
+                    state::settings::GpuBackend::ALL,
+                    Some(self.gpu_backend),
+                    Message::GpuBackendChanged,
+                ),
+                text("GPU").size(14),
+                iced::widget::pick_list(
+                    state::settings::GpuPower::ALL,
+                    Some(self.gpu_power),
+                    Message::GpuPowerChanged,
+                ),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+            text("Applies after restarting the app; an unavailable API falls back to automatic").size(12),
+        ]
+        .spacing(12)
+        .padding(20)
+        .max_width(600);
+        
+        scrollable(content).into()
+    }
+    
+    /// Build the Library tab view (grid of thumbnails)
+    fn view_library<'a>(&'a self) -> Element<'a, Message> {
+        // Count thumbnails and deleted files
+        let cached_count = self.images.iter()
+            .filter(|img| img.cache_path_thumb.is_some())
+            .count();
+        let deleted_count = self.images.iter()
+            .filter(|img| img.file_status == "deleted")
+            .count();
+        let total_count = self.images.len();
+        
+        // ========== LEFT PANE: Thumbnail Grid ==========
+        
+        // Header for grid pane
+        let grid_header = column![
+            text("RAW Editor v0.1.5 - Zoom and panning")
+                .size(24),
+            row![
+                button("Import Folder")
+                    .on_press(Message::ImportFolder)
+                    .padding(8),
+                button("Import Files")
+                    .on_press(Message::ImportFiles)
+                    .padding(8),
+                text("or drop RAW files and folders here").size(12),
+                button(text("Compare").size(12))
+                    .on_press_maybe(match self.selection_in_grid_order()[..] {
+                        [left_id, right_id] => Some(Message::EnterCompare(left_id, right_id)),
+                        _ => None,
+                    })
+                    .padding(6)
+                    .style(button::secondary),
+                button(text("Regenerate Thumbnails").size(12))
+                    .on_press_maybe((!self.selection_in_grid_order().is_empty()).then_some(Message::RegenerateThumbnails))
+                    .padding(6)
+                    .style(button::secondary),
+                button(text("Delete Rejected...").size(12))
+                    .on_press(Message::RequestDeleteRejected)
+                    .padding(6)
+                    .style(button::secondary),
+            ]
+            .spacing(8)
+            .align_y(Alignment::Center),
+            row![text(&self.status).size(12)]
+                .push_maybe(
+                    (!self.session_import_batches.is_empty()
+                        && self.import_scope != state::data::ImportScope::LastImport)
+                        .then(|| {
+                            button(text("Show imported").size(11))
+                                .on_press(Message::ImportScopeChanged(state::data::ImportScope::LastImport))
+                                .padding([2, 8])
+                                .style(button::secondary)
+                        }),
+                )
+                .spacing(10)
+                .align_y(Alignment::Center),
+            text(format!("Thumbnails: {}/{}  |  Deleted: {}  |  Selected: {}",
+                cached_count, total_count, deleted_count, self.selection_in_grid_order().len()))
+                .size(11),
+            self.view_library_filter_bar(),
+            self.view_batch_export_bar(),
+            row![
+                text("Thumbnail size").size(12),
+                slider(
+                    state::settings::MIN_THUMBNAIL_SIZE..=state::settings::MAX_THUMBNAIL_SIZE,
+                    self.thumbnail_size,
+                    Message::ThumbnailSizeChanged,
+                )
+                .step(10.0)
+                .width(200),
+                text(format!("{}px", self.thumbnail_size.round())).size(12),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+        ]
+        .push_maybe(self.view_delete_confirmation())
+        .push_maybe(self.view_library_progress())
+        .spacing(10)
+        .padding(10);
+        
+        // Create wrapping grid of clickable thumbnails
+        // Cell size follows the thumbnail size slider; gaps scale with it
+        let (cell_width, cell_height) = state::settings::thumbnail_cell(self.thumbnail_size);
+        let cell_spacing = (self.thumbnail_size / 25.0).round();
+        
+        let wrap_of = |images: &'a [ImageData]| {
+            images.iter().fold(
+                Wrap::new().spacing(cell_spacing).line_spacing(cell_spacing),
+                |wrap, img| wrap.push(self.view_thumbnail(img, cell_width, cell_height)),
+            )
+        };
+        let thumbnail_grid: Element<Message> = if self.group_by_date {
+            // One collapsible section per day; `images` is already in section order
+            let mut sections = column![].spacing(cell_spacing);
+            let mut start = 0;
+            for &(day, count) in &self.date_sections {
+                let images = &self.images[start..start + count];
+                start += count;
+                let collapsed = self.collapsed_days.contains(&day);
+                let header = button(
+                    row![
+                        text(if collapsed { "▸" } else { "▾" }).size(14),
+                        text(day.format("%A, %B %-d, %Y").to_string()).size(14),
+                        text(format!("{} images", count)).size(11),
+                    ]
+                    .spacing(8)
+                    .align_y(Alignment::Center),
+                )
+                .on_press(Message::ToggleDayCollapsed(day))
+                .padding([4, 8])
+                .style(button::text);
+                sections = sections.push(header);
+                if !collapsed {
+                    sections = sections.push(wrap_of(images));
+                }
+            }
+            sections.into()
+        } else {
+            wrap_of(&self.images).into()
+        };
+        
+        // Phase 20: Full-screen thumbnail grid (no preview pane)
+        // Wrap grid in scrollable container
+        let content = column![
+            grid_header,
+            scrollable(thumbnail_grid)
+                .height(Length::Fill)
+                .width(Length::Fill),
+        ];
+        
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+    
+    /// One clickable Library grid cell
+    fn view_thumbnail<'a>(&'a self, img: &'a ImageData, cell_width: f32, cell_height: f32) -> Element<'a, Message> {
+        // Check if file is deleted
+        let is_deleted = img.file_status == "deleted";
+        
+        // Create thumbnail content
+        let thumbnail_content = if is_deleted {
+            // Show deleted file indicator with grey background
+            container(
+                column![
+                    text("❌").size(24),
+                    text(&img.filename).size(8),
+                    text("(deleted)").size(7),
+                ]
+                .align_x(Alignment::Center)
+                .spacing(4)
+            )
+            .center_x(iced::Length::Fixed(cell_width))
+            .center_y(iced::Length::Fixed(cell_height))
+            .style(|_theme| {
+                container::Style {
+                    background: Some(Background::Color(Color::from_rgb(0.3, 0.3, 0.3))),
+                    border: Border {
+                        color: Color::from_rgb(0.5, 0.2, 0.2),
+                        width: 2.0,
+                        radius: 4.0.into(),
+                    },
+                    ..Default::default()
+                }
+            })
+        } else if let Some(ref thumb_path) = img.cache_path_thumb {
+            // Phase 28: Show 256px thumbnail tier
+            let handle = Handle::from_path(thumb_path.clone());
+            container(
+                Image::new(handle)
+                    .content_fit(iced::ContentFit::Contain) // Fit image inside square
+            )
+            .center_x(iced::Length::Fixed(cell_width))
+            .center_y(iced::Length::Fixed(cell_height))
+            .style(|_theme| {
+                container::Style {
+                    background: Some(Background::Color(Color::from_rgb(0.25, 0.25, 0.25))),
+                    border: Border {
+                        color: Color::from_rgb(0.4, 0.4, 0.4),
+                        width: 1.0,
+                        radius: 4.0.into(),
+                    },
+                    ..Default::default()
+                }
+            })
+        } else if let Some([r, g, b]) = img.avg_color {
+            // Pending thumbnail with a known color: a quiet block of the
+            // photo's average color instead of the flickering hourglass
+            container(iced::widget::Space::new(Length::Fill, Length::Fill))
+                .width(iced::Length::Fixed(cell_width))
+                .height(iced::Length::Fixed(cell_height))
+                .style(move |_theme| {
+                    container::Style {
+                        background: Some(Background::Color(Color::from_rgb8(r, g, b))),
+                        border: Border {
+                            color: Color::from_rgb(0.4, 0.4, 0.4),
+                            width: 1.0,
+                            radius: 4.0.into(),
+                        },
+                        ..Default::default()
+                    }
+                })
+        } else {
+            // Show placeholder for pending thumbnails with grey background
+            container(
+                text("⏳").size(48)
+            )
+            .center_x(iced::Length::Fixed(cell_width))
+            .center_y(iced::Length::Fixed(cell_height))
+            .style(|_theme| {
+                container::Style {
+                    background: Some(Background::Color(Color::from_rgb(0.2, 0.2, 0.2))),
+                    border: Border {
+                        color: Color::from_rgb(0.3, 0.3, 0.3),
+                        width: 1.0,
+                        radius: 4.0.into(),
+                    },
+                    ..Default::default()
+                }
+            })
+        };
+        
+        // Rating stars and pick/reject flag overlaid on the bottom edge,
+        // an "edited" mark in the top-right corner
+        let badge = match img.flag.as_deref() {
+            Some(state::data::FLAG_PICK) => "⚑ ",
+            Some(state::data::FLAG_REJECT) => "✕ ",
+            _ => "",
+        };
+        let stars = "★".repeat(img.rating.clamp(0, 5) as usize);
+        let thumbnail_content: Element<Message> = if badge.is_empty() && stars.is_empty() && !img.is_edited {
+            thumbnail_content.into()
+        } else {
+            let overlay = |label: String, color: Color, align: iced::alignment::Horizontal, bottom: bool| {
+                container(
+                    container(text(label).size(12))
+                        .padding([2, 6])
+                        .style(move |_theme| container::Style {
+                            background: Some(Background::Color(Color::from_rgba(0.0, 0.0, 0.0, 0.6))),
+                            text_color: Some(color),
+                            border: Border {
+                                radius: 4.0.into(),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        })
+                )
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .padding(4)
+                .align_x(align)
+                .align_y(if bottom { iced::alignment::Vertical::Bottom } else { iced::alignment::Vertical::Top })
+            };
+            iced::widget::stack![thumbnail_content]
+                .push_maybe((!badge.is_empty() || !stars.is_empty()).then(|| overlay(
+                    format!("{}{}", badge, stars),
+                    Color::from_rgb(1.0, 0.85, 0.3),
+                    iced::alignment::Horizontal::Left,
+                    true,
+                )))
+                .push_maybe(img.is_edited.then(|| overlay(
+                    "✎".to_string(),
+                    Color::from_rgb(0.6, 0.85, 1.0),
+                    iced::alignment::Horizontal::Right,
+                    false,
+                )))
+                .into()
+        };
+        
+        // Wrap in clickable button: Ctrl+click toggles, Shift+click selects a range
+        let on_press = if self.keyboard_modifiers.command() {
+            Message::ToggleSelection(img.id)
+        } else if self.keyboard_modifiers.shift() {
+            Message::RangeSelectTo(img.id)
+        } else {
+            Message::ImageSelected(img.id)
+        };
+        let is_primary = self.selected_image_id == Some(img.id);
+        let is_selected = is_primary || self.selected_ids.contains(&img.id);
+        let thumbnail_widget = button(thumbnail_content)
+            .on_press(on_press)
+            .padding(if is_selected { 3 } else { 0 })
+            .style(move |theme, status| {
+                // Selected thumbnails get an accent border (brighter for the primary one)
+                let border = if is_selected {
+                    Border {
+                        color: if is_primary {
+                            Color::from_rgb(0.4, 0.7, 1.0)
+                        } else {
+                            Color::from_rgb(0.25, 0.45, 0.7)
+                        },
+                        width: 3.0,
+                        radius: 6.0.into(),
+                    }
+                } else {
+                    Border::default()
+                };
+                button::Style {
+                    background: None,
+                    border,
+                    ..button::primary(theme, status)
+                }
+            });
+        
+        thumbnail_widget.into()
+    }
+    
+    /// Sort picker and filter checkboxes above the Library grid
+    fn view_library_filter_bar(&self) -> Element<'_, Message> {
+        let filter = self.library_filter;
+        row![
+            text("Sort:").size(12),
+            iced::widget::pick_list(
+                state::data::SortOrder::ALL,
+                Some(self.library_sort),
+                Message::LibrarySortChanged,
+            )
+            .text_size(12),
+            iced::widget::checkbox("Rated only", filter.only_rated)
+                .on_toggle(move |only_rated| Message::LibraryFilterChanged(
+                    state::data::LibraryFilter { only_rated, ..filter }
+                ))
+                .text_size(12),
+            iced::widget::checkbox("Flagged only", filter.only_flagged)
+                .on_toggle(move |only_flagged| Message::LibraryFilterChanged(
+                    state::data::LibraryFilter { only_flagged, ..filter }
+                ))
+                .text_size(12),
+            iced::widget::checkbox("Hide deleted", filter.hide_deleted)
+                .on_toggle(move |hide_deleted| Message::LibraryFilterChanged(
+                    state::data::LibraryFilter { hide_deleted, ..filter }
+                ))
+                .text_size(12),
+            iced::widget::checkbox("Hide rejects", filter.hide_rejects)
+                .on_toggle(move |hide_rejects| Message::LibraryFilterChanged(
+                    state::data::LibraryFilter { hide_rejects, ..filter }
+                ))
+                .text_size(12),
+            iced::widget::pick_list(
+                state::data::ImportScope::ALL,
+                Some(self.import_scope),
+                Message::ImportScopeChanged,
+            )
+            .text_size(12),
+            iced::widget::checkbox("Group by day", self.group_by_date)
+                .on_toggle(Message::GroupByDateToggled)
+                .text_size(12),
+            iced::widget::text_input("Search keywords", &self.keyword_search)
+                .on_input(Message::KeywordSearchChanged)
+                .size(12)
+                .width(180),
+        ]
+        .spacing(12)
+        .align_y(Alignment::Center)
+        .into()
+    }
+    
+    /// Progress bar for the running import or thumbnail queue (None when idle)
+    fn view_library_progress(&self) -> Option<Element<'_, Message>> {
+        let (done, total) = self.progress?;
+        let label = if total == 0 {
+            "Scanning folder...".to_string()
+        } else {
+            format!("{}/{}", done, total)
+        };
+        Some(
+            row![
+                iced::widget::progress_bar(0.0..=total.max(1) as f32, done as f32)
+                    .height(Length::Fixed(8.0))
+                    .width(Length::Fixed(300.0)),
+                text(label).size(11),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .into(),
+        )
+    }
+    
+    /// Batch export controls above the Library grid
+    fn view_batch_export_bar(&self) -> Element<'_, Message> {
+        let idle = self.batch_export_progress.is_none();
+        let can_export = idle && !self.selection_in_grid_order().is_empty();
+        let progress = match self.batch_export_progress {
+            Some((done, total)) => format!("Exporting {}/{}...", done, total),
+            None => String::new(),
+        };
+        row![
+            button(text("Batch Export").size(12))
+                .on_press_maybe(can_export.then_some(Message::BatchExport))
+                .padding(6),
+            iced::widget::pick_list(
+                state::export::ExportFormat::ALL,
+                Some(self.batch_export_format),
+                Message::BatchExportFormatChanged,
+            )
+            .text_size(12),
+            text(format!("→ {}", self.export_name_preview())).size(12),
+            self.view_batch_encoder_control(),
+            text(progress).size(12),
+        ]
+        .spacing(12)
+        .align_y(Alignment::Center)
+        .into()
+    }
+    
+    /// "Convert to B&W" toggle, then either vibrance/saturation (color) or the
+    /// channel mixer (B&W) - the color sliders do nothing to a gray image
+    fn view_color_controls(&self) -> Element<'_, Message> {
+        let params = &self.current_edit_params;
+        let defaults = state::edit::EditParams::default();
+        let toggle = iced::widget::checkbox("Convert to B&W", params.monochrome)
+            .on_toggle(Message::MonochromeToggled);
+        
+        if params.monochrome {
+            let mixers = ["Red", "Green", "Blue"].iter().enumerate().map(|(channel, name)| {
+                column![
+                    text(format!("{} mix: {:.0}%", name, params.bw_mix[channel] * 100.0)),
+                    slider(-1.0..=2.0, params.bw_mix[channel], move |v| Message::BwMixChanged(channel, v))
+                        .step(0.01),
+                ]
+                .spacing(10)
+                .into()
+            });
+            column![toggle, iced::widget::Column::with_children(mixers).spacing(10)]
+                .spacing(10)
+                .into()
+        } else {
+            column![
+                toggle,
+                // Vibrance (Phase 27: Smart saturation protecting skin tones)
+                slider_label(format!("Vibrance: {:.0}", params.vibrance * 100.0), Message::VibranceChanged(defaults.vibrance)),
+                slider(-1.0..=1.0, params.vibrance, Message::VibranceChanged)
+                    .step(0.01),
+                // Saturation
+                slider_label(format!("Saturation: {:.0}", params.saturation), Message::SaturationChanged(defaults.saturation)),
+                slider(-100.0..=100.0, params.saturation, Message::SaturationChanged),
+            ]
+            .spacing(10)
+            .into()
+        }
+    }
+    
+    /// "Remove N images?" prompt shown after pressing Delete in the Library
+    fn view_delete_confirmation(&self) -> Option<Element<'_, Message>> {
+        let request = self.delete_pending.as_ref()?;
+        let count = request.image_ids.len();
+        let which = if request.rejected { "rejected" } else { "selected" };
+        let prompt = if count == 1 {
+            format!("Remove the {} image?", which)
+        } else {
+            format!("Remove {} {} images?", count, which)
+        };
+        // Trashing only affects the files still on disk
+        let prompt = if request.files_on_disk == count {
+            prompt
+        } else {
+            format!("{} ({} of the RAW files are on disk)", prompt, request.files_on_disk)
+        };
+        Some(
+            row![
+                text(prompt).size(12),
+                button("Remove from Catalog").on_press(Message::ConfirmDelete(false)).style(button::danger),
+                button("Move RAW to Trash").on_press(Message::ConfirmDelete(true)).style(button::danger),
+                button("Cancel").on_press(Message::CancelDelete).style(button::secondary),
+            ]
+            .spacing(5)
+            .align_y(Alignment::Center)
+            .into(),
+        )
+    }
+    
+    /// "Quit anyway?" prompt shown when closing while background work runs
+    fn view_quit_confirmation(&self) -> Option<Element<'_, Message>> {
+        self.quit_pending?;
+        let prompt = if self.shutdown.is_cancelled() {
+            format!("Waiting for {} to stop...", self.background_work().join(", "))
+        } else {
+            format!("Still running: {}. Quit anyway?", self.background_work().join(", "))
+        };
+        let buttons = if self.shutdown.is_cancelled() {
+            row![button("Quit Now").on_press(Message::ForceQuit).style(button::danger)]
+        } else {
+            row![
+                button("Stop and Quit").on_press(Message::ConfirmQuit).style(button::danger),
+                button("Keep Working").on_press(Message::CancelQuit).style(button::secondary),
+            ]
+        };
+        Some(
+            row![text(prompt).size(12), buttons.spacing(5)]
+                .spacing(5)
+                .padding([0, 10])
+                .align_y(Alignment::Center)
+                .into(),
+        )
+    }
+    
+    /// Preset dropdown, "save as preset" field, and the overwrite prompt
+    fn view_preset_controls(&self) -> Element<'_, Message> {
+        let mut controls = column![
+            text("Presets"),
+            iced::widget::pick_list(self.presets.as_slice(), None::<String>, Message::ApplyPreset)
+                .placeholder("Apply preset...")
+                .width(Length::Fill),
+            row![
+                iced::widget::text_input("Preset name", &self.preset_name_input)
+                    .on_input(Message::PresetNameChanged)
+                    .on_submit(Message::SavePreset),
+                button("Save").on_press_maybe(
+                    (!self.preset_name_input.trim().is_empty()).then_some(Message::SavePreset)
+                ),
+            ]
+            .spacing(5),
+        ]
+        .spacing(5);
+        
+        if let Some(name) = &self.preset_overwrite_pending {
+            controls = controls.push(text(format!("\"{}\" exists. Overwrite?", name)).size(12))
+                .push(row![
+                    button("Overwrite").on_press(Message::ConfirmOverwritePreset).style(button::danger),
+                    button("Cancel").on_press(Message::CancelOverwritePreset).style(button::secondary),
+                ].spacing(5));
+        }
+        
+        controls.into()
+    }
+    
+    /// Snapshots of this image: save the current look, restore or compare saved ones
+    fn view_snapshot_controls(&self) -> Element<'_, Message> {
+        let mut controls = column![
+            text("Snapshots"),
+            row![
+                iced::widget::text_input("Snapshot name", &self.snapshot_name_input)
+                    .on_input(Message::SnapshotNameChanged)
+                    .on_submit(Message::SaveSnapshot),
+                button("Save").on_press_maybe(
+                    (!self.snapshot_name_input.trim().is_empty()).then_some(Message::SaveSnapshot)
+                ),
+            ]
+            .spacing(5),
+        ]
+        .spacing(5);
+        
+        for snapshot in &self.snapshots {
+            let comparing = self.compare_snapshot == Some(snapshot.id);
+            controls = controls.push(
+                row![
+                    text(&snapshot.name).size(12).width(Length::Fill),
+                    button(text("Restore").size(11))
+                        .on_press(Message::RestoreSnapshot(snapshot.id))
+                        .padding([2, 6])
+                        .style(button::secondary),
+                    button(text("Compare").size(11))
+                        .on_press(Message::CompareSnapshot(if comparing { None } else { Some(snapshot.id) }))
+                        .padding([2, 6])
+                        .style(if comparing { button::primary } else { button::secondary }),
+                    button(text("✕").size(11))
+                        .on_press(Message::DeleteSnapshot(snapshot.id))
+                        .padding([2, 6])
+                        .style(button::text),
+                ]
+                .spacing(4)
+                .align_y(Alignment::Center),
+            );
+        }
+        
+        controls.into()
+    }
+    
+    /// What's changed from default, so an image edited days ago can be read
+    /// at a glance
+    fn view_edit_summary(&self) -> Element<'_, Message> {
+        let changes = self.current_edit_params.diff_summary();
+        let dimmed = |theme: &Theme| text::Style {
+            color: Some(theme.palette().text.scale_alpha(0.6)),
+        };
+        if changes.is_empty() {
+            return text("No edits").size(12).style(dimmed).into();
+        }
+        let lines = changes.into_iter().map(|(label, value)| {
+            row![
+                text(label).size(12).style(dimmed),
+                iced::widget::horizontal_space(),
+                text(value).size(12),
+            ]
+            .into()
+        });
+        column![text("Changes").size(13)]
+            .extend(lines)
+            .spacing(2)
+            .into()
+    }
+    
+    /// Keywords of the selected image: remove existing ones, type to add more
+    fn view_keyword_controls(&self) -> Element<'_, Message> {
+        let tags = self.keywords.iter().fold(
+            Wrap::new().spacing(4.0).line_spacing(4.0),
+            |wrap, keyword| {
+                wrap.push(
+                    button(text(format!("{} ✕", keyword)).size(11))
+                        .on_press(Message::RemoveKeyword(keyword.clone()))
+                        .padding([2, 6])
+                        .style(button::secondary),
+                )
+            },
+        );
+        column![
+            text("Keywords"),
+            tags,
+            iced::widget::text_input("Add keyword", &self.keyword_input)
+                .on_input(Message::KeywordInputChanged)
+                .on_submit(Message::AddKeyword),
+        ]
+        .spacing(5)
+        .into()
+    }
+    
+    /// Export button (progress and Cancel while an export runs; the result
+    /// shows up as a toast)
+    fn view_export_controls(&self) -> Element<'_, Message> {
+        match self.export_progress {
+            Some(done) => {
+                let label = if done >= 1.0 {
+                    "⏳ Saving…".to_string()
+                } else {
+                    format!("⏳ Exporting… {:.0}%", done * 100.0)
+                };
+                column![
+                    row![
+                        text(label).size(12),
+                        iced::widget::horizontal_space(),
+                        button(text("Cancel").size(12))
+                            .on_press(Message::CancelExport)
+                            .padding([2, 8])
+                            .style(button::secondary),
+                    ]
+                    .align_y(Alignment::Center),
+                    iced::widget::progress_bar(0.0..=1.0, done).height(Length::Fixed(6.0)),
+                ]
+                .spacing(5)
+                .into()
+            }
+            None => button("Export").on_press(Message::ExportImage).into(),
+        }
+    }
+    
+    /// Film negative controls: the invert switch and the film base eyedropper
+    fn view_film_controls(&self) -> Element<'_, Message> {
+        let params = &self.current_edit_params;
+        let [r, g, b] = params.film_base;
+        column![
+            iced::widget::checkbox("Film negative", params.invert)
+                .on_toggle(Message::FilmInvertToggled),
+            row![
+                button(text(if self.film_base_picker_active { "🎞 Click the film rebate…" } else { "🎞 Pick Film Base" }).size(12))
+                    .on_press(Message::ToggleFilmBasePicker)
+                    .padding([2, 8])
+                    .style(if self.film_base_picker_active { button::primary } else { button::secondary }),
+                text(format!("Base: {:.2} / {:.2} / {:.2}", r, g, b)).size(11),
+            ]
+            .spacing(8)
+            .align_y(Alignment::Center),
         ]
-        .align_x(iced::Alignment::Center);
+        .spacing(5)
+        .into()
+    }
+    
+    /// Lens correction: on/off, which profile applies, manual distortion
+    fn view_lens_controls(&self) -> Element<'_, Message> {
+        let params = &self.current_edit_params;
+        let defaults = state::edit::EditParams::default();
+        let lens_model = self.selected_metadata.lens_model.as_deref();
+        let profile_line = match (state::lens::find_profile(lens_model), lens_model) {
+            (Some(profile), _) => format!("Profile: {}", profile.name),
+            (None, Some(model)) => format!("No profile for {} (manual only)", model),
+            (None, None) => "Lens unknown (manual only)".to_string(),
+        };
         
-        let left_panel = container(left_content)
-        .width(Length::FillPortion(7))  // 70% of width (7/10)
-        .height(Length::Fill)
-        .style(|_theme| {
-            container::Style {
-                background: Some(Background::Color(Color::from_rgb(0.08, 0.08, 0.10))), // Darker, more Adobe-like
-                ..Default::default()
-            }
-        });
+        column![
+            iced::widget::checkbox("Lens correction", params.lens_correction)
+                .on_toggle(Message::LensCorrectionToggled),
+            text(profile_line).size(11),
+            slider_label(format!("Distortion: {:.0}", params.lens_distortion * 100.0), Message::LensDistortionChanged(defaults.lens_distortion)),
+            slider(-1.0..=1.0, params.lens_distortion, Message::LensDistortionChanged)
+                .step(0.01),
+        ]
+        .spacing(5)
+        .into()
+    }
+    
+    /// Collapsible HSL panel: hue/saturation/luminance sliders for each color band
+    fn view_hsl_panel(&self) -> Element<'_, Message> {
+        use state::edit::{HslChannel, HSL_BAND_NAMES};
         
-        // Right half: Loading message
-        let right_panel = container(
+        let header = button(text(if self.hsl_panel_open { "▼ HSL" } else { "▶ HSL" }))
+            .on_press(Message::ToggleHslPanel)
+            .style(button::text);
+        if !self.hsl_panel_open {
+            return header.into();
+        }
+        
+        let bands = self.current_edit_params.hsl.iter().zip(HSL_BAND_NAMES).enumerate().map(|(i, (band, name))| {
+            let channel_slider = |label: &str, channel: HslChannel, value: f32| {
+                row![
+                    text(format!("{} {:+.0}", label, value)).size(11).width(Length::Fixed(48.0)),
+                    slider(-100.0..=100.0, value, move |v| Message::HslChanged(i, channel, v)),
+                ]
+                .spacing(5)
+                .align_y(Alignment::Center)
+            };
             column![
-                Space::with_height(Length::Fill),
-                text("RAW Editor")
-                    .size(56)
-                    .center()
-                    .style(|_theme| text::Style {
-                        color: Some(Color::from_rgb(0.9, 0.9, 0.9)),
-                    }),
-                Space::with_height(10.0),
-                text("Professional RAW Photo Editor")
-                    .size(14)
-                    .center()
-                    .style(|_theme| text::Style {
-                        color: Some(Color::from_rgb(0.6, 0.6, 0.6)),
-                    }),
-                Space::with_height(40.0),
-                text(&self.status)
-                    .size(16)
-                    .center()
-                    .style(|_theme| text::Style {
-                        color: Some(Color::from_rgb(0.8, 0.8, 0.8)),
-                    }),
-                Space::with_height(15.0),
-                text("⏳")
-                    .size(32)
-                    .center()
-                    .style(|_theme| text::Style {
-                        color: Some(Color::from_rgb(0.5, 0.7, 1.0)),
-                    }),
-                Space::with_height(Length::Fill),
-                text("Version 0.1.5")
-                    .size(11)
-                    .center()
-                    .style(|_theme| text::Style {
-                        color: Some(Color::from_rgb(0.4, 0.4, 0.4)),
-                    }),
-                Space::with_height(10.0),
+                text(name).size(12),
+                channel_slider("H", HslChannel::Hue, band.hue),
+                channel_slider("S", HslChannel::Saturation, band.saturation),
+                channel_slider("L", HslChannel::Luminance, band.luminance),
             ]
-            .align_x(iced::Alignment::Center)
-        )
-        .width(Length::FillPortion(3))  // 30% of width (3/10)
-        .height(Length::Fill)
-        .style(|_theme| {
-            container::Style {
-                background: Some(Background::Color(Color::from_rgb(0.08, 0.08, 0.10))), // Match left panel for seamless look
-                ..Default::default()
-            }
+            .spacing(2)
+            .into()
         });
         
-        // Full-screen splash layout
-        row![
-            left_panel,
-            right_panel,
+        column![header, iced::widget::Column::with_children(bands).spacing(8)]
+            .spacing(6)
+            .into()
+    }
+    
+    /// Split toning: R/G/B of the shadow and highlight tints, then the balance
+    fn view_split_tone_controls(&self) -> Element<'_, Message> {
+        let params = &self.current_edit_params;
+        let defaults = state::edit::EditParams::default();
+        let tint_sliders = |name: &'static str, tint: [f32; 3], on_change: fn(usize, f32) -> Message| {
+            let swatch = Color::from_rgb(tint[0], tint[1], tint[2]);
+            let channels = ["R", "G", "B"].iter().enumerate().map(move |(channel, label)| {
+                row![
+                    text(format!("{} {:.0}", label, tint[channel] * 100.0)).size(11).width(Length::Fixed(48.0)),
+                    slider(0.0..=1.0, tint[channel], move |v| on_change(channel, v)).step(0.01),
+                ]
+                .spacing(5)
+                .align_y(Alignment::Center)
+                .into()
+            });
+            column![
+                row![
+                    text(name).size(12),
+                    container(iced::widget::Space::new(Length::Fixed(12.0), Length::Fixed(12.0)))
+                        .style(move |_theme| container::Style {
+                            background: Some(Background::Color(swatch)),
+                            border: Border { color: Color::from_rgb(0.4, 0.4, 0.4), width: 1.0, radius: 2.0.into() },
+                            ..Default::default()
+                        }),
+                ]
+                .spacing(6)
+                .align_y(Alignment::Center),
+                iced::widget::Column::with_children(channels).spacing(2),
+            ]
+            .spacing(2)
+        };
+        
+        column![
+            text("Split Toning"),
+            tint_sliders("Shadows", params.shadow_tint, Message::ShadowTintChanged),
+            tint_sliders("Highlights", params.highlight_tint, Message::HighlightTintChanged),
+            slider_label(format!("Balance: {:.0}", params.balance * 100.0), Message::BalanceChanged(defaults.balance)),
+            slider(-1.0..=1.0, params.balance, Message::BalanceChanged).step(0.01),
         ]
-        .width(Length::Fill)
-        .height(Length::Fill)
+        .spacing(5)
         .into()
     }
     
-    /// Phase 23: Main application UI (shown after database loads)
-    fn view_main(&self) -> Element<Message> {
-        // Tab navigation bar
-        let library_button = button(
-            text("📚 Library")
-                .size(16)
-        )
-        .on_press(Message::TabChanged(AppTab::Library))
-        .padding(12);
+    /// Local adjustments: add/remove masks, pick one, and its sliders
+    fn view_mask_controls(&self) -> Element<'_, Message> {
+        use state::edit::{MaskKind, MaskSetting, MAX_MASKS};
         
-        let library_button = if self.current_tab == AppTab::Library {
-            library_button.style(button::primary)
-        } else {
-            library_button.style(button::secondary)
-        };
+        let masks = self.current_edit_params.masks;
+        let header = row![
+            text("Masks"),
+            iced::widget::Space::with_width(Length::Fill),
+            button(if self.mask_tool_active { "Done" } else { "🎯 Edit" })
+                .on_press_maybe((!masks.is_empty() || self.mask_tool_active).then_some(Message::ToggleMaskTool))
+                .style(if self.mask_tool_active { button::primary } else { button::secondary }),
+        ]
+        .align_y(Alignment::Center);
+        let add = iced::widget::Row::with_children(MaskKind::ALL.iter().map(|&kind| {
+            button(text(format!("+ {}", kind)).size(12))
+                .on_press_maybe((masks.len() < MAX_MASKS).then_some(Message::AddMask(kind)))
+                .style(button::secondary)
+                .into()
+        }))
+        .spacing(4);
+        let list = iced::widget::Row::with_children(masks.as_slice().iter().enumerate().map(|(i, mask)| {
+            let label = match mask.kind {
+                MaskKind::Radial => "◯",
+                MaskKind::Linear => "▤",
+            };
+            button(text(format!("{} {}", label, i + 1)).size(12))
+                .on_press(Message::MaskSelected(i))
+                .style(if self.selected_mask == Some(i) { button::primary } else { button::secondary })
+                .into()
+        }))
+        .spacing(4);
         
-        let develop_button = button(
-            text("🎨 Develop")
-                .size(16)
+        let mut controls = column![header, add, list].spacing(5);
+        if let Some((index, mask)) = self.selected_mask.and_then(|i| masks.as_slice().get(i).map(|mask| (i, mask))) {
+            let defaults = state::edit::LocalMask::default();
+            controls = controls
+                .push(slider_label(format!("Exposure: {:+.2}", mask.exposure), Message::MaskChanged(index, MaskSetting::Exposure, defaults.exposure)))
+                .push(slider(-5.0..=5.0, mask.exposure, move |v| Message::MaskChanged(index, MaskSetting::Exposure, v)).step(0.01))
+                .push(slider_label(format!("Contrast: {:.0}", mask.contrast), Message::MaskChanged(index, MaskSetting::Contrast, defaults.contrast)))
+                .push(slider(-100.0..=100.0, mask.contrast, move |v| Message::MaskChanged(index, MaskSetting::Contrast, v)))
+                .push(slider_label(format!("Saturation: {:.0}", mask.saturation), Message::MaskChanged(index, MaskSetting::Saturation, defaults.saturation)))
+                .push(slider(-100.0..=100.0, mask.saturation, move |v| Message::MaskChanged(index, MaskSetting::Saturation, v)))
+                .push_maybe((mask.kind == MaskKind::Radial).then(|| {
+                    column![
+                        slider_label(format!("Feather: {:.0}", mask.feather * 100.0), Message::MaskChanged(index, MaskSetting::Feather, defaults.feather)),
+                        slider(0.0..=1.0, mask.feather, move |v| Message::MaskChanged(index, MaskSetting::Feather, v)).step(0.01),
+                    ]
+                    .spacing(5)
+                }))
+                .push(button(text("Remove Mask").size(12)).on_press(Message::RemoveMask(index)).style(button::secondary));
+        }
+        controls.into()
+    }
+    
+    /// Encoder setting for the batch's format: JPEG quality or PNG compression
+    fn view_batch_encoder_control(&self) -> Element<'_, Message> {
+        match self.batch_export_format {
+            state::export::ExportFormat::Jpeg => row![
+                text(format!("Quality: {}", self.export_options.jpeg_quality)).size(12),
+                self.view_jpeg_quality_slider(),
+            ]
+            .spacing(6)
+            .width(Length::Fixed(220.0))
+            .align_y(Alignment::Center)
+            .into(),
+            state::export::ExportFormat::Png => self.view_png_compression_picker(),
+        }
+    }
+    
+    /// JPEG quality slider (shared by Export and Batch Export)
+    fn view_jpeg_quality_slider(&self) -> Element<'_, Message> {
+        slider(1..=100, self.export_options.jpeg_quality, Message::JpegQualityChanged).into()
+    }
+    
+    /// PNG compression picker (shared by Export and Batch Export)
+    fn view_png_compression_picker(&self) -> Element<'_, Message> {
+        iced::widget::pick_list(
+            state::export::PngCompression::ALL,
+            Some(self.export_options.png_compression),
+            Message::PngCompressionChanged,
         )
-        .on_press(Message::TabChanged(AppTab::Develop))
-        .padding(12);
-        
-        let develop_button = if self.current_tab == AppTab::Develop {
-            develop_button.style(button::primary)
-        } else {
-            develop_button.style(button::secondary)
+        .text_size(12)
+        .into()
+    }
+    
+    /// Two images side by side at the same zoom and pan, for picking the best
+    /// of a burst. The left one is the selected image.
+    fn view_compare(&self, right_id: i64) -> Element<'_, Message> {
+        let filename = |image_id: Option<i64>| {
+            self.images
+                .iter()
+                .find(|img| Some(img.id) == image_id)
+                .map_or(String::new(), |img| img.filename.clone())
+        };
+        let zoom_percent = match &self.editor_status {
+            EditorStatus::Ready(pipeline) => self.zoom / self.actual_pixels_zoom(pipeline) * 100.0,
+            _ => self.zoom * 100.0,
         };
         
-        let tab_bar = row![
-            library_button,
-            develop_button,
+        let header = row![
+            text("Compare").size(18),
+            iced::widget::Space::with_width(Length::Fill),
+            text(format!("{:.0}%", zoom_percent)).size(12),
+            button(text("Fit").size(12)).on_press(Message::ZoomToFit).style(button::secondary),
+            button(text("100%").size(12)).on_press(Message::SetZoom(1.0)).style(button::secondary),
+            button(text("200%").size(12)).on_press(Message::SetZoom(2.0)).style(button::secondary),
+            button("⇄ Swap Sides")
+                .on_press_maybe(self.selected_image_id.map(|left_id| Message::EnterCompare(right_id, left_id)))
+                .style(button::secondary),
+            button("Exit Compare").on_press(Message::ExitCompare),
         ]
-        .spacing(8)
+        .spacing(5)
+        .align_y(Alignment::Center)
         .padding(10);
         
-        // Render content based on current tab
-        let content = match self.current_tab {
-            AppTab::Library => self.view_library(),
-            AppTab::Develop => self.view_develop(),
+        let pane = |image_id: Option<i64>, preview: Element<'static, Message>| {
+            column![
+                text(filename(image_id)).size(14),
+                container(preview)
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .style(|_theme| container::Style {
+                        background: Some(Background::Color(Color::from_rgb(0.0, 0.0, 0.0))),
+                        ..Default::default()
+                    }),
+            ]
+            .spacing(4)
+            .width(Length::Fill)
         };
+        let left = self.view_compare_pane(&self.editor_status, self.current_edit_params, &self.preview_frame);
+        let right = self.view_compare_pane(&self.compare_status, self.compare_params, &self.compare_frame);
         
-        // Main layout: tab bar + content
         column![
-            tab_bar,
-            content,
+            header,
+            row![pane(self.selected_image_id, left), pane(Some(right_id), right)]
+                .spacing(4)
+                .padding(4)
+                .height(Length::Fill),
         ]
         .into()
     }
     
-    /// Build the Library tab view (grid of thumbnails)
-    fn view_library(&self) -> Element<Message> {
-        // Count thumbnails and deleted files
-        let cached_count = self.images.iter()
-            .filter(|img| img.cache_path_thumb.is_some())
-            .count();
-        let deleted_count = self.images.iter()
-            .filter(|img| img.file_status == "deleted")
-            .count();
-        let total_count = self.images.len();
-        
-        // ========== LEFT PANE: Thumbnail Grid ==========
-        
-        // Header for grid pane
-        let grid_header = column![
-            text("RAW Editor v0.1.5 - Zoom and panning")
-                .size(24),
-            button("Import Folder")
-                .on_press(Message::ImportFolder)
-                .padding(8),
-            text(&self.status).size(12),
-            text(format!("Thumbnails: {}/{}  |  Deleted: {}", cached_count, total_count, deleted_count))
-                .size(11),
-        ]
-        .spacing(10)
-        .padding(10);
-        
-        // Create wrapping grid of clickable thumbnails
-        const THUMB_SIZE: u16 = 1; // Equal size for all squares
-        
-        let thumbnail_grid = self.images.iter().fold(
-            Wrap::new().spacing(8.0).line_spacing(8.0),
-            |wrap, img| {
-                // Check if file is deleted
-                let is_deleted = img.file_status == "deleted";
-                
-                // Create thumbnail content
-                let thumbnail_content = if is_deleted {
-                    // Show deleted file indicator with grey background
-                    container(
-                        column![
-                            text("❌").size(24),
-                            text(&img.filename).size(8),
-                            text("(deleted)").size(7),
-                        ]
-                        .align_x(Alignment::Center)
-                        .spacing(4)
-                    )
-                    .width(THUMB_SIZE)
-                    .height(THUMB_SIZE)
-                    .center_x(iced::Length::Fixed(200.0))
-                    .center_y(iced::Length::Fixed(150.0))
-                    .style(|_theme| {
-                        container::Style {
-                            background: Some(Background::Color(Color::from_rgb(0.3, 0.3, 0.3))),
-                            border: Border {
-                                color: Color::from_rgb(0.5, 0.2, 0.2),
-                                width: 2.0,
-                                radius: 4.0.into(),
-                            },
-                            ..Default::default()
-                        }
-                    })
-                } else if let Some(ref thumb_path) = img.cache_path_thumb {
-                    // Phase 28: Show 256px thumbnail tier
-                    let handle = Handle::from_path(thumb_path.clone());
-                    container(
-                        Image::new(handle)
-                            .content_fit(iced::ContentFit::Contain) // Fit image inside square
-                    )
-                    .width(THUMB_SIZE)
-                    .height(THUMB_SIZE)
-                    .center_x(iced::Length::Fixed(200.0))
-                    .center_y(iced::Length::Fixed(150.0))
-                    .style(|_theme| {
-                        container::Style {
-                            background: Some(Background::Color(Color::from_rgb(0.25, 0.25, 0.25))),
-                            border: Border {
-                                color: Color::from_rgb(0.4, 0.4, 0.4),
-                                width: 1.0,
-                                radius: 4.0.into(),
-                            },
-                            ..Default::default()
-                        }
-                    })
-                } else {
-                    // Show placeholder for pending thumbnails with grey background
-                    container(
-                        text("⏳").size(48)
-                    )
-                    .width(THUMB_SIZE)
-                    .height(THUMB_SIZE)
-                    .center_x(iced::Length::Fixed(200.0))
-                    .center_y(iced::Length::Fixed(150.0))
-                    .style(|_theme| {
-                        container::Style {
-                            background: Some(Background::Color(Color::from_rgb(0.2, 0.2, 0.2))),
-                            border: Border {
-                                color: Color::from_rgb(0.3, 0.3, 0.3),
-                                width: 1.0,
-                                radius: 4.0.into(),
-                            },
-                            ..Default::default()
-                        }
-                    })
-                };
-                
-                // Wrap in clickable button
-                let thumbnail_widget = button(thumbnail_content)
-                    .on_press(Message::ImageSelected(img.id))
-                    .padding(0)
-                    .style(|theme, status| {
-                        button::Style {
-                            background: None,
-                            border: Border::default(),
-                            ..button::primary(theme, status)
-                        }
-                    });
-                
-                wrap.push(thumbnail_widget)
-            },
-        );
+    /// One side of the compare view: rendered with the shared zoom/pan, and
+    /// scrolling or dragging over either side moves both
+    fn view_compare_pane(
+        &self,
+        status: &EditorStatus,
+        params: state::edit::EditParams,
+        frames: &ui::canvas::FrameCache,
+    ) -> Element<'static, Message> {
+        let pipeline = match status {
+            EditorStatus::Ready(pipeline) => pipeline,
+            EditorStatus::Failed(_, err) => {
+                return container(text(format!("⚠️ {}", err)).size(14)).center(Length::Fill).into();
+            }
+            _ => return container(text("⌛ Loading…").size(16)).center(Length::Fill).into(),
+        };
         
-        // Phase 20: Full-screen thumbnail grid (no preview pane)
-        // Wrap grid in scrollable container
-        let content = column![
-            grid_header,
-            scrollable(thumbnail_grid)
-                .height(Length::Fill)
-                .width(Length::Fill),
-        ];
+        // Each side gets half the window, so the viewport (one pane) sets the
+        // width once zoomed past fit, as in the single view
+        let (max_preview_width, native) = self.zoomed_preview_width(pipeline);
+        let frame_key = ui::canvas::FrameKey {
+            generation: pipeline.generation,
+            image_id: pipeline.image_id,
+            params,
+            zoom: self.zoom,
+            pan: (self.pan_offset.x, self.pan_offset.y),
+            max_preview_width,
+            detail_preview: false,
+            show_clipping: self.show_clipping,
+            orientation: pipeline.orientation(),
+            split_x: None,
+            compare_params: None,
+        };
+        let (frame, _) = frames.get_or_render(frame_key, || {
+            pipeline.update_uniforms_with_zoom(&params, self.zoom, self.pan_offset.x, self.pan_offset.y);
+            pipeline.set_max_preview_width(max_preview_width);
+            let (width, height) = pipeline.preview_size();
+            println!("🎨 GPU rendering {}x{} compare preview of image {}", width, height, pipeline.image_id);
+            ui::canvas::PreviewFrame {
+                handle: iced::widget::image::Handle::from_rgba(width, height, pipeline.render_to_bytes()),
+                width,
+                height,
+            }
+        });
         
-        container(content)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .into()
+        let handle = frame.handle;
+        iced::widget::responsive(move |size| {
+            let image = iced::widget::Image::new(handle.clone())
+                .content_fit(iced::ContentFit::Contain)
+                .filter_method(preview_filter(native))
+                .width(Length::Fill)
+                .height(Length::Fill);
+            iced::widget::mouse_area(image)
+                .on_scroll(|delta| {
+                    let zoom_delta = match delta {
+                        iced::mouse::ScrollDelta::Lines { y, .. } => y * 0.1,
+                        iced::mouse::ScrollDelta::Pixels { y, .. } => y * 0.01,
+                    };
+                    Message::Zoom(zoom_delta, Point::new(-1.0, -1.0))
+                })
+                .on_press(Message::MousePressed)
+                .on_release(Message::MouseReleased)
+                .on_move(move |position| Message::MouseMoved(position, size))
+                .into()
+        })
+        .into()
     }
     
     /// Build the Develop tab view (full-screen editor with preview)
     fn view_develop(&self) -> Element<Message> {
+        if let Some(right_id) = self.compare_with {
+            return self.view_compare(right_id);
+        }
         match &self.editor_status {
             EditorStatus::NoSelection => {
                 // No image selected - show prompt
@@ -1457,12 +6011,45 @@ impl RawEditor {
             }
             EditorStatus::Loading(image_id) => {
                 // Show loading state
-                if let Some(img) = self.images.iter().find(|i| i.id == *image_id) {
+                let img = self.images.iter().find(|i| i.id == *image_id);
+                
+                // The camera's embedded JPEG (largest cached tier, already
+                // upright) stands in until the pipeline is ready
+                let embedded = img
+                    .filter(|_| self.fast_preview)
+                    .and_then(|img| img.cache_path_working.as_ref().or(img.cache_path_instant.as_ref()))
+                    .filter(|path| std::path::Path::new(path).exists());
+                if let (Some(img), Some(preview_path)) = (img, embedded) {
+                    return column![
+                        text(&img.filename).size(16),
+                        Image::new(Handle::from_path(preview_path))
+                            .width(Length::Fill)
+                            .height(Length::Fill)
+                            .content_fit(iced::ContentFit::Contain),
+                        row![
+                            ui::spinner::view(self.spinner_frame, 16.0),
+                            text("Camera preview - decoding the RAW for editing...").size(14),
+                        ]
+                        .spacing(10)
+                        .align_y(Alignment::Center),
+                    ]
+                    .spacing(10)
+                    .padding(20)
+                    .align_x(Alignment::Center)
+                    .into();
+                }
+                
+                if let Some(img) = img {
                     container(
                         column![
                             text(&img.filename).size(24),
                             text("").size(30),
-                            text("⌛ Generating full preview...").size(20),
+                            row![
+                                ui::spinner::view(self.spinner_frame, 24.0),
+                                text("Generating full preview...").size(20),
+                            ]
+                            .spacing(10)
+                            .align_y(Alignment::Center),
                             text("").size(10),
                             text("This may take a few seconds for large RAW files")
                                 .size(14)
@@ -1494,79 +6081,289 @@ impl RawEditor {
                 if let Some(image_id) = self.selected_image_id {
                     if let Some(img) = self.images.iter().find(|i| i.id == image_id) {
                         // Header with image info
+                        let metadata = &self.selected_metadata;
+                        let exif_line = [
+                            metadata.camera_display(),
+                            metadata.iso_display(),
+                            metadata.shutter_display(),
+                            metadata.aperture_display(),
+                            metadata.focal_length_display(),
+                            metadata.captured_at_display(),
+                        ]
+                        .join("  ·  ");
+                        let file_missing = img.file_status == "deleted";
                         let header = row![
-                            text(&img.filename).size(18),
+                            // Hover the name for the full path
+                            iced::widget::tooltip(
+                                text(&img.filename).size(18),
+                                container(text(&img.path).size(12))
+                                    .padding(5)
+                                    .style(container::rounded_box),
+                                iced::widget::tooltip::Position::Bottom,
+                            ),
                             text(" • ").size(18),
-                            text("🎨 GPU Rendering + Debayering").size(18),
+                            text(exif_line)
+                                .size(14)
+                                .style(|theme: &Theme| {
+                                    text::Style {
+                                        color: Some(theme.palette().text.scale_alpha(0.7)),
+                                    }
+                                }),
+                            iced::widget::Space::with_width(Length::Fill),
+                            button("📂 Reveal")
+                                .on_press_maybe((!file_missing).then_some(Message::RevealInFileManager(img.id)))
+                                .style(button::secondary),
+                            button("Copy Path")
+                                .on_press(Message::CopyPathToClipboard(img.id))
+                                .style(button::secondary),
+                            button(if self.wb_picker_active { "💧 Click a gray point…" } else { "💧 WB Picker" })
+                                .on_press(Message::ToggleWbPicker)
+                                .style(if self.wb_picker_active { button::primary } else { button::secondary }),
                         ]
+                        .align_y(Alignment::Center)
                         .spacing(5)
                         .padding(10);
                         
                         // 🎨 Phase 25: GPU-Accelerated Zoom & Pan (with smart caching)
                         // Determine which params to render based on show_before toggle
-                        let params_to_render = if self.show_before {
+                        // (split view renders the edits and composites the original in)
+                        let split_x = self.split_view_active().then_some(self.split_x);
+                        let solo_key = self.solo_key();
+                        let mut params_to_render = if self.show_before && split_x.is_none() {
                             state::edit::EditParams::default() // Show original (no edits)
+                        } else if let Some(key) = solo_key {
+                            self.current_edit_params.solo(key) // Alt held: one adjustment alone
                         } else {
                             self.current_edit_params.clone() // Show edited version
                         };
                         
-                        // Phase 25: Update GPU uniforms with correct params + zoom/pan
-                        // This updates the shader uniforms (very fast, no readback)
-                        pipeline.update_uniforms_with_zoom(&params_to_render, self.zoom, self.pan_offset.x, self.pan_offset.y);
+                        // Crop mode shows the whole straightened frame under the overlay
+                        if self.crop_tool_active {
+                            params_to_render.crop_rect = None;
+                        }
                         
-                        // Phase 25: Render with zoom/pan applied in shader
-                        println!("🎨 GPU rendering {}x{} preview (zoom: {:.1}%, pan: {:.3}, {:.3})", 
-                            pipeline.preview_width, 
-                            pipeline.preview_height,
-                            self.zoom * 100.0,
-                            self.pan_offset.x,
-                            self.pan_offset.y
-                        );
-                        let rgba_bytes = pipeline.render_to_bytes();
-                        println!("✅ Rendered {} bytes (preview with zoom/pan)", rgba_bytes.len());
+                        // Preview width: the quality setting, wider when zoomed in, and the
+                        // visible region at 1:1 once past actual pixels (see zoomed_preview_width)
+                        let (max_preview_width, native) = self.zoomed_preview_width(pipeline);
                         
-                        // Phase 22: Calculate histogram from TINY 256px render (only if enabled)
-                        if self.histogram_enabled {
-                            let histogram_bytes = pipeline.render_to_histogram_bytes();
-                            let histogram = pipeline.calculate_histogram(&histogram_bytes);
-                            *self.histogram_data.borrow_mut() = histogram;
-                            self.histogram_cache.clear(); // Force histogram redraw
-                        }
+                        // Phase 25: Render with zoom/pan applied in shader - but only when
+                        // something that affects the frame changed. view() also runs for
+                        // mouse moves and status updates; those reuse the last frame
+                        let frame_key = ui::canvas::FrameKey {
+                            generation: pipeline.generation,
+                            image_id: pipeline.image_id,
+                            params: params_to_render,
+                            zoom: self.zoom,
+                            pan: (self.pan_offset.x, self.pan_offset.y),
+                            max_preview_width,
+                            detail_preview: self.detail_preview,
+                            show_clipping: self.show_clipping,
+                            orientation: pipeline.orientation(),
+                            split_x,
+                            compare_params: split_x.and(self.compared_snapshot_params()),
+                        };
+                        // Single view on the GPU: the shader draws straight onto the
+                        // window. The pipeline still gets the uniforms (the histogram and
+                        // pixel readout render from them), but only when the key changes
+                        let on_surface = split_x.is_none() && gpu::surface::can_draw(pipeline);
+                        let (preview, (preview_width, preview_height), rendered) = if on_surface {
+                            let changed = self.preview_frame.note_drawn(frame_key);
+                            if changed {
+                                pipeline.update_uniforms_with_zoom(&params_to_render, self.zoom, self.pan_offset.x, self.pan_offset.y);
+                                pipeline.set_max_preview_width(max_preview_width);
+                            }
+                            let renderer = ui::canvas::GpuRenderer {
+                                pipeline: pipeline.clone(),
+                                params: params_to_render,
+                                zoom: self.zoom,
+                                offset: self.pan_offset,
+                            };
+                            (ui::canvas::Preview::Surface(Box::new(renderer)), pipeline.preview_size(), changed)
+                        } else {
+                            let (frame, rendered) = self.preview_frame.get_or_render(frame_key, || {
+                                // Phase 25: Update GPU uniforms with correct params + zoom/pan
+                                pipeline.update_uniforms_with_zoom(&params_to_render, self.zoom, self.pan_offset.x, self.pan_offset.y);
+                                pipeline.set_max_preview_width(max_preview_width);
+                                let (width, height) = pipeline.preview_size();
+                            
+                                println!("🎨 GPU rendering {}x{} preview (zoom: {:.1}%, pan: {:.3}, {:.3})",
+                                    width,
+                                    height,
+                                    self.zoom * 100.0,
+                                    self.pan_offset.x,
+                                    self.pan_offset.y
+                                );
+                                let mut rgba_bytes = pipeline.render_to_bytes();
+                                println!("✅ Rendered {} bytes (preview with zoom/pan)", rgba_bytes.len());
+                            
+                                // Split view: second render of the original, or of the snapshot
+                                // being compared (same crop and straighten, so both halves line
+                                // up), left of the divider
+                                if let Some(split_x) = split_x {
+                                    let before_look = self.compared_snapshot_params().unwrap_or_default();
+                                    let before = params_to_render.with_preset(&before_look);
+                                    pipeline.update_uniforms_with_zoom(&before, self.zoom, self.pan_offset.x, self.pan_offset.y);
+                                    let before_bytes = pipeline.render_to_bytes();
+                                    ui::canvas::split_composite(&before_bytes, &mut rgba_bytes, width, split_x);
+                                    pipeline.update_uniforms_with_zoom(&params_to_render, self.zoom, self.pan_offset.x, self.pan_offset.y);
+                                }
+                            
+                                ui::canvas::PreviewFrame {
+                                    handle: iced::widget::image::Handle::from_rgba(width, height, rgba_bytes),
+                                    width,
+                                    height,
+                                }
+                            });
+                            (ui::canvas::Preview::Frame(frame.handle), (frame.width, frame.height), rendered)
+                        };
                         
-                        // Create Image handle from rendered bytes
-                        let image_handle = iced::widget::image::Handle::from_rgba(
-                            pipeline.preview_width,
-                            pipeline.preview_height,
-                            rgba_bytes
-                        );
+                        // Tell the user what they're looking at
+                        let header = header
+                            .push(text(format!("Preview {}×{}", preview_width, preview_height)).size(14))
+                            .push_maybe(solo_key.map(|key| text(format!("Solo: {}", key.replace('_', " "))).size(14)))
+                            .push(iced::widget::pick_list(
+                                state::settings::PreviewQuality::ALL,
+                                Some(self.preview_quality),
+                                Message::PreviewQualityChanged,
+                            ))
+                            .push(iced::widget::pick_list(
+                                CompareMode::ALL,
+                                Some(self.compare_mode),
+                                Message::CompareModeChanged,
+                            ))
+                            // Rotate left/right (Ctrl+[ / Ctrl+])
+                            .push(button(text("⟲").size(14)).on_press(Message::RotateImage(false)))
+                            .push(button(text("⟳").size(14)).on_press(Message::RotateImage(true)));
                         
-                        // Phase 25: Image widget with zoom/pan already applied in GPU shader!
-                        let gpu_image = iced::widget::Image::new(image_handle)
-                            .content_fit(iced::ContentFit::Contain);
+                        // Phase 22: Histogram of the full preview, binned on the GPU (only if enabled)
+                        // Same inputs as the frame, so it only needs refreshing with it
+                        if self.histogram_enabled && rendered {
+                            *self.histogram_data.borrow_mut() = pipeline.calculate_histogram_gpu();
+                            self.histogram_cache.clear(); // Force histogram redraw
+                        }
                         
                         // Phase 25: Wrap in mouse_area to capture zoom/pan events
                         use iced::widget::mouse_area;
-                        use iced::mouse::{self, ScrollDelta};
+                        use iced::mouse::ScrollDelta;
                         
-                        let interactive_image = mouse_area(gpu_image)
-                            .on_scroll(|delta| {
-                                let zoom_delta = match delta {
-                                    ScrollDelta::Lines { y, .. } => y * 0.1,
-                                    ScrollDelta::Pixels { y, .. } => y * 0.01,
-                                };
-                                // Phase 26: Pass sentinel value (-1, -1) for cursor
-                                // Actual position will be retrieved from last_cursor_position in handler
-                                Message::Zoom(zoom_delta, Point::new(-1.0, -1.0))
-                            })
-                            .on_press(Message::MousePressed)
-                            .on_release(Message::MouseReleased)
-                            .on_move(|position| Message::MouseMoved(position));
+                        // The image fills the preview area (Contain fit, centered) and
+                        // responsive hands us that area's exact size: it's recorded on
+                        // every layout (so the fit and the zoom readout are right from the
+                        // start) and rides along with every MouseMoved for the zoom/pan math
+                        let gpu_preview = preview.clone();
+                        let viewport_size = &self.viewport_size;
+                        let interactive_image = iced::widget::responsive(move |size| {
+                            viewport_size.set((size.width, size.height));
+                            // Phase 25: zoom/pan already applied in GPU shader!
+                            mouse_area(gpu_preview.widget(preview_filter(native)))
+                                .on_scroll(|delta| {
+                                    let zoom_delta = match delta {
+                                        ScrollDelta::Lines { y, .. } => y * 0.1,
+                                        ScrollDelta::Pixels { y, .. } => y * 0.01,
+                                    };
+                                    // Phase 26: Pass sentinel value (-1, -1) for cursor
+                                    // Actual position will be retrieved from last_cursor_position in handler
+                                    Message::Zoom(zoom_delta, Point::new(-1.0, -1.0))
+                                })
+                                .on_press(Message::MousePressed)
+                                .on_release(Message::MouseReleased)
+                                .on_move(move |position| Message::MouseMoved(position, size))
+                                .on_exit(Message::PreviewCursorLeft)
+                                .into()
+                        });
+                        
+                        // Crop mode: image fills the area so the overlay can find it
+                        // (same Contain fit), and the overlay takes over the mouse
+                        let preview_content: Element<'_, Message> = if self.crop_tool_active {
+                            let (frame_width, frame_height) = pipeline.dimensions();
+                            iced::widget::stack![
+                                preview.widget(iced::widget::image::FilterMethod::Linear),
+                                canvas::Canvas::new(crate::ui::crop::CropOverlay {
+                                    rect: self.current_edit_params.crop_or_full(),
+                                    aspect: self.crop_aspect.ratio(),
+                                    lock_ratio: self.crop_lock_ratio,
+                                    image_aspect: frame_width as f32 / frame_height as f32,
+                                    guide: self.crop_guide,
+                                    straighten: self.straighten_active,
+                                    rotation_degrees: self.current_edit_params.rotation_degrees,
+                                })
+                                .width(Length::Fill)
+                                .height(Length::Fill),
+                            ]
+                            .into()
+                        } else if self.mask_tool_active {
+                            // Mask mode: the cropped preview at fit, handles on top
+                            let crop = self.current_edit_params.crop_or_full();
+                            let (frame_width, frame_height) = pipeline.dimensions();
+                            let (output_width, output_height) = state::edit::crop_size(crop, frame_width, frame_height);
+                            iced::widget::stack![
+                                preview.widget(preview_filter(native)),
+                                canvas::Canvas::new(crate::ui::masks::MaskOverlay {
+                                    masks: self.current_edit_params.masks,
+                                    selected: self.selected_mask,
+                                    crop,
+                                    image_aspect: output_width as f32 / output_height as f32,
+                                })
+                                .width(Length::Fill)
+                                .height(Length::Fill),
+                            ]
+                            .into()
+                        } else {
+                            interactive_image.into()
+                        };
+                        
+                        // Presentation mode: the image alone on black
+                        if self.fullscreen_preview {
+                            return container(preview_content)
+                                .width(Length::Fill)
+                                .height(Length::Fill)
+                                .center_x(Length::Fill)
+                                .center_y(Length::Fill)
+                                .style(|_theme| container::Style {
+                                    background: Some(Background::Color(Color::BLACK)),
+                                    ..Default::default()
+                                })
+                                .into();
+                        }
+                        
+                        // Zoom readout (relative to actual pixels) and presets, bottom right
+                        let zoom_percent = self.zoom / self.actual_pixels_zoom(pipeline) * 100.0;
+                        let zoom_controls = container(
+                            row![
+                                text(format!("{:.0}%", zoom_percent)).size(12),
+                                button(text("Fit").size(12)).on_press(Message::ZoomToFit).style(button::secondary),
+                                button(text("100%").size(12)).on_press(Message::SetZoom(1.0)).style(button::secondary),
+                                button(text("200%").size(12)).on_press(Message::SetZoom(2.0)).style(button::secondary),
+                            ]
+                            .spacing(4)
+                            .align_y(Alignment::Center)
+                        )
+                        .padding(4)
+                        .style(|_theme| container::Style {
+                            background: Some(Background::Color(Color::from_rgba(0.0, 0.0, 0.0, 0.6))),
+                            text_color: Some(Color::WHITE),
+                            border: Border {
+                                radius: 4.0.into(),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        });
                         
-                        let preview = container(interactive_image)
+                        let preview = container(iced::widget::stack![
+                            container(preview_content)
+                                .width(Length::Fill)
+                                .height(Length::Fill)
+                                .center_x(Length::Fill)
+                                .center_y(Length::Fill),
+                            container(zoom_controls)
+                                .width(Length::Fill)
+                                .height(Length::Fill)
+                                .padding(8)
+                                .align_x(iced::alignment::Horizontal::Right)
+                                .align_y(iced::alignment::Vertical::Bottom),
+                        ])
                             .width(Length::Fill)
                             .height(Length::Fill)
-                            .center_x(Length::Fill)
-                            .center_y(Length::Fill)
                             .style(|_theme| {
                                 container::Style {
                                     background: Some(Background::Color(Color::from_rgb(0.0, 0.0, 0.0))),
@@ -1581,18 +6378,44 @@ impl RawEditor {
                         self.histogram_enabled
                     )
                     .on_toggle(Message::HistogramToggled);
+                    let clipping_toggle = iced::widget::checkbox(
+                        "Show Clipping (J)",
+                        self.show_clipping
+                    )
+                    .on_toggle(|_| Message::ToggleClipping);
                     
                     // Build histogram widget only if enabled
                     let histogram_section = if self.histogram_enabled {
                         let histogram_widget = iced::widget::canvas::Canvas::new(
                             crate::ui::histogram::Histogram {
-                                data: self.histogram_data.borrow().clone(),
+                                data: if self.histogram_mode == ui::histogram::HistogramMode::Raw {
+                                    pipeline.raw_histogram()
+                                } else {
+                                    *self.histogram_data.borrow()
+                                },
+                                mode: self.histogram_mode,
+                                log_scale: self.histogram_log_scale,
                             }
                         )
                         .width(iced::Length::Fill)
                         .height(iced::Length::Fixed(120.0));
                         
-                        Some(container(histogram_widget)
+                        let histogram_options = row![
+                            iced::widget::pick_list(
+                                ui::histogram::HistogramMode::ALL,
+                                Some(self.histogram_mode),
+                                Message::HistogramModeChanged,
+                            )
+                            .text_size(12),
+                            iced::widget::checkbox("Log", self.histogram_log_scale)
+                                .on_toggle(Message::HistogramLogScaleToggled)
+                                .size(14)
+                                .text_size(12),
+                        ]
+                        .spacing(8)
+                        .align_y(Alignment::Center);
+                        
+                        Some(container(column![histogram_options, histogram_widget].spacing(4))
                             .padding(5)
                             .style(|_theme| {
                                 iced::widget::container::Style {
@@ -1612,54 +6435,229 @@ impl RawEditor {
                     let mut sidebar = column![
                         text("Edit Controls").size(16),
                         histogram_toggle,
+                        clipping_toggle,
                     ];
                     
                     if let Some(hist) = histogram_section {
                         sidebar = sidebar.push(hist);
                     }
                     
+                    // Color under the cursor: 0-255 and 0-1, optionally the camera values
+                    let readout = match self.pixel_sample {
+                        Some(PixelSample { rgb: [r, g, b], camera }) => {
+                            let swatch = container(iced::widget::Space::new(14.0, 14.0)).style(move |_theme| container::Style {
+                                background: Some(Background::Color(Color::from_rgb8(r, g, b))),
+                                border: iced::Border { color: Color::from_rgb(0.5, 0.5, 0.5), width: 1.0, radius: 2.0.into() },
+                                ..Default::default()
+                            });
+                            let values = column![
+                                text(format!("RGB {} / {} / {}", r, g, b)).size(12),
+                                text(format!("{:.3} / {:.3} / {:.3}", r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)).size(12),
+                            ]
+                            .push_maybe(camera.map(|[cr, cg, cb]| {
+                                text(format!("Camera {:.3} / {:.3} / {:.3}", cr, cg, cb)).size(12)
+                            }));
+                            row![swatch, values].spacing(8).align_y(Alignment::Center)
+                        }
+                        None => row![text("RGB – (hover the image)").size(12)],
+                    };
+                    sidebar = sidebar
+                        .push(readout)
+                        .push(iced::widget::checkbox("Sensor values", self.show_sensor_values)
+                            .on_toggle(Message::SensorReadoutToggled)
+                            .size(14)
+                            .text_size(12))
+                        .push(self.view_edit_summary());
+                    
+                    // Double-clicking a slider's label sets it back to this
+                    let defaults = state::edit::EditParams::default();
                     let sidebar = sidebar
-                        // Exposure
-                        .push(text(format!("Exposure: {:.2}", self.current_edit_params.exposure)))
+                        // Exposure (Auto sets exposure, whites and blacks from the histogram)
+                        .push(row![
+                            slider_label(format!("Exposure: {:.2}", self.current_edit_params.exposure), Message::ExposureChanged(defaults.exposure)),
+                            iced::widget::horizontal_space(),
+                            button(text("Auto").size(12))
+                                .on_press(Message::AutoTone)
+                                .padding([2, 8])
+                                .style(button::secondary),
+                        ].align_y(Alignment::Center))
                         .push(slider(-5.0..=5.0, self.current_edit_params.exposure, Message::ExposureChanged)
                             .step(0.1))
                         // Highlights
-                        .push(text(format!("Highlights: {:.0}", self.current_edit_params.highlights * 100.0)))
-                        .push(slider(-1.0..=1.0, self.current_edit_params.highlights, Message::HighlightsChanged)
+                        .push(slider_label(format!("Highlights: {:.0}", self.current_edit_params.highlights), Message::HighlightsChanged(defaults.highlights)))
+                        .push(slider(-100.0..=100.0, self.current_edit_params.highlights, Message::HighlightsChanged)
+                            .step(1.0))
+                        // Highlight recovery (rebuilds clipped channels)
+                        .push(slider_label(format!("Highlight Recovery: {:.0}", self.current_edit_params.highlight_recovery * 100.0), Message::HighlightRecoveryChanged(defaults.highlight_recovery)))
+                        .push(slider(0.0..=1.0, self.current_edit_params.highlight_recovery, Message::HighlightRecoveryChanged)
                             .step(0.01))
                         // Shadows
-                        .push(text(format!("Shadows: {:.0}", self.current_edit_params.shadows * 100.0)))
-                        .push(slider(-1.0..=1.0, self.current_edit_params.shadows, Message::ShadowsChanged)
-                            .step(0.01))
+                        .push(slider_label(format!("Shadows: {:.0}", self.current_edit_params.shadows), Message::ShadowsChanged(defaults.shadows)))
+                        .push(slider(-100.0..=100.0, self.current_edit_params.shadows, Message::ShadowsChanged)
+                            .step(1.0))
                         // Contrast
-                        .push(text(format!("Contrast: {:.2}", self.current_edit_params.contrast)))
-                        .push(slider(-10.0..=10.0, self.current_edit_params.contrast, Message::ContrastChanged)
-                            .step(0.005))
-                        // Vibrance (Phase 27: Smart saturation protecting skin tones)
-                        .push(text(format!("Vibrance: {:.0}", self.current_edit_params.vibrance * 100.0)))
-                        .push(slider(-1.0..=1.0, self.current_edit_params.vibrance, Message::VibranceChanged)
+                        .push(row![
+                            slider_label(format!("Contrast: {:.0}", self.current_edit_params.contrast), Message::ContrastChanged(defaults.contrast)),
+                            iced::widget::horizontal_space(),
+                            iced::widget::pick_list(
+                                state::edit::ToneMode::ALL,
+                                Some(self.current_edit_params.tone_mode),
+                                Message::ToneModeChanged,
+                            ).text_size(12),
+                        ].spacing(5).align_y(Alignment::Center))
+                        .push(slider(-100.0..=100.0, self.current_edit_params.contrast, Message::ContrastChanged)
+                            .step(1.0))
+                        // Clarity (midtone local contrast)
+                        .push(slider_label(format!("Clarity: {:.0}", self.current_edit_params.clarity * 100.0), Message::ClarityChanged(defaults.clarity)))
+                        .push(slider(-1.0..=1.0, self.current_edit_params.clarity, Message::ClarityChanged)
                             .step(0.01))
-                        // Saturation
-                        .push(text(format!("Saturation: {:.0}", self.current_edit_params.saturation)))
-                        .push(slider(-100.0..=100.0, self.current_edit_params.saturation, Message::SaturationChanged))
+                        // Color: vibrance & saturation, or the B&W channel mixer
+                        .push(self.view_color_controls())
+                        // Film negative inversion
+                        .push(self.view_film_controls())
                         // Temperature
-                        .push(text(format!("Temperature: {:.0}", self.current_edit_params.temperature * 100.0)))
+                        .push(slider_label(format!("Temperature: {:.0}", self.current_edit_params.temperature * 100.0), Message::TemperatureChanged(defaults.temperature)))
                         .push(slider(-1.0..=1.0, self.current_edit_params.temperature, Message::TemperatureChanged)
                             .step(0.01))
                         // Tint
-                        .push(text(format!("Tint: {:.0}", self.current_edit_params.tint * 100.0)))
+                        .push(slider_label(format!("Tint: {:.0}", self.current_edit_params.tint * 100.0), Message::TintChanged(defaults.tint)))
                         .push(slider(-1.0..=1.0, self.current_edit_params.tint, Message::TintChanged)
                             .step(0.01))
                         // Whites
-                        .push(text(format!("Whites: {:.2}", self.current_edit_params.whites)))
-                        .push(slider(0.8..=1.2, self.current_edit_params.whites, Message::WhitesChanged)
-                            .step(0.01))
+                        .push(slider_label(format!("Whites: {:.0}", self.current_edit_params.whites), Message::WhitesChanged(defaults.whites)))
+                        .push(slider(-100.0..=100.0, self.current_edit_params.whites, Message::WhitesChanged)
+                            .step(1.0))
                         // Blacks
-                        .push(text(format!("Blacks: {:.3}", self.current_edit_params.blacks)))
-                        .push(slider(0.0..=0.2, self.current_edit_params.blacks, Message::BlacksChanged)
-                            .step(0.005))
+                        .push(slider_label(format!("Blacks: {:.0}", self.current_edit_params.blacks), Message::BlacksChanged(defaults.blacks)))
+                        .push(slider(-100.0..=100.0, self.current_edit_params.blacks, Message::BlacksChanged)
+                            .step(1.0))
+                        // Sharpening
+                        .push(slider_label(format!("Sharpening: {:.0}", self.current_edit_params.sharpen_amount * 100.0), Message::SharpenAmountChanged(defaults.sharpen_amount)))
+                        .push(slider(0.0..=1.5, self.current_edit_params.sharpen_amount, Message::SharpenAmountChanged)
+                            .step(0.01))
+                        .push(slider_label(format!("Radius: {:.1}", self.current_edit_params.sharpen_radius), Message::SharpenRadiusChanged(defaults.sharpen_radius)))
+                        .push(slider(0.5..=3.0, self.current_edit_params.sharpen_radius, Message::SharpenRadiusChanged)
+                            .step(0.1))
+                        .push(iced::widget::checkbox("Show sharpening detail", self.detail_preview)
+                            .on_toggle(Message::ToggleDetailPreview))
+                        // Noise Reduction
+                        .push(slider_label(format!("Luminance NR: {:.0}", self.current_edit_params.luma_nr * 100.0), Message::LumaNrChanged(defaults.luma_nr)))
+                        .push(slider(0.0..=1.0, self.current_edit_params.luma_nr, Message::LumaNrChanged)
+                            .step(0.01))
+                        .push(slider_label(format!("Color NR: {:.0}", self.current_edit_params.chroma_nr * 100.0), Message::ChromaNrChanged(defaults.chroma_nr)))
+                        .push(slider(0.0..=1.0, self.current_edit_params.chroma_nr, Message::ChromaNrChanged)
+                            .step(0.01))
+                        .push(button(text(format!("Suggest for {}", self.selected_metadata.iso_display())))
+                            .on_press_maybe(self.selected_metadata.iso.map(|_| Message::SuggestNoiseReduction))
+                            .style(button::secondary))
+                        // Vignette (post-crop: centered on the cropped frame)
+                        .push(slider_label(format!("Vignette: {:.0}", self.current_edit_params.vignette_amount * 100.0), Message::VignetteAmountChanged(defaults.vignette_amount)))
+                        .push(slider(-1.0..=1.0, self.current_edit_params.vignette_amount, Message::VignetteAmountChanged)
+                            .step(0.01))
+                        .push(slider_label(format!("Vignette Midpoint: {:.0}", self.current_edit_params.vignette_midpoint * 100.0), Message::VignetteMidpointChanged(defaults.vignette_midpoint)))
+                        .push(slider(0.0..=1.0, self.current_edit_params.vignette_midpoint, Message::VignetteMidpointChanged)
+                            .step(0.01))
+                        // Tone Curve
+                        .push(text("Tone Curve"))
+                        .push(container(
+                            canvas::Canvas::new(crate::ui::curve::CurveEditor {
+                                curve: self.current_edit_params.curve,
+                            })
+                            .width(Length::Fill)
+                            .height(Length::Fixed(170.0))
+                        )
+                        .style(|_theme| {
+                            container::Style {
+                                background: Some(Background::Color(Color::from_rgb(0.1, 0.1, 0.1))),
+                                border: Border {
+                                    color: Color::from_rgb(0.3, 0.3, 0.3),
+                                    width: 1.0,
+                                    radius: 4.0.into(),
+                                },
+                                ..Default::default()
+                            }
+                        }))
+                        .push(button("Reset Curve")
+                            .on_press(Message::CurveChanged(state::edit::ToneCurve::default())))
+                        // HSL (collapsible: 24 sliders; nothing to adjust in B&W)
+                        .push_maybe((!self.current_edit_params.monochrome).then(|| self.view_hsl_panel()))
+                        // Split toning (works on B&W too: that's the classic use)
+                        .push(self.view_split_tone_controls())
+                        // Local adjustments (radial / linear gradient masks)
+                        .push(self.view_mask_controls())
+                        // Crop & Straighten
+                        .push(row![
+                            text("Crop"),
+                            iced::widget::Space::with_width(Length::Fill),
+                            button(if self.crop_tool_active { "Done" } else { "✂️ Crop" })
+                                .on_press(Message::ToggleCropTool)
+                                .style(if self.crop_tool_active { button::primary } else { button::secondary }),
+                        ].align_y(Alignment::Center))
+                        .push(iced::widget::Row::with_children(
+                            state::edit::CropAspect::ALL.iter().map(|&aspect| {
+                                button(text(aspect.to_string()).size(12))
+                                    .on_press(Message::CropAspectChanged(aspect))
+                                    .style(if self.crop_aspect == aspect { button::primary } else { button::secondary })
+                                    .into()
+                            })
+                        ).spacing(4))
+                        .push(row![
+                            iced::widget::checkbox("Lock ratio (Shift)", self.crop_lock_ratio)
+                                .on_toggle(Message::CropLockRatioToggled)
+                                .text_size(12),
+                            iced::widget::pick_list(
+                                ui::crop::CropGuide::ALL,
+                                Some(self.crop_guide),
+                                Message::CropGuideChanged,
+                            )
+                            .text_size(12),
+                        ].spacing(8).align_y(Alignment::Center))
+                        .push(row![
+                            slider_label(format!("Straighten: {:.1}°", self.current_edit_params.rotation_degrees), Message::RotationChanged(defaults.rotation_degrees)),
+                            iced::widget::Space::with_width(Length::Fill),
+                            button(text("📐 Level").size(12))
+                                .on_press(Message::ToggleStraighten)
+                                .style(if self.straighten_active { button::primary } else { button::secondary }),
+                        ].align_y(Alignment::Center))
+                        .push(slider(
+                            -state::edit::MAX_ROTATION_DEGREES..=state::edit::MAX_ROTATION_DEGREES,
+                            self.current_edit_params.rotation_degrees,
+                            Message::RotationChanged,
+                        )
+                            .step(0.1))
+                        .push(button("Reset Crop").on_press(Message::ResetCrop))
+                        // Lens correction (before crop)
+                        .push(self.view_lens_controls())
+                        // Detail
+                        .push(text("Demosaic"))
+                        .push(iced::widget::pick_list(
+                            state::edit::DemosaicMethod::ALL,
+                            Some(self.current_edit_params.demosaic),
+                            Message::DemosaicChanged,
+                        ).width(Length::Fill))
+                        .push(row![
+                            button("Undo").on_press_maybe(self.edit_history.can_undo().then_some(Message::Undo)),
+                            button("Redo").on_press_maybe(self.edit_history.can_redo().then_some(Message::Redo)),
+                        ].spacing(5))
+                        .push(text("Hold Alt to see the last adjusted slider on its own").size(11))
+                        .push(row![
+                            button("Copy").on_press(Message::CopySettings),
+                            button("Paste").on_press_maybe(self.clipboard_params.map(|_| Message::PasteSettings)),
+                        ].spacing(5))
+                        .push(self.view_preset_controls())
+                        .push(self.view_snapshot_controls())
+                        .push(self.view_keyword_controls())
                         .push(button("Reset All").on_press(Message::ResetEdits))
-                        .push(button("Export").on_press(Message::ExportImage))
+                        .push(iced::widget::checkbox("Write XMP sidecars", self.write_sidecars)
+                            .on_toggle(Message::SidecarWritingToggled))
+                        // Export (quality applies to JPEG, compression to PNG)
+                        .push(text(format!("JPEG Quality: {}", self.export_options.jpeg_quality)))
+                        .push(self.view_jpeg_quality_slider())
+                        .push(row![
+                            text("PNG:"),
+                            self.view_png_compression_picker(),
+                        ].spacing(5).align_y(Alignment::Center))
+                        .push(self.view_export_controls())
                     .spacing(10)
                     .padding(15)
 
@@ -1735,67 +6733,447 @@ impl RawEditor {
 
     /// Set the application theme
     fn theme(&self) -> Theme {
-        Theme::Dark
+        let base = match self.ui_theme {
+            state::settings::UiTheme::Dark => Theme::Dark,
+            state::settings::UiTheme::Light => Theme::Light,
+            // iced's default follows the OS setting
+            state::settings::UiTheme::System => Theme::default(),
+        };
+        match self.accent_color.rgb() {
+            Some([r, g, b]) => Theme::custom(
+                format!("{} ({})", base, self.accent_color),
+                iced::theme::Palette { primary: Color::from_rgb(r, g, b), ..base.palette() },
+            ),
+            None => base,
+        }
+    }
+}
+
+/// Slider label that resets the slider on double-click (`reset` sets the
+/// parameter back to its default)
+fn slider_label<'a>(label: String, reset: Message) -> Element<'a, Message> {
+    iced::widget::mouse_area(text(label))
+        .on_press(Message::SliderLabelPressed(Box::new(reset)))
+        .interaction(iced::mouse::Interaction::Pointer)
+        .into()
+}
+
+/// Scaling for the preview image: nearest for native-resolution renders so
+/// zoomed-in pixels stay sharp squares, smooth otherwise
+fn preview_filter(native: bool) -> iced::widget::image::FilterMethod {
+    if native {
+        iced::widget::image::FilterMethod::Nearest
+    } else {
+        iced::widget::image::FilterMethod::Linear
     }
 }
 
+/// Add up the cache folders in the background (CacheSizeMeasured)
+fn measure_cache() -> Task<Message> {
+    Task::perform(
+        async {
+            let root = state::settings::cache_root();
+            tokio::task::spawn_blocking(move || state::settings::cache_size(&root))
+                .await
+                .unwrap_or(0)
+        },
+        Message::CacheSizeMeasured,
+    )
+}
+
+/// Byte count for people: "820.4 MB", "3.25 GB"
+fn format_size(bytes: u64) -> String {
+    const MB: f64 = 1024.0 * 1024.0;
+    const GB: f64 = MB * 1024.0;
+    if bytes as f64 >= GB {
+        format!("{:.2} GB", bytes as f64 / GB)
+    } else {
+        format!("{:.1} MB", bytes as f64 / MB)
+    }
+}
+
+/// Read the window geometry back, then save it and close (WindowClosing)
+fn close_window(id: window::Id) -> Task<Message> {
+    window::get_maximized(id).then(move |maximized| {
+        window::get_size(id).then(move |size| {
+            window::get_position(id).map(move |position| Message::WindowClosing {
+                id,
+                maximized,
+                size,
+                position,
+            })
+        })
+    })
+}
+
 /// Phase 19: Async export function that renders full resolution and saves to disk
 /// This runs in a background thread to avoid freezing the UI
-async fn export_image_async(
+/// The image open in Develop, exported with its current edits
+struct ExportJob {
     pipeline: Arc<gpu::RenderPipeline>,
-    save_path: std::path::PathBuf,
-) -> Result<std::path::PathBuf, String> {
-    // Run the heavy rendering work in a blocking task
-    tokio::task::spawn_blocking(move || {
-        println!("🖼️  Starting full-resolution export...");
-        
-        // Render at FULL resolution (24MP for 6016x4016 image)
-        // This will take 1-2 seconds - that's why we're async!
-        let rgba_bytes = pipeline.render_full_res_to_bytes();
-        println!("✅ Rendered {} bytes at full resolution", rgba_bytes.len());
-        
-        // Determine format from file extension
-        let extension = save_path
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("jpg")
-            .to_lowercase();
-        
-        // Save using image crate
-        let result = match extension.as_str() {
-            "png" => {
-                image::save_buffer(
-                    &save_path,
-                    &rgba_bytes,
-                    pipeline.width,
-                    pipeline.height,
-                    image::ColorType::Rgba8,
-                )
-            }
-            _ => {
-                // Default to JPEG
-                // Convert RGBA to RGB (JPEG doesn't support alpha)
-                let rgb_bytes: Vec<u8> = rgba_bytes
-                    .chunks_exact(4)
-                    .flat_map(|rgba| [rgba[0], rgba[1], rgba[2]])
-                    .collect();
-                
-                image::save_buffer(
-                    &save_path,
-                    &rgb_bytes,
-                    pipeline.width,
-                    pipeline.height,
-                    image::ColorType::Rgb8,
-                )
+    params: state::edit::EditParams,
+    raw_path: PathBuf,
+    save_path: PathBuf,
+    options: state::export::ExportOptions,
+    /// Copy the RAW's EXIF into the output
+    include_metadata: bool,
+    metadata: state::exif::ExportMetadata,
+}
+
+/// Error of an export stopped with Cancel
+const EXPORT_CANCELLED: &str = "Export cancelled";
+
+/// Export the image open in Develop, off the main thread
+/// Emits ExportProgress as tiles finish, then ExportComplete. `cancel` stops
+/// the render between tiles; a cancelled export leaves no file behind.
+fn export_image_stream(job: ExportJob, cancel: CancelToken) -> impl iced::futures::Stream<Item = Message> {
+    iced::stream::channel(1, move |mut output| async move {
+        use iced::futures::SinkExt;
+        
+        let path = job.save_path.clone();
+        let mut progress_output = output.clone();
+        // Run the heavy rendering work in a blocking task
+        let result = tokio::task::spawn_blocking(move || {
+            let ExportJob { pipeline, params, raw_path, save_path, options, include_metadata, metadata } = job;
+            let fields = export_metadata_fields(&raw_path, include_metadata, &metadata);
+            // Progress is best effort: a full channel just skips an update
+            let mut progress = |done: f32| {
+                let _ = progress_output.try_send(Message::ExportProgress(done));
+                !cancel.is_cancelled()
+            };
+            let result = render_and_save(&pipeline, &params, &save_path, options, &fields, Some(&mut progress));
+            
+            // Cancelled while the file was being encoded: it's complete but unwanted
+            if result.is_ok() && cancel.is_cancelled() {
+                if let Err(e) = std::fs::remove_file(&save_path) {
+                    eprintln!("⚠️  Couldn't remove cancelled export {}: {}", save_path.display(), e);
+                }
+                return Err(EXPORT_CANCELLED.to_string());
             }
+            result
+        })
+        .await
+        .map_err(|e| format!("Export task failed: {}", e))
+        .and_then(|result| result);
+        
+        let _ = output.send(Message::ExportComplete(path, result)).await;
+    })
+}
+
+/// EXIF to write into an export of `raw_path` (none when copying is off)
+/// Reads the RAW: call from `spawn_blocking`.
+fn export_metadata_fields(
+    raw_path: &std::path::Path,
+    include_metadata: bool,
+    metadata: &state::exif::ExportMetadata,
+) -> Vec<exif::Field> {
+    if include_metadata {
+        state::exif::export_fields(raw_path, metadata)
+    } else {
+        Vec::new()
+    }
+}
+
+/// Render `params` at full resolution and save to `save_path` with the given EXIF
+/// The format follows the file extension (JPEG by default, PNG, 16-bit TIFF).
+/// `progress` follows the render tile by tile and can stop it (Err
+/// `EXPORT_CANCELLED`, nothing written). Blocking: call from `spawn_blocking`.
+fn render_and_save(
+    pipeline: &gpu::RenderPipeline,
+    params: &state::edit::EditParams,
+    save_path: &std::path::Path,
+    options: state::export::ExportOptions,
+    metadata: &[exif::Field],
+    progress: Option<gpu::pipeline::RenderProgress>,
+) -> Result<(), String> {
+    println!("🖼️  Starting full-resolution export...");
+    
+    // Export the saved edits with the crop applied, not whatever the
+    // preview shows (zoomed in, or uncropped in crop mode)
+    let (width, height) = pipeline.export_size(params);
+    
+    // Determine format from file extension
+    let extension = save_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("jpg")
+        .to_lowercase();
+    
+    // 16-bit TIFF for print work: render through the Rgba16Uint pipeline
+    if extension == "tif" || extension == "tiff" {
+        let rgba_16 = match progress {
+            Some(progress) => pipeline
+                .render_full_res_to_bytes_16_with_progress(params, progress)
+                .ok_or_else(|| EXPORT_CANCELLED.to_string())?,
+            None => pipeline.render_full_res_to_bytes_16(params),
         };
+        println!("✅ Rendered {} u16 samples at full resolution (16-bit)", rgba_16.len());
+        
+        // Drop alpha - write 16-bit RGB
+        let rgb_16: Vec<u16> = rgba_16
+            .chunks_exact(4)
+            .flat_map(|rgba| [rgba[0], rgba[1], rgba[2]])
+            .collect();
+        
+        return state::export::save_tiff_16(save_path, &rgb_16, width, height, metadata);
+    }
+    
+    // Render at FULL resolution (24MP for 6016x4016 image)
+    // This will take 1-2 seconds - that's why we're async!
+    let rgba_bytes = match progress {
+        Some(progress) => pipeline
+            .render_full_res_to_bytes_with_progress(params, progress)
+            .ok_or_else(|| EXPORT_CANCELLED.to_string())?,
+        None => pipeline.render_full_res_to_bytes(params),
+    };
+    println!("✅ Rendered {} bytes at full resolution", rgba_bytes.len());
+    
+    // Save with the chosen encoder settings
+    match extension.as_str() {
+        "png" => {
+            let recipe = if options.embed_recipe {
+                Some(params.to_json().map_err(|e| format!("Failed to serialize the edit settings: {}", e))?)
+            } else {
+                None
+            };
+            state::export::save_png(save_path, &rgba_bytes, width, height, options.png_compression, metadata, recipe.as_deref())
+        }
+        _ => {
+            // Default to JPEG
+            // Convert RGBA to RGB (JPEG doesn't support alpha)
+            let rgb_bytes: Vec<u8> = rgba_bytes
+                .chunks_exact(4)
+                .flat_map(|rgba| [rgba[0], rgba[1], rgba[2]])
+                .collect();
+            
+            state::export::save_jpeg(save_path, &rgb_bytes, width, height, options.jpeg_quality, metadata)
+        }
+    }
+}
+
+/// Shared stop flag for background tasks: set once when the app quits, checked
+/// by imports, the thumbnail worker and batch exports between items
+#[derive(Debug, Clone, Default)]
+struct CancelToken {
+    cancelled: Arc<std::sync::atomic::AtomicBool>,
+    /// Flag of the token this one was made from (see `child`)
+    parent: Option<Arc<std::sync::atomic::AtomicBool>>,
+}
+
+impl CancelToken {
+    /// A token that can be cancelled on its own, and is along with this one
+    fn child(&self) -> CancelToken {
+        CancelToken {
+            cancelled: Arc::default(),
+            parent: Some(self.cancelled.clone()),
+        }
+    }
+    
+    fn cancel(&self) {
+        self.cancelled.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+    
+    fn is_cancelled(&self) -> bool {
+        let ordering = std::sync::atomic::Ordering::Relaxed;
+        self.cancelled.load(ordering) || self.parent.as_ref().is_some_and(|parent| parent.load(ordering))
+    }
+}
+
+/// One image of a batch export: where the RAW is, how to name the output,
+/// and the saved edits to apply
+struct BatchExportJob {
+    raw_path: String,
+    output_path: PathBuf,
+    orientation: state::orientation::Orientation,
+    lens_profile: Option<state::lens::LensProfile>,
+    params: state::edit::EditParams,
+    options: state::export::ExportOptions,
+    /// Copy the RAW's EXIF into the output
+    include_metadata: bool,
+    metadata: state::exif::ExportMetadata,
+}
+
+/// Export a batch of images one after another, off the main thread
+/// Each image gets its own RAW load and GPU pipeline (dropped before the next
+/// one, so only one full-resolution image is in memory at a time). A failure
+/// is logged and skipped - it never aborts the rest of the batch.
+/// Emits BatchExportProgress before every image and once more at the end;
+/// `cancel` stops it before the next image.
+fn batch_export_stream(jobs: Vec<BatchExportJob>, cancel: CancelToken) -> impl iced::futures::Stream<Item = Message> {
+    iced::stream::channel(1, move |mut output| async move {
+        use iced::futures::SinkExt;
+        
+        let total = jobs.len();
+        let mut failed = 0;
+        
+        // Every job renders on the app's shared device (or the CPU without one)
+        let gpu = gpu::GpuContext::shared_or_cpu().await;
+        
+        let mut done = 0;
+        for job in jobs {
+            if cancel.is_cancelled() {
+                println!("🛑 Batch export stopped after {} of {} images", done, total);
+                break;
+            }
+            let current = Some(job.output_path.clone());
+            let _ = output.send(Message::BatchExportProgress { done, total, current }).await;
+            
+            let result = match &gpu {
+                Ok(gpu) => export_batch_job(gpu.clone(), job).await,
+                Err(err) => Err(format!("{}: {}", job.raw_path, err)),
+            };
+            if let Err(err) = result {
+                eprintln!("❌ Batch export: {}", err);
+                failed += 1;
+            }
+            done += 1;
+        }
+        
+        let _ = output.send(Message::BatchExportProgress { done, total, current: None }).await;
+        println!("✅ Batch export complete: {} exported, {} failed", done - failed, failed);
+    })
+}
+
+/// Everything needed to render one edited image's thumbnail off the main thread
+struct ThumbnailJob {
+    image_id: i64,
+    raw_path: String,
+    orientation: state::orientation::Orientation,
+    lens_profile: Option<state::lens::LensProfile>,
+    params: state::edit::EditParams,
+}
+
+/// Preview width rendered for an edited thumbnail (twice the thumbnail tier,
+/// scaled down when saved)
+const EDITED_THUMBNAIL_RENDER_WIDTH: u32 = 512;
+
+/// Render edited images' thumbnails one after another, off the main thread
+/// Emits EditedThumbnailRendered for each one that worked; a failure is
+/// logged and that image keeps its current thumbnail.
+fn edited_thumbnail_stream(jobs: Vec<ThumbnailJob>, cancel: CancelToken) -> impl iced::futures::Stream<Item = Message> {
+    iced::stream::channel(1, move |mut output| async move {
+        use iced::futures::SinkExt;
         
-        result
-            .map(|_| save_path.clone())
-            .map_err(|e| format!("Failed to save image: {}", e))
+        let gpu = match gpu::GpuContext::shared_or_cpu().await {
+            Ok(gpu) => gpu,
+            Err(err) => {
+                eprintln!("⚠️  Edited thumbnails skipped: {}", err);
+                return;
+            }
+        };
+        for job in jobs {
+            if cancel.is_cancelled() {
+                break;
+            }
+            let image_id = job.image_id;
+            match render_edited_thumbnail(gpu.clone(), job).await {
+                Ok(path) => {
+                    let _ = output.send(Message::EditedThumbnailRendered(image_id, path)).await;
+                }
+                Err(err) => eprintln!("⚠️  Edited thumbnail for image {} failed: {}", image_id, err),
+            }
+        }
+    })
+}
+
+/// Load the RAW, render it with its edits at a small preview size and save
+/// that as the image's thumbnail
+async fn render_edited_thumbnail(gpu: Option<Arc<gpu::GpuContext>>, job: ThumbnailJob) -> Result<PathBuf, String> {
+    let raw_data = raw::loader::load_raw_data(job.raw_path.clone())
+        .await
+        .map_err(|e| format!("{}: {}", job.raw_path, e))?;
+    
+    let cam_to_srgb = camera_to_srgb(raw_data.color_matrix, raw_data.forward_matrix);
+    let pipeline = gpu::RenderPipeline::new(
+        gpu,
+        0, // Not tied to the Develop view
+        raw_data.data,
+        raw_data.width,
+        raw_data.height,
+        &job.params,
+        raw_data.wb_multipliers,
+        cam_to_srgb,
+        raw_data.cfa_pattern,
+        raw_data.levels,
+        EDITED_THUMBNAIL_RENDER_WIDTH,
+    )
+    .await
+    .map_err(|e| format!("{}: {}", job.raw_path, e))?;
+    pipeline.set_orientation(job.orientation);
+    pipeline.set_lens_profile(job.lens_profile);
+    pipeline.update_uniforms_for_export(&job.params);
+    
+    let image_id = job.image_id;
+    tokio::task::spawn_blocking(move || {
+        let rgba = pipeline.render_to_bytes();
+        let (width, height) = pipeline.preview_size();
+        raw::thumbnail::save_rendered_thumbnail(rgba, width, height, image_id)
+    })
+    .await
+    .map_err(|e| format!("thumbnail task failed: {}", e))?
+}
+
+/// Open the OS file manager with `path` selected (Explorer, Finder); on
+/// Linux there's no standard way to select a file, so its folder is opened
+///
+/// Blocks until the launcher exits, so the child doesn't linger as a zombie.
+/// Its exit code isn't checked: Explorer reports 1 even when it worked.
+fn reveal_in_file_manager(path: &std::path::Path) -> Result<(), String> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = std::process::Command::new("explorer");
+        command.arg("/select,").arg(path);
+        command
+    } else if cfg!(target_os = "macos") {
+        let mut command = std::process::Command::new("open");
+        command.arg("-R").arg(path);
+        command
+    } else {
+        let mut command = std::process::Command::new("xdg-open");
+        command.arg(path.parent().unwrap_or(path));
+        command
+    };
+    command
+        .status()
+        .map(|_| ())
+        .map_err(|e| format!("Couldn't open the file manager for {}: {}", path.display(), e))
+}
+
+/// Load, render and save a single batch export job
+async fn export_batch_job(gpu: Option<Arc<gpu::GpuContext>>, job: BatchExportJob) -> Result<(), String> {
+    let raw_data = raw::loader::load_raw_data(job.raw_path.clone())
+        .await
+        .map_err(|e| format!("{}: {}", job.raw_path, e))?;
+    
+    let cam_to_srgb = camera_to_srgb(raw_data.color_matrix, raw_data.forward_matrix);
+    let pipeline = gpu::RenderPipeline::new(
+        gpu,
+        0, // Not tied to the Develop view
+        raw_data.data,
+        raw_data.width,
+        raw_data.height,
+        &job.params,
+        raw_data.wb_multipliers,
+        cam_to_srgb,
+        raw_data.cfa_pattern,
+        raw_data.levels,
+        state::settings::PreviewQuality::default().max_width(), // Only full-res renders are used
+    )
+    .await
+    .map_err(|e| format!("{}: {}", job.raw_path, e))?;
+    pipeline.set_orientation(job.orientation);
+    pipeline.set_lens_profile(job.lens_profile);
+    
+    let BatchExportJob { raw_path, output_path, params, options, include_metadata, metadata, .. } = job;
+    let source = PathBuf::from(&raw_path);
+    tokio::task::spawn_blocking(move || {
+        let fields = export_metadata_fields(&source, include_metadata, &metadata);
+        render_and_save(&pipeline, &params, &output_path, options, &fields, None)?;
+        println!("📤 Exported {}", output_path.display());
+        Ok(())
     })
     .await
-    .map_err(|e| format!("Export task failed: {}", e))?
+    .map_err(|e| format!("{}: export task failed: {}", raw_path, e))?
+    .map_err(|e: String| format!("{}: {}", raw_path, e))
 }
 
 /// Phase 23: Application entry point
@@ -1815,6 +7193,12 @@ async fn export_image_async(
 /// Note: You'll need to manually add decorations back after loading,
 /// or keep the app borderless throughout (like some Adobe products)
 fn main() -> iced::Result {
+    let window_state = state::library::Library::peek_setting(state::settings::WINDOW_KEY)
+        .and_then(|value| state::settings::WindowState::from_setting(&value))
+        .unwrap_or_default();
+    let _ = SAVED_WINDOW.set(window_state);
+    let (min_width, min_height) = state::settings::WindowState::MIN_SIZE;
+    
     iced::application(
         "RAW Editor",
         RawEditor::update,
@@ -1826,152 +7210,399 @@ fn main() -> iced::Result {
     // Note: iced::application() uses a single window throughout
     // To have a separate splash window, you'd need the multi-window API
     .window(iced::window::Settings {
-        size: iced::Size::new(900.0, 400.0),  // Main app size
-        min_size: Some(iced::Size::new(600.0, 400.0)),
+        size: iced::Size::new(window_state.width, window_state.height),  // Last session's size
+        position: match window_state.position {
+            Some(_) => window::Position::SpecificWith(restored_window_position),
+            None => window::Position::Centered,
+        },
+        min_size: Some(iced::Size::new(min_width, min_height)),
         decorations: true,  // Keep title bar for usability
+        exit_on_close_request: false,  // WindowCloseRequested saves the layout, then closes
         ..Default::default()
     })
-    .centered()
-    .run_with(RawEditor::new)
+    .run_with(move || RawEditor::new(window_state))
+}
+
+/// Window layout from the last session, read before the catalog loads
+static SAVED_WINDOW: std::sync::OnceLock<state::settings::WindowState> = std::sync::OnceLock::new();
+
+/// Place the window at the saved position unless it would be off the
+/// (primary) monitor - iced passes a plain function, hence the static
+fn restored_window_position(_window: iced::Size, monitor: iced::Size) -> Point {
+    let (x, y) = SAVED_WINDOW.get().copied().unwrap_or_default().placement((monitor.width, monitor.height));
+    Point::new(x, y)
+}
+
+/// Import progress is reported every this many files (a message per file would flood the UI)
+const IMPORT_PROGRESS_STEP: usize = 25;
+
+/// Stream an import: `ImportProgress` messages while it runs, then `ImportComplete`
+fn import_stream(
+    paths: Vec<PathBuf>,
+    db_path: PathBuf,
+    custom_extensions: Vec<String>,
+    cancel: CancelToken,
+) -> impl iced::futures::Stream<Item = Message> {
+    iced::stream::channel(1, move |mut output| async move {
+        use iced::futures::SinkExt;
+        
+        let result = import_paths_async(paths, db_path, custom_extensions, output.clone(), cancel).await;
+        let _ = output.send(Message::ImportComplete(result)).await;
+    })
+}
+
+/// Next (done, total) for a draining queue with `remaining` items left
+///
+/// The total is fixed when the queue starts (remaining + the batch just
+/// finished) so the bar fills up instead of shrinking; it grows if new work
+/// arrives mid-run. Returns None once the queue is empty.
+fn queue_progress(previous: Option<(usize, usize)>, remaining: usize, just_done: usize) -> Option<(usize, usize)> {
+    if remaining == 0 {
+        return None;
+    }
+    let total = previous
+        .map(|(_, total)| total)
+        .filter(|&total| total >= remaining)
+        .unwrap_or(remaining + just_done);
+    Some((total - remaining, total))
+}
+
+/// True if the path has an extension imported as RAW (see `raw::loader::is_raw_extension`)
+fn has_raw_extension(path: &std::path::Path, custom_extensions: &[String]) -> bool {
+    path.extension()
+        .map(|ext| raw::loader::is_raw_extension(&ext.to_string_lossy(), custom_extensions))
+        .unwrap_or(false)
 }
 
-/// Async function to import all RAW files from a folder
+/// Async function to import RAW files and folders (folders are walked recursively)
 /// Runs in a background thread to avoid blocking the UI
-async fn import_folder_async(folder_path: PathBuf, db_path: PathBuf) -> ImportResult {
+///
+/// A first pass finds every RAW file so progress can report a real total,
+/// and files already in the catalog are skipped without touching the
+/// database (re-importing a big tree is then a quick diff). New files are
+/// written a progress step at a time, one transaction each. `cancel` stops
+/// it between steps; what's imported by then stays imported.
+async fn import_paths_async(
+    paths: Vec<PathBuf>,
+    db_path: PathBuf,
+    custom_extensions: Vec<String>,
+    mut progress: iced::futures::channel::mpsc::Sender<Message>,
+    cancel: CancelToken,
+) -> ImportResult {
+    use iced::futures::SinkExt;
+    
     let mut imported_count = 0;
     let mut skipped_count = 0;
+    let batch_id = Utc::now().timestamp_millis();
     
     // Open a new database connection for this background thread
     // rusqlite::Connection is not Send, so we can't share the main connection
-    let conn = Connection::open(&db_path)
+    let mut conn = Connection::open(&db_path)
         .expect("Failed to open database connection for import");
+    // The UI and thumbnail worker write too: wait out their short locks
+    let _ = conn.busy_timeout(std::time::Duration::from_secs(2));
     
-    println!("🔍 Scanning folder: {}", folder_path.display());
+    // Walk each directory tree recursively (a file walks to just itself),
+    // keeping RAW files only
+    let mut raw_files: Vec<PathBuf> = Vec::new();
+    for path in &paths {
+        println!("🔍 Scanning: {}", path.display());
+        raw_files.extend(
+            WalkDir::new(path)
+                .follow_links(true)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|entry| entry.path().is_file() && has_raw_extension(entry.path(), &custom_extensions))
+                .map(|entry| entry.into_path()),
+        );
+    }
     
-    // Supported RAW file extensions (common formats)
-    let raw_extensions = [
-        "nef", "dng", "cr2", "cr3", "arw", "raf", "orf", "rw2", 
-        "pef", "srw", "erf", "kdc", "dcr", "mos", "raw", "rwl",
-    ];
+    // Leave out what rawloader can't decode (wrong container, e.g. CR3)
+    let found = raw_files.len();
+    raw_files.retain(|path| raw::loader::is_supported(path, &custom_extensions));
+    let unsupported_count = found - raw_files.len();
+    if unsupported_count > 0 {
+        println!("⚠️  {} files are in unsupported RAW formats", unsupported_count);
+    }
     
-    // Walk the directory tree recursively
-    for entry in WalkDir::new(&folder_path)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        let path = entry.path();
-        
-        // Only process files (not directories)
-        if !path.is_file() {
-            continue;
+    let total = raw_files.len();
+    println!("📂 Found {} RAW files", total);
+    
+    // Diff against what's cataloged under the chosen paths in one query each
+    let mut cataloged: HashSet<String> = HashSet::new();
+    for path in &paths {
+        match state::library::cataloged_paths_under(&conn, path) {
+            Ok(existing) => cataloged.extend(existing),
+            Err(e) => eprintln!("⚠️  Couldn't list cataloged files under {}: {}", path.display(), e),
+        }
+    }
+    raw_files.retain(|path| !cataloged.contains(path.to_string_lossy().as_ref()));
+    skipped_count += total - raw_files.len();
+    if skipped_count > 0 {
+        println!("⏭️  {} files are already in the catalog", skipped_count);
+    }
+    
+    let mut done = skipped_count;
+    let _ = progress.send(Message::ImportProgress { done, total }).await;
+    
+    for chunk in raw_files.chunks(IMPORT_PROGRESS_STEP) {
+        if cancel.is_cancelled() {
+            println!("🛑 Import stopped after {} of {} files", done, total);
+            break;
         }
         
-        // Check if this is a RAW file by extension
-        if let Some(extension) = path.extension() {
-            let ext = extension.to_string_lossy().to_lowercase();
-            if !raw_extensions.contains(&ext.as_str()) {
-                continue;
+        // Read the files first, then write the step in one short transaction
+        // (no disk reads while the catalog is locked)
+        let rows: Vec<ImportRow> = chunk.iter().map(|path| ImportRow::read(path)).collect();
+        match insert_import_rows(&mut conn, &rows, batch_id) {
+            Ok((imported, skipped)) => {
+                imported_count += imported;
+                skipped_count += skipped;
+                if imported_count / 100 > (imported_count - imported) / 100 {
+                    println!("⏳ Imported {} files...", imported_count);
+                }
             }
-        } else {
-            continue;
+            Err(e) => eprintln!("⚠️  Failed to save {} imported files: {}", rows.len(), e),
         }
         
-        // Extract path and filename
-        let path_str = path.to_string_lossy().to_string();
-        let filename = path.file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-        
-        // Try to insert into database
-        let result = conn.execute(
-            "INSERT INTO images (path, filename, imported_at) VALUES (?1, ?2, ?3)",
+        done += chunk.len();
+        let _ = progress.send(Message::ImportProgress { done, total }).await;
+    }
+    
+    println!("✅ Import complete: {} new, {} skipped", imported_count, skipped_count);
+    
+    ImportResult {
+        batch_id,
+        imported_count,
+        skipped_count,
+        unsupported_count,
+    }
+}
+
+/// One file's catalog row, read before the import opens its transaction
+struct ImportRow {
+    path: String,
+    filename: String,
+    metadata: state::exif::ImageMetadata,
+    avg_color: Option<u32>,
+    /// Edits from an existing .xmp sidecar (e.g. the catalog was lost)
+    sidecar_edits: Option<String>,
+}
+
+impl ImportRow {
+    fn read(path: &std::path::Path) -> Self {
+        ImportRow {
+            path: path.to_string_lossy().to_string(),
+            filename: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            // Camera/exposure info from the EXIF block (all None if missing)
+            metadata: state::exif::read_metadata(path),
+            // Grid placeholder color until the thumbnail is generated
+            avg_color: raw::thumbnail::placeholder_color(path).map(state::library::pack_color),
+            sidecar_edits: state::sidecar::read_sidecar(path)
+                .filter(|params| !params.is_unedited())
+                .and_then(|params| params.to_json().ok()),
+        }
+    }
+}
+
+/// Insert a step of imported files in one transaction; returns how many were
+/// added and how many were already cataloged
+///
+/// A file that fails on its own is logged and left out; only a transaction
+/// that can't start or commit (e.g. the catalog is locked) is an error.
+fn insert_import_rows(conn: &mut Connection, rows: &[ImportRow], batch_id: i64) -> rusqlite::Result<(usize, usize)> {
+    let (mut imported, mut skipped) = (0, 0);
+    let tx = conn.transaction()?;
+    for row in rows {
+        let metadata = &row.metadata;
+        let result = tx.execute(
+            "INSERT INTO images (path, filename, imported_at, camera_make, camera_model, iso,
+             shutter_seconds, aperture, focal_length, captured_at, orientation, lens_model, import_batch, avg_color)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
             rusqlite::params![
-                &path_str,
-                &filename,
+                &row.path,
+                &row.filename,
                 Utc::now().timestamp(),
+                metadata.camera_make,
+                metadata.camera_model,
+                metadata.iso,
+                metadata.shutter_seconds,
+                metadata.aperture,
+                metadata.focal_length,
+                metadata.captured_at,
+                metadata.orientation.unwrap_or(1),
+                metadata.lens_model,
+                batch_id,
+                row.avg_color,
             ],
         );
         
         match result {
             Ok(_) => {
-                imported_count += 1;
-                if imported_count % 100 == 0 {
-                    println!("⏳ Imported {} files...", imported_count);
+                // Seed the edit stack from the sidecar
+                if let Some(json) = &row.sidecar_edits {
+                    let _ = tx.execute(
+                        "INSERT INTO edits (image_id, settings_json) VALUES (?1, ?2)",
+                        rusqlite::params![tx.last_insert_rowid(), json],
+                    );
+                    println!("📝 Restored edits from sidecar for {}", row.filename);
                 }
+                imported += 1;
             }
-            Err(rusqlite::Error::SqliteFailure(err, _)) => {
-                // Check if this is a UNIQUE constraint violation (duplicate)
-                if err.code == ErrorCode::ConstraintViolation {
-                    skipped_count += 1;
-                } else {
-                    eprintln!("⚠️  Error importing {}: {:?}", filename, err);
-                }
+            // A UNIQUE constraint violation is a duplicate the diff didn't
+            // catch (e.g. a file given twice)
+            Err(rusqlite::Error::SqliteFailure(err, _)) if err.code == ErrorCode::ConstraintViolation => {
+                skipped += 1;
             }
             Err(e) => {
-                eprintln!("⚠️  Error importing {}: {:?}", filename, e);
+                eprintln!("⚠️  Error importing {}: {:?}", row.filename, e);
             }
         }
     }
-    
-    println!("✅ Import complete: {} new, {} skipped", imported_count, skipped_count);
-    
-    ImportResult {
-        imported_count,
-        skipped_count,
+    tx.commit()?;
+    Ok((imported, skipped))
+}
+
+/// Thumbnail worker: runs batches until both queues are empty
+///
+/// Opens one connection for the whole run and sends a `ThumbnailProgress`
+/// after every batch; the last message has both queues at zero, after which
+/// the stream ends and the app drops the subscription. `cancel` ends it after
+/// the current batch.
+fn thumbnail_worker(db_path: PathBuf, cancel: CancelToken) -> impl iced::futures::Stream<Item = Message> {
+    iced::stream::channel(1, move |mut output| async move {
+        use iced::futures::SinkExt;
+        
+        // Workers just extract, results are written here; the UI and imports
+        // write to the catalog too, so wait for their locks instead of failing
+        let mut conn = match Connection::open(&db_path) {
+            Ok(conn) => conn,
+            Err(e) => {
+                eprintln!("❌ Thumbnail worker could not open the database: {}", e);
+                return;
+            }
+        };
+        let _ = conn.busy_timeout(std::time::Duration::from_secs(2));
+        println!("🖼️  Thumbnail worker started");
+        
+        loop {
+            let mut result = generate_thumbnail_batch(&mut conn, &cancel).await;
+            let drained = (result.fast_remaining == 0 && result.slow_remaining == 0) || cancel.is_cancelled();
+            result.last = drained;
+            if output.send(Message::ThumbnailProgress(result)).await.is_err() || drained {
+                break;
+            }
+        }
+        
+        println!("🖼️  Thumbnail worker stopped");
+    })
+}
+
+/// Number of images with the given cache_status
+fn count_cache_status(conn: &Connection, status: &str) -> usize {
+    conn.query_row(
+        "SELECT COUNT(*) FROM images WHERE cache_status = ?1",
+        [status],
+        |row| row.get::<_, i64>(0),
+    )
+    .unwrap_or(0) as usize
+}
+
+/// Mark images as being worked on by the thumbnail worker
+fn mark_processing(conn: &Connection, image_ids: impl Iterator<Item = i64>) {
+    for image_id in image_ids {
+        if let Err(e) = conn.execute("UPDATE images SET cache_status = 'processing' WHERE id = ?1", [image_id]) {
+            eprintln!("⚠️  Failed to mark image {} as processing: {}", image_id, e);
+        }
     }
 }
 
-/// Async function to generate thumbnails using two-tier queue system:
+/// Generate one batch of thumbnails using the two-tier queue system:
 /// - HIGH PRIORITY: Process 'pending' images with fast methods (tiers 1-3)
 /// - LOW PRIORITY: Process 'needs_slow' images with slow method (tier 4) AFTER fast queue is empty
-async fn generate_thumbnails_async(db_path: PathBuf) -> ThumbnailResult {
+///
+/// Images are marked 'processing' while their thumbnail is being written, so
+/// `Library::reset_interrupted_caches` can requeue them if the app is closed
+/// mid-batch. The slow queue is skipped once `cancel` is set.
+async fn generate_thumbnail_batch(conn: &mut Connection, cancel: &CancelToken) -> ThumbnailResult {
     let mut generated_count = 0;
-    
-    // Open database connection
-    let conn = Connection::open(&db_path)
-        .expect("Failed to open database connection for thumbnail generation");
+    let started = std::time::Instant::now();
     
     // ========================================
     // PHASE 1: HIGH PRIORITY - Fast Queue
     // Process 'pending' images with fast methods (tiers 1-3)
+    // Embedded-JPEG extraction is independent per file, so a batch runs in
+    // parallel (a few jobs per core to overlap file I/O)
     // ========================================
-    let fast_batch_size = 5; // Process 5 at a time for efficiency
-    
-    let mut stmt = conn.prepare(
-        "SELECT id, path FROM images 
-         WHERE cache_status = 'pending' 
-         ORDER BY id 
-         LIMIT ?"
-    ).expect("Failed to prepare statement for fast queue");
-    
-    let pending_images: Vec<(i64, String)> = stmt
-        .query_map([fast_batch_size], |row| {
-            Ok((row.get(0)?, row.get(1)?))
+    let workers = std::thread::available_parallelism().map_or(4, |n| n.get());
+    let fast_batch_size = workers * 4;
+    
+    let pending_images: Vec<(i64, String, u8)> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, path, COALESCE(orientation, 1) FROM images 
+             WHERE cache_status = 'pending' 
+             ORDER BY id 
+             LIMIT ?"
+        ).expect("Failed to prepare statement for fast queue");
+        
+        stmt.query_map([fast_batch_size], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .expect("Failed to query pending images")
+            .filter_map(|r| r.ok())
+            .collect()
+    };
+    mark_processing(conn, pending_images.iter().map(|(image_id, _, _)| *image_id));
+    
+    let jobs: Vec<_> = pending_images
+        .into_iter()
+        .map(|(image_id, raw_path_str, orientation)| {
+            tokio::task::spawn_blocking(move || {
+                let raw_path = std::path::Path::new(&raw_path_str);
+                let orientation = state::orientation::Orientation::from_exif(orientation);
+                (image_id, raw::thumbnail::generate_thumbnail_fast(raw_path, image_id, orientation))
+            })
         })
-        .expect("Failed to query pending images")
-        .filter_map(|r| r.ok())
         .collect();
     
-    for (image_id, raw_path_str) in pending_images {
-        let raw_path = std::path::Path::new(&raw_path_str);
-        
-        // Try FAST methods only (tiers 1-3)
-        if let Some(thumbnail_path) = raw::thumbnail::generate_thumbnail_fast(raw_path, image_id) {
-            // Success! Update database
-            let thumbnail_path_str = thumbnail_path.to_string_lossy().to_string();
-            let _ = conn.execute(
-                "UPDATE images SET thumbnail_path = ?1, cache_status = 'cached' WHERE id = ?2",
-                rusqlite::params![thumbnail_path_str, image_id],
-            );
-            generated_count += 1;
-        } else {
-            // Fast methods failed - add to low-priority slow queue
-            let _ = conn.execute(
-                "UPDATE images SET cache_status = 'needs_slow' WHERE id = ?1",
-                rusqlite::params![image_id],
-            );
+    let mut fast_results = Vec::with_capacity(jobs.len());
+    for job in jobs {
+        match job.await {
+            Ok(result) => fast_results.push(result),
+            Err(e) => eprintln!("⚠️  Thumbnail worker failed: {}", e),
+        }
+    }
+    
+    // Write the whole batch in one transaction
+    if !fast_results.is_empty() {
+        let batch: Vec<i64> = fast_results.iter().map(|(image_id, _)| *image_id).collect();
+        let tx = conn.transaction().expect("Failed to start thumbnail transaction");
+        for (image_id, thumbnail_path) in fast_results {
+            if let Some(thumbnail_path) = thumbnail_path {
+                // Success! Update database
+                let thumbnail_path_str = thumbnail_path.to_string_lossy().to_string();
+                let _ = tx.execute(
+                    "UPDATE images SET cache_path_thumb = ?1, cache_status = 'cached' WHERE id = ?2",
+                    rusqlite::params![thumbnail_path_str, image_id],
+                );
+                generated_count += 1;
+            } else {
+                // Fast methods failed - add to low-priority slow queue
+                let _ = tx.execute(
+                    "UPDATE images SET cache_status = 'needs_slow' WHERE id = ?1",
+                    rusqlite::params![image_id],
+                );
+            }
+        }
+        if let Err(e) = tx.commit() {
+            // Rolled back: queue the batch again rather than leave it 'processing'
+            eprintln!("⚠️  Failed to save thumbnail batch: {}", e);
+            for image_id in batch {
+                let _ = conn.execute(
+                    "UPDATE images SET cache_status = 'pending' WHERE id = ?1 AND cache_status = 'processing'",
+                    [image_id],
+                );
+            }
         }
     }
     
@@ -1979,40 +7610,36 @@ async fn generate_thumbnails_async(db_path: PathBuf) -> ThumbnailResult {
     // PHASE 2: LOW PRIORITY - Slow Queue
     // Only process if fast queue is empty (no more 'pending' images)
     // ========================================
-    let pending_count: i64 = conn.query_row(
-        "SELECT COUNT(*) FROM images WHERE cache_status = 'pending'",
-        [],
-        |row| row.get(0)
-    ).unwrap_or(0);
-    
-    if pending_count == 0 {
+    if count_cache_status(conn, "pending") == 0 && !cancel.is_cancelled() {
         // Fast queue is empty - process slow queue
         let slow_batch_size = 1; // Process 1 at a time (slow operations)
         
         let mut stmt = conn.prepare(
-            "SELECT id, path FROM images 
+            "SELECT id, path, COALESCE(orientation, 1) FROM images 
              WHERE cache_status = 'needs_slow' 
              ORDER BY id 
              LIMIT ?"
         ).expect("Failed to prepare statement for slow queue");
         
-        let slow_images: Vec<(i64, String)> = stmt
+        let slow_images: Vec<(i64, String, u8)> = stmt
             .query_map([slow_batch_size], |row| {
-                Ok((row.get(0)?, row.get(1)?))
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
             })
             .expect("Failed to query slow images")
             .filter_map(|r| r.ok())
             .collect();
+        mark_processing(conn, slow_images.iter().map(|(image_id, _, _)| *image_id));
         
-        for (image_id, raw_path_str) in slow_images {
+        for (image_id, raw_path_str, orientation) in slow_images {
             let raw_path = std::path::Path::new(&raw_path_str);
+            let orientation = state::orientation::Orientation::from_exif(orientation);
             
             // Try SLOW method (tier 4)
-            if let Some(thumbnail_path) = raw::thumbnail::generate_thumbnail_slow(raw_path, image_id) {
+            if let Some(thumbnail_path) = raw::thumbnail::generate_thumbnail_slow(raw_path, image_id, orientation) {
                 // Success! Update database
                 let thumbnail_path_str = thumbnail_path.to_string_lossy().to_string();
                 let _ = conn.execute(
-                    "UPDATE images SET thumbnail_path = ?1, cache_status = 'cached' WHERE id = ?2",
+                    "UPDATE images SET cache_path_thumb = ?1, cache_status = 'cached' WHERE id = ?2",
                     rusqlite::params![thumbnail_path_str, image_id],
                 );
                 generated_count += 1;
@@ -2028,6 +7655,10 @@ async fn generate_thumbnails_async(db_path: PathBuf) -> ThumbnailResult {
     
     ThumbnailResult {
         generated_count,
+        elapsed: started.elapsed(),
+        fast_remaining: count_cache_status(conn, "pending"),
+        slow_remaining: count_cache_status(conn, "needs_slow"),
+        last: false,
     }
 }
 
@@ -2039,15 +7670,15 @@ async fn process_cache_async(db_path: PathBuf) -> Result<(i64, String, String, S
         .map_err(|e| (0, format!("Failed to open database: {}", e)))?;
     
     // Find one pending image
-    let pending_image: Option<(i64, String)> = conn
+    let pending_image: Option<(i64, String, u8)> = conn
         .query_row(
-            "SELECT id, path FROM images WHERE cache_status = 'pending' LIMIT 1",
+            "SELECT id, path, COALESCE(orientation, 1) FROM images WHERE cache_status = 'pending' LIMIT 1",
             [],
-            |row| Ok((row.get(0)?, row.get(1)?)),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
         )
         .ok();
     
-    if let Some((image_id, raw_path_str)) = pending_image {
+    if let Some((image_id, raw_path_str, orientation)) = pending_image {
         // Process in blocking task (image decoding is CPU-intensive)
         let result = tokio::task::spawn_blocking(move || {
             let cache_dir = std::path::PathBuf::from("/tmp"); // Not used by processor
@@ -2055,6 +7686,7 @@ async fn process_cache_async(db_path: PathBuf) -> Result<(i64, String, String, S
                 std::path::Path::new(&raw_path_str),
                 image_id,
                 &cache_dir,
+                state::orientation::Orientation::from_exif(orientation),
             )
         })
         .await
@@ -3,9 +3,135 @@
 /// This module loads the actual sensor data from RAW files (not embedded JPEGs).
 /// The data is returned as raw u16 values which will be processed by the GPU.
 
+use std::io::Read;
 use std::path::Path;
 use tokio::task;
 
+/// Extensions rawloader 0.37 has a decoder for
+///
+/// Checked against the decoders in that release: CR3 is missing on purpose
+/// (Canon's ISO-BMFF container isn't supported), as are Sigma X3F and Phase
+/// One IIQ. Revisit when bumping rawloader.
+pub const SUPPORTED_EXTENSIONS: [&str; 23] = [
+    "nef", "nrw", "dng", "cr2", "crw", "arw", "srf", "sr2", "raf", "orf", "rw2", "rwl",
+    "raw", "pef", "srw", "erf", "kdc", "dcr", "mos", "mrw", "mef", "3fr", "fff",
+];
+
+/// RAW formats rawloader can't decode. Imports still pick them up so they
+/// can be counted and reported as unsupported instead of silently ignored.
+pub const UNSUPPORTED_EXTENSIONS: [&str; 3] = ["cr3", "x3f", "iiq"];
+
+/// Normalize a user-entered extension: lowercase, without the leading dot
+pub fn normalize_extension(ext: &str) -> String {
+    ext.trim().trim_start_matches('.').to_lowercase()
+}
+
+/// True if files with this extension are treated as RAW photos: the built-in
+/// formats (decodable or not) plus the user's custom extensions from
+/// Preferences. Case doesn't matter and a leading dot is allowed. This is
+/// the one check import, file drops and the file picker go through.
+pub fn is_raw_extension(ext: &str, custom: &[String]) -> bool {
+    let ext = normalize_extension(ext);
+    SUPPORTED_EXTENSIONS.contains(&ext.as_str())
+        || UNSUPPORTED_EXTENSIONS.contains(&ext.as_str())
+        || custom.iter().any(|c| normalize_extension(c) == ext)
+}
+
+/// Bytes read from the start of a file to recognize its container
+const HEADER_PROBE_LEN: usize = 16;
+
+/// True if the start of a file matches a container rawloader can parse
+fn has_known_header(header: &[u8]) -> bool {
+    header.starts_with(b"II*\0") || header.starts_with(b"MM\0*")  // TIFF-based (NEF, CR2, ARW, DNG, PEF, ...)
+        || header.starts_with(b"IIRO") || header.starts_with(b"IIRS") || header.starts_with(b"MMOR")  // Olympus ORF
+        || header.starts_with(b"IIU\0")     // Panasonic/Leica RW2, RWL, RAW
+        || header.starts_with(b"FUJIFILM")  // Fujifilm RAF
+        || header.starts_with(b"\0MRM")     // Minolta MRW
+        || header.get(6..14) == Some(b"HEAPCCDR".as_slice())  // Canon CRW (CIFF)
+}
+
+/// Quick check whether a file can be decoded, without decoding it: a
+/// supported (or custom) extension and a header rawloader recognizes
+pub fn is_supported(path: &Path, custom: &[String]) -> bool {
+    let extension_ok = path
+        .extension()
+        .map(|ext| {
+            let ext = ext.to_string_lossy();
+            is_raw_extension(&ext, custom) && !UNSUPPORTED_EXTENSIONS.contains(&normalize_extension(&ext).as_str())
+        })
+        .unwrap_or(false);
+    if !extension_ok {
+        return false;
+    }
+    
+    let mut header = [0u8; HEADER_PROBE_LEN];
+    let Ok(mut file) = std::fs::File::open(path) else {
+        return false;
+    };
+    let Ok(read) = file.read(&mut header) else {
+        return false;
+    };
+    has_known_header(&header[..read])
+}
+
+/// Why rawloader couldn't decode a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeFailure {
+    /// The format is readable but rawloader doesn't know this camera
+    UnsupportedCamera(String),
+    /// No decoder for this kind of file (extension, lowercase)
+    NoDecoder(String),
+    /// A decoder took the file but the data is damaged or truncated
+    Corrupt(String),
+}
+
+impl DecodeFailure {
+    /// Sort a rawloader error message into one of the failure kinds
+    ///
+    /// rawloader only reports strings: "Couldn't find a decoder for this
+    /// file" when nothing recognizes the container, and "Couldn't find camera
+    /// ..." / "Camera ... is not supported" for models missing from its
+    /// camera database. Anything else comes from a decoder choking on the data.
+    pub fn classify(path: &Path, message: &str) -> Self {
+        let lower = message.to_lowercase();
+        if lower.contains("decoder") {
+            let extension = path
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            DecodeFailure::NoDecoder(extension)
+        } else if lower.contains("camera") {
+            DecodeFailure::UnsupportedCamera(message.to_string())
+        } else {
+            DecodeFailure::Corrupt(message.to_string())
+        }
+    }
+}
+
+impl std::fmt::Display for DecodeFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeFailure::UnsupportedCamera(detail) => write!(
+                f,
+                "Unsupported camera model. The file format is supported, but this camera isn't in rawloader's database yet ({})",
+                detail
+            ),
+            DecodeFailure::NoDecoder(extension) if extension.is_empty() => {
+                write!(f, "No decoder for this file: it isn't a RAW format this build can read")
+            }
+            DecodeFailure::NoDecoder(extension) => write!(
+                f,
+                "No decoder for .{} files. Supported formats: {}",
+                extension,
+                SUPPORTED_EXTENSIONS.join(", ")
+            ),
+            DecodeFailure::Corrupt(detail) => {
+                write!(f, "The file looks corrupt or truncated and couldn't be decoded ({})", detail)
+            }
+        }
+    }
+}
+
 /// Result type for RAW data loading
 #[derive(Debug, Clone)]
 pub struct RawDataResult {
@@ -16,6 +142,219 @@ pub struct RawDataResult {
     pub wb_multipliers: [f32; 4],
     /// Color matrix (3x3) for camera RGB to sRGB conversion
     pub color_matrix: [f32; 9],
+    /// DNG forward matrix (white-balanced camera → XYZ D50), preferred over
+    /// `color_matrix` when present
+    pub forward_matrix: Option<[f32; 9]>,
+    /// Bayer layout of the sensor, used by the GPU demosaic
+    pub cfa_pattern: CfaPattern,
+    /// Black pedestal and saturation point, used to normalize the raw values
+    pub levels: SensorLevels,
+}
+
+/// Sensor black and white levels, in raw units
+///
+/// Raw values start at a black pedestal (e.g. 512 or 2048) rather than 0, and
+/// saturate at the white level, which depends on the bit depth (4095 for
+/// 12-bit, 16383 for 14-bit). Normalizing with both maps black to 0.0 and
+/// clipping to 1.0 for every camera.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SensorLevels {
+    pub black: f32,
+    pub white: f32,
+}
+
+impl SensorLevels {
+    /// 12-bit data without a pedestal (synthetic test data)
+    pub const TWELVE_BIT: SensorLevels = SensorLevels { black: 0.0, white: 4096.0 };
+
+    /// From rawloader's per-channel levels: average black, lowest white (so
+    /// every channel reaches 1.0 when it clips). Falls back to the white level
+    /// of 16-bit data if the metadata makes no sense.
+    pub fn from_rawloader(blacklevels: &[u16; 4], whitelevels: &[u16; 4]) -> Self {
+        let black = blacklevels.iter().map(|&v| v as f32).sum::<f32>() / 4.0;
+        let white = whitelevels.iter().copied().min().unwrap_or(u16::MAX) as f32;
+        if white > black {
+            SensorLevels { black, white }
+        } else {
+            SensorLevels { black: 0.0, white: u16::MAX as f32 }
+        }
+    }
+
+    /// Raw value to 0.0 (black) - 1.0 (white); CPU mirror of `get_neighbor` in the shader
+    pub fn normalize(self, raw: f32) -> f32 {
+        ((raw - self.black) / (self.white - self.black)).max(0.0)
+    }
+}
+
+/// Bayer color filter layout, named by the 2x2 tile at the image origin
+/// (row-major: "GRBG" = G R on the first row, B G on the second)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CfaPattern {
+    Rggb,
+    Bggr,
+    Grbg,
+    Gbrg,
+}
+
+impl CfaPattern {
+    /// Parse a rawloader CFA name like "RGGB"
+    /// Returns None for anything that isn't a 2x2 Bayer tile (X-Trans, CYGM, ...)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "RGGB" => Some(CfaPattern::Rggb),
+            "BGGR" => Some(CfaPattern::Bggr),
+            "GRBG" => Some(CfaPattern::Grbg),
+            "GBRG" => Some(CfaPattern::Gbrg),
+            _ => None,
+        }
+    }
+    
+    /// Pixel offset (x, y) that moves the pattern onto an RGGB origin,
+    /// i.e. the position of the red photosite in the 2x2 tile
+    /// The shader adds this to every coordinate before picking a color.
+    pub fn offset(self) -> [u32; 2] {
+        match self {
+            CfaPattern::Rggb => [0, 0],
+            CfaPattern::Grbg => [1, 0],
+            CfaPattern::Gbrg => [0, 1],
+            CfaPattern::Bggr => [1, 1],
+        }
+    }
+}
+
+impl std::fmt::Display for CfaPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            CfaPattern::Rggb => "RGGB",
+            CfaPattern::Bggr => "BGGR",
+            CfaPattern::Grbg => "GRBG",
+            CfaPattern::Gbrg => "GBRG",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Pull the 3x3 XYZ→camera matrix out of rawloader's metadata as flat row-major
+///
+/// rawloader stores `xyz_to_cam` as 4 rows (one per camera channel, the 4th only
+/// used by 4-color sensors) of 3 XYZ columns, so we take the first 3 rows as-is.
+/// Returns None when the camera has no matrix (all zeros) or it's corrupt.
+fn extract_xyz_to_cam(xyz_to_cam: &[[f32; 3]; 4]) -> Option<[f32; 9]> {
+    let rows = &xyz_to_cam[..3];
+    let all_finite = rows.iter().flatten().all(|v| v.is_finite());
+    let every_row_set = rows.iter().all(|row| row.iter().any(|&v| v != 0.0));
+    if !all_finite || !every_row_set {
+        return None;
+    }
+    
+    Some([
+        rows[0][0], rows[0][1], rows[0][2],  // Row 0 (camera R)
+        rows[1][0], rows[1][1], rows[1][2],  // Row 1 (camera G)
+        rows[2][0], rows[2][1], rows[2][2],  // Row 2 (camera B)
+    ])
+}
+
+/// DNG color tags (TIFF tag numbers from the DNG specification)
+const DNG_COLOR_MATRIX_1: u16 = 0xC621;
+const DNG_COLOR_MATRIX_2: u16 = 0xC622;
+const DNG_AS_SHOT_NEUTRAL: u16 = 0xC628;
+const DNG_FORWARD_MATRIX_1: u16 = 0xC714;
+const DNG_FORWARD_MATRIX_2: u16 = 0xC715;
+
+/// Color metadata a DNG carries beyond rawloader's generic fields
+///
+/// Phones and Adobe conversions write these instead of (or on top of) the
+/// maker-specific tags the other decoders read. The "2" variants are the
+/// D65 calibration in practice, so they win over the "1" ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DngColor {
+    /// Camera values of a neutral surface under the shot's light
+    pub as_shot_neutral: Option<[f32; 3]>,
+    /// XYZ → camera (`ColorMatrix2`, else `ColorMatrix1`), flat row-major
+    pub color_matrix: Option<[f32; 9]>,
+    /// White-balanced camera → XYZ D50 (`ForwardMatrix2`, else `ForwardMatrix1`)
+    pub forward_matrix: Option<[f32; 9]>,
+}
+
+impl DngColor {
+    /// As-shot white balance multipliers [R, G, B, G2], green normalized to 1
+    pub fn wb_multipliers(&self) -> Option<[f32; 4]> {
+        let [r, g, b] = self.as_shot_neutral?;
+        if [r, g, b].iter().any(|&v| !v.is_finite() || v <= 0.0) {
+            return None;
+        }
+        Some([g / r, 1.0, g / b, 1.0])
+    }
+}
+
+/// True for DNG files (by extension), the only ones read with `read_dng_color`
+fn is_dng(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| normalize_extension(&ext.to_string_lossy()) == "dng")
+}
+
+/// Read a DNG's color tags from its primary IFD
+///
+/// None for other formats, unreadable files, or DNGs without any of the tags.
+pub fn read_dng_color(path: &Path) -> Option<DngColor> {
+    if !is_dng(path) {
+        return None;
+    }
+    let file = std::fs::File::open(path).ok()?;
+    let exif = match exif::Reader::new().read_from_container(&mut std::io::BufReader::new(file)) {
+        Ok(exif) => exif,
+        Err(e) => {
+            println!("⚠️  Couldn't read DNG tags from {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    
+    let values = |tag: u16| -> Option<Vec<f32>> {
+        let field = exif.get_field(exif::Tag(exif::Context::Tiff, tag), exif::In::PRIMARY)?;
+        let values: Vec<f32> = match &field.value {
+            exif::Value::Rational(v) => v.iter().map(|r| r.to_f64() as f32).collect(),
+            exif::Value::SRational(v) => v.iter().map(|r| r.to_f64() as f32).collect(),
+            exif::Value::Short(v) => v.iter().map(|&x| x as f32).collect(),
+            exif::Value::Long(v) => v.iter().map(|&x| x as f32).collect(),
+            exif::Value::Float(v) => v.clone(),
+            exif::Value::Double(v) => v.iter().map(|&x| x as f32).collect(),
+            _ => return None,
+        };
+        values.iter().all(|v| v.is_finite()).then_some(values)
+    };
+    // Only 3-color sensors: 4-color DNGs have 4x3 matrices
+    let matrix = |tag: u16| values(tag).and_then(|v| <[f32; 9]>::try_from(v).ok());
+    
+    let color = DngColor {
+        as_shot_neutral: values(DNG_AS_SHOT_NEUTRAL).and_then(|v| <[f32; 3]>::try_from(v).ok()),
+        color_matrix: matrix(DNG_COLOR_MATRIX_2).or_else(|| matrix(DNG_COLOR_MATRIX_1)),
+        forward_matrix: matrix(DNG_FORWARD_MATRIX_2).or_else(|| matrix(DNG_FORWARD_MATRIX_1)),
+    };
+    (color != DngColor::default()).then_some(color)
+}
+
+/// Work out the Bayer layout of a decoded RAW, or explain why we can't render it
+fn detect_cfa_pattern(raw_image: &rawloader::RawImage) -> Result<CfaPattern, String> {
+    if raw_image.cpp != 1 {
+        return Err(format!(
+            "Unsupported RAW: {} {} stores {} components per pixel (already demosaiced)",
+            raw_image.clean_make, raw_image.clean_model, raw_image.cpp
+        ));
+    }
+    
+    let cfa = &raw_image.cfa;
+    if cfa.width == 6 && cfa.height == 6 {
+        return Err(format!(
+            "Unsupported sensor: {} {} uses a Fujifilm X-Trans (6x6) color filter, only Bayer sensors are supported",
+            raw_image.clean_make, raw_image.clean_model
+        ));
+    }
+    
+    CfaPattern::from_name(&cfa.name).ok_or_else(|| {
+        format!(
+            "Unsupported sensor: {} {} has color filter pattern {:?} ({}x{}), only 2x2 Bayer is supported",
+            raw_image.clean_make, raw_image.clean_model, cfa.name, cfa.width, cfa.height
+        )
+    })
 }
 
 /// Load raw sensor data from a RAW file
@@ -39,7 +378,7 @@ pub async fn load_raw_data(path: String) -> Result<RawDataResult, String> {
 }
 
 /// Blocking implementation of RAW data loading
-fn load_raw_data_blocking(path: &str) -> Result<RawDataResult, String> {
+pub(crate) fn load_raw_data_blocking(path: &str) -> Result<RawDataResult, String> {
     let path = Path::new(path);
     
     // Verify file exists
@@ -49,9 +388,18 @@ fn load_raw_data_blocking(path: &str) -> Result<RawDataResult, String> {
     
     let mut decoder = rawloader::RawLoader::new();
     
-    // Decode the RAW file (rawloader expects &Path)
-    let raw_image = decoder.decode_file(path)
-        .map_err(|e| format!("Failed to decode RAW: {:?}", e))?;
+    // Decode the RAW file (rawloader expects &Path). Some decoders panic on
+    // truncated data instead of returning an error, so catch that too.
+    let decoded = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| decoder.decode_file(path)));
+    let raw_image = match decoded {
+        Ok(Ok(raw_image)) => raw_image,
+        Ok(Err(e)) => return Err(DecodeFailure::classify(path, &e.to_string()).to_string()),
+        Err(_) => return Err(DecodeFailure::Corrupt("the decoder crashed".to_string()).to_string()),
+    };
+    
+    // Bail out early on sensors the Bayer demosaic can't handle
+    let cfa_pattern = detect_cfa_pattern(&raw_image)?;
+    println!("🔲 CFA pattern: {}", cfa_pattern);
     
     // Get dimensions
     let width = raw_image.width as u32;
@@ -59,18 +407,20 @@ fn load_raw_data_blocking(path: &str) -> Result<RawDataResult, String> {
     
     // Extract raw sensor data
     // rawloader returns data in different formats, we need to normalize to u16
-    let data: Vec<u16> = match &raw_image.data {
+    let (data, levels): (Vec<u16>, SensorLevels) = match &raw_image.data {
         rawloader::RawImageData::Integer(values) => {
-            // Already u16, perfect!
-            values.clone()
+            // Already u16, perfect! The camera's levels say where black and white are
+            (values.clone(), SensorLevels::from_rawloader(&raw_image.blacklevels, &raw_image.whitelevels))
         }
         rawloader::RawImageData::Float(values) => {
-            // Convert f32 (0.0-1.0) to u16 (0-65535)
-            values.iter()
+            // Convert f32 (0.0-1.0) to u16 (0-65535), which is then the full range
+            let data = values.iter()
                 .map(|&v| (v * 65535.0).clamp(0.0, 65535.0) as u16)
-                .collect()
+                .collect();
+            (data, SensorLevels { black: 0.0, white: 65535.0 })
         }
     };
+    println!("⚫ Black level: {:.0}, white level: {:.0}", levels.black, levels.white);
     
     println!("📷 Loaded RAW data: {}x{} ({} pixels)", width, height, data.len());
     
@@ -109,28 +459,33 @@ fn load_raw_data_blocking(path: &str) -> Result<RawDataResult, String> {
         },
     ];
     
+    // DNGs: the as-shot neutral and color matrices from the file itself
+    // beat rawloader's generic values
+    let dng_color = read_dng_color(path).unwrap_or_default();
+    let wb_normalized = match dng_color.wb_multipliers() {
+        Some(wb) => {
+            println!("🎨 Using the DNG as-shot neutral for white balance");
+            wb
+        }
+        None => wb_normalized,
+    };
+    
     // Extract xyz_to_cam matrix (3x3) from camera metadata
     // Phase 15: Return the actual matrix, will be converted to cam_to_srgb in main.rs
-    // rawloader provides xyz_to_cam as [3][4], we only need first 3 columns
-    let xyz_cam = &raw_image.xyz_to_cam;
-    let has_matrix = xyz_cam[0][0] != 0.0 || xyz_cam[1][1] != 0.0;
-    
-    let xyz_to_cam_matrix: [f32; 9] = if has_matrix {
-        // Extract first 3 columns (4th column is usually white point info)
-        println!("🎨 Found xyz_to_cam matrix from camera");
-        [
-            xyz_cam[0][0], xyz_cam[0][1], xyz_cam[0][2],  // Row 0
-            xyz_cam[1][0], xyz_cam[1][1], xyz_cam[1][2],  // Row 1
-            xyz_cam[2][0], xyz_cam[2][1], xyz_cam[2][2],  // Row 2
-        ]
-    } else {
-        // No matrix available, use identity
-        println!("⚠️  No xyz_to_cam matrix found, using identity");
-        [
-            1.0, 0.0, 0.0,
-            0.0, 1.0, 0.0,
-            0.0, 0.0, 1.0,
-        ]
+    let xyz_to_cam_matrix: [f32; 9] = match dng_color.color_matrix.or_else(|| extract_xyz_to_cam(&raw_image.xyz_to_cam)) {
+        Some(matrix) => {
+            println!("🎨 Found xyz_to_cam matrix from camera");
+            matrix
+        }
+        None => {
+            // No matrix available, use identity
+            println!("⚠️  No xyz_to_cam matrix found, using identity");
+            [
+                1.0, 0.0, 0.0,
+                0.0, 1.0, 0.0,
+                0.0, 0.0, 1.0,
+            ]
+        }
     };
     
     println!("🎨 White Balance: R={:.3}, G={:.3}, B={:.3}, G2={:.3}", 
@@ -148,6 +503,9 @@ fn load_raw_data_blocking(path: &str) -> Result<RawDataResult, String> {
         height,
         wb_multipliers: wb_normalized,
         color_matrix: xyz_to_cam_matrix,  // Return xyz_to_cam, will convert in main.rs
+        forward_matrix: dng_color.forward_matrix,
+        cfa_pattern,
+        levels,
     })
 }
 
@@ -163,4 +521,176 @@ mod tests {
         let result = load_raw_data("/nonexistent/path.nef".to_string()).await;
         assert!(result.is_err());
     }
+    
+    #[test]
+    fn test_is_raw_extension() {
+        // Any case, with or without the dot
+        assert!(is_raw_extension("nef", &[]));
+        assert!(is_raw_extension("NEF", &[]));
+        assert!(is_raw_extension(".Cr2", &[]));
+        // Known but undecodable formats still count as RAW (reported as unsupported)
+        assert!(is_raw_extension("CR3", &[]));
+        assert!(!is_raw_extension("jpg", &[]));
+        assert!(!is_raw_extension("", &[]));
+
+        let custom = vec![".RWZ".to_string(), "tif".to_string()];
+        assert!(is_raw_extension("rwz", &custom));
+        assert!(is_raw_extension("TIF", &custom));
+        assert!(!is_raw_extension("tiff", &custom));
+    }
+
+    #[test]
+    fn test_is_supported_checks_extension_and_header() {
+        let dir = std::env::temp_dir().join(format!("raw-editor-loader-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, bytes: &[u8]| {
+            let path = dir.join(name);
+            std::fs::write(&path, bytes).unwrap();
+            path
+        };
+        
+        assert!(is_supported(&write("a.NEF", b"MM\0*\0\0\0\x08"), &[]));
+        assert!(is_supported(&write("b.raf", b"FUJIFILMCCD-RAW "), &[]));
+        assert!(is_supported(&write("c.crw", b"II\x1a\0\0\0HEAPCCDR"), &[]));
+        // CR3 is ISO-BMFF: no decoder, whatever the header says
+        assert!(!is_supported(&write("d.cr3", b"\0\0\0\x18ftypcrx "), &[]));
+        // Right extension, wrong contents (renamed JPEG)
+        assert!(!is_supported(&write("e.dng", b"\xff\xd8\xff\xe0"), &[]));
+        assert!(!is_supported(&dir.join("missing.nef"), &[]));
+        // A custom extension still needs a known container
+        let custom = vec!["tif".to_string()];
+        assert!(!is_supported(&write("f.tif", b"II*\0\x08\0\0\0"), &[]));
+        assert!(is_supported(&write("f.tif", b"II*\0\x08\0\0\0"), &custom));
+        assert!(!is_supported(&write("g.tif", b"\xff\xd8\xff\xe0"), &custom));
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_read_dng_color() {
+        use exif::{Field, In, Rational, SRational, Tag, Value};
+        
+        let dir = std::env::temp_dir().join(format!("raw-editor-dng-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let field = |tag: u16, value: Value| Field { tag: Tag(exif::Context::Tiff, tag), ifd_num: In::PRIMARY, value };
+        let srational = |values: [i32; 9]| {
+            Value::SRational(values.iter().map(|&num| SRational { num, denom: 10000 }).collect())
+        };
+        let mut writer = exif::experimental::Writer::new();
+        let fields = [
+            field(DNG_AS_SHOT_NEUTRAL, Value::Rational(vec![
+                Rational { num: 1, denom: 2 }, Rational { num: 1, denom: 1 }, Rational { num: 4, denom: 5 },
+            ])),
+            field(DNG_COLOR_MATRIX_1, srational([10000, 0, 0, 0, 10000, 0, 0, 0, 10000])),
+            field(DNG_COLOR_MATRIX_2, srational([9020, -2890, -715, -4535, 12436, 2348, -934, 1919, 7086])),
+            field(DNG_FORWARD_MATRIX_1, srational([7367, 1078, 1198, 2836, 7749, -585, 67, -1750, 9934])),
+        ];
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut buf = std::io::Cursor::new(Vec::new());
+        writer.write(&mut buf, true).unwrap();
+        let dng = dir.join("PXL_0001.DNG");
+        std::fs::write(&dng, buf.get_ref()).unwrap();
+        
+        let color = read_dng_color(&dng).unwrap();
+        assert_eq!(color.as_shot_neutral, Some([0.5, 1.0, 0.8]));
+        assert_eq!(color.wb_multipliers(), Some([2.0, 1.0, 1.25, 1.0]));
+        // ColorMatrix2 wins, ForwardMatrix1 is used when there's no 2
+        assert!((color.color_matrix.unwrap()[0] - 0.902).abs() < 1e-6);
+        assert!((color.forward_matrix.unwrap()[0] - 0.7367).abs() < 1e-6);
+        
+        // Same tags in a non-DNG file are left to rawloader
+        let nef = dir.join("DSC_0001.NEF");
+        std::fs::write(&nef, buf.get_ref()).unwrap();
+        assert_eq!(read_dng_color(&nef), None);
+        
+        let neutral = DngColor { as_shot_neutral: Some([0.0, 1.0, 0.5]), ..DngColor::default() };
+        assert_eq!(neutral.wb_multipliers(), None);
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_decode_failure_classification() {
+        let path = Path::new("/photos/IMG_0001.CR3");
+        assert_eq!(
+            DecodeFailure::classify(path, "Couldn't find a decoder for this file"),
+            DecodeFailure::NoDecoder("cr3".to_string())
+        );
+        assert!(matches!(
+            DecodeFailure::classify(path, "Couldn't find camera \"Canon\" \"EOS R5\" mode \"\""),
+            DecodeFailure::UnsupportedCamera(_)
+        ));
+        assert!(matches!(
+            DecodeFailure::classify(path, "ljpeg: huffman table out of range"),
+            DecodeFailure::Corrupt(_)
+        ));
+        assert!(DecodeFailure::NoDecoder("cr3".to_string()).to_string().contains(".cr3"));
+    }
+    
+    #[test]
+    fn test_sensor_levels() {
+        // 14-bit camera with a 512 pedestal
+        let levels = SensorLevels::from_rawloader(&[512; 4], &[16383, 16383, 16383, 16383]);
+        assert_eq!(levels, SensorLevels { black: 512.0, white: 16383.0 });
+        assert_eq!(levels.normalize(512.0), 0.0);
+        assert_eq!(levels.normalize(16383.0), 1.0);
+        // Noise below the pedestal doesn't go negative
+        assert_eq!(levels.normalize(400.0), 0.0);
+        
+        // Nonsense metadata falls back to the full 16-bit range
+        let broken = SensorLevels::from_rawloader(&[0; 4], &[0; 4]);
+        assert_eq!(broken.white, 65535.0);
+    }
+    
+    #[test]
+    fn test_cfa_pattern_from_name() {
+        assert_eq!(CfaPattern::from_name("RGGB"), Some(CfaPattern::Rggb));
+        assert_eq!(CfaPattern::from_name("BGGR"), Some(CfaPattern::Bggr));
+        assert_eq!(CfaPattern::from_name("GRBG"), Some(CfaPattern::Grbg));
+        assert_eq!(CfaPattern::from_name("GBRG"), Some(CfaPattern::Gbrg));
+        assert_eq!(CfaPattern::from_name("rggb"), Some(CfaPattern::Rggb));
+        
+        // X-Trans (6x6), four-color and empty patterns aren't Bayer
+        assert_eq!(CfaPattern::from_name("GGRGGBGGBGGRBRGRBGGGBGGRGGRGGBRBGBRG"), None);
+        assert_eq!(CfaPattern::from_name("CYGM"), None);
+        assert_eq!(CfaPattern::from_name(""), None);
+        
+        // Names and Display agree
+        for pattern in [CfaPattern::Rggb, CfaPattern::Bggr, CfaPattern::Grbg, CfaPattern::Gbrg] {
+            assert_eq!(CfaPattern::from_name(&pattern.to_string()), Some(pattern));
+        }
+    }
+    
+    #[test]
+    fn test_cfa_offset_points_at_red() {
+        // The offset is where the 'R' sits in the row-major 2x2 name
+        for pattern in [CfaPattern::Rggb, CfaPattern::Bggr, CfaPattern::Grbg, CfaPattern::Gbrg] {
+            let [x, y] = pattern.offset();
+            let red_index = pattern.to_string().find('R').unwrap() as u32;
+            assert_eq!((x, y), (red_index % 2, red_index / 2), "{}", pattern);
+        }
+    }
+    
+    #[test]
+    fn test_extract_xyz_to_cam() {
+        // Nikon D750 ColorMatrix2, with an unused 4th row
+        let xyz_to_cam = [
+            [0.9020, -0.2890, -0.0715],
+            [-0.4535, 1.2436, 0.2348],
+            [-0.0934, 0.1919, 0.7086],
+            [0.0, 0.0, 0.0],
+        ];
+        let flat = extract_xyz_to_cam(&xyz_to_cam).unwrap();
+        assert_eq!(flat[1], -0.2890);  // Row 0, column 1 (not transposed)
+        assert_eq!(flat[3], -0.4535);  // Row 1, column 0
+        assert_eq!(flat[8], 0.7086);
+        
+        // Missing or corrupt metadata
+        assert!(extract_xyz_to_cam(&[[0.0; 3]; 4]).is_none());
+        let mut corrupt = xyz_to_cam;
+        corrupt[2][1] = f32::NAN;
+        assert!(extract_xyz_to_cam(&corrupt).is_none());
+    }
 }
@@ -9,6 +9,8 @@ use image::{imageops::FilterType, ImageFormat};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::state::orientation::Orientation;
+
 /// Cache tier sizes
 const TIER_THUMB: u32 = 256;    // Grid thumbnails
 const TIER_INSTANT: u32 = 384;  // Quick preview
@@ -16,12 +18,7 @@ const TIER_WORKING: u32 = 1280; // Editing preview
 
 /// Get the cache directory for a specific tier
 fn get_cache_dir(tier_name: &str) -> PathBuf {
-    let mut path = dirs_next::cache_dir()
-        .or_else(|| dirs_next::home_dir())
-        .expect("Could not determine cache directory");
-    
-    path.push("raw-editor");
-    path.push(tier_name);
+    let path = crate::state::settings::cache_root().join(tier_name);
     
     // Ensure the directory exists
     fs::create_dir_all(&path)
@@ -38,6 +35,7 @@ pub fn process_image(
     raw_path: &Path,
     image_id: i64,
     _cache_dir: &Path, // Not used, we use tier-specific dirs
+    orientation: Orientation,
 ) -> Result<(String, String, String), String> {
     // Step 1: Extract the largest embedded JPEG from the RAW file
     let jpeg_data = extract_largest_jpeg(raw_path)
@@ -53,6 +51,9 @@ pub fn process_image(
     
     println!("   Original size: {}x{}", img.width(), img.height());
     
+    // The embedded JPEG is stored sensor-side up; every tier is saved upright
+    let img = orientation.apply(img);
+    
     // Step 3: Generate all 3 tiers from this single JPEG
     let thumb_path = generate_tier(&img, TIER_THUMB, "thumb", image_id)?;
     let instant_path = generate_tier(&img, TIER_INSTANT, "instant", image_id)?;
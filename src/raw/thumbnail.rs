@@ -1,19 +1,17 @@
-use image::{imageops::FilterType, ImageFormat};
+use image::{imageops::FilterType, DynamicImage, ImageFormat, Rgb, RgbImage};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use super::loader::{self, RawDataResult};
+use crate::state::orientation::Orientation;
+
 /// Size of generated thumbnails (square)
 const THUMBNAIL_SIZE: u32 = 256;
 
 /// Get the thumbnail cache directory
 /// Returns ~/.cache/raw-editor/thumbnails on Linux
 pub fn get_thumbnail_cache_dir() -> PathBuf {
-    let mut path = dirs_next::cache_dir()
-        .or_else(|| dirs_next::home_dir())
-        .expect("Could not determine cache directory");
-    
-    path.push("raw-editor");
-    path.push("thumbnails");
+    let path = crate::state::settings::cache_root().join("thumbnails");
     
     // Ensure the directory exists
     fs::create_dir_all(&path).expect("Failed to create thumbnail cache directory");
@@ -23,17 +21,20 @@ pub fn get_thumbnail_cache_dir() -> PathBuf {
 
 /// Generate a thumbnail using FAST methods only (tiers 1-3: embedded JPEG extraction)
 /// Returns Some(path) if successful, None if image needs slow processing (tier 4)
-pub fn generate_thumbnail_fast(raw_path: &Path, image_id: i64) -> Option<PathBuf> {
+///
+/// Embedded JPEGs are stored the way the sensor saw the scene; `orientation`
+/// turns the thumbnail upright.
+pub fn generate_thumbnail_fast(raw_path: &Path, image_id: i64, orientation: Orientation) -> Option<PathBuf> {
     // Tier 1: Fast embedded JPEG search (256KB)
     if let Some(thumbnail_data) = extract_embedded_jpeg_fast(raw_path) {
-        if let Some(path) = save_thumbnail(thumbnail_data, image_id) {
+        if let Some(path) = save_thumbnail(thumbnail_data, image_id, orientation) {
             return Some(path);
         }
     }
     
     // Tier 2: Extended embedded JPEG search (512KB)
     if let Some(thumbnail_data) = extract_embedded_jpeg_extended(raw_path) {
-        if let Some(path) = save_thumbnail(thumbnail_data, image_id) {
+        if let Some(path) = save_thumbnail(thumbnail_data, image_id, orientation) {
             println!("📸 Generated thumbnail (tier 2): {}", path.display());
             return Some(path);
         }
@@ -41,7 +42,7 @@ pub fn generate_thumbnail_fast(raw_path: &Path, image_id: i64) -> Option<PathBuf
     
     // Tier 3: Full embedded JPEG search (5MB)
     if let Some(thumbnail_data) = extract_embedded_jpeg_full(raw_path) {
-        if let Some(path) = save_thumbnail(thumbnail_data, image_id) {
+        if let Some(path) = save_thumbnail(thumbnail_data, image_id, orientation) {
             println!("📸 Generated thumbnail (tier 3): {}", path.display());
             return Some(path);
         }
@@ -53,13 +54,19 @@ pub fn generate_thumbnail_fast(raw_path: &Path, image_id: i64) -> Option<PathBuf
 
 /// Generate a thumbnail using SLOW method (tier 4: full RAW decode)
 /// This should only be called for images that failed fast methods
-pub fn generate_thumbnail_slow(raw_path: &Path, image_id: i64) -> Option<PathBuf> {
+pub fn generate_thumbnail_slow(raw_path: &Path, image_id: i64, orientation: Orientation) -> Option<PathBuf> {
     // Tier 4: Decode actual RAW data (slowest but always works)
-    if let Some(path) = decode_raw_to_thumbnail(raw_path, image_id) {
+    if let Some(path) = decode_raw_to_thumbnail(raw_path, image_id, orientation) {
         println!("🔥 Generated thumbnail from RAW decode: {}", path.display());
         return Some(path);
     }
     
+    // Tier 4b: No usable embedded JPEG at all - build it from the sensor data
+    if let Some(path) = sensor_data_thumbnail(raw_path, image_id, orientation) {
+        println!("🔥 Generated thumbnail from sensor data: {}", path.display());
+        return Some(path);
+    }
+    
     eprintln!("❌ All methods failed for: {:?}", raw_path.file_name());
     eprintln!("   File exists: {}", raw_path.exists());
     eprintln!("   File size: {:?}", std::fs::metadata(raw_path).ok().map(|m| m.len()));
@@ -68,12 +75,12 @@ pub fn generate_thumbnail_slow(raw_path: &Path, image_id: i64) -> Option<PathBuf
 }
 
 /// Helper to save thumbnail from JPEG data
-fn save_thumbnail(jpeg_data: Vec<u8>, image_id: i64) -> Option<PathBuf> {
+fn save_thumbnail(jpeg_data: Vec<u8>, image_id: i64, orientation: Orientation) -> Option<PathBuf> {
     // Decode the JPEG
     let img = image::load_from_memory_with_format(&jpeg_data, ImageFormat::Jpeg).ok()?;
     
-    // Resize to thumbnail size
-    let thumbnail = img.resize(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Lanczos3);
+    // Resize to thumbnail size, then turn upright
+    let thumbnail = orientation.apply(img.resize(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Lanczos3));
     
     // Generate thumbnail filename
     let cache_dir = get_thumbnail_cache_dir();
@@ -86,6 +93,45 @@ fn save_thumbnail(jpeg_data: Vec<u8>, image_id: i64) -> Option<PathBuf> {
     Some(thumbnail_path)
 }
 
+/// Average color of an image's first small embedded JPEG (usually the EXIF
+/// thumbnail near the start of the file), shown as the grid placeholder while
+/// the real thumbnail is generated. Cheap enough to run during import; None
+/// when the first 256KB hold no decodable JPEG.
+pub fn placeholder_color(raw_path: &Path) -> Option<[u8; 3]> {
+    use std::io::Read;
+    
+    let mut data = Vec::new();
+    std::fs::File::open(raw_path).ok()?.take(256 * 1024).read_to_end(&mut data).ok()?;
+    
+    // SOI followed by a marker; stray FF D8 pairs in other data don't decode
+    let starts = data.windows(3).enumerate().filter(|(_, window)| *window == [0xFF, 0xD8, 0xFF]);
+    for (start, _) in starts.take(8) {
+        let Some(end_offset) = data[start..].windows(2).position(|window| window == [0xFF, 0xD9]) else {
+            break;
+        };
+        if let Some(color) = jpeg_average_color(&data[start..start + end_offset + 2]) {
+            return Some(color);
+        }
+    }
+    None
+}
+
+/// Mean RGB of a JPEG
+fn jpeg_average_color(jpeg_data: &[u8]) -> Option<[u8; 3]> {
+    let img = image::load_from_memory_with_format(jpeg_data, ImageFormat::Jpeg).ok()?.to_rgb8();
+    let count = img.pixels().len() as u64;
+    if count == 0 {
+        return None;
+    }
+    let mut sum = [0u64; 3];
+    for pixel in img.pixels() {
+        for (total, value) in sum.iter_mut().zip(pixel.0) {
+            *total += value as u64;
+        }
+    }
+    Some(sum.map(|total| (total / count) as u8))
+}
+
 /// Extract embedded JPEG - FAST VERSION (500KB)
 fn extract_embedded_jpeg_fast(raw_path: &Path) -> Option<Vec<u8>> {
     extract_jpeg_from_raw(raw_path, 256 * 1024, 50_000) // 256KB
@@ -160,7 +206,7 @@ pub fn thumbnail_exists(image_id: i64) -> bool {
 }
 
 /// Decode RAW file and generate thumbnail using rawloader's JPEG extraction
-fn decode_raw_to_thumbnail(raw_path: &Path, image_id: i64) -> Option<PathBuf> {
+fn decode_raw_to_thumbnail(raw_path: &Path, image_id: i64, orientation: Orientation) -> Option<PathBuf> {
     use std::io::Read;
     
     // Try reading entire file and extracting ALL JPEGs (no size limit)
@@ -193,7 +239,7 @@ fn decode_raw_to_thumbnail(raw_path: &Path, image_id: i64) -> Option<PathBuf> {
         // Try to decode this JPEG
         if let Ok(img) = image::load_from_memory_with_format(&jpeg_data, ImageFormat::Jpeg) {
             // Successfully decoded, resize and save
-            let thumbnail = img.resize(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Lanczos3);
+            let thumbnail = orientation.apply(img.resize(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Lanczos3));
             let cache_dir = get_thumbnail_cache_dir();
             let thumbnail_path = cache_dir.join(format!("{}.jpg", image_id));
             
@@ -207,3 +253,153 @@ fn decode_raw_to_thumbnail(raw_path: &Path, image_id: i64) -> Option<PathBuf> {
     None
 }
 
+/// Decode the sensor data with rawloader and build the thumbnail from it
+/// For RAWs without a usable embedded preview (some DNGs, stripped files)
+fn sensor_data_thumbnail(raw_path: &Path, image_id: i64, orientation: Orientation) -> Option<PathBuf> {
+    let raw = match loader::load_raw_data_blocking(&raw_path.to_string_lossy()) {
+        Ok(raw) => raw,
+        Err(e) => {
+            eprintln!("⚠️  Sensor data thumbnail failed: {}", e);
+            return None;
+        }
+    };
+    let mosaic = DynamicImage::ImageRgb8(mosaic_thumbnail(&raw, THUMBNAIL_SIZE)?);
+    let thumbnail = orientation.apply(mosaic.resize(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Triangle));
+    let thumbnail_path = get_thumbnail_cache_dir().join(format!("{}.jpg", image_id));
+    thumbnail.save(&thumbnail_path).ok()?;
+    Some(thumbnail_path)
+}
+
+/// Quick CPU debayer for thumbnails: every output pixel is one 2x2 Bayer tile
+/// (red, the average of both greens, blue) with the camera white balance and
+/// the sRGB curve. Tiles are skipped so the result is at least `target`
+/// pixels across; no interpolation or color matrix, it only has to be
+/// recognizable at grid size. None if the image is smaller than one tile.
+fn mosaic_thumbnail(raw: &RawDataResult, target: u32) -> Option<RgbImage> {
+    let (tiles_x, tiles_y) = (raw.width / 2, raw.height / 2);
+    if tiles_x == 0 || tiles_y == 0 {
+        return None;
+    }
+    let step = (tiles_x / target.max(1)).max(1);
+    let [red_x, red_y] = raw.cfa_pattern.offset();
+    let (blue_x, blue_y) = (1 - red_x, 1 - red_y);
+    let wb = raw.wb_multipliers;
+    let sample = |x: u32, y: u32| raw.levels.normalize(raw.data[(y * raw.width + x) as usize] as f32);
+    let to_byte = |v: f32| (crate::color::linear_to_srgb(v.clamp(0.0, 1.0)) * 255.0).round() as u8;
+    
+    Some(RgbImage::from_fn(tiles_x / step, tiles_y / step, |tx, ty| {
+        let (x, y) = (tx * step * 2, ty * step * 2);
+        let r = sample(x + red_x, y + red_y);
+        let g = (sample(x + blue_x, y + red_y) + sample(x + red_x, y + blue_y)) * 0.5;
+        let b = sample(x + blue_x, y + blue_y);
+        Rgb([to_byte(r * wb[0]), to_byte(g * wb[1]), to_byte(b * wb[2])])
+    }))
+}
+
+/// Save a render of the edited image as its thumbnail
+/// `rgba` is pipeline output, already upright with the edits applied. Every
+/// render gets a new file name (the grid's image cache is keyed by path, so
+/// rewriting the same file wouldn't show up); earlier renders are removed.
+pub fn save_rendered_thumbnail(rgba: Vec<u8>, width: u32, height: u32, image_id: i64) -> Result<PathBuf, String> {
+    let render = image::RgbaImage::from_raw(width, height, rgba)
+        .ok_or_else(|| format!("Render of image {} doesn't match its {}x{} size", image_id, width, height))?;
+    let thumbnail = DynamicImage::ImageRgba8(render)
+        .resize(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Triangle)
+        .to_rgb8();
+    
+    let cache_dir = get_thumbnail_cache_dir();
+    let prefix = format!("{}_edited_", image_id);
+    if let Ok(entries) = fs::read_dir(&cache_dir) {
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    let thumbnail_path = cache_dir.join(format!("{}{}.jpg", prefix, stamp));
+    thumbnail
+        .save(&thumbnail_path)
+        .map_err(|e| format!("Failed to save {}: {}", thumbnail_path.display(), e))?;
+    Ok(thumbnail_path)
+}
+
+/// Rotate an already cached JPEG (thumbnail or preview tier) a quarter turn in place
+/// Used by rotate left/right so the grid updates without re-extracting from the RAW
+pub fn rotate_cached_image(path: &Path, clockwise: bool) -> Result<(), String> {
+    let img = image::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let rotated = if clockwise { img.rotate90() } else { img.rotate270() };
+    rotated.save(path).map_err(|e| format!("Failed to save {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw::loader::{CfaPattern, SensorLevels};
+
+    #[test]
+    fn test_placeholder_color_from_embedded_jpeg() {
+        // Half teal, half orange: the average sits in between
+        let img = RgbImage::from_fn(32, 16, |x, _| if x < 16 { Rgb([0, 160, 160]) } else { Rgb([240, 120, 0]) });
+        let mut jpeg = Vec::new();
+        DynamicImage::ImageRgb8(img)
+            .write_to(&mut std::io::Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .unwrap();
+        let [r, g, b] = jpeg_average_color(&jpeg).unwrap();
+        for (channel, expected) in [(r, 120), (g, 140), (b, 80)] {
+            assert!(channel.abs_diff(expected) <= 4, "{:?}", (r, g, b));
+        }
+
+        // A RAW with the JPEG after some header bytes
+        let dir = std::env::temp_dir().join(format!("raw-editor-placeholder-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let raw_path = dir.join("DSC_0001.NEF");
+        fs::write(&raw_path, [vec![0u8; 512], jpeg].concat()).unwrap();
+        assert_eq!(placeholder_color(&raw_path), Some([r, g, b]));
+        assert_eq!(placeholder_color(&dir.join("missing.NEF")), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_mosaic_thumbnail_from_sensor_data() {
+        // Minimal 14-bit GRBG sensor (512 pedestal): a warm gray scene where
+        // red reads half as bright as green and blue, fixed by the as-shot WB
+        let (width, height) = (8u32, 6u32);
+        let levels = SensorLevels { black: 512.0, white: 16383.0 };
+        let value = |fraction: f32| (levels.black + (levels.white - levels.black) * fraction) as u16;
+        let [red_x, red_y] = CfaPattern::Grbg.offset();
+        let data = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| match ((x + 2 - red_x) % 2, (y + 2 - red_y) % 2) {
+                (0, 0) => value(0.25), // red
+                _ => value(0.5),       // green and blue
+            })
+            .collect();
+        let raw = RawDataResult {
+            data,
+            width,
+            height,
+            wb_multipliers: [2.0, 1.0, 1.0, 1.0],
+            color_matrix: [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+            forward_matrix: None,
+            cfa_pattern: CfaPattern::Grbg,
+            levels,
+        };
+
+        let thumbnail = mosaic_thumbnail(&raw, 256).unwrap();
+        assert_eq!(thumbnail.dimensions(), (4, 3));
+        for pixel in thumbnail.pixels() {
+            // Not black, and neutral once white balanced (0.5 linear ≈ 188)
+            let [r, g, b] = pixel.0;
+            assert!((187..=189).contains(&g), "{:?}", pixel);
+            assert_eq!((r, b), (g, g), "{:?}", pixel);
+        }
+
+        // Big sensors are sampled down to about the target size
+        let big = RawDataResult { width: 4000, height: 2, data: vec![value(0.5); 8000], ..raw };
+        assert_eq!(mosaic_thumbnail(&big, 256).unwrap().width(), 2000 / 7);
+    }
+}
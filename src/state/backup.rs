@@ -0,0 +1,152 @@
+/// Catalog backup: the whole catalog as one portable JSON file
+///
+/// A backup lists every image by path with its live edits, rating, flag,
+/// orientation and keywords (see `Library::export_catalog`). Caches, snapshots
+/// and EXIF stay out: they're rebuilt from the RAW files. Importing matches
+/// images by path; a known image gets the backup's edits and culling, an
+/// unknown one is added pointing at the same file.
+///
+/// Paths inside the photos folder (a setting) are stored relative to it with
+/// `/` separators, and resolved against the importing catalog's photos folder,
+/// so a backup survives moving the photos to another disk or machine. Paths
+/// outside it stay absolute.
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::edit::EditParams;
+
+/// Format version written into new backups (imports refuse newer ones)
+///
+/// 2: tone sliders on the -100..100 scale (see `EditParams::upgrade_legacy_ranges`)
+pub const BACKUP_VERSION: u32 = 2;
+
+/// Everything a backup file holds
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CatalogBackup {
+    pub version: u32,
+    pub images: Vec<BackupImage>,
+}
+
+/// One image in a backup
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BackupImage {
+    /// Absolute path, or relative to the photos folder when `relative`
+    pub path: String,
+    #[serde(default)]
+    pub relative: bool,
+    pub filename: String,
+    #[serde(default)]
+    pub rating: i32,
+    #[serde(default)]
+    pub flag: Option<String>,
+    #[serde(default = "upright")]
+    pub orientation: u8,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Live develop settings (None = unedited)
+    #[serde(default)]
+    pub edits: Option<EditParams>,
+}
+
+fn upright() -> u8 {
+    1
+}
+
+/// What importing a backup did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Images already in the catalog whose settings were replaced
+    pub updated: usize,
+    /// Images the catalog didn't have yet
+    pub added: usize,
+}
+
+impl std::fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} updated, {} added", self.updated, self.added)
+    }
+}
+
+/// Path as stored in a backup: relative (with `/` separators) when it's
+/// inside `root`, unchanged otherwise. The bool is true for relative paths.
+pub fn portable_path(path: &str, root: Option<&Path>) -> (String, bool) {
+    let relative = root.and_then(|root| Path::new(path).strip_prefix(root).ok());
+    match relative {
+        Some(relative) => {
+            let parts: Vec<String> = relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy().to_string())
+                .collect();
+            (parts.join("/"), true)
+        }
+        None => (path.to_string(), false),
+    }
+}
+
+/// Path a backup entry points at on this machine: relative paths are
+/// resolved against `root`, which they need
+pub fn resolve_path(image: &BackupImage, root: Option<&Path>) -> Result<String, String> {
+    if !image.relative {
+        return Ok(image.path.clone());
+    }
+    let root = root.ok_or_else(|| {
+        format!("{} is relative to a photos folder; set one in Preferences first", image.path)
+    })?;
+    let mut path = PathBuf::from(root);
+    for part in image.path.split('/') {
+        // Never step outside the photos folder
+        match Path::new(part).components().next() {
+            Some(Component::Normal(_)) => path.push(part),
+            None | Some(Component::CurDir) => {}
+            Some(_) => return Err(format!("Unsafe path in backup: {}", image.path)),
+        }
+    }
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, relative: bool) -> BackupImage {
+        BackupImage {
+            path: path.to_string(),
+            relative,
+            filename: "DSC_0001.NEF".to_string(),
+            rating: 0,
+            flag: None,
+            orientation: 1,
+            keywords: Vec::new(),
+            edits: None,
+        }
+    }
+
+    #[test]
+    fn test_portable_path_round_trip() {
+        let old_root = Path::new("/home/ana/Photos");
+        let new_root = Path::new("/mnt/archive/Photos");
+
+        let (stored, relative) = portable_path("/home/ana/Photos/2024/DSC_0001.NEF", Some(old_root));
+        assert_eq!((stored.as_str(), relative), ("2024/DSC_0001.NEF", true));
+        let resolved = resolve_path(&entry(&stored, relative), Some(new_root)).unwrap();
+        assert_eq!(PathBuf::from(resolved), new_root.join("2024").join("DSC_0001.NEF"));
+
+        // Outside the photos folder (or without one): absolute, as is
+        assert_eq!(portable_path("/tmp/x.NEF", Some(old_root)), ("/tmp/x.NEF".to_string(), false));
+        assert_eq!(portable_path("/tmp/x.NEF", None), ("/tmp/x.NEF".to_string(), false));
+        assert_eq!(resolve_path(&entry("/tmp/x.NEF", false), Some(new_root)).unwrap(), "/tmp/x.NEF");
+    }
+
+    #[test]
+    fn test_resolve_path_needs_a_safe_root() {
+        assert!(resolve_path(&entry("2024/a.NEF", true), None).is_err());
+        assert!(resolve_path(&entry("../etc/passwd", true), Some(Path::new("/photos"))).is_err());
+    }
+
+    #[test]
+    fn test_missing_fields_default() {
+        let image: BackupImage = serde_json::from_str(r#"{"path": "/a.NEF", "filename": "DSC_0001.NEF"}"#).unwrap();
+        assert_eq!(image, entry("/a.NEF", false));
+    }
+}
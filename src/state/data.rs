@@ -3,6 +3,15 @@
 /// These structs represent the data model that flows between
 /// the database layer and the UI layer.
 
+/// A named look saved for one image, so several versions of the same RAW
+/// can be kept side by side (see `Library::save_snapshot`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub id: i64,
+    pub name: String,
+    pub params: super::edit::EditParams,
+}
+
 /// Represents a single image in the library
 #[derive(Debug, Clone, PartialEq)]
 pub struct Image {
@@ -20,4 +29,128 @@ pub struct Image {
     pub cache_path_working: Option<String>,
     /// File status: 'exists' or 'deleted'
     pub file_status: String,
+    /// Star rating, 0 (unrated) to 5
+    pub rating: i32,
+    /// Culling flag: `FLAG_PICK`, `FLAG_REJECT`, or None (unflagged)
+    pub flag: Option<String>,
+    /// EXIF orientation (1-8) with the user's manual rotations applied
+    pub orientation: u8,
+    /// Has develop settings other than the defaults
+    pub is_edited: bool,
+    /// Average color of the embedded JPEG, shown while the thumbnail is pending
+    pub avg_color: Option<[u8; 3]>,
+}
+
+/// Flag value for picked images
+pub const FLAG_PICK: &str = "pick";
+/// Flag value for rejected images
+pub const FLAG_REJECT: &str = "reject";
+
+/// Sort order for the Library grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    /// Newest imports first
+    #[default]
+    ImportDate,
+    /// Filename A-Z
+    Filename,
+    /// Highest rated first
+    Rating,
+}
+
+impl SortOrder {
+    /// All sort orders, in the order shown in the UI
+    pub const ALL: [SortOrder; 3] = [SortOrder::ImportDate, SortOrder::Filename, SortOrder::Rating];
+}
+
+/// Which import runs the Library grid shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportScope {
+    /// Everything in the catalog
+    #[default]
+    All,
+    /// Only the most recent import run (this session's or an earlier one)
+    LastImport,
+    /// Everything imported since the app started
+    ThisSession,
+}
+
+impl ImportScope {
+    /// All scopes, in the order shown in the UI
+    pub const ALL: [ImportScope; 3] = [ImportScope::All, ImportScope::LastImport, ImportScope::ThisSession];
+}
+
+impl std::fmt::Display for ImportScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportScope::All => write!(f, "All imports"),
+            ImportScope::LastImport => write!(f, "Last import"),
+            ImportScope::ThisSession => write!(f, "This session"),
+        }
+    }
+}
+
+impl std::fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortOrder::ImportDate => write!(f, "Import date"),
+            SortOrder::Filename => write!(f, "Filename"),
+            SortOrder::Rating => write!(f, "Rating"),
+        }
+    }
+}
+
+/// Which images the Library grid shows (default: everything)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LibraryFilter {
+    /// Only images with at least one star
+    pub only_rated: bool,
+    /// Only picked or rejected images
+    pub only_flagged: bool,
+    /// Hide images whose RAW file is missing on disk
+    pub hide_deleted: bool,
+    /// Hide images flagged reject
+    pub hide_rejects: bool,
+}
+
+/// Ids of the images between `from` and `to` (inclusive) in grid order
+/// Used for Shift+click range selection; empty if either id isn't shown.
+pub fn ids_between(images: &[Image], from: i64, to: i64) -> Vec<i64> {
+    let position = |id: i64| images.iter().position(|img| img.id == id);
+    let (Some(a), Some(b)) = (position(from), position(to)) else {
+        return Vec::new();
+    };
+    images[a.min(b)..=a.max(b)].iter().map(|img| img.id).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(id: i64) -> Image {
+        Image {
+            id,
+            filename: format!("DSC_{:04}.NEF", id),
+            path: format!("/photos/DSC_{:04}.NEF", id),
+            cache_path_thumb: None,
+            cache_path_instant: None,
+            cache_path_working: None,
+            file_status: "exists".to_string(),
+            rating: 0,
+            flag: None,
+            orientation: 1,
+            is_edited: false,
+            avg_color: None,
+        }
+    }
+
+    #[test]
+    fn test_ids_between_follows_grid_order() {
+        // Grid sorted newest first: ids are not in ascending order
+        let images: Vec<Image> = [5, 3, 9, 1, 7].into_iter().map(image).collect();
+        assert_eq!(ids_between(&images, 3, 1), vec![3, 9, 1]);
+        assert_eq!(ids_between(&images, 1, 3), vec![3, 9, 1]);
+        assert_eq!(ids_between(&images, 7, 7), vec![7]);
+        assert!(ids_between(&images, 3, 42).is_empty());
+    }
 }
@@ -5,13 +5,541 @@
 /// enabling complete non-destructive editing with undo/redo capability.
 
 use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Number of control points on the tone curve (including both endpoints)
+pub const CURVE_POINTS: usize = 5;
+
+/// Number of entries in the tone curve lookup table uploaded to the GPU
+pub const CURVE_LUT_SIZE: usize = 256;
+
+/// Demosaic algorithm used to reconstruct RGB from the Bayer mosaic
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DemosaicMethod {
+    /// Average of the 2 or 4 nearest same-color neighbors
+    #[default]
+    Bilinear,
+    /// Malvar-He-Cutler: bilinear plus a gradient correction from the
+    /// center channel (sharper edges, less false color)
+    Malvar,
+}
+
+impl DemosaicMethod {
+    /// All methods, in the order shown in the UI
+    pub const ALL: [DemosaicMethod; 2] = [DemosaicMethod::Bilinear, DemosaicMethod::Malvar];
+}
+
+impl std::fmt::Display for DemosaicMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DemosaicMethod::Bilinear => write!(f, "Bilinear"),
+            DemosaicMethod::Malvar => write!(f, "Malvar-He-Cutler"),
+        }
+    }
+}
+
+/// How the contrast slider shapes the tone response
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneMode {
+    /// Straight-line contrast around mid gray (the original behavior)
+    #[default]
+    Linear,
+    /// Blend toward a smoothstep curve: contrast sets how far
+    SCurve,
+    /// ACES-style filmic curve with a soft highlight shoulder, after the
+    /// linear contrast
+    Filmic,
+}
+
+impl ToneMode {
+    /// All modes, in the order shown in the UI
+    pub const ALL: [ToneMode; 3] = [ToneMode::Linear, ToneMode::SCurve, ToneMode::Filmic];
+}
+
+impl std::fmt::Display for ToneMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ToneMode::Linear => write!(f, "Linear"),
+            ToneMode::SCurve => write!(f, "S-curve"),
+            ToneMode::Filmic => write!(f, "Filmic"),
+        }
+    }
+}
+
+/// Normalized crop rectangle (x, y, width, height), 0.0-1.0 of the straightened frame
+pub type CropRect = (f32, f32, f32, f32);
+
+/// Crop covering the whole frame
+pub const FULL_FRAME: CropRect = (0.0, 0.0, 1.0, 1.0);
+
+/// Aspect ratio presets for the crop tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CropAspect {
+    /// Any shape
+    #[default]
+    Free,
+    /// 1:1
+    Square,
+    /// 3:2 (full-frame sensor)
+    ThreeTwo,
+    /// 16:9 (widescreen)
+    SixteenNine,
+}
+
+impl CropAspect {
+    /// All presets, in the order shown in the UI
+    pub const ALL: [CropAspect; 4] = [
+        CropAspect::Free,
+        CropAspect::Square,
+        CropAspect::ThreeTwo,
+        CropAspect::SixteenNine,
+    ];
+    
+    /// Long side / short side in pixels (None = free)
+    pub fn ratio(self) -> Option<f32> {
+        match self {
+            CropAspect::Free => None,
+            CropAspect::Square => Some(1.0),
+            CropAspect::ThreeTwo => Some(3.0 / 2.0),
+            CropAspect::SixteenNine => Some(16.0 / 9.0),
+        }
+    }
+}
+
+impl std::fmt::Display for CropAspect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CropAspect::Free => write!(f, "Free"),
+            CropAspect::Square => write!(f, "1:1"),
+            CropAspect::ThreeTwo => write!(f, "3:2"),
+            CropAspect::SixteenNine => write!(f, "16:9"),
+        }
+    }
+}
+
+/// Largest rectangle with the given long/short pixel ratio, centered inside `rect`
+/// Keeps the orientation (landscape/portrait) of `rect`.
+pub fn fit_crop_to_aspect(rect: CropRect, ratio: f32, image_width: u32, image_height: u32) -> CropRect {
+    let (x, y, w, h) = rect;
+    let pixel_width = w * image_width as f32;
+    let pixel_height = h * image_height as f32;
+    let target = if pixel_width >= pixel_height { ratio } else { 1.0 / ratio };
+    
+    let (new_w, new_h) = if pixel_width / pixel_height > target {
+        (pixel_height * target / image_width as f32, h)
+    } else {
+        (w, pixel_width / target / image_height as f32)
+    };
+    (x + (w - new_w) * 0.5, y + (h - new_h) * 0.5, new_w, new_h)
+}
+
+/// Straighten range in degrees either way (the slider's ends)
+pub const MAX_ROTATION_DEGREES: f32 = 45.0;
+
+/// Rotation that levels a line drawn over the straightened frame
+///
+/// `dx`, `dy` is the line's direction in screen pixels (y down) as it looks
+/// with `rotation_degrees` applied. Mostly-horizontal lines (a horizon) end
+/// up horizontal, mostly-vertical ones (a building edge) vertical.
+pub fn level_rotation(rotation_degrees: f32, dx: f32, dy: f32) -> f32 {
+    // A line has no direction, and steep lines level to vertical:
+    // only the tilt away from the nearest axis matters
+    let mut tilt = dy.atan2(dx).to_degrees();
+    while tilt > 45.0 {
+        tilt -= 90.0;
+    }
+    while tilt <= -45.0 {
+        tilt += 90.0;
+    }
+    (rotation_degrees - tilt).clamp(-MAX_ROTATION_DEGREES, MAX_ROTATION_DEGREES)
+}
+
+/// The same crop after the image is turned a quarter turn (rotate left/right),
+/// so it keeps framing the same part of the picture
+pub fn rotate_crop(rect: CropRect, clockwise: bool) -> CropRect {
+    let (x, y, w, h) = rect;
+    if clockwise {
+        (1.0 - y - h, x, h, w)
+    } else {
+        (y, 1.0 - x - w, h, w)
+    }
+}
+
+/// Output size in pixels of a `width` x `height` image cropped to `rect`
+pub fn crop_size(rect: CropRect, width: u32, height: u32) -> (u32, u32) {
+    (
+        ((rect.2 * width as f32).round() as u32).clamp(1, width),
+        ((rect.3 * height as f32).round() as u32).clamp(1, height),
+    )
+}
+
+/// A single tone curve control point (both axes 0.0 to 1.0, display space)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct CurvePoint {
+    /// Input tone
+    pub x: f32,
+    /// Output tone
+    pub y: f32,
+}
+
+/// Point tone curve applied after levels
+///
+/// A fixed number of control points (so `EditParams` stays `Copy`) joined
+/// by a monotone cubic spline. Endpoints stay pinned to x = 0 and x = 1;
+/// inner points can move freely between their neighbours.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ToneCurve {
+    pub points: [CurvePoint; CURVE_POINTS],
+}
+
+impl Default for ToneCurve {
+    /// Identity curve: points evenly spaced along the diagonal
+    fn default() -> Self {
+        let mut points = [CurvePoint { x: 0.0, y: 0.0 }; CURVE_POINTS];
+        for (i, point) in points.iter_mut().enumerate() {
+            let t = i as f32 / (CURVE_POINTS - 1) as f32;
+            *point = CurvePoint { x: t, y: t };
+        }
+        Self { points }
+    }
+}
+
+impl ToneCurve {
+    /// Check if every control point sits on the diagonal (no adjustment)
+    pub fn is_identity(&self) -> bool {
+        self.points.iter().all(|p| (p.x - p.y).abs() < 1e-4)
+    }
+    
+    /// Move a control point, keeping endpoints pinned horizontally and
+    /// inner points strictly between their neighbours
+    pub fn set_point(&mut self, index: usize, x: f32, y: f32) {
+        if index >= CURVE_POINTS {
+            return;
+        }
+        const MIN_GAP: f32 = 0.01;
+        let x = if index == 0 {
+            0.0
+        } else if index == CURVE_POINTS - 1 {
+            1.0
+        } else {
+            let lo = self.points[index - 1].x + MIN_GAP;
+            let hi = self.points[index + 1].x - MIN_GAP;
+            x.clamp(lo, hi.max(lo))
+        };
+        self.points[index] = CurvePoint { x, y: y.clamp(0.0, 1.0) };
+    }
+    
+    /// Evaluate the curve at `x` using monotone cubic (Fritsch-Carlson)
+    /// interpolation, so the curve never overshoots between points
+    pub fn evaluate(&self, x: f32) -> f32 {
+        let p = &self.points;
+        let x = x.clamp(0.0, 1.0);
+        
+        // Secant slopes between consecutive points
+        let mut secants = [0.0f32; CURVE_POINTS - 1];
+        for i in 0..CURVE_POINTS - 1 {
+            let dx = (p[i + 1].x - p[i].x).max(1e-6);
+            secants[i] = (p[i + 1].y - p[i].y) / dx;
+        }
+        
+        // Tangents at each point, limited to keep the spline monotone
+        let mut tangents = [0.0f32; CURVE_POINTS];
+        tangents[0] = secants[0];
+        tangents[CURVE_POINTS - 1] = secants[CURVE_POINTS - 2];
+        for i in 1..CURVE_POINTS - 1 {
+            tangents[i] = if secants[i - 1] * secants[i] <= 0.0 {
+                0.0
+            } else {
+                (secants[i - 1] + secants[i]) * 0.5
+            };
+        }
+        for i in 0..CURVE_POINTS - 1 {
+            if secants[i] == 0.0 {
+                tangents[i] = 0.0;
+                tangents[i + 1] = 0.0;
+            } else {
+                let a = tangents[i] / secants[i];
+                let b = tangents[i + 1] / secants[i];
+                let h = a * a + b * b;
+                if h > 9.0 {
+                    let t = 3.0 / h.sqrt();
+                    tangents[i] = t * a * secants[i];
+                    tangents[i + 1] = t * b * secants[i];
+                }
+            }
+        }
+        
+        // Find the segment containing x and evaluate the Hermite cubic
+        let seg = (0..CURVE_POINTS - 1)
+            .find(|&i| x <= p[i + 1].x)
+            .unwrap_or(CURVE_POINTS - 2);
+        let h = (p[seg + 1].x - p[seg].x).max(1e-6);
+        let t = ((x - p[seg].x) / h).clamp(0.0, 1.0);
+        let t2 = t * t;
+        let t3 = t2 * t;
+        let y = (2.0 * t3 - 3.0 * t2 + 1.0) * p[seg].y
+            + (t3 - 2.0 * t2 + t) * h * tangents[seg]
+            + (-2.0 * t3 + 3.0 * t2) * p[seg + 1].y
+            + (t3 - t2) * h * tangents[seg + 1];
+        
+        y.clamp(0.0, 1.0)
+    }
+    
+    /// Sample the curve into a lookup table for the GPU
+    pub fn to_lut(self) -> [f32; CURVE_LUT_SIZE] {
+        let mut lut = [0.0f32; CURVE_LUT_SIZE];
+        for (i, value) in lut.iter_mut().enumerate() {
+            *value = self.evaluate(i as f32 / (CURVE_LUT_SIZE - 1) as f32);
+        }
+        lut
+    }
+}
+
+/// Default B&W channel mix (Rec. 709 luminance, same as saturation -100)
+pub const DEFAULT_BW_MIX: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+/// Number of color bands in the HSL panel
+pub const HSL_BANDS: usize = 8;
+
+/// HSL color bands, in hue order (names shown in the UI)
+/// Band centers are at 0°, 30°, 60°, 120°, 180°, 240°, 270° and 300°; pixels
+/// between two centers get a linear blend of both bands (`hsl_adjustment` in
+/// the shader).
+pub const HSL_BAND_NAMES: [&str; HSL_BANDS] = [
+    "Red", "Orange", "Yellow", "Green", "Aqua", "Blue", "Purple", "Magenta",
+];
+
+/// Hue / saturation / luminance adjustment for one color band
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(default)]
+pub struct HslBand {
+    /// Hue shift (-100 to +100, ±30° at the extremes)
+    pub hue: f32,
+    /// Saturation (-100 = grayscale to +100 = double)
+    pub saturation: f32,
+    /// Luminance (-100 to +100)
+    pub luminance: f32,
+}
+
+impl HslBand {
+    /// True when this band changes nothing
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Which slider of an HSL band changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HslChannel {
+    Hue,
+    Saturation,
+    Luminance,
+}
+
+/// Most local adjustment masks one image can hold (the shader loops over
+/// at most this many)
+pub const MAX_MASKS: usize = 8;
+
+/// Shape of a local adjustment mask
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MaskKind {
+    /// Ellipse: full effect inside, fading out over the feather
+    #[default]
+    Radial,
+    /// Graduated filter: full effect behind the start line, none past the end
+    Linear,
+}
+
+impl MaskKind {
+    /// All mask shapes, in the order shown in the UI
+    pub const ALL: [MaskKind; 2] = [MaskKind::Radial, MaskKind::Linear];
+}
+
+impl std::fmt::Display for MaskKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MaskKind::Radial => write!(f, "Radial"),
+            MaskKind::Linear => write!(f, "Linear"),
+        }
+    }
+}
+
+/// One local adjustment: a gradient mask and the deltas it applies
+///
+/// Points are in the straightened frame before the crop (0-1 on both axes),
+/// so a mask stays on the same part of the picture when the crop changes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct LocalMask {
+    pub kind: MaskKind,
+    /// Radial: center. Linear: where the gradient starts (full effect)
+    pub start: [f32; 2],
+    /// Radial: a corner of the ellipse's bounding box (sets both radii).
+    /// Linear: where the gradient ends (no effect)
+    pub end: [f32; 2],
+    /// Radial only: fraction of the radius the effect fades over (0 to 1)
+    pub feather: f32,
+    /// Exposure delta in stops (-5.0 to +5.0)
+    pub exposure: f32,
+    /// Contrast delta (-100.0 to +100.0)
+    pub contrast: f32,
+    /// Saturation delta (-100.0 to +100.0)
+    pub saturation: f32,
+}
+
+impl Default for LocalMask {
+    /// A radial mask in the middle of the frame that changes nothing yet
+    fn default() -> Self {
+        Self {
+            kind: MaskKind::Radial,
+            start: [0.5, 0.5],
+            end: [0.75, 0.75],
+            feather: 0.5,
+            exposure: 0.0,
+            contrast: 0.0,
+            saturation: 0.0,
+        }
+    }
+}
+
+impl LocalMask {
+    /// New mask of the given shape (linear ones run top to bottom)
+    pub fn new(kind: MaskKind) -> Self {
+        match kind {
+            MaskKind::Radial => Self::default(),
+            MaskKind::Linear => Self { kind, start: [0.5, 0.2], end: [0.5, 0.6], ..Self::default() },
+        }
+    }
+    
+    /// Set one slider (values are clamped to the slider's range)
+    pub fn set(&mut self, setting: MaskSetting, value: f32) {
+        match setting {
+            MaskSetting::Exposure => self.exposure = value.clamp(-5.0, 5.0),
+            MaskSetting::Contrast => self.contrast = value.clamp(-100.0, 100.0),
+            MaskSetting::Saturation => self.saturation = value.clamp(-100.0, 100.0),
+            MaskSetting::Feather => self.feather = value.clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// Which slider of a local mask changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskSetting {
+    Exposure,
+    Contrast,
+    Saturation,
+    Feather,
+}
+
+/// The image's local adjustment masks, in the order they were added
+///
+/// A fixed array plus a length, so `EditParams` stays `Copy`; serialized as
+/// a plain JSON array of the masks in use (extra entries are dropped).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalMasks {
+    items: [LocalMask; MAX_MASKS],
+    len: usize,
+}
+
+impl LocalMasks {
+    /// Masks in use
+    pub fn as_slice(&self) -> &[LocalMask] {
+        &self.items[..self.len]
+    }
+    
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    
+    /// Mask at `index`, if there is one
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut LocalMask> {
+        self.items[..self.len].get_mut(index)
+    }
+    
+    /// Add a mask at the end; returns its index (None when all slots are taken)
+    pub fn push(&mut self, mask: LocalMask) -> Option<usize> {
+        if self.len == MAX_MASKS {
+            return None;
+        }
+        self.items[self.len] = mask;
+        self.len += 1;
+        Some(self.len - 1)
+    }
+    
+    /// Remove the mask at `index`, moving later ones down
+    pub fn remove(&mut self, index: usize) {
+        if index >= self.len {
+            return;
+        }
+        self.items.copy_within(index + 1..self.len, index);
+        self.len -= 1;
+        // Unused slots stay at the default so they never leak into comparisons
+        self.items[self.len] = LocalMask::default();
+    }
+}
+
+impl PartialEq for LocalMasks {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Serialize for LocalMasks {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_slice().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LocalMasks {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let list = Vec::<LocalMask>::deserialize(deserializer)?;
+        let mut masks = LocalMasks::default();
+        for mask in list.into_iter().take(MAX_MASKS) {
+            masks.push(mask);
+        }
+        Ok(masks)
+    }
+}
+
+/// Fraction of pixels auto tone lets clip at each end of the histogram
+pub const AUTO_TONE_CLIP: f32 = 0.001;
+
+/// Linear value auto tone brings the average luminance to (18% gray)
+const AUTO_TONE_MID_GRAY: f32 = 0.18;
+
+/// First bin (walking `bins` in order) where more than `clip` pixels have been passed
+fn clip_point(channel: &[u32; 256], bins: impl IntoIterator<Item = usize>, clip: u64) -> Option<usize> {
+    let mut seen = 0u64;
+    bins.into_iter().find(|&bin| {
+        seen += channel[bin] as u64;
+        seen > clip
+    })
+}
+
+/// How far Whites / Blacks at ±100 move the white and black points
+pub const LEVELS_RANGE: f32 = 0.2;
+
+/// How much Highlights at ±100 scales white (±50%)
+pub const HIGHLIGHTS_RANGE: f32 = 0.5;
+
+/// Version of the settings scales, written next to settings stored outside
+/// the catalog (XMP sidecars, PNG recipes) so older ones can be upgraded
+///
+/// 2: tone sliders on the -100..100 scale (see `EditParams::upgrade_legacy_ranges`)
+pub const SETTINGS_VERSION: u32 = 2;
 
 /// All edit parameters for a RAW image
 /// 
 /// These values represent adjustments that will be applied to the image
 /// during the rendering pipeline. All edits are non-destructive and stored
-/// as JSON in the database.
+/// as JSON in the database. Fields missing from older JSON blobs fall back
+/// to their default value.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
 pub struct EditParams {
     // ========== Exposure & Tone ==========
     
@@ -22,30 +550,42 @@ pub struct EditParams {
     pub exposure: f32,
     
     /// Contrast adjustment (-100.0 to +100.0)
-    /// - Negative values reduce contrast (flatten)
-    /// - Positive values increase contrast (boost midtones)
+    /// - Negative values reduce contrast (-100 = flat mid-gray)
+    /// - Positive values increase contrast (+100 = twice the slope around
+    ///   mid-gray; the S-curve reaches full strength)
     /// - 0.0 = no adjustment
     pub contrast: f32,
     
+    /// Shape of the contrast response (Linear keeps the plain slider)
+    pub tone_mode: ToneMode,
+    
     /// Highlights adjustment (-100.0 to +100.0)
-    /// - Negative values recover blown highlights
-    /// - Positive values boost bright areas
+    /// - Negative values recover blown highlights (-100 halves white)
+    /// - Positive values boost bright areas (+100 makes white 1.5x)
     /// - 0.0 = no adjustment
     pub highlights: f32,
     
+    /// Highlight reconstruction (0.0 to 1.0, displayed as 0 to 100)
+    /// - Rebuilds channels that clipped at the sensor's white level from the
+    ///   ones that didn't, so blown highlights stay neutral instead of magenta
+    /// - 0.0 = off (clipped channels are left as they are)
+    pub highlight_recovery: f32,
+    
     /// Shadows adjustment (-100.0 to +100.0)
-    /// - Negative values darken shadows
-    /// - Positive values lift/recover shadows
+    /// - Negative values darken shadows (-100 crushes the darkest tones to black)
+    /// - Positive values lift/recover shadows (+100 doubles them)
     /// - 0.0 = no adjustment
     pub shadows: f32,
     
     /// Whites adjustment (-100.0 to +100.0)
-    /// - Adjusts the white point
+    /// - Moves the white point by up to `LEVELS_RANGE` (see `white_point`)
+    /// - Positive values brighten (+100 clips at 0.8), negative values dim
     /// - 0.0 = no adjustment
     pub whites: f32,
     
     /// Blacks adjustment (-100.0 to +100.0)
-    /// - Adjusts the black point
+    /// - Moves the black point by up to `LEVELS_RANGE` (see `black_point`)
+    /// - Negative values crush (-100 clips at 0.2), positive values lift
     /// - 0.0 = no adjustment
     pub blacks: f32,
     
@@ -61,6 +601,41 @@ pub struct EditParams {
     /// - -100.0 = grayscale, 0.0 = original, +100.0 = maximum saturation
     pub saturation: f32,
     
+    // ========== Black & White ==========
+    
+    /// Convert to black and white using `bw_mix` (color sliders have no effect)
+    pub monochrome: bool,
+    
+    /// Channel mixer for the B&W conversion: gray = R*w0 + G*w1 + B*w2
+    /// (defaults to Rec. 709 luminance weights)
+    pub bw_mix: [f32; 3],
+    
+    // ========== Film Negative ==========
+    
+    /// Invert a scanned film negative (after white balance and the color matrix)
+    pub invert: bool,
+    
+    /// Color of the unexposed film base (the orange mask), in the same space
+    /// the inversion runs in; it ends up black. [1, 1, 1] = plain inversion
+    pub film_base: [f32; 3],
+    
+    // ========== Split Toning ==========
+    
+    /// Color pushed into the shadows (RGB, 0.0 to 1.0 each)
+    /// - Only its hue and strength count: the tint's own brightness is taken
+    ///   out, so it recolors without lightening
+    /// - [0, 0, 0] = no tint
+    pub shadow_tint: [f32; 3],
+    
+    /// Color pushed into the highlights (same as `shadow_tint`)
+    pub highlight_tint: [f32; 3],
+    
+    /// Split toning balance (-1.0 to +1.0, displayed as -100 to +100)
+    /// - Positive values move the crossover down, so the highlight tint
+    ///   reaches further into the shadows; negative values the other way
+    /// - 0.0 = crossover at mid-gray
+    pub balance: f32,
+    
     // ========== White Balance ==========
     
     /// Temperature adjustment (-1.0 to +1.0, displayed as -100 to +100)
@@ -74,6 +649,83 @@ pub struct EditParams {
     /// - Positive values = more green
     /// - 0.0 = as-shot
     pub tint: f32,
+    
+    /// White balance multipliers [R, G, B, G2] picked with the eyedropper,
+    /// used instead of the camera's as-shot ones (temperature and tint still
+    /// apply on top). None = as shot
+    pub wb_override: Option<[f32; 4]>,
+    
+    // ========== Tone Curve ==========
+    
+    /// Point tone curve applied after levels (identity = no adjustment)
+    pub curve: ToneCurve,
+    
+    // ========== HSL ==========
+    
+    /// Per-band hue/saturation/luminance (see `HSL_BAND_NAMES`), all zero = no adjustment
+    pub hsl: [HslBand; HSL_BANDS],
+    
+    // ========== Local Adjustments ==========
+    
+    /// Radial and linear gradient masks with their own exposure, contrast
+    /// and saturation deltas (empty = none; the shader skips the step)
+    pub masks: LocalMasks,
+    
+    // ========== Detail ==========
+    
+    /// Demosaic algorithm (Bilinear is the fast default)
+    pub demosaic: DemosaicMethod,
+    
+    /// Clarity / local contrast (-1.0 to +1.0, displayed as -100 to +100)
+    /// - Positive values boost midtone detail against its surroundings
+    /// - Negative values soften it
+    /// - 0.0 = no adjustment
+    pub clarity: f32,
+    
+    /// Luminance noise reduction (0.0 to 1.0, displayed as 0 to 100)
+    /// - Smooths brightness grain while keeping edges
+    /// - 0.0 = off (the shader skips noise reduction entirely)
+    pub luma_nr: f32,
+    
+    /// Color noise reduction (0.0 to 1.0, displayed as 0 to 100)
+    /// - Smooths color blotches; brightness detail is untouched
+    pub chroma_nr: f32,
+    
+    /// Capture sharpening strength (0.0 to 1.5, displayed as 0 to 150)
+    /// - 0.0 = off (output is identical to no sharpening)
+    pub sharpen_amount: f32,
+    
+    /// Sharpening radius in source pixels (0.5 to 3.0, default 1.0)
+    pub sharpen_radius: f32,
+    
+    // ========== Effects ==========
+    
+    /// Post-crop vignette (-1.0 to +1.0, displayed as -100 to +100)
+    /// - Negative values darken the corners, positive values brighten them
+    /// - 0.0 = no vignette
+    pub vignette_amount: f32,
+    
+    /// Where the vignette starts, as a fraction of the center-to-corner
+    /// distance (0.0 to 1.0, default 0.5; higher = smaller vignette)
+    pub vignette_midpoint: f32,
+    
+    // ========== Geometry ==========
+    
+    /// Correct distortion and vignetting from the lens profile (see lens.rs),
+    /// applied before crop and straighten
+    pub lens_correction: bool,
+    
+    /// Manual distortion correction on top of the profile (-1.0 to +1.0,
+    /// displayed as -100 to +100)
+    /// - Positive values straighten barrel distortion (lines bowing outward)
+    /// - Negative values straighten pincushion distortion
+    pub lens_distortion: f32,
+    
+    /// Crop rectangle in the straightened frame (None = uncropped)
+    pub crop_rect: Option<CropRect>,
+    
+    /// Straighten angle in degrees around the image center (positive = clockwise)
+    pub rotation_degrees: f32,
 }
 
 impl Default for EditParams {
@@ -83,14 +735,39 @@ impl Default for EditParams {
             // All defaults are "no adjustment"
             exposure: 0.0,
             contrast: 0.0,
+            tone_mode: ToneMode::default(),
             highlights: 0.0,
+            highlight_recovery: 0.0,
             shadows: 0.0,
-            whites: 1.0,   // Phase 16: Default white point (no adjustment)
-            blacks: 0.0,   // Phase 16: Default black point (no adjustment)
+            whites: 0.0,
+            blacks: 0.0,
             vibrance: 0.0,
             saturation: 0.0,
+            monochrome: false,
+            bw_mix: DEFAULT_BW_MIX,
+            invert: false,
+            film_base: [1.0; 3],
+            shadow_tint: [0.0; 3],
+            highlight_tint: [0.0; 3],
+            balance: 0.0,
             temperature: 0.0,  // Phase 18: Manual white balance (as-shot)
             tint: 0.0,         // Phase 18: Manual white balance (as-shot)
+            wb_override: None,
+            curve: ToneCurve::default(),
+            hsl: [HslBand::default(); HSL_BANDS],
+            masks: LocalMasks::default(),
+            demosaic: DemosaicMethod::default(),
+            clarity: 0.0,
+            luma_nr: 0.0,
+            chroma_nr: 0.0,
+            sharpen_amount: 0.0,
+            sharpen_radius: 1.0,
+            vignette_amount: 0.0,
+            vignette_midpoint: 0.5,
+            lens_correction: false,
+            lens_distortion: 0.0,
+            crop_rect: None,       // Full frame
+            rotation_degrees: 0.0, // Not straightened
         }
     }
 }
@@ -111,15 +788,461 @@ impl EditParams {
         serde_json::from_str(json)
     }
     
+    /// Input level that maps to white (1.0 unless Whites is moved)
+    pub fn white_point(&self) -> f32 {
+        1.0 - self.whites / 100.0 * LEVELS_RANGE
+    }
+    
+    /// Input level that maps to black (0.0 unless Blacks is moved)
+    pub fn black_point(&self) -> f32 {
+        -self.blacks / 100.0 * LEVELS_RANGE
+    }
+    
+    /// Convert settings saved before the tone sliders all went to -100..100:
+    /// highlights and shadows were -1..1 (highlights at full strength, so
+    /// past ±0.5 they're clamped), whites and blacks were the white and black
+    /// points themselves (0.8..1.2 and 0..0.2), and the S-curve reached full
+    /// strength at a contrast of 10. Linear and filmic contrast already used
+    /// the -100..100 scale and are kept.
+    pub fn upgrade_legacy_ranges(self) -> EditParams {
+        EditParams {
+            highlights: (self.highlights / HIGHLIGHTS_RANGE * 100.0).clamp(-100.0, 100.0),
+            shadows: (self.shadows * 100.0).clamp(-100.0, 100.0),
+            whites: ((1.0 - self.whites) / LEVELS_RANGE * 100.0).clamp(-100.0, 100.0),
+            blacks: (-self.blacks / LEVELS_RANGE * 100.0).clamp(-100.0, 100.0),
+            contrast: if self.tone_mode == ToneMode::SCurve {
+                (self.contrast * 10.0).clamp(-100.0, 100.0)
+            } else {
+                self.contrast
+            },
+            ..self
+        }
+    }
+    
+    /// `upgrade_legacy_ranges` for settings JSON saved before the change. Blobs
+    /// older than the Whites slider don't have it, and its old default (a
+    /// white point of 1.0) isn't the new one.
+    pub fn from_legacy_json(json: &str) -> Result<Self, serde_json::Error> {
+        let mut value: serde_json::Value = serde_json::from_str(json)?;
+        if let Some(fields) = value.as_object_mut() {
+            fields.entry("whites").or_insert(serde_json::json!(1.0));
+        }
+        Ok(serde_json::from_value::<EditParams>(value)?.upgrade_legacy_ranges())
+    }
+    
     /// Check if this represents an unedited image (all values at default)
     pub fn is_unedited(&self) -> bool {
         *self == Self::default()
     }
     
+    /// The settings that differ from default, as (label, value) pairs in
+    /// roughly sidebar order, with values shown the way their sliders show them
+    /// (empty for an unedited image)
+    pub fn diff_summary(&self) -> Vec<(String, String)> {
+        let defaults = Self::default();
+        let mut changes: Vec<(&str, String)> = Vec::new();
+        // Signed slider stored as -1..1, shown as -100..100
+        let percent = |v: f32| format!("{:+.0}", v * 100.0);
+        
+        if self.exposure != defaults.exposure {
+            changes.push(("Exposure", format!("{:+.2} EV", self.exposure)));
+        }
+        if self.highlights != defaults.highlights {
+            changes.push(("Highlights", format!("{:+.0}", self.highlights)));
+        }
+        if self.highlight_recovery != defaults.highlight_recovery {
+            changes.push(("Highlight Recovery", format!("{:.0}", self.highlight_recovery * 100.0)));
+        }
+        if self.shadows != defaults.shadows {
+            changes.push(("Shadows", format!("{:+.0}", self.shadows)));
+        }
+        if self.contrast != defaults.contrast {
+            changes.push(("Contrast", format!("{:+.0}", self.contrast)));
+        }
+        if self.tone_mode != defaults.tone_mode {
+            changes.push(("Tone", self.tone_mode.to_string()));
+        }
+        if self.clarity != defaults.clarity {
+            changes.push(("Clarity", percent(self.clarity)));
+        }
+        if self.temperature != defaults.temperature {
+            changes.push(("Temperature", percent(self.temperature)));
+        }
+        if self.tint != defaults.tint {
+            changes.push(("Tint", percent(self.tint)));
+        }
+        if self.wb_override.is_some() {
+            changes.push(("White Balance", "Picked".to_string()));
+        }
+        if self.whites != defaults.whites {
+            changes.push(("Whites", format!("{:+.0}", self.whites)));
+        }
+        if self.blacks != defaults.blacks {
+            changes.push(("Blacks", format!("{:+.0}", self.blacks)));
+        }
+        if self.vibrance != defaults.vibrance {
+            changes.push(("Vibrance", percent(self.vibrance)));
+        }
+        if self.saturation != defaults.saturation {
+            changes.push(("Saturation", format!("{:+.0}", self.saturation)));
+        }
+        if self.monochrome {
+            let [r, g, b] = self.bw_mix;
+            let value = if self.bw_mix == defaults.bw_mix {
+                "On".to_string()
+            } else {
+                format!("R {:.2} G {:.2} B {:.2}", r, g, b)
+            };
+            changes.push(("Black & White", value));
+        }
+        if self.invert {
+            changes.push(("Negative", "Inverted".to_string()));
+        }
+        if !self.split_tone_is_identity() {
+            changes.push(("Split Toning", format!("balance {}", percent(self.balance))));
+        }
+        if !self.curve.is_identity() {
+            changes.push(("Tone Curve", "Custom".to_string()));
+        }
+        let hsl_bands: Vec<&str> = HSL_BAND_NAMES
+            .iter()
+            .zip(&self.hsl)
+            .filter(|(_, band)| !band.is_identity())
+            .map(|(name, _)| *name)
+            .collect();
+        if !hsl_bands.is_empty() {
+            changes.push(("HSL", hsl_bands.join(", ")));
+        }
+        if !self.masks.is_empty() {
+            changes.push(("Masks", self.masks.len().to_string()));
+        }
+        if self.sharpen_amount != defaults.sharpen_amount || self.sharpen_radius != defaults.sharpen_radius {
+            changes.push(("Sharpening", format!("{:.0} @ {:.1}px", self.sharpen_amount * 100.0, self.sharpen_radius)));
+        }
+        if self.luma_nr != defaults.luma_nr {
+            changes.push(("Luminance NR", format!("{:.0}", self.luma_nr * 100.0)));
+        }
+        if self.chroma_nr != defaults.chroma_nr {
+            changes.push(("Color NR", format!("{:.0}", self.chroma_nr * 100.0)));
+        }
+        if self.vignette_amount != defaults.vignette_amount || self.vignette_midpoint != defaults.vignette_midpoint {
+            changes.push(("Vignette", format!("{} @ {:.0}", percent(self.vignette_amount), self.vignette_midpoint * 100.0)));
+        }
+        if self.lens_correction {
+            changes.push(("Lens Profile", "On".to_string()));
+        }
+        if self.lens_distortion != defaults.lens_distortion {
+            changes.push(("Distortion", percent(self.lens_distortion)));
+        }
+        if let Some((_, _, w, h)) = self.crop_rect {
+            changes.push(("Crop", format!("{:.0}% × {:.0}%", w * 100.0, h * 100.0)));
+        }
+        if self.rotation_degrees != defaults.rotation_degrees {
+            changes.push(("Straighten", format!("{:+.1}°", self.rotation_degrees)));
+        }
+        if self.demosaic != defaults.demosaic {
+            changes.push(("Demosaic", self.demosaic.to_string()));
+        }
+        
+        changes.into_iter().map(|(label, value)| (label.to_string(), value)).collect()
+    }
+    
     /// Reset all adjustments to default (no edits)
     pub fn reset(&mut self) {
         *self = Self::default();
     }
+    
+    /// Starting noise reduction (luma, chroma) for a shot at this ISO
+    ///
+    /// Base ISOs up to 400 are clean enough to leave alone; above that both
+    /// amounts grow with each stop, chroma first since color noise shows sooner.
+    pub fn suggested_noise_reduction(iso: Option<u32>) -> (f32, f32) {
+        let Some(iso) = iso else {
+            return (0.0, 0.0);
+        };
+        let stops = (iso as f32 / 400.0).log2();
+        if stops <= 0.0 {
+            return (0.0, 0.0);
+        }
+        ((stops * 0.12).min(0.6), (0.2 + stops * 0.1).min(0.8))
+    }
+    
+    /// Auto tone: exposure, whites and blacks picked from a histogram
+    ///
+    /// `histogram` must come from a render with exposure 0 and neutral levels
+    /// (bins are sRGB-encoded display values). Exposure moves the average
+    /// luminance to mid-gray; whites and blacks then stretch the range until
+    /// about `AUTO_TONE_CLIP` of the pixels clip at each end. Everything else
+    /// is kept.
+    pub fn with_auto_tone(&self, histogram: &[[u32; 256]; 3]) -> EditParams {
+        let to_linear = |bin: usize| crate::color::srgb_to_linear(bin as f32 / 255.0);
+        let total: u64 = histogram[0].iter().map(|&count| count as u64).sum();
+        if total == 0 {
+            return *self;
+        }
+        
+        // Average luminance in linear light (Rec. 709 weights, like the shader)
+        let weights = [0.2126, 0.7152, 0.0722];
+        let mean = histogram
+            .iter()
+            .zip(weights)
+            .map(|(channel, weight)| {
+                let sum: f32 = channel.iter().enumerate().map(|(bin, &count)| to_linear(bin) * count as f32).sum();
+                weight * sum / total as f32
+            })
+            .sum::<f32>();
+        let exposure = (AUTO_TONE_MID_GRAY / mean.max(1e-6)).log2().clamp(-5.0, 5.0);
+        let gain = exposure.exp2();
+        
+        // Darkest and brightest channel values once the clip allowance is skipped
+        let clip = (total as f32 * AUTO_TONE_CLIP) as u64;
+        let brightest = histogram
+            .iter()
+            .map(|channel| to_linear(clip_point(channel, (0..256).rev(), clip).unwrap_or(0)))
+            .fold(0.0, f32::max);
+        let darkest = histogram
+            .iter()
+            .map(|channel| to_linear(clip_point(channel, 0..256, clip).unwrap_or(255)))
+            .fold(1.0, f32::min);
+        
+        // Points to slider values, with the sliders' ranges and precision
+        let white_point = (brightest * gain).clamp(1.0 - LEVELS_RANGE, 1.0 + LEVELS_RANGE);
+        let black_point = (darkest * gain).clamp(0.0, LEVELS_RANGE);
+        EditParams {
+            exposure: (exposure * 100.0).round() / 100.0,
+            whites: ((1.0 - white_point) / LEVELS_RANGE * 100.0).round(),
+            blacks: (-black_point / LEVELS_RANGE * 100.0).round(),
+            ..*self
+        }
+    }
+    
+    /// Only the adjustment behind one history key (see `EditHistory::record`),
+    /// everything else at default, to show what that slider contributes.
+    /// Geometry (crop, straighten, lens) is always kept so the framing doesn't
+    /// jump; an unknown key keeps just the geometry.
+    pub fn solo(&self, key: &str) -> EditParams {
+        let mut solo = EditParams {
+            crop_rect: self.crop_rect,
+            rotation_degrees: self.rotation_degrees,
+            lens_correction: self.lens_correction,
+            lens_distortion: self.lens_distortion,
+            ..EditParams::default()
+        };
+        match key {
+            "exposure" => solo.exposure = self.exposure,
+            "contrast" => {
+                solo.contrast = self.contrast;
+                solo.tone_mode = self.tone_mode;
+            }
+            "highlights" => solo.highlights = self.highlights,
+            "highlight_recovery" => solo.highlight_recovery = self.highlight_recovery,
+            "shadows" => solo.shadows = self.shadows,
+            "whites" => solo.whites = self.whites,
+            "blacks" => solo.blacks = self.blacks,
+            "vibrance" => solo.vibrance = self.vibrance,
+            "saturation" => solo.saturation = self.saturation,
+            "monochrome" | "bw_mix" => {
+                solo.monochrome = self.monochrome;
+                solo.bw_mix = self.bw_mix;
+            }
+            "invert" => {
+                solo.invert = self.invert;
+                solo.film_base = self.film_base;
+            }
+            "shadow_tint" | "highlight_tint" | "balance" => {
+                solo.shadow_tint = self.shadow_tint;
+                solo.highlight_tint = self.highlight_tint;
+                solo.balance = self.balance;
+            }
+            "temperature" => solo.temperature = self.temperature,
+            "tint" => solo.tint = self.tint,
+            "curve" => solo.curve = self.curve,
+            "hsl" => solo.hsl = self.hsl,
+            "mask" => solo.masks = self.masks,
+            "demosaic" => solo.demosaic = self.demosaic,
+            "clarity" => solo.clarity = self.clarity,
+            "luma_nr" => solo.luma_nr = self.luma_nr,
+            "chroma_nr" => solo.chroma_nr = self.chroma_nr,
+            "sharpen_amount" | "sharpen_radius" => {
+                solo.sharpen_amount = self.sharpen_amount;
+                solo.sharpen_radius = self.sharpen_radius;
+            }
+            "vignette_amount" | "vignette_midpoint" => {
+                solo.vignette_amount = self.vignette_amount;
+                solo.vignette_midpoint = self.vignette_midpoint;
+            }
+            _ => {}
+        }
+        solo
+    }
+    
+    /// True when neither split toning tint is set (the shader skips the step;
+    /// balance alone does nothing)
+    pub fn split_tone_is_identity(&self) -> bool {
+        self.shadow_tint == [0.0; 3] && self.highlight_tint == [0.0; 3]
+    }
+    
+    /// True when no HSL band changes anything (the shader skips the HSL step)
+    pub fn hsl_is_identity(&self) -> bool {
+        self.hsl.iter().all(HslBand::is_identity)
+    }
+    
+    /// Set one slider of one HSL band (values are clamped to -100..100)
+    pub fn set_hsl(&mut self, band: usize, channel: HslChannel, value: f32) {
+        let Some(band) = self.hsl.get_mut(band) else {
+            return;
+        };
+        let value = value.clamp(-100.0, 100.0);
+        match channel {
+            HslChannel::Hue => band.hue = value,
+            HslChannel::Saturation => band.saturation = value,
+            HslChannel::Luminance => band.luminance = value,
+        }
+    }
+    
+    /// Crop rectangle actually applied (full frame when uncropped)
+    pub fn crop_or_full(&self) -> CropRect {
+        self.crop_rect.unwrap_or(FULL_FRAME)
+    }
+    
+    /// Map output coordinates (0-1 over the cropped, straightened image) to
+    /// source texture coordinates. CPU mirror of `apply_geometry` in the shader.
+    pub fn source_coords(&self, u: f32, v: f32, width: u32, height: u32) -> (f32, f32) {
+        let (crop_x, crop_y, crop_w, crop_h) = self.crop_or_full();
+        let frame_x = crop_x + u * crop_w;
+        let frame_y = crop_y + v * crop_h;
+        
+        // Rotate around the center in pixel space so the aspect ratio is kept
+        let (w, h) = (width as f32, height as f32);
+        let (px, py) = ((frame_x - 0.5) * w, (frame_y - 0.5) * h);
+        let (sin, cos) = self.rotation_degrees.to_radians().sin_cos();
+        let sx = cos * px + sin * py;
+        let sy = -sin * px + cos * py;
+        (sx / w + 0.5, sy / h + 0.5)
+    }
+}
+
+/// Maximum number of history entries kept per image
+const MAX_HISTORY: usize = 100;
+
+/// Consecutive changes to the same parameter within this window are merged
+/// into a single history entry (one slider drag = one undo step)
+const COALESCE_WINDOW: Duration = Duration::from_millis(750);
+
+/// Undo/redo stack of edit parameters for the selected image
+///
+/// Holds a bounded list of snapshots with a cursor pointing at the one
+/// currently applied. Undo/redo move the cursor and return the snapshot
+/// to apply; recording a new change discards anything after the cursor.
+#[derive(Debug, Clone)]
+pub struct EditHistory {
+    entries: Vec<EditParams>,
+    cursor: usize,
+    /// Parameter touched by the most recent `record` (for coalescing)
+    last_key: Option<&'static str>,
+    last_record: Option<Instant>,
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self::new(EditParams::default())
+    }
+}
+
+impl EditHistory {
+    /// Create a history whose only entry is `initial`
+    pub fn new(initial: EditParams) -> Self {
+        Self {
+            entries: vec![initial],
+            cursor: 0,
+            last_key: None,
+            last_record: None,
+        }
+    }
+    
+    /// Drop all history and start over from `initial` (e.g. new image selected)
+    pub fn reset(&mut self, initial: EditParams) {
+        *self = Self::new(initial);
+    }
+    
+    /// Push a new snapshot as its own undo step
+    pub fn push(&mut self, params: EditParams) {
+        self.last_key = None;
+        self.last_record = None;
+        
+        // A new change invalidates everything that was undone
+        self.entries.truncate(self.cursor + 1);
+        
+        if self.entries.last() == Some(&params) {
+            return;
+        }
+        
+        self.entries.push(params);
+        if self.entries.len() > MAX_HISTORY {
+            self.entries.remove(0);
+        }
+        self.cursor = self.entries.len() - 1;
+    }
+    
+    /// Record a change to a single parameter, merging rapid consecutive
+    /// changes to the same parameter into one entry
+    pub fn record(&mut self, params: EditParams, key: &'static str) {
+        let now = Instant::now();
+        let coalesce = self.last_key == Some(key)
+            && self.cursor + 1 == self.entries.len()
+            && self.last_record.is_some_and(|t| now.duration_since(t) < COALESCE_WINDOW);
+        
+        if coalesce {
+            self.entries[self.cursor] = params;
+        } else {
+            self.push(params);
+        }
+        
+        self.last_key = Some(key);
+        self.last_record = Some(now);
+    }
+    
+    /// Parameter of the most recent `record`, while its entry is still open
+    /// (None after undo/redo, a push or a seal)
+    pub fn last_key(&self) -> Option<&'static str> {
+        self.last_key
+    }
+    
+    /// End the current entry: the next `record` starts a new undo step
+    /// even for the same parameter (e.g. a reset right after a drag)
+    pub fn seal(&mut self) {
+        self.last_key = None;
+    }
+    
+    /// Step back one entry, returning the parameters to apply
+    pub fn undo(&mut self) -> Option<EditParams> {
+        self.last_key = None;
+        if self.cursor == 0 {
+            return None;
+        }
+        self.cursor -= 1;
+        Some(self.entries[self.cursor])
+    }
+    
+    /// Step forward one entry, returning the parameters to apply
+    pub fn redo(&mut self) -> Option<EditParams> {
+        self.last_key = None;
+        if self.cursor + 1 >= self.entries.len() {
+            return None;
+        }
+        self.cursor += 1;
+        Some(self.entries[self.cursor])
+    }
+    
+    /// Whether there is anything to undo
+    pub fn can_undo(&self) -> bool {
+        self.cursor > 0
+    }
+    
+    /// Whether there is anything to redo
+    pub fn can_redo(&self) -> bool {
+        self.cursor + 1 < self.entries.len()
+    }
 }
 
 #[cfg(test)]
@@ -161,4 +1284,438 @@ mod tests {
         
         assert!(params.is_unedited());
     }
+    
+    #[test]
+    fn test_history_undo_redo() {
+        let mut history = EditHistory::default();
+        let mut params = EditParams::default();
+        
+        params.exposure = 1.0;
+        history.push(params);
+        params.contrast = 5.0;
+        history.push(params);
+        
+        assert_eq!(history.undo().unwrap().contrast, 0.0);
+        assert_eq!(history.undo().unwrap(), EditParams::default());
+        assert!(history.undo().is_none());
+        
+        assert_eq!(history.redo().unwrap().exposure, 1.0);
+        assert_eq!(history.redo().unwrap().contrast, 5.0);
+        assert!(history.redo().is_none());
+    }
+    
+    #[test]
+    fn test_history_coalesces_same_parameter() {
+        let mut history = EditHistory::default();
+        let mut params = EditParams::default();
+        
+        // Simulate a slider drag on exposure
+        for step in 1..=10 {
+            params.exposure = step as f32 * 0.1;
+            history.record(params, "exposure");
+        }
+        // Then a different parameter starts a new entry
+        params.contrast = 2.0;
+        history.record(params, "contrast");
+        
+        assert_eq!(history.undo().unwrap().contrast, 0.0);
+        assert_eq!(history.undo().unwrap(), EditParams::default());
+        assert!(!history.can_undo());
+    }
+    
+    #[test]
+    fn test_history_seal_splits_same_parameter() {
+        let mut history = EditHistory::default();
+        let mut params = EditParams { exposure: 1.5, ..EditParams::default() };
+        history.record(params, "exposure");
+        // Resetting the slider right after the drag is its own step
+        history.seal();
+        params.exposure = 0.0;
+        history.record(params, "exposure");
+        
+        assert_eq!(history.undo().unwrap().exposure, 1.5);
+        assert_eq!(history.undo().unwrap(), EditParams::default());
+    }
+    
+    #[test]
+    fn test_history_new_change_discards_redo() {
+        let mut history = EditHistory::default();
+        let mut params = EditParams::default();
+        
+        params.exposure = 1.0;
+        history.push(params);
+        history.undo();
+        assert!(history.can_redo());
+        
+        params.exposure = -1.0;
+        history.push(params);
+        assert!(!history.can_redo());
+        assert_eq!(history.undo().unwrap(), EditParams::default());
+    }
+    
+    #[test]
+    fn test_history_is_bounded() {
+        let mut history = EditHistory::default();
+        let mut params = EditParams::default();
+        
+        for step in 0..(MAX_HISTORY * 2) {
+            params.exposure = step as f32;
+            history.push(params);
+        }
+        
+        let mut undo_count = 0;
+        while history.undo().is_some() {
+            undo_count += 1;
+        }
+        assert_eq!(undo_count, MAX_HISTORY - 1);
+    }
+    
+    #[test]
+    fn test_identity_curve() {
+        let curve = ToneCurve::default();
+        assert!(curve.is_identity());
+        for (i, value) in curve.to_lut().iter().enumerate() {
+            let x = i as f32 / (CURVE_LUT_SIZE - 1) as f32;
+            assert!((value - x).abs() < 1e-4);
+        }
+    }
+    
+    #[test]
+    fn test_curve_is_monotone_and_hits_points() {
+        let mut curve = ToneCurve::default();
+        curve.set_point(1, 0.25, 0.15); // Darken shadows
+        curve.set_point(3, 0.75, 0.9);  // Brighten highlights
+        assert!(!curve.is_identity());
+        
+        assert!((curve.evaluate(0.25) - 0.15).abs() < 1e-4);
+        assert!((curve.evaluate(0.75) - 0.9).abs() < 1e-4);
+        
+        let lut = curve.to_lut();
+        for pair in lut.windows(2) {
+            assert!(pair[1] >= pair[0] - 1e-6);
+        }
+    }
+    
+    #[test]
+    fn test_curve_set_point_constraints() {
+        let mut curve = ToneCurve::default();
+        curve.set_point(0, 0.5, 0.1);
+        assert_eq!(curve.points[0].x, 0.0);
+        curve.set_point(2, 0.99, 1.5);
+        assert!(curve.points[2].x < curve.points[3].x);
+        assert_eq!(curve.points[2].y, 1.0);
+    }
+    
+    #[test]
+    fn test_curve_edit_is_not_unedited() {
+        let mut params = EditParams::default();
+        params.curve.set_point(2, 0.5, 0.6);
+        assert!(!params.is_unedited());
+        
+        let restored = EditParams::from_json(&params.to_json().unwrap()).unwrap();
+        assert_eq!(params, restored);
+    }
+    
+    #[test]
+    fn test_legacy_json_without_curve() {
+        // Edits saved before the tone curve existed must still load
+        let json = r#"{"exposure":1.0,"contrast":0.0,"highlights":0.0,"shadows":0.0,
+            "whites":1.0,"blacks":0.0,"vibrance":0.0,"saturation":0.0,
+            "temperature":0.0,"tint":0.0}"#;
+        let params = EditParams::from_json(json).unwrap();
+        assert_eq!(params.exposure, 1.0);
+        assert!(params.curve.is_identity());
+        assert_eq!(params.demosaic, DemosaicMethod::Bilinear);
+        assert_eq!(params.crop_rect, None);
+        assert!(params.hsl_is_identity());
+    }
+    
+    #[test]
+    fn test_suggested_noise_reduction() {
+        assert_eq!(EditParams::suggested_noise_reduction(None), (0.0, 0.0));
+        assert_eq!(EditParams::suggested_noise_reduction(Some(100)), (0.0, 0.0));
+        assert_eq!(EditParams::suggested_noise_reduction(Some(400)), (0.0, 0.0));
+        
+        let (luma_1600, chroma_1600) = EditParams::suggested_noise_reduction(Some(1600));
+        let (luma_12800, chroma_12800) = EditParams::suggested_noise_reduction(Some(12800));
+        assert!(luma_1600 > 0.0 && chroma_1600 > luma_1600);
+        assert!(luma_12800 > luma_1600 && chroma_12800 > chroma_1600);
+        assert!(luma_12800 <= 1.0 && chroma_12800 <= 1.0);
+    }
+    
+    #[test]
+    fn test_monochrome_serialization() {
+        let params = EditParams {
+            monochrome: true,
+            bw_mix: [0.5, 0.4, 0.1],
+            ..EditParams::default()
+        };
+        assert!(!params.is_unedited());
+        
+        let restored = EditParams::from_json(&params.to_json().unwrap()).unwrap();
+        assert_eq!(params, restored);
+        
+        // Older edits load as color with the default mix
+        let legacy = EditParams::from_json(r#"{"exposure":0.5}"#).unwrap();
+        assert!(!legacy.monochrome);
+        assert_eq!(legacy.bw_mix, DEFAULT_BW_MIX);
+    }
+    
+    #[test]
+    fn test_hsl_edit_and_serialization() {
+        let mut params = EditParams::default();
+        assert!(params.hsl_is_identity());
+        
+        params.set_hsl(5, HslChannel::Saturation, -40.0);
+        params.set_hsl(0, HslChannel::Hue, 250.0); // Clamped
+        params.set_hsl(HSL_BANDS, HslChannel::Luminance, 10.0); // Out of range: ignored
+        assert_eq!(params.hsl[5].saturation, -40.0);
+        assert_eq!(params.hsl[0].hue, 100.0);
+        assert!(!params.hsl_is_identity());
+        assert!(!params.is_unedited());
+        
+        let restored = EditParams::from_json(&params.to_json().unwrap()).unwrap();
+        assert_eq!(params, restored);
+        
+        // Back to zero is unedited again
+        params.set_hsl(5, HslChannel::Saturation, 0.0);
+        params.set_hsl(0, HslChannel::Hue, 0.0);
+        assert!(params.is_unedited());
+    }
+    
+    #[test]
+    fn test_crop_serialization() {
+        let params = EditParams {
+            crop_rect: Some((0.1, 0.2, 0.5, 0.6)),
+            rotation_degrees: -3.5,
+            ..EditParams::default()
+        };
+        assert!(!params.is_unedited());
+        
+        let restored = EditParams::from_json(&params.to_json().unwrap()).unwrap();
+        assert_eq!(params, restored);
+    }
+    
+    #[test]
+    fn test_solo_keeps_one_adjustment() {
+        let params = EditParams {
+            exposure: 1.2,
+            contrast: 30.0,
+            tone_mode: ToneMode::Filmic,
+            saturation: -20.0,
+            crop_rect: Some((0.1, 0.1, 0.5, 0.5)),
+            ..EditParams::default()
+        };
+        
+        let solo = params.solo("exposure");
+        assert_eq!(solo.exposure, 1.2);
+        assert_eq!((solo.contrast, solo.saturation), (0.0, 0.0));
+        assert_eq!(solo.crop_rect, params.crop_rect); // Framing stays
+        
+        // Contrast brings its tone mode along
+        let solo = params.solo("contrast");
+        assert_eq!((solo.contrast, solo.tone_mode, solo.exposure), (30.0, ToneMode::Filmic, 0.0));
+        
+        // Unknown keys show just the geometry
+        assert_eq!(params.solo("nope"), EditParams { crop_rect: params.crop_rect, ..EditParams::default() });
+    }
+    
+    #[test]
+    fn test_diff_summary_lists_only_changes() {
+        assert!(EditParams::default().diff_summary().is_empty());
+        
+        let mut params = EditParams {
+            exposure: 0.7,
+            temperature: -0.1,
+            saturation: 15.0,
+            ..EditParams::default()
+        };
+        params.hsl[0].hue = 20.0;
+        let summary = params.diff_summary();
+        let expected = [
+            ("Temperature", "-10"),
+            ("Exposure", "+0.70 EV"),
+            ("Saturation", "+15"),
+            ("HSL", HSL_BAND_NAMES[0]),
+        ];
+        assert_eq!(summary.len(), expected.len());
+        for (label, value) in expected {
+            assert!(summary.contains(&(label.to_string(), value.to_string())), "{} missing from {:?}", label, summary);
+        }
+    }
+    
+    #[test]
+    fn test_legacy_ranges_convert_to_slider_scale() {
+        let legacy = EditParams {
+            contrast: 5.0,
+            highlights: -0.4,
+            shadows: 0.3,
+            whites: 0.9,
+            blacks: 0.05,
+            ..EditParams::default()
+        };
+        let params = legacy.upgrade_legacy_ranges();
+        assert_eq!(params.contrast, 5.0);
+        assert!((params.highlights + 80.0).abs() < 1e-3, "{}", params.highlights);
+        assert!((params.shadows - 30.0).abs() < 1e-3, "{}", params.shadows);
+        // Same white and black points as before
+        assert!((params.white_point() - 0.9).abs() < 1e-5 && (params.black_point() - 0.05).abs() < 1e-5);
+        
+        // The old white point default was 1.0; S-curve contrast took 10 for full strength
+        let s_curve = EditParams { whites: 1.0, contrast: 10.0, tone_mode: ToneMode::SCurve, ..EditParams::default() };
+        assert_eq!(
+            s_curve.upgrade_legacy_ranges(),
+            EditParams { contrast: 100.0, tone_mode: ToneMode::SCurve, ..EditParams::default() }
+        );
+        
+        // JSON from before the Whites slider had the old neutral white point
+        assert_eq!(EditParams::from_legacy_json(r#"{"exposure": 1.0}"#).unwrap(), EditParams { exposure: 1.0, ..EditParams::default() });
+    }
+    
+    #[test]
+    fn test_split_tone_serialization() {
+        let mut params = EditParams::default();
+        assert!(params.split_tone_is_identity());
+        
+        params.balance = 0.4;
+        assert!(params.split_tone_is_identity()); // Balance alone tints nothing
+        params.shadow_tint = [0.0, 0.3, 0.5];
+        params.highlight_tint = [0.6, 0.3, 0.0];
+        assert!(!params.split_tone_is_identity());
+        
+        let restored = EditParams::from_json(&params.to_json().unwrap()).unwrap();
+        assert_eq!(params, restored);
+        
+        // Older edits without the fields stay neutral
+        let legacy = EditParams::from_json(r#"{"exposure": 0.5}"#).unwrap();
+        assert!(legacy.split_tone_is_identity());
+        assert_eq!(legacy.balance, 0.0);
+    }
+    
+    #[test]
+    fn test_masks_serialize_as_array() {
+        let mut params = EditParams::default();
+        assert!(params.to_json().unwrap().contains("\"masks\":[]"));
+        
+        params.masks.push(LocalMask { exposure: 1.0, ..LocalMask::new(MaskKind::Radial) });
+        params.masks.push(LocalMask { saturation: -30.0, ..LocalMask::new(MaskKind::Linear) });
+        let json = params.to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["masks"].as_array().unwrap().len(), 2);
+        assert_eq!(EditParams::from_json(&json).unwrap(), params);
+        
+        // Removing shifts the rest down; none left is unedited again
+        params.masks.remove(0);
+        assert_eq!(params.masks.as_slice()[0].kind, MaskKind::Linear);
+        params.masks.remove(0);
+        assert!(params.masks.is_empty() && params.is_unedited());
+        
+        // Never more than MAX_MASKS
+        for _ in 0..MAX_MASKS {
+            assert!(params.masks.push(LocalMask::default()).is_some());
+        }
+        assert_eq!(params.masks.push(LocalMask::default()), None);
+    }
+    
+    #[test]
+    fn test_source_coords() {
+        let approx = |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4;
+        let mut params = EditParams::default();
+        
+        // Uncropped, unrotated: identity
+        assert!(approx(params.source_coords(0.25, 0.75, 600, 400), (0.25, 0.75)));
+        
+        // Crop remaps the output onto the rectangle
+        params.crop_rect = Some((0.5, 0.0, 0.5, 0.5));
+        assert!(approx(params.source_coords(0.0, 0.0, 600, 400), (0.5, 0.0)));
+        assert!(approx(params.source_coords(1.0, 1.0, 600, 400), (1.0, 0.5)));
+        
+        // 90° clockwise: the output's right edge shows the source's top edge
+        params.crop_rect = None;
+        params.rotation_degrees = 90.0;
+        assert!(approx(params.source_coords(0.5, 0.5, 600, 400), (0.5, 0.5)));
+        let (sx, sy) = params.source_coords(0.5 + 100.0 / 600.0, 0.5, 600, 400);
+        assert!(approx((sx, sy), (0.5, 0.5 - 100.0 / 400.0)));
+    }
+    
+    #[test]
+    fn test_level_rotation() {
+        // A horizon tilted 5° in the photo, seen with 2° of straighten applied
+        let horizon = 5f32.to_radians();
+        let seen = horizon + 2f32.to_radians();
+        let rotation = level_rotation(2.0, seen.cos(), seen.sin());
+        assert!((rotation - -5.0).abs() < 1e-3, "rotation {}", rotation);
+        
+        // With it applied, a level line on screen follows the horizon in the photo
+        let params = EditParams { rotation_degrees: rotation, ..EditParams::default() };
+        let (ax, ay) = params.source_coords(0.3, 0.5, 1000, 1000);
+        let (bx, by) = params.source_coords(0.7, 0.5, 1000, 1000);
+        assert!(((by - ay).atan2(bx - ax) - horizon).abs() < 1e-3);
+        
+        // Drawn right to left, or along a vertical edge: same result
+        assert!((level_rotation(2.0, -seen.cos(), -seen.sin()) - rotation).abs() < 1e-3);
+        assert!((level_rotation(2.0, -seen.sin(), seen.cos()) - rotation).abs() < 1e-3);
+        // Never past the slider range
+        assert_eq!(level_rotation(40.0, 1.0, -0.5), MAX_ROTATION_DEGREES);
+    }
+    
+    #[test]
+    fn test_fit_crop_to_aspect() {
+        // 3:2 inside a 600x400 full frame is the full frame
+        let rect = fit_crop_to_aspect(FULL_FRAME, 1.5, 600, 400);
+        assert!((rect.2 - 1.0).abs() < 1e-4 && (rect.3 - 1.0).abs() < 1e-4);
+        
+        // 1:1 keeps the full height and centers horizontally
+        let (x, y, w, h) = fit_crop_to_aspect(FULL_FRAME, 1.0, 600, 400);
+        assert_eq!(crop_size((x, y, w, h), 600, 400), (400, 400));
+        assert!((x - 1.0 / 6.0).abs() < 1e-4 && y.abs() < 1e-4);
+        
+        // Portrait crops stay portrait
+        let portrait = fit_crop_to_aspect((0.0, 0.0, 0.25, 1.0), 16.0 / 9.0, 600, 400);
+        let (pw, ph) = crop_size(portrait, 600, 400);
+        assert!(ph > pw);
+        assert!((ph as f32 / pw as f32 - 16.0 / 9.0).abs() < 0.02);
+    }
+    
+    #[test]
+    fn test_rotate_crop() {
+        // Top-left quarter ends up top-right after a clockwise turn
+        let rect = (0.0, 0.0, 0.5, 0.25);
+        assert_eq!(rotate_crop(rect, true), (0.75, 0.0, 0.25, 0.5));
+        assert_eq!(rotate_crop(rect, false), (0.0, 0.5, 0.25, 0.5));
+        // Left then right is a no-op
+        assert_eq!(rotate_crop(rotate_crop(rect, false), true), rect);
+    }
+    
+    #[test]
+    fn test_auto_tone_brightens_dark_image() {
+        // Dark, low-contrast frame: every channel spread over display values 10-100
+        let mut histogram = [[0u32; 256]; 3];
+        for channel in histogram.iter_mut() {
+            for count in &mut channel[10..=100] {
+                *count = 100;
+            }
+        }
+        let params = EditParams { contrast: 20.0, ..EditParams::default() };
+        let auto = params.with_auto_tone(&histogram);
+        
+        assert!(auto.exposure > 0.5, "dark image should get brighter, got {}", auto.exposure);
+        assert!(auto.blacks < 0.0 && auto.blacks >= -100.0, "blacks {}", auto.blacks);
+        assert!((-100.0..=100.0).contains(&auto.whites), "whites {}", auto.whites);
+        assert!(auto.black_point() > 0.0 && auto.black_point() <= LEVELS_RANGE);
+        // Only the auto tone fields change
+        assert_eq!(auto.contrast, params.contrast);
+        
+        // A bright frame goes the other way
+        let mut bright = [[0u32; 256]; 3];
+        for channel in bright.iter_mut() {
+            for count in &mut channel[200..=255] {
+                *count = 100;
+            }
+        }
+        assert!(EditParams::default().with_auto_tone(&bright).exposure < 0.0);
+        
+        // No pixels: nothing to go on
+        assert_eq!(params.with_auto_tone(&[[0u32; 256]; 3]), params);
+    }
 }
@@ -0,0 +1,388 @@
+/// EXIF shooting metadata
+///
+/// Most RAW formats (NEF, CR2, ARW, DNG, PEF, ...) are TIFF containers, so
+/// `kamadak-exif` can read their EXIF block directly. Formats it doesn't
+/// understand (CR3, RAF, ...) simply end up with every field set to None,
+/// which is stored as NULL in the catalog and rendered as "—" in the UI.
+///
+/// Exports can carry the RAW's EXIF along (`export_fields`): camera, lens,
+/// exposure and capture time are copied, plus the artist/copyright set in
+/// Preferences. GPS position and the camera's private maker notes stay
+/// behind unless switched on. Tags describing the RAW's own pixel layout
+/// (size, strips, orientation, ...) never carry over, since the export's
+/// pixels are already rotated and developed.
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use exif::{Context, Exif, Field, In, Tag, Value};
+
+/// Placeholder shown for missing metadata
+pub const MISSING: &str = "—";
+
+/// Camera and exposure settings for one image
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImageMetadata {
+    /// Camera manufacturer (e.g., "NIKON CORPORATION")
+    pub camera_make: Option<String>,
+    /// Camera model (e.g., "NIKON D750")
+    pub camera_model: Option<String>,
+    /// ISO sensitivity
+    pub iso: Option<u32>,
+    /// Exposure time in seconds
+    pub shutter_seconds: Option<f64>,
+    /// Aperture as an f-number (2.8 = f/2.8)
+    pub aperture: Option<f64>,
+    /// Focal length in millimeters
+    pub focal_length: Option<f64>,
+    /// Lens model (e.g., "FE 24mm F1.4 GM"), used to pick a lens profile
+    pub lens_model: Option<String>,
+    /// Capture time as written by the camera ("YYYY-MM-DD HH:MM:SS", no time zone)
+    pub captured_at: Option<String>,
+    /// EXIF orientation tag (1-8, see `orientation::Orientation`)
+    pub orientation: Option<u8>,
+}
+
+/// Read EXIF metadata from an image file
+///
+/// Never fails: unreadable files or files without EXIF give empty metadata.
+pub fn read_metadata(path: &Path) -> ImageMetadata {
+    let Ok(file) = File::open(path) else {
+        return ImageMetadata::default();
+    };
+
+    match exif::Reader::new().read_from_container(&mut BufReader::new(file)) {
+        Ok(exif) => metadata_from_exif(&exif),
+        Err(e) => {
+            println!("⚠️  No EXIF metadata in {}: {}", path.display(), e);
+            ImageMetadata::default()
+        }
+    }
+}
+
+/// Tags copied into exports (always from the primary image)
+const EXPORT_TAGS: &[Tag] = &[
+    Tag::Make,
+    Tag::Model,
+    Tag::DateTime,
+    Tag::ImageDescription,
+    Tag::Artist,
+    Tag::Copyright,
+    Tag::ExifVersion,
+    Tag::DateTimeOriginal,
+    Tag::DateTimeDigitized,
+    Tag::OffsetTime,
+    Tag::OffsetTimeOriginal,
+    Tag::OffsetTimeDigitized,
+    Tag::SubSecTimeOriginal,
+    Tag::SubSecTimeDigitized,
+    Tag::ExposureTime,
+    Tag::FNumber,
+    Tag::ExposureProgram,
+    Tag::PhotographicSensitivity,
+    Tag::SensitivityType,
+    Tag::ShutterSpeedValue,
+    Tag::ApertureValue,
+    Tag::ExposureBiasValue,
+    Tag::MaxApertureValue,
+    Tag::MeteringMode,
+    Tag::LightSource,
+    Tag::Flash,
+    Tag::FocalLength,
+    Tag::FocalLengthIn35mmFilm,
+    Tag::ExposureMode,
+    Tag::WhiteBalance,
+    Tag::SceneCaptureType,
+    Tag::CameraOwnerName,
+    Tag::BodySerialNumber,
+    Tag::LensSpecification,
+    Tag::LensMake,
+    Tag::LensModel,
+    Tag::LensSerialNumber,
+];
+
+/// What to write into exported files' EXIF besides the camera settings
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExportMetadata {
+    /// Replaces the camera's Artist tag when not blank
+    pub artist: String,
+    /// Replaces the camera's Copyright tag when not blank
+    pub copyright: String,
+    /// Copy the GPS position (off by default: it gives away where the photo was taken)
+    pub include_gps: bool,
+    /// Copy the camera's private maker notes (off by default: they can hold
+    /// serial numbers, and are often too big for a JPEG's EXIF block)
+    pub include_maker_notes: bool,
+}
+
+/// EXIF fields from a RAW to write into its export
+///
+/// Never fails: a RAW without readable EXIF gives just the artist/copyright.
+pub fn export_fields(path: &Path, settings: &ExportMetadata) -> Vec<Field> {
+    let exif = File::open(path)
+        .ok()
+        .and_then(|file| exif::Reader::new().read_from_container(&mut BufReader::new(file)).ok());
+
+    let overrides = [(Tag::Artist, &settings.artist), (Tag::Copyright, &settings.copyright)];
+    let overridden = |tag: Tag| overrides.iter().any(|(t, value)| *t == tag && !value.trim().is_empty());
+
+    let mut fields: Vec<Field> = exif
+        .iter()
+        .flat_map(|exif| exif.fields())
+        .filter(|field| field.ifd_num == In::PRIMARY && !overridden(field.tag))
+        .filter(|field| {
+            EXPORT_TAGS.contains(&field.tag)
+                || (settings.include_gps && field.tag.0 == Context::Gps)
+                || (settings.include_maker_notes && field.tag == Tag::MakerNote)
+        })
+        // Types this crate couldn't decode can't be written back either
+        .filter(|field| !matches!(field.value, Value::Unknown(..)))
+        .cloned()
+        .collect();
+
+    for (tag, value) in overrides {
+        let value = value.trim();
+        if !value.is_empty() {
+            fields.push(Field { tag, ifd_num: In::PRIMARY, value: Value::Ascii(vec![value.as_bytes().to_vec()]) });
+        }
+    }
+    fields
+}
+
+/// Pull the fields we care about out of a parsed EXIF block
+fn metadata_from_exif(exif: &Exif) -> ImageMetadata {
+    let value = |tag: Tag| exif.get_field(tag, In::PRIMARY).map(|field| &field.value);
+
+    ImageMetadata {
+        camera_make: value(Tag::Make).and_then(ascii),
+        camera_model: value(Tag::Model).and_then(ascii),
+        iso: value(Tag::PhotographicSensitivity).and_then(|v| v.get_uint(0)),
+        shutter_seconds: value(Tag::ExposureTime).and_then(rational),
+        aperture: value(Tag::FNumber).and_then(rational),
+        focal_length: value(Tag::FocalLength).and_then(rational),
+        lens_model: value(Tag::LensModel).and_then(ascii),
+        captured_at: value(Tag::DateTimeOriginal)
+            .or_else(|| value(Tag::DateTime))
+            .and_then(ascii)
+            .and_then(|s| normalize_datetime(&s)),
+        orientation: value(Tag::Orientation)
+            .and_then(|v| v.get_uint(0))
+            .and_then(|v| u8::try_from(v).ok())
+            .filter(|v| (1..=8).contains(v)),
+    }
+}
+
+/// First string of an ASCII value, trimmed (cameras pad with spaces/NULs)
+fn ascii(value: &Value) -> Option<String> {
+    match value {
+        Value::Ascii(strings) => {
+            let s = String::from_utf8_lossy(strings.first()?);
+            let s = s.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+            (!s.is_empty()).then(|| s.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// First rational of a value as a float (None for 0/0 "unknown" values)
+fn rational(value: &Value) -> Option<f64> {
+    let v = match value {
+        Value::Rational(r) => r.first()?.to_f64(),
+        Value::SRational(r) => r.first()?.to_f64(),
+        _ => return None,
+    };
+    (v.is_finite() && v > 0.0).then_some(v)
+}
+
+/// Convert EXIF "YYYY:MM:DD HH:MM:SS" to "YYYY-MM-DD HH:MM:SS"
+///
+/// Cameras with an unset clock write all zeros or blanks; those become None.
+fn normalize_datetime(s: &str) -> Option<String> {
+    let parsed = chrono::NaiveDateTime::parse_from_str(s, "%Y:%m:%d %H:%M:%S").ok()?;
+    Some(parsed.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+impl ImageMetadata {
+    /// Camera name, without repeating the make when the model already has it
+    /// ("NIKON CORPORATION" + "NIKON D750" → "NIKON D750")
+    pub fn camera_display(&self) -> String {
+        match (&self.camera_make, &self.camera_model) {
+            (Some(make), Some(model)) => {
+                let brand = make.split_whitespace().next().unwrap_or(make);
+                if model.to_lowercase().starts_with(&brand.to_lowercase()) {
+                    model.clone()
+                } else {
+                    format!("{} {}", make, model)
+                }
+            }
+            (None, Some(model)) => model.clone(),
+            (Some(make), None) => make.clone(),
+            (None, None) => MISSING.to_string(),
+        }
+    }
+
+    /// "ISO 400"
+    pub fn iso_display(&self) -> String {
+        self.iso.map_or(MISSING.to_string(), |iso| format!("ISO {}", iso))
+    }
+
+    /// "1/250 s" for fast shutters, "2.5 s" for long exposures
+    pub fn shutter_display(&self) -> String {
+        match self.shutter_seconds {
+            Some(t) if t < 1.0 => format!("1/{:.0} s", 1.0 / t),
+            Some(t) => format!("{} s", trim_decimal(t)),
+            None => MISSING.to_string(),
+        }
+    }
+
+    /// "f/2.8"
+    pub fn aperture_display(&self) -> String {
+        self.aperture.map_or(MISSING.to_string(), |f| format!("f/{}", trim_decimal(f)))
+    }
+
+    /// "50 mm"
+    pub fn focal_length_display(&self) -> String {
+        self.focal_length.map_or(MISSING.to_string(), |mm| format!("{} mm", trim_decimal(mm)))
+    }
+
+    /// Capture time, or "—"
+    pub fn captured_at_display(&self) -> String {
+        self.captured_at.clone().unwrap_or_else(|| MISSING.to_string())
+    }
+}
+
+/// Format with at most one decimal, dropping a trailing ".0" (2.0 → "2", 2.8 → "2.8")
+fn trim_decimal(value: f64) -> String {
+    let s = format!("{:.1}", value);
+    s.strip_suffix(".0").map(str::to_string).unwrap_or(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exif::{Field, Rational};
+
+    /// Write a minimal TIFF carrying the given EXIF fields
+    fn write_tiff(path: &Path, fields: &[Field]) {
+        let mut writer = exif::experimental::Writer::new();
+        for field in fields {
+            writer.push_field(field);
+        }
+        let mut buf = std::io::Cursor::new(Vec::new());
+        writer.write(&mut buf, true).unwrap();
+        std::fs::write(path, buf.into_inner()).unwrap();
+    }
+
+    fn field(tag: Tag, value: Value) -> Field {
+        Field { tag, ifd_num: In::PRIMARY, value }
+    }
+
+    #[test]
+    fn test_read_metadata_from_tiff() {
+        let dir = std::env::temp_dir().join(format!("raw-editor-exif-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("DSC_0001.NEF");
+
+        write_tiff(&path, &[
+            field(Tag::Make, Value::Ascii(vec![b"NIKON CORPORATION".to_vec()])),
+            field(Tag::Model, Value::Ascii(vec![b"NIKON D750".to_vec()])),
+            field(Tag::PhotographicSensitivity, Value::Short(vec![400])),
+            field(Tag::ExposureTime, Value::Rational(vec![Rational { num: 1, denom: 250 }])),
+            field(Tag::FNumber, Value::Rational(vec![Rational { num: 28, denom: 10 }])),
+            field(Tag::FocalLength, Value::Rational(vec![Rational { num: 50, denom: 1 }])),
+            field(Tag::LensModel, Value::Ascii(vec![b"50.0 mm f/1.8".to_vec()])),
+            field(Tag::DateTimeOriginal, Value::Ascii(vec![b"2024:06:01 18:30:05".to_vec()])),
+            field(Tag::Orientation, Value::Short(vec![6])),
+        ]);
+
+        let metadata = read_metadata(&path);
+        assert_eq!(metadata.camera_display(), "NIKON D750");
+        assert_eq!(metadata.iso_display(), "ISO 400");
+        assert_eq!(metadata.shutter_display(), "1/250 s");
+        assert_eq!(metadata.aperture_display(), "f/2.8");
+        assert_eq!(metadata.focal_length_display(), "50 mm");
+        assert_eq!(metadata.lens_model.as_deref(), Some("50.0 mm f/1.8"));
+        assert_eq!(metadata.captured_at.as_deref(), Some("2024-06-01 18:30:05"));
+        assert_eq!(metadata.orientation, Some(6));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_export_fields_filter_private_tags() {
+        let dir = std::env::temp_dir().join(format!("raw-editor-exif-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("DSC_0002.NEF");
+
+        write_tiff(&path, &[
+            field(Tag::Make, Value::Ascii(vec![b"NIKON CORPORATION".to_vec()])),
+            field(Tag::DateTimeOriginal, Value::Ascii(vec![b"2024:06:01 18:30:05".to_vec()])),
+            field(Tag::Orientation, Value::Short(vec![6])),
+            field(Tag::Artist, Value::Ascii(vec![b"Camera Owner".to_vec()])),
+            field(Tag::MakerNote, Value::Undefined(b"Nikon\0private".to_vec(), 0)),
+            field(Tag::GPSLatitudeRef, Value::Ascii(vec![b"N".to_vec()])),
+        ]);
+        let tags = |fields: &[Field]| fields.iter().map(|f| f.tag).collect::<Vec<_>>();
+
+        // Defaults: camera info only, no orientation, GPS or maker notes
+        let fields = export_fields(&path, &ExportMetadata::default());
+        let copied = tags(&fields);
+        assert!(copied.contains(&Tag::Make) && copied.contains(&Tag::DateTimeOriginal));
+        assert!(copied.contains(&Tag::Artist));
+        assert!(!copied.contains(&Tag::Orientation));
+        assert!(!copied.contains(&Tag::MakerNote));
+        assert!(!copied.contains(&Tag::GPSLatitudeRef));
+
+        // Opted in, and the artist from Preferences wins over the camera's
+        let settings = ExportMetadata {
+            artist: "Jane Doe".into(),
+            copyright: "© 2024 Jane Doe".into(),
+            include_gps: true,
+            include_maker_notes: true,
+        };
+        let fields = export_fields(&path, &settings);
+        let copied = tags(&fields);
+        assert!(copied.contains(&Tag::MakerNote) && copied.contains(&Tag::GPSLatitudeRef));
+        let artists: Vec<_> = fields.iter().filter(|f| f.tag == Tag::Artist).collect();
+        assert_eq!(artists.len(), 1);
+        assert_eq!(ascii(&artists[0].value).as_deref(), Some("Jane Doe"));
+        assert!(copied.contains(&Tag::Copyright));
+
+        // No EXIF at all: only what the user typed
+        let fields = export_fields(Path::new("/nonexistent/DSC_0002.NEF"), &settings);
+        assert_eq!(tags(&fields), vec![Tag::Artist, Tag::Copyright]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_missing_exif_renders_placeholder() {
+        let metadata = read_metadata(Path::new("/nonexistent/DSC_0001.NEF"));
+        assert_eq!(metadata, ImageMetadata::default());
+        assert_eq!(metadata.camera_display(), MISSING);
+        assert_eq!(metadata.iso_display(), MISSING);
+        assert_eq!(metadata.shutter_display(), MISSING);
+        assert_eq!(metadata.aperture_display(), MISSING);
+        assert_eq!(metadata.focal_length_display(), MISSING);
+        assert_eq!(metadata.captured_at_display(), MISSING);
+    }
+
+    #[test]
+    fn test_display_formatting() {
+        let metadata = ImageMetadata {
+            camera_make: Some("FUJIFILM".into()),
+            camera_model: Some("X-T4".into()),
+            shutter_seconds: Some(2.5),
+            aperture: Some(11.0),
+            focal_length: Some(23.0),
+            ..Default::default()
+        };
+        assert_eq!(metadata.camera_display(), "FUJIFILM X-T4");
+        assert_eq!(metadata.shutter_display(), "2.5 s");
+        assert_eq!(metadata.aperture_display(), "f/11");
+        assert_eq!(metadata.focal_length_display(), "23 mm");
+
+        // Unset camera clocks are treated as missing
+        assert_eq!(normalize_datetime("0000:00:00 00:00:00"), None);
+    }
+}
@@ -0,0 +1,720 @@
+/// Export settings shared by single and batch export
+///
+/// Exports are named from a template (see `expand_template`): `{original}`
+/// is the RAW's filename without extension and `{seq}` is the image's
+/// position in the batch (1-based), so `{original}_edited` turns
+/// `DSC_0001.NEF` into `DSC_0001_edited.jpg`. Capture date, camera and
+/// rating are available too.
+///
+/// JPEG quality and PNG compression are chosen per export (`ExportOptions`);
+/// the `image` crate's `save_buffer` would otherwise always use its defaults.
+/// Every format is tagged with the sRGB ICC profile the shader renders into,
+/// and carries the EXIF fields passed in (see `exif::export_fields`). The
+/// `image` crate's TIFF encoder can't write either, so 16-bit TIFFs are laid
+/// out with `kamadak-exif`'s writer instead.
+///
+/// PNGs can also carry the edit recipe (the `EditParams` JSON) in an iTXt
+/// chunk, so dropping one back on the window recovers its settings (see
+/// `read_embedded_recipe`). A second chunk holds the settings version;
+/// recipes from before it are on the old tone slider scales.
+use std::fs::File;
+use std::io::{BufWriter, Cursor, Write};
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+use exif::experimental::Writer;
+use exif::{Context, Field, In, Tag, Value};
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ExtendedColorType, ImageEncoder};
+
+use super::edit::{EditParams, SETTINGS_VERSION};
+use super::exif::ImageMetadata;
+
+/// Default batch export filename template
+pub const DEFAULT_TEMPLATE: &str = "{original}_edited";
+
+/// Output format for batch export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Jpeg,
+    Png,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 2] = [ExportFormat::Jpeg, ExportFormat::Png];
+
+    /// File extension (without the dot)
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Jpeg => "jpg",
+            ExportFormat::Png => "png",
+        }
+    }
+
+    /// Parse a stored value (the extension; unknown values fall back to JPEG)
+    pub fn from_setting(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extension() == value)
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportFormat::Jpeg => write!(f, "JPEG"),
+            ExportFormat::Png => write!(f, "PNG"),
+        }
+    }
+}
+
+/// PNG compression level: bigger files save faster
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PngCompression {
+    Fast,
+    #[default]
+    Default,
+    Best,
+}
+
+impl PngCompression {
+    pub const ALL: [PngCompression; 3] = [PngCompression::Fast, PngCompression::Default, PngCompression::Best];
+
+    fn compression_type(&self) -> CompressionType {
+        match self {
+            PngCompression::Fast => CompressionType::Fast,
+            PngCompression::Default => CompressionType::Default,
+            PngCompression::Best => CompressionType::Best,
+        }
+    }
+}
+
+impl std::fmt::Display for PngCompression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PngCompression::Fast => write!(f, "Fast"),
+            PngCompression::Default => write!(f, "Balanced"),
+            PngCompression::Best => write!(f, "Smallest"),
+        }
+    }
+}
+
+/// Default JPEG quality (good for web delivery without visible artifacts)
+pub const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+/// Largest EXIF block that fits in a JPEG's APP1 segment (64 KiB minus the
+/// segment length and the "Exif\0\0" header)
+const MAX_JPEG_EXIF_BYTES: usize = 65_527;
+
+/// TIFF ICC profile tag (not in `exif::Tag`'s list)
+const ICC_PROFILE_TAG: Tag = Tag(Context::Tiff, 34675);
+
+/// Keyword of the PNG text chunk holding the edit recipe
+pub const RECIPE_KEYWORD: &str = "RawEditorSettings";
+
+/// Keyword of the PNG text chunk holding the recipe's `SETTINGS_VERSION`
+const RECIPE_VERSION_KEYWORD: &str = "RawEditorSettingsVersion";
+
+/// PNG file signature
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Target size of one uncompressed TIFF strip
+const TIFF_STRIP_BYTES: usize = 1 << 20;
+
+/// Encoder settings for an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// JPEG quality, 1-100
+    pub jpeg_quality: u8,
+    /// PNG compression level
+    pub png_compression: PngCompression,
+    /// Write the edit recipe into PNG exports
+    pub embed_recipe: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            jpeg_quality: DEFAULT_JPEG_QUALITY,
+            png_compression: PngCompression::default(),
+            embed_recipe: false,
+        }
+    }
+}
+
+/// Tag an encoder's output as sRGB, attach the EXIF block if any, then write the pixels
+fn write_srgb<E: ImageEncoder>(
+    mut encoder: E,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    color_type: ExtendedColorType,
+    exif: Option<Vec<u8>>,
+) -> Result<(), String> {
+    encoder
+        .set_icc_profile(crate::color::srgb_icc_profile())
+        .map_err(|e| format!("Failed to embed color profile: {}", e))?;
+    if let Some(exif) = exif {
+        encoder
+            .set_exif_metadata(exif)
+            .map_err(|e| format!("Failed to embed EXIF metadata: {}", e))?;
+    }
+    encoder
+        .write_image(pixels, width, height, color_type)
+        .map_err(|e| format!("Failed to save image: {}", e))
+}
+
+fn create(path: &Path) -> Result<BufWriter<File>, String> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))
+}
+
+/// Serialize EXIF fields as the bare TIFF structure JPEG and PNG embed
+/// (None when there's nothing to write)
+fn exif_block(metadata: &[Field]) -> Result<Option<Vec<u8>>, String> {
+    if metadata.is_empty() {
+        return Ok(None);
+    }
+    let mut writer = Writer::new();
+    for field in metadata {
+        writer.push_field(field);
+    }
+    let mut buf = Cursor::new(Vec::new());
+    writer
+        .write(&mut buf, false)
+        .map_err(|e| format!("Failed to write EXIF metadata: {}", e))?;
+    Ok(Some(buf.into_inner()))
+}
+
+/// EXIF block for a JPEG: the maker note is dropped if the whole block
+/// doesn't fit in one APP1 segment, and if it still doesn't the export goes
+/// out without metadata rather than failing
+fn jpeg_exif_block(metadata: &[Field]) -> Result<Option<Vec<u8>>, String> {
+    let block = exif_block(metadata)?;
+    if block.as_ref().is_none_or(|block| block.len() <= MAX_JPEG_EXIF_BYTES) {
+        return Ok(block);
+    }
+
+    let without_maker_note: Vec<Field> = metadata
+        .iter()
+        .filter(|field| field.tag != Tag::MakerNote)
+        .cloned()
+        .collect();
+    let block = exif_block(&without_maker_note)?;
+    if block.as_ref().is_none_or(|block| block.len() <= MAX_JPEG_EXIF_BYTES) {
+        println!("⚠️  Maker notes too large for JPEG EXIF, left out");
+        return Ok(block);
+    }
+    println!("⚠️  EXIF metadata too large for JPEG, exporting without it");
+    Ok(None)
+}
+
+/// Save 8-bit RGB pixels as a JPEG at the given quality (clamped to 1-100)
+pub fn save_jpeg(path: &Path, rgb: &[u8], width: u32, height: u32, quality: u8, metadata: &[Field]) -> Result<(), String> {
+    let exif = jpeg_exif_block(metadata)?;
+    let encoder = JpegEncoder::new_with_quality(create(path)?, quality.clamp(1, 100));
+    write_srgb(encoder, rgb, width, height, ExtendedColorType::Rgb8, exif)
+}
+
+/// Save 8-bit RGBA pixels as a PNG with the given compression, with the
+/// edit recipe (EditParams JSON) in an iTXt chunk if one is given
+pub fn save_png(
+    path: &Path,
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    compression: PngCompression,
+    metadata: &[Field],
+    recipe: Option<&str>,
+) -> Result<(), String> {
+    let exif = exif_block(metadata)?;
+    let Some(recipe) = recipe else {
+        let encoder = PngEncoder::new_with_quality(create(path)?, compression.compression_type(), FilterType::Adaptive);
+        return write_srgb(encoder, rgba, width, height, ExtendedColorType::Rgba8, exif);
+    };
+
+    // The image crate can't write text chunks: encode in memory, then put
+    // the recipe and its version right before IEND (always the last 12 bytes)
+    let mut png = Vec::new();
+    let encoder = PngEncoder::new_with_quality(&mut png, compression.compression_type(), FilterType::Adaptive);
+    write_srgb(encoder, rgba, width, height, ExtendedColorType::Rgba8, exif)?;
+    let iend = png.len().checked_sub(12).ok_or("Encoded PNG is truncated")?;
+    let mut chunks = text_chunk(RECIPE_VERSION_KEYWORD, &SETTINGS_VERSION.to_string());
+    chunks.extend(text_chunk(RECIPE_KEYWORD, recipe));
+    png.splice(iend..iend, chunks);
+
+    let mut file = create(path)?;
+    file.write_all(&png)
+        .and_then(|()| file.flush())
+        .map_err(|e| format!("Failed to save image: {}", e))
+}
+
+/// iTXt chunk: keyword, uncompressed text, no language tag
+fn text_chunk(keyword: &str, text: &str) -> Vec<u8> {
+    let mut data = Vec::with_capacity(keyword.len() + text.len() + 5);
+    data.extend_from_slice(keyword.as_bytes());
+    data.extend_from_slice(&[0, 0, 0, 0, 0]); // Separator, compression flag/method, empty language and translated keyword
+    data.extend_from_slice(text.as_bytes());
+
+    let mut chunk = Vec::with_capacity(data.len() + 12);
+    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(b"iTXt");
+    chunk.extend_from_slice(&data);
+    chunk.extend_from_slice(&crc32(&chunk[4..]).to_be_bytes());
+    chunk
+}
+
+/// CRC-32 (ISO 3309) over a chunk's type and data, as PNG requires
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Read the edit recipe back out of a PNG exported with `embed_recipe`
+///
+/// Ok(None) for PNGs (or other files) without one; Err when the file can't
+/// be read or its recipe doesn't parse. Recipes without a version are from
+/// before the tone slider rescale and get upgraded.
+pub fn read_embedded_recipe(path: &Path) -> Result<Option<EditParams>, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let Some(mut rest) = bytes.strip_prefix(PNG_SIGNATURE.as_slice()) else {
+        return Ok(None);
+    };
+
+    let mut recipe = None;
+    let mut version = 1;
+    while rest.len() >= 12 {
+        let length = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let Some(data) = rest.get(8..8 + length) else {
+            break;
+        };
+        let kind = &rest[4..8];
+        if kind == b"IEND" {
+            break;
+        }
+        if let Some(text) = chunk_text(kind, data, RECIPE_KEYWORD) {
+            recipe = Some(text);
+        } else if let Some(text) = chunk_text(kind, data, RECIPE_VERSION_KEYWORD) {
+            version = std::str::from_utf8(text).ok().and_then(|text| text.trim().parse().ok()).unwrap_or(version);
+        }
+        rest = &rest[(12 + length).min(rest.len())..];
+    }
+
+    let Some(recipe) = recipe else {
+        return Ok(None);
+    };
+    let json = std::str::from_utf8(recipe).map_err(|_| "The embedded settings aren't valid text".to_string())?;
+    if version > SETTINGS_VERSION {
+        return Err(format!("The embedded settings (version {}) are newer than this app understands", version));
+    }
+    let params = if version < 2 { EditParams::from_legacy_json(json) } else { EditParams::from_json(json) };
+    params
+        .map(Some)
+        .map_err(|e| format!("Couldn't read the embedded settings: {}", e))
+}
+
+/// Text of a tEXt or (uncompressed) iTXt chunk with this keyword
+fn chunk_text<'a>(kind: &[u8], data: &'a [u8], keyword: &str) -> Option<&'a [u8]> {
+    let text = data.strip_prefix(keyword.as_bytes())?.strip_prefix(&[0])?;
+    match (kind, text) {
+        // Uncompressed only, which is all save_png writes
+        (b"iTXt", [0, _, tail @ ..]) => {
+            // Skip the language tag and translated keyword
+            tail.splitn(3, |&b| b == 0).nth(2)
+        }
+        (b"tEXt", text) => Some(text),
+        _ => None,
+    }
+}
+
+/// Save 16-bit RGB samples as an uncompressed TIFF (for print work)
+///
+/// The image structure tags and ICC profile go in the same IFD as the EXIF
+/// fields, so the file reads like any camera TIFF.
+pub fn save_tiff_16(path: &Path, rgb: &[u16], width: u32, height: u32, metadata: &[Field]) -> Result<(), String> {
+    let samples: Vec<u8> = rgb.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+    let row_bytes = (width as usize * 6).max(1);
+    let rows_per_strip = (TIFF_STRIP_BYTES / row_bytes).max(1);
+    let strips: Vec<&[u8]> = samples.chunks(rows_per_strip * row_bytes).collect();
+
+    let field = |tag: Tag, value: Value| Field { tag, ifd_num: In::PRIMARY, value };
+    let structure = [
+        field(Tag::ImageWidth, Value::Long(vec![width])),
+        field(Tag::ImageLength, Value::Long(vec![height])),
+        field(Tag::BitsPerSample, Value::Short(vec![16; 3])),
+        field(Tag::Compression, Value::Short(vec![1])), // None
+        field(Tag::PhotometricInterpretation, Value::Short(vec![2])), // RGB
+        field(Tag::SamplesPerPixel, Value::Short(vec![3])),
+        field(Tag::RowsPerStrip, Value::Long(vec![rows_per_strip as u32])),
+        field(Tag::PlanarConfiguration, Value::Short(vec![1])), // Interleaved
+        field(ICC_PROFILE_TAG, Value::Undefined(crate::color::srgb_icc_profile(), 0)),
+    ];
+
+    let mut writer = Writer::new();
+    for field in structure.iter().chain(metadata) {
+        writer.push_field(field);
+    }
+    writer.set_strips(&strips, In::PRIMARY);
+
+    let mut file = create(path)?;
+    writer
+        .write(&mut file, true)
+        .map_err(|e| format!("Failed to save image: {}", e))?;
+    file.flush().map_err(|e| format!("Failed to save image: {}", e))
+}
+
+/// What a filename template can refer to, for one exported image
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExportContext {
+    /// The RAW's filename, with extension
+    pub original_filename: String,
+    /// Position in the batch (1-based; always 1 for a single export)
+    pub sequence: usize,
+    /// EXIF capture time
+    pub captured_at: Option<NaiveDateTime>,
+    /// Camera name as shown in Develop
+    pub camera: Option<String>,
+    /// 0-5 stars
+    pub rating: i32,
+}
+
+impl ExportContext {
+    pub fn new(original_filename: &str, sequence: usize, metadata: &ImageMetadata, rating: i32) -> Self {
+        Self {
+            original_filename: original_filename.to_string(),
+            sequence,
+            captured_at: metadata.captured_at.as_deref().and_then(|at| {
+                NaiveDateTime::parse_from_str(at, "%Y-%m-%d %H:%M:%S").ok()
+            }),
+            camera: (metadata.camera_make.is_some() || metadata.camera_model.is_some())
+                .then(|| metadata.camera_display()),
+            rating,
+        }
+    }
+}
+
+/// Fill in a filename template
+///
+/// Tokens:
+/// - `{original}` or `{filename}`: RAW filename without extension
+/// - `{seq}`: position in the batch; `{seq:03}` pads it to 3 digits
+/// - `{n}`: same as `{seq}` (older templates)
+/// - `{date}`: capture date as 2024-06-01; `{date:%Y%m%d_%H%M}` takes any
+///   strftime format. "undated" when the file has no capture time.
+/// - `{camera}`: camera name ("unknown" without EXIF)
+/// - `{rating}`: number of stars
+///
+/// `{{` and `}}` are literal braces. Unknown tokens and malformed date
+/// formats are left as typed so the mistake shows in the preview.
+pub fn expand_template(template: &str, context: &ExportContext) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        let tail = &rest[i..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            out.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        let token = tail.strip_prefix('{').and_then(|t| t.find('}').map(|end| &t[..end]));
+        match token.and_then(|token| expand_token(token, context)) {
+            Some(value) => {
+                out.push_str(&value);
+                rest = &tail[token.map_or(0, str::len) + 2..];
+            }
+            None => {
+                out.push_str(&tail[..1]);
+                rest = &tail[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Value of one `{name}` or `{name:argument}` token (None = not a token)
+fn expand_token(token: &str, context: &ExportContext) -> Option<String> {
+    let (name, argument) = match token.split_once(':') {
+        Some((name, argument)) => (name, Some(argument)),
+        None => (token, None),
+    };
+    let stem = || {
+        Path::new(&context.original_filename)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| context.original_filename.clone())
+    };
+    match (name, argument) {
+        ("original" | "filename", None) => Some(stem()),
+        ("n" | "seq", None) => Some(context.sequence.to_string()),
+        ("seq", Some(width)) => {
+            let width: usize = width.parse().ok()?;
+            Some(format!("{:0width$}", context.sequence, width = width))
+        }
+        ("date", format) => {
+            let format = format.unwrap_or("%Y-%m-%d");
+            // A bad strftime spec would panic in Display: check it first
+            let items: Vec<_> = chrono::format::StrftimeItems::new(format).collect();
+            if items.iter().any(|item| matches!(item, chrono::format::Item::Error)) {
+                return None;
+            }
+            Some(context.captured_at.map_or_else(
+                || "undated".to_string(),
+                |at| at.format_with_items(items.into_iter()).to_string(),
+            ))
+        }
+        ("camera", None) => Some(context.camera.clone().unwrap_or_else(|| "unknown".to_string())),
+        ("rating", None) => Some(context.rating.to_string()),
+        _ => None,
+    }
+}
+
+/// Build the output filename for one exported image
+///
+/// A blank template falls back to the default; a template without a
+/// per-image token (filename or sequence) gets `_{seq}` appended so files
+/// don't overwrite each other. Path separators and colons are replaced so
+/// every file lands in the chosen folder under a name any OS accepts.
+pub fn export_file_name(template: &str, context: &ExportContext, format: ExportFormat) -> String {
+    let template = template.trim();
+    let template = if template.is_empty() { DEFAULT_TEMPLATE } else { template };
+
+    let mut name = expand_template(template, context);
+    let unique = ["{original}", "{filename}", "{n}", "{seq}", "{seq:"]
+        .iter()
+        .any(|token| template.contains(token));
+    if !unique {
+        name = format!("{}_{}", name, context.sequence);
+    }
+
+    let name: String = name
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':') { '_' } else { c })
+        .collect();
+    format!("{}.{}", name, format.extension())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(filename: &str, sequence: usize) -> ExportContext {
+        ExportContext { original_filename: filename.to_string(), sequence, ..Default::default() }
+    }
+
+    #[test]
+    fn test_export_file_name_template() {
+        assert_eq!(
+            export_file_name("{original}_edited", &context("DSC_0001.NEF", 1), ExportFormat::Jpeg),
+            "DSC_0001_edited.jpg"
+        );
+        assert_eq!(
+            export_file_name("trip_{n}", &context("DSC_0001.NEF", 3), ExportFormat::Png),
+            "trip_3.png"
+        );
+        // Blank falls back to the default template
+        assert_eq!(export_file_name("  ", &context("IMG_42.CR2", 1), ExportFormat::Jpeg), "IMG_42_edited.jpg");
+        // No placeholder: numbered so files stay unique
+        assert_eq!(export_file_name("export", &context("IMG_42.CR2", 2), ExportFormat::Jpeg), "export_2.jpg");
+        assert_eq!(export_file_name("{camera}", &context("IMG_42.CR2", 2), ExportFormat::Jpeg), "unknown_2.jpg");
+        // Can't escape the chosen folder
+        assert_eq!(export_file_name("../{original}", &context("a.NEF", 1), ExportFormat::Jpeg), ".._a.jpg");
+        // Times get a filesystem-safe separator
+        let timed = ExportContext {
+            captured_at: NaiveDateTime::parse_from_str("2024-06-01 18:30:05", "%Y-%m-%d %H:%M:%S").ok(),
+            ..context("a.NEF", 1)
+        };
+        assert_eq!(export_file_name("{date:%H:%M}_{seq}", &timed, ExportFormat::Jpeg), "18_30_1.jpg");
+    }
+
+    #[test]
+    fn test_expand_template_tokens() {
+        let metadata = ImageMetadata {
+            camera_make: Some("NIKON CORPORATION".to_string()),
+            camera_model: Some("NIKON D750".to_string()),
+            captured_at: Some("2024-06-01 18:30:05".to_string()),
+            ..Default::default()
+        };
+        let context = ExportContext::new("DSC_0001.NEF", 7, &metadata, 4);
+
+        assert_eq!(expand_template("{filename}", &context), "DSC_0001");
+        assert_eq!(expand_template("{date}_{seq:03}", &context), "2024-06-01_007");
+        assert_eq!(expand_template("{date:%Y%m%d-%H%M}", &context), "20240601-1830");
+        assert_eq!(expand_template("{camera} {rating}★", &context), "NIKON D750 4★");
+
+        // Escaped braces, unknown tokens and bad formats stay literal
+        assert_eq!(expand_template("{{seq}} {{{seq}}}", &context), "{seq} {7}");
+        assert_eq!(expand_template("{lens}_{seq:x}_{date:%Q}", &context), "{lens}_{seq:x}_{date:%Q}");
+        assert_eq!(expand_template("open { brace", &context), "open { brace");
+
+        // No EXIF
+        let bare = ExportContext::new("a.NEF", 1, &ImageMetadata::default(), 0);
+        assert_eq!(expand_template("{date}_{camera}", &bare), "undated_unknown");
+    }
+
+    #[test]
+    fn test_sequence_numbers_across_batch() {
+        let names: Vec<String> = ["a.NEF", "b.NEF", "c.NEF"]
+            .iter()
+            .enumerate()
+            .map(|(i, file)| export_file_name("wedding_{seq:02}", &context(file, i + 1), ExportFormat::Jpeg))
+            .collect();
+        assert_eq!(names, vec!["wedding_01.jpg", "wedding_02.jpg", "wedding_03.jpg"]);
+    }
+
+    #[test]
+    fn test_jpeg_quality_changes_file_size() {
+        let dir = std::env::temp_dir().join(format!("raw-editor-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // Noisy gradient: enough detail for quality to matter
+        let (width, height) = (64u32, 64u32);
+        let rgb: Vec<u8> = (0..width * height)
+            .flat_map(|i| {
+                let (x, y) = (i % width, i / width);
+                let noise = (i.wrapping_mul(2654435761) >> 24) as u8;
+                [(x * 4) as u8, (y * 4) as u8, noise]
+            })
+            .collect();
+
+        let low = dir.join("q50.jpg");
+        let high = dir.join("q95.jpg");
+        save_jpeg(&low, &rgb, width, height, 50, &[]).unwrap();
+        save_jpeg(&high, &rgb, width, height, 95, &[]).unwrap();
+
+        let low_size = std::fs::metadata(&low).unwrap().len();
+        let high_size = std::fs::metadata(&high).unwrap().len();
+        assert!(high_size > low_size, "q95 ({} bytes) should be larger than q50 ({} bytes)", high_size, low_size);
+
+        // Both decode back at the right size
+        assert_eq!(image::open(&low).unwrap().width(), width);
+        assert_eq!(image::open(&high).unwrap().height(), height);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_exports_embed_srgb_profile() {
+        use image::ImageDecoder;
+
+        let dir = std::env::temp_dir().join(format!("raw-editor-icc-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let profile = crate::color::srgb_icc_profile();
+        let (width, height) = (8u32, 4u32);
+        let reader = |path: &Path| std::io::BufReader::new(File::open(path).unwrap());
+
+        let jpeg = dir.join("tagged.jpg");
+        save_jpeg(&jpeg, &vec![128; (width * height * 3) as usize], width, height, 90, &[]).unwrap();
+        let mut decoder = image::codecs::jpeg::JpegDecoder::new(reader(&jpeg)).unwrap();
+        assert_eq!(decoder.icc_profile().unwrap(), Some(profile.clone()));
+
+        let png = dir.join("tagged.png");
+        save_png(&png, &vec![128; (width * height * 4) as usize], width, height, PngCompression::Fast, &[], None).unwrap();
+        let mut decoder = image::codecs::png::PngDecoder::new(reader(&png)).unwrap();
+        assert_eq!(decoder.icc_profile().unwrap(), Some(profile.clone()));
+
+        let tiff = dir.join("tagged.tif");
+        save_tiff_16(&tiff, &vec![32768; (width * height * 3) as usize], width, height, &[]).unwrap();
+        let mut decoder = image::codecs::tiff::TiffDecoder::new(reader(&tiff)).unwrap();
+        assert_eq!(decoder.icc_profile().unwrap(), Some(profile));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_exports_carry_exif_metadata() {
+        use image::ImageDecoder;
+
+        let dir = std::env::temp_dir().join(format!("raw-editor-export-exif-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (width, height) = (8u32, 4u32);
+        let ascii = |tag: Tag, value: &str| Field { tag, ifd_num: In::PRIMARY, value: Value::Ascii(vec![value.as_bytes().to_vec()]) };
+        let metadata = [
+            ascii(Tag::Model, "NIKON D750"),
+            ascii(Tag::DateTimeOriginal, "2024:06:01 18:30:05"),
+            ascii(Tag::Copyright, "Jane Doe"),
+        ];
+        let read = |path: &Path| {
+            let file = File::open(path).unwrap();
+            exif::Reader::new().read_from_container(&mut std::io::BufReader::new(file)).unwrap()
+        };
+        let model = |exif: &exif::Exif| exif.get_field(Tag::Model, In::PRIMARY).unwrap().display_value().to_string();
+
+        let jpeg = dir.join("exif.jpg");
+        save_jpeg(&jpeg, &vec![128; (width * height * 3) as usize], width, height, 90, &metadata).unwrap();
+        let exif = read(&jpeg);
+        assert_eq!(model(&exif), "\"NIKON D750\"");
+        assert!(exif.get_field(Tag::DateTimeOriginal, In::PRIMARY).is_some());
+
+        // 16-bit TIFF keeps its pixels, profile and metadata
+        let tiff = dir.join("exif.tif");
+        save_tiff_16(&tiff, &vec![32768; (width * height * 3) as usize], width, height, &metadata).unwrap();
+        assert!(read(&tiff).get_field(Tag::Copyright, In::PRIMARY).is_some());
+        let mut decoder = image::codecs::tiff::TiffDecoder::new(std::io::BufReader::new(File::open(&tiff).unwrap())).unwrap();
+        assert_eq!(decoder.icc_profile().unwrap(), Some(crate::color::srgb_icc_profile()));
+        let decoded = image::open(&tiff).unwrap().into_rgb16();
+        assert_eq!(decoded.dimensions(), (width, height));
+        assert!(decoded.pixels().all(|pixel| pixel.0 == [32768; 3]));
+
+        // A maker note too big for APP1 is dropped; the rest still goes in
+        let mut oversized = metadata.to_vec();
+        oversized.push(Field { tag: Tag::MakerNote, ifd_num: In::PRIMARY, value: Value::Undefined(vec![0; 70_000], 0) });
+        save_jpeg(&jpeg, &vec![128; (width * height * 3) as usize], width, height, 90, &oversized).unwrap();
+        let exif = read(&jpeg);
+        assert_eq!(model(&exif), "\"NIKON D750\"");
+        assert!(exif.get_field(Tag::MakerNote, In::PRIMARY).is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    #[test]
+    fn test_png_recipe_round_trip() {
+        let dir = std::env::temp_dir().join(format!("raw-editor-recipe-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (width, height) = (8u32, 4u32);
+        let pixels = vec![200; (width * height * 4) as usize];
+        let params = EditParams { exposure: 1.25, saturation: -20.0, ..EditParams::default() };
+        let recipe = params.to_json().unwrap();
+
+        let png = dir.join("with_recipe.png");
+        save_png(&png, &pixels, width, height, PngCompression::Fast, &[], Some(&recipe)).unwrap();
+        assert_eq!(read_embedded_recipe(&png).unwrap(), Some(params));
+        // Still a valid PNG with the same pixels
+        let decoded = image::open(&png).unwrap().into_rgba8();
+        assert_eq!(decoded.into_raw(), pixels);
+
+        let plain = dir.join("plain.png");
+        save_png(&plain, &pixels, width, height, PngCompression::Fast, &[], None).unwrap();
+        assert_eq!(read_embedded_recipe(&plain).unwrap(), None);
+        let jpeg = dir.join("photo.jpg");
+        save_jpeg(&jpeg, &vec![128; (width * height * 3) as usize], width, height, 90, &[]).unwrap();
+        assert_eq!(read_embedded_recipe(&jpeg).unwrap(), None);
+
+        // A recipe that isn't EditParams JSON is an error, not a silent reset
+        save_png(&plain, &pixels, width, height, PngCompression::Fast, &[], Some("{not json")).unwrap();
+        assert!(read_embedded_recipe(&plain).is_err());
+
+        // A recipe from before the version chunk is on the old tone scales
+        let mut legacy = Vec::new();
+        let encoder = PngEncoder::new_with_quality(&mut legacy, CompressionType::Fast, FilterType::Adaptive);
+        encoder.write_image(&pixels, width, height, ExtendedColorType::Rgba8).unwrap();
+        let iend = legacy.len() - 12;
+        legacy.splice(iend..iend, text_chunk(RECIPE_KEYWORD, r#"{"highlights": -0.25, "whites": 0.9}"#));
+        std::fs::write(&plain, legacy).unwrap();
+        let upgraded = read_embedded_recipe(&plain).unwrap().unwrap();
+        assert_eq!(upgraded.highlights, -50.0);
+        assert!((upgraded.whites - 50.0).abs() < 1e-3, "{}", upgraded.whites);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,90 @@
+/// Lens correction profiles
+///
+/// Both corrections are radial, with `r` measured from the image center and
+/// normalized so the corners are at 1.0:
+/// - Distortion: a point the corrected image shows at radius `r` was imaged
+///   by the lens at `r * (1 + k1 r² + k2 r⁴)`. Barrel distortion (lines
+///   bowing outward, typical of wide angles) has a negative `k1`.
+/// - Vignetting: the lens passes `1 + v1 r² + v2 r⁴` of the light at the
+///   center, so the shader divides by that.
+///
+/// The built-in table holds rough values for a few common wide primes, looked
+/// up by the EXIF lens model. Anything else only gets the manual distortion
+/// slider. Corrections run before crop and straighten, so the crop frames the
+/// corrected image.
+use super::edit::EditParams;
+
+/// Distortion and vignetting coefficients for one lens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LensProfile {
+    /// Shown in the Develop panel
+    pub name: &'static str,
+    /// Matched case-insensitively anywhere in the EXIF lens model
+    pub model_match: &'static str,
+    pub k1: f32,
+    pub k2: f32,
+    pub v1: f32,
+    pub v2: f32,
+}
+
+/// Lenses with a built-in profile
+pub const LENS_PROFILES: [LensProfile; 4] = [
+    LensProfile { name: "Sony FE 24mm F1.4 GM", model_match: "FE 24mm F1.4 GM", k1: -0.025, k2: 0.004, v1: -0.38, v2: 0.06 },
+    LensProfile { name: "Fujifilm XF16mmF1.4 R WR", model_match: "XF16mmF1.4", k1: -0.045, k2: 0.008, v1: -0.45, v2: 0.09 },
+    LensProfile { name: "Canon RF16mm F2.8 STM", model_match: "RF16mm F2.8", k1: -0.12, k2: 0.03, v1: -0.55, v2: 0.12 },
+    LensProfile { name: "Nikon NIKKOR Z 20mm f/1.8 S", model_match: "NIKKOR Z 20mm f/1.8", k1: -0.035, k2: 0.006, v1: -0.42, v2: 0.07 },
+];
+
+/// `k1` added at the manual distortion slider's +1 end
+const MANUAL_DISTORTION_K1: f32 = -0.15;
+
+/// Built-in profile for an EXIF lens model, if there is one
+pub fn find_profile(lens_model: Option<&str>) -> Option<LensProfile> {
+    let model = lens_model?.to_lowercase();
+    LENS_PROFILES
+        .iter()
+        .find(|profile| model.contains(&profile.model_match.to_lowercase()))
+        .copied()
+}
+
+/// Coefficients the shader applies: the lens profile (if any) plus the
+/// manual distortion slider. None when lens correction is switched off.
+pub fn coefficients(params: &EditParams, profile: Option<&LensProfile>) -> Option<[f32; 4]> {
+    if !params.lens_correction {
+        return None;
+    }
+    let [k1, k2, v1, v2] = profile.map_or([0.0; 4], |p| [p.k1, p.k2, p.v1, p.v2]);
+    Some([k1 + MANUAL_DISTORTION_K1 * params.lens_distortion, k2, v1, v2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_profile_matches_exif_model() {
+        let profile = find_profile(Some("FE 24mm F1.4 GM")).unwrap();
+        assert_eq!(profile.name, "Sony FE 24mm F1.4 GM");
+
+        // Case and surrounding text don't matter
+        let profile = find_profile(Some("xf16mmf1.4 r wr")).unwrap();
+        assert_eq!(profile.name, "Fujifilm XF16mmF1.4 R WR");
+
+        assert_eq!(find_profile(Some("EF50mm f/1.8 STM")), None);
+        assert_eq!(find_profile(None), None);
+    }
+
+    #[test]
+    fn test_coefficients_follow_switch_and_slider() {
+        let profile = LENS_PROFILES[0];
+        let off = EditParams { lens_distortion: 0.5, ..EditParams::default() };
+        assert_eq!(coefficients(&off, Some(&profile)), None);
+
+        let on = EditParams { lens_correction: true, ..EditParams::default() };
+        assert_eq!(coefficients(&on, Some(&profile)), Some([profile.k1, profile.k2, profile.v1, profile.v2]));
+
+        // Unknown lens: only the manual slider, no vignetting correction
+        let manual = EditParams { lens_correction: true, lens_distortion: 1.0, ..EditParams::default() };
+        assert_eq!(coefficients(&manual, None), Some([MANUAL_DISTORTION_K1, 0.0, 0.0, 0.0]));
+    }
+}
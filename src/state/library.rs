@@ -1,6 +1,135 @@
-use rusqlite::{Connection, Result as SqlResult};
-use std::path::PathBuf;
-use super::data::Image;
+use chrono::NaiveDate;
+use rusqlite::{Connection, OpenFlags, OptionalExtension, Result as SqlResult};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use super::backup::{self, BackupImage, CatalogBackup, ImportSummary, BACKUP_VERSION};
+use super::data::{Image, LibraryFilter, SortOrder, Snapshot};
+use super::exif::ImageMetadata;
+use super::presets::PresetError;
+
+/// Columns selected for every `Image` query, in the order `image_from_row` expects
+///
+/// An image is edited when it has an edits row (`save_edit_params` drops the
+/// row for default settings); the EXISTS is answered from idx_edits_image_id.
+const IMAGE_COLUMNS: &str =
+    "id, filename, path, cache_path_thumb, cache_path_instant, cache_path_working, COALESCE(file_status, 'exists'), \
+     COALESCE(rating, 0), flag, COALESCE(orientation, 1), \
+     EXISTS(SELECT 1 FROM edits WHERE edits.image_id = images.id), avg_color";
+
+/// Build an `Image` from a row selected with `IMAGE_COLUMNS`
+fn image_from_row(row: &rusqlite::Row) -> SqlResult<Image> {
+    Ok(Image {
+        id: row.get(0)?,
+        filename: row.get(1)?,
+        path: row.get(2)?,
+        cache_path_thumb: row.get(3)?,
+        cache_path_instant: row.get(4)?,
+        cache_path_working: row.get(5)?,
+        file_status: row.get(6)?,
+        rating: row.get(7)?,
+        flag: row.get(8)?,
+        orientation: row.get(9)?,
+        is_edited: row.get(10)?,
+        avg_color: row.get::<_, Option<u32>>(11)?.map(unpack_color),
+    })
+}
+
+/// 0xRRGGBB as stored in `avg_color`
+pub fn pack_color([r, g, b]: [u8; 3]) -> u32 {
+    u32::from_be_bytes([0, r, g, b])
+}
+
+/// Inverse of `pack_color`
+fn unpack_color(packed: u32) -> [u8; 3] {
+    let [_, r, g, b] = packed.to_be_bytes();
+    [r, g, b]
+}
+
+/// Paths already in the catalog at or below `root` (a folder or a single file)
+///
+/// Import diffs against this set instead of attempting an INSERT per file;
+/// takes a bare connection since the import runs on its own thread.
+pub fn cataloged_paths_under(conn: &Connection, root: &Path) -> SqlResult<HashSet<String>> {
+    // % and _ in folder names are literal, not LIKE wildcards
+    let pattern = format!(
+        "{}%",
+        root.to_string_lossy().replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+    );
+    let mut stmt = conn.prepare("SELECT path FROM images WHERE path LIKE ?1 ESCAPE '\\'")?;
+    let rows = stmt.query_map([pattern], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Why the catalog couldn't be opened
+#[derive(Debug, Clone)]
+pub enum LibraryError {
+    /// The file is damaged or isn't a database (back it up and start fresh)
+    Corrupt(String),
+    /// Another process holds the catalog's lock (retrying may work)
+    Locked(String),
+    /// Anything else: no data directory, permissions, ...
+    Other(String),
+}
+
+impl LibraryError {
+    /// Whether backing up the catalog and recreating it would help
+    pub fn is_corrupt(&self) -> bool {
+        matches!(self, LibraryError::Corrupt(_))
+    }
+}
+
+impl std::fmt::Display for LibraryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LibraryError::Corrupt(e) => write!(f, "The catalog is damaged ({})", e),
+            LibraryError::Locked(e) => write!(f, "The catalog is in use by another program ({})", e),
+            LibraryError::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for LibraryError {}
+
+/// What `Library::recover` got back out of a damaged catalog
+#[derive(Debug, Clone, Default)]
+pub struct Recovery {
+    /// Where the damaged file was moved
+    pub backup: PathBuf,
+    /// Images in the new catalog
+    pub images: usize,
+    /// Of those, images whose edits came from the old catalog
+    pub edits_salvaged: usize,
+    /// ...and images whose edits came from an XMP sidecar instead
+    pub edits_from_sidecars: usize,
+    /// Images whose edits were unreadable with no sidecar to fall back on
+    pub edits_lost: usize,
+    /// Image rows that couldn't be read at all
+    pub images_lost: usize,
+}
+
+/// One image read back from a damaged catalog
+#[derive(Debug, Default)]
+struct SalvagedImage {
+    path: String,
+    filename: String,
+    rating: i32,
+    flag: Option<String>,
+    orientation: Option<u8>,
+    keywords: Vec<String>,
+    /// None: no edits row. Some(None): a row that couldn't be read or parsed
+    edits: Option<Option<super::edit::EditParams>>,
+}
+
+impl From<rusqlite::Error> for LibraryError {
+    fn from(e: rusqlite::Error) -> Self {
+        use rusqlite::ErrorCode;
+        match e.sqlite_error_code() {
+            Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase) => LibraryError::Corrupt(e.to_string()),
+            Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => LibraryError::Locked(e.to_string()),
+            _ => LibraryError::Other(e.to_string()),
+        }
+    }
+}
 
 /// The Library manages the SQLite catalog database.
 /// It stores image metadata, edit history, and references to RAW files.
@@ -16,17 +145,21 @@ impl Library {
     /// - Linux: ~/.local/share/raw-editor/raw_editor.db
     /// - macOS: ~/Library/Application Support/raw-editor/raw_editor.db
     /// - Windows: %APPDATA%\raw-editor\raw_editor.db
-    pub fn new() -> SqlResult<Self> {
-        let db_path = Self::get_db_path();
-        
+    pub fn new() -> Result<Self, LibraryError> {
+        Self::open_at(Self::get_db_path()?)
+    }
+
+    /// Open or create the catalog at `db_path`
+    fn open_at(db_path: PathBuf) -> Result<Self, LibraryError> {
         // Ensure the parent directory exists
         if let Some(parent) = db_path.parent() {
             std::fs::create_dir_all(parent)
-                .expect("Failed to create application data directory");
+                .map_err(|e| LibraryError::Other(format!("Failed to create {}: {}", parent.display(), e)))?;
         }
 
-        // Open or create the database
+        // Open or create the database; wait a little for a lock held elsewhere
         let conn = Connection::open(&db_path)?;
+        conn.busy_timeout(std::time::Duration::from_secs(2))?;
         
         println!("📁 Database initialized at: {}", db_path.display());
         
@@ -36,89 +169,170 @@ impl Library {
         Ok(library)
     }
 
+    /// Move a damaged catalog aside and create a fresh one in its place.
+    /// Whatever can still be read from the old file is carried over, one
+    /// row at a time: images, ratings, flags, orientation, keywords and
+    /// edits. Images whose edits are gone get them from their XMP sidecar,
+    /// if there is one.
+    pub fn recover() -> Result<Recovery, LibraryError> {
+        Self::recover_at(&Self::get_db_path()?)
+    }
+
+    fn recover_at(db_path: &Path) -> Result<Recovery, LibraryError> {
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let backup_path = db_path.with_extension(format!("db.corrupt-{}", stamp));
+        std::fs::rename(db_path, &backup_path)
+            .map_err(|e| LibraryError::Other(format!("Failed to back up {}: {}", db_path.display(), e)))?;
+        // The journal files belong to the old catalog; a new one must not replay them
+        for suffix in ["-wal", "-shm", "-journal"] {
+            let journal = PathBuf::from(format!("{}{}", db_path.display(), suffix));
+            if journal.exists() {
+                let _ = std::fs::rename(&journal, format!("{}{}", backup_path.display(), suffix));
+            }
+        }
+        println!("🗄️  Damaged catalog moved to {}", backup_path.display());
+
+        let (salvaged, images_lost) = Self::salvage(&backup_path);
+        let library = Self::open_at(db_path.to_path_buf())?;
+        let mut recovery = Recovery { backup: backup_path, images_lost, ..Default::default() };
+        for image in salvaged {
+            let Ok(image_id) = library.import_image(&image.path, &image.filename) else {
+                continue;
+            };
+            recovery.images += 1;
+            // Best effort from here on: the image itself is back
+            let _ = library.set_rating(image_id, image.rating);
+            if let Some(flag) = &image.flag {
+                let _ = library.set_flag(image_id, flag);
+            }
+            if let Some(orientation) = image.orientation {
+                let _ = library.set_orientation(image_id, orientation);
+            }
+            for keyword in &image.keywords {
+                let _ = library.add_keyword(image_id, keyword);
+            }
+            let edits = match image.edits {
+                Some(Some(params)) => {
+                    recovery.edits_salvaged += 1;
+                    Some(params)
+                }
+                damaged => match super::sidecar::read_sidecar(Path::new(&image.path)) {
+                    Some(params) => {
+                        recovery.edits_from_sidecars += 1;
+                        Some(params)
+                    }
+                    None => {
+                        if damaged.is_some() {
+                            recovery.edits_lost += 1;
+                        }
+                        None
+                    }
+                },
+            };
+            if let Some(params) = edits {
+                let _ = library.save_edit_params(image_id, &params);
+            }
+        }
+        println!(
+            "✅ Recreated the catalog with {} recovered images ({} with edits, {} from sidecars, {} edits lost, {} images lost)",
+            recovery.images,
+            recovery.edits_salvaged,
+            recovery.edits_from_sidecars,
+            recovery.edits_lost,
+            recovery.images_lost
+        );
+
+        Ok(recovery)
+    }
+
+    /// Images still readable from a damaged catalog, and how many rows weren't
+    ///
+    /// Rows are read one by one by id, so a bad page only costs the rows on
+    /// it; columns a catalog predates (or can't read) keep their defaults.
+    fn salvage(path: &Path) -> (Vec<SalvagedImage>, usize) {
+        let Ok(conn) = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY) else {
+            return (Vec::new(), 0);
+        };
+        let Ok(max_id) = conn.query_row("SELECT COALESCE(MAX(id), 0) FROM images", [], |row| row.get::<_, i64>(0)) else {
+            return (Vec::new(), 0);
+        };
+        // Settings saved before v14 are on the old tone scales
+        let legacy = super::migrations::schema_version(&conn)
+            .is_ok_and(|version| version < super::migrations::TONE_SCALE_VERSION);
+        let mut images = Vec::new();
+        let mut lost = 0;
+        for id in 1..=max_id {
+            let row = conn.query_row("SELECT path, filename FROM images WHERE id = ?1", [id], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            });
+            let (path, filename) = match row {
+                Ok(row) => row,
+                Err(rusqlite::Error::QueryReturnedNoRows) => continue,
+                Err(_) => {
+                    lost += 1;
+                    continue;
+                }
+            };
+            let mut image = SalvagedImage { path, filename, ..Default::default() };
+            if let Ok((rating, flag)) = conn.query_row(
+                "SELECT COALESCE(rating, 0), flag FROM images WHERE id = ?1",
+                [id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            ) {
+                (image.rating, image.flag) = (rating, flag);
+            }
+            image.orientation = conn
+                .query_row("SELECT orientation FROM images WHERE id = ?1", [id], |row| row.get(0))
+                .ok()
+                .flatten();
+            if let Ok(mut stmt) = conn.prepare("SELECT keyword FROM keywords WHERE image_id = ?1") {
+                if let Ok(rows) = stmt.query_map([id], |row| row.get(0)) {
+                    image.keywords = rows.map_while(Result::ok).collect();
+                }
+            }
+            image.edits = match conn.query_row(
+                "SELECT settings_json FROM edits WHERE image_id = ?1 ORDER BY id DESC LIMIT 1",
+                [id],
+                |row| row.get::<_, String>(0),
+            ) {
+                Ok(json) if legacy => Some(super::edit::EditParams::from_legacy_json(&json).ok()),
+                Ok(json) => Some(super::edit::EditParams::from_json(&json).ok()),
+                Err(rusqlite::Error::QueryReturnedNoRows) => None,
+                Err(_) => Some(None),
+            };
+            images.push(image);
+        }
+        (images, lost)
+    }
+
+    /// Open a throwaway in-memory catalog (used by tests)
+    #[cfg(test)]
+    pub fn open_in_memory() -> SqlResult<Self> {
+        let conn = Connection::open_in_memory()?;
+        let mut library = Library { conn, db_path: PathBuf::from(":memory:") };
+        library.init_schema()?;
+        Ok(library)
+    }
+
     /// Get the path where the database should be stored
-    fn get_db_path() -> PathBuf {
+    fn get_db_path() -> Result<PathBuf, LibraryError> {
         let mut path = dirs::data_dir()
             .or_else(|| dirs::home_dir())
-            .expect("Could not determine user data directory");
+            .ok_or_else(|| LibraryError::Other("Could not determine user data directory".to_string()))?;
         
         path.push("raw-editor");
         path.push("raw_editor.db");
-        path
+        Ok(path)
     }
 
     /// Initialize the database schema.
-    /// Creates all necessary tables and indexes if they don't exist.
+    /// Runs any pending migrations (see migrations.rs), then seeds the
+    /// built-in presets into a catalog that has none.
     fn init_schema(&mut self) -> SqlResult<()> {
-        // Create images table
-        // This stores metadata about imported RAW files
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS images (
-                id              INTEGER PRIMARY KEY AUTOINCREMENT,
-                path            TEXT NOT NULL UNIQUE,
-                filename        TEXT NOT NULL,
-                width           INTEGER,
-                height          INTEGER,
-                imported_at     INTEGER NOT NULL,
-                cache_status    TEXT DEFAULT 'pending'
-            )",
-            [],
-        )?;
-
-        // Create edits table
-        // This stores the edit stack for each image as JSON
-        self.conn.execute(
-            "CREATE TABLE IF NOT EXISTS edits (
-                id              INTEGER PRIMARY KEY AUTOINCREMENT,
-                image_id        INTEGER NOT NULL,
-                settings_json   TEXT NOT NULL,
-                FOREIGN KEY(image_id) REFERENCES images(id) ON DELETE CASCADE
-            )",
-            [],
-        )?;
-
-        // Create indexes for fast queries
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_images_imported_at 
-             ON images(imported_at DESC)",
-            [],
-        )?;
-
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_edits_image_id 
-             ON edits(image_id)",
-            [],
-        )?;
-
-        // Phase 28: Multi-tier cache system
-        // Add 3 cache path columns for different resolution tiers
-        let _ = self.conn.execute(
-            "ALTER TABLE images ADD COLUMN cache_path_thumb TEXT",  // 256px
-            [],
-        );
-        let _ = self.conn.execute(
-            "ALTER TABLE images ADD COLUMN cache_path_instant TEXT",  // 384px
-            [],
-        );
-        let _ = self.conn.execute(
-            "ALTER TABLE images ADD COLUMN cache_path_working TEXT",  // 1280px
-            [],
-        );
+        super::migrations::migrate(&mut self.conn)?;
+        self.seed_builtin_presets()?;
 
-        // Add file_status column for tracking deleted files
-        let _ = self.conn.execute(
-            "ALTER TABLE images ADD COLUMN file_status TEXT DEFAULT 'exists'",
-            [],
-        );
-
-        // Create index for cache_status to quickly find pending thumbnails
-        self.conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_images_cache_status 
-             ON images(cache_status)",
-            [],
-        )?;
-
-        println!("✅ Database schema initialized");
+        println!("✅ Database schema initialized (v{})", super::migrations::LATEST_VERSION);
         
         Ok(())
     }
@@ -161,24 +375,54 @@ impl Library {
         Ok(self.conn.last_insert_rowid())
     }
 
+    /// Get the EXIF metadata stored for an image (all None if it had none)
+    pub fn get_metadata(&self, image_id: i64) -> SqlResult<ImageMetadata> {
+        self.conn.query_row(
+            "SELECT camera_make, camera_model, iso, shutter_seconds, aperture, focal_length, captured_at,
+             orientation, lens_model FROM images WHERE id = ?1",
+            [image_id],
+            |row| Ok(ImageMetadata {
+                camera_make: row.get(0)?,
+                camera_model: row.get(1)?,
+                iso: row.get(2)?,
+                shutter_seconds: row.get(3)?,
+                aperture: row.get(4)?,
+                focal_length: row.get(5)?,
+                captured_at: row.get(6)?,
+                orientation: row.get(7)?,
+                lens_model: row.get(8)?,
+            }),
+        )
+    }
+
     /// Get all images from the library
     /// Returns a vector of Image structs ordered by import date (newest first)
     pub fn get_all_images(&self) -> SqlResult<Vec<Image>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, filename, path, cache_path_thumb, cache_path_instant, cache_path_working, COALESCE(file_status, 'exists') FROM images ORDER BY imported_at DESC"
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM images ORDER BY imported_at DESC",
+            IMAGE_COLUMNS
+        ))?;
 
-        let image_iter = stmt.query_map([], |row| {
-            Ok(Image {
-                id: row.get(0)?,
-                filename: row.get(1)?,
-                path: row.get(2)?,
-                cache_path_thumb: row.get(3)?,
-                cache_path_instant: row.get(4)?,
-                cache_path_working: row.get(5)?,
-                file_status: row.get(6)?,
-            })
-        })?;
+        let image_iter = stmt.query_map([], image_from_row)?;
+
+        let mut images = Vec::new();
+        for image in image_iter {
+            images.push(image?);
+        }
+
+        Ok(images)
+    }
+
+    /// Get the images matching `filter`, in `sort` order
+    /// With the default filter and sort this is the same as `get_all_images`.
+    pub fn get_images_filtered(&self, sort: SortOrder, filter: LibraryFilter) -> SqlResult<Vec<Image>> {
+        let (where_clause, order_by) = Self::filter_clauses(sort, filter);
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM images {} ORDER BY {}",
+            IMAGE_COLUMNS, where_clause, order_by
+        ))?;
+
+        let image_iter = stmt.query_map([], image_from_row)?;
 
         let mut images = Vec::new();
         for image in image_iter {
@@ -188,26 +432,103 @@ impl Library {
         Ok(images)
     }
 
+    /// Same images as `get_images_filtered`, split into one section per day
+    /// (newest first). The day is the EXIF capture date, or the import date
+    /// in local time for images without one; `sort` orders each section.
+    pub fn get_images_grouped_by_date(
+        &self,
+        sort: SortOrder,
+        filter: LibraryFilter,
+    ) -> SqlResult<Vec<(NaiveDate, Vec<Image>)>> {
+        let (where_clause, order_by) = Self::filter_clauses(sort, filter);
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}, COALESCE(substr(captured_at, 1, 10), date(imported_at, 'unixepoch', 'localtime')) AS day
+             FROM images {} ORDER BY day DESC, {}",
+            IMAGE_COLUMNS, where_clause, order_by
+        ))?;
+
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>("day")?, image_from_row(row)?)))?;
+
+        let mut groups: Vec<(NaiveDate, Vec<Image>)> = Vec::new();
+        for row in rows {
+            let (day, image) = row?;
+            let date = NaiveDate::parse_from_str(&day, "%Y-%m-%d").unwrap_or_default();
+            match groups.last_mut() {
+                Some((last, images)) if *last == date => images.push(image),
+                _ => groups.push((date, vec![image])),
+            }
+        }
+
+        Ok(groups)
+    }
+
+    /// WHERE and ORDER BY clauses shared by the Library grid queries
+    fn filter_clauses(sort: SortOrder, filter: LibraryFilter) -> (String, &'static str) {
+        let mut conditions = Vec::new();
+        if filter.only_rated {
+            conditions.push("COALESCE(rating, 0) > 0");
+        }
+        if filter.only_flagged {
+            conditions.push("flag IS NOT NULL");
+        }
+        if filter.hide_deleted {
+            conditions.push("COALESCE(file_status, 'exists') != 'deleted'");
+        }
+        if filter.hide_rejects {
+            conditions.push("COALESCE(flag, '') != 'reject'");
+        }
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let order_by = match sort {
+            SortOrder::ImportDate => "imported_at DESC, id DESC",
+            SortOrder::Filename => "filename COLLATE NOCASE ASC, id ASC",
+            SortOrder::Rating => "COALESCE(rating, 0) DESC, imported_at DESC, id DESC",
+        };
+
+        (where_clause, order_by)
+    }
+
+    /// Images added by one import run, newest first
+    pub fn get_images_by_batch(&self, batch_id: i64) -> SqlResult<Vec<Image>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM images WHERE import_batch = ?1 ORDER BY imported_at DESC, id DESC",
+            IMAGE_COLUMNS
+        ))?;
+        let rows = stmt.query_map([batch_id], image_from_row)?;
+        rows.collect()
+    }
+    
+    /// Every image flagged reject, newest first (for "Delete Rejected")
+    pub fn get_rejected(&self) -> SqlResult<Vec<Image>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM images WHERE flag = ?1 ORDER BY imported_at DESC, id DESC",
+            IMAGE_COLUMNS
+        ))?;
+        let rows = stmt.query_map([crate::state::data::FLAG_REJECT], image_from_row)?;
+        rows.collect()
+    }
+    
+    /// Id of the most recent import run that added anything (None if every
+    /// image predates import batches)
+    pub fn latest_import_batch(&self) -> SqlResult<Option<i64>> {
+        self.conn.query_row("SELECT MAX(import_batch) FROM images", [], |row| row.get(0))
+    }
+    
     /// Get images that need thumbnail generation (cache_status = 'pending')
     pub fn get_pending_thumbnails(&self, limit: usize) -> SqlResult<Vec<Image>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, filename, path, cache_path_thumb, cache_path_instant, cache_path_working, COALESCE(file_status, 'exists') 
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} 
              FROM images 
              WHERE cache_status = 'pending' 
-             LIMIT ?1"
-        )?;
+             LIMIT ?1",
+            IMAGE_COLUMNS
+        ))?;
 
-        let image_iter = stmt.query_map([limit], |row| {
-            Ok(Image {
-                id: row.get(0)?,
-                filename: row.get(1)?,
-                path: row.get(2)?,
-                cache_path_thumb: row.get(3)?,
-                cache_path_instant: row.get(4)?,
-                cache_path_working: row.get(5)?,
-                file_status: row.get(6)?,
-            })
-        })?;
+        let image_iter = stmt.query_map([limit], image_from_row)?;
 
         let mut images = Vec::new();
         for image in image_iter {
@@ -217,53 +538,102 @@ impl Library {
         Ok(images)
     }
 
-    /// Update an image's thumbnail path and mark it as cached
+    /// Update an image's thumbnail tier path and mark it as cached
     pub fn update_thumbnail(&self, image_id: i64, thumbnail_path: &str) -> SqlResult<()> {
         self.conn.execute(
-            "UPDATE images SET thumbnail_path = ?1, cache_status = 'cached' WHERE id = ?2",
+            "UPDATE images SET cache_path_thumb = ?1, cache_status = 'cached' WHERE id = ?2",
             rusqlite::params![thumbnail_path, image_id],
         )?;
         Ok(())
     }
 
-    /// Set an image's preview path (full-size embedded JPEG)
+    /// Set an image's working preview tier path (1280px)
     pub fn set_image_preview_path(&self, image_id: i64, path: &str) -> SqlResult<()> {
         self.conn.execute(
-            "UPDATE images SET preview_path = ?1 WHERE id = ?2",
+            "UPDATE images SET cache_path_working = ?1 WHERE id = ?2",
             rusqlite::params![path, image_id],
         )?;
         Ok(())
     }
 
-    /// Verify cached thumbnails actually exist on disk
-    /// Reset to 'pending' if thumbnail file is missing
+    /// Set an image's star rating (clamped to 0-5, 0 = unrated)
+    pub fn set_rating(&self, image_id: i64, rating: i32) -> SqlResult<()> {
+        self.conn.execute(
+            "UPDATE images SET rating = ?1 WHERE id = ?2",
+            rusqlite::params![rating.clamp(0, 5), image_id],
+        )?;
+        Ok(())
+    }
+
+    /// Set an image's orientation (EXIF value 1-8, manual rotations included)
+    pub fn set_orientation(&self, image_id: i64, orientation: u8) -> SqlResult<()> {
+        self.conn.execute(
+            "UPDATE images SET orientation = ?1 WHERE id = ?2",
+            rusqlite::params![orientation, image_id],
+        )?;
+        Ok(())
+    }
+
+    /// Set an image's culling flag ("pick" / "reject"); an empty string clears it
+    pub fn set_flag(&self, image_id: i64, flag: &str) -> SqlResult<()> {
+        let flag = if flag.is_empty() { None } else { Some(flag) };
+        self.conn.execute(
+            "UPDATE images SET flag = ?1 WHERE id = ?2",
+            rusqlite::params![flag, image_id],
+        )?;
+        Ok(())
+    }
+
+    /// Verify cached thumbnails and previews are still usable
+    /// A thumbnail that's missing, unreadable or broken (e.g. cut off by an
+    /// interrupted write, see `cache_file_is_valid`) is reset to 'pending' so
+    /// the worker makes it again; a broken preview tier is just forgotten.
+    /// Broken files are deleted. Returns how many thumbnails were reset.
     pub fn verify_thumbnails(&self) -> SqlResult<usize> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, thumbnail_path FROM images WHERE cache_status = 'cached' AND thumbnail_path IS NOT NULL"
+            "SELECT id, cache_status = 'cached', cache_path_thumb, cache_path_instant, cache_path_working FROM images
+             WHERE cache_path_thumb IS NOT NULL OR cache_path_instant IS NOT NULL OR cache_path_working IS NOT NULL"
         )?;
 
-        let cached_images: Vec<(i64, String)> = stmt
+        let cached_images: Vec<(i64, bool, [Option<String>; 3])> = stmt
             .query_map([], |row| {
-                Ok((row.get(0)?, row.get(1)?))
+                Ok((row.get(0)?, row.get(1)?, [row.get(2)?, row.get(3)?, row.get(4)?]))
             })?
             .filter_map(|r| r.ok())
             .collect();
 
         let mut reset_count = 0;
-        for (id, thumbnail_path) in cached_images {
-            // Check if file exists
-            if !std::path::Path::new(&thumbnail_path).exists() {
-                // Reset to pending since thumbnail is missing
-                self.conn.execute(
-                    "UPDATE images SET cache_status = 'pending', thumbnail_path = NULL WHERE id = ?1",
-                    rusqlite::params![id],
-                )?;
-                reset_count += 1;
+        let mut preview_count = 0;
+        for (id, cached, [thumbnail, instant, working]) in cached_images {
+            if let Some(thumbnail_path) = thumbnail.filter(|_| cached) {
+                if !cache_file_is_valid(Path::new(&thumbnail_path)) {
+                    // Reset to pending since thumbnail is missing or broken
+                    self.conn.execute(
+                        "UPDATE images SET cache_status = 'pending', cache_path_thumb = NULL WHERE id = ?1",
+                        rusqlite::params![id],
+                    )?;
+                    let _ = std::fs::remove_file(&thumbnail_path);
+                    reset_count += 1;
+                }
+            }
+            for (column, preview_path) in [("cache_path_instant", instant), ("cache_path_working", working)] {
+                let Some(preview_path) = preview_path else { continue };
+                if !cache_file_is_valid(Path::new(&preview_path)) {
+                    self.conn.execute(
+                        &format!("UPDATE images SET {} = NULL WHERE id = ?1", column),
+                        rusqlite::params![id],
+                    )?;
+                    let _ = std::fs::remove_file(&preview_path);
+                    preview_count += 1;
+                }
             }
         }
 
         if reset_count > 0 {
-            println!("🔄 Reset {} missing thumbnails to pending", reset_count);
+            println!("🔄 Reset {} missing or broken thumbnails to pending", reset_count);
+        }
+        if preview_count > 0 {
+            println!("🔄 Dropped {} missing or broken cached previews", preview_count);
         }
 
         Ok(reset_count)
@@ -303,11 +673,117 @@ impl Library {
         Ok(deleted_count)
     }
     
+    /// Remove an image from the catalog: its row, its edits, and its cached
+    /// thumbnail/preview files (every tier, plus the legacy thumbnails and
+    /// previews folders). The RAW file itself is left alone.
+    ///
+    /// Returns the RAW path so the caller can trash it, or None if the image
+    /// wasn't in the catalog.
+    pub fn remove_image(&self, image_id: i64) -> SqlResult<Option<String>> {
+        let row = self.conn
+            .query_row(
+                "SELECT path, cache_path_thumb, cache_path_instant, cache_path_working FROM images WHERE id = ?1",
+                [image_id],
+                |row| Ok((row.get::<_, String>(0)?, [row.get::<_, Option<String>>(1)?, row.get(2)?, row.get(3)?])),
+            )
+            .optional()?;
+        let Some((raw_path, cache_paths)) = row else {
+            return Ok(None);
+        };
+        
+        // foreign_keys is off by default in SQLite, so don't rely on ON DELETE CASCADE
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM edits WHERE image_id = ?1", [image_id])?;
+        tx.execute("DELETE FROM snapshots WHERE image_id = ?1", [image_id])?;
+        tx.execute("DELETE FROM keywords WHERE image_id = ?1", [image_id])?;
+        tx.execute("DELETE FROM images WHERE id = ?1", [image_id])?;
+        tx.commit()?;
+        
+        let removed = delete_cache_files(image_id, cache_paths);
+        
+        println!("🗑️  Removed image {} from catalog ({} cache files deleted)", image_id, removed);
+        Ok(Some(raw_path))
+    }
+    
+    /// Throw away an image's cached thumbnail/preview tiers and queue it for
+    /// the thumbnail worker again. Returns false if the image isn't in the catalog.
+    pub fn reset_cache_status(&self, image_id: i64) -> SqlResult<bool> {
+        let cache_paths = self.conn
+            .query_row(
+                "SELECT cache_path_thumb, cache_path_instant, cache_path_working FROM images WHERE id = ?1",
+                [image_id],
+                |row| Ok([row.get::<_, Option<String>>(0)?, row.get(1)?, row.get(2)?]),
+            )
+            .optional()?;
+        let Some(cache_paths) = cache_paths else {
+            return Ok(false);
+        };
+        
+        self.conn.execute(
+            "UPDATE images SET cache_status = 'pending', cache_path_thumb = NULL,
+                 cache_path_instant = NULL, cache_path_working = NULL
+             WHERE id = ?1",
+            [image_id],
+        )?;
+        let removed = delete_cache_files(image_id, cache_paths);
+        println!("🔄 Queued image {} for new thumbnails ({} cache files deleted)", image_id, removed);
+        Ok(true)
+    }
+    
+    /// `reset_cache_status` for every image whose RAW file is still on disk.
+    /// Returns how many images were queued.
+    pub fn reset_all_cache_status(&self) -> SqlResult<usize> {
+        let ids: Vec<i64> = self.conn
+            .prepare("SELECT id FROM images WHERE file_status = 'exists'")?
+            .query_map([], |row| row.get(0))?
+            .collect::<SqlResult<_>>()?;
+        for &id in &ids {
+            self.reset_cache_status(id)?;
+        }
+        Ok(ids.len())
+    }
+    
+    /// Forget every cached file path and queue all images whose RAW is still
+    /// there for new thumbnails (the files themselves are deleted by
+    /// `settings::clear_cache_folders`). Returns how many images were queued.
+    pub fn clear_all_caches(&self) -> SqlResult<usize> {
+        self.conn.execute(
+            "UPDATE images SET cache_path_thumb = NULL, cache_path_instant = NULL, cache_path_working = NULL",
+            [],
+        )?;
+        let queued = self.conn.execute(
+            "UPDATE images SET cache_status = 'pending' WHERE COALESCE(file_status, 'exists') = 'exists'",
+            [],
+        )?;
+        Ok(queued)
+    }
+    
+    /// Put images the thumbnail worker was in the middle of ('processing')
+    /// back in its queue, deleting whatever it had written for them. Run at
+    /// startup and on quit, so a forced close can't leave a half-written
+    /// thumbnail behind. Returns how many images were reset.
+    pub fn reset_interrupted_caches(&self) -> SqlResult<usize> {
+        let ids: Vec<i64> = self.conn
+            .prepare("SELECT id FROM images WHERE cache_status = 'processing'")?
+            .query_map([], |row| row.get(0))?
+            .collect::<SqlResult<_>>()?;
+        for &id in &ids {
+            self.reset_cache_status(id)?;
+        }
+        Ok(ids.len())
+    }
+    
     // ========== Edit Parameters Management ==========
     
     /// Save edit parameters for an image to the database
     /// Creates a new edit record or updates the most recent one
     pub fn save_edit_params(&self, image_id: i64, params: &super::edit::EditParams) -> SqlResult<()> {
+        // Back at the defaults (e.g. undone to the start): same as no edits,
+        // so the grid's edited badge goes away
+        if params.is_unedited() {
+            return self.delete_edits(image_id);
+        }
+        
         // Serialize params to JSON
         let json = params.to_json()
             .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
@@ -369,6 +845,110 @@ impl Library {
         Ok(())
     }
     
+    // ========== Snapshots ==========
+    
+    /// Save a look as a named snapshot of an image
+    /// A snapshot with the same name on this image is replaced.
+    pub fn save_snapshot(&self, image_id: i64, name: &str, params: &super::edit::EditParams) -> SqlResult<()> {
+        let json = params.to_json()
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        self.conn.execute(
+            "INSERT INTO snapshots (image_id, name, settings_json, created_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(image_id, name) DO UPDATE SET
+                settings_json = excluded.settings_json,
+                created_at = excluded.created_at",
+            rusqlite::params![image_id, name.trim(), json, chrono::Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+    
+    /// Snapshots of an image, oldest first
+    pub fn list_snapshots(&self, image_id: i64) -> SqlResult<Vec<Snapshot>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, settings_json FROM snapshots WHERE image_id = ?1 ORDER BY created_at, id",
+        )?;
+        let rows = stmt.query_map([image_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+        })?;
+        rows.map(|row| {
+            let (id, name, json) = row?;
+            let params = super::edit::EditParams::from_json(&json)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?;
+            Ok(Snapshot { id, name, params })
+        })
+        .collect()
+    }
+    
+    /// Restore a snapshot: its look becomes the image's live edits
+    /// Returns the restored parameters.
+    pub fn apply_snapshot(&self, image_id: i64, snapshot_id: i64) -> SqlResult<super::edit::EditParams> {
+        let json: String = self.conn.query_row(
+            "SELECT settings_json FROM snapshots WHERE id = ?1 AND image_id = ?2",
+            [snapshot_id, image_id],
+            |row| row.get(0),
+        )?;
+        let params = super::edit::EditParams::from_json(&json)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        self.save_edit_params(image_id, &params)?;
+        Ok(params)
+    }
+    
+    /// Delete a snapshot (the live edits are untouched)
+    pub fn delete_snapshot(&self, snapshot_id: i64) -> SqlResult<()> {
+        self.conn.execute("DELETE FROM snapshots WHERE id = ?1", [snapshot_id])?;
+        Ok(())
+    }
+    
+    /// Tag an image with a keyword (surrounding whitespace is dropped;
+    /// adding one it already has, in any letter case, does nothing)
+    pub fn add_keyword(&self, image_id: i64, keyword: &str) -> SqlResult<()> {
+        let keyword = keyword.trim();
+        if keyword.is_empty() {
+            return Ok(());
+        }
+        self.conn.execute(
+            "INSERT OR IGNORE INTO keywords (image_id, keyword) VALUES (?1, ?2)",
+            rusqlite::params![image_id, keyword],
+        )?;
+        Ok(())
+    }
+    
+    /// Remove a keyword from an image (case-insensitive)
+    pub fn remove_keyword(&self, image_id: i64, keyword: &str) -> SqlResult<()> {
+        self.conn.execute(
+            "DELETE FROM keywords WHERE image_id = ?1 AND keyword = ?2",
+            rusqlite::params![image_id, keyword.trim()],
+        )?;
+        Ok(())
+    }
+    
+    /// An image's keywords, alphabetically
+    pub fn get_keywords(&self, image_id: i64) -> SqlResult<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT keyword FROM keywords WHERE image_id = ?1 ORDER BY keyword",
+        )?;
+        let rows = stmt.query_map([image_id], |row| row.get(0))?;
+        rows.collect()
+    }
+    
+    /// Images with a keyword starting with `prefix` (case-insensitive),
+    /// newest imports first. "sun" finds "sunset" and "Sunrise".
+    pub fn search_by_keyword(&self, prefix: &str) -> SqlResult<Vec<Image>> {
+        // Typed % and _ are literal, not LIKE wildcards
+        let pattern = format!(
+            "{}%",
+            prefix.trim().replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+        );
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM images
+             WHERE id IN (SELECT image_id FROM keywords WHERE keyword LIKE ?1 ESCAPE '\\')
+             ORDER BY imported_at DESC, id DESC",
+            IMAGE_COLUMNS
+        ))?;
+        let rows = stmt.query_map([pattern], image_from_row)?;
+        rows.collect()
+    }
+    
     /// Phase 28: Set all 3 cache tier paths for an image
     /// Updates cache_status to 'cached' and stores paths for thumb, instant, and working tiers
     pub fn set_image_cache_paths(
@@ -389,6 +969,273 @@ impl Library {
         )?;
         Ok(())
     }
+    
+    // ========== Settings ==========
+    
+    /// Read a setting (None if it was never saved)
+    pub fn get_setting(&self, key: &str) -> SqlResult<Option<String>> {
+        self.conn
+            .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get(0))
+            .optional()
+    }
+    
+    /// Read a setting straight from the catalog file, read-only and without
+    /// migrating it. For the few settings needed before the window opens
+    /// (the catalog itself loads in the background); None if there's no
+    /// catalog yet.
+    pub fn peek_setting(key: &str) -> Option<String> {
+        let conn = Connection::open_with_flags(Self::get_db_path().ok()?, OpenFlags::SQLITE_OPEN_READ_ONLY).ok()?;
+        conn.query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get(0))
+            .optional()
+            .ok()
+            .flatten()
+    }
+    
+    /// Save a setting, replacing any previous value
+    pub fn set_setting(&self, key: &str, value: &str) -> SqlResult<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+            [key, value],
+        )?;
+        Ok(())
+    }
+    
+    // ========== Presets ==========
+    
+    /// Write the built-in presets the first time the catalog is opened
+    /// (only into an empty table, so deleted built-ins stay deleted)
+    fn seed_builtin_presets(&self) -> SqlResult<()> {
+        let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM presets", [], |row| row.get(0))?;
+        if count > 0 {
+            return Ok(());
+        }
+        for (name, params) in super::presets::builtin_presets() {
+            let json = params.to_json()
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+            self.conn.execute(
+                "INSERT INTO presets (name, settings_json) VALUES (?1, ?2)",
+                rusqlite::params![name, json],
+            )?;
+        }
+        println!("🎨 Created built-in presets");
+        Ok(())
+    }
+    
+    /// Save edit parameters as a new named preset
+    /// Fails with `PresetError::AlreadyExists` if the name is taken, so the UI
+    /// can ask before calling `overwrite_preset`.
+    pub fn save_preset(&self, name: &str, params: &super::edit::EditParams) -> Result<(), PresetError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(PresetError::EmptyName);
+        }
+        let exists: bool = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM presets WHERE name = ?1)",
+            [name],
+            |row| row.get(0),
+        )?;
+        if exists {
+            return Err(PresetError::AlreadyExists(name.to_string()));
+        }
+        self.overwrite_preset(name, params)
+    }
+    
+    /// Save a preset, replacing any existing preset with the same name
+    pub fn overwrite_preset(&self, name: &str, params: &super::edit::EditParams) -> Result<(), PresetError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(PresetError::EmptyName);
+        }
+        let json = params.to_json()
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO presets (name, settings_json) VALUES (?1, ?2)",
+            rusqlite::params![name, json],
+        )?;
+        Ok(())
+    }
+    
+    /// Names of all presets, alphabetically
+    pub fn list_presets(&self) -> SqlResult<Vec<String>> {
+        let mut stmt = self.conn.prepare("SELECT name FROM presets ORDER BY name COLLATE NOCASE")?;
+        let names = stmt.query_map([], |row| row.get(0))?
+            .collect::<SqlResult<Vec<String>>>()?;
+        Ok(names)
+    }
+    
+    /// Load a preset's edit parameters by name
+    pub fn load_preset(&self, name: &str) -> SqlResult<super::edit::EditParams> {
+        let json: String = self.conn.query_row(
+            "SELECT settings_json FROM presets WHERE name = ?1",
+            [name],
+            |row| row.get(0),
+        )?;
+        super::edit::EditParams::from_json(&json)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+    }
+    
+    // ========== Catalog Backup ==========
+    
+    /// Write every image's path, live edits, rating, flag, orientation and
+    /// keywords to a JSON backup (see backup.rs). Paths inside `root` (the
+    /// photos folder) are stored relative to it. Returns how many images
+    /// were written.
+    pub fn export_catalog(&self, path: &Path, root: Option<&Path>) -> Result<usize, String> {
+        let backup = self.catalog_backup(root).map_err(|e| format!("Database error: {}", e))?;
+        let json = serde_json::to_string_pretty(&backup).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| format!("Couldn't write {}: {}", path.display(), e))?;
+        Ok(backup.images.len())
+    }
+    
+    /// The catalog as a backup, oldest imports first
+    fn catalog_backup(&self, root: Option<&Path>) -> SqlResult<CatalogBackup> {
+        let mut images = self.get_all_images()?;
+        images.reverse();
+        let images = images
+            .into_iter()
+            .map(|image| {
+                let (path, relative) = backup::portable_path(&image.path, root);
+                let edits = if image.is_edited { Some(self.load_edit_params(image.id)?) } else { None };
+                Ok(BackupImage {
+                    path,
+                    relative,
+                    keywords: self.get_keywords(image.id)?,
+                    filename: image.filename,
+                    rating: image.rating,
+                    flag: image.flag,
+                    orientation: image.orientation,
+                    edits,
+                })
+            })
+            .collect::<SqlResult<Vec<_>>>()?;
+        Ok(CatalogBackup { version: BACKUP_VERSION, images })
+    }
+    
+    /// Read a JSON backup into the catalog, all or nothing
+    /// Images are matched by path (relative ones resolved against `root`): a
+    /// known image gets the backup's edits, rating, flag, orientation and
+    /// keywords; an unknown one is added, pointing at the same file, and its
+    /// thumbnail is queued like any import.
+    pub fn import_catalog(&self, path: &Path, root: Option<&Path>) -> Result<ImportSummary, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+        let mut backup: CatalogBackup = serde_json::from_str(&json)
+            .map_err(|e| format!("Not a catalog backup: {}", e))?;
+        if backup.version > BACKUP_VERSION {
+            return Err(format!("Backup format {} is newer than this app understands", backup.version));
+        }
+        if backup.version < 2 {
+            // Old scales, and edits from before the Whites slider don't have it
+            // (see `EditParams::from_legacy_json`), so they're read again raw
+            let raw: serde_json::Value = serde_json::from_str(&json)
+                .map_err(|e| format!("Not a catalog backup: {}", e))?;
+            let raw_images = raw["images"].as_array().cloned().unwrap_or_default();
+            for (image, raw_image) in backup.images.iter_mut().zip(raw_images) {
+                if image.edits.is_some() {
+                    image.edits = Some(
+                        super::edit::EditParams::from_legacy_json(&raw_image["edits"].to_string())
+                            .map_err(|e| format!("Not a catalog backup: {}", e))?,
+                    );
+                }
+            }
+        }
+        let paths = backup
+            .images
+            .iter()
+            .map(|image| backup::resolve_path(image, root))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.apply_backup(&backup.images, &paths).map_err(|e| format!("Database error: {}", e))
+    }
+    
+    /// Write backup entries (at their resolved paths) in one transaction
+    fn apply_backup(&self, images: &[BackupImage], paths: &[String]) -> SqlResult<ImportSummary> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut summary = ImportSummary::default();
+        for (image, path) in images.iter().zip(paths) {
+            let existing: Option<i64> = self.conn.query_row(
+                "SELECT id FROM images WHERE path = ?1",
+                [path],
+                |row| row.get(0),
+            ).optional()?;
+            let image_id = match existing {
+                Some(image_id) => {
+                    summary.updated += 1;
+                    image_id
+                }
+                None => {
+                    summary.added += 1;
+                    self.import_image(path, &image.filename)?
+                }
+            };
+            self.set_rating(image_id, image.rating)?;
+            self.set_flag(image_id, image.flag.as_deref().unwrap_or(""))?;
+            self.set_orientation(image_id, image.orientation)?;
+            match &image.edits {
+                Some(params) => self.save_edit_params(image_id, params)?,
+                None => self.delete_edits(image_id)?,
+            }
+            self.conn.execute("DELETE FROM keywords WHERE image_id = ?1", [image_id])?;
+            for keyword in &image.keywords {
+                self.add_keyword(image_id, keyword)?;
+            }
+        }
+        tx.commit()?;
+        Ok(summary)
+    }
+}
+
+/// Cheap check that a cached image file is whole: it opens, its header
+/// parses to a non-empty size, and a JPEG ends with the end-of-image marker
+/// (a write cut off midway doesn't). Nothing is decoded.
+fn cache_file_is_valid(path: &Path) -> bool {
+    use std::io::{Read, Seek, SeekFrom};
+    
+    let Ok(reader) = image::ImageReader::open(path).and_then(|reader| reader.with_guessed_format()) else {
+        return false;
+    };
+    let format = reader.format();
+    if !reader.into_dimensions().is_ok_and(|(width, height)| width > 0 && height > 0) {
+        return false;
+    }
+    if format != Some(image::ImageFormat::Jpeg) {
+        return true;
+    }
+    let mut tail = [0u8; 2];
+    std::fs::File::open(path)
+        .and_then(|mut file| {
+            file.seek(SeekFrom::End(-2))?;
+            file.read_exact(&mut tail)
+        })
+        .is_ok_and(|()| tail == [0xFF, 0xD9])
+}
+
+/// Delete an image's cached JPEGs: the stored tier paths plus the files named
+/// after its id in every cache folder (legacy thumbnails/previews included).
+/// Returns how many files were deleted.
+fn delete_cache_files(image_id: i64, cache_paths: [Option<String>; 3]) -> usize {
+    let file_name = format!("{}.jpg", image_id);
+    let mut cache_files: Vec<PathBuf> = cache_paths
+        .into_iter()
+        .flatten()
+        .map(PathBuf::from)
+        .collect();
+    let cache_root = super::settings::cache_root();
+    for folder in super::settings::CACHE_FOLDERS {
+        cache_files.push(cache_root.join(folder).join(&file_name));
+    }
+    cache_files.sort();
+    cache_files.dedup();
+    
+    let mut removed = 0;
+    for file in &cache_files {
+        match std::fs::remove_file(file) {
+            Ok(()) => removed += 1,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => eprintln!("⚠️  Couldn't delete cache file {}: {}", file.display(), e),
+        }
+    }
+    
+    removed
 }
 
 // Implement Debug for better error messages
@@ -399,3 +1246,607 @@ impl std::fmt::Debug for Library {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cataloged_paths_under() {
+        let library = Library::open_in_memory().unwrap();
+        library.import_image("/photos/2024_trip/a.NEF", "a.NEF").unwrap();
+        library.import_image("/photos/2024_trip/day 2/b.NEF", "b.NEF").unwrap();
+        library.import_image("/photos/2024-trip/c.NEF", "c.NEF").unwrap();
+        library.import_image("/elsewhere/d.NEF", "d.NEF").unwrap();
+
+        // The _ in the folder name doesn't match the - of its neighbour
+        let paths = cataloged_paths_under(library.conn(), Path::new("/photos/2024_trip")).unwrap();
+        let expected: HashSet<String> = ["/photos/2024_trip/a.NEF", "/photos/2024_trip/day 2/b.NEF"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(paths, expected);
+
+        // A single file finds just itself
+        let paths = cataloged_paths_under(library.conn(), Path::new("/elsewhere/d.NEF")).unwrap();
+        assert_eq!(paths.len(), 1);
+        assert!(cataloged_paths_under(library.conn(), Path::new("/new")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_import_round_trip_cache_paths() {
+        let library = Library::open_in_memory().unwrap();
+        let id = library.import_image("/photos/DSC_0001.NEF", "DSC_0001.NEF").unwrap();
+
+        // Freshly imported images have no cache tiers yet
+        let images = library.get_all_images().unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].id, id);
+        assert_eq!(images[0].filename, "DSC_0001.NEF");
+        assert_eq!(images[0].file_status, "exists");
+        assert!(images[0].cache_path_thumb.is_none());
+        assert!(images[0].cache_path_instant.is_none());
+        assert!(images[0].cache_path_working.is_none());
+        assert_eq!(images[0].avg_color, None);
+        assert_eq!(library.get_pending_thumbnails(10).unwrap().len(), 1);
+
+        // The placeholder color set at import reads back as RGB
+        library.conn()
+            .execute("UPDATE images SET avg_color = ?1 WHERE id = ?2", rusqlite::params![pack_color([200, 120, 40]), id])
+            .unwrap();
+        assert_eq!(library.get_all_images().unwrap()[0].avg_color, Some([200, 120, 40]));
+
+        library
+            .set_image_cache_paths(id, "/cache/thumb/1.jpg", "/cache/instant/1.jpg", "/cache/working/1.jpg")
+            .unwrap();
+
+        let images = library.get_all_images().unwrap();
+        assert_eq!(images[0].cache_path_thumb.as_deref(), Some("/cache/thumb/1.jpg"));
+        assert_eq!(images[0].cache_path_instant.as_deref(), Some("/cache/instant/1.jpg"));
+        assert_eq!(images[0].cache_path_working.as_deref(), Some("/cache/working/1.jpg"));
+        assert!(library.get_pending_thumbnails(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_update_thumbnail_sets_thumb_tier() {
+        let library = Library::open_in_memory().unwrap();
+        let id = library.import_image("/photos/DSC_0002.NEF", "DSC_0002.NEF").unwrap();
+
+        library.update_thumbnail(id, "/cache/thumb/2.jpg").unwrap();
+
+        let images = library.get_all_images().unwrap();
+        assert_eq!(images[0].cache_path_thumb.as_deref(), Some("/cache/thumb/2.jpg"));
+        assert!(images[0].cache_path_working.is_none());
+    }
+
+    #[test]
+    fn test_rating_and_flag_round_trip() {
+        use crate::state::data::{FLAG_PICK, FLAG_REJECT};
+
+        let library = Library::open_in_memory().unwrap();
+        let id = library.import_image("/photos/DSC_0003.NEF", "DSC_0003.NEF").unwrap();
+
+        let images = library.get_all_images().unwrap();
+        assert_eq!(images[0].rating, 0);
+        assert!(images[0].flag.is_none());
+
+        library.set_rating(id, 4).unwrap();
+        library.set_flag(id, FLAG_PICK).unwrap();
+        let images = library.get_all_images().unwrap();
+        assert_eq!(images[0].rating, 4);
+        assert_eq!(images[0].flag.as_deref(), Some(FLAG_PICK));
+
+        // Out-of-range ratings clamp, empty flag clears
+        library.set_rating(id, 9).unwrap();
+        library.set_flag(id, FLAG_REJECT).unwrap();
+        assert_eq!(library.get_all_images().unwrap()[0].rating, 5);
+        assert_eq!(library.get_all_images().unwrap()[0].flag.as_deref(), Some(FLAG_REJECT));
+        library.set_flag(id, "").unwrap();
+        assert!(library.get_all_images().unwrap()[0].flag.is_none());
+    }
+
+    #[test]
+    fn test_filtered_queries() {
+        use crate::state::data::{FLAG_PICK, FLAG_REJECT};
+
+        let library = Library::open_in_memory().unwrap();
+        let a = library.import_image("/photos/b.NEF", "b.NEF").unwrap();
+        let b = library.import_image("/photos/A.NEF", "A.NEF").unwrap();
+        let c = library.import_image("/photos/c.NEF", "c.NEF").unwrap();
+        library.set_rating(a, 2).unwrap();
+        library.set_rating(c, 5).unwrap();
+        library.set_flag(b, FLAG_PICK).unwrap();
+        library.conn().execute("UPDATE images SET file_status = 'deleted' WHERE id = ?1", [c]).unwrap();
+
+        let ids = |sort, filter| -> Vec<i64> {
+            library.get_images_filtered(sort, filter).unwrap().iter().map(|img| img.id).collect()
+        };
+
+        // Same import timestamp (same second), so ties fall back to id
+        assert_eq!(ids(SortOrder::ImportDate, LibraryFilter::default()), vec![c, b, a]);
+        assert_eq!(ids(SortOrder::Filename, LibraryFilter::default()), vec![b, a, c]);
+        assert_eq!(ids(SortOrder::Rating, LibraryFilter::default()), vec![c, a, b]);
+
+        let only_rated = LibraryFilter { only_rated: true, ..Default::default() };
+        assert_eq!(ids(SortOrder::Rating, only_rated), vec![c, a]);
+        let only_flagged = LibraryFilter { only_flagged: true, ..Default::default() };
+        assert_eq!(ids(SortOrder::ImportDate, only_flagged), vec![b]);
+        let rated_existing = LibraryFilter { only_rated: true, hide_deleted: true, ..Default::default() };
+        assert_eq!(ids(SortOrder::Rating, rated_existing), vec![a]);
+
+        // Rejects: hidden by the filter, listed by get_rejected
+        library.set_flag(c, FLAG_REJECT).unwrap();
+        let hide_rejects = LibraryFilter { hide_rejects: true, ..Default::default() };
+        assert_eq!(ids(SortOrder::ImportDate, hide_rejects), vec![b, a]);
+        let rejected: Vec<i64> = library.get_rejected().unwrap().iter().map(|img| img.id).collect();
+        assert_eq!(rejected, vec![c]);
+
+        // The thumbnail queue ignores the grid filter
+        assert_eq!(library.get_pending_thumbnails(10).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_edited_flag() {
+        use crate::state::edit::EditParams;
+
+        let library = Library::open_in_memory().unwrap();
+        let a = library.import_image("/photos/a.NEF", "a.NEF").unwrap();
+        let b = library.import_image("/photos/b.NEF", "b.NEF").unwrap();
+        let edited = |id: i64| library.get_all_images().unwrap().iter().find(|img| img.id == id).unwrap().is_edited;
+        assert!(!edited(a) && !edited(b));
+
+        library.save_edit_params(a, &EditParams { exposure: 0.5, ..Default::default() }).unwrap();
+        assert!(edited(a) && !edited(b));
+        let grouped = library.get_images_grouped_by_date(SortOrder::Filename, LibraryFilter::default()).unwrap();
+        assert!(grouped[0].1[0].is_edited);
+
+        // Saving the defaults (undo back to the start) clears it
+        library.save_edit_params(a, &EditParams::default()).unwrap();
+        assert!(!edited(a));
+        assert!(!library.has_edits(a).unwrap());
+    }
+
+    #[test]
+    fn test_import_batches() {
+        let library = Library::open_in_memory().unwrap();
+        let old = library.import_image("/photos/old.NEF", "old.NEF").unwrap();
+        assert_eq!(library.latest_import_batch().unwrap(), None);
+
+        let a = library.import_image("/photos/a.NEF", "a.NEF").unwrap();
+        let b = library.import_image("/photos/b.NEF", "b.NEF").unwrap();
+        let c = library.import_image("/photos/c.NEF", "c.NEF").unwrap();
+        let set_batch = |id: i64, batch: i64| {
+            library.conn().execute("UPDATE images SET import_batch = ?1 WHERE id = ?2", [batch, id]).unwrap();
+        };
+        set_batch(a, 1_000);
+        set_batch(b, 2_000);
+        set_batch(c, 2_000);
+
+        assert_eq!(library.latest_import_batch().unwrap(), Some(2_000));
+        let ids = |batch| -> Vec<i64> {
+            library.get_images_by_batch(batch).unwrap().iter().map(|img| img.id).collect()
+        };
+        assert_eq!(ids(2_000), vec![c, b]);
+        assert_eq!(ids(1_000), vec![a]);
+        assert!(ids(3_000).is_empty());
+        assert!(!ids(2_000).contains(&old));
+    }
+
+    #[test]
+    fn test_keywords() {
+        let library = Library::open_in_memory().unwrap();
+        let a = library.import_image("/photos/a.NEF", "a.NEF").unwrap();
+        let b = library.import_image("/photos/b.NEF", "b.NEF").unwrap();
+        library.add_keyword(a, " Sunset ").unwrap();
+        library.add_keyword(a, "beach").unwrap();
+        library.add_keyword(a, "SUNSET").unwrap(); // Already tagged
+        library.add_keyword(b, "sunrise").unwrap();
+        library.add_keyword(b, "100%_crop").unwrap();
+        library.add_keyword(b, "  ").unwrap();
+        assert_eq!(library.get_keywords(a).unwrap(), vec!["beach", "Sunset"]);
+
+        let ids = |prefix: &str| -> Vec<i64> {
+            library.search_by_keyword(prefix).unwrap().iter().map(|img| img.id).collect()
+        };
+        assert_eq!(ids("sun"), vec![b, a]);
+        assert_eq!(ids("sunset"), vec![a]);
+        assert_eq!(ids("BEA"), vec![a]);
+        assert_eq!(ids("100%_"), vec![b]);
+        assert_eq!(ids("1000"), Vec::<i64>::new());
+        // Wildcards in the search are literal
+        assert_eq!(ids("_"), Vec::<i64>::new());
+
+        library.remove_keyword(a, "sunset").unwrap();
+        assert_eq!(ids("sunset"), Vec::<i64>::new());
+
+        // Removing the image drops its keywords
+        library.remove_image(b).unwrap();
+        let orphans: i64 = library.conn()
+            .query_row("SELECT COUNT(*) FROM keywords WHERE image_id = ?1", [b], |row| row.get(0))
+            .unwrap();
+        assert_eq!(orphans, 0);
+    }
+
+    #[test]
+    fn test_grouped_by_date() {
+        let library = Library::open_in_memory().unwrap();
+        let a = library.import_image("/photos/a.NEF", "a.NEF").unwrap();
+        let b = library.import_image("/photos/b.NEF", "b.NEF").unwrap();
+        let c = library.import_image("/photos/c.NEF", "c.NEF").unwrap();
+        let d = library.import_image("/photos/d.NEF", "d.NEF").unwrap();
+        let set_captured = |id: i64, at: &str| {
+            library.conn().execute("UPDATE images SET captured_at = ?1 WHERE id = ?2", rusqlite::params![at, id]).unwrap();
+        };
+        set_captured(a, "2024-06-01 18:30:05");
+        set_captured(b, "2024-06-02 09:00:00");
+        set_captured(c, "2024-06-01 07:15:00");
+        // d has no capture date: grouped by import day (noon, so any timezone keeps the date)
+        library.conn().execute("UPDATE images SET imported_at = ?1 WHERE id = ?2", [1_577_880_000, d]).unwrap();
+
+        let groups = library.get_images_grouped_by_date(SortOrder::Filename, LibraryFilter::default()).unwrap();
+        let summary: Vec<(String, Vec<i64>)> = groups
+            .iter()
+            .map(|(date, images)| (date.to_string(), images.iter().map(|img| img.id).collect()))
+            .collect();
+        assert_eq!(summary, vec![
+            ("2024-06-02".to_string(), vec![b]),
+            ("2024-06-01".to_string(), vec![a, c]),
+            ("2020-01-01".to_string(), vec![d]),
+        ]);
+
+        // Same filters as the flat grid
+        library.set_rating(c, 3).unwrap();
+        let only_rated = LibraryFilter { only_rated: true, ..Default::default() };
+        let groups = library.get_images_grouped_by_date(SortOrder::ImportDate, only_rated).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].1[0].id, c);
+    }
+
+    #[test]
+    fn test_metadata_round_trip() {
+        let library = Library::open_in_memory().unwrap();
+        let id = library.import_image("/photos/DSC_0001.NEF", "DSC_0001.NEF").unwrap();
+
+        // Files without EXIF keep NULL columns (orientation defaults to upright)
+        assert_eq!(
+            library.get_metadata(id).unwrap(),
+            ImageMetadata { orientation: Some(1), ..ImageMetadata::default() }
+        );
+
+        library.conn().execute(
+            "UPDATE images SET camera_model = 'NIKON D750', iso = 400, shutter_seconds = 0.004 WHERE id = ?1",
+            [id],
+        ).unwrap();
+        let metadata = library.get_metadata(id).unwrap();
+        assert_eq!(metadata.camera_model.as_deref(), Some("NIKON D750"));
+        assert_eq!(metadata.iso, Some(400));
+        assert_eq!(metadata.shutter_display(), "1/250 s");
+        assert_eq!(metadata.aperture, None);
+    }
+
+    #[test]
+    fn test_presets() {
+        let library = Library::open_in_memory().unwrap();
+
+        // Built-ins are there on first open
+        let names = library.list_presets().unwrap();
+        assert_eq!(names, vec!["B&W", "Flat/Log", "Punchy"]);
+        assert!(library.load_preset("B&W").unwrap().monochrome);
+
+        let look = crate::state::edit::EditParams { exposure: 0.7, ..Default::default() };
+        library.save_preset("  Sunset ", &look).unwrap();
+        assert_eq!(library.load_preset("Sunset").unwrap(), look);
+
+        // Name collisions need an explicit overwrite
+        let other = crate::state::edit::EditParams { exposure: -0.3, ..Default::default() };
+        assert!(matches!(library.save_preset("Sunset", &other), Err(PresetError::AlreadyExists(name)) if name == "Sunset"));
+        assert_eq!(library.load_preset("Sunset").unwrap(), look);
+        library.overwrite_preset("Sunset", &other).unwrap();
+        assert_eq!(library.load_preset("Sunset").unwrap(), other);
+
+        assert!(matches!(library.save_preset("   ", &look), Err(PresetError::EmptyName)));
+        assert!(library.load_preset("Missing").is_err());
+        assert_eq!(library.list_presets().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_snapshots() {
+        use crate::state::edit::EditParams;
+
+        let library = Library::open_in_memory().unwrap();
+        let id = library.import_image("/photos/DSC_0001.NEF", "DSC_0001.NEF").unwrap();
+        let other = library.import_image("/photos/DSC_0002.NEF", "DSC_0002.NEF").unwrap();
+        let color = EditParams { vibrance: 0.4, ..Default::default() };
+        let mono = EditParams { monochrome: true, ..Default::default() };
+
+        library.save_snapshot(id, "Color", &color).unwrap();
+        library.save_snapshot(id, "B&W", &mono).unwrap();
+        let snapshots = library.list_snapshots(id).unwrap();
+        assert_eq!(snapshots.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), vec!["Color", "B&W"]);
+        assert_eq!(snapshots[1].params, mono);
+        assert!(library.list_snapshots(other).unwrap().is_empty());
+
+        // Same name replaces
+        let warmer = EditParams { temperature: 0.3, ..color };
+        library.save_snapshot(id, "Color", &warmer).unwrap();
+        assert_eq!(library.list_snapshots(id).unwrap().len(), 2);
+
+        // Restoring makes it the live edit; snapshots belong to their image
+        let color_id = library.list_snapshots(id).unwrap().into_iter().find(|s| s.name == "Color").unwrap().id;
+        assert_eq!(library.apply_snapshot(id, color_id).unwrap(), warmer);
+        assert_eq!(library.load_edit_params(id).unwrap(), warmer);
+        assert!(library.apply_snapshot(other, color_id).is_err());
+
+        library.delete_snapshot(color_id).unwrap();
+        assert_eq!(library.list_snapshots(id).unwrap().len(), 1);
+        assert_eq!(library.load_edit_params(id).unwrap(), warmer);
+    }
+
+    #[test]
+    fn test_remove_image() {
+        let library = Library::open_in_memory().unwrap();
+        let id = library.import_image("/photos/DSC_0001.NEF", "DSC_0001.NEF").unwrap();
+        let other = library.import_image("/photos/DSC_0002.NEF", "DSC_0002.NEF").unwrap();
+        library.save_edit_params(id, &crate::state::edit::EditParams { exposure: 1.0, ..Default::default() }).unwrap();
+        
+        // Cached tiers on disk get deleted along with the row
+        let dir = std::env::temp_dir().join(format!("raw-editor-remove-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let thumb = dir.join("thumb.jpg");
+        std::fs::write(&thumb, b"jpeg").unwrap();
+        library.set_image_cache_paths(id, thumb.to_str().unwrap(), "/missing/instant.jpg", "/missing/working.jpg").unwrap();
+        
+        assert_eq!(library.remove_image(id).unwrap().as_deref(), Some("/photos/DSC_0001.NEF"));
+        assert!(!thumb.exists());
+        let remaining: Vec<i64> = library.get_all_images().unwrap().iter().map(|img| img.id).collect();
+        assert_eq!(remaining, vec![other]);
+        let edit_rows: i64 = library.conn
+            .query_row("SELECT COUNT(*) FROM edits WHERE image_id = ?1", [id], |row| row.get(0))
+            .unwrap();
+        assert_eq!(edit_rows, 0);
+        
+        // Already gone
+        assert_eq!(library.remove_image(id).unwrap(), None);
+        
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_verify_thumbnails_resets_broken_files() {
+        let library = Library::open_in_memory().unwrap();
+        let dir = std::env::temp_dir().join(format!("raw-editor-verify-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut jpeg = Vec::new();
+        image::DynamicImage::new_rgb8(16, 16)
+            .write_to(&mut std::io::Cursor::new(&mut jpeg), image::ImageFormat::Jpeg)
+            .unwrap();
+        let write = |name: &str, bytes: &[u8]| {
+            let path = dir.join(name);
+            std::fs::write(&path, bytes).unwrap();
+            path.to_string_lossy().to_string()
+        };
+
+        let good = library.import_image("/photos/good.NEF", "good.NEF").unwrap();
+        let empty = library.import_image("/photos/empty.NEF", "empty.NEF").unwrap();
+        let cut_off = library.import_image("/photos/cut.NEF", "cut.NEF").unwrap();
+        let good_thumb = write("good.jpg", &jpeg);
+        library.set_image_cache_paths(good, &good_thumb, &write("good_instant.jpg", &jpeg), &write("zero.jpg", b"")).unwrap();
+        library.update_thumbnail(empty, &write("empty.jpg", b"")).unwrap();
+        library.update_thumbnail(cut_off, &write("cut.jpg", &jpeg[..jpeg.len() - 10])).unwrap();
+
+        assert_eq!(library.verify_thumbnails().unwrap(), 2);
+        let pending = library.get_pending_thumbnails(10).unwrap();
+        let mut pending_ids: Vec<i64> = pending.iter().map(|img| img.id).collect();
+        pending_ids.sort();
+        assert_eq!(pending_ids, vec![empty, cut_off]);
+        assert!(!dir.join("empty.jpg").exists());
+
+        // The good thumbnail stays; only its zero-byte preview tier is dropped
+        let images = library.get_all_images().unwrap();
+        let kept = images.iter().find(|img| img.id == good).unwrap();
+        assert_eq!(kept.cache_path_thumb.as_deref(), Some(good_thumb.as_str()));
+        assert!(kept.cache_path_instant.is_some());
+        assert!(kept.cache_path_working.is_none());
+        assert_eq!(library.verify_thumbnails().unwrap(), 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_reset_cache_status() {
+        let library = Library::open_in_memory().unwrap();
+        let id = library.import_image("/photos/DSC_0001.NEF", "DSC_0001.NEF").unwrap();
+        let other = library.import_image("/photos/DSC_0002.NEF", "DSC_0002.NEF").unwrap();
+        
+        let dir = std::env::temp_dir().join(format!("raw-editor-reset-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let thumb = dir.join("thumb.jpg");
+        std::fs::write(&thumb, b"jpeg").unwrap();
+        library.set_image_cache_paths(id, thumb.to_str().unwrap(), "/missing/instant.jpg", "/missing/working.jpg").unwrap();
+        library.update_thumbnail(other, "/missing/thumb.jpg").unwrap();
+        assert!(library.get_pending_thumbnails(10).unwrap().is_empty());
+        
+        // Back in the worker's queue with the stale file gone
+        assert!(library.reset_cache_status(id).unwrap());
+        assert!(!thumb.exists());
+        let pending = library.get_pending_thumbnails(10).unwrap();
+        assert_eq!(pending.iter().map(|img| img.id).collect::<Vec<_>>(), vec![id]);
+        assert!(pending[0].cache_path_thumb.is_none());
+        assert!(pending[0].cache_path_working.is_none());
+        assert!(!library.reset_cache_status(999).unwrap());
+        
+        // Images whose RAW file is gone can't be re-extracted
+        library.conn.execute("UPDATE images SET file_status = 'deleted' WHERE id = ?1", [id]).unwrap();
+        assert_eq!(library.reset_all_cache_status().unwrap(), 1);
+        assert_eq!(library.get_pending_thumbnails(10).unwrap().len(), 2);
+        
+        // Clearing the whole cache forgets every path; the missing RAW isn't queued
+        library.update_thumbnail(other, "/cache/thumb/2.jpg").unwrap();
+        assert_eq!(library.clear_all_caches().unwrap(), 1);
+        assert!(library.get_all_images().unwrap().iter().all(|img| img.cache_path_thumb.is_none()));
+        
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_reset_interrupted_caches() {
+        let library = Library::open_in_memory().unwrap();
+        let id = library.import_image("/photos/DSC_0001.NEF", "DSC_0001.NEF").unwrap();
+        let done = library.import_image("/photos/DSC_0002.NEF", "DSC_0002.NEF").unwrap();
+        library.conn.execute("UPDATE images SET cache_status = 'processing' WHERE id = ?1", [id]).unwrap();
+        library.update_thumbnail(done, "/cache/thumb/2.jpg").unwrap();
+        
+        assert_eq!(library.reset_interrupted_caches().unwrap(), 1);
+        let pending = library.get_pending_thumbnails(10).unwrap();
+        assert_eq!(pending.iter().map(|img| img.id).collect::<Vec<_>>(), vec![id]);
+        assert_eq!(library.reset_interrupted_caches().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_settings() {
+        let library = Library::open_in_memory().unwrap();
+        assert_eq!(library.get_setting("preview_quality").unwrap(), None);
+
+        library.set_setting("preview_quality", "high").unwrap();
+        library.set_setting("preview_quality", "native").unwrap();
+        assert_eq!(library.get_setting("preview_quality").unwrap().as_deref(), Some("native"));
+    }
+
+    #[test]
+    fn test_catalog_backup_round_trip() {
+        let dir = std::env::temp_dir().join(format!("raw-editor-backup-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("catalog.json");
+
+        let source = Library::open_in_memory().unwrap();
+        let a = source.import_image("/home/ana/Photos/2024/a.NEF", "a.NEF").unwrap();
+        source.import_image("/tmp/b.NEF", "b.NEF").unwrap();
+        let edits = super::super::edit::EditParams { exposure: 1.5, ..Default::default() };
+        source.save_edit_params(a, &edits).unwrap();
+        source.set_rating(a, 4).unwrap();
+        source.set_flag(a, "pick").unwrap();
+        source.set_orientation(a, 6).unwrap();
+        source.add_keyword(a, "beach").unwrap();
+        assert_eq!(source.export_catalog(&file, Some(Path::new("/home/ana/Photos"))).unwrap(), 2);
+
+        // The photos moved; b.NEF is already in the target catalog with a rating
+        let target = Library::open_in_memory().unwrap();
+        let b = target.import_image("/tmp/b.NEF", "b.NEF").unwrap();
+        target.set_rating(b, 2).unwrap();
+        let summary = target.import_catalog(&file, Some(Path::new("/mnt/Photos"))).unwrap();
+        assert_eq!(summary, ImportSummary { updated: 1, added: 1 });
+
+        let images = target.get_all_images().unwrap();
+        let moved = images.iter().find(|img| img.filename == "a.NEF").unwrap();
+        assert_eq!(PathBuf::from(&moved.path), Path::new("/mnt/Photos").join("2024").join("a.NEF"));
+        assert_eq!((moved.rating, moved.flag.as_deref(), moved.orientation), (4, Some("pick"), 6));
+        assert_eq!(target.load_edit_params(moved.id).unwrap(), edits);
+        assert_eq!(target.get_keywords(moved.id).unwrap(), vec!["beach"]);
+        assert_eq!(target.get_all_images().unwrap().iter().find(|img| img.id == b).unwrap().rating, 0);
+
+        // Relative paths without a photos folder: nothing is written
+        let empty = Library::open_in_memory().unwrap();
+        assert!(empty.import_catalog(&file, None).is_err());
+        assert_eq!(empty.image_count().unwrap(), 0);
+
+        // A version 1 backup is on the old tone scales, and may predate Whites
+        std::fs::write(
+            &file,
+            r#"{"version": 1, "images": [{"path": "/tmp/c.NEF", "filename": "c.NEF", "edits": {"highlights": -0.25}}]}"#,
+        )
+        .unwrap();
+        let legacy = Library::open_in_memory().unwrap();
+        legacy.import_catalog(&file, None).unwrap();
+        let c = legacy.get_all_images().unwrap()[0].id;
+        let upgraded = legacy.load_edit_params(c).unwrap();
+        assert_eq!((upgraded.highlights, upgraded.whites), (-50.0, 0.0));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_recover_damaged_catalog() {
+        use crate::state::edit::EditParams;
+
+        let dir = std::env::temp_dir().join(format!("raw-editor-recover-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // Garbage where the catalog should be is reported as corrupt
+        let garbage = dir.join("garbage.db");
+        std::fs::write(&garbage, vec![0x5a; 4096]).unwrap();
+        let Err(err) = Library::open_at(garbage.clone()) else {
+            panic!("garbage must not open");
+        };
+        assert!(err.is_corrupt(), "{:?}", err);
+
+        // ...and recovering it leaves a backup and an empty, working catalog
+        let recovery = Library::recover_at(&garbage).unwrap();
+        assert!(recovery.backup.exists());
+        assert_eq!(recovery.images, 0);
+        assert_eq!(Library::open_at(garbage).unwrap().image_count().unwrap(), 0);
+
+        // Rows still readable from the old file are carried over: the image,
+        // its culling, keywords and edits
+        let readable = dir.join("readable.db");
+        let library = Library::open_at(readable.clone()).unwrap();
+        let id = library.import_image("/photos/DSC_0001.NEF", "DSC_0001.NEF").unwrap();
+        library.set_rating(id, 4).unwrap();
+        library.set_flag(id, "pick").unwrap();
+        library.add_keyword(id, "beach").unwrap();
+        let params = EditParams { exposure: 1.5, ..Default::default() };
+        library.save_edit_params(id, &params).unwrap();
+
+        // An image whose edits row is garbage gets its sidecar's instead
+        let raw = dir.join("DSC_0002.NEF");
+        std::fs::write(&raw, b"raw").unwrap();
+        let sidecar_params = EditParams { contrast: 20.0, ..Default::default() };
+        crate::state::sidecar::write_sidecar(&raw, &sidecar_params).unwrap();
+        let with_sidecar = library.import_image(&raw.to_string_lossy(), "DSC_0002.NEF").unwrap();
+        // ...and one with neither has its edits reported lost
+        let without = library.import_image("/photos/DSC_0003.NEF", "DSC_0003.NEF").unwrap();
+        for image_id in [with_sidecar, without] {
+            library.conn.execute(
+                "INSERT INTO edits (image_id, settings_json) VALUES (?1, 'not json')",
+                [image_id],
+            ).unwrap();
+        }
+        drop(library);
+
+        let recovery = Library::recover_at(&readable).unwrap();
+        assert_eq!(recovery.images, 3);
+        assert_eq!(recovery.edits_salvaged, 1);
+        assert_eq!(recovery.edits_from_sidecars, 1);
+        assert_eq!(recovery.edits_lost, 1);
+        assert_eq!(recovery.images_lost, 0);
+        let library = Library::open_at(readable).unwrap();
+        let images = library.get_all_images().unwrap();
+        let image = images.iter().find(|image| image.filename == "DSC_0001.NEF").unwrap();
+        assert_eq!((image.rating, image.flag.as_deref()), (4, Some("pick")));
+        assert_eq!(library.get_keywords(image.id).unwrap(), vec!["beach".to_string()]);
+        assert_eq!(library.load_edit_params(image.id).unwrap().exposure, 1.5);
+        let image = images.iter().find(|image| image.filename == "DSC_0002.NEF").unwrap();
+        assert_eq!(library.load_edit_params(image.id).unwrap().contrast, 20.0);
+        drop(library);
+
+        // A catalog from before v14 has its edits moved to the new tone scales
+        let old = dir.join("old.db");
+        let library = Library::open_at(old.clone()).unwrap();
+        let id = library.import_image("/photos/DSC_0004.NEF", "DSC_0004.NEF").unwrap();
+        library.conn.execute(
+            "INSERT INTO edits (image_id, settings_json) VALUES (?1, ?2)",
+            rusqlite::params![id, r#"{"exposure": 0.5, "highlights": -0.25, "whites": 0.9}"#],
+        ).unwrap();
+        library.conn.execute_batch("PRAGMA user_version = 13").unwrap();
+        drop(library);
+
+        let recovery = Library::recover_at(&old).unwrap();
+        assert_eq!(recovery.edits_salvaged, 1);
+        let library = Library::open_at(old).unwrap();
+        let image = &library.get_all_images().unwrap()[0];
+        let edit = library.load_edit_params(image.id).unwrap();
+        assert_eq!((edit.exposure, edit.highlights), (0.5, -50.0));
+        assert!((edit.whites - 50.0).abs() < 1e-3, "{}", edit.whites);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,410 @@
+/// Catalog schema migrations
+///
+/// The schema version lives in SQLite's `PRAGMA user_version` (0 for a new
+/// file). `migrate` applies every step above the stored version in order,
+/// each in its own transaction together with the version bump, so a failed
+/// step leaves the catalog at the last good version and the error surfaces
+/// instead of being swallowed.
+///
+/// Catalogs from before versioning sit at version 0 but already have some of
+/// the columns that the early steps add (they were added with best-effort
+/// `ALTER TABLE`s on every launch). Those steps go through `add_column`,
+/// which skips columns that are already there, so old catalogs upgrade the
+/// same way as new ones. New steps are appended at the end; never edit or
+/// reorder one that has shipped.
+use rusqlite::{Connection, Result as SqlResult, Transaction};
+
+/// One schema change
+struct Migration {
+    /// Logged while the step runs
+    description: &'static str,
+    apply: fn(&Transaction) -> SqlResult<()>,
+}
+
+/// All schema steps, oldest first. Step N (1-based) upgrades version N-1 to N.
+const MIGRATIONS: &[Migration] = &[
+    Migration { description: "images and edits tables", apply: create_base_tables },
+    Migration { description: "multi-tier cache paths", apply: add_cache_tiers },
+    Migration { description: "file status", apply: add_file_status },
+    Migration { description: "ratings and flags", apply: add_culling },
+    Migration { description: "EXIF metadata", apply: add_exif_metadata },
+    Migration { description: "develop presets", apply: create_presets_table },
+    Migration { description: "app settings", apply: create_settings_table },
+    Migration { description: "snapshots", apply: create_snapshots_table },
+    Migration { description: "lens model", apply: add_lens_model },
+    Migration { description: "keywords", apply: create_keywords_table },
+    Migration { description: "import batches", apply: add_import_batch },
+    Migration { description: "drop default edit rows", apply: drop_default_edits },
+    Migration { description: "placeholder colors", apply: add_avg_color },
+    Migration { description: "tone sliders on a -100..100 scale", apply: rescale_tone_sliders },
+];
+
+/// Schema version of a fully migrated catalog
+pub const LATEST_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// First schema version whose saved settings use the -100..100 tone scales
+/// (see `rescale_tone_sliders`); older ones need `EditParams::from_legacy_json`
+pub const TONE_SCALE_VERSION: u32 = 14;
+
+/// Current schema version of a catalog
+pub fn schema_version(conn: &Connection) -> SqlResult<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Bring a catalog up to `LATEST_VERSION`
+pub fn migrate(conn: &mut Connection) -> SqlResult<()> {
+    let current = schema_version(conn)?;
+    if current > LATEST_VERSION {
+        // Written by a newer build: leave it alone and hope the columns we use still exist
+        println!("⚠️  Catalog schema v{} is newer than this build (v{})", current, LATEST_VERSION);
+        return Ok(());
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+        let version = index as u32 + 1;
+        println!("🗄️  Migrating catalog to schema v{}: {}", version, migration.description);
+        let tx = conn.transaction()?;
+        (migration.apply)(&tx)?;
+        // PRAGMA doesn't take bound parameters
+        tx.execute_batch(&format!("PRAGMA user_version = {}", version))?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+/// True if `table` has a column named `column`
+fn has_column(tx: &Transaction, table: &str, column: &str) -> SqlResult<bool> {
+    let mut stmt = tx.prepare(&format!("PRAGMA table_info({})", table))?;
+    let mut names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    names.try_fold(false, |found, name| Ok(found || name? == column))
+}
+
+/// `ALTER TABLE ... ADD COLUMN`, unless a pre-versioning catalog already has it
+/// (`definition` is the column name followed by its type and default)
+fn add_column(tx: &Transaction, table: &str, definition: &str) -> SqlResult<()> {
+    let column = definition.split_whitespace().next().unwrap_or(definition);
+    if has_column(tx, table, column)? {
+        return Ok(());
+    }
+    tx.execute(&format!("ALTER TABLE {} ADD COLUMN {}", table, definition), [])?;
+    Ok(())
+}
+
+/// v1: imported RAW files and their edit stacks
+fn create_base_tables(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS images (
+            id              INTEGER PRIMARY KEY AUTOINCREMENT,
+            path            TEXT NOT NULL UNIQUE,
+            filename        TEXT NOT NULL,
+            width           INTEGER,
+            height          INTEGER,
+            imported_at     INTEGER NOT NULL,
+            cache_status    TEXT DEFAULT 'pending'
+        );
+        CREATE TABLE IF NOT EXISTS edits (
+            id              INTEGER PRIMARY KEY AUTOINCREMENT,
+            image_id        INTEGER NOT NULL,
+            settings_json   TEXT NOT NULL,
+            FOREIGN KEY(image_id) REFERENCES images(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_images_imported_at ON images(imported_at DESC);
+        CREATE INDEX IF NOT EXISTS idx_edits_image_id ON edits(image_id);
+        CREATE INDEX IF NOT EXISTS idx_images_cache_status ON images(cache_status);",
+    )
+}
+
+/// v2: Phase 28 cache tiers (256px grid, 384px instant, 1280px working)
+fn add_cache_tiers(tx: &Transaction) -> SqlResult<()> {
+    add_column(tx, "images", "cache_path_thumb TEXT")?;
+    add_column(tx, "images", "cache_path_instant TEXT")?;
+    add_column(tx, "images", "cache_path_working TEXT")?;
+
+    // Catalogs created before Phase 28 stored the grid thumbnail in a
+    // `thumbnail_path` column. Carry it over to the thumb tier so those
+    // images don't have to be re-cached.
+    if has_column(tx, "images", "thumbnail_path")? {
+        tx.execute(
+            "UPDATE images SET cache_path_thumb = thumbnail_path
+             WHERE cache_path_thumb IS NULL AND thumbnail_path IS NOT NULL",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// v3: whether the RAW file is still on disk
+fn add_file_status(tx: &Transaction) -> SqlResult<()> {
+    add_column(tx, "images", "file_status TEXT DEFAULT 'exists'")
+}
+
+/// v4: 0-5 star rating and pick/reject flag (NULL = unflagged)
+fn add_culling(tx: &Transaction) -> SqlResult<()> {
+    add_column(tx, "images", "rating INTEGER DEFAULT 0")?;
+    add_column(tx, "images", "flag TEXT")
+}
+
+/// v5: EXIF metadata read on import (NULL when the file has none)
+fn add_exif_metadata(tx: &Transaction) -> SqlResult<()> {
+    for column in [
+        "camera_make TEXT",
+        "camera_model TEXT",
+        "iso INTEGER",
+        "shutter_seconds REAL",
+        "aperture REAL",
+        "focal_length REAL",
+        "captured_at TEXT",
+        "orientation INTEGER DEFAULT 1",
+    ] {
+        add_column(tx, "images", column)?;
+    }
+    Ok(())
+}
+
+/// v6: named develop looks (full EditParams as JSON)
+fn create_presets_table(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS presets (
+            name            TEXT PRIMARY KEY,
+            settings_json   TEXT NOT NULL
+        )",
+    )
+}
+
+/// v7: app settings (key/value, see settings.rs)
+fn create_settings_table(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key     TEXT PRIMARY KEY,
+            value   TEXT NOT NULL
+        )",
+    )
+}
+
+/// v8: named looks per image ("virtual copies"), see `Library::save_snapshot`
+fn create_snapshots_table(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS snapshots (
+            id              INTEGER PRIMARY KEY AUTOINCREMENT,
+            image_id        INTEGER NOT NULL,
+            name            TEXT NOT NULL,
+            settings_json   TEXT NOT NULL,
+            created_at      INTEGER NOT NULL,
+            UNIQUE(image_id, name),
+            FOREIGN KEY(image_id) REFERENCES images(id) ON DELETE CASCADE
+        )",
+    )
+}
+
+/// v9: EXIF lens model for lens correction (NULL for images imported before it)
+fn add_lens_model(tx: &Transaction) -> SqlResult<()> {
+    add_column(tx, "images", "lens_model TEXT")
+}
+
+/// v10: keyword tags (many per image, matched case-insensitively)
+fn create_keywords_table(tx: &Transaction) -> SqlResult<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS keywords (
+            image_id        INTEGER NOT NULL,
+            keyword         TEXT NOT NULL COLLATE NOCASE,
+            PRIMARY KEY(image_id, keyword),
+            FOREIGN KEY(image_id) REFERENCES images(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_keywords_keyword ON keywords(keyword);",
+    )
+}
+
+/// v11: which import run added the image (its start time in unix
+/// milliseconds; NULL for images imported before it)
+fn add_import_batch(tx: &Transaction) -> SqlResult<()> {
+    add_column(tx, "images", "import_batch INTEGER")?;
+    tx.execute_batch("CREATE INDEX IF NOT EXISTS idx_images_import_batch ON images(import_batch)")
+}
+
+/// v12: an edits row now means "edited" (the grid badge), so remove rows
+/// left at the default settings by undoing back to the start
+fn drop_default_edits(tx: &Transaction) -> SqlResult<()> {
+    let unedited: Vec<i64> = {
+        let mut stmt = tx.prepare("SELECT id, settings_json FROM edits")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+        let mut ids = Vec::new();
+        for row in rows {
+            let (id, json) = row?;
+            // Unparsable rows are left for load_edit_params to report
+            if super::edit::EditParams::from_json(&json).is_ok_and(|params| params.is_unedited()) {
+                ids.push(id);
+            }
+        }
+        ids
+    };
+    for id in unedited {
+        tx.execute("DELETE FROM edits WHERE id = ?1", [id])?;
+    }
+    Ok(())
+}
+
+/// v13: average color of the embedded JPEG (0xRRGGBB), the grid placeholder
+/// while the thumbnail is generated (NULL when unknown)
+fn add_avg_color(tx: &Transaction) -> SqlResult<()> {
+    add_column(tx, "images", "avg_color INTEGER")
+}
+
+/// v14: highlights, shadows, whites and blacks (and S-curve contrast) moved
+/// to the same -100..100 scale as the other sliders; convert every saved
+/// settings blob, and drop edit rows that turn out to be the defaults (rows
+/// reset before v12 kept the old white point of 1.0, so v12 missed them)
+fn rescale_tone_sliders(tx: &Transaction) -> SqlResult<()> {
+    for table in ["edits", "presets", "snapshots"] {
+        let rows: Vec<(i64, String)> = {
+            let mut stmt = tx.prepare(&format!("SELECT rowid, settings_json FROM {}", table))?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.collect::<SqlResult<_>>()?
+        };
+        for (id, json) in rows {
+            // Unparsable rows are left for load_edit_params to report
+            let Ok(params) = super::edit::EditParams::from_legacy_json(&json) else {
+                continue;
+            };
+            if table == "edits" && params.is_unedited() {
+                tx.execute("DELETE FROM edits WHERE rowid = ?1", [id])?;
+                continue;
+            }
+            let json = params.to_json().map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+            tx.execute(&format!("UPDATE {} SET settings_json = ?1 WHERE rowid = ?2", table), rusqlite::params![json, id])?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(conn: &Connection, table: &str) -> Vec<String> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table)).unwrap();
+        let names = stmt.query_map([], |row| row.get(1)).unwrap();
+        names.map(|name| name.unwrap()).collect()
+    }
+
+    #[test]
+    fn test_new_catalog_reaches_latest_version() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), LATEST_VERSION);
+        let images = columns(&conn, "images");
+        for column in ["cache_path_thumb", "file_status", "rating", "flag", "orientation", "avg_color"] {
+            assert!(images.contains(&column.to_string()), "missing {}", column);
+        }
+
+        // Nothing left to do the second time
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), LATEST_VERSION);
+    }
+
+    #[test]
+    fn test_unversioned_catalog_upgrades() {
+        // A catalog from before versioning: some columns already added by the
+        // old ad-hoc ALTERs, plus the pre-Phase 28 thumbnail column
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE images (
+                id              INTEGER PRIMARY KEY AUTOINCREMENT,
+                path            TEXT NOT NULL UNIQUE,
+                filename        TEXT NOT NULL,
+                width           INTEGER,
+                height          INTEGER,
+                imported_at     INTEGER NOT NULL,
+                cache_status    TEXT DEFAULT 'pending',
+                thumbnail_path  TEXT,
+                file_status     TEXT DEFAULT 'exists',
+                rating          INTEGER DEFAULT 0
+            );
+            INSERT INTO images (path, filename, imported_at, thumbnail_path, rating)
+            VALUES ('/photos/a.NEF', 'a.NEF', 0, '/cache/1.jpg', 4);",
+        )
+        .unwrap();
+
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), LATEST_VERSION);
+        let (thumb, rating, orientation): (String, i64, i64) = conn
+            .query_row("SELECT cache_path_thumb, rating, orientation FROM images", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+        assert_eq!(thumb, "/cache/1.jpg");
+        assert_eq!(rating, 4);
+        assert_eq!(orientation, 1);
+    }
+
+    #[test]
+    fn test_default_edit_rows_dropped() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let defaults = crate::state::edit::EditParams::default().to_json().unwrap();
+        let edited = crate::state::edit::EditParams { exposure: 1.0, ..Default::default() }.to_json().unwrap();
+        // Edit rows from before v12: one reset to the defaults, one real edit
+        conn.execute_batch(
+            "CREATE TABLE edits (
+                id              INTEGER PRIMARY KEY AUTOINCREMENT,
+                image_id        INTEGER NOT NULL,
+                settings_json   TEXT NOT NULL
+            )",
+        )
+        .unwrap();
+        conn.execute("INSERT INTO edits (image_id, settings_json) VALUES (1, ?1), (2, ?2)", [&defaults, &edited])
+            .unwrap();
+
+        migrate(&mut conn).unwrap();
+        let remaining: Vec<i64> = conn
+            .prepare("SELECT image_id FROM edits")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|id| id.unwrap())
+            .collect();
+        assert_eq!(remaining, vec![2]);
+    }
+
+    #[test]
+    fn test_tone_sliders_rescaled() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        // Back to v13 with settings saved on the old scales
+        conn.execute_batch(
+            "PRAGMA user_version = 13;
+             INSERT INTO images (id, path, filename, imported_at) VALUES (1, '/a.NEF', 'a.NEF', 0), (2, '/b.NEF', 'b.NEF', 0);",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO edits (image_id, settings_json) VALUES (1, ?1), (2, ?2)",
+            [r#"{"exposure": 0.5, "highlights": -0.25, "whites": 0.9}"#, r#"{"whites": 1.0}"#],
+        )
+        .unwrap();
+        conn.execute("INSERT INTO presets (name, settings_json) VALUES ('Old', ?1)", [r#"{"blacks": 0.1}"#])
+            .unwrap();
+
+        migrate(&mut conn).unwrap();
+        let load = |sql: &str| {
+            let json: String = conn.query_row(sql, [], |row| row.get(0)).unwrap();
+            crate::state::edit::EditParams::from_json(&json).unwrap()
+        };
+        let edit = load("SELECT settings_json FROM edits WHERE image_id = 1");
+        assert_eq!((edit.exposure, edit.highlights), (0.5, -50.0));
+        assert!((edit.whites - 50.0).abs() < 1e-3, "{}", edit.whites);
+        assert!((load("SELECT settings_json FROM presets WHERE name = 'Old'").blacks + 50.0).abs() < 1e-3);
+        // The old neutral white point alone is no edit
+        let reset: i64 = conn.query_row("SELECT COUNT(*) FROM edits WHERE image_id = 2", [], |row| row.get(0)).unwrap();
+        assert_eq!(reset, 0);
+    }
+
+    #[test]
+    fn test_failed_step_keeps_previous_version() {
+        // Claims v1 but has no images table: v2's ALTER fails and rolls back
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA user_version = 1").unwrap();
+
+        assert!(migrate(&mut conn).is_err());
+        assert_eq!(schema_version(&conn).unwrap(), 1);
+    }
+}
@@ -2,11 +2,28 @@
 /// 
 /// This module handles all application state, including:
 /// - Database connections and queries (library.rs)
+/// - Versioned catalog schema migrations (migrations.rs)
 /// - Shared data structures (data.rs)
-/// - Edit parameters and non-destructive editing (edit.rs)
-/// - Edit history and undo/redo stacks (future)
+/// - Edit parameters, non-destructive editing and undo/redo history (edit.rs)
+/// - XMP sidecar files for edits (sidecar.rs)
+/// - EXIF camera/exposure metadata (exif.rs)
+/// - Image orientation from EXIF plus manual rotation (orientation.rs)
+/// - Lens distortion/vignetting profiles (lens.rs)
+/// - Export formats and batch filename templates (export.rs)
+/// - Named develop presets (presets.rs)
+/// - App settings such as preview quality (settings.rs)
+/// - Portable JSON backups of the whole catalog (backup.rs)
 /// - Background job queue (future)
 
 pub mod library;
+pub mod migrations;
 pub mod data;
 pub mod edit;
+pub mod sidecar;
+pub mod exif;
+pub mod orientation;
+pub mod lens;
+pub mod export;
+pub mod presets;
+pub mod settings;
+pub mod backup;
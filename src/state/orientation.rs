@@ -0,0 +1,174 @@
+/// Image orientation: the EXIF tag plus the user's manual rotations
+///
+/// Cameras store the sensor data (and usually the embedded JPEG) the way
+/// the sensor saw it and record how to turn it upright in the EXIF
+/// `Orientation` tag (1-8). We keep that value per image in the catalog;
+/// rotate left/right compose a quarter turn onto it, so one value drives the
+/// thumbnails, the cached previews and the Develop render.
+///
+/// An orientation is "mirror horizontally (maybe), then rotate clockwise by
+/// a number of quarter turns". Display coordinates are the upright image.
+use image::DynamicImage;
+
+/// How to turn stored pixels upright
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Orientation {
+    /// Clockwise quarter turns (0-3), applied after the mirror
+    pub quarter_turns: u8,
+    /// Mirror left-right before rotating
+    pub mirrored: bool,
+}
+
+impl Orientation {
+    /// From the EXIF tag value (anything outside 1-8 is treated as upright)
+    pub fn from_exif(value: u8) -> Self {
+        let (quarter_turns, mirrored) = match value {
+            2 => (0, true),
+            3 => (2, false),
+            4 => (2, true),
+            5 => (3, true),
+            6 => (1, false),
+            7 => (1, true),
+            8 => (3, false),
+            _ => (0, false),
+        };
+        Orientation { quarter_turns, mirrored }
+    }
+
+    /// The EXIF tag value (1-8) for this orientation
+    pub fn to_exif(self) -> u8 {
+        match (self.quarter_turns % 4, self.mirrored) {
+            (0, false) => 1,
+            (0, true) => 2,
+            (2, false) => 3,
+            (2, true) => 4,
+            (3, true) => 5,
+            (1, false) => 6,
+            (1, true) => 7,
+            _ => 8, // (3, false)
+        }
+    }
+
+    /// This orientation followed by a quarter turn of the upright image
+    pub fn rotated(self, clockwise: bool) -> Self {
+        let turn = if clockwise { 1 } else { 3 };
+        Orientation {
+            quarter_turns: (self.quarter_turns + turn) % 4,
+            ..self
+        }
+    }
+
+    /// True when width and height trade places
+    pub fn swaps_axes(self) -> bool {
+        self.quarter_turns % 2 == 1
+    }
+
+    /// Upright size of a stored `width` x `height` image
+    pub fn oriented_size(self, width: u32, height: u32) -> (u32, u32) {
+        if self.swaps_axes() {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Map upright coordinates (0-1) back to stored coordinates (0-1)
+    ///
+    /// CPU mirror of `orient_to_source` in the shader.
+    pub fn to_source(self, x: f32, y: f32) -> (f32, f32) {
+        // Undo the rotation (one counter-clockwise quarter turn at a time)...
+        let (mut x, mut y) = (x, y);
+        for _ in 0..self.quarter_turns % 4 {
+            (x, y) = (y, 1.0 - x);
+        }
+        // ...then the mirror
+        if self.mirrored {
+            x = 1.0 - x;
+        }
+        (x, y)
+    }
+
+    /// Turn a decoded image (thumbnail, embedded preview) upright
+    pub fn apply(self, image: DynamicImage) -> DynamicImage {
+        let image = if self.mirrored { image.fliph() } else { image };
+        match self.quarter_turns % 4 {
+            1 => image.rotate90(),
+            2 => image.rotate180(),
+            3 => image.rotate270(),
+            _ => image,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Rgb, RgbImage};
+
+    /// 3x2 fixture with a distinct value in every pixel (red = x, green = y)
+    fn fixture() -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(3, 2, |x, y| Rgb([x as u8 * 100, y as u8 * 100, 7])))
+    }
+
+    /// Upright position of stored pixel (x, y)
+    fn find(image: &DynamicImage, x: u32, y: u32) -> (u32, u32) {
+        image
+            .pixels()
+            .find(|(_, _, pixel)| pixel.0[0] == x as u8 * 100 && pixel.0[1] == y as u8 * 100)
+            .map(|(px, py, _)| (px, py))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_all_exif_orientations() {
+        // EXIF: where stored row 0 / column 0 end up. Checked through the
+        // stored top-left pixel and its right neighbour (which shows the
+        // direction row 0 runs in)
+        let expected = [
+            // (value, upright size, stored (0,0) lands at, stored (1,0) lands at)
+            (1, (3, 2), (0, 0), (1, 0)), // row 0 top, column 0 left
+            (2, (3, 2), (2, 0), (1, 0)), // row 0 top, column 0 right
+            (3, (3, 2), (2, 1), (1, 1)), // row 0 bottom, column 0 right
+            (4, (3, 2), (0, 1), (1, 1)), // row 0 bottom, column 0 left
+            (5, (2, 3), (0, 0), (0, 1)), // row 0 left, column 0 top
+            (6, (2, 3), (1, 0), (1, 1)), // row 0 right, column 0 top
+            (7, (2, 3), (1, 2), (1, 1)), // row 0 right, column 0 bottom
+            (8, (2, 3), (0, 2), (0, 1)), // row 0 left, column 0 bottom
+        ];
+
+        for (value, size, origin, neighbour) in expected {
+            let orientation = Orientation::from_exif(value);
+            assert_eq!(orientation.to_exif(), value);
+            assert_eq!(orientation.oriented_size(3, 2), size, "orientation {}", value);
+
+            let upright = orientation.apply(fixture());
+            assert_eq!(upright.dimensions(), size, "orientation {}", value);
+            assert_eq!(find(&upright, 0, 0), origin, "orientation {}", value);
+            assert_eq!(find(&upright, 1, 0), neighbour, "orientation {}", value);
+
+            // The shader's mapping agrees with the CPU rotation: sample every
+            // upright pixel center back in the stored image
+            for (ux, uy, pixel) in upright.pixels() {
+                let (sx, sy) = orientation.to_source(
+                    (ux as f32 + 0.5) / size.0 as f32,
+                    (uy as f32 + 0.5) / size.1 as f32,
+                );
+                let stored = fixture().get_pixel((sx * 3.0) as u32, (sy * 2.0) as u32);
+                assert_eq!(stored, pixel, "orientation {} at ({}, {})", value, ux, uy);
+            }
+        }
+    }
+
+    #[test]
+    fn test_manual_rotation_composes() {
+        // Portrait shot (6), rotated back left by the user: upright sensor order
+        let orientation = Orientation::from_exif(6).rotated(false);
+        assert_eq!(orientation.to_exif(), 1);
+        // Four turns in either direction are a no-op, mirror included
+        let mirrored = Orientation::from_exif(5);
+        let spun = (0..4).fold(mirrored, |o, _| o.rotated(true));
+        assert_eq!(spun, mirrored);
+        // Unknown tag values are upright
+        assert_eq!(Orientation::from_exif(0), Orientation::default());
+    }
+}
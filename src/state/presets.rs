@@ -0,0 +1,97 @@
+/// Develop presets: named looks saved in the catalog
+///
+/// A preset is a full `EditParams` stored as JSON in the `presets` table.
+/// Applying one copies the look (tone, color, curve, HSL, detail) but keeps
+/// the target image's own crop and straighten angle. A few built-in presets
+/// are written to the table the first time the catalog is opened.
+use super::edit::EditParams;
+
+/// Why saving a preset failed
+#[derive(Debug)]
+pub enum PresetError {
+    /// A preset with this name already exists (ask before overwriting)
+    AlreadyExists(String),
+    /// Blank preset name
+    EmptyName,
+    /// Database or serialization error
+    Sql(rusqlite::Error),
+}
+
+impl std::fmt::Display for PresetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PresetError::AlreadyExists(name) => write!(f, "A preset named \"{}\" already exists", name),
+            PresetError::EmptyName => write!(f, "Preset name can't be empty"),
+            PresetError::Sql(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PresetError {}
+
+impl From<rusqlite::Error> for PresetError {
+    fn from(e: rusqlite::Error) -> Self {
+        PresetError::Sql(e)
+    }
+}
+
+/// Presets shipped with the app (seeded into new catalogs)
+pub fn builtin_presets() -> Vec<(&'static str, EditParams)> {
+    vec![
+        ("Punchy", EditParams {
+            contrast: 2.0,
+            clarity: 0.25,
+            vibrance: 0.3,
+            saturation: 10.0,
+            ..EditParams::default()
+        }),
+        ("Flat/Log", EditParams {
+            contrast: -3.0,
+            highlights: -80.0,
+            shadows: 40.0,
+            saturation: -20.0,
+            ..EditParams::default()
+        }),
+        // Red-leaning mix darkens blue skies, like a yellow filter on B&W film
+        ("B&W", EditParams {
+            monochrome: true,
+            bw_mix: [0.35, 0.6, 0.05],
+            contrast: 1.5,
+            clarity: 0.2,
+            ..EditParams::default()
+        }),
+    ]
+}
+
+impl EditParams {
+    /// Take the look from a preset, keeping this image's crop and straighten
+    pub fn with_preset(&self, preset: &EditParams) -> EditParams {
+        EditParams {
+            crop_rect: self.crop_rect,
+            rotation_degrees: self.rotation_degrees,
+            ..*preset
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_preset_keeps_geometry() {
+        let image = EditParams {
+            exposure: 1.0,
+            crop_rect: Some((0.1, 0.1, 0.5, 0.5)),
+            rotation_degrees: 2.0,
+            ..EditParams::default()
+        };
+        let (_, punchy) = builtin_presets().into_iter().find(|(name, _)| *name == "Punchy").unwrap();
+
+        let applied = image.with_preset(&punchy);
+        assert_eq!(applied.contrast, punchy.contrast);
+        assert_eq!(applied.exposure, punchy.exposure);
+        assert_eq!(applied.crop_rect, image.crop_rect);
+        assert_eq!(applied.rotation_degrees, image.rotation_degrees);
+    }
+}
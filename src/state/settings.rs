@@ -0,0 +1,761 @@
+/// App settings stored in the catalog's `settings` table
+///
+/// Settings are plain key/value strings (see `Library::get_setting`); this
+/// module holds the typed values and their string forms. `AppSettings::load`
+/// reads them all at startup; each change in the Preferences tab is written
+/// back under its own key right away.
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use super::exif::ExportMetadata;
+use super::export::{ExportFormat, DEFAULT_JPEG_QUALITY, DEFAULT_TEMPLATE};
+use super::library::Library;
+
+/// Settings key for the preview quality
+pub const PREVIEW_QUALITY_KEY: &str = "preview_quality";
+
+/// Settings key for the Library grid thumbnail width
+pub const THUMBNAIL_SIZE_KEY: &str = "thumbnail_size";
+
+/// Settings key for the default export format
+pub const EXPORT_FORMAT_KEY: &str = "export_format";
+
+/// Settings key for the export filename template (see `export::expand_template`)
+pub const EXPORT_TEMPLATE_KEY: &str = "export_template";
+
+/// Settings key for the default JPEG export quality
+pub const JPEG_QUALITY_KEY: &str = "jpeg_quality";
+
+/// Settings key for copying the RAW's EXIF into exports ("true"/"false")
+pub const EXPORT_METADATA_KEY: &str = "export_metadata";
+
+/// Settings key for the artist written into exports' EXIF
+pub const EXPORT_ARTIST_KEY: &str = "export_artist";
+
+/// Settings key for the copyright notice written into exports' EXIF
+pub const EXPORT_COPYRIGHT_KEY: &str = "export_copyright";
+
+/// Settings key for copying GPS tags into exports ("true"/"false")
+pub const EXPORT_GPS_KEY: &str = "export_gps";
+
+/// Settings key for copying maker notes into exports ("true"/"false")
+pub const EXPORT_MAKER_NOTES_KEY: &str = "export_maker_notes";
+
+/// Settings key for embedding the edit recipe in PNG exports ("true"/"false")
+pub const EXPORT_RECIPE_KEY: &str = "export_recipe";
+
+/// Settings key for writing .xmp sidecars on every edit ("true"/"false")
+pub const WRITE_SIDECARS_KEY: &str = "write_sidecars";
+
+/// Settings key for rendering edited images' thumbnails from the pipeline ("true"/"false")
+pub const EDITED_THUMBNAILS_KEY: &str = "edited_thumbnails";
+
+/// Settings key for showing the embedded preview while Develop decodes the RAW ("true"/"false")
+pub const FAST_PREVIEW_KEY: &str = "fast_preview";
+
+/// Settings key for selecting the next image after flagging one reject ("true"/"false")
+pub const AUTO_ADVANCE_KEY: &str = "auto_advance_on_reject";
+
+/// Settings key for extra file extensions imported as RAW (comma-separated)
+pub const CUSTOM_EXTENSIONS_KEY: &str = "custom_extensions";
+
+/// Settings key for the thumbnail/preview cache folder (unset = OS cache dir)
+pub const CACHE_DIR_KEY: &str = "cache_dir";
+
+/// Settings key for the photos folder catalog backups store paths relative to (unset = absolute paths)
+pub const PHOTOS_ROOT_KEY: &str = "photos_root";
+
+/// Settings key for the graphics API the GPU context uses
+pub const GPU_BACKEND_KEY: &str = "gpu_backend";
+
+/// Settings key for preferring the fast or the power-saving GPU
+pub const GPU_POWER_KEY: &str = "gpu_power";
+
+/// Settings key for the UI theme (dark, light or following the OS)
+pub const UI_THEME_KEY: &str = "ui_theme";
+
+/// Settings key for the UI accent color
+pub const ACCENT_COLOR_KEY: &str = "accent_color";
+
+/// Settings key for the main window's size, position and maximized flag
+pub const WINDOW_KEY: &str = "window";
+
+/// Settings key for the image that was selected when the app closed
+pub const LAST_IMAGE_KEY: &str = "last_image";
+
+/// Settings key for the tab that was open when the app closed
+pub const LAST_TAB_KEY: &str = "last_tab";
+
+/// Default grid thumbnail width in pixels
+pub const DEFAULT_THUMBNAIL_SIZE: f32 = 200.0;
+/// Smallest grid thumbnail width (slider minimum)
+pub const MIN_THUMBNAIL_SIZE: f32 = 100.0;
+/// Largest grid thumbnail width (slider maximum)
+pub const MAX_THUMBNAIL_SIZE: f32 = 400.0;
+
+/// Parse a stored thumbnail width, clamped to the slider range
+pub fn thumbnail_size_from_setting(value: &str) -> f32 {
+    value
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|size| size.is_finite())
+        .map(|size| size.clamp(MIN_THUMBNAIL_SIZE, MAX_THUMBNAIL_SIZE))
+        .unwrap_or(DEFAULT_THUMBNAIL_SIZE)
+}
+
+/// Grid cell (width, height) for a thumbnail width: cells keep the 4:3 shape
+pub fn thumbnail_cell(size: f32) -> (f32, f32) {
+    (size, size * 0.75)
+}
+
+/// How wide the Develop preview is rendered
+///
+/// Higher widths are sharper on big displays but slower on weak GPUs. Native
+/// renders at the image's full (cropped) width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PreviewQuality {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Native,
+}
+
+impl PreviewQuality {
+    pub const ALL: [PreviewQuality; 4] = [
+        PreviewQuality::Low,
+        PreviewQuality::Medium,
+        PreviewQuality::High,
+        PreviewQuality::Native,
+    ];
+
+    /// Target preview width in pixels (previews never exceed the image width)
+    pub fn max_width(&self) -> u32 {
+        match self {
+            PreviewQuality::Low => 960,
+            PreviewQuality::Medium => 1280,
+            PreviewQuality::High => 2560,
+            PreviewQuality::Native => u32::MAX,
+        }
+    }
+
+    /// Value stored in the settings table
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PreviewQuality::Low => "low",
+            PreviewQuality::Medium => "medium",
+            PreviewQuality::High => "high",
+            PreviewQuality::Native => "native",
+        }
+    }
+
+    /// Parse a stored value (unknown values fall back to the default)
+    pub fn from_setting(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|quality| quality.as_str() == value)
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for PreviewQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PreviewQuality::Low => write!(f, "Low"),
+            PreviewQuality::Medium => write!(f, "Medium"),
+            PreviewQuality::High => write!(f, "High"),
+            PreviewQuality::Native => write!(f, "Native"),
+        }
+    }
+}
+
+/// Graphics API for the GPU context (Auto lets wgpu try every one the
+/// platform has)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GpuBackend {
+    #[default]
+    Auto,
+    Vulkan,
+    Metal,
+    Dx12,
+    Gl,
+}
+
+impl GpuBackend {
+    pub const ALL: [GpuBackend; 5] = [
+        GpuBackend::Auto,
+        GpuBackend::Vulkan,
+        GpuBackend::Metal,
+        GpuBackend::Dx12,
+        GpuBackend::Gl,
+    ];
+
+    /// Value stored in the settings table
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GpuBackend::Auto => "auto",
+            GpuBackend::Vulkan => "vulkan",
+            GpuBackend::Metal => "metal",
+            GpuBackend::Dx12 => "dx12",
+            GpuBackend::Gl => "gl",
+        }
+    }
+
+    /// Parse a stored value (unknown values fall back to Auto)
+    pub fn from_setting(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|backend| backend.as_str() == value)
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for GpuBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpuBackend::Auto => write!(f, "Automatic"),
+            GpuBackend::Vulkan => write!(f, "Vulkan"),
+            GpuBackend::Metal => write!(f, "Metal"),
+            GpuBackend::Dx12 => write!(f, "DirectX 12"),
+            GpuBackend::Gl => write!(f, "OpenGL"),
+        }
+    }
+}
+
+/// Which GPU to prefer on machines with two (laptops with hybrid graphics)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GpuPower {
+    /// The discrete GPU
+    #[default]
+    HighPerformance,
+    /// The integrated GPU (longer battery life)
+    LowPower,
+}
+
+impl GpuPower {
+    pub const ALL: [GpuPower; 2] = [GpuPower::HighPerformance, GpuPower::LowPower];
+
+    /// Value stored in the settings table
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GpuPower::HighPerformance => "high_performance",
+            GpuPower::LowPower => "low_power",
+        }
+    }
+
+    /// Parse a stored value (unknown values fall back to high performance)
+    pub fn from_setting(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|power| power.as_str() == value)
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for GpuPower {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpuPower::HighPerformance => write!(f, "High performance"),
+            GpuPower::LowPower => write!(f, "Power saving"),
+        }
+    }
+}
+
+/// Light or dark UI
+///
+/// Only the app's chrome follows it: the Develop preview keeps its neutral
+/// black surround in every theme, since a bright frame changes how the
+/// photo's tones are judged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UiTheme {
+    #[default]
+    Dark,
+    Light,
+    /// Whatever the OS uses (read at startup)
+    System,
+}
+
+impl UiTheme {
+    pub const ALL: [UiTheme; 3] = [UiTheme::Dark, UiTheme::Light, UiTheme::System];
+
+    /// Value stored in the settings table
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UiTheme::Dark => "dark",
+            UiTheme::Light => "light",
+            UiTheme::System => "system",
+        }
+    }
+
+    /// Parse a stored value (unknown values fall back to dark)
+    pub fn from_setting(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|theme| theme.as_str() == value)
+            .unwrap_or_default()
+    }
+}
+
+impl std::fmt::Display for UiTheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UiTheme::Dark => write!(f, "Dark"),
+            UiTheme::Light => write!(f, "Light"),
+            UiTheme::System => write!(f, "Same as system"),
+        }
+    }
+}
+
+/// Highlight color for buttons, sliders and selections
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AccentColor {
+    /// The theme's own
+    #[default]
+    Default,
+    Blue,
+    Teal,
+    Green,
+    Orange,
+    Magenta,
+}
+
+impl AccentColor {
+    pub const ALL: [AccentColor; 6] = [
+        AccentColor::Default,
+        AccentColor::Blue,
+        AccentColor::Teal,
+        AccentColor::Green,
+        AccentColor::Orange,
+        AccentColor::Magenta,
+    ];
+
+    /// Value stored in the settings table
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccentColor::Default => "default",
+            AccentColor::Blue => "blue",
+            AccentColor::Teal => "teal",
+            AccentColor::Green => "green",
+            AccentColor::Orange => "orange",
+            AccentColor::Magenta => "magenta",
+        }
+    }
+
+    /// Parse a stored value (unknown values fall back to the theme's own)
+    pub fn from_setting(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|accent| accent.as_str() == value)
+            .unwrap_or_default()
+    }
+
+    /// sRGB color (0-1), None for the theme's own
+    pub fn rgb(&self) -> Option<[f32; 3]> {
+        match self {
+            AccentColor::Default => None,
+            AccentColor::Blue => Some([0.25, 0.5, 0.95]),
+            AccentColor::Teal => Some([0.1, 0.65, 0.65]),
+            AccentColor::Green => Some([0.3, 0.7, 0.35]),
+            AccentColor::Orange => Some([0.95, 0.55, 0.15]),
+            AccentColor::Magenta => Some([0.8, 0.3, 0.65]),
+        }
+    }
+}
+
+impl std::fmt::Display for AccentColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccentColor::Default => write!(f, "Theme default"),
+            AccentColor::Blue => write!(f, "Blue"),
+            AccentColor::Teal => write!(f, "Teal"),
+            AccentColor::Green => write!(f, "Green"),
+            AccentColor::Orange => write!(f, "Orange"),
+            AccentColor::Magenta => write!(f, "Magenta"),
+        }
+    }
+}
+
+/// Every setting, typed (missing or unparsable values use the defaults)
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppSettings {
+    pub preview_quality: PreviewQuality,
+    pub thumbnail_size: f32,
+    pub export_format: ExportFormat,
+    /// Filename template for single and batch export
+    pub export_template: String,
+    pub jpeg_quality: u8,
+    /// Copy the RAW's EXIF into exports
+    pub include_metadata: bool,
+    /// Artist/copyright and the privacy switches for copied EXIF
+    pub export_metadata: ExportMetadata,
+    /// Write the edit recipe into PNG exports
+    pub embed_recipe: bool,
+    pub write_sidecars: bool,
+    /// Re-render the thumbnail of an image whose edits changed
+    pub edited_thumbnails: bool,
+    /// Move on to the next image after rejecting one
+    pub auto_advance: bool,
+    /// Show the camera's embedded preview in Develop while the RAW decodes
+    pub fast_preview: bool,
+    /// Extensions imported as RAW on top of the built-in list
+    pub custom_extensions: Vec<String>,
+    /// Custom cache folder (None = `raw-editor` in the OS cache directory)
+    pub cache_dir: Option<PathBuf>,
+    /// Folder catalog backups keep paths relative to (see backup.rs)
+    pub photos_root: Option<PathBuf>,
+    /// Graphics API and adapter preference (read once, when the GPU context is created)
+    pub gpu_backend: GpuBackend,
+    pub gpu_power: GpuPower,
+    pub ui_theme: UiTheme,
+    pub accent_color: AccentColor,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            preview_quality: PreviewQuality::default(),
+            thumbnail_size: DEFAULT_THUMBNAIL_SIZE,
+            export_format: ExportFormat::default(),
+            export_template: DEFAULT_TEMPLATE.to_string(),
+            jpeg_quality: DEFAULT_JPEG_QUALITY,
+            include_metadata: true, // GPS and maker notes stay off (ExportMetadata::default)
+            export_metadata: ExportMetadata::default(),
+            embed_recipe: false,
+            write_sidecars: true, // Edits survive catalog loss by default
+            edited_thumbnails: false, // A RAW decode per edited image, so opt-in
+            auto_advance: false,
+            fast_preview: true,
+            custom_extensions: Vec::new(),
+            cache_dir: None,
+            photos_root: None,
+            gpu_backend: GpuBackend::default(),
+            gpu_power: GpuPower::default(),
+            ui_theme: UiTheme::default(),
+            accent_color: AccentColor::default(),
+        }
+    }
+}
+
+impl AppSettings {
+    /// Read every setting from the catalog
+    pub fn load(library: &Library) -> Self {
+        let get = |key: &str| library.get_setting(key).ok().flatten();
+        let defaults = Self::default();
+        Self {
+            preview_quality: get(PREVIEW_QUALITY_KEY)
+                .map(|value| PreviewQuality::from_setting(&value))
+                .unwrap_or(defaults.preview_quality),
+            thumbnail_size: get(THUMBNAIL_SIZE_KEY)
+                .map(|value| thumbnail_size_from_setting(&value))
+                .unwrap_or(defaults.thumbnail_size),
+            export_format: get(EXPORT_FORMAT_KEY)
+                .map(|value| ExportFormat::from_setting(&value))
+                .unwrap_or(defaults.export_format),
+            export_template: get(EXPORT_TEMPLATE_KEY)
+                .filter(|value| !value.trim().is_empty())
+                .unwrap_or(defaults.export_template),
+            jpeg_quality: get(JPEG_QUALITY_KEY)
+                .and_then(|value| value.trim().parse::<u8>().ok())
+                .map(|quality| quality.clamp(1, 100))
+                .unwrap_or(defaults.jpeg_quality),
+            include_metadata: get(EXPORT_METADATA_KEY)
+                .map(|value| value != "false")
+                .unwrap_or(defaults.include_metadata),
+            export_metadata: ExportMetadata {
+                artist: get(EXPORT_ARTIST_KEY).unwrap_or_default(),
+                copyright: get(EXPORT_COPYRIGHT_KEY).unwrap_or_default(),
+                include_gps: get(EXPORT_GPS_KEY).is_some_and(|value| value == "true"),
+                include_maker_notes: get(EXPORT_MAKER_NOTES_KEY).is_some_and(|value| value == "true"),
+            },
+            embed_recipe: get(EXPORT_RECIPE_KEY).is_some_and(|value| value == "true"),
+            write_sidecars: get(WRITE_SIDECARS_KEY)
+                .map(|value| value != "false")
+                .unwrap_or(defaults.write_sidecars),
+            edited_thumbnails: get(EDITED_THUMBNAILS_KEY).is_some_and(|value| value == "true"),
+            auto_advance: get(AUTO_ADVANCE_KEY).is_some_and(|value| value == "true"),
+            fast_preview: get(FAST_PREVIEW_KEY)
+                .map(|value| value != "false")
+                .unwrap_or(defaults.fast_preview),
+            custom_extensions: get(CUSTOM_EXTENSIONS_KEY)
+                .map(|value| parse_extensions(&value))
+                .unwrap_or_default(),
+            cache_dir: get(CACHE_DIR_KEY)
+                .filter(|value| !value.trim().is_empty())
+                .map(PathBuf::from),
+            photos_root: get(PHOTOS_ROOT_KEY)
+                .filter(|value| !value.trim().is_empty())
+                .map(PathBuf::from),
+            gpu_backend: get(GPU_BACKEND_KEY)
+                .map(|value| GpuBackend::from_setting(&value))
+                .unwrap_or(defaults.gpu_backend),
+            gpu_power: get(GPU_POWER_KEY)
+                .map(|value| GpuPower::from_setting(&value))
+                .unwrap_or(defaults.gpu_power),
+            ui_theme: get(UI_THEME_KEY)
+                .map(|value| UiTheme::from_setting(&value))
+                .unwrap_or(defaults.ui_theme),
+            accent_color: get(ACCENT_COLOR_KEY)
+                .map(|value| AccentColor::from_setting(&value))
+                .unwrap_or(defaults.accent_color),
+        }
+    }
+}
+
+/// Custom extensions typed in Preferences ("rwz, .ABC tif") as a clean list:
+/// lowercase, no dots, no duplicates, and nothing that isn't alphanumeric
+pub fn parse_extensions(value: &str) -> Vec<String> {
+    let mut extensions: Vec<String> = Vec::new();
+    for ext in value.split(|c: char| c == ',' || c.is_whitespace()) {
+        let ext = crate::raw::loader::normalize_extension(ext);
+        if !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric()) && !extensions.contains(&ext) {
+            extensions.push(ext);
+        }
+    }
+    extensions
+}
+
+/// Main window geometry, saved when the app closes and restored at launch
+///
+/// Size and position are the un-maximized ones in logical pixels, so
+/// un-maximizing after a restore goes back to the user's own layout.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowState {
+    pub width: f32,
+    pub height: f32,
+    /// Top-left corner (None where the platform doesn't report it, e.g. Wayland)
+    pub position: Option<(f32, f32)>,
+    pub maximized: bool,
+}
+
+impl Default for WindowState {
+    /// First launch: small and centered, maximized once the catalog is open
+    fn default() -> Self {
+        Self { width: 900.0, height: 400.0, position: None, maximized: true }
+    }
+}
+
+impl WindowState {
+    /// Smallest size restored (the window's minimum size)
+    pub const MIN_SIZE: (f32, f32) = (600.0, 400.0);
+
+    /// Value stored in the settings table: "width,height,x,y,maximized"
+    /// (x and y left empty when the position is unknown)
+    pub fn as_setting(&self) -> String {
+        let (x, y) = self.position.map_or((String::new(), String::new()), |(x, y)| (x.to_string(), y.to_string()));
+        format!("{},{},{},{},{}", self.width, self.height, x, y, self.maximized)
+    }
+
+    /// Parse a stored value (None if it's malformed)
+    pub fn from_setting(value: &str) -> Option<Self> {
+        let fields: Vec<&str> = value.trim().split(',').collect();
+        let [width, height, x, y, maximized] = fields[..] else {
+            return None;
+        };
+        let number = |s: &str| s.parse::<f32>().ok().filter(|v| v.is_finite());
+        let position = match (number(x), number(y)) {
+            (Some(x), Some(y)) => Some((x, y)),
+            _ => None,
+        };
+        Some(Self {
+            width: number(width)?.max(Self::MIN_SIZE.0),
+            height: number(height)?.max(Self::MIN_SIZE.1),
+            position,
+            maximized: maximized.parse().ok()?,
+        })
+    }
+
+    /// Where to open the window on a monitor of `monitor` size: the saved
+    /// position if enough of the title bar lands on that monitor to grab,
+    /// otherwise centered (a monitor was unplugged or the resolution dropped)
+    pub fn placement(&self, monitor: (f32, f32)) -> (f32, f32) {
+        const GRAB_WIDTH: f32 = 100.0;
+        const TITLE_BAR: f32 = 30.0;
+        let centered = ((monitor.0 - self.width).max(0.0) / 2.0, (monitor.1 - self.height).max(0.0) / 2.0);
+        match self.position {
+            Some((x, y)) if x + self.width >= GRAB_WIDTH
+                && x <= monitor.0 - GRAB_WIDTH
+                && y >= 0.0
+                && y <= monitor.1 - TITLE_BAR => (x, y),
+            _ => centered,
+        }
+    }
+}
+
+/// Custom cache folder from the settings (read by the thumbnail and preview
+/// code, which runs on worker threads without access to the catalog)
+static CACHE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Use a custom cache folder for newly cached files (None = the default)
+pub fn set_cache_dir(dir: Option<PathBuf>) {
+    *CACHE_DIR.write().unwrap() = dir;
+}
+
+/// Root of the thumbnail/preview cache: the custom folder if one is set,
+/// otherwise `raw-editor` in the OS cache directory
+pub fn cache_root() -> PathBuf {
+    if let Some(dir) = CACHE_DIR.read().unwrap().clone() {
+        return dir;
+    }
+    dirs_next::cache_dir()
+        .or_else(dirs_next::home_dir)
+        .expect("Could not determine cache directory")
+        .join("raw-editor")
+}
+
+/// Folders under the cache root that hold cached images (the current tiers
+/// plus the legacy thumbnail and preview folders)
+pub const CACHE_FOLDERS: [&str; 5] = ["thumbnails", "previews", "thumb", "instant", "working"];
+
+/// Bytes taken by the cache folders under `root`
+pub fn cache_size(root: &Path) -> u64 {
+    CACHE_FOLDERS
+        .iter()
+        .flat_map(|folder| walkdir::WalkDir::new(root.join(folder)).into_iter().filter_map(|entry| entry.ok()))
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Delete every file in the cache folders under `root` (the folders stay,
+/// anything else in `root` is left alone). Returns how many files were deleted.
+pub fn clear_cache_folders(root: &Path) -> usize {
+    let files: Vec<PathBuf> = CACHE_FOLDERS
+        .iter()
+        .flat_map(|folder| walkdir::WalkDir::new(root.join(folder)).into_iter().filter_map(|entry| entry.ok()))
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .collect();
+    let mut removed = 0;
+    for file in files {
+        match std::fs::remove_file(&file) {
+            Ok(()) => removed += 1,
+            Err(e) => eprintln!("⚠️  Couldn't delete cache file {}: {}", file.display(), e),
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_size_and_clear() {
+        let root = std::env::temp_dir().join(format!("raw-editor-cache-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("thumbnails")).unwrap();
+        std::fs::create_dir_all(root.join("working").join("old")).unwrap();
+        std::fs::write(root.join("thumbnails").join("1.jpg"), [0u8; 300]).unwrap();
+        std::fs::write(root.join("working").join("old").join("1.jpg"), [0u8; 200]).unwrap();
+        std::fs::write(root.join("notes.txt"), [0u8; 1000]).unwrap(); // Not a cache folder
+        assert_eq!(cache_size(&root), 500);
+
+        assert_eq!(clear_cache_folders(&root), 2);
+        assert_eq!(cache_size(&root), 0);
+        assert!(root.join("thumbnails").is_dir());
+        assert!(root.join("notes.txt").exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_preview_quality_setting_round_trip() {
+        for quality in PreviewQuality::ALL {
+            assert_eq!(PreviewQuality::from_setting(quality.as_str()), quality);
+        }
+        assert_eq!(PreviewQuality::from_setting("ultra"), PreviewQuality::Medium);
+        assert!(PreviewQuality::Low.max_width() < PreviewQuality::High.max_width());
+    }
+
+    #[test]
+    fn test_thumbnail_size_setting() {
+        assert_eq!(thumbnail_size_from_setting("250"), 250.0);
+        assert_eq!(thumbnail_size_from_setting("5000"), MAX_THUMBNAIL_SIZE);
+        assert_eq!(thumbnail_size_from_setting("10"), MIN_THUMBNAIL_SIZE);
+        assert_eq!(thumbnail_size_from_setting("big"), DEFAULT_THUMBNAIL_SIZE);
+        assert_eq!(thumbnail_cell(200.0), (200.0, 150.0));
+    }
+
+    #[test]
+    fn test_window_state_setting_round_trip() {
+        let state = WindowState { width: 1440.0, height: 900.0, position: Some((120.0, 80.5)), maximized: false };
+        assert_eq!(WindowState::from_setting(&state.as_setting()), Some(state));
+
+        // Wayland: no position
+        let state = WindowState { position: None, ..WindowState::default() };
+        assert_eq!(WindowState::from_setting(&state.as_setting()), Some(state));
+
+        // Too small comes back at the minimum; garbage is rejected
+        let tiny = WindowState::from_setting("10,10,0,0,false").unwrap();
+        assert_eq!((tiny.width, tiny.height), WindowState::MIN_SIZE);
+        assert_eq!(WindowState::from_setting("1440,900"), None);
+        assert_eq!(WindowState::from_setting("wide,900,0,0,true"), None);
+    }
+
+    #[test]
+    fn test_window_placement_recenters_off_screen() {
+        let monitor = (1920.0, 1080.0);
+        let at = |x: f32, y: f32| WindowState { width: 1000.0, height: 600.0, position: Some((x, y)), maximized: false };
+
+        assert_eq!(at(200.0, 100.0).placement(monitor), (200.0, 100.0));
+        // Partly off the left edge, but the title bar can still be grabbed
+        assert_eq!(at(-800.0, 100.0).placement(monitor), (-800.0, 100.0));
+        // Saved on a second monitor that's gone, or above the screen
+        assert_eq!(at(2500.0, 100.0).placement(monitor), (460.0, 240.0));
+        assert_eq!(at(200.0, -50.0).placement(monitor), (460.0, 240.0));
+        assert_eq!(WindowState::default().placement(monitor), (510.0, 340.0));
+    }
+
+    #[test]
+    fn test_parse_extensions() {
+        assert_eq!(parse_extensions(" .RWZ, tif  abc,,rwz "), vec!["rwz", "tif", "abc"]);
+        assert_eq!(parse_extensions("*.x, ../y, ok"), vec!["ok"]);
+        assert!(parse_extensions("").is_empty());
+    }
+
+    #[test]
+    fn test_app_settings_load() {
+        let library = Library::open_in_memory().unwrap();
+        assert_eq!(AppSettings::load(&library), AppSettings::default());
+
+        library.set_setting(PREVIEW_QUALITY_KEY, "high").unwrap();
+        library.set_setting(EXPORT_FORMAT_KEY, "png").unwrap();
+        library.set_setting(EXPORT_TEMPLATE_KEY, "{date}_{seq:03}").unwrap();
+        library.set_setting(JPEG_QUALITY_KEY, "250").unwrap();
+        library.set_setting(WRITE_SIDECARS_KEY, "false").unwrap();
+        library.set_setting(EDITED_THUMBNAILS_KEY, "true").unwrap();
+        library.set_setting(AUTO_ADVANCE_KEY, "true").unwrap();
+        library.set_setting(FAST_PREVIEW_KEY, "false").unwrap();
+        library.set_setting(CACHE_DIR_KEY, "/mnt/fast/cache").unwrap();
+        library.set_setting(PHOTOS_ROOT_KEY, "").unwrap();
+        library.set_setting(CUSTOM_EXTENSIONS_KEY, "rwz, .TIF").unwrap();
+        library.set_setting(EXPORT_COPYRIGHT_KEY, "© Jane Doe").unwrap();
+        library.set_setting(EXPORT_GPS_KEY, "true").unwrap();
+        library.set_setting(EXPORT_RECIPE_KEY, "true").unwrap();
+        library.set_setting(GPU_BACKEND_KEY, "vulkan").unwrap();
+        library.set_setting(GPU_POWER_KEY, "turbo").unwrap();
+        library.set_setting(UI_THEME_KEY, "light").unwrap();
+        library.set_setting(ACCENT_COLOR_KEY, "chartreuse").unwrap();
+        let settings = AppSettings::load(&library);
+        assert_eq!(settings.preview_quality, PreviewQuality::High);
+        assert_eq!(settings.export_format, ExportFormat::Png);
+        assert_eq!(settings.export_template, "{date}_{seq:03}");
+        assert_eq!(settings.jpeg_quality, 100);
+        assert!(!settings.write_sidecars);
+        assert!(settings.edited_thumbnails);
+        assert!(settings.auto_advance);
+        assert!(!settings.fast_preview);
+        assert!(settings.include_metadata);
+        assert_eq!(settings.export_metadata.copyright, "© Jane Doe");
+        assert!(settings.export_metadata.include_gps && !settings.export_metadata.include_maker_notes);
+        assert!(settings.embed_recipe);
+        assert_eq!(settings.gpu_backend, GpuBackend::Vulkan);
+        assert_eq!(settings.gpu_power, GpuPower::HighPerformance);
+        assert_eq!(settings.ui_theme, UiTheme::Light);
+        assert_eq!(settings.accent_color, AccentColor::Default);
+        assert_eq!(settings.cache_dir, Some(PathBuf::from("/mnt/fast/cache")));
+        assert_eq!(settings.photos_root, None); // Cleared
+        assert_eq!(settings.custom_extensions, vec!["rwz", "tif"]);
+        assert_eq!(settings.thumbnail_size, DEFAULT_THUMBNAIL_SIZE);
+    }
+}
@@ -0,0 +1,407 @@
+/// XMP sidecar files for edit parameters
+///
+/// Edits normally live in the SQLite catalog, which means they are lost if the
+/// database is deleted and no other program can read them. This module mirrors
+/// `EditParams` into an Adobe-compatible `.xmp` file next to the RAW
+/// (`DSC_0001.NEF` → `DSC_0001.xmp`) and reads them back on import.
+///
+/// Each sidecar carries the edits twice:
+/// - `rawed:Settings`: the complete `EditParams` as JSON (curve, HSL, crop and
+///   rotation, masks, monochrome, split toning, ...), which is what this app
+///   reads back
+/// - `crs:` (Camera Raw Settings) fields for other programs. These are only a
+///   subset, the basic tone, white balance and detail sliders below; a
+///   sidecar written by another program is read from them alone.
+///
+/// `rawed:Version` is the `SETTINGS_VERSION` it was written with. Sidecars
+/// this app wrote before it existed have our tone fields on the old slider
+/// scales, and are upgraded when read.
+///
+/// Most of our slider ranges don't match Adobe's, so those fields go through
+/// a conversion:
+/// - Exposure2012: stops, same scale
+/// - Contrast2012 / Highlights2012 / Shadows2012 / Whites2012 / Blacks2012:
+///   same -100..100 scale
+/// - Vibrance: our -1..1 → -100..100
+/// - Saturation: same -100..100 scale
+/// - Clarity2012: our -1..1 → -100..100
+/// - Sharpness: our 0..1.5 → 0..150; SharpenRadius: pixels, same scale
+/// - LuminanceSmoothing / ColorNoiseReduction: our 0..1 → 0..100
+/// - PostCropVignetteAmount / PostCropVignetteMidpoint: our -1..1 / 0..1 → -100..100 / 0..100
+/// - Temperature: our relative -1..1 → absolute Kelvin around a 5500K reference
+/// - Tint: our -1..1 (positive = green) → Adobe's +150..-150 (positive = magenta)
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::edit::{EditParams, LEVELS_RANGE, SETTINGS_VERSION};
+
+/// Camera Raw Settings namespace URI
+const CRS_NAMESPACE: &str = "http://ns.adobe.com/camera-raw-settings/1.0/";
+
+/// Namespace of the full settings field (`rawed:Settings`)
+const RAWED_NAMESPACE: &str = "urn:raw-editor:settings:1.0";
+
+/// Reference white point our relative temperature slider is centered on
+const REFERENCE_KELVIN: f32 = 5500.0;
+/// Mired shift for a full ±1.0 temperature slider move
+const MIRED_RANGE: f32 = 50.0;
+
+/// Get the sidecar path for a RAW file (same name, `.xmp` extension)
+pub fn sidecar_path(raw_path: &Path) -> PathBuf {
+    raw_path.with_extension("xmp")
+}
+
+/// Write the edit parameters to an XMP sidecar next to the RAW file
+pub fn write_sidecar(raw_path: &Path, params: &EditParams) -> io::Result<()> {
+    let fields = [
+        ("Exposure2012", format!("{:+.2}", params.exposure)),
+        ("Contrast2012", format!("{:+.0}", params.contrast)),
+        ("Highlights2012", format!("{:+.0}", params.highlights)),
+        ("Shadows2012", format!("{:+.0}", params.shadows)),
+        ("Whites2012", format!("{:+.0}", params.whites)),
+        ("Blacks2012", format!("{:+.0}", params.blacks)),
+        ("Vibrance", format!("{:+.0}", params.vibrance * 100.0)),
+        ("Saturation", format!("{:+.0}", params.saturation)),
+        ("Clarity2012", format!("{:+.0}", params.clarity * 100.0)),
+        ("Sharpness", format!("{:.0}", params.sharpen_amount * 100.0)),
+        ("SharpenRadius", format!("{:+.1}", params.sharpen_radius)),
+        ("LuminanceSmoothing", format!("{:.0}", params.luma_nr * 100.0)),
+        ("ColorNoiseReduction", format!("{:.0}", params.chroma_nr * 100.0)),
+        ("PostCropVignetteAmount", format!("{:+.0}", params.vignette_amount * 100.0)),
+        ("PostCropVignetteMidpoint", format!("{:.0}", params.vignette_midpoint * 100.0)),
+        ("LensProfileEnable", format!("{}", params.lens_correction as u8)),
+        ("LensManualDistortionAmount", format!("{:+.0}", params.lens_distortion * 100.0)),
+        ("Temperature", format!("{:.0}", temperature_to_kelvin(params.temperature))),
+        ("Tint", format!("{:+.0}", -params.tint * 150.0)),
+    ];
+
+    let mut attributes = String::new();
+    for (name, value) in fields.iter() {
+        attributes.push_str(&format!("\n    crs:{}=\"{}\"", name, value));
+    }
+    let settings = params.to_json().map_err(io::Error::other)?;
+
+    let xmp = format!(
+        r#"<x:xmpmeta xmlns:x="adobe:ns:meta/" x:xmptk="RAW Editor {version}">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:crs="{ns}"
+    xmlns:rawed="{rawed_ns}"
+    rawed:Version="{settings_version}"
+    rawed:Settings="{settings}"
+    crs:ProcessVersion="11.0"
+    crs:WhiteBalance="Custom"{attributes}/>
+ </rdf:RDF>
+</x:xmpmeta>
+"#,
+        version = env!("CARGO_PKG_VERSION"),
+        ns = CRS_NAMESPACE,
+        rawed_ns = RAWED_NAMESPACE,
+        settings_version = SETTINGS_VERSION,
+        settings = escape_attribute(&settings),
+        attributes = attributes,
+    );
+
+    fs::write(sidecar_path(raw_path), xmp)
+}
+
+/// Read edit parameters from the XMP sidecar next to the RAW file
+///
+/// Returns None if there is no sidecar or it has no Camera Raw Settings we
+/// understand. Fields we don't know about are ignored, and missing fields
+/// keep their default value.
+pub fn read_sidecar(raw_path: &Path) -> Option<EditParams> {
+    let xmp = fs::read_to_string(sidecar_path(raw_path)).ok()?;
+    parse_xmp(&xmp)
+}
+
+/// Parse edits out of an XMP document: our full settings if it has them,
+/// otherwise its Camera Raw Settings
+fn parse_xmp(xmp: &str) -> Option<EditParams> {
+    let version = rawed_attribute(xmp, "Version").and_then(|version| version.trim().parse::<u32>().ok());
+    // Settings from a newer version could mean anything; the crs: fields don't change
+    if version.is_some_and(|version| version <= SETTINGS_VERSION) {
+        if let Some(params) = rawed_attribute(xmp, "Settings").and_then(|json| EditParams::from_json(&json).ok()) {
+            return Some(params);
+        }
+    }
+    // Written by this app before the version marker: old tone scales
+    let legacy = version.is_none() && xmp.contains("x:xmptk=\"RAW Editor");
+    let mut params = EditParams::default();
+    let mut found = false;
+
+    for (name, value) in crs_fields(xmp) {
+        let Ok(value) = value.trim().trim_start_matches('+').parse::<f32>() else {
+            continue; // Non-numeric values like WhiteBalance="As Shot"
+        };
+        if !value.is_finite() {
+            continue;
+        }
+
+        match name {
+            "Exposure2012" | "Exposure" => params.exposure = value.clamp(-5.0, 5.0),
+            "Contrast2012" | "Contrast" => params.contrast = value.clamp(-100.0, 100.0),
+            "Highlights2012" => params.highlights = value.clamp(-100.0, 100.0),
+            "Shadows2012" | "Shadows" => params.shadows = value.clamp(-100.0, 100.0),
+            "Whites2012" => params.whites = value.clamp(-100.0, 100.0),
+            "Blacks2012" => params.blacks = value.clamp(-100.0, 100.0),
+            "Vibrance" => params.vibrance = (value / 100.0).clamp(-1.0, 1.0),
+            "Saturation" => params.saturation = value.clamp(-100.0, 100.0),
+            "Clarity2012" => params.clarity = (value / 100.0).clamp(-1.0, 1.0),
+            "Sharpness" => params.sharpen_amount = (value / 100.0).clamp(0.0, 1.5),
+            "SharpenRadius" => params.sharpen_radius = value.clamp(0.5, 3.0),
+            "LuminanceSmoothing" => params.luma_nr = (value / 100.0).clamp(0.0, 1.0),
+            "ColorNoiseReduction" => params.chroma_nr = (value / 100.0).clamp(0.0, 1.0),
+            "PostCropVignetteAmount" => params.vignette_amount = (value / 100.0).clamp(-1.0, 1.0),
+            "PostCropVignetteMidpoint" => params.vignette_midpoint = (value / 100.0).clamp(0.0, 1.0),
+            "LensProfileEnable" => params.lens_correction = value >= 0.5,
+            "LensManualDistortionAmount" => params.lens_distortion = (value / 100.0).clamp(-1.0, 1.0),
+            "Temperature" => params.temperature = kelvin_to_temperature(value),
+            "Tint" => params.tint = (-value / 150.0).clamp(-1.0, 1.0),
+            _ => continue, // Settings this app doesn't support
+        }
+        found = true;
+    }
+
+    if !found {
+        None
+    } else if legacy {
+        Some(upgrade_legacy_tone_fields(params))
+    } else {
+        Some(params)
+    }
+}
+
+/// Value of a `rawed:` attribute, unescaped
+fn rawed_attribute(xmp: &str, name: &str) -> Option<String> {
+    let prefix = format!("rawed:{}=\"", name);
+    let start = xmp.find(&prefix)? + prefix.len();
+    let end = xmp[start..].find('"')?;
+    Some(unescape_attribute(&xmp[start..start + end]))
+}
+
+/// Tone fields as this app wrote them before `SETTINGS_VERSION` 2 (contrast
+/// ×10, highlights and shadows ×100, whites and blacks from the white and
+/// black points) put back on the old scales, then upgraded
+fn upgrade_legacy_tone_fields(params: EditParams) -> EditParams {
+    EditParams {
+        contrast: params.contrast / 10.0,
+        highlights: params.highlights / 100.0,
+        shadows: params.shadows / 100.0,
+        whites: 1.0 - params.whites / 100.0 * LEVELS_RANGE,
+        blacks: -params.blacks / 100.0 * LEVELS_RANGE,
+        ..params
+    }
+    .upgrade_legacy_ranges()
+}
+
+/// Escape text for a double-quoted XML attribute
+fn escape_attribute(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Inverse of `escape_attribute`
+fn unescape_attribute(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Collect every `crs:` field from an XMP document
+///
+/// Handles both the attribute form (`crs:Exposure2012="+0.50"`) and the
+/// element form (`<crs:Exposure2012>+0.50</crs:Exposure2012>`).
+fn crs_fields(xmp: &str) -> Vec<(&str, &str)> {
+    let mut fields = Vec::new();
+    let mut rest = xmp;
+
+    while let Some(start) = rest.find("crs:") {
+        let is_element = rest[..start].ends_with('<');
+        let after = &rest[start + 4..];
+        let name_len = after
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(after.len());
+        let name = &after[..name_len];
+        let tail = &after[name_len..];
+
+        if is_element {
+            // <crs:Name>value</crs:Name>
+            if let Some(body) = tail.strip_prefix('>') {
+                if let Some(end) = body.find('<') {
+                    fields.push((name, &body[..end]));
+                }
+            }
+        } else if let Some(quoted) = tail.strip_prefix("=\"") {
+            // crs:Name="value"
+            if let Some(end) = quoted.find('"') {
+                fields.push((name, &quoted[..end]));
+            }
+        }
+
+        rest = tail;
+    }
+
+    fields
+}
+
+/// Convert our relative temperature slider to absolute Kelvin
+/// (positive = warmer = higher Kelvin, like Lightroom)
+fn temperature_to_kelvin(temperature: f32) -> f32 {
+    let mired = 1_000_000.0 / REFERENCE_KELVIN - temperature * MIRED_RANGE;
+    1_000_000.0 / mired
+}
+
+/// Convert absolute Kelvin back to our relative temperature slider
+fn kelvin_to_temperature(kelvin: f32) -> f32 {
+    if kelvin <= 0.0 {
+        return 0.0;
+    }
+    let mired_shift = 1_000_000.0 / REFERENCE_KELVIN - 1_000_000.0 / kelvin;
+    (mired_shift / MIRED_RANGE).clamp(-1.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn test_sidecar_round_trip() {
+        let dir = std::env::temp_dir().join(format!("raw-editor-sidecar-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let raw_path = dir.join("DSC_0001.NEF");
+
+        let mut params = EditParams::default();
+        params.exposure = 1.25;
+        params.contrast = 30.0;
+        params.highlights = -40.0;
+        params.shadows = 60.0;
+        params.whites = 50.0;
+        params.blacks = -25.0;
+        params.vibrance = 0.2;
+        params.saturation = -15.0;
+        params.clarity = 0.35;
+        params.sharpen_amount = 0.4;
+        params.sharpen_radius = 1.3;
+        params.luma_nr = 0.2;
+        params.chroma_nr = 0.45;
+        params.vignette_amount = -0.3;
+        params.vignette_midpoint = 0.25;
+        params.lens_correction = true;
+        params.lens_distortion = 0.3;
+        params.temperature = 0.5;
+        params.tint = -0.2;
+
+        write_sidecar(&raw_path, &params).unwrap();
+        assert!(dir.join("DSC_0001.xmp").exists());
+
+        let restored = read_sidecar(&raw_path).unwrap();
+        assert!(approx(restored.exposure, params.exposure));
+        assert!(approx(restored.contrast, params.contrast));
+        assert!(approx(restored.highlights, params.highlights));
+        assert!(approx(restored.shadows, params.shadows));
+        assert!(approx(restored.whites, params.whites));
+        assert!(approx(restored.blacks, params.blacks));
+        assert!(approx(restored.vibrance, params.vibrance));
+        assert!(approx(restored.saturation, params.saturation));
+        assert!(approx(restored.clarity, params.clarity));
+        assert!(approx(restored.sharpen_amount, params.sharpen_amount));
+        assert!(approx(restored.sharpen_radius, params.sharpen_radius));
+        assert!(approx(restored.luma_nr, params.luma_nr));
+        assert!(approx(restored.chroma_nr, params.chroma_nr));
+        assert!(approx(restored.vignette_amount, params.vignette_amount));
+        assert!(approx(restored.vignette_midpoint, params.vignette_midpoint));
+        assert!(restored.lens_correction);
+        assert!(approx(restored.lens_distortion, params.lens_distortion));
+        assert!((restored.temperature - params.temperature).abs() < 0.02);
+        assert!(approx(restored.tint, params.tint));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_sidecar_keeps_every_setting() {
+        let dir = std::env::temp_dir().join(format!("raw-editor-sidecar-full-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let raw_path = dir.join("DSC_0002.NEF");
+
+        let mut hsl = EditParams::default().hsl;
+        hsl[2].saturation = 25.0;
+        let params = EditParams {
+            exposure: 0.7,
+            monochrome: true,
+            shadow_tint: [0.2, 0.4, 0.9],
+            balance: -0.3,
+            hsl,
+            rotation_degrees: 3.5,
+            crop_rect: Some((0.1, 0.2, 0.6, 0.5)),
+            ..Default::default()
+        };
+
+        write_sidecar(&raw_path, &params).unwrap();
+        assert_eq!(read_sidecar(&raw_path), Some(params));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_legacy_sidecar_is_upgraded() {
+        // Written by this app before the version marker (old white point 0.9)
+        let legacy = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/" x:xmptk="RAW Editor 0.1.0">
+            <rdf:Description crs:Exposure2012="+0.50" crs:Highlights2012="-25"
+                crs:Shadows2012="+60" crs:Whites2012="+50"/>
+        </x:xmpmeta>"#;
+        let params = parse_xmp(legacy).unwrap();
+        assert!(approx(params.exposure, 0.5));
+        assert!(approx(params.highlights, -50.0));
+        assert!(approx(params.shadows, 60.0));
+        assert!(approx(params.whites, 50.0));
+        assert!(approx(params.blacks, 0.0));
+
+        // Another program's sidecar is already on Adobe's scales
+        let adobe = legacy.replace("RAW Editor 0.1.0", "Adobe XMP Core 7.0");
+        assert!(approx(parse_xmp(&adobe).unwrap().highlights, -25.0));
+    }
+
+    #[test]
+    fn test_unknown_fields_are_ignored() {
+        let xmp = r#"<rdf:Description
+            crs:Exposure2012="+0.50"
+            crs:Texture="+30"
+            crs:WhiteBalance="As Shot">
+            <crs:Saturation>-20</crs:Saturation>
+            <crs:ToneCurveName2012>Linear</crs:ToneCurveName2012>
+        </rdf:Description>"#;
+
+        let params = parse_xmp(xmp).unwrap();
+        assert!(approx(params.exposure, 0.5));
+        assert!(approx(params.saturation, -20.0));
+        assert!(approx(params.contrast, 0.0));
+    }
+
+    #[test]
+    fn test_no_known_fields_returns_none() {
+        assert!(parse_xmp(r#"<rdf:Description crs:Texture="+30"/>"#).is_none());
+        assert!(parse_xmp("not xmp at all").is_none());
+    }
+
+    #[test]
+    fn test_kelvin_conversion() {
+        assert!(approx(temperature_to_kelvin(0.0), REFERENCE_KELVIN));
+        assert!(temperature_to_kelvin(1.0) > REFERENCE_KELVIN);
+        assert!(temperature_to_kelvin(-1.0) < REFERENCE_KELVIN);
+        assert!(approx(kelvin_to_temperature(temperature_to_kelvin(0.3)), 0.3));
+        // Out-of-range Kelvin values clamp to the slider range
+        assert!(approx(kelvin_to_temperature(50_000.0), 1.0));
+        assert!(approx(kelvin_to_temperature(2000.0), -1.0));
+    }
+}
@@ -1,16 +1,162 @@
-use iced::widget::canvas::{self, Program};
-use iced::mouse::{self, Cursor};
-use iced::{Rectangle, Renderer, Theme, Point};
+use iced::widget::shader::Program;
+use iced::mouse::Cursor;
+use iced::Rectangle;
+use iced::widget::image::{FilterMethod, Handle};
+use iced::{Element, Length};
+use std::cell::RefCell;
 use std::sync::Arc;
 
 use crate::gpu;
+use crate::state::edit::EditParams;
+use crate::state::orientation::Orientation;
 use crate::Message;
 
-/// GPU-accelerated canvas renderer for RAW images
+/// Everything a Develop preview frame depends on
+///
+/// `view()` runs on every event (mouse moves, status updates, ...). When the
+/// key matches the last frame, the cached image is shown again: no uniform
+/// upload, no GPU render, no readback, and iced keeps its uploaded texture
+/// because the handle is the same.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameKey {
+    /// Pipeline that rendered it (a reloaded image gets a new generation)
+    pub generation: u64,
+    pub image_id: i64,
+    pub params: EditParams,
+    pub zoom: f32,
+    pub pan: (f32, f32),
+    pub max_preview_width: u32,
+    pub detail_preview: bool,
+    pub show_clipping: bool,
+    pub orientation: Orientation,
+    /// Split before/after divider position (None = single image)
+    pub split_x: Option<f32>,
+    /// Snapshot look left of the divider (None = the original)
+    pub compare_params: Option<EditParams>,
+}
+
+/// A rendered preview, ready for an Image widget
+#[derive(Debug, Clone)]
+pub struct PreviewFrame {
+    pub handle: Handle,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// What the Develop preview shows: drawn straight onto the window, or a
+/// read-back frame (split view, software rendering, oversized sensors)
+#[derive(Clone)]
+pub enum Preview {
+    Surface(Box<GpuRenderer>),
+    Frame(Handle),
+}
+
+impl Preview {
+    /// The preview filling its area, Contain fit and centered either way
+    pub fn widget(&self, filter: FilterMethod) -> Element<'static, Message> {
+        match self {
+            Preview::Surface(renderer) => iced::widget::shader(GpuRenderer::clone(renderer))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into(),
+            Preview::Frame(handle) => iced::widget::Image::new(handle.clone())
+                .content_fit(iced::ContentFit::Contain)
+                .filter_method(filter)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into(),
+        }
+    }
+}
+
+/// Split before/after: copy the columns left of `split_x` (0-1 across the
+/// frame) from `before` into `after`, then draw the divider line
+pub fn split_composite(before: &[u8], after: &mut [u8], width: u32, split_x: f32) {
+    let width = width as usize;
+    let divider = ((split_x.clamp(0.0, 1.0) * width as f32) as usize).min(width);
+    for (before_row, after_row) in before.chunks_exact(width * 4).zip(after.chunks_exact_mut(width * 4)) {
+        after_row[..divider * 4].copy_from_slice(&before_row[..divider * 4]);
+        // 2px white line, clamped to the frame
+        for x in divider.saturating_sub(1)..(divider + 1).min(width) {
+            after_row[x * 4..x * 4 + 3].fill(255);
+        }
+    }
+}
+
+/// The last Develop preview frame and the key it was rendered for
+///
+/// Frames drawn straight onto the window (see `GpuRenderer`) only record
+/// their key; a read-back copy is made if something asks for its pixels.
+#[derive(Debug, Default)]
+pub struct FrameCache {
+    last: RefCell<Option<(FrameKey, Option<PreviewFrame>)>>,
+}
+
+impl FrameCache {
+    /// Cached frame for `key`, or a fresh one from `render` (which then replaces it)
+    ///
+    /// The bool is true when `render` ran.
+    pub fn get_or_render(&self, key: FrameKey, render: impl FnOnce() -> PreviewFrame) -> (PreviewFrame, bool) {
+        if let Some((cached_key, Some(frame))) = self.last.borrow().as_ref() {
+            if *cached_key == key {
+                return (frame.clone(), false);
+            }
+        }
+        let frame = render();
+        *self.last.borrow_mut() = Some((key, Some(frame.clone())));
+        (frame, true)
+    }
+
+    /// Record a frame drawn on the window for `key`; true if the key changed
+    pub fn note_drawn(&self, key: FrameKey) -> bool {
+        if matches!(self.last.borrow().as_ref(), Some((cached_key, _)) if *cached_key == key) {
+            return false;
+        }
+        *self.last.borrow_mut() = Some((key, None));
+        true
+    }
+
+    /// Forget the cached frame (the next view renders again)
+    pub fn clear(&self) {
+        self.last.borrow_mut().take();
+    }
+
+    /// RGBA of the cached frame at (x, y), given as fractions of its width
+    /// and height (None without a frame or outside it). A frame that was
+    /// only drawn on the window is read back with `render` first.
+    pub fn sample(&self, x: f32, y: f32, render: impl FnOnce() -> PreviewFrame) -> Option<[u8; 4]> {
+        if !(0.0..1.0).contains(&x) || !(0.0..1.0).contains(&y) {
+            return None;
+        }
+        if let Some((_, frame @ None)) = self.last.borrow_mut().as_mut() {
+            *frame = Some(render());
+        }
+        let last = self.last.borrow();
+        let (_, Some(frame)) = last.as_ref()? else {
+            return None;
+        };
+        let Handle::Rgba { pixels, .. } = &frame.handle else {
+            return None;
+        };
+        let column = (x * frame.width as f32) as usize;
+        let row = (y * frame.height as f32) as usize;
+        let offset = (row * frame.width as usize + column) * 4;
+        pixels.get(offset..offset + 4)?.try_into().ok()
+    }
+}
+
+/// GPU-accelerated renderer for RAW images
 /// Phase 25: Direct wgpu rendering with zoom/pan support
+///
+/// A shader widget program: each frame is drawn by the edit shader straight
+/// into the widget on the window, with no readback (see gpu/surface.rs).
+/// Zoom and pan input is handled by the mouse_area around it.
+#[derive(Clone)]
 pub struct GpuRenderer {
     /// The GPU rendering pipeline
     pub pipeline: Arc<gpu::RenderPipeline>,
+    /// Edits to draw with
+    pub params: EditParams,
     /// Zoom level (1.0 = 100%)
     pub zoom: f32,
     /// Pan offset in normalized coordinates
@@ -18,98 +164,10 @@ pub struct GpuRenderer {
 }
 
 impl Program<Message> for GpuRenderer {
-    type State = DragState;
-
-    fn draw(
-        &self,
-        _state: &Self::State,
-        renderer: &Renderer,
-        _theme: &Theme,
-        bounds: Rectangle,
-        _cursor: Cursor,
-    ) -> Vec<canvas::Geometry> {
-        // Phase 25: CRITICAL - Direct GPU rendering to screen!
-        // This is where the magic happens - zero CPU readback!
-        
-        // Get wgpu backend from iced renderer
-        // Note: This will be a direct call to render_to_target in pipeline.rs
-        // The Canvas::draw() in iced calls this, and we'll hook into wgpu directly
-        
-        // For now, return empty geometry - the actual rendering happens
-        // via custom primitive/layer in iced's rendering pipeline
-        // TODO: Integrate with iced's wgpu backend using custom layer
-        
-        vec![]
-    }
+    type State = ();
+    type Primitive = gpu::surface::SurfaceFrame;
 
-    fn update(
-        &self,
-        state: &mut Self::State,
-        event: canvas::Event,
-        _bounds: Rectangle,
-        cursor: Cursor,
-    ) -> (canvas::event::Status, Option<Message>) {
-        // Phase 25: Handle zoom and pan interactions
-        match event {
-            // Mouse wheel for zooming
-            canvas::Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
-                let zoom_delta = match delta {
-                    mouse::ScrollDelta::Lines { y, .. } => y * 0.1,
-                    mouse::ScrollDelta::Pixels { y, .. } => y * 0.01,
-                };
-                // Phase 26: Include cursor position for zoom-to-cursor
-                let cursor_pos = cursor.position().unwrap_or(iced::Point::ORIGIN);
-                return (canvas::event::Status::Captured, Some(Message::Zoom(zoom_delta, cursor_pos)));
-            }
-            
-            // Mouse button press - start dragging
-            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
-                if let Some(pos) = cursor.position() {
-                    state.is_dragging = true;
-                    state.last_position = Some(pos);
-                    return (canvas::event::Status::Captured, None);
-                }
-            }
-            
-            // Mouse button release - stop dragging
-            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
-                state.is_dragging = false;
-                state.last_position = None;
-                return (canvas::event::Status::Captured, None);
-            }
-            
-            // Mouse move - pan if dragging
-            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) => {
-                if state.is_dragging {
-                    if let Some(current_pos) = cursor.position() {
-                        if let Some(last_pos) = state.last_position {
-                            // Calculate pan delta in screen space
-                            let delta_x = current_pos.x - last_pos.x;
-                            let delta_y = current_pos.y - last_pos.y;
-                            
-                            // Convert to normalized coordinates (adjust for zoom)
-                            let delta = cgmath::Vector2::new(
-                                delta_x * 0.001, // Sensitivity factor
-                                delta_y * 0.001,
-                            );
-                            
-                            state.last_position = Some(current_pos);
-                            return (canvas::event::Status::Captured, Some(Message::Pan(delta)));
-                        }
-                    }
-                }
-            }
-            
-            _ => {}
-        }
-        
-        (canvas::event::Status::Ignored, None)
+    fn draw(&self, _state: &Self::State, _cursor: Cursor, _bounds: Rectangle) -> Self::Primitive {
+        gpu::surface::SurfaceFrame::new(self.pipeline.clone(), &self.params, self.zoom, (self.offset.x, self.offset.y))
     }
 }
-
-/// State for drag interactions
-#[derive(Debug, Clone, Default)]
-pub struct DragState {
-    pub is_dragging: bool,
-    pub last_position: Option<Point>,
-}
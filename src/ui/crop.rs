@@ -0,0 +1,363 @@
+/// Crop overlay
+/// Drawn over the full (straightened) frame in crop mode: drag a corner to
+/// resize, drag inside to move. Emits CropChanged with the normalized rect.
+/// Composition guides are drawn inside the crop box; they're only UI and
+/// never reach the shader. In straighten mode a drag draws a line instead,
+/// and letting go emits RotationChanged with the angle that levels it.
+use iced::keyboard;
+use iced::mouse::{self, Cursor};
+use iced::widget::canvas::{self, Path, Stroke};
+use iced::{Color, Point, Rectangle, Size, Vector};
+
+use crate::state::edit::{level_rotation, CropRect};
+use crate::Message;
+
+/// Grab radius around a corner handle (in pixels)
+const GRAB_RADIUS: f32 = 12.0;
+
+/// Size of the drawn corner handles (in pixels)
+const HANDLE_SIZE: f32 = 8.0;
+
+/// Smallest crop side (in screen pixels) so the rect can't collapse
+const MIN_SIDE: f32 = 16.0;
+
+/// Shortest straighten line (in pixels) that counts; shorter drags are clicks
+const MIN_LINE_LENGTH: f32 = 10.0;
+
+/// Golden ratio section (1/φ²): the guides sit at 38.2% and 61.8%
+const GOLDEN_SECTION: f32 = 0.381_966;
+
+/// Composition guides drawn inside the crop box
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CropGuide {
+    /// Lines at 1/3 and 2/3
+    #[default]
+    Thirds,
+    /// Lines at the golden section (phi grid)
+    GoldenRatio,
+    /// Just the border
+    None,
+}
+
+impl CropGuide {
+    pub const ALL: [CropGuide; 3] = [CropGuide::Thirds, CropGuide::GoldenRatio, CropGuide::None];
+
+    /// Guide line positions across the crop box (0-1)
+    fn positions(&self) -> &'static [f32] {
+        match self {
+            CropGuide::Thirds => &[1.0 / 3.0, 2.0 / 3.0],
+            CropGuide::GoldenRatio => &[GOLDEN_SECTION, 1.0 - GOLDEN_SECTION],
+            CropGuide::None => &[],
+        }
+    }
+}
+
+impl std::fmt::Display for CropGuide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CropGuide::Thirds => write!(f, "Rule of Thirds"),
+            CropGuide::GoldenRatio => write!(f, "Golden Ratio"),
+            CropGuide::None => write!(f, "No Guides"),
+        }
+    }
+}
+
+/// Crop overlay widget data
+#[derive(Debug, Clone)]
+pub struct CropOverlay {
+    /// Current crop rectangle (normalized to the frame)
+    pub rect: CropRect,
+    /// Aspect preset's long/short ratio in pixels (None = free)
+    pub aspect: Option<f32>,
+    /// Keep the crop's current shape while resizing a free crop (Shift does
+    /// the same while held)
+    pub lock_ratio: bool,
+    /// Frame width / height, to find where the image sits in the canvas
+    pub image_aspect: f32,
+    /// Guides drawn inside the crop box
+    pub guide: CropGuide,
+    /// Straighten mode: drags draw a line to level instead of moving the crop
+    pub straighten: bool,
+    /// Current straighten angle (the line is leveled relative to it)
+    pub rotation_degrees: f32,
+}
+
+/// What the current drag is doing
+#[derive(Debug, Clone, Copy)]
+pub enum CropDrag {
+    /// Resizing: the opposite corner stays put. `ratio` is the crop's
+    /// long/short ratio when the drag started, kept if the ratio is locked.
+    Corner { anchor: Point, landscape: bool, ratio: f32 },
+    /// Moving the whole rect: cursor offset from its top-left corner
+    Move { grab: Vector },
+    /// Straighten: a line from `from` to `to` (canvas coordinates)
+    Line { from: Point, to: Point },
+}
+
+/// Interaction state: the drag in progress and the held modifiers
+#[derive(Debug, Clone, Default)]
+pub struct CropState {
+    pub drag: Option<CropDrag>,
+    pub modifiers: keyboard::Modifiers,
+}
+
+impl CropOverlay {
+    /// Where the image is drawn inside the canvas (ContentFit::Contain)
+    fn image_bounds(&self, size: Size) -> Rectangle {
+        let (width, height) = if size.width / size.height > self.image_aspect {
+            (size.height * self.image_aspect, size.height)
+        } else {
+            (size.width, size.width / self.image_aspect)
+        };
+        Rectangle {
+            x: (size.width - width) / 2.0,
+            y: (size.height - height) / 2.0,
+            width,
+            height,
+        }
+    }
+
+    /// Crop rectangle in canvas coordinates
+    fn crop_bounds(&self, image: Rectangle) -> Rectangle {
+        let (x, y, w, h) = self.rect;
+        Rectangle {
+            x: image.x + x * image.width,
+            y: image.y + y * image.height,
+            width: w * image.width,
+            height: h * image.height,
+        }
+    }
+
+    /// Convert a canvas rectangle back to a normalized crop
+    fn to_crop(rect: Rectangle, image: Rectangle) -> CropRect {
+        (
+            (rect.x - image.x) / image.width,
+            (rect.y - image.y) / image.height,
+            rect.width / image.width,
+            rect.height / image.height,
+        )
+    }
+
+    /// Ratio the resize is held to: the aspect preset, or the crop's own
+    /// shape when the lock is on or Shift is held (None = free)
+    fn locked_ratio(&self, state: &CropState, start_ratio: f32) -> Option<f32> {
+        self.aspect
+            .or_else(|| (self.lock_ratio || state.modifiers.shift()).then_some(start_ratio))
+    }
+
+    /// Resize from a fixed anchor corner towards the cursor, honoring the
+    /// aspect lock and staying inside the image
+    fn resize(&self, anchor: Point, cursor: Point, landscape: bool, ratio: Option<f32>, image: Rectangle) -> Rectangle {
+        let right = cursor.x >= anchor.x;
+        let down = cursor.y >= anchor.y;
+        let max_width = if right { image.x + image.width - anchor.x } else { anchor.x - image.x };
+        let max_height = if down { image.y + image.height - anchor.y } else { anchor.y - image.y };
+
+        let mut width = (cursor.x - anchor.x).abs().clamp(MIN_SIDE, max_width.max(MIN_SIDE));
+        let mut height = (cursor.y - anchor.y).abs().clamp(MIN_SIDE, max_height.max(MIN_SIDE));
+
+        if let Some(ratio) = ratio {
+            let target = if landscape { ratio } else { 1.0 / ratio };
+            if width / height > target {
+                width = height * target;
+            } else {
+                height = width / target;
+            }
+            if width > max_width {
+                width = max_width;
+                height = width / target;
+            }
+            if height > max_height {
+                height = max_height;
+                width = height * target;
+            }
+        }
+
+        Rectangle {
+            x: if right { anchor.x } else { anchor.x - width },
+            y: if down { anchor.y } else { anchor.y - height },
+            width,
+            height,
+        }
+    }
+}
+
+impl canvas::Program<Message> for CropOverlay {
+    type State = CropState;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> (canvas::event::Status, Option<Message>) {
+        let image = self.image_bounds(bounds.size());
+        let crop = self.crop_bounds(image);
+
+        match event {
+            canvas::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                state.modifiers = modifiers;
+            }
+
+            // Straighten: start a line anywhere on the image
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) if self.straighten => {
+                if let Some(position) = cursor.position_in(bounds).filter(|p| image.contains(*p)) {
+                    state.drag = Some(CropDrag::Line { from: position, to: position });
+                    return (canvas::event::Status::Captured, None);
+                }
+            }
+
+            // Grab a corner (resize) or the inside (move)
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                if let Some(position) = cursor.position_in(bounds) {
+                    let corners = [
+                        (Point::new(crop.x, crop.y), Point::new(crop.x + crop.width, crop.y + crop.height)),
+                        (Point::new(crop.x + crop.width, crop.y), Point::new(crop.x, crop.y + crop.height)),
+                        (Point::new(crop.x, crop.y + crop.height), Point::new(crop.x + crop.width, crop.y)),
+                        (Point::new(crop.x + crop.width, crop.y + crop.height), Point::new(crop.x, crop.y)),
+                    ];
+                    let landscape = crop.width >= crop.height;
+                    let ratio = crop.width.max(crop.height) / crop.width.min(crop.height).max(1.0);
+
+                    if let Some((_, anchor)) = corners
+                        .iter()
+                        .find(|(corner, _)| corner.distance(position) <= GRAB_RADIUS)
+                    {
+                        state.drag = Some(CropDrag::Corner { anchor: *anchor, landscape, ratio });
+                        return (canvas::event::Status::Captured, None);
+                    }
+
+                    if crop.contains(position) {
+                        state.drag = Some(CropDrag::Move { grab: position - Point::new(crop.x, crop.y) });
+                        return (canvas::event::Status::Captured, None);
+                    }
+                }
+            }
+
+            // Let go (a finished straighten line sets the rotation)
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                if state.drag.is_some() =>
+            {
+                let message = match state.drag.take() {
+                    Some(CropDrag::Line { from, to }) if from.distance(to) >= MIN_LINE_LENGTH => {
+                        Some(Message::RotationChanged(level_rotation(self.rotation_degrees, to.x - from.x, to.y - from.y)))
+                    }
+                    _ => None,
+                };
+                return (canvas::event::Status::Captured, message);
+            }
+
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                if let (Some(drag), Some(position)) = (state.drag, cursor.position_from(bounds.position())) {
+                    let rect = match drag {
+                        CropDrag::Corner { anchor, landscape, ratio } => {
+                            let ratio = self.locked_ratio(state, ratio);
+                            self.resize(anchor, position, landscape, ratio, image)
+                        }
+                        CropDrag::Line { from, .. } => {
+                            // Only the line moves; the rotation changes on release
+                            state.drag = Some(CropDrag::Line { from, to: position });
+                            return (canvas::event::Status::Captured, None);
+                        }
+                        CropDrag::Move { grab } => {
+                            let top_left = position - grab;
+                            Rectangle {
+                                x: top_left.x.min(image.x + image.width - crop.width).max(image.x),
+                                y: top_left.y.min(image.y + image.height - crop.height).max(image.y),
+                                ..crop
+                            }
+                        }
+                    };
+                    return (
+                        canvas::event::Status::Captured,
+                        Some(Message::CropChanged(Self::to_crop(rect, image))),
+                    );
+                }
+            }
+
+            _ => {}
+        }
+
+        (canvas::event::Status::Ignored, None)
+    }
+
+    fn draw(
+        &self,
+        state: &Self::State,
+        renderer: &iced::Renderer,
+        _theme: &iced::Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let image = self.image_bounds(bounds.size());
+        let crop = self.crop_bounds(image);
+
+        // Dim everything outside the crop (top, bottom, left, right bands)
+        let shade = Color::from_rgba(0.0, 0.0, 0.0, 0.6);
+        let bands = [
+            Rectangle { height: crop.y - image.y, ..image },
+            Rectangle { y: crop.y + crop.height, height: image.y + image.height - crop.y - crop.height, ..image },
+            Rectangle { x: image.x, y: crop.y, width: crop.x - image.x, height: crop.height },
+            Rectangle { x: crop.x + crop.width, y: crop.y, width: image.x + image.width - crop.x - crop.width, height: crop.height },
+        ];
+        for band in bands.iter().filter(|b| b.width > 0.0 && b.height > 0.0) {
+            frame.fill_rectangle(band.position(), band.size(), shade);
+        }
+
+        // Composition guides
+        let guide = Stroke::default()
+            .with_color(Color::from_rgba(1.0, 1.0, 1.0, 0.35))
+            .with_width(1.0);
+        for &t in self.guide.positions() {
+            let x = crop.x + crop.width * t;
+            let y = crop.y + crop.height * t;
+            frame.stroke(&Path::line(Point::new(x, crop.y), Point::new(x, crop.y + crop.height)), guide);
+            frame.stroke(&Path::line(Point::new(crop.x, y), Point::new(crop.x + crop.width, y)), guide);
+        }
+
+        // Border
+        frame.stroke(
+            &Path::rectangle(crop.position(), crop.size()),
+            Stroke::default().with_color(Color::WHITE).with_width(1.5),
+        );
+
+        // Corner handles
+        for corner in [
+            Point::new(crop.x, crop.y),
+            Point::new(crop.x + crop.width, crop.y),
+            Point::new(crop.x, crop.y + crop.height),
+            Point::new(crop.x + crop.width, crop.y + crop.height),
+        ] {
+            frame.fill_rectangle(
+                Point::new(corner.x - HANDLE_SIZE / 2.0, corner.y - HANDLE_SIZE / 2.0),
+                Size::new(HANDLE_SIZE, HANDLE_SIZE),
+                Color::WHITE,
+            );
+        }
+
+        // Straighten line being drawn
+        if let Some(CropDrag::Line { from, to }) = state.drag {
+            frame.stroke(
+                &Path::line(from, to),
+                Stroke::default().with_color(Color::from_rgb(1.0, 0.85, 0.2)).with_width(2.0),
+            );
+        }
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        _state: &Self::State,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> mouse::Interaction {
+        if self.straighten && cursor.is_over(bounds) {
+            mouse::Interaction::Crosshair
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
@@ -0,0 +1,168 @@
+/// Tone curve editor
+/// Draggable control points over a square graph, emits CurveChanged
+use iced::mouse::{self, Cursor};
+use iced::widget::canvas::{self, Path, Stroke};
+use iced::{Color, Point, Rectangle, Size};
+
+use crate::state::edit::ToneCurve;
+use crate::Message;
+
+/// Grab radius around a control point (in pixels)
+const GRAB_RADIUS: f32 = 10.0;
+
+/// Number of segments used to draw the spline
+const DRAW_SEGMENTS: usize = 64;
+
+/// Curve editor widget data
+#[derive(Debug, Clone)]
+pub struct CurveEditor {
+    /// Curve currently applied to the image
+    pub curve: ToneCurve,
+}
+
+/// Interaction state: which control point is being dragged
+#[derive(Debug, Clone, Default)]
+pub struct CurveState {
+    pub dragging: Option<usize>,
+}
+
+impl CurveEditor {
+    /// Convert a curve coordinate (0-1, y up) to a canvas position
+    fn to_canvas(x: f32, y: f32, size: Size) -> Point {
+        Point::new(x * size.width, (1.0 - y) * size.height)
+    }
+
+    /// Convert a canvas position to a curve coordinate (0-1, y up)
+    fn from_canvas(position: Point, size: Size) -> (f32, f32) {
+        (
+            (position.x / size.width).clamp(0.0, 1.0),
+            (1.0 - position.y / size.height).clamp(0.0, 1.0),
+        )
+    }
+}
+
+impl canvas::Program<Message> for CurveEditor {
+    type State = CurveState;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> (canvas::event::Status, Option<Message>) {
+        match event {
+            // Pick up the nearest control point under the cursor
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                if let Some(position) = cursor.position_in(bounds) {
+                    let nearest = self.curve.points
+                        .iter()
+                        .enumerate()
+                        .map(|(i, p)| {
+                            let point = Self::to_canvas(p.x, p.y, bounds.size());
+                            (i, point.distance(position))
+                        })
+                        .filter(|(_, distance)| *distance <= GRAB_RADIUS)
+                        .min_by(|a, b| a.1.total_cmp(&b.1));
+
+                    if let Some((index, _)) = nearest {
+                        state.dragging = Some(index);
+                        return (canvas::event::Status::Captured, None);
+                    }
+                }
+            }
+
+            // Drop the point
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                if state.dragging.is_some() =>
+            {
+                state.dragging = None;
+                return (canvas::event::Status::Captured, None);
+            }
+
+            // Move the dragged point (endpoints only move vertically)
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                if let Some(index) = state.dragging {
+                    if let Some(position) = cursor.position_from(bounds.position()) {
+                        let (x, y) = Self::from_canvas(position, bounds.size());
+                        let mut curve = self.curve;
+                        curve.set_point(index, x, y);
+                        return (canvas::event::Status::Captured, Some(Message::CurveChanged(curve)));
+                    }
+                }
+            }
+
+            _ => {}
+        }
+
+        (canvas::event::Status::Ignored, None)
+    }
+
+    fn draw(
+        &self,
+        state: &Self::State,
+        renderer: &iced::Renderer,
+        _theme: &iced::Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let size = bounds.size();
+
+        // Quarter grid
+        let grid_color = Color::from_rgba(1.0, 1.0, 1.0, 0.1);
+        for i in 1..4 {
+            let t = i as f32 / 4.0;
+            frame.stroke(
+                &Path::line(Self::to_canvas(t, 0.0, size), Self::to_canvas(t, 1.0, size)),
+                Stroke::default().with_color(grid_color).with_width(1.0),
+            );
+            frame.stroke(
+                &Path::line(Self::to_canvas(0.0, t, size), Self::to_canvas(1.0, t, size)),
+                Stroke::default().with_color(grid_color).with_width(1.0),
+            );
+        }
+
+        // Identity reference diagonal
+        frame.stroke(
+            &Path::line(Self::to_canvas(0.0, 0.0, size), Self::to_canvas(1.0, 1.0, size)),
+            Stroke::default()
+                .with_color(Color::from_rgba(1.0, 1.0, 1.0, 0.25))
+                .with_width(1.0),
+        );
+
+        // The spline itself
+        let curve_path = Path::new(|builder| {
+            for i in 0..=DRAW_SEGMENTS {
+                let x = i as f32 / DRAW_SEGMENTS as f32;
+                let point = Self::to_canvas(x, self.curve.evaluate(x), size);
+                if i == 0 {
+                    builder.move_to(point);
+                } else {
+                    builder.line_to(point);
+                }
+            }
+        });
+        frame.stroke(
+            &curve_path,
+            Stroke::default()
+                .with_color(Color::from_rgb(0.9, 0.9, 0.9))
+                .with_width(2.0),
+        );
+
+        // Control points (highlight the one being dragged)
+        for (i, point) in self.curve.points.iter().enumerate() {
+            let color = if state.dragging == Some(i) {
+                Color::from_rgb(0.5, 0.7, 1.0)
+            } else {
+                Color::WHITE
+            };
+            frame.fill(
+                &Path::circle(Self::to_canvas(point.x, point.y, size), 4.0),
+                color,
+            );
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
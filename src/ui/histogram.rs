@@ -1,15 +1,107 @@
 /// Phase 21: Real-Time Histogram
 /// Displays RGB histogram for visual exposure feedback
-use iced::widget::canvas::{self, Path, Stroke};
+use iced::widget::canvas::{self, Stroke};
 use iced::{Color, Point, Rectangle, Size};
 
 use crate::Message;
 
+/// Index of the luminance row in the histogram data
+const LUMA: usize = 3;
+
+/// How the histogram is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HistogramMode {
+    /// R, G and B drawn over each other
+    #[default]
+    Overlay,
+    /// A single Rec. 709 luminance histogram
+    Luminance,
+    /// R, G and B side by side
+    Parade,
+    /// R, G and B in three rows, each scaled to its own peak
+    Stacked,
+    /// The sensor's R, G and B before any processing (shows true clipping)
+    Raw,
+}
+
+impl HistogramMode {
+    pub const ALL: [HistogramMode; 5] = [
+        HistogramMode::Overlay,
+        HistogramMode::Luminance,
+        HistogramMode::Parade,
+        HistogramMode::Stacked,
+        HistogramMode::Raw,
+    ];
+}
+
+impl std::fmt::Display for HistogramMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HistogramMode::Overlay => write!(f, "RGB"),
+            HistogramMode::Luminance => write!(f, "Luminance"),
+            HistogramMode::Parade => write!(f, "Parade"),
+            HistogramMode::Stacked => write!(f, "Stacked"),
+            HistogramMode::Raw => write!(f, "RAW (sensor)"),
+        }
+    }
+}
+
 /// Histogram data structure
 #[derive(Debug, Clone)]
 pub struct Histogram {
-    /// RGB histogram data: [R[256], G[256], B[256]]
-    pub data: [[u32; 256]; 3],
+    /// Histogram data: [R[256], G[256], B[256], Luma[256]] (in Raw mode the
+    /// pipeline's `raw_histogram`)
+    pub data: [[u32; 256]; 4],
+    pub mode: HistogramMode,
+    /// Logarithmic Y axis, so big peaks don't flatten the tails
+    pub log_scale: bool,
+}
+
+impl Histogram {
+    /// Bar height as a fraction of the tallest bar
+    fn scaled(&self, count: u32, max_value: u32) -> f32 {
+        if self.log_scale {
+            (count as f32).ln_1p() / (max_value as f32).ln_1p()
+        } else {
+            count as f32 / max_value as f32
+        }
+    }
+
+    /// Draw one channel's 256 bins into `area`, scaled against `max_value`
+    fn draw_channel(
+        &self,
+        frame: &mut canvas::Frame,
+        channel_data: &[u32; 256],
+        max_value: u32,
+        area: Rectangle,
+        color: Color,
+    ) {
+        if max_value == 0 {
+            return;
+        }
+
+        let bar_width = area.width / 256.0;
+        let bottom = area.y + area.height;
+        let mut path_builder = canvas::path::Builder::new();
+
+        for (i, &count) in channel_data.iter().enumerate() {
+            if count > 0 {
+                let bar_height = self.scaled(count, max_value) * area.height;
+                let x = area.x + i as f32 * bar_width;
+
+                // Draw vertical line for this bin
+                path_builder.move_to(Point::new(x, bottom));
+                path_builder.line_to(Point::new(x, bottom - bar_height));
+            }
+        }
+
+        frame.stroke(
+            &path_builder.build(),
+            Stroke::default()
+                .with_color(color)
+                .with_width(bar_width.max(1.0)),
+        );
+    }
 }
 
 impl canvas::Program<Message> for Histogram {
@@ -25,51 +117,47 @@ impl canvas::Program<Message> for Histogram {
     ) -> Vec<canvas::Geometry> {
         let mut frame = canvas::Frame::new(renderer, bounds.size());
 
-        // Find maximum value across all channels for normalization
-        let max_value = self.data.iter()
-            .flat_map(|channel| channel.iter())
-            .copied()
-            .max()
-            .unwrap_or(1) as f32;
-
-        if max_value < 1.0 {
-            return vec![frame.into_geometry()];
-        }
-
         let width = bounds.width;
         let height = bounds.height;
-        let bar_width = width / 256.0;
+        let peak = |channel: &[u32; 256]| channel.iter().copied().max().unwrap_or(0);
+        // Shared scale across R, G and B so the channels compare directly
+        let rgb_max = self.data[..3].iter().map(peak).max().unwrap_or(0);
 
-        // Draw three histogram channels (R, G, B)
         let colors = [
             Color::from_rgba(1.0, 0.0, 0.0, 0.5), // Red
             Color::from_rgba(0.0, 1.0, 0.0, 0.5), // Green
             Color::from_rgba(0.0, 0.0, 1.0, 0.5), // Blue
         ];
 
-        for (channel_idx, channel_data) in self.data.iter().enumerate() {
-            let mut path_builder = canvas::path::Builder::new();
-
-            for (i, &count) in channel_data.iter().enumerate() {
-                if count > 0 {
-                    let normalized = count as f32 / max_value;
-                    let bar_height = normalized * height;
-                    let x = i as f32 * bar_width;
-                    let y = height - bar_height;
-
-                    // Draw vertical line for this bin
-                    path_builder.move_to(Point::new(x, height));
-                    path_builder.line_to(Point::new(x, y));
+        match self.mode {
+            HistogramMode::Overlay | HistogramMode::Raw => {
+                let area = Rectangle::new(Point::ORIGIN, Size::new(width, height));
+                for (channel_data, color) in self.data.iter().zip(colors) {
+                    self.draw_channel(&mut frame, channel_data, rgb_max, area, color);
+                }
+            }
+            HistogramMode::Luminance => {
+                let area = Rectangle::new(Point::ORIGIN, Size::new(width, height));
+                let luma = &self.data[LUMA];
+                self.draw_channel(&mut frame, luma, peak(luma), area, Color::from_rgba(0.9, 0.9, 0.9, 0.8));
+            }
+            HistogramMode::Parade => {
+                // Small gap between the columns so they read as separate
+                let gap = 4.0;
+                let column_width = (width - 2.0 * gap) / 3.0;
+                for (i, (channel_data, color)) in self.data.iter().zip(colors).enumerate() {
+                    let x = i as f32 * (column_width + gap);
+                    let area = Rectangle::new(Point::new(x, 0.0), Size::new(column_width, height));
+                    self.draw_channel(&mut frame, channel_data, rgb_max, area, color);
+                }
+            }
+            HistogramMode::Stacked => {
+                let row_height = height / 3.0;
+                for (i, (channel_data, color)) in self.data.iter().zip(colors).enumerate() {
+                    let area = Rectangle::new(Point::new(0.0, i as f32 * row_height), Size::new(width, row_height));
+                    self.draw_channel(&mut frame, channel_data, peak(channel_data), area, color);
                 }
             }
-
-            let path = path_builder.build();
-            frame.stroke(
-                &path,
-                Stroke::default()
-                    .with_color(colors[channel_idx])
-                    .with_width(bar_width.max(1.0)),
-            );
         }
 
         vec![frame.into_geometry()]
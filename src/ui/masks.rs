@@ -0,0 +1,230 @@
+/// Local adjustment mask overlay
+/// Drawn over the cropped preview while the mask tool is on: radial masks
+/// show their ellipse (and the feather's inner edge), linear ones the lines
+/// where the gradient starts and ends. Dragging a handle emits MaskMoved with
+/// the point in frame coordinates; pressing one selects that mask.
+use iced::mouse::{self, Cursor};
+use iced::widget::canvas::{self, Path, Stroke};
+use iced::{Color, Point, Rectangle, Size, Vector};
+
+use crate::state::edit::{CropRect, LocalMask, LocalMasks, MaskKind};
+use crate::Message;
+
+/// Grab radius around a handle (in pixels)
+const GRAB_RADIUS: f32 = 12.0;
+
+/// Radius of the drawn handles (in pixels)
+const HANDLE_RADIUS: f32 = 5.0;
+
+/// Segments used to draw an ellipse
+const ELLIPSE_SEGMENTS: usize = 64;
+
+/// Which point of a mask a handle moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaskHandle {
+    /// Radial center / linear start
+    Start,
+    /// Radial bounding-box corner / linear end
+    End,
+}
+
+/// Mask overlay widget data
+#[derive(Debug, Clone)]
+pub struct MaskOverlay {
+    /// The image's masks (points in the straightened frame, 0-1)
+    pub masks: LocalMasks,
+    /// Mask whose sliders are shown in the sidebar
+    pub selected: Option<usize>,
+    /// Crop the preview shows, to map frame points onto it
+    pub crop: CropRect,
+    /// Cropped output width / height, to find where the image sits in the canvas
+    pub image_aspect: f32,
+}
+
+/// Interaction state: the handle being dragged
+#[derive(Debug, Clone, Default)]
+pub struct MaskState {
+    pub drag: Option<(usize, MaskHandle)>,
+}
+
+impl MaskOverlay {
+    /// Where the image is drawn inside the canvas (ContentFit::Contain)
+    fn image_bounds(&self, size: Size) -> Rectangle {
+        let (width, height) = if size.width / size.height > self.image_aspect {
+            (size.height * self.image_aspect, size.height)
+        } else {
+            (size.width, size.width / self.image_aspect)
+        };
+        Rectangle {
+            x: (size.width - width) / 2.0,
+            y: (size.height - height) / 2.0,
+            width,
+            height,
+        }
+    }
+
+    /// Frame point to canvas coordinates
+    fn to_canvas(&self, point: [f32; 2], image: Rectangle) -> Point {
+        let (x, y, w, h) = self.crop;
+        Point::new(
+            image.x + (point[0] - x) / w * image.width,
+            image.y + (point[1] - y) / h * image.height,
+        )
+    }
+
+    /// Canvas position back to a frame point (kept on the visible image)
+    fn to_frame(&self, position: Point, image: Rectangle) -> [f32; 2] {
+        let (x, y, w, h) = self.crop;
+        let u = ((position.x - image.x) / image.width).clamp(0.0, 1.0);
+        let v = ((position.y - image.y) / image.height).clamp(0.0, 1.0);
+        [x + u * w, y + v * h]
+    }
+
+    /// Handle under the cursor, the selected mask's first
+    fn handle_at(&self, position: Point, image: Rectangle) -> Option<(usize, MaskHandle)> {
+        let masks = self.masks.as_slice();
+        let order = self.selected.into_iter().chain(0..masks.len());
+        for index in order.filter(|&i| i < masks.len()) {
+            let mask = &masks[index];
+            for (handle, point) in [(MaskHandle::Start, mask.start), (MaskHandle::End, mask.end)] {
+                if self.to_canvas(point, image).distance(position) <= GRAB_RADIUS {
+                    return Some((index, handle));
+                }
+            }
+        }
+        None
+    }
+
+    /// Outline of a radial mask's ellipse, scaled by `scale` (1 = outer edge)
+    fn ellipse(&self, mask: &LocalMask, scale: f32, image: Rectangle) -> Path {
+        let center = self.to_canvas(mask.start, image);
+        let corner = self.to_canvas(mask.end, image);
+        let radii = Vector::new((corner.x - center.x).abs() * scale, (corner.y - center.y).abs() * scale);
+        Path::new(|builder| {
+            for i in 0..=ELLIPSE_SEGMENTS {
+                let angle = i as f32 / ELLIPSE_SEGMENTS as f32 * std::f32::consts::TAU;
+                let point = Point::new(center.x + radii.x * angle.cos(), center.y + radii.y * angle.sin());
+                if i == 0 {
+                    builder.move_to(point);
+                } else {
+                    builder.line_to(point);
+                }
+            }
+        })
+    }
+
+    /// Line through `point` across the gradient direction, long enough to
+    /// span the image
+    fn cross_line(point: Point, direction: Vector, image: Rectangle) -> Path {
+        let length = (direction.x * direction.x + direction.y * direction.y).sqrt().max(1e-3);
+        let reach = image.width + image.height;
+        let across = Vector::new(-direction.y / length * reach, direction.x / length * reach);
+        Path::line(point - across, point + across)
+    }
+}
+
+impl canvas::Program<Message> for MaskOverlay {
+    type State = MaskState;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> (canvas::event::Status, Option<Message>) {
+        let image = self.image_bounds(bounds.size());
+
+        match event {
+            // Grab a handle (and select its mask)
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                if let Some((index, handle)) = cursor.position_in(bounds).and_then(|p| self.handle_at(p, image)) {
+                    state.drag = Some((index, handle));
+                    return (canvas::event::Status::Captured, Some(Message::MaskSelected(index)));
+                }
+            }
+
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) if state.drag.is_some() => {
+                state.drag = None;
+                return (canvas::event::Status::Captured, None);
+            }
+
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                if let (Some((index, handle)), Some(position)) = (state.drag, cursor.position_from(bounds.position())) {
+                    let point = self.to_frame(position, image);
+                    return (canvas::event::Status::Captured, Some(Message::MaskMoved(index, handle, point)));
+                }
+            }
+
+            _ => {}
+        }
+
+        (canvas::event::Status::Ignored, None)
+    }
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &iced::Renderer,
+        _theme: &iced::Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let image = self.image_bounds(bounds.size());
+
+        frame.with_clip(image, |frame| {
+            // The clipped frame's origin is the image's top-left corner
+            let image = Rectangle { x: 0.0, y: 0.0, ..image };
+            for (index, mask) in self.masks.as_slice().iter().enumerate() {
+                let color = if self.selected == Some(index) {
+                    Color::WHITE
+                } else {
+                    Color::from_rgba(1.0, 1.0, 1.0, 0.45)
+                };
+                let outline = Stroke::default().with_color(color).with_width(1.5);
+                let guide = Stroke::default().with_color(Color { a: color.a * 0.5, ..color }).with_width(1.0);
+                let start = self.to_canvas(mask.start, image);
+                let end = self.to_canvas(mask.end, image);
+
+                match mask.kind {
+                    MaskKind::Radial => {
+                        frame.stroke(&self.ellipse(mask, 1.0, image), outline);
+                        if mask.feather > 0.0 {
+                            frame.stroke(&self.ellipse(mask, 1.0 - mask.feather.min(1.0), image), guide);
+                        }
+                    }
+                    MaskKind::Linear => {
+                        let direction = end - start;
+                        frame.stroke(&Self::cross_line(start, direction, image), outline);
+                        frame.stroke(&Self::cross_line(end, direction, image), guide);
+                        frame.stroke(&Path::line(start, end), guide);
+                    }
+                }
+
+                // Handles: filled at the start, hollow at the end
+                frame.fill(&Path::circle(start, HANDLE_RADIUS), color);
+                frame.stroke(&Path::circle(end, HANDLE_RADIUS), outline);
+            }
+        });
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &Self::State,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> mouse::Interaction {
+        let image = self.image_bounds(bounds.size());
+        let over_handle = cursor.position_in(bounds).and_then(|p| self.handle_at(p, image)).is_some();
+        if state.drag.is_some() {
+            mouse::Interaction::Grabbing
+        } else if over_handle {
+            mouse::Interaction::Grab
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
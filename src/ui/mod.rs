@@ -2,3 +2,8 @@
 
 pub mod canvas;
 pub mod histogram; // Phase 21: Real-time histogram
+pub mod curve; // Tone curve editor
+pub mod crop; // Crop overlay
+pub mod masks; // Local adjustment mask handles
+pub mod notifications; // Toasts for export results and errors
+pub mod spinner; // Busy indicator for background work
@@ -0,0 +1,74 @@
+/// Toast notifications
+///
+/// Short messages stacked in the top-right corner of the main view: export
+/// results, decode failures and catalog errors. Each one goes away on its own
+/// after a few seconds (errors stay a bit longer), or when it's clicked.
+/// Everything shown here is still logged to the console too.
+use std::time::{Duration, Instant};
+
+use iced::widget::{column, container, mouse_area, text};
+use iced::{Background, Border, Color, Element, Theme};
+
+use crate::Message;
+
+/// How long info and success toasts stay up
+const LIFETIME: Duration = Duration::from_secs(4);
+
+/// Errors stay up longer so there's time to read them
+const ERROR_LIFETIME: Duration = Duration::from_secs(8);
+
+/// Toast width (in pixels)
+const WIDTH: f32 = 320.0;
+
+/// How a notification is colored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Success,
+    Error,
+}
+
+/// One toast in the queue
+#[derive(Debug, Clone)]
+pub struct Notification {
+    /// Identifies the toast for DismissNotification
+    pub id: u64,
+    pub severity: Severity,
+    pub message: String,
+    pub created: Instant,
+}
+
+impl Notification {
+    /// Whether the toast has been up long enough to go away at `now`
+    pub fn expired(&self, now: Instant) -> bool {
+        let lifetime = match self.severity {
+            Severity::Error => ERROR_LIFETIME,
+            Severity::Info | Severity::Success => LIFETIME,
+        };
+        now.duration_since(self.created) >= lifetime
+    }
+}
+
+/// The toast stack, newest at the bottom. Clicking a toast dismisses it.
+pub fn view(notifications: &[Notification]) -> Element<'_, Message> {
+    let toasts = notifications.iter().map(|notification| {
+        let (icon, accent) = match notification.severity {
+            Severity::Info => ("ℹ", Color::from_rgb(0.4, 0.65, 1.0)),
+            Severity::Success => ("✅", Color::from_rgb(0.35, 0.8, 0.45)),
+            Severity::Error => ("❌", Color::from_rgb(0.95, 0.35, 0.35)),
+        };
+        let toast = container(text(format!("{} {}", icon, notification.message)).size(13))
+            .width(WIDTH)
+            .padding([8, 12])
+            .style(move |theme: &Theme| container::Style {
+                background: Some(Background::Color(theme.extended_palette().background.weak.color)),
+                border: Border { color: accent, width: 1.5, radius: 6.0.into() },
+                ..container::Style::default()
+            });
+        mouse_area(toast)
+            .on_press(Message::DismissNotification(notification.id))
+            .interaction(iced::mouse::Interaction::Pointer)
+            .into()
+    });
+    column(toasts).spacing(8).into()
+}
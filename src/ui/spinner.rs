@@ -0,0 +1,64 @@
+/// Busy spinner
+/// A rotating arc drawn on a canvas. The rotation comes from the caller
+/// (`RawEditor::spinner_frame`, advanced by a timer subscription only while
+/// something is loading), so an idle app doesn't redraw.
+use iced::mouse::Cursor;
+use iced::widget::canvas::{self, Canvas, LineCap, Path, Stroke};
+use iced::{Element, Point, Radians, Rectangle, Theme};
+
+use crate::Message;
+
+/// How far the arc turns per frame (radians)
+const STEP: f32 = 0.35;
+
+/// Length of the arc (radians)
+const SWEEP: f32 = std::f32::consts::PI * 1.4;
+
+/// Spinner widget data
+#[derive(Debug, Clone, Copy)]
+pub struct Spinner {
+    /// Animation frame, see `STEP`
+    pub frame: u32,
+}
+
+impl canvas::Program<Message> for Spinner {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &iced::Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let width = (bounds.width.min(bounds.height) / 8.0).max(2.0);
+        let center = Point::new(bounds.width / 2.0, bounds.height / 2.0);
+        let radius = bounds.width.min(bounds.height) / 2.0 - width;
+        let color = theme.palette().primary;
+
+        // Faint full circle as the track, the turning arc on top
+        let track = Stroke::default().with_color(color.scale_alpha(0.2)).with_width(width);
+        frame.stroke(&Path::circle(center, radius), track);
+
+        let start = (self.frame as f32 * STEP) % std::f32::consts::TAU;
+        let arc = Path::new(|builder| {
+            builder.arc(canvas::path::Arc {
+                center,
+                radius,
+                start_angle: Radians(start),
+                end_angle: Radians(start + SWEEP),
+            });
+        });
+        let stroke = Stroke::default().with_color(color).with_width(width).with_line_cap(LineCap::Round);
+        frame.stroke(&arc, stroke);
+
+        vec![frame.into_geometry()]
+    }
+}
+
+/// A spinner `size` pixels square
+pub fn view<'a>(frame: u32, size: f32) -> Element<'a, Message> {
+    Canvas::new(Spinner { frame }).width(size).height(size).into()
+}